@@ -5,14 +5,21 @@ use crossterm::terminal::{self, EnterAlternateScreen, LeaveAlternateScreen};
 use ratatui::backend::CrosstermBackend;
 use ratatui::layout::{Alignment, Rect};
 use ratatui::style::{Color, Style};
+use ratatui::text::{Line, Span, Text};
 use ratatui::widgets::{Block, Borders, Paragraph, Wrap};
 use ratatui::{Frame, Terminal as RatatuiTerminal};
 use rusterix::prelude::*;
+use rusterix::server::SaveGameRequest;
+use rusterix::server::savegame::{SaveGame, save_slot_path};
 use rusterix::{Command, EntityAction, PlayerCamera, ServerState};
 use rustyline::error::ReadlineError;
 use rustyline::{DefaultEditor, ExternalPrinter};
-use shared::prelude::{TextSession, TextSessionOutput};
+use shared::prelude::{
+    RoguelikeCell, RoguelikeFov, RoguelikeFovConfig, RoguelikeFovMemory, TextSession,
+    TextSessionOutput,
+};
 use shared::project::Project;
+use shared::simulation::{Simulation, SimulationScript};
 use shared::terminal_screen::TerminalScreenFrame;
 use shared::text_game as sg;
 use std::collections::BTreeMap;
@@ -55,10 +62,14 @@ impl TerminalPlayMode {
 struct TerminalCliOptions {
     path: Option<PathBuf>,
     mode: Option<TerminalPlayMode>,
+    load_slot: Option<String>,
+    connect: Option<String>,
+    name: Option<String>,
 }
 
 struct TerminalApp {
     project: Project,
+    game_path: PathBuf,
     assets: Assets,
     server: Server,
     current_map: String,
@@ -66,6 +77,9 @@ struct TerminalApp {
     screen_messages: Vec<String>,
     auto_attack_target: Option<u32>,
     server_log_cursor: usize,
+    save_notices: Vec<String>,
+    fov: RoguelikeFovConfig,
+    fov_memory: RoguelikeFovMemory,
 }
 
 enum InputEvent {
@@ -81,7 +95,7 @@ enum AutoAttackMode {
 }
 
 impl TerminalApp {
-    fn load(path: &Path) -> Result<Self, String> {
+    fn new(path: &Path) -> Result<Self, String> {
         let contents = fs::read_to_string(path)
             .map_err(|err| format!("Failed to read {}: {}", path.display(), err))?;
         let mut project: Project = serde_json::from_str(&contents)
@@ -93,8 +107,10 @@ impl TerminalApp {
             return Err("Game config is missing [game].start_region".into());
         }
 
-        let mut app = Self {
+        let fov = RoguelikeFovConfig::from_config(&project.config);
+        Ok(Self {
             project,
+            game_path: path.to_path_buf(),
             assets: Assets::default(),
             server: Server::default(),
             current_map,
@@ -102,8 +118,14 @@ impl TerminalApp {
             screen_messages: Vec::new(),
             auto_attack_target: None,
             server_log_cursor: 0,
-        };
+            save_notices: Vec::new(),
+            fov,
+            fov_memory: RoguelikeFovMemory::default(),
+        })
+    }
 
+    fn load(path: &Path) -> Result<Self, String> {
+        let mut app = Self::new(path)?;
         app.start_server(false)?;
         app.create_local_player()?;
 
@@ -118,6 +140,33 @@ impl TerminalApp {
         Ok(app)
     }
 
+    /// Join a game hosted by a dedicated server instead of running the regions locally.
+    fn connect(path: &Path, addr: &str, name: &str) -> Result<Self, String> {
+        let mut app = Self::new(path)?;
+        app.server.print_log_messages = false;
+        app.load_assets(false);
+        app.server.connect(addr, name)?;
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while app.server.remote_player_id().is_none() {
+            if Instant::now() >= deadline {
+                return Err(format!("No answer from the server at {}.", addr));
+            }
+            app.tick();
+            let log = app.server.get_log();
+            if !log.is_empty() {
+                return Err(log);
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        app.tick();
+
+        app.session
+            .set_current_hour(app.current_time_hour_and_label().map(|(hour, _)| hour));
+
+        Ok(app)
+    }
+
     fn start_server(&mut self, debug: bool) -> Result<(), String> {
         self.server.clear();
         self.server.debug_mode = debug;
@@ -125,6 +174,28 @@ impl TerminalApp {
         self.server.print_log_messages = false;
         self.server_log_cursor = 0;
 
+        self.load_assets(debug);
+
+        for region in &self.project.regions {
+            let region_config =
+                shared::project::merge_config_toml(&self.project.config, &region.config);
+            self.server.create_region_instance(
+                region.name.clone(),
+                region.map.clone(),
+                &self.assets,
+                region_config,
+            );
+        }
+
+        thread::sleep(Duration::from_millis(10));
+        for region in &self.project.regions {
+            self.server.set_time(&region.map.id, self.project.time);
+        }
+        self.server.set_state(ServerState::Running);
+        Ok(())
+    }
+
+    fn load_assets(&mut self, debug: bool) {
         insert_content_into_maps_mode(&mut self.project, debug);
 
         if json_module_has_routines(&self.project.world_module) {
@@ -269,22 +340,7 @@ impl TerminalApp {
                     region.source.clone()
                 },
             );
-            let region_config =
-                shared::project::merge_config_toml(&self.project.config, &region.config);
-            self.server.create_region_instance(
-                region.name.clone(),
-                region.map.clone(),
-                &self.assets,
-                region_config,
-            );
-        }
-
-        thread::sleep(Duration::from_millis(10));
-        for region in &self.project.regions {
-            self.server.set_time(&region.map.id, self.project.time);
         }
-        self.server.set_state(ServerState::Running);
-        Ok(())
     }
 
     fn create_local_player(&mut self) -> Result<(), String> {
@@ -361,6 +417,14 @@ impl TerminalApp {
             self.current_map = new_region;
         }
 
+        for request in self.server.get_save_game_requests() {
+            let notice = match request {
+                SaveGameRequest::Save(slot) => self.save_game(&slot),
+                SaveGameRequest::Load(slot) => self.load_game(&slot),
+            };
+            self.save_notices.push(notice);
+        }
+
         if let Some(index) = self.current_region_index() {
             let region_id = self.project.regions[index].map.id;
             let time = self.server.get_time(&region_id);
@@ -370,6 +434,83 @@ impl TerminalApp {
                 self.project.time = time;
             }
         }
+        self.update_fov();
+    }
+
+    /// Recompute what the player sees and add it to the region's memory.
+    fn update_fov(&mut self) {
+        if !self.fov.enabled {
+            return;
+        }
+        let Some(player) = roguelike_player_cell(self) else {
+            return;
+        };
+        let Some(visible) = self.current_region().and_then(|region| {
+            shared::roguelike_fov::roguelike_visibility(
+                &self.project,
+                &region.map,
+                player,
+                &self.fov,
+            )
+        }) else {
+            return;
+        };
+        self.fov_memory
+            .update(&self.current_map, visible, self.fov.memory);
+    }
+
+    fn current_fov(&self) -> Option<RoguelikeFov> {
+        if !self.fov.enabled {
+            return None;
+        }
+        Some(
+            self.fov_memory
+                .get(&self.current_map)
+                .cloned()
+                .unwrap_or_default(),
+        )
+    }
+
+    /// Save the running game into the given slot next to the game file.
+    fn save_game(&mut self, slot: &str) -> String {
+        if self.server.is_remote() {
+            return "Saving is handled by the server.".into();
+        }
+        let path = save_slot_path(&self.game_path, slot);
+        match self.server.save_state().write(&path) {
+            Ok(()) => format!("Game saved to {}.", path.display()),
+            Err(err) => err,
+        }
+    }
+
+    /// Restore the running game from the given slot.
+    fn load_game(&mut self, slot: &str) -> String {
+        if self.server.is_remote() {
+            return "Loading is handled by the server.".into();
+        }
+        let path = save_slot_path(&self.game_path, slot);
+        let loaded = SaveGame::read(&path).and_then(|save| self.server.load_state(&save));
+        match loaded {
+            Ok(player_region) => {
+                if let Some(region) = player_region {
+                    self.current_map = region;
+                }
+                self.auto_attack_target = None;
+                self.session.clear_auto_attack_target();
+                self.server.system_tick();
+                self.server.redraw_tick();
+                self.server.update(&mut self.assets);
+                if let Some(index) = self.current_region_index() {
+                    self.server
+                        .apply_entities_items(&mut self.project.regions[index].map);
+                }
+                self.fov_memory.clear();
+                self.update_fov();
+                self.discard_pending_messages();
+                format!("Game loaded from {}.", path.display())
+            }
+            Err(err) => err,
+        }
     }
 
     fn drain_server_diagnostics(&mut self) -> Vec<String> {
@@ -996,6 +1137,9 @@ fn parse_terminal_args(args: &[String]) -> Result<TerminalCliOptions, String> {
     let mut options = TerminalCliOptions {
         path: None,
         mode: None,
+        load_slot: None,
+        connect: None,
+        name: None,
     };
     let mut index = 1;
     while index < args.len() {
@@ -1008,6 +1152,30 @@ fn parse_terminal_args(args: &[String]) -> Result<TerminalCliOptions, String> {
             options.mode = Some(TerminalPlayMode::parse(value)?);
         } else if let Some(value) = arg.strip_prefix("--mode=") {
             options.mode = Some(TerminalPlayMode::parse(value)?);
+        } else if arg == "--load" {
+            index += 1;
+            let Some(value) = args.get(index) else {
+                return Err("Missing save slot after --load.".to_string());
+            };
+            options.load_slot = Some(value.clone());
+        } else if let Some(value) = arg.strip_prefix("--load=") {
+            options.load_slot = Some(value.to_string());
+        } else if arg == "--connect" {
+            index += 1;
+            let Some(value) = args.get(index) else {
+                return Err("Missing server address after --connect.".to_string());
+            };
+            options.connect = Some(value.clone());
+        } else if let Some(value) = arg.strip_prefix("--connect=") {
+            options.connect = Some(value.to_string());
+        } else if arg == "--name" {
+            index += 1;
+            let Some(value) = args.get(index) else {
+                return Err("Missing player name after --name.".to_string());
+            };
+            options.name = Some(value.clone());
+        } else if let Some(value) = arg.strip_prefix("--name=") {
+            options.name = Some(value.to_string());
         } else if arg == "--help" || arg == "-h" || arg == "help" {
             return Err(terminal_usage().to_string());
         } else if arg.starts_with('-') {
@@ -1028,8 +1196,10 @@ fn parse_terminal_args(args: &[String]) -> Result<TerminalCliOptions, String> {
 
 fn terminal_usage() -> &'static str {
     "Usage:\n\
-       eldiron-client-terminal [game.eldiron] [--mode text|roguelike]\n\
+       eldiron-client-terminal [game.eldiron] [--mode text|roguelike] [--load slot]\n\
+       eldiron-client-terminal [game.eldiron] --connect host:port [--name name]\n\
        eldiron-client-terminal rules <command> ...\n\
+       eldiron-client-terminal sim run|replay <game.eldiron> <script.toml> ...\n\
      Modes:\n\
        text       Current room/description terminal play.\n\
        roguelike  Terminal glyph-map play mode for source-authored maps."
@@ -1082,6 +1252,110 @@ fn resolve_data_path(path_arg: Option<&PathBuf>) -> Result<PathBuf, String> {
     }
 }
 
+fn sim_command_usage() -> &'static str {
+    "Usage:\n\
+       eldiron-client-terminal sim run <game.eldiron> <script.toml> [--seed N] [--record log.toml] [--transcript file]\n\
+       eldiron-client-terminal sim replay <game.eldiron> <log.toml> [--transcript file]\n\
+     Runs the game headless and deterministically, feeding the scripted inputs to the player.\n\
+     Prints the Say / Message transcript and the digest of the final state. A replay fails\n\
+     when it does not reproduce the digest stored in the recorded log."
+}
+
+struct SimCliOptions {
+    game: PathBuf,
+    script: PathBuf,
+    seed: Option<u64>,
+    record: Option<PathBuf>,
+    transcript: Option<PathBuf>,
+}
+
+fn parse_sim_args(args: &[String], allow_record: bool) -> Result<SimCliOptions, String> {
+    let mut paths = vec![];
+    let mut seed = None;
+    let mut record = None;
+    let mut transcript = None;
+    let mut index = 0;
+    while index < args.len() {
+        let arg = &args[index];
+        let mut value = |name: &str| {
+            index += 1;
+            args.get(index)
+                .cloned()
+                .ok_or_else(|| format!("Missing value after {}.", name))
+        };
+        match arg.as_str() {
+            "--seed" if allow_record => {
+                let raw = value("--seed")?;
+                seed = Some(
+                    raw.parse::<u64>()
+                        .map_err(|_| format!("Invalid seed '{}'.", raw))?,
+                );
+            }
+            "--record" if allow_record => record = Some(PathBuf::from(value("--record")?)),
+            "--transcript" => transcript = Some(PathBuf::from(value("--transcript")?)),
+            _ if arg.starts_with('-') => {
+                return Err(format!(
+                    "Unknown option '{}'.\n{}",
+                    arg,
+                    sim_command_usage()
+                ));
+            }
+            _ => paths.push(PathBuf::from(arg)),
+        }
+        index += 1;
+    }
+    let [game, script] = <[PathBuf; 2]>::try_from(paths).map_err(|_| sim_command_usage())?;
+    Ok(SimCliOptions {
+        game,
+        script,
+        seed,
+        record,
+        transcript,
+    })
+}
+
+fn run_sim_command(args: &[String]) -> Result<(), String> {
+    let replay = match args.first().map(String::as_str) {
+        Some("run") => false,
+        Some("replay") => true,
+        _ => return Err(sim_command_usage().into()),
+    };
+    let options = parse_sim_args(&args[1..], !replay)?;
+    let src = fs::read_to_string(&options.script)
+        .map_err(|err| format!("Failed to read {}: {}", options.script.display(), err))?;
+    let mut script = SimulationScript::parse(&src)?;
+    if let Some(seed) = options.seed {
+        script.seed = seed;
+    }
+
+    let mut simulation = Simulation::load(&options.game, script.seed)?;
+    let report = if replay {
+        simulation.replay(&script)?
+    } else {
+        simulation.run(&script)?
+    };
+
+    match &options.transcript {
+        Some(path) => {
+            let mut text = report.transcript.join("\n");
+            text.push('\n');
+            fs::write(path, text)
+                .map_err(|err| format!("Failed to write {}: {}", path.display(), err))?;
+        }
+        None => {
+            for line in &report.transcript {
+                println!("{}", line);
+            }
+        }
+    }
+    if let Some(path) = &options.record {
+        fs::write(path, simulation.input_log().to_toml()?)
+            .map_err(|err| format!("Failed to write {}: {}", path.display(), err))?;
+    }
+    println!("digest {} after {} ticks", report.digest, report.ticks);
+    Ok(())
+}
+
 fn rules_command_usage() -> &'static str {
     "Usage:\n\
        eldiron-client-terminal rules check [game.eldiron]\n\
@@ -1174,6 +1448,8 @@ fn format_rules_catalog_summary(
          skills: {} ({})\n\
          resources: {} ({})\n\
          recipes: {} ({})\n\
+         quests: {} ({})\n\
+         shops: {} ({})\n\
          actions: {} ({})\n\
          abilities: {} ({})\n\
          spells: {} ({})\n\
@@ -1198,6 +1474,10 @@ fn format_rules_catalog_summary(
         format_rules_list(&catalog.resources),
         catalog.recipes.len(),
         format_rules_list(&catalog.recipes),
+        catalog.quests.len(),
+        format_rules_list(&catalog.quests),
+        catalog.shops.len(),
+        format_rules_list(&catalog.shops),
         catalog.actions.len(),
         format_rules_list(&catalog.actions),
         catalog.abilities.len(),
@@ -2211,8 +2491,9 @@ fn should_print_terminal_message(message: &str, category: &str) -> bool {
 }
 
 fn collect_region_output(app: &mut TerminalApp, include_room: bool) -> Vec<String> {
+    let notices = std::mem::take(&mut app.save_notices);
     let Some(index) = app.current_region_index() else {
-        return Vec::new();
+        return notices;
     };
     let region_id = app.project.regions[index].map.id;
     let Some(map) = app.current_region().map(|region| region.map.clone()) else {
@@ -2229,7 +2510,7 @@ fn collect_region_output(app: &mut TerminalApp, include_room: bool) -> Vec<Strin
     );
     app.auto_attack_target = app.session.auto_attack_target();
 
-    let mut rendered = Vec::new();
+    let mut rendered = notices;
     let mut saw_death = false;
     for entry in outputs {
         match entry {
@@ -2266,11 +2547,12 @@ fn collect_roguelike_screen_output(app: &mut TerminalApp) -> Vec<String> {
         return Vec::new();
     };
     let region_id = app.project.regions[index].map.id;
-    let output = if roguelike_text_updates_enabled(app) {
+    let mut output = std::mem::take(&mut app.save_notices);
+    output.extend(if roguelike_text_updates_enabled(app) {
         collect_roguelike_text_updates(app, index, region_id)
     } else {
         collect_roguelike_runtime_messages(app, region_id)
-    };
+    });
 
     append_roguelike_screen_messages(app, &output);
 
@@ -2545,23 +2827,57 @@ fn handle_command(app: &mut TerminalApp, input: &str) -> (bool, Vec<String>) {
                 output.push(format!("Current region '{}' not found.", app.current_map));
             }
         }
+        "journal" | "quests" => {
+            if let Some(region) = app.current_region() {
+                if let Some(text) = sg::render_player_journal(&region.map) {
+                    output.push(text);
+                } else {
+                    output.push("No local player found.".into());
+                }
+            } else {
+                output.push(format!("Current region '{}' not found.", app.current_map));
+            }
+        }
         "help" => output.push(
             [
                 "Commands:",
                 "  look | l           Show the current room",
                 "  inventory | inv    Show your inventory",
+                "  journal | quests   Show your quest journal",
+                "  list [vendor]      Show the wares of a nearby merchant",
+                "  buy [n] <item>     Buy from a nearby merchant",
+                "  sell [n] <item>    Sell to a nearby merchant",
                 "  north | east | south | west",
                 "  n | e | s | w",
                 "                     Move through a text exit",
                 "  go <name>          Move by exit direction or title",
                 "  <intent> <target>  Trigger a configured player intent on a visible target",
                 "  intent <name>      Set the current player intent",
+                "  save [slot]        Save the game (default slot: quicksave)",
+                "  load [slot]        Load a saved game",
                 "  help               Show this help",
                 "  quit | exit        Leave the client",
             ]
             .join("\n"),
         ),
         "quit" | "exit" => return (false, output),
+        _ if lower == "save" || lower.starts_with("save ") => {
+            output.push(app.save_game(input["save".len()..].trim()));
+        }
+        _ if lower == "load" || lower.starts_with("load ") => {
+            output.push(app.load_game(input["load".len()..].trim()));
+            output.push(app.render_room_text());
+        }
+        _ if lower == "list"
+            || ["list ", "buy ", "sell "]
+                .iter()
+                .any(|verb| lower.starts_with(verb)) =>
+        {
+            app.server
+                .local_player_action(EntityAction::TextCommand(input.to_string()));
+            app.tick();
+            output.extend(collect_region_output(app, false));
+        }
         _ if lower.starts_with("go ") => {
             let target = input["go ".len()..].trim().to_ascii_lowercase();
             if target.is_empty() {
@@ -3006,6 +3322,7 @@ fn render_roguelike_tui_frame(
         hint: String::new(),
         message: roguelike_screen_message(app, message),
         input_prompt: input_prompt.map(str::to_string),
+        fov: app.current_fov(),
     };
 
     let Some(layout) = shared::terminal_screen::project_terminal_screen_layout(&app.project) else {
@@ -3037,6 +3354,31 @@ fn render_roguelike_tui_frame(
     }
 }
 
+/// Dim the remembered cells of the game widget, keeping runs of equally
+/// styled cells in one span.
+fn roguelike_fov_text(lines: &[String], fov: &RoguelikeFov) -> Text<'static> {
+    let lines = lines
+        .iter()
+        .enumerate()
+        .map(|(y, row)| {
+            let mut spans: Vec<Span<'static>> = Vec::new();
+            for (x, glyph) in row.chars().enumerate() {
+                let style = if fov.cell(x as i32, y as i32) == RoguelikeCell::Remembered {
+                    Style::default().fg(Color::DarkGray)
+                } else {
+                    Style::default()
+                };
+                match spans.last_mut() {
+                    Some(span) if span.style == style => span.content.to_mut().push(glyph),
+                    _ => spans.push(Span::styled(glyph.to_string(), style)),
+                }
+            }
+            Line::from(spans)
+        })
+        .collect::<Vec<_>>();
+    Text::from(lines)
+}
+
 fn terminal_rect_to_tui(
     rect: &shared::terminal_screen::TerminalRect,
     layout: &shared::terminal_screen::TerminalScreenLayout,
@@ -3073,6 +3415,10 @@ fn render_roguelike_tui_widget(
     let text = lines.join("\n");
     match widget.role.as_str() {
         "game" => {
+            let text = match &screen_frame.fov {
+                Some(fov) => roguelike_fov_text(&lines, fov),
+                None => Text::from(text),
+            };
             frame.render_widget(
                 Paragraph::new(text).style(Style::default().fg(Color::LightGreen)),
                 rect,
@@ -3307,6 +3653,7 @@ fn handle_roguelike_input(app: &mut TerminalApp, input: &str) -> (bool, Option<S
                     "  movement keys come from the active player's [input] table",
                     "  look - redraw",
                     "  wait or . - advance one tick",
+                    "  save [slot] / load [slot] - save or restore the game",
                     "  quit - leave the client",
                 ]
                 .join("\n"),
@@ -3317,6 +3664,14 @@ fn handle_roguelike_input(app: &mut TerminalApp, input: &str) -> (bool, Option<S
             let output = collect_roguelike_screen_output(app);
             (true, (!output.is_empty()).then(|| output.join("\n")))
         }
+        _ if command == "save" || command.starts_with("save ") => (
+            true,
+            Some(app.save_game(input.trim()["save".len()..].trim())),
+        ),
+        _ if command == "load" || command.starts_with("load ") => (
+            true,
+            Some(app.load_game(input.trim()["load".len()..].trim())),
+        ),
         _ => {
             if let Some(action) = roguelike_input_action(app, &command) {
                 let message = apply_roguelike_player_action(app, action);
@@ -3528,6 +3883,7 @@ fn render_roguelike_view_with_message(app: &TerminalApp, message: Option<&str>)
             hint: String::new(),
             message: roguelike_screen_message(app, message),
             input_prompt: None,
+            fov: app.current_fov(),
         },
     )
 }
@@ -3820,6 +4176,13 @@ fn main() {
         }
         return;
     }
+    if args.get(1).map(String::as_str) == Some("sim") {
+        if let Err(err) = run_sim_command(&args[2..]) {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+        return;
+    }
 
     let cli_options = match parse_terminal_args(&args) {
         Ok(options) => options,
@@ -3837,13 +4200,23 @@ fn main() {
         }
     };
 
-    let app = match TerminalApp::load(&path) {
+    let app = match &cli_options.connect {
+        Some(addr) => {
+            TerminalApp::connect(&path, addr, cli_options.name.as_deref().unwrap_or_default())
+        }
+        None => TerminalApp::load(&path),
+    };
+    let mut app = match app {
         Ok(app) => app,
         Err(err) => {
             eprintln!("{}", err);
             std::process::exit(1);
         }
     };
+    if let Some(slot) = &cli_options.load_slot {
+        let notice = app.load_game(slot);
+        app.save_notices.push(notice);
+    }
 
     let mode = match cli_options
         .mode
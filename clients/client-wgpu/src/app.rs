@@ -67,6 +67,7 @@ pub struct EldironPlayerApp {
     pub window_scale: f32,
     pub iso_paint_overlay_cache: IsoPaintRenderCache,
     pub render_debug: bool,
+    pub load_slot: Option<String>,
     render_debug_frames: u32,
     render_debug_last_log: Instant,
     render_debug_update_ms: f64,
@@ -111,6 +112,7 @@ impl EldironPlayerApp {
             window_scale: 1.0,
             iso_paint_overlay_cache: IsoPaintRenderCache::default(),
             render_debug,
+            load_slot: None,
             render_debug_frames: 0,
             render_debug_last_log: Instant::now(),
             render_debug_update_ms: 0.0,
@@ -125,9 +127,12 @@ impl EldironPlayerApp {
     pub fn from_args(args: Vec<String>) -> Self {
         let mut data_path = None;
         let mut render_debug = false;
-        for arg in args.into_iter().skip(1) {
+        let mut load_slot = None;
+        let mut args = args.into_iter().skip(1);
+        while let Some(arg) = args.next() {
             match arg.as_str() {
                 "--render-debug" => render_debug = true,
+                "--load" => load_slot = args.next(),
                 _ if data_path.is_none() => data_path = Some(PathBuf::from(arg)),
                 _ => {}
             }
@@ -138,7 +143,9 @@ impl EldironPlayerApp {
                 std::env::set_var("ELDIRON_RENDER_DEBUG", "1");
             }
         }
-        Self::new(data_path)
+        let mut app = Self::new(data_path);
+        app.load_slot = load_slot;
+        app
     }
 
     fn resolve_data_path(&self) -> Option<PathBuf> {
@@ -232,6 +239,15 @@ impl EldironPlayerApp {
         let commands = setup_client(&mut self.rusterix, &mut project);
         self.rusterix.server.process_client_commands(commands);
         warmup_runtime(&mut self.rusterix, &mut project, 3);
+        if let Some(slot) = self.load_slot.clone()
+            && let Some(game_path) = self.resolve_data_path()
+        {
+            match load_game_slot(&mut self.rusterix, &game_path, &slot) {
+                Ok(Some(region)) => self.rusterix.client.current_map = region,
+                Ok(None) => {}
+                Err(err) => eprintln!("{}", err),
+            }
+        }
         self.rusterix.client.server_time = project.time;
         self.project = project;
         self.initialized = true;
@@ -258,6 +274,11 @@ impl EldironPlayerApp {
         if let Some(new_region_name) = self.rusterix.update_server() {
             self.rusterix.client.current_map = new_region_name;
         }
+        if let Some(game_path) = self.resolve_data_path()
+            && let Some(region) = process_save_game_requests(&mut self.rusterix, &game_path)
+        {
+            self.rusterix.client.current_map = region;
+        }
 
         let current_map = self.rusterix.client.current_map.clone();
         for r in &mut self.project.regions {
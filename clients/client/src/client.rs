@@ -99,6 +99,7 @@ pub struct Client {
     rusterix: Rusterix,
     iso_paint_overlay_cache: IsoPaintRenderCache,
     cmd_line_path: Option<PathBuf>,
+    load_slot: Option<String>,
     frame_timing_stats: FrameTimingStats,
 }
 
@@ -233,6 +234,7 @@ impl TheTrait for Client {
             rusterix,
             iso_paint_overlay_cache: IsoPaintRenderCache::default(),
             cmd_line_path: None,
+            load_slot: None,
             frame_timing_stats: FrameTimingStats::default(),
         }
     }
@@ -246,6 +248,9 @@ impl TheTrait for Client {
                 self.cmd_line_path = Some(path);
             }
         }
+        if let Some(index) = args.iter().position(|arg| arg == "--load") {
+            self.load_slot = args.get(index + 1).cloned();
+        }
 
         // Load the game data path
         if let Some(path) = self.get_data_path() {
@@ -275,6 +280,13 @@ impl TheTrait for Client {
             let commands = setup_client(&mut self.rusterix, &mut project);
             self.rusterix.server.process_client_commands(commands);
             warmup_runtime(&mut self.rusterix, &mut project, 3);
+            if let (Some(slot), Some(game_path)) = (&self.load_slot, &self.cmd_line_path) {
+                match load_game_slot(&mut self.rusterix, game_path, slot) {
+                    Ok(Some(region)) => self.rusterix.client.current_map = region,
+                    Ok(None) => {}
+                    Err(err) => eprintln!("{}", err),
+                }
+            }
             self.rusterix.client.server_time = project.time;
             self.project = project;
 
@@ -386,6 +398,11 @@ impl TheTrait for Client {
         if let Some(new_region_name) = self.rusterix.update_server() {
             self.rusterix.client.current_map = new_region_name;
         }
+        if let Some(game_path) = &self.cmd_line_path
+            && let Some(region) = process_save_game_requests(&mut self.rusterix, game_path)
+        {
+            self.rusterix.client.current_map = region;
+        }
         let update_time = update_started.elapsed();
 
         let mut sync_time = Duration::ZERO;
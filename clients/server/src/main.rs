@@ -0,0 +1,158 @@
+use rusterix::Rusterix;
+use rusterix::server::net::{DEFAULT_NET_PORT, NetHost};
+use shared::project::Project;
+use shared::rusterix_utils::{insert_content_into_maps, start_region_player, start_server};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+use toml::Table;
+
+struct ServerCliOptions {
+    path: Option<PathBuf>,
+    bind: String,
+}
+
+fn parse_server_args(args: &[String]) -> Result<ServerCliOptions, String> {
+    let mut options = ServerCliOptions {
+        path: None,
+        bind: format!("0.0.0.0:{}", DEFAULT_NET_PORT),
+    };
+    let mut index = 1;
+    while index < args.len() {
+        let arg = &args[index];
+        if arg == "--bind" {
+            index += 1;
+            let Some(value) = args.get(index) else {
+                return Err("Missing address after --bind.".to_string());
+            };
+            options.bind = value.clone();
+        } else if let Some(value) = arg.strip_prefix("--bind=") {
+            options.bind = value.to_string();
+        } else if arg == "--help" || arg == "-h" || arg == "help" {
+            return Err(server_usage().to_string());
+        } else if arg.starts_with('-') {
+            return Err(format!("Unknown option '{}'.\n{}", arg, server_usage()));
+        } else if options.path.is_none() {
+            options.path = Some(PathBuf::from(arg));
+        } else {
+            return Err(format!(
+                "Unexpected argument '{}'.\n{}",
+                arg,
+                server_usage()
+            ));
+        }
+        index += 1;
+    }
+    Ok(options)
+}
+
+fn server_usage() -> &'static str {
+    "Usage:\n\
+       eldiron-server [game.eldiron] [--bind address:port]\n\
+     Players join with:\n\
+       eldiron-client-terminal game.eldiron --connect address:port [--name name]"
+}
+
+fn resolve_data_path(path_arg: Option<&PathBuf>) -> Result<PathBuf, String> {
+    if let Some(path) = path_arg {
+        return Ok(path.clone());
+    }
+    let cwd = std::env::current_dir().map_err(|err| err.to_string())?;
+    let mut entries = fs::read_dir(&cwd)
+        .map_err(|err| format!("Failed to read {}: {}", cwd.display(), err))?
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "eldiron"))
+        .collect::<Vec<_>>();
+    entries.sort();
+    entries
+        .into_iter()
+        .next()
+        .ok_or_else(|| "No .eldiron file given or found in the current directory.".into())
+}
+
+fn load_project(path: &Path) -> Result<Project, String> {
+    let contents = fs::read_to_string(path)
+        .map_err(|err| format!("Failed to read {}: {}", path.display(), err))?;
+    let mut project: Project = serde_json::from_str(&contents)
+        .map_err(|err| format!("Failed to parse {}: {}", path.display(), err))?;
+    project.migrate_default_ruleset();
+    Ok(project)
+}
+
+fn config_value(src: &str, section: &str, key: &str) -> Option<toml::Value> {
+    src.parse::<Table>()
+        .ok()?
+        .get(section)?
+        .as_table()?
+        .get(key)
+        .cloned()
+}
+
+fn run(options: ServerCliOptions) -> Result<(), String> {
+    let path = resolve_data_path(options.path.as_ref())?;
+    let mut project = load_project(&path)?;
+    insert_content_into_maps(&mut project);
+    let (start_region, template) = start_region_player(&project)?;
+
+    let mut rusterix = Rusterix::new_without_audio();
+    rusterix.assets.config = project.config.clone();
+    for region in &project.regions {
+        rusterix
+            .assets
+            .maps
+            .insert(region.map.name.clone(), region.map.clone());
+    }
+    start_server(&mut rusterix, &mut project, false);
+
+    let mut host = NetHost::bind(&options.bind, &mut rusterix.server, start_region, template)?;
+    println!(
+        "Serving '{}' on {}.",
+        path.display(),
+        host.local_addr()
+            .map(|addr| addr.to_string())
+            .unwrap_or(options.bind)
+    );
+
+    let tick_ms = config_value(&project.config, "game", "game_tick_ms")
+        .and_then(|value| value.as_integer())
+        .unwrap_or(250)
+        .max(1) as u64;
+    let tick_dt = Duration::from_millis(tick_ms);
+    let frame_dt = Duration::from_millis(16);
+    let mut next_tick = Instant::now() + tick_dt;
+
+    loop {
+        let frame_start = Instant::now();
+        if frame_start >= next_tick {
+            rusterix.server.system_tick();
+            next_tick += tick_dt;
+        }
+        rusterix.server.redraw_tick();
+        rusterix.update_server();
+        for line in host.update(&mut rusterix.server) {
+            println!("{} ({} online)", line, host.player_count());
+        }
+
+        let elapsed = frame_start.elapsed();
+        if elapsed < frame_dt {
+            thread::sleep(frame_dt - elapsed);
+        }
+    }
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    let options = match parse_server_args(&args) {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+    };
+    if let Err(err) = run(options) {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    }
+}
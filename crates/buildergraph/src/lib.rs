@@ -4137,6 +4137,26 @@ impl BuilderDocument {
             Self::Graph(graph) => graph.render_preview(size),
         }
     }
+    /// True when the document replaces its host surface with cuts, so the
+    /// host sector is hidden where the asset is placed.
+    pub fn hides_host(&self) -> bool {
+        self.evaluate()
+            .map(|assembly| {
+                assembly.cuts.iter().any(|cut| {
+                    matches!(
+                        cut,
+                        BuilderCutMask::Rect {
+                            mode: BuilderCutMode::Replace,
+                            ..
+                        } | BuilderCutMask::Loop {
+                            mode: BuilderCutMode::Replace,
+                            ..
+                        }
+                    )
+                })
+            })
+            .unwrap_or(false)
+    }
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -0,0 +1,316 @@
+use rusterix::vm::{Parser, Scanner, Token, TokenType, VM};
+use std::panic::{AssertUnwindSafe, catch_unwind};
+use std::path::{Path, PathBuf};
+
+/// A problem reported by the parser or compiler. Lines are 0-based.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Diagnostic {
+    pub line: usize,
+    pub message: String,
+}
+
+/// A symbol declared in a document. Lines and columns are 0-based.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Symbol {
+    pub name: String,
+    pub kind: SymbolKind,
+    pub line: usize,
+    pub column: usize,
+    /// The declaration line, e.g. `fn event(event, value)`.
+    pub detail: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SymbolKind {
+    Function,
+    Variable,
+}
+
+/// An `import "file.eldrin";` statement.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Import {
+    pub path: PathBuf,
+    pub line: usize,
+}
+
+/// A location in another (or the same) file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Definition {
+    pub path: PathBuf,
+    pub line: usize,
+    pub column: usize,
+}
+
+/// Parse and compile the source and collect the errors. The VM stops at the
+/// first error, so there is at most one diagnostic.
+pub fn diagnostics(path: &Path, source: &str) -> Vec<Diagnostic> {
+    let result = catch_unwind(AssertUnwindSafe(|| compile(path, source)));
+    match result {
+        Ok(Ok(())) => vec![],
+        Ok(Err((line, error_path, message))) => {
+            let message = if error_path.as_path() != path && !error_path.as_os_str().is_empty() {
+                format!("{} (in {})", message, error_path.display())
+            } else {
+                message
+            };
+            vec![Diagnostic {
+                line: line.saturating_sub(1),
+                message,
+            }]
+        }
+        Err(_) => vec![Diagnostic {
+            line: source.lines().count().saturating_sub(1),
+            message: "Unexpected end of script".into(),
+        }],
+    }
+}
+
+fn compile(path: &Path, source: &str) -> Result<(), (usize, PathBuf, String)> {
+    let name = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_else(|| "main".into());
+    let mut parser = Parser::new();
+    let module = parser
+        .compile_module(name, source.into(), path.to_path_buf())
+        .map_err(|err| (err.line, err.path, err.message))?;
+    let mut vm = VM::default();
+    vm.compile(&module)
+        .map_err(|err| (err.line, err.path, err.message))
+}
+
+fn tokens(source: &str) -> Vec<Token> {
+    let mut scanner = Scanner::new(source.into());
+    let mut tokens = vec![];
+    loop {
+        let token = scanner.scan_token();
+        if token.kind == TokenType::Eof || tokens.len() > 1_000_000 {
+            break;
+        }
+        tokens.push(token);
+    }
+    tokens
+}
+
+/// The functions and variables declared in the source.
+pub fn symbols(source: &str) -> Vec<Symbol> {
+    let lines: Vec<&str> = source.lines().collect();
+    let tokens = tokens(source);
+    let mut symbols: Vec<Symbol> = vec![];
+    for pair in tokens.windows(2) {
+        let kind = match pair[0].kind {
+            TokenType::Fn => SymbolKind::Function,
+            TokenType::Let => SymbolKind::Variable,
+            _ => continue,
+        };
+        if pair[1].kind != TokenType::Identifier {
+            continue;
+        }
+        let name = pair[1].lexeme.clone();
+        if kind == SymbolKind::Variable && symbols.iter().any(|symbol| symbol.name == name) {
+            continue;
+        }
+        let line = pair[1].line.saturating_sub(1);
+        let text = lines.get(line).copied().unwrap_or_default();
+        let column = find_word(text, &name).unwrap_or(0);
+        symbols.push(Symbol {
+            name,
+            kind,
+            line,
+            column,
+            detail: text.trim().trim_end_matches('{').trim_end().to_string(),
+        });
+    }
+    symbols
+}
+
+/// The files imported by the source, resolved relative to its path.
+pub fn imports(path: &Path, source: &str) -> Vec<Import> {
+    let base_dir = path.parent().unwrap_or_else(|| Path::new(""));
+    tokens(source)
+        .windows(2)
+        .filter(|pair| pair[0].kind == TokenType::Import && pair[1].kind == TokenType::String)
+        .map(|pair| Import {
+            path: base_dir.join(pair[1].lexeme.replace('"', "")),
+            line: pair[1].line.saturating_sub(1),
+        })
+        .collect()
+}
+
+/// Find the definition of the word at the given position: an imported file
+/// when on an import line, otherwise a function in this file or its imports.
+pub fn definition(path: &Path, source: &str, line: usize, column: usize) -> Option<Definition> {
+    if let Some(import) = imports(path, source)
+        .into_iter()
+        .find(|import| import.line == line)
+    {
+        return Some(Definition {
+            path: import.path,
+            line: 0,
+            column: 0,
+        });
+    }
+
+    let word = word_at(source, line, column)?;
+    let mut visited = vec![];
+    find_function(path, source, &word, &mut visited)
+}
+
+fn find_function(
+    path: &Path,
+    source: &str,
+    name: &str,
+    visited: &mut Vec<PathBuf>,
+) -> Option<Definition> {
+    visited.push(path.to_path_buf());
+    if let Some(symbol) = symbols(source)
+        .into_iter()
+        .find(|symbol| symbol.kind == SymbolKind::Function && symbol.name == name)
+    {
+        return Some(Definition {
+            path: path.to_path_buf(),
+            line: symbol.line,
+            column: symbol.column,
+        });
+    }
+    for import in imports(path, source) {
+        if visited.contains(&import.path) {
+            continue;
+        }
+        if let Ok(imported) = std::fs::read_to_string(&import.path)
+            && let Some(definition) = find_function(&import.path, &imported, name, visited)
+        {
+            return Some(definition);
+        }
+    }
+    None
+}
+
+/// Functions declared in the imported files.
+pub fn imported_symbols(path: &Path, source: &str) -> Vec<Symbol> {
+    imports(path, source)
+        .into_iter()
+        .filter_map(|import| std::fs::read_to_string(&import.path).ok())
+        .flat_map(|imported| symbols(&imported))
+        .filter(|symbol| symbol.kind == SymbolKind::Function)
+        .collect()
+}
+
+fn is_word_char(ch: char) -> bool {
+    ch.is_ascii_alphanumeric() || ch == '_'
+}
+
+/// The identifier at a 0-based line and character.
+pub fn word_at(source: &str, line: usize, column: usize) -> Option<String> {
+    let chars: Vec<char> = source.lines().nth(line)?.chars().collect();
+    let column = column.min(chars.len());
+    let mut start = column;
+    while start > 0 && is_word_char(chars[start - 1]) {
+        start -= 1;
+    }
+    let mut end = column;
+    while end < chars.len() && is_word_char(chars[end]) {
+        end += 1;
+    }
+    (start < end).then(|| chars[start..end].iter().collect())
+}
+
+/// The identifier prefix left of the cursor, used to filter completions.
+pub fn prefix_at(source: &str, line: usize, column: usize) -> String {
+    let Some(text) = source.lines().nth(line) else {
+        return String::new();
+    };
+    let chars: Vec<char> = text.chars().take(column).collect();
+    let start = chars
+        .iter()
+        .rposition(|ch| !is_word_char(*ch))
+        .map(|i| i + 1)
+        .unwrap_or(0);
+    chars[start..].iter().collect()
+}
+
+/// The character column of `word` as a whole word in `text`.
+fn find_word(text: &str, word: &str) -> Option<usize> {
+    let mut offset = 0;
+    while let Some(found) = text[offset..].find(word) {
+        let start = offset + found;
+        let end = start + word.len();
+        let before = text[..start].chars().next_back();
+        let after = text[end..].chars().next();
+        if !before.is_some_and(is_word_char) && !after.is_some_and(is_word_char) {
+            return Some(text[..start].chars().count());
+        }
+        offset = end;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCRIPT: &str = r#"fn helper(a) {
+    return a * 2;
+}
+
+fn event(event, value) {
+    let doubled = helper(value);
+    if event == "startup" {
+        set_attr("count", doubled);
+    }
+}
+"#;
+
+    #[test]
+    fn valid_scripts_have_no_diagnostics() {
+        assert!(diagnostics(Path::new("/tmp/orc.eldrin"), SCRIPT).is_empty());
+    }
+
+    #[test]
+    fn errors_report_their_line() {
+        let source = "fn event(event, value) {\n    let a = 1;\n    unknown_call(a);\n}\n";
+        let errors = diagnostics(Path::new("/tmp/orc.eldrin"), source);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].line, 2);
+        assert!(errors[0].message.contains("unknown_call"));
+    }
+
+    #[test]
+    fn incomplete_scripts_do_not_panic() {
+        let errors = diagnostics(Path::new("/tmp/orc.eldrin"), "fn event(");
+        assert_eq!(errors.len(), 1);
+    }
+
+    #[test]
+    fn finds_function_definitions() {
+        let path = Path::new("/tmp/orc.eldrin");
+        let helper = definition(path, SCRIPT, 5, 20).unwrap();
+        assert_eq!(helper.line, 0);
+        assert_eq!(helper.column, 3);
+        assert!(definition(path, SCRIPT, 7, 10).is_none());
+    }
+
+    #[test]
+    fn resolves_imports_relative_to_the_script() {
+        let source = "import \"lib/common.eldrin\";\n";
+        let path = Path::new("/game/scripts/orc.eldrin");
+        let import = definition(path, source, 0, 12).unwrap();
+        assert_eq!(
+            import.path,
+            PathBuf::from("/game/scripts/lib/common.eldrin")
+        );
+    }
+
+    #[test]
+    fn words_and_prefixes() {
+        assert_eq!(word_at(SCRIPT, 5, 22).as_deref(), Some("helper"));
+        assert_eq!(prefix_at(SCRIPT, 7, 13), "set_a");
+        let declared = symbols(SCRIPT);
+        assert_eq!(declared[0].detail, "fn helper(a)");
+        assert!(
+            declared
+                .iter()
+                .any(|s| s.name == "doubled" && s.kind == SymbolKind::Variable)
+        );
+    }
+}
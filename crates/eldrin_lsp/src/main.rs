@@ -0,0 +1,77 @@
+mod analysis;
+mod reference;
+mod server;
+
+use serde_json::Value;
+use server::LspServer;
+use std::io::{self, BufRead, Write};
+
+/// Read one `Content-Length` framed message. Returns `None` at end of input.
+fn read_message(input: &mut impl BufRead) -> io::Result<Option<Value>> {
+    let mut length = None;
+    loop {
+        let mut header = String::new();
+        if input.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':')
+            && name.eq_ignore_ascii_case("Content-Length")
+        {
+            length = value.trim().parse::<usize>().ok();
+        }
+    }
+    let Some(length) = length else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Missing Content-Length header",
+        ));
+    };
+    let mut body = vec![0; length];
+    input.read_exact(&mut body)?;
+    serde_json::from_slice(&body)
+        .map(Some)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+fn write_message(output: &mut impl Write, message: &Value) -> io::Result<()> {
+    let body = message.to_string();
+    write!(output, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    output.flush()
+}
+
+fn run() -> io::Result<bool> {
+    let stdin = io::stdin();
+    let mut input = stdin.lock();
+    let mut output = io::stdout().lock();
+    let mut server = LspServer::new();
+
+    while let Some(message) = read_message(&mut input)? {
+        for response in server.handle(&message) {
+            write_message(&mut output, &response)?;
+        }
+        if server.exit {
+            break;
+        }
+    }
+    Ok(server.shutdown)
+}
+
+fn main() {
+    if std::env::args().any(|arg| arg == "--help" || arg == "-h") {
+        println!("Usage:\n  eldrin-lsp\nSpeaks the Language Server Protocol over stdio.");
+        return;
+    }
+    match run() {
+        Ok(true) => {}
+        // Exiting without a shutdown request is an error per the protocol.
+        Ok(false) => std::process::exit(1),
+        Err(err) => {
+            eprintln!("eldrin-lsp: {}", err);
+            std::process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,132 @@
+use rusterix::vm::NodeOp;
+use rusterix::vm::builtin::Builtins;
+use std::collections::BTreeMap;
+
+/// The server command reference, the source of host call signatures and docs.
+const SERVER_COMMANDS: &str =
+    include_str!("../../../docs/docs/characters_items/server_commands.md");
+
+pub const KEYWORDS: &[&str] = &[
+    "fn", "let", "if", "else", "for", "in", "match", "return", "import", "true", "false",
+];
+
+/// A callable builtin: a VM function or a host call handled by the server.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BuiltinInfo {
+    pub name: String,
+    pub arity: u8,
+    pub host_call: bool,
+    pub signature: String,
+    pub documentation: String,
+}
+
+/// All builtins known to the compiler, by name.
+pub struct Reference {
+    pub builtins: BTreeMap<String, BuiltinInfo>,
+}
+
+impl Default for Reference {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Reference {
+    pub fn new() -> Self {
+        let docs = parse_command_docs(SERVER_COMMANDS);
+        let mut builtins = BTreeMap::new();
+        for (name, (arity, op)) in Builtins::default().entries() {
+            let host_call = matches!(op, NodeOp::HostCall { .. });
+            let (signature, documentation) = match docs.get(name.as_str()) {
+                Some((signature, documentation)) => (signature.clone(), documentation.clone()),
+                None => (generic_signature(name, *arity, op), String::new()),
+            };
+            builtins.insert(
+                name.clone(),
+                BuiltinInfo {
+                    name: name.clone(),
+                    arity: *arity,
+                    host_call,
+                    signature,
+                    documentation,
+                },
+            );
+        }
+        Self { builtins }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&BuiltinInfo> {
+        self.builtins.get(name)
+    }
+}
+
+fn generic_signature(name: &str, arity: u8, op: &NodeOp) -> String {
+    if matches!(op, NodeOp::Print(_) | NodeOp::Format(_)) {
+        return format!("{}(...)", name);
+    }
+    let args: Vec<String> = (0..arity)
+        .map(|i| {
+            ["a", "b", "c", "d"]
+                .get(i as usize)
+                .unwrap_or(&"arg")
+                .to_string()
+        })
+        .collect();
+    format!("{}({})", name, args.join(", "))
+}
+
+/// Extract `(signature, description)` for every `` ## `name` `` section of the
+/// command reference. The signature is the first line of the first code block.
+fn parse_command_docs(markdown: &str) -> BTreeMap<String, (String, String)> {
+    let mut docs = BTreeMap::new();
+    for section in markdown.split("\n## `").skip(1) {
+        let Some((name, body)) = section.split_once('`') else {
+            continue;
+        };
+        let body = body.split("\n---").next().unwrap_or(body);
+
+        let mut description = vec![];
+        let mut signature = None;
+        let mut in_code = false;
+        for line in body.lines() {
+            let trimmed = line.trim();
+            if trimmed.starts_with("```") {
+                if in_code || signature.is_some() {
+                    break;
+                }
+                in_code = true;
+                continue;
+            }
+            if in_code {
+                if signature.is_none() && !trimmed.is_empty() {
+                    signature = Some(trimmed.trim_end_matches(';').to_string());
+                }
+            } else if !trimmed.is_empty() && !trimmed.starts_with('#') {
+                description.push(trimmed.trim_matches('*'));
+            }
+        }
+
+        if let Some(signature) = signature {
+            docs.insert(name.to_string(), (signature, description.join("\n")));
+        }
+    }
+    docs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn host_calls_use_documented_signatures() {
+        let reference = Reference::new();
+        let equip = reference.get("equip").unwrap();
+        assert!(equip.host_call);
+        assert_eq!(equip.signature, "equip(item_id)");
+        assert!(equip.documentation.contains("Equips an item"));
+
+        let sin = reference.get("sin").unwrap();
+        assert!(!sin.host_call);
+        assert_eq!(sin.signature, "sin(a)");
+    }
+}
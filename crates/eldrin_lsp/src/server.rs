@@ -0,0 +1,375 @@
+use crate::analysis::{self, SymbolKind};
+use crate::reference::{KEYWORDS, Reference};
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+// LSP constants used by the server.
+const TEXT_DOCUMENT_SYNC_FULL: u32 = 1;
+const SEVERITY_ERROR: u32 = 1;
+const COMPLETION_KEYWORD: u32 = 14;
+const COMPLETION_FUNCTION: u32 = 3;
+const COMPLETION_VARIABLE: u32 = 6;
+const METHOD_NOT_FOUND: i64 = -32601;
+
+/// The language server state: the open documents and the builtin reference.
+pub struct LspServer {
+    documents: HashMap<String, String>,
+    reference: Reference,
+    pub shutdown: bool,
+    pub exit: bool,
+}
+
+impl Default for LspServer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LspServer {
+    pub fn new() -> Self {
+        Self {
+            documents: HashMap::new(),
+            reference: Reference::new(),
+            shutdown: false,
+            exit: false,
+        }
+    }
+
+    /// Handle one incoming JSON-RPC message and return the messages to send.
+    pub fn handle(&mut self, message: &Value) -> Vec<Value> {
+        let method = message["method"].as_str().unwrap_or_default();
+        let params = &message["params"];
+        let id = message.get("id").cloned();
+
+        let result = match method {
+            "initialize" => Some(self.initialize()),
+            "shutdown" => {
+                self.shutdown = true;
+                Some(Value::Null)
+            }
+            "exit" => {
+                self.exit = true;
+                None
+            }
+            "textDocument/didOpen" => {
+                let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+                let text = params["textDocument"]["text"].as_str().unwrap_or_default();
+                return self.update_document(uri, text.to_string());
+            }
+            "textDocument/didChange" => {
+                let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+                // Full sync: the last change holds the whole document.
+                let Some(text) = params["contentChanges"]
+                    .as_array()
+                    .and_then(|changes| changes.last())
+                    .and_then(|change| change["text"].as_str())
+                else {
+                    return vec![];
+                };
+                return self.update_document(uri, text.to_string());
+            }
+            "textDocument/didClose" => {
+                let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+                self.documents.remove(uri);
+                return vec![publish_diagnostics(uri, vec![])];
+            }
+            "textDocument/completion" => Some(self.completion(params)),
+            "textDocument/hover" => Some(self.hover(params)),
+            "textDocument/definition" => Some(self.definition(params)),
+            _ => None,
+        };
+
+        // Notifications never get a response.
+        let Some(id) = id else {
+            return vec![];
+        };
+        match result {
+            Some(result) => vec![json!({ "jsonrpc": "2.0", "id": id, "result": result })],
+            None => vec![json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": { "code": METHOD_NOT_FOUND, "message": format!("Unknown method '{}'", method) }
+            })],
+        }
+    }
+
+    fn initialize(&self) -> Value {
+        json!({
+            "capabilities": {
+                "textDocumentSync": TEXT_DOCUMENT_SYNC_FULL,
+                "completionProvider": { "triggerCharacters": [] },
+                "hoverProvider": true,
+                "definitionProvider": true,
+            },
+            "serverInfo": { "name": "eldrin-lsp", "version": env!("CARGO_PKG_VERSION") },
+        })
+    }
+
+    fn update_document(&mut self, uri: &str, text: String) -> Vec<Value> {
+        let path = uri_to_path(uri);
+        let diagnostics = analysis::diagnostics(&path, &text)
+            .into_iter()
+            .map(|diagnostic| {
+                let length = text
+                    .lines()
+                    .nth(diagnostic.line)
+                    .map(utf16_len)
+                    .unwrap_or(0);
+                json!({
+                    "range": range(diagnostic.line, 0, diagnostic.line, length),
+                    "severity": SEVERITY_ERROR,
+                    "source": "eldrin",
+                    "message": diagnostic.message,
+                })
+            })
+            .collect();
+        self.documents.insert(uri.to_string(), text);
+        vec![publish_diagnostics(uri, diagnostics)]
+    }
+
+    /// The document, its path and the 0-based cursor position of a request.
+    fn position<'a>(&'a self, params: &Value) -> Option<(&'a str, PathBuf, usize, usize)> {
+        let uri = params["textDocument"]["uri"].as_str()?;
+        let text = self.documents.get(uri)?;
+        let line = params["position"]["line"].as_u64()? as usize;
+        let character = params["position"]["character"].as_u64()? as usize;
+        let column = text
+            .lines()
+            .nth(line)
+            .map(|text| utf16_to_char(text, character))
+            .unwrap_or(0);
+        Some((text, uri_to_path(uri), line, column))
+    }
+
+    fn completion(&self, params: &Value) -> Value {
+        let Some((text, path, line, column)) = self.position(params) else {
+            return json!([]);
+        };
+        let prefix = analysis::prefix_at(text, line, column);
+        let mut items = vec![];
+        let mut push = |label: &str, kind: u32, detail: &str, documentation: &str| {
+            if label.starts_with(&prefix)
+                && !items.iter().any(|item: &Value| item["label"] == label)
+            {
+                items.push(json!({
+                    "label": label,
+                    "kind": kind,
+                    "detail": detail,
+                    "documentation": documentation,
+                }));
+            }
+        };
+
+        let symbols = analysis::symbols(text)
+            .into_iter()
+            .chain(analysis::imported_symbols(&path, text));
+        for symbol in symbols {
+            let kind = match symbol.kind {
+                SymbolKind::Function => COMPLETION_FUNCTION,
+                SymbolKind::Variable => COMPLETION_VARIABLE,
+            };
+            push(&symbol.name, kind, &symbol.detail, "");
+        }
+        for builtin in self.reference.builtins.values() {
+            push(
+                &builtin.name,
+                COMPLETION_FUNCTION,
+                &builtin.signature,
+                &builtin.documentation,
+            );
+        }
+        for keyword in KEYWORDS {
+            push(keyword, COMPLETION_KEYWORD, "", "");
+        }
+        Value::Array(items)
+    }
+
+    fn hover(&self, params: &Value) -> Value {
+        let Some((text, path, line, column)) = self.position(params) else {
+            return Value::Null;
+        };
+        let Some(word) = analysis::word_at(text, line, column) else {
+            return Value::Null;
+        };
+
+        let contents = if let Some(builtin) = self.reference.get(&word) {
+            let kind = if builtin.host_call {
+                "Server command"
+            } else {
+                "Builtin"
+            };
+            format!(
+                "```eldrin\n{}\n```\n{}\n\n{}",
+                builtin.signature, kind, builtin.documentation
+            )
+        } else if let Some(symbol) = analysis::symbols(text)
+            .into_iter()
+            .chain(analysis::imported_symbols(&path, text))
+            .find(|symbol| symbol.name == word)
+        {
+            format!("```eldrin\n{}\n```", symbol.detail)
+        } else {
+            return Value::Null;
+        };
+        json!({ "contents": { "kind": "markdown", "value": contents.trim_end() } })
+    }
+
+    fn definition(&self, params: &Value) -> Value {
+        let Some((text, path, line, column)) = self.position(params) else {
+            return Value::Null;
+        };
+        match analysis::definition(&path, text, line, column) {
+            Some(definition) => json!({
+                "uri": path_to_uri(&definition.path),
+                "range": range(definition.line, definition.column, definition.line, definition.column),
+            }),
+            None => Value::Null,
+        }
+    }
+}
+
+fn publish_diagnostics(uri: &str, diagnostics: Vec<Value>) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/publishDiagnostics",
+        "params": { "uri": uri, "diagnostics": diagnostics },
+    })
+}
+
+fn range(start_line: usize, start: usize, end_line: usize, end: usize) -> Value {
+    json!({
+        "start": { "line": start_line, "character": start },
+        "end": { "line": end_line, "character": end },
+    })
+}
+
+fn utf16_len(text: &str) -> usize {
+    text.chars().map(char::len_utf16).sum()
+}
+
+/// Convert an LSP (UTF-16) character offset into a char index.
+fn utf16_to_char(text: &str, character: usize) -> usize {
+    let mut units = 0;
+    for (index, ch) in text.chars().enumerate() {
+        if units >= character {
+            return index;
+        }
+        units += ch.len_utf16();
+    }
+    text.chars().count()
+}
+
+/// Convert a `file://` URI into a path, decoding percent escapes.
+pub fn uri_to_path(uri: &str) -> PathBuf {
+    let path = uri.strip_prefix("file://").unwrap_or(uri);
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%'
+            && i + 2 < bytes.len()
+            && let Some(hex) = path.get(i + 1..i + 3)
+            && let Ok(byte) = u8::from_str_radix(hex, 16)
+        {
+            decoded.push(byte);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    let path = String::from_utf8_lossy(&decoded).to_string();
+    // Windows URIs look like file:///C:/...
+    match path.strip_prefix('/') {
+        Some(rest) if rest.get(1..2) == Some(":") => PathBuf::from(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+/// Convert a path into a `file://` URI.
+pub fn path_to_uri(path: &Path) -> String {
+    let path = path.to_string_lossy().replace('\\', "/");
+    let mut uri = String::from("file://");
+    if !path.starts_with('/') {
+        uri.push('/');
+    }
+    for byte in path.bytes() {
+        if byte.is_ascii_alphanumeric() || b"/-_.~:".contains(&byte) {
+            uri.push(byte as char);
+        } else {
+            uri.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    uri
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn open(server: &mut LspServer, text: &str) -> Vec<Value> {
+        server.handle(&json!({
+            "jsonrpc": "2.0",
+            "method": "textDocument/didOpen",
+            "params": { "textDocument": { "uri": "file:///game/orc.eldrin", "text": text } },
+        }))
+    }
+
+    fn request(server: &mut LspServer, method: &str, line: u32, character: u32) -> Value {
+        let mut responses = server.handle(&json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": method,
+            "params": {
+                "textDocument": { "uri": "file:///game/orc.eldrin" },
+                "position": { "line": line, "character": character },
+            },
+        }));
+        responses.remove(0)["result"].take()
+    }
+
+    #[test]
+    fn publishes_diagnostics_on_open() {
+        let mut server = LspServer::new();
+        let messages = open(&mut server, "fn event(event, value) {\n    oops(1);\n}\n");
+        let diagnostics = &messages[0]["params"]["diagnostics"];
+        assert_eq!(diagnostics[0]["range"]["start"]["line"], 1);
+    }
+
+    #[test]
+    fn completes_hovers_and_finds_definitions() {
+        let mut server = LspServer::new();
+        let text = "fn helper(a) {\n    return a;\n}\nfn event(event, value) {\n    inv\n    helper(1);\n}\n";
+        open(&mut server, text);
+
+        let completions = request(&mut server, "textDocument/completion", 4, 7);
+        let labels: Vec<&str> = completions
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter_map(|item| item["label"].as_str())
+            .collect();
+        assert!(labels.contains(&"inventory_items"));
+        assert!(!labels.contains(&"helper"));
+
+        let hover = request(&mut server, "textDocument/hover", 5, 6);
+        assert!(
+            hover["contents"]["value"]
+                .as_str()
+                .unwrap()
+                .contains("fn helper(a)")
+        );
+
+        let definition = request(&mut server, "textDocument/definition", 5, 6);
+        assert_eq!(definition["uri"], "file:///game/orc.eldrin");
+        assert_eq!(definition["range"]["start"]["line"], 0);
+    }
+
+    #[test]
+    fn uris_round_trip() {
+        let path = uri_to_path("file:///home/me/My%20Game/orc.eldrin");
+        assert_eq!(path, PathBuf::from("/home/me/My Game/orc.eldrin"));
+        assert_eq!(path_to_uri(&path), "file:///home/me/My%20Game/orc.eldrin");
+    }
+}
@@ -251,6 +251,9 @@ pub struct NoiseField {
     pub octaves: u32,
     pub persistence: f32,
     pub seed: u64,
+    /// Whole noise periods scrolled per animation loop on each axis.
+    #[serde(default)]
+    pub drift: [f32; 2],
 }
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
@@ -341,7 +344,7 @@ pub enum Domain {
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Warp {
     pub source: ScalarSource,
-    pub amount: f32,
+    pub amount: ScalarSource,
 }
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
@@ -445,6 +448,8 @@ pub enum IdSource {
 pub enum ScalarSource {
     Constant(f32),
     Coordinate(CoordinateChannel),
+    /// Normalized animation loop time in `0..1`.
+    Time,
     InputHeight,
     Field(String),
     Pattern {
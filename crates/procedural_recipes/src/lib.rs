@@ -15,8 +15,8 @@ pub use ast::{
 };
 pub use palette::{PaletteError, PaletteModel};
 pub use parser::{
-    ParseError, ParseErrorCode, parse_document, parse_material_document, parse_recipe,
-    parse_sdf_document,
+    FileIncludes, IncludeResolver, ParseError, ParseErrorCode, ParseOptions, expand_recipe_source,
+    parse_document, parse_document_with, parse_material_document, parse_recipe, parse_sdf_document,
 };
 pub use render::{
     RecipeRenderer, RenderError, RenderOptions, RenderSurface, RenderSurfaceFrame,
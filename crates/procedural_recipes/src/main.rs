@@ -1,8 +1,8 @@
 use procedural_recipes::{
-    BinaryOperator, ColorSource, Colorize, CoordinateChannel, MaterialOutput, MaterialRecipe,
-    Output, PaletteMode, Recipe, RecipeDocument, RecipeRenderer, RenderOptions, RenderSurface,
-    RenderSurfaceFrame, RenderSurfaceMapping, ScalarSource, SdfRenderer, UnaryOperator,
-    parse_document,
+    BinaryOperator, ColorSource, Colorize, CoordinateChannel, FileIncludes, MaterialOutput,
+    MaterialRecipe, Output, PaletteMode, ParseOptions, Recipe, RecipeDocument, RecipeRenderer,
+    RenderOptions, RenderSurface, RenderSurfaceFrame, RenderSurfaceMapping, ScalarSource,
+    SdfRenderer, UnaryOperator, parse_document_with,
 };
 use serde::Deserialize;
 use std::{
@@ -589,13 +589,18 @@ fn print_rendered_outputs(
 fn load_document(path: &Path) -> Result<RecipeDocument, String> {
     let source = fs::read_to_string(path)
         .map_err(|error| format!("could not read '{}': {error}", path.display()))?;
-    let mut document = parse_document(&source).map_err(|error| {
-        error
-            .with_source_name(path.display().to_string())
-            .to_string()
-    })?;
-    attach_warning_source_names(&mut document, path);
-    Ok(document)
+    parse_recipe_file(&source, path)
+}
+
+fn parse_recipe_file(source: &str, path: &Path) -> Result<RecipeDocument, String> {
+    let source_name = path.display().to_string();
+    let includes = FileIncludes::new(path.parent().unwrap_or(Path::new(".")));
+    let options = ParseOptions {
+        source_name: Some(&source_name),
+        includes: Some(&includes),
+        ..ParseOptions::default()
+    };
+    parse_document_with(source, &options).map_err(|error| error.to_string())
 }
 
 fn load_referenced_material(tile_path: &Path, alias: &str) -> Result<MaterialRecipe, String> {
@@ -607,12 +612,7 @@ fn load_referenced_material(tile_path: &Path, alias: &str) -> Result<MaterialRec
             tile_path.display()
         )
     })?;
-    let mut document = parse_document(&source).map_err(|error| {
-        error
-            .with_source_name(recipe_path.display().to_string())
-            .to_string()
-    })?;
-    attach_warning_source_names(&mut document, &recipe_path);
+    let document = parse_recipe_file(&source, &recipe_path)?;
     print_document_warnings(&document);
     let RecipeDocument::Materials(document) = document else {
         return Err(format!(
@@ -641,29 +641,6 @@ fn load_referenced_material(tile_path: &Path, alias: &str) -> Result<MaterialRec
     }
 }
 
-fn attach_warning_source_names(document: &mut RecipeDocument, path: &Path) {
-    let source_name = path.display().to_string();
-    match document {
-        RecipeDocument::Tile(recipe) => {
-            for pattern in &mut recipe.patterns {
-                for warning in &mut pattern.warnings {
-                    warning.source_name = Some(source_name.clone());
-                }
-            }
-        }
-        RecipeDocument::Materials(document) => {
-            for material in &mut document.materials {
-                for pattern in &mut material.patterns {
-                    for warning in &mut pattern.warnings {
-                        warning.source_name = Some(source_name.clone());
-                    }
-                }
-            }
-        }
-        RecipeDocument::Sdfs(_) => {}
-    }
-}
-
 fn print_document_warnings(document: &RecipeDocument) {
     match document {
         RecipeDocument::Tile(recipe) => {
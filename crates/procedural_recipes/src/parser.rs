@@ -2,7 +2,8 @@ use crate::ast::*;
 use std::{
     collections::{BTreeMap, BTreeSet},
     error::Error,
-    fmt,
+    fmt, fs,
+    path::{Path, PathBuf},
 };
 
 /// Stable categories for machine-readable recipe diagnostics.
@@ -20,6 +21,7 @@ pub enum ParseErrorCode {
     InvalidValue,
     ConflictingFields,
     UnknownReference,
+    IncludeCycle,
 }
 
 impl ParseErrorCode {
@@ -33,6 +35,7 @@ impl ParseErrorCode {
             Self::InvalidValue => "PR0006",
             Self::ConflictingFields => "PR0007",
             Self::UnknownReference => "PR0008",
+            Self::IncludeCycle => "PR0009",
         }
     }
 }
@@ -92,6 +95,9 @@ impl ParseError {
     }
 
     fn attach_source(mut self, source: &str) -> Self {
+        if self.source_line.is_some() {
+            return self;
+        }
         if let Some(source_line) = source.lines().nth(self.line.saturating_sub(1)) {
             self.column = source_line
                 .chars()
@@ -204,44 +210,391 @@ pub fn parse_sdf_document(source: &str) -> Result<SdfDocument, ParseError> {
 }
 
 pub fn parse_document(source: &str) -> Result<RecipeDocument, ParseError> {
-    let mut document = parse_document_inner(source).map_err(|error| error.attach_source(source))?;
-    attach_warning_sources(&mut document, source);
-    Ok(document)
+    parse_document_with(source, &ParseOptions::default())
+}
+
+/// Resolves `Include "path"` blocks to the source of the included recipe.
+pub trait IncludeResolver {
+    /// Returns a stable name for the included file together with its source.
+    /// `from` is the name of the including file, if it has one. The name is
+    /// used in diagnostics and to detect include cycles.
+    fn resolve(&self, from: Option<&str>, path: &str) -> Result<(String, String), String>;
+}
+
+/// Resolves includes on disk relative to the including file, or to `root`
+/// for a source without a file name.
+#[derive(Clone, Debug)]
+pub struct FileIncludes {
+    pub root: PathBuf,
+}
+
+impl FileIncludes {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+}
+
+impl IncludeResolver for FileIncludes {
+    fn resolve(&self, from: Option<&str>, path: &str) -> Result<(String, String), String> {
+        let base = from
+            .and_then(|from| Path::new(from).parent())
+            .map_or_else(|| self.root.clone(), Path::to_path_buf);
+        let path = base.join(path);
+        let source =
+            fs::read_to_string(&path).map_err(|error| format!("{}: {error}", path.display()))?;
+        let name = path.canonicalize().unwrap_or(path);
+        Ok((name.display().to_string(), source))
+    }
 }
 
-fn attach_warning_sources(document: &mut RecipeDocument, source: &str) {
-    let attach = |patterns: &mut [PatternDefinition]| {
-        for pattern in patterns {
-            for warning in &mut pattern.warnings {
-                if let Some(source_line) = source.lines().nth(warning.line.saturating_sub(1)) {
-                    warning.column = source_line
-                        .chars()
-                        .take_while(|character| character.is_whitespace())
-                        .count()
-                        + 1;
-                    warning.source_line = Some(source_line.to_string());
+/// Context for parsing recipes that use `Include` and `Param`.
+#[derive(Clone, Copy, Default)]
+pub struct ParseOptions<'a> {
+    /// File name of the parsed source, used for diagnostics and to resolve
+    /// relative includes.
+    pub source_name: Option<&'a str>,
+    /// Overrides for the `Param` values declared by the parsed source, as
+    /// recipe source text keyed by parameter name.
+    pub params: Option<&'a BTreeMap<String, String>>,
+    /// Resolver for `Include` blocks. Without one, includes are an error.
+    pub includes: Option<&'a dyn IncludeResolver>,
+}
+
+/// Parses a recipe, expanding its includes and parameters.
+pub fn parse_document_with(
+    source: &str,
+    options: &ParseOptions,
+) -> Result<RecipeDocument, ParseError> {
+    let mut expander = Expander::new(options);
+    let result = expander
+        .expand_main(source)
+        .and_then(|roots| document_from_roots(&roots));
+    match result {
+        Ok(mut document) => {
+            expander.sources.attach_warnings(&mut document);
+            Ok(document)
+        }
+        Err(error) => Err(expander.sources.localize(error)),
+    }
+}
+
+/// Expands includes and parameters into a self-contained recipe source that
+/// `parse_document` accepts without any resolver.
+pub fn expand_recipe_source(source: &str, options: &ParseOptions) -> Result<String, ParseError> {
+    let mut expander = Expander::new(options);
+    let roots = expander
+        .expand_main(source)
+        .and_then(|roots| document_from_roots(&roots).map(|_| roots))
+        .map_err(|error| expander.sources.localize(error))?;
+    let mut expanded = String::new();
+    for (index, root) in roots.iter().enumerate() {
+        if index > 0 {
+            expanded.push('\n');
+        }
+        write_node(root, 0, &mut expanded);
+    }
+    Ok(expanded)
+}
+
+fn write_node(node: &Node, depth: usize, output: &mut String) {
+    let indent = "    ".repeat(depth);
+    output.push_str(&format!("{indent}{}\n", node.name));
+    for (key, (value, _)) in &node.fields {
+        output.push_str(&format!("{indent}    {key} = {value}\n"));
+    }
+    for child in &node.children {
+        write_node(child, depth + 1, output);
+    }
+}
+
+/// Every file taking part in one parse. Lines are numbered consecutively
+/// across files so nodes from different files never share a line number;
+/// diagnostics are mapped back to the file and its local line at the end.
+#[derive(Default)]
+struct SourceMap {
+    files: Vec<SourceFile>,
+    next_line: usize,
+}
+
+struct SourceFile {
+    name: Option<String>,
+    source: String,
+    offset: usize,
+    lines: usize,
+}
+
+impl SourceMap {
+    fn add(&mut self, name: Option<String>, source: &str) -> usize {
+        let offset = self.next_line;
+        let lines = source.lines().count().max(1);
+        self.next_line += lines;
+        self.files.push(SourceFile {
+            name,
+            source: source.to_string(),
+            offset,
+            lines,
+        });
+        offset
+    }
+
+    fn locate(&self, line: usize) -> Option<(&SourceFile, usize)> {
+        self.files
+            .iter()
+            .find(|file| line > file.offset && line <= file.offset + file.lines)
+            .map(|file| (file, line - file.offset))
+    }
+
+    fn localize(&self, mut error: ParseError) -> ParseError {
+        let Some((file, line)) = self
+            .locate(error.line)
+            .or_else(|| self.files.first().map(|file| (file, error.line)))
+        else {
+            return error;
+        };
+        error.line = line;
+        let mut error = error.attach_source(&file.source);
+        if error.source_name.is_none() {
+            error.source_name = file.name.clone();
+        }
+        error
+    }
+
+    fn attach_warnings(&self, document: &mut RecipeDocument) {
+        let attach = |patterns: &mut [PatternDefinition]| {
+            for pattern in patterns {
+                for warning in &mut pattern.warnings {
+                    let Some((file, line)) = self.locate(warning.line) else {
+                        continue;
+                    };
+                    warning.line = line;
+                    if let Some(source_line) = file.source.lines().nth(line - 1) {
+                        warning.column = source_line
+                            .chars()
+                            .take_while(|character| character.is_whitespace())
+                            .count()
+                            + 1;
+                        warning.source_line = Some(source_line.to_string());
+                    }
+                    if warning.source_name.is_none() {
+                        warning.source_name = file.name.clone();
+                    }
                 }
             }
+        };
+        match document {
+            RecipeDocument::Tile(recipe) => attach(&mut recipe.patterns),
+            RecipeDocument::Materials(document) => {
+                for material in &mut document.materials {
+                    attach(&mut material.patterns);
+                }
+            }
+            RecipeDocument::Sdfs(_) => {}
+        }
+    }
+}
+
+/// Expands `Include` and `Param` blocks into one node tree.
+struct Expander<'a> {
+    options: &'a ParseOptions<'a>,
+    sources: SourceMap,
+    stack: Vec<String>,
+}
+
+impl<'a> Expander<'a> {
+    fn new(options: &'a ParseOptions<'a>) -> Self {
+        Self {
+            options,
+            sources: SourceMap::default(),
+            stack: options
+                .source_name
+                .map(str::to_string)
+                .into_iter()
+                .collect(),
+        }
+    }
+
+    fn expand_main(&mut self, source: &str) -> Result<Vec<Node>, ParseError> {
+        let overrides = self
+            .options
+            .params
+            .into_iter()
+            .flatten()
+            .map(|(name, value)| (name.clone(), value.clone(), 1))
+            .collect();
+        let name = self.options.source_name.map(str::to_string);
+        self.expand_file(name, source, overrides)
+    }
+
+    fn expand_file(
+        &mut self,
+        name: Option<String>,
+        source: &str,
+        overrides: Vec<(String, String, usize)>,
+    ) -> Result<Vec<Node>, ParseError> {
+        let offset = self.sources.add(name.clone(), source);
+        let lines = tokenize_at(source, offset)?;
+        let mut cursor = 0;
+        let mut roots = parse_nodes(&lines, &mut cursor, 0)?;
+
+        let mut declared = BTreeMap::new();
+        take_params(&mut roots, &mut declared)?;
+        let mut values = declared.clone();
+        for (param, value, line) in overrides {
+            let key = param.to_ascii_lowercase();
+            if !declared.contains_key(&key) {
+                return Err(ParseError::reference(
+                    line,
+                    format!("unknown param '{param}'"),
+                ));
+            }
+            values.insert(key, value);
         }
+        substitute_params(&mut roots, &values)?;
+        self.expand_includes(&mut roots, name.as_deref())?;
+        Ok(roots)
+    }
+
+    fn expand_includes(
+        &mut self,
+        nodes: &mut Vec<Node>,
+        from: Option<&str>,
+    ) -> Result<(), ParseError> {
+        let mut index = 0;
+        while index < nodes.len() {
+            let Some(path) = include_path(&nodes[index])? else {
+                self.expand_includes(&mut nodes[index].children, from)?;
+                index += 1;
+                continue;
+            };
+            let node = nodes.remove(index);
+            if let Some(child) = node.children.first() {
+                return Err(ParseError::unknown(
+                    child.line,
+                    "Include takes param overrides, not blocks",
+                ));
+            }
+            let resolver = self.options.includes.ok_or_else(|| {
+                ParseError::reference(
+                    node.line,
+                    format!("cannot include '{path}' without an include resolver"),
+                )
+            })?;
+            let (name, source) = resolver.resolve(from, &path).map_err(|error| {
+                ParseError::reference(node.line, format!("cannot include '{path}': {error}"))
+            })?;
+            if let Some(start) = self.stack.iter().position(|entry| *entry == name) {
+                let mut chain = self.stack[start..].to_vec();
+                chain.push(name);
+                return Err(ParseError::with_code(
+                    ParseErrorCode::IncludeCycle,
+                    node.line,
+                    format!("cyclic include: {}", chain.join(" -> ")),
+                ));
+            }
+            let overrides = node
+                .fields
+                .into_iter()
+                .map(|(param, (value, line))| (param, value, line))
+                .collect();
+            self.stack.push(name.clone());
+            let included = self.expand_file(Some(name), &source, overrides)?;
+            self.stack.pop();
+            let count = included.len();
+            nodes.splice(index..index, included);
+            index += count;
+        }
+        Ok(())
+    }
+}
+
+fn include_path(node: &Node) -> Result<Option<String>, ParseError> {
+    let Some((keyword, path)) = node.name.split_once(char::is_whitespace) else {
+        if node.name.eq_ignore_ascii_case("include") {
+            return Err(ParseError::missing(
+                node.line,
+                "Include requires a quoted path, for example Include \"bricks.recipe\"",
+            ));
+        }
+        return Ok(None);
     };
-    match document {
-        RecipeDocument::Tile(recipe) => attach(&mut recipe.patterns),
-        RecipeDocument::Materials(document) => {
-            for material in &mut document.materials {
-                attach(&mut material.patterns);
+    if !keyword.eq_ignore_ascii_case("include") {
+        return Ok(None);
+    }
+    parse_string(path, node.line).map(Some)
+}
+
+/// Removes every `Param <Name>` block from the tree, recording its default
+/// source text by lowercase name.
+fn take_params(
+    nodes: &mut Vec<Node>,
+    declared: &mut BTreeMap<String, String>,
+) -> Result<(), ParseError> {
+    let mut index = 0;
+    while index < nodes.len() {
+        let (kind, name) = declaration(&nodes[index].name);
+        if kind != "param" {
+            take_params(&mut nodes[index].children, declared)?;
+            index += 1;
+            continue;
+        }
+        let node = &nodes[index];
+        let name = required_declaration_name(node, name)?;
+        reject_unknown_fields(node, &["default"])?;
+        reject_children(node)?;
+        let (default, _) = field(node, "default").ok_or_else(|| {
+            ParseError::missing(node.line, format!("Param {name} requires a default"))
+        })?;
+        if declared
+            .insert(name.to_ascii_lowercase(), default.to_string())
+            .is_some()
+        {
+            return Err(ParseError::duplicate(
+                node.line,
+                format!("duplicate param '{name}'"),
+            ));
+        }
+        nodes.remove(index);
+    }
+    Ok(())
+}
+
+/// Replaces `$Name` references in field values with parameter source text.
+fn substitute_params(
+    nodes: &mut [Node],
+    values: &BTreeMap<String, String>,
+) -> Result<(), ParseError> {
+    for node in nodes {
+        for (value, line) in node.fields.values_mut() {
+            if !value.contains('$') {
+                continue;
+            }
+            let mut result = String::with_capacity(value.len());
+            let mut rest = value.as_str();
+            while let Some(start) = rest.find('$') {
+                result.push_str(&rest[..start]);
+                let after = &rest[start + 1..];
+                let end = after
+                    .find(|character: char| !character.is_ascii_alphanumeric() && character != '_')
+                    .unwrap_or(after.len());
+                let name = &after[..end];
+                let replacement = values.get(&name.to_ascii_lowercase()).ok_or_else(|| {
+                    ParseError::reference(*line, format!("unknown param '${name}'"))
+                })?;
+                result.push_str(replacement);
+                rest = &after[end..];
             }
+            result.push_str(rest);
+            *value = result;
         }
-        RecipeDocument::Sdfs(_) => {}
+        substitute_params(&mut node.children, values)?;
     }
+    Ok(())
 }
 
-fn parse_document_inner(source: &str) -> Result<RecipeDocument, ParseError> {
-    let lines = tokenize(source)?;
-    if lines.is_empty() {
+fn document_from_roots(roots: &[Node]) -> Result<RecipeDocument, ParseError> {
+    if roots.is_empty() {
         return Err(ParseError::document(1, "recipe is empty"));
     }
-    let mut cursor = 0;
-    let roots = parse_nodes(&lines, &mut cursor, 0)?;
     if roots.len() == 1 && roots[0].name.eq_ignore_ascii_case("Tile") {
         return recipe_from_node(&roots[0]).map(RecipeDocument::Tile);
     }
@@ -250,7 +603,7 @@ fn parse_document_inner(source: &str) -> Result<RecipeDocument, ParseError> {
     if root_kind == "sdf" {
         let mut ids = BTreeSet::new();
         let mut recipes = Vec::with_capacity(roots.len());
-        for root in &roots {
+        for root in roots {
             let (kind, declared_name) = declaration(&root.name);
             if kind != "sdf" {
                 return Err(ParseError::document(
@@ -272,7 +625,7 @@ fn parse_document_inner(source: &str) -> Result<RecipeDocument, ParseError> {
 
     let mut ids = BTreeSet::new();
     let mut materials = Vec::with_capacity(roots.len());
-    for root in &roots {
+    for root in roots {
         let (kind, declared_name) = declaration(&root.name);
         if kind != "material" {
             return Err(ParseError::document(
@@ -493,10 +846,10 @@ fn parse_sdf_reference(value: &str, line: usize) -> Result<String, ParseError> {
     Ok(value.to_string())
 }
 
-fn tokenize(source: &str) -> Result<Vec<SourceLine>, ParseError> {
+fn tokenize_at(source: &str, offset: usize) -> Result<Vec<SourceLine>, ParseError> {
     let mut result = Vec::new();
     for (index, raw) in source.lines().enumerate() {
-        let line_number = index + 1;
+        let line_number = offset + index + 1;
         if raw.contains('\t') {
             return Err(ParseError::new(
                 line_number,
@@ -1202,6 +1555,7 @@ fn parse_noise(node: &Node, name: Option<&str>) -> Result<NoiseField, ParseError
             "octaves",
             "persistence",
             "seed",
+            "drift",
         ],
     )?;
     reject_children(node)?;
@@ -1233,9 +1587,28 @@ fn parse_noise(node: &Node, name: Option<&str>) -> Result<NoiseField, ParseError
         octaves: optional_u32(node, "octaves", 1)?.clamp(1, 8),
         persistence: optional_f32(node, "persistence", 0.5)?.clamp(0.0, 1.0),
         seed: optional_u64(node, "seed", 0)?,
+        drift: parse_noise_drift(node)?,
     })
 }
 
+/// Noise is periodic over the recipe domain, so scrolling by whole periods
+/// per loop keeps the last animation frame continuous with the first.
+fn parse_noise_drift(node: &Node) -> Result<[f32; 2], ParseError> {
+    let drift = optional_f2(node, "drift", [0.0, 0.0])?;
+    if drift
+        .iter()
+        .any(|component| !component.is_finite() || component.fract() != 0.0)
+    {
+        return Err(ParseError::invalid(
+            field(node, "drift")
+                .map(|(_, line)| line)
+                .unwrap_or(node.line),
+            "drift values must be whole numbers of noise periods per loop",
+        ));
+    }
+    Ok(drift)
+}
+
 fn parse_height(node: &Node, name: Option<&str>) -> Result<HeightField, ParseError> {
     reject_unknown_fields(node, &["source"])?;
     let source = required_scalar(node, "source")?;
@@ -1432,9 +1805,7 @@ fn parse_pattern(node: &Node, name: Option<&str>) -> Result<PatternDefinition, P
         .map(|(value, line)| {
             Ok::<_, ParseError>(Warp {
                 source: parse_scalar_source(value, line)?,
-                amount: optional_f32(generator, "warp_amount", 0.05)?
-                    .abs()
-                    .clamp(0.0, 1.0),
+                amount: optional_scalar(generator, "warp_amount", 0.05)?,
             })
         })
         .transpose()?;
@@ -1889,6 +2260,12 @@ fn validate_names_and_references(
                 &patterns,
                 field_line(generator_node, "warp"),
             )?;
+            validate_scalar_source(
+                &warp.amount,
+                &fields,
+                &patterns,
+                field_line(generator_node, "warp_amount"),
+            )?;
         }
         if let Some(perturb) = &pattern.perturb {
             validate_scalar_source(
@@ -2177,6 +2554,7 @@ fn scalar_uses_input_height(source: &ScalarSource) -> bool {
         }
         ScalarSource::Constant(_)
         | ScalarSource::Coordinate(_)
+        | ScalarSource::Time
         | ScalarSource::Field(_)
         | ScalarSource::Pattern { .. }
         | ScalarSource::Geometry { .. }
@@ -2238,6 +2616,7 @@ fn validate_scalar_source(
         }
         ScalarSource::Constant(_)
         | ScalarSource::Coordinate(_)
+        | ScalarSource::Time
         | ScalarSource::InputHeight
         | ScalarSource::Wave { .. } => {}
     }
@@ -2666,6 +3045,9 @@ fn scalar_identifier(value: &str, line: usize) -> Result<ScalarSource, ParseErro
     if let Some(coordinate) = coordinate {
         return Ok(ScalarSource::Coordinate(coordinate));
     }
+    if value.eq_ignore_ascii_case("Time") {
+        return Ok(ScalarSource::Time);
+    }
     if value.eq_ignore_ascii_case("Input.height") {
         return Ok(ScalarSource::InputHeight);
     }
@@ -3332,6 +3714,148 @@ fn reject_children(node: &Node) -> Result<(), ParseError> {
 mod tests {
     use super::*;
 
+    struct MemoryIncludes(BTreeMap<&'static str, &'static str>);
+
+    impl IncludeResolver for MemoryIncludes {
+        fn resolve(&self, _from: Option<&str>, path: &str) -> Result<(String, String), String> {
+            self.0
+                .get(path)
+                .map(|source| (path.to_string(), source.to_string()))
+                .ok_or_else(|| "not found".to_string())
+        }
+    }
+
+    const BRICK_TEMPLATE: &str = r#"Tile
+    name = $Name
+
+    Param Name
+        default = "Bricks"
+
+    Param Moss
+        default = 0.0
+
+    Noise Grain
+        scale = F2(6.0, 6.0)
+
+    Pattern Wall
+        Bricks
+            columns = 4
+            rows = 6
+
+    Height Surface
+        source = Wall.height * (1.0 - $Moss) + Grain * $Moss
+
+    Output
+        height = Surface
+"#;
+
+    fn brick_includes() -> MemoryIncludes {
+        MemoryIncludes(BTreeMap::from([
+            ("bricks.recipe", BRICK_TEMPLATE),
+            (
+                "stone.recipe",
+                "Noise Stone\n    scale = F2($Scale, $Scale)\n\nParam Scale\n    default = 3.0\n",
+            ),
+            ("loop-a.recipe", "Include \"loop-b.recipe\"\n"),
+            ("loop-b.recipe", "Include \"loop-a.recipe\"\n"),
+            (
+                "broken.recipe",
+                "Tile\n    Output\n        height = Missing\n",
+            ),
+        ]))
+    }
+
+    #[test]
+    fn include_params_build_template_variants() {
+        let includes = brick_includes();
+        let options = ParseOptions {
+            includes: Some(&includes),
+            ..ParseOptions::default()
+        };
+        let plain = parse_recipe(BRICK_TEMPLATE).unwrap();
+        assert_eq!(plain.name, "Bricks");
+
+        let RecipeDocument::Tile(mossy) = parse_document_with(
+            "Include \"bricks.recipe\"\n    name = \"Mossy Bricks\"\n    moss = 0.4\n",
+            &options,
+        )
+        .unwrap() else {
+            panic!("expected a tile");
+        };
+        assert_eq!(mossy.name, "Mossy Bricks");
+        assert_eq!(mossy.patterns.len(), 1);
+        assert_ne!(mossy.fields, plain.fields);
+
+        // Includes also splice fragments into a block.
+        let RecipeDocument::Tile(fragment) = parse_document_with(
+            "Tile\n    Include \"stone.recipe\"\n        scale = 5.0\n    Output\n        height = Stone\n",
+            &options,
+        )
+        .unwrap() else {
+            panic!("expected a tile");
+        };
+        let FieldDefinition::Noise(stone) = &fragment.fields[0] else {
+            panic!("expected the included noise");
+        };
+        assert_eq!(stone.scale, [5.0, 5.0]);
+    }
+
+    #[test]
+    fn metadata_params_override_defaults_and_unknown_params_fail() {
+        let params = BTreeMap::from([("moss".to_string(), "0.5".to_string())]);
+        let options = ParseOptions {
+            params: Some(&params),
+            ..ParseOptions::default()
+        };
+        let expanded = expand_recipe_source(BRICK_TEMPLATE, &options).unwrap();
+        assert!(expanded.contains("Grain * 0.5"));
+        assert!(!expanded.contains("Param"));
+        assert!(parse_document(&expanded).is_ok());
+
+        let params = BTreeMap::from([("rust".to_string(), "0.5".to_string())]);
+        let options = ParseOptions {
+            params: Some(&params),
+            ..ParseOptions::default()
+        };
+        let error = parse_document_with(BRICK_TEMPLATE, &options).unwrap_err();
+        assert_eq!(error.code, ParseErrorCode::UnknownReference);
+        assert!(error.message.contains("unknown param 'rust'"));
+
+        let error = parse_document("Tile\n    Output\n        height = $Depth\n").unwrap_err();
+        assert_eq!(error.code, ParseErrorCode::UnknownReference);
+        assert_eq!(error.line, 3);
+    }
+
+    #[test]
+    fn include_diagnostics_name_the_included_file() {
+        let includes = brick_includes();
+        let options = ParseOptions {
+            source_name: Some("main.recipe"),
+            includes: Some(&includes),
+            ..ParseOptions::default()
+        };
+        let error = parse_document_with("Include \"loop-a.recipe\"\n", &options).unwrap_err();
+        assert_eq!(error.code, ParseErrorCode::IncludeCycle);
+        assert_eq!(error.stable_code(), "PR0009");
+        assert_eq!(
+            error.message,
+            "cyclic include: loop-a.recipe -> loop-b.recipe -> loop-a.recipe"
+        );
+        assert_eq!(error.source_name.as_deref(), Some("loop-b.recipe"));
+
+        let error = parse_document_with("\n\nInclude \"broken.recipe\"\n", &options).unwrap_err();
+        assert_eq!(error.code, ParseErrorCode::UnknownReference);
+        assert_eq!(error.source_name.as_deref(), Some("broken.recipe"));
+        assert_eq!(error.line, 3);
+        assert_eq!(
+            error.source_line.as_deref(),
+            Some("        height = Missing")
+        );
+
+        let error = parse_document("Include \"bricks.recipe\"\n").unwrap_err();
+        assert!(error.message.contains("without an include resolver"));
+    }
+
     #[test]
     fn parses_height_first_recipe_and_typed_references() {
         let recipe = parse_recipe(include_str!("../examples/bricks.recipe")).unwrap();
@@ -3551,6 +4075,43 @@ Tile
         );
     }
 
+    #[test]
+    fn parses_time_and_whole_period_noise_drift() {
+        let recipe = parse_recipe(
+            r#"
+Tile
+    Noise Flow
+        drift = F2(1, -2)
+
+    Output
+        height = Flow * Time
+"#,
+        )
+        .unwrap();
+        let FieldDefinition::Noise(flow) = &recipe.fields[0] else {
+            panic!("expected a noise field");
+        };
+        assert_eq!(flow.drift, [1.0, -2.0]);
+        assert!(matches!(
+            &recipe.output.height,
+            ScalarSource::Binary { right, .. } if **right == ScalarSource::Time
+        ));
+
+        let error = parse_recipe(
+            r#"
+Tile
+    Noise Flow
+        drift = F2(0.5, 0.0)
+
+    Output
+        height = Flow
+"#,
+        )
+        .unwrap_err();
+        assert_eq!(error.line, 4);
+        assert!(error.message.contains("whole numbers"));
+    }
+
     #[test]
     fn output_can_select_a_pattern_local_context() {
         let recipe = parse_recipe(
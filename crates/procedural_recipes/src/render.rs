@@ -404,12 +404,38 @@ impl RecipeRenderer {
         }
 
         let frame_count = recipe.animation.frames.max(1);
-        let mut frames = Vec::with_capacity(frame_count as usize);
+        let mut heights = Vec::with_capacity(frame_count as usize);
         for frame_index in 0..frame_count {
             let time = frame_index as f32 / frame_count as f32;
-            frames.push(self.render_frame(recipe, width, height, time, options.seed_offset)?);
+            heights.push((
+                time,
+                self.frame_height(recipe, width, height, time, options.seed_offset)?,
+            ));
         }
 
+        // An automatic color range is shared by every frame of an animation so
+        // a pulsing height reads as a change in color instead of being
+        // normalized back to the same ramp on each frame.
+        let colorize = recipe.colorize.as_ref().map(|colorize| {
+            let mut colorize = colorize.clone();
+            if colorize.range == ColorRange::Auto && heights.len() > 1 {
+                let (min, max) = heights
+                    .iter()
+                    .flat_map(|(_, values)| values.iter().copied())
+                    .fold((f32::MAX, f32::MIN), |(min, max), value| {
+                        (min.min(value), max.max(value))
+                    });
+                colorize.range = ColorRange::Fixed([min, max]);
+            }
+            colorize
+        });
+        let frames = heights
+            .into_iter()
+            .map(|(time, scalar_height)| {
+                self.finish_frame(colorize.as_ref(), scalar_height, width, time)
+            })
+            .collect();
+
         Ok(RenderedRecipe {
             name: recipe.name.clone(),
             width,
@@ -834,14 +860,14 @@ impl RecipeRenderer {
         (rgba, palette_indices)
     }
 
-    fn render_frame(
+    fn frame_height(
         &self,
         recipe: &Recipe,
         width: u32,
         height: u32,
         time: f32,
         seed_offset: u64,
-    ) -> Result<RenderedFrame, RenderError> {
+    ) -> Result<Vec<f32>, RenderError> {
         let pixel_count = (width * height) as usize;
         let mut scalar_height = vec![0.0_f32; pixel_count];
         let evaluator = Evaluator {
@@ -871,24 +897,33 @@ impl RecipeRenderer {
                     .clamp(0.0, 1.0);
             }
         }
+        Ok(scalar_height)
+    }
 
-        let (rgba, palette_indices) = recipe.colorize.as_ref().map_or_else(
+    fn finish_frame(
+        &self,
+        colorize: Option<&Colorize>,
+        scalar_height: Vec<f32>,
+        width: u32,
+        time: f32,
+    ) -> RenderedFrame {
+        let (rgba, palette_indices) = colorize.map_or_else(
             || grayscale_values(&scalar_height),
             |colorize| self.colorize_values(colorize, &scalar_height, width),
         );
-        let coverage = vec![255_u8; pixel_count];
+        let coverage = vec![255_u8; scalar_height.len()];
         let height_values = scalar_height
             .into_iter()
             .map(|value| (value * 255.0).round() as u8)
             .collect();
 
-        Ok(RenderedFrame {
+        RenderedFrame {
             rgba,
             palette_indices,
             coverage,
             height: height_values,
             time,
-        })
+        }
     }
 }
 
@@ -1005,6 +1040,7 @@ impl Evaluator<'_> {
                         .rem_euclid(1.0)
                 }
             }),
+            ScalarSource::Time => Ok(context.time),
             ScalarSource::InputHeight => Ok(context.input_height),
             ScalarSource::Field(name) => self.field(name, context, stack),
             ScalarSource::Pattern { name, channel } => {
@@ -1181,6 +1217,10 @@ impl Evaluator<'_> {
                     let seed = mix64(
                         self.seed ^ noise.seed ^ mix64(domain_id) ^ mix64(key_id.rotate_left(11)),
                     );
+                    let uv = [
+                        uv[0] + noise.drift[0] * context.time,
+                        uv[1] + noise.drift[1] * context.time,
+                    ];
                     Ok(fractal_noise(
                         uv,
                         noise.kind,
@@ -1496,8 +1536,12 @@ impl Evaluator<'_> {
                 wrap_coordinate(context.uv[1] + 0.317, self.recipe.wrap),
             ]);
             let second = self.scalar(&warp.source, shifted, stack)?;
-            uv[0] += (first - 0.5) * 2.0 * warp.amount;
-            uv[1] += (second - 0.5) * 2.0 * warp.amount;
+            let amount = self
+                .scalar(&warp.amount, context, stack)?
+                .abs()
+                .clamp(0.0, 1.0);
+            uv[0] += (first - 0.5) * 2.0 * amount;
+            uv[1] += (second - 0.5) * 2.0 * amount;
         }
         Ok((
             [
@@ -2160,6 +2204,88 @@ Tile
         assert_ne!(rendered.frames[0].height, rendered.frames[1].height);
     }
 
+    #[test]
+    fn time_drives_expressions_and_warp_amounts() {
+        let animated = parse_recipe(
+            r#"
+Tile
+    size = I2(8, 8)
+
+    Animation
+        frames = 4
+
+    Noise Ripple
+        scale = F2(2.0, 2.0)
+
+    Pattern Tiles
+        Bricks
+            columns = 2
+            rows = 2
+            warp = Ripple
+            warp_amount = 0.1 * Sin(Time)
+
+    Height Surface
+        source = Tiles.height * 0.5 + Fract(U + Time) * 0.5
+
+    Output
+        height = Surface
+"#,
+        )
+        .unwrap();
+        let rendered = RecipeRenderer::grayscale()
+            .render(&animated, &RenderOptions::default())
+            .unwrap();
+        assert_eq!(rendered.frames.len(), 4);
+        assert_ne!(rendered.frames[0].height, rendered.frames[1].height);
+        assert_ne!(rendered.frames[1].height, rendered.frames[3].height);
+
+        // `Sin` takes cycles, so both expressions are periodic over `0..1` and
+        // the loop end matches its start.
+        let renderer = RecipeRenderer::grayscale();
+        let start = renderer.frame_height(&animated, 8, 8, 0.0, 0).unwrap();
+        let end = renderer.frame_height(&animated, 8, 8, 1.0, 0).unwrap();
+        for (start, end) in start.iter().zip(&end) {
+            assert!((start - end).abs() < 1.0 / 255.0);
+        }
+    }
+
+    #[test]
+    fn noise_drift_scrolls_one_period_per_loop() {
+        let animated = parse_recipe(
+            r#"
+Tile
+    size = I2(16, 16)
+
+    Animation
+        frames = 4
+
+    Noise Water
+        type = Gradient
+        scale = F2(4.0, 4.0)
+        drift = F2(1, 0)
+
+    Output
+        height = Water
+"#,
+        )
+        .unwrap();
+        let rendered = RecipeRenderer::grayscale()
+            .render(&animated, &RenderOptions::default())
+            .unwrap();
+        let first = &rendered.frames[0].height;
+        // Every frame is the first one scrolled by a quarter of the tile, so
+        // the step from the last frame back to the first matches all others.
+        for (index, frame) in rendered.frames.iter().enumerate() {
+            for y in 0..16 {
+                for x in 0..16 {
+                    let scrolled = first[y * 16 + (x + index * 4) % 16];
+                    assert!(frame.height[y * 16 + x].abs_diff(scrolled) <= 1);
+                }
+            }
+        }
+        assert_ne!(rendered.frames[0].height, rendered.frames[1].height);
+    }
+
     #[test]
     fn nested_scalar_functions_drive_height() {
         let expression_recipe = parse_recipe(
@@ -3,10 +3,13 @@ use std::{env, fs, path::Path};
 use toml::{Table, Value};
 
 use crate::{
-    RulesetAttributeMap, RulesetCatalog, RulesetRollSummary, RulesetValidationReport,
-    RulesetValidationSeverity, latest_official_ruleset, parse_ruleset_table, ruleset_catalog,
-    ruleset_section_ids_from_source, ruleset_show_path_from_source, ruleset_table_at_path,
-    ruleset_xp_for_level, summarize_class, summarize_roll_path, summarize_spell_roll,
+    CombatLoadout, DuelOptions, DuelSideReport, LootSample, RulesetAttributeMap, RulesetCatalog,
+    RulesetDiff, RulesetRollSummary, RulesetValidationReport, RulesetValidationSeverity,
+    RulesetVersionInfo, SUPPORTED_RULESET_SCHEMA_VERSIONS, diff_rulesets, latest_official_ruleset,
+    migrate_ruleset_override, official_ruleset, parse_ruleset_table, ruleset_catalog,
+    ruleset_override_schema_version, ruleset_section_ids_from_source,
+    ruleset_show_path_from_source, ruleset_table_at_path, ruleset_xp_for_level, sample_loot_table,
+    simulate_duels, summarize_class, summarize_roll_path, summarize_spell_roll,
     summarize_weapon_damage, validate_ruleset,
 };
 
@@ -34,6 +37,10 @@ pub fn run(args: &[String]) -> Result<(), String> {
         "class" => run_class(&rules_src, tail),
         "item" => run_item(&rules_src, tail),
         "recipe" => run_recipe(&rules_src, tail),
+        "simulate" => run_simulate(&rules_src, tail),
+        "loot" => run_loot(&rules_src, tail),
+        "diff" => run_diff(tail),
+        "migrate" => run_migrate(tail),
         "help" | "--help" | "-h" => {
             println!("{}", usage());
             Ok(())
@@ -54,7 +61,15 @@ fn usage() -> &'static str {
        eldiron-ruleset [--rules rules.toml] xp <level>\n\
        eldiron-ruleset [--rules rules.toml] weapon <weapon_id> [ATTR=VALUE ...]\n\
        eldiron-ruleset [--rules rules.toml] spell <spell_id> [ATTR=VALUE ...]\n\
-       eldiron-ruleset [--rules rules.toml] roll <ruleset.path.to.roll> [ATTR=VALUE ...]"
+       eldiron-ruleset [--rules rules.toml] roll <ruleset.path.to.roll> [ATTR=VALUE ...]\n\
+       eldiron-ruleset [--rules rules.toml] simulate <loadout> <loadout> [--duels N] [--seed N]\n\
+       eldiron-ruleset [--rules rules.toml] loot <table_id> [--level N] [--samples N] [--seed N]\n\
+       eldiron-ruleset diff <ruleset> <ruleset> [--override rules.toml]\n\
+       eldiron-ruleset migrate <rules.toml> [--from SCHEMA] [--to SCHEMA] [--write]\n\
+     \n\
+     A loadout is a comma-separated list such as Warrior,race=Orc,level=3,weapon=hand_axe,STR=14.\n\
+     Without weapon/armor/clothing entries the class starting loadout is equipped.\n\
+     diff accepts ruleset files or bundled rulesets as official or <id>@<version>."
 }
 
 fn rules_source_from_args(args: &[String]) -> Result<(String, String, &[String]), String> {
@@ -98,6 +113,8 @@ fn format_catalog_summary(source: &str, catalog: &RulesetCatalog) -> String {
          skills: {} ({})\n\
          resources: {} ({})\n\
          recipes: {} ({})\n\
+         quests: {} ({})\n\
+         shops: {} ({})\n\
          actions: {} ({})\n\
          conditions: {} ({})\n\
          invocation schemes: {} ({})\n\
@@ -124,6 +141,10 @@ fn format_catalog_summary(source: &str, catalog: &RulesetCatalog) -> String {
         format_list(&catalog.resources),
         catalog.recipes.len(),
         format_list(&catalog.recipes),
+        catalog.quests.len(),
+        format_list(&catalog.quests),
+        catalog.shops.len(),
+        format_list(&catalog.shops),
         catalog.actions.len(),
         format_list(&catalog.actions),
         catalog.conditions.len(),
@@ -515,6 +536,372 @@ fn run_recipe(src: &str, args: &[String]) -> Result<(), String> {
     Ok(())
 }
 
+fn run_simulate(src: &str, args: &[String]) -> Result<(), String> {
+    let mut loadouts = Vec::new();
+    let mut options = DuelOptions::default();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--duels" | "--seed" => {
+                let Some(value) = args.next() else {
+                    return Err(format!("{} requires a number.", arg));
+                };
+                if arg == "--duels" {
+                    options.duels =
+                        value
+                            .parse()
+                            .ok()
+                            .filter(|duels| *duels > 0)
+                            .ok_or_else(|| {
+                                format!("Duel count '{}' is not a positive integer.", value)
+                            })?;
+                } else {
+                    options.seed = value
+                        .parse()
+                        .map_err(|_| format!("Seed '{}' is not a non-negative integer.", value))?;
+                }
+            }
+            _ => loadouts.push(arg.as_str()),
+        }
+    }
+    let [first, second] = loadouts[..] else {
+        return Err(usage().into());
+    };
+    let rules = rules_table(src)?;
+    let first = CombatLoadout::parse(&rules, first)?;
+    let second = CombatLoadout::parse(&rules, second)?;
+    let report = simulate_duels(&rules, &first, &second, &options)?;
+
+    let mut out = vec![
+        format!("duels: {}", report.duels),
+        format!("seed: {}", options.seed),
+        format!(
+            "draws: {} ({})",
+            report.draws,
+            format_percent(report.draws, report.duels)
+        ),
+    ];
+    for side in &report.sides {
+        out.push(String::new());
+        out.extend(format_duel_side(side, report.duels));
+    }
+    println!("{}", out.join("\n"));
+    Ok(())
+}
+
+fn run_loot(src: &str, args: &[String]) -> Result<(), String> {
+    let mut table_id = None;
+    let (mut level, mut samples, mut seed) = (1u32, 1000u32, 0u64);
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--level" | "--samples" | "--seed" => {
+                let Some(value) = args.next() else {
+                    return Err(format!("{} requires a number.", arg));
+                };
+                let invalid = || format!("{} value '{}' is not a valid number.", arg, value);
+                match arg.as_str() {
+                    "--level" => level = value.parse().map_err(|_| invalid())?,
+                    "--samples" => {
+                        samples = value
+                            .parse()
+                            .ok()
+                            .filter(|samples| *samples > 0)
+                            .ok_or_else(invalid)?
+                    }
+                    _ => seed = value.parse().map_err(|_| invalid())?,
+                }
+            }
+            _ if table_id.is_none() => table_id = Some(arg.as_str()),
+            _ => return Err(usage().into()),
+        }
+    }
+    let Some(table_id) = table_id else {
+        return Err(usage().into());
+    };
+    let rules = rules_table(src)?;
+    if ruleset_table_at_path(&rules, &["loot", "tables", table_id]).is_none() {
+        return Err(format!("Loot table '{}' was not found.", table_id));
+    }
+    let sample = sample_loot_table(&rules, table_id, level, samples, seed)?;
+    println!(
+        "{}",
+        format_loot_sample(table_id, level, seed, &sample).join("\n")
+    );
+    Ok(())
+}
+
+fn format_loot_sample(table_id: &str, level: u32, seed: u64, sample: &LootSample) -> Vec<String> {
+    let mut out = vec![
+        format!("table: {}", table_id),
+        format!("level: {}", level),
+        format!("samples: {}", sample.samples),
+        format!("seed: {}", seed),
+        format!(
+            "empty: {} ({})",
+            sample.empty_samples,
+            format_percent(sample.empty_samples, sample.samples)
+        ),
+        "drops:".into(),
+    ];
+    let mut items: Vec<_> = sample.items.iter().collect();
+    items.sort_by_key(|(_, stats)| std::cmp::Reverse(stats.samples_with_drop));
+    let mut lines = Vec::new();
+    for (item, stats) in items {
+        lines.push(format!(
+            "  {}: {} of samples, quantity min {}, mean {:.2}, max {}",
+            item,
+            format_percent(stats.samples_with_drop, sample.samples),
+            stats.min_quantity,
+            stats.total_quantity as f64 / stats.samples_with_drop.max(1) as f64,
+            stats.max_quantity,
+        ));
+        let rolls: u32 = stats.rarities.values().sum();
+        if rolls > 0 {
+            let rarities: Vec<String> = stats
+                .rarities
+                .iter()
+                .map(|(rarity, count)| format!("{} {}", rarity, format_percent(*count, rolls)))
+                .collect();
+            lines.push(format!("    rarity: {}", rarities.join(", ")));
+        }
+    }
+    out.extend_or_dash(lines);
+    out
+}
+
+fn diff_source(spec: &str) -> Result<Table, String> {
+    let bundled = if spec == "official" {
+        Some(latest_official_ruleset())
+    } else {
+        spec.split_once('@')
+            .and_then(|(id, version)| official_ruleset(id, version))
+    };
+    match bundled {
+        Some(src) => rules_table(src),
+        None => {
+            let src = fs::read_to_string(spec)
+                .map_err(|err| format!("Could not read ruleset '{}': {}", spec, err))?;
+            rules_table(&src)
+        }
+    }
+}
+
+fn run_diff(args: &[String]) -> Result<(), String> {
+    let mut sources = Vec::new();
+    let mut overrides = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "--override" {
+            let Some(path) = args.next() else {
+                return Err("--override requires a path.".into());
+            };
+            overrides = Some(path.as_str());
+        } else {
+            sources.push(arg.as_str());
+        }
+    }
+    let [from, to] = sources[..] else {
+        return Err(usage().into());
+    };
+    let diff = diff_rulesets(&diff_source(from)?, &diff_source(to)?);
+
+    let mut out = vec![
+        format!("from: {}", format_diff_version(from, &diff.from)),
+        format!("to: {}", format_diff_version(to, &diff.to)),
+    ];
+    out.extend(format_diff_changes(&diff));
+    out.push(String::new());
+    out.push(format!(
+        "changes: {}, breaking: {}",
+        diff.changes.len(),
+        diff.breaking_changes().count()
+    ));
+    if let Some(warning) = diff.version_warning() {
+        out.push(format!("warning: {}", warning));
+    }
+    if let Some(path) = overrides {
+        let src = fs::read_to_string(path)
+            .map_err(|err| format!("Could not read override '{}': {}", path, err))?;
+        let conflicts = diff.override_conflicts(&rules_table(&src)?);
+        out.push(String::new());
+        out.push(format!("override {}:", path));
+        if conflicts.is_empty() {
+            out.push("  still applies".into());
+        }
+        for conflict in conflicts {
+            out.push(format!("  {}", conflict.message));
+        }
+    }
+    println!("{}", out.join("\n"));
+    Ok(())
+}
+
+fn format_diff_version(source: &str, info: &RulesetVersionInfo) -> String {
+    format!(
+        "{} ({} {}, schema {})",
+        source,
+        info.id.as_deref().unwrap_or("-"),
+        info.version.as_deref().unwrap_or("-"),
+        info.schema_version.as_deref().unwrap_or("-"),
+    )
+}
+
+fn format_diff_changes(diff: &RulesetDiff) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut section = "";
+    for change in &diff.changes {
+        if change.section != section {
+            section = &change.section;
+            out.push(String::new());
+            out.push(format!("{}:", section));
+        }
+        let breaking = if change.is_breaking() {
+            " [breaking]"
+        } else {
+            ""
+        };
+        out.push(format!(
+            "  {} {}{}",
+            change.kind.marker(),
+            change.id,
+            breaking
+        ));
+        for field in &change.fields {
+            out.push(format!(
+                "      {} {}{}",
+                field.kind.marker(),
+                field.path,
+                if field.breaking { " [breaking]" } else { "" }
+            ));
+        }
+        if !change.referenced_by.is_empty() {
+            out.push(format!(
+                "      referenced by: {}",
+                format_list(&change.referenced_by)
+            ));
+        }
+    }
+    out
+}
+
+fn run_migrate(args: &[String]) -> Result<(), String> {
+    let mut path = None;
+    let mut from = None;
+    let mut to = None;
+    let mut write = false;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--from" | "--to" => {
+                let Some(value) = args.next() else {
+                    return Err(format!("{} requires a schema version.", arg));
+                };
+                if arg == "--from" {
+                    from = Some(value.clone());
+                } else {
+                    to = Some(value.clone());
+                }
+            }
+            "--write" => write = true,
+            _ if path.is_none() => path = Some(arg.as_str()),
+            _ => return Err(usage().into()),
+        }
+    }
+    let Some(path) = path else {
+        return Err(usage().into());
+    };
+    let src = fs::read_to_string(path)
+        .map_err(|err| format!("Could not read rules '{}': {}", path, err))?;
+    let mut overrides = rules_table(&src)?;
+    let from = from
+        .or_else(|| ruleset_override_schema_version(&overrides).map(str::to_string))
+        .unwrap_or_else(|| SUPPORTED_RULESET_SCHEMA_VERSIONS[0].to_string());
+    let to = to.unwrap_or_else(|| {
+        SUPPORTED_RULESET_SCHEMA_VERSIONS
+            .last()
+            .copied()
+            .unwrap_or("1")
+            .to_string()
+    });
+    let report = migrate_ruleset_override(&mut overrides, &from, &to)?;
+
+    let mut notes = vec![format!("schema: {} -> {}", report.from, report.to)];
+    if report.steps.is_empty() {
+        notes.push("already up to date".into());
+    }
+    notes.extend(report.steps.iter().map(|step| format!("step {}", step)));
+    notes.extend(report.notes.iter().cloned());
+    let migrated = toml::to_string(&overrides)
+        .map_err(|err| format!("Migrated rules serialize error: {}", err))?;
+    if write {
+        if !report.steps.is_empty() {
+            fs::write(path, &migrated)
+                .map_err(|err| format!("Could not write rules '{}': {}", path, err))?;
+        }
+        println!("{}", notes.join("\n"));
+    } else {
+        for note in notes {
+            println!("# {}", note);
+        }
+        print!("{}", migrated);
+    }
+    Ok(())
+}
+
+fn format_percent(count: u32, total: u32) -> String {
+    format!("{:.1}%", count as f32 * 100.0 / total.max(1) as f32)
+}
+
+fn format_seconds(seconds: Option<f32>) -> String {
+    seconds.map_or_else(|| "-".into(), |seconds| format!("{:.1}s", seconds))
+}
+
+fn format_duel_side(side: &DuelSideReport, duels: u32) -> Vec<String> {
+    let hits = side.hit_damage.len() as u32;
+    let mut out = vec![
+        format!("{}:", side.label),
+        format!("  win rate: {}", format_percent(side.wins, duels)),
+        format!(
+            "  time to kill: mean {}, median {}, p90 {}",
+            format_seconds(side.mean_kill_time()),
+            format_seconds(side.kill_time_percentile(50.0)),
+            format_seconds(side.kill_time_percentile(90.0)),
+        ),
+        format!(
+            "  damage per hit: min {}, mean {}, max {}",
+            side.hit_damage
+                .iter()
+                .min()
+                .map_or("-".into(), i32::to_string),
+            side.mean_hit_damage()
+                .map_or("-".into(), |mean| format!("{:.2}", mean)),
+            side.hit_damage
+                .iter()
+                .max()
+                .map_or("-".into(), i32::to_string),
+        ),
+        format!(
+            "  critical hits: {} ({})",
+            side.critical_hits,
+            format_percent(side.critical_hits, hits)
+        ),
+        "  damage distribution:".into(),
+    ];
+    let histogram = side.hit_damage_histogram();
+    let most = histogram.values().copied().max().unwrap_or(0);
+    for (damage, count) in histogram {
+        let bar = "#".repeat(((count * 40).div_ceil(most.max(1))) as usize);
+        out.push(format!(
+            "    {:>4}: {:>6.1}% {}",
+            damage,
+            count as f32 * 100.0 / hits.max(1) as f32,
+            bar
+        ));
+    }
+    out
+}
+
 trait ExtendOrDash {
     fn extend_or_dash(&mut self, values: Vec<String>);
 }
@@ -0,0 +1,1174 @@
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, BTreeSet},
+};
+
+use toml::{Table, Value};
+
+use crate::{
+    FormulaContext, ResolvedDerivedStat, RulesetAttributeMap, RulesetDice, evaluate_formula,
+    evaluate_formula_with, parse_ruleset_dice, resolve_action_catalogue, resolve_attribute_roles,
+    resolve_class_resource_gains, resolve_derived_stats, resolve_identity_defaults, rng::SeededRng,
+    ruleset_table_at_path,
+};
+
+/// A character as seen by the combat rules.
+///
+/// The server implements this for region entities, the duel simulator for
+/// loadouts built from the ruleset alone.
+pub trait Combatant {
+    /// The effective attribute value after derived stats and conditions.
+    fn attribute(&self, id: &str, default: f32) -> f32;
+    /// `id` summed over the equipped weapons.
+    fn weapon_attribute(&self, id: &str) -> f32;
+    /// `id` summed over the equipped armor and clothing.
+    fn armor_attribute(&self, id: &str) -> f32;
+    /// `id` summed over everything equipped.
+    fn equipped_attribute(&self, id: &str) -> f32;
+    fn race(&self) -> Option<String>;
+    fn class(&self) -> Option<String>;
+    /// A text attribute compared in formulas, such as
+    /// `defender.race == "Skeleton"`.
+    fn text_attribute(&self, id: &str) -> Option<String> {
+        match id {
+            "race" => self.race(),
+            "class" => self.class(),
+            _ => None,
+        }
+    }
+}
+
+/// Everything damage resolution reads besides the amount and damage kind.
+pub struct DamageContext<'a> {
+    pub rules: &'a Table,
+    /// The attribute holding a character's level, which defaults to 1.
+    pub level_attribute: &'a str,
+    pub attacker: Option<&'a dyn Combatant>,
+    pub defender: Option<&'a dyn Combatant>,
+    /// Attribute lookup on the weapon or item the damage comes from.
+    pub source: Option<&'a dyn Fn(&str) -> f32>,
+    /// Rolls the dice terms of damage formulas; without it they use their
+    /// average roll.
+    pub roll: Option<&'a dyn Fn(&RulesetDice) -> f32>,
+}
+
+impl DamageContext<'_> {
+    fn combatant_attribute(&self, combatant: Option<&dyn Combatant>, id: &str) -> f32 {
+        let default = if id == self.level_attribute { 1.0 } else { 0.0 };
+        combatant.map_or(0.0, |combatant| combatant.attribute(id, default))
+    }
+
+    /// The ruleset root followed by the attacker's race and class tables,
+    /// the order in which their combat rules apply.
+    fn rule_roots(&self) -> Vec<&Table> {
+        let mut roots = vec![self.rules];
+        if let Some(attacker) = self.attacker {
+            for (section, id) in [("races", attacker.race()), ("classes", attacker.class())] {
+                if let Some(root) = id
+                    .map(|id| id.trim().to_string())
+                    .filter(|id| !id.is_empty())
+                    .and_then(|id| ruleset_table_at_path(self.rules, &[section, &id]))
+                {
+                    roots.push(root);
+                }
+            }
+        }
+        roots
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DamageStage {
+    /// Applied for the attacker, before the defender's reductions.
+    Outgoing,
+    /// Applied for the defender.
+    Incoming,
+}
+
+impl DamageStage {
+    pub fn key(self) -> &'static str {
+        match self {
+            Self::Outgoing => "outgoing_damage",
+            Self::Incoming => "incoming_damage",
+        }
+    }
+}
+
+/// Resolve a variable of a combat formula such as `value`,
+/// `attacker.STR`, `defender.armor.ARMOR` or `source.POWER`.
+pub fn combat_variable(ctx: &DamageContext, name: &str, value: f32) -> f32 {
+    if name == "value" {
+        return value;
+    }
+    if let Some(attr) = name
+        .strip_prefix("attacker.source.")
+        .or_else(|| name.strip_prefix("source."))
+    {
+        return ctx.source.map_or(0.0, |source| source(attr));
+    }
+    let (attacker, defender) = (ctx.attacker, ctx.defender);
+    if let Some(attr) = name.strip_prefix("attacker.equipped.") {
+        return attacker.map_or(0.0, |attacker| attacker.equipped_attribute(attr));
+    }
+    if let Some(attr) = name.strip_prefix("defender.equipped.") {
+        return defender.map_or(0.0, |defender| defender.equipped_attribute(attr));
+    }
+    if let Some(attr) = name.strip_prefix("equipped.") {
+        return attacker.map_or(0.0, |attacker| attacker.equipped_attribute(attr));
+    }
+    if let Some(attr) = name.strip_prefix("attacker.armor.") {
+        return attacker.map_or(0.0, |attacker| attacker.armor_attribute(attr));
+    }
+    if let Some(attr) = name.strip_prefix("defender.armor.") {
+        return defender.map_or(0.0, |defender| defender.armor_attribute(attr));
+    }
+    if let Some(attr) = name.strip_prefix("armor.") {
+        return defender.map_or(0.0, |defender| defender.armor_attribute(attr));
+    }
+    if let Some(attr) = name.strip_prefix("attacker.weapon.") {
+        return attacker.map_or(0.0, |attacker| attacker.weapon_attribute(attr));
+    }
+    if let Some(attr) = name.strip_prefix("defender.weapon.") {
+        return defender.map_or(0.0, |defender| defender.weapon_attribute(attr));
+    }
+    if let Some(attr) = name.strip_prefix("weapon.") {
+        return attacker.map_or(0.0, |attacker| attacker.weapon_attribute(attr));
+    }
+    if let Some(attr) = name.strip_prefix("attacker.") {
+        return ctx.combatant_attribute(attacker, attr);
+    }
+    if let Some(attr) = name.strip_prefix("defender.") {
+        return ctx.combatant_attribute(defender, attr);
+    }
+    0.0
+}
+
+/// Resolve a text variable of a combat formula, `attacker.<attr>` or
+/// `defender.<attr>`, such as `defender.race`.
+pub fn combat_text_variable(ctx: &DamageContext, name: &str) -> Option<String> {
+    if let Some(attr) = name.strip_prefix("attacker.") {
+        return ctx.attacker?.text_attribute(attr);
+    }
+    if let Some(attr) = name.strip_prefix("defender.") {
+        return ctx.defender?.text_attribute(attr);
+    }
+    None
+}
+
+/// A damage stage formula's view of the fight, with `value` as the amount so
+/// far.
+struct CombatFormulaContext<'a, 'b> {
+    ctx: &'a DamageContext<'b>,
+    value: f32,
+}
+
+impl FormulaContext for CombatFormulaContext<'_, '_> {
+    fn number(&mut self, name: &str) -> f32 {
+        combat_variable(self.ctx, name, self.value)
+    }
+
+    fn text(&mut self, name: &str) -> Option<String> {
+        combat_text_variable(self.ctx, name)
+    }
+
+    fn roll(&mut self, dice: &RulesetDice) -> f32 {
+        self.ctx
+            .roll
+            .map_or_else(|| dice.average(), |roll| roll(dice))
+    }
+}
+
+/// The `bonus_attribute` / `bonus_attributes` bonus of a roll, damage or
+/// reduction table: one point per `bonus_every` points of each attribute.
+pub fn combat_attribute_bonus(table: &Table, combatant: Option<&dyn Combatant>) -> f32 {
+    let every = rule_number(table, "bonus_every", 1.0).max(1.0);
+    let mut attributes: Vec<&str> = rule_string(table, "bonus_attribute").into_iter().collect();
+    if let Some(extra) = table.get("bonus_attributes").and_then(Value::as_array) {
+        attributes.extend(extra.iter().filter_map(Value::as_str));
+    }
+    attributes
+        .into_iter()
+        .map(|attr| {
+            combatant.map_or(0.0, |combatant| {
+                (combatant.attribute(attr, 0.0) / every).floor()
+            })
+        })
+        .sum()
+}
+
+pub fn combat_kind_table<'a>(root: &'a Table, kind: &str) -> Option<&'a Table> {
+    ruleset_table_at_path(root, &["combat", "kinds", kind])
+}
+
+/// A `combat.kinds.<kind>` or `combat` formula for `stage`. Incoming damage
+/// also accepts the older `received_damage` key.
+fn combat_rule_expr<'a>(root: &'a Table, kind: &str, stage: DamageStage) -> Option<&'a str> {
+    let keys: &[&str] = match stage {
+        DamageStage::Outgoing => &["outgoing_damage"],
+        DamageStage::Incoming => &["incoming_damage", "received_damage"],
+    };
+    let find = |table: &'a Table| keys.iter().find_map(|key| table.get(*key)?.as_str());
+    if !kind.is_empty()
+        && let Some(expr) = combat_kind_table(root, kind).and_then(find)
+    {
+        return Some(expr);
+    }
+    ruleset_table_at_path(root, &["combat"]).and_then(find)
+}
+
+/// Apply the structured `damage` or `reduction` table of a damage kind.
+fn apply_combat_kind_table(
+    ctx: &DamageContext,
+    amount: f32,
+    stage: DamageStage,
+    kind_table: &Table,
+) -> Option<f32> {
+    match stage {
+        DamageStage::Outgoing => {
+            let damage = kind_table.get("damage").and_then(Value::as_table)?;
+            let mut value = amount + rule_number(damage, "bonus", 0.0);
+            if let Some(attr) = rule_string(damage, "source_bonus_attribute") {
+                value += ctx.source.map_or(0.0, |source| source(attr));
+            }
+            if let Some(attr) = rule_string(damage, "attacker_bonus_attribute") {
+                value += ctx
+                    .attacker
+                    .map_or(0.0, |attacker| attacker.attribute(attr, 0.0));
+            }
+            value += combat_attribute_bonus(damage, ctx.attacker);
+            Some(value.max(0.0))
+        }
+        DamageStage::Incoming => {
+            let reduction = kind_table.get("reduction").and_then(Value::as_table)?;
+            let mut value = amount - rule_number(reduction, "bonus", 0.0);
+            if let Some(attr) = rule_string(reduction, "attribute") {
+                value -= ctx
+                    .defender
+                    .map_or(0.0, |defender| defender.attribute(attr, 0.0));
+            }
+            if let Some(attr) = rule_string(reduction, "equipped_armor_attribute") {
+                value -= ctx
+                    .defender
+                    .map_or(0.0, |defender| defender.armor_attribute(attr));
+            }
+            value -= combat_attribute_bonus(reduction, ctx.defender);
+            Some(value.max(0.0))
+        }
+    }
+}
+
+/// Run one damage stage. Formulas from the ruleset, the attacker's race and
+/// class apply in turn; without any, the structured kind tables do. Returns
+/// `None` when no rule applies or a formula fails.
+pub fn evaluate_damage_stage(
+    ctx: &DamageContext,
+    kind: &str,
+    amount: i32,
+    stage: DamageStage,
+) -> Option<i32> {
+    let roots = ctx.rule_roots();
+    let exprs: Vec<&str> = roots
+        .iter()
+        .filter_map(|root| combat_rule_expr(root, kind, stage))
+        .collect();
+    let mut value = amount as f32;
+    if exprs.is_empty() {
+        let mut applied = false;
+        for root in roots {
+            if let Some(table) = combat_kind_table(root, kind)
+                && let Some(next) = apply_combat_kind_table(ctx, value, stage, table)
+            {
+                value = next;
+                applied = true;
+            }
+        }
+        return applied.then_some(value.round().max(0.0) as i32);
+    }
+    for expr in exprs {
+        let next = evaluate_formula_with(expr, &mut CombatFormulaContext { ctx, value }).ok()?;
+        value = next.max(0.0);
+    }
+    Some(value.round().max(0.0) as i32)
+}
+
+/// The damage a hit of `amount` deals after outgoing and incoming rules.
+pub fn resolve_damage(ctx: &DamageContext, kind: &str, amount: i32) -> i32 {
+    let amount = amount.max(0);
+    let outgoing =
+        evaluate_damage_stage(ctx, kind, amount, DamageStage::Outgoing).unwrap_or(amount);
+    evaluate_damage_stage(ctx, kind, outgoing, DamageStage::Incoming)
+        .unwrap_or(outgoing)
+        .max(0)
+}
+
+pub fn item_quality_damage_multiplier(percent: f32) -> f32 {
+    0.75 + percent.clamp(1.0, 100.0) / 400.0
+}
+
+/// Scale weapon damage by item quality and condition percentages. A hit
+/// that did damage keeps at least 1.
+pub fn apply_item_quality_to_damage(value: f32, quality: f32, condition: f32) -> f32 {
+    let scaled =
+        value * item_quality_damage_multiplier(quality) * item_quality_damage_multiplier(condition);
+    if value > 0.0 { scaled.max(1.0) } else { 0.0 }
+}
+
+/// Critical hits from `combat.critical_roll` and `combat.critical_multiplier`:
+/// a d20 of at least `roll` multiplies the hit's damage.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RulesetCritical {
+    pub roll: u32,
+    pub multiplier: f32,
+}
+
+impl RulesetCritical {
+    pub fn chance(&self) -> f32 {
+        (21 - self.roll) as f32 / 20.0
+    }
+
+    pub fn apply(&self, amount: i32) -> i32 {
+        (amount as f32 * self.multiplier).round().max(0.0) as i32
+    }
+}
+
+pub fn resolve_critical(root: &Table) -> Result<Option<RulesetCritical>, String> {
+    let Some(combat) = ruleset_table_at_path(root, &["combat"]) else {
+        return Ok(None);
+    };
+    let Some(roll) = combat.get("critical_roll") else {
+        return Ok(None);
+    };
+    let roll = match roll {
+        Value::Integer(roll) => u32::try_from(*roll).ok(),
+        Value::String(roll) => roll.trim().parse::<u32>().ok(),
+        _ => None,
+    }
+    .filter(|roll| (1..=20).contains(roll))
+    .ok_or_else(|| "combat.critical_roll must be a d20 result from 1 to 20.".to_string())?;
+    let multiplier = rule_number(combat, "critical_multiplier", 1.0);
+    if !multiplier.is_finite() || multiplier < 0.0 {
+        return Err("combat.critical_multiplier must be a non-negative number.".into());
+    }
+    Ok(Some(RulesetCritical { roll, multiplier }))
+}
+
+/// The damage table of an equipped weapon with its quality and condition
+/// percentages.
+pub struct WeaponDamage<'a> {
+    pub table: &'a Table,
+    pub quality: f32,
+    pub condition: f32,
+}
+
+/// Roll a damage table: its `roll` dice plus `bonus` and the attribute
+/// bonuses of the combatant. None when the table has no valid roll.
+pub fn roll_damage_table(
+    table: &Table,
+    combatant: Option<&dyn Combatant>,
+    roll: &dyn Fn(&RulesetDice) -> f32,
+) -> Option<f32> {
+    let dice = parse_ruleset_dice(rule_string(table, "roll")?).ok()?;
+    Some(
+        (roll(&dice) + rule_number(table, "bonus", 0.0) + combat_attribute_bonus(table, combatant))
+            .max(0.0),
+    )
+}
+
+/// The base damage of a weapon attack before the damage rules. Falls back
+/// from the weapon's damage table (scaled by quality and condition) to
+/// `combat.unarmed_damage`, then to the `progression.damage` stat and
+/// finally to the raw `weapon_damage` role attribute, or 1.
+pub fn roll_attack_damage(
+    root: &Table,
+    attacker: Option<&dyn Combatant>,
+    weapon: Option<WeaponDamage>,
+    progression_damage: impl FnOnce() -> Option<f32>,
+    raw_attribute: impl FnOnce(&str) -> Option<f32>,
+    roll: &dyn Fn(&RulesetDice) -> f32,
+) -> i32 {
+    if let Some(weapon) = weapon
+        && let Some(value) = roll_damage_table(weapon.table, attacker, roll)
+    {
+        return apply_item_quality_to_damage(value, weapon.quality, weapon.condition)
+            .round()
+            .max(0.0) as i32;
+    }
+    if let Some(table) = ruleset_table_at_path(root, &["combat", "unarmed_damage"])
+        && let Some(value) = roll_damage_table(table, attacker, roll)
+    {
+        return value.round().max(0.0) as i32;
+    }
+    progression_damage()
+        .or_else(|| {
+            let roles = resolve_attribute_roles(root).ok()?;
+            raw_attribute(roles.get("weapon_damage")?)
+        })
+        .unwrap_or(1.0)
+        .round()
+        .max(0.0) as i32
+}
+
+/// A progression stat, `base + (level - 1) * (per_level + gain)`, summed over
+/// its ruleset, race and class tables. None when no table defines it.
+pub fn progression_stat(
+    tables: &[&Table],
+    level: f32,
+    attribute: impl Fn(&str) -> f32,
+) -> Option<f32> {
+    if tables.is_empty() {
+        return None;
+    }
+    let base: f32 = tables
+        .iter()
+        .map(|table| rule_number(table, "base", 0.0))
+        .sum();
+    let per_level: f32 = tables
+        .iter()
+        .map(|table| rule_number(table, "per_level", 0.0))
+        .sum();
+    let gain: f32 = tables
+        .iter()
+        .filter_map(|table| {
+            evaluate_formula(table.get("gain")?.as_str()?, |name| {
+                if name == "level" {
+                    level
+                } else {
+                    attribute(name)
+                }
+            })
+            .ok()
+        })
+        .sum();
+    Some((base + (level - 1.0).max(0.0) * (per_level + gain)).max(0.0))
+}
+
+/// A character for the duel simulator, described by race, class, level,
+/// equipment and attribute overrides.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CombatLoadout {
+    pub race: Option<String>,
+    pub class: Option<String>,
+    pub level: u32,
+    /// Equipped item ids. `None` equips the class starting loadout.
+    pub equipment: Option<Vec<String>>,
+    pub attributes: RulesetAttributeMap,
+}
+
+impl CombatLoadout {
+    /// Parse a comma-separated loadout such as
+    /// `Warrior,race=Human,level=3,weapon=hand_axe,STR=14`. Bare words name a
+    /// class or race; `weapon`, `armor`, `clothing` and `item` equip an item,
+    /// and `equipment=none` equips nothing.
+    pub fn parse(root: &Table, spec: &str) -> Result<Self, String> {
+        let mut loadout = Self {
+            level: 1,
+            ..Default::default()
+        };
+        for part in spec
+            .split(',')
+            .map(str::trim)
+            .filter(|part| !part.is_empty())
+        {
+            let Some((key, value)) = part.split_once('=') else {
+                if ruleset_table_at_path(root, &["classes", part]).is_some() {
+                    loadout.class = Some(part.to_string());
+                } else if ruleset_table_at_path(root, &["races", part]).is_some() {
+                    loadout.race = Some(part.to_string());
+                } else {
+                    return Err(format!(
+                        "'{}' is neither a class nor a race; use KEY=VALUE.",
+                        part
+                    ));
+                }
+                continue;
+            };
+            let (key, value) = (key.trim(), value.trim());
+            match key {
+                "race" => loadout.race = Some(value.to_string()),
+                "class" => loadout.class = Some(value.to_string()),
+                "level" => {
+                    loadout.level = value
+                        .parse::<u32>()
+                        .ok()
+                        .filter(|level| *level >= 1)
+                        .ok_or_else(|| format!("Level '{}' is not a positive integer.", value))?
+                }
+                "weapon" | "armor" | "clothing" | "item" | "equipment" => {
+                    let equipment = loadout.equipment.get_or_insert_with(Vec::new);
+                    if value != "none" {
+                        equipment.push(value.to_string());
+                    }
+                }
+                _ => {
+                    let number = value
+                        .parse::<f32>()
+                        .map_err(|_| format!("Attribute '{}' has a non-numeric value.", part))?;
+                    loadout.attributes.insert(key.to_string(), number);
+                }
+            }
+        }
+        Ok(loadout)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct EquippedItem {
+    group: String,
+    attributes: RulesetAttributeMap,
+    damage_kind: Option<String>,
+    damage: Option<Table>,
+}
+
+/// A loadout resolved against a ruleset, with the attributes a spawned
+/// character of that race, class and level would start with.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SimulatedCombatant {
+    pub label: String,
+    race: Option<String>,
+    class: Option<String>,
+    level_attribute: String,
+    attributes: RulesetAttributeMap,
+    derived_stats: BTreeMap<String, ResolvedDerivedStat>,
+    items: Vec<(String, EquippedItem)>,
+}
+
+impl SimulatedCombatant {
+    pub fn new(root: &Table, loadout: &CombatLoadout) -> Result<Self, String> {
+        let identity = resolve_identity_defaults(root)?;
+        let race = loadout.race.clone().or(identity.race);
+        let class = loadout.class.clone().or(identity.class);
+        let roles = resolve_attribute_roles(root)?;
+        let level_attribute = roles.get("level").unwrap_or("LEVEL").to_string();
+
+        let mut attributes = RulesetAttributeMap::new();
+        let mut merge = |table: Option<&Table>| {
+            for (key, value) in table.into_iter().flatten() {
+                if let Some(number) = value_number(value) {
+                    attributes.insert(key.clone(), number);
+                }
+            }
+        };
+        merge(ruleset_table_at_path(root, &["attributes", "defaults"]));
+        for (section, id) in [("races", &race), ("classes", &class)] {
+            if let Some(id) = id {
+                let entry = ruleset_table_at_path(root, &[section, id])
+                    .ok_or_else(|| format!("{} '{}' was not found.", section_label(section), id))?;
+                merge(entry.get("attributes").and_then(Value::as_table));
+            }
+        }
+
+        let max_level = ruleset_table_at_path(root, &["progression", "level"])
+            .and_then(|level| level.get("max_level"))
+            .and_then(Value::as_integer)
+            .map(|level| level.max(1) as u32)
+            .unwrap_or(u32::MAX);
+        let level = loadout.level.clamp(1, max_level);
+        attributes.insert(level_attribute.clone(), level as f32);
+        let levels_gained = (level - 1) as f32;
+        if let Some(class_id) = &class
+            && levels_gained > 0.0
+        {
+            for gain in resolve_class_resource_gains(root, class_id)? {
+                let amount = gain.per_level as f32 * levels_gained;
+                let maximum = attributes
+                    .get(&gain.maximum_attribute)
+                    .copied()
+                    .unwrap_or(0.0)
+                    + amount;
+                attributes.insert(gain.maximum_attribute.clone(), maximum.max(0.0));
+                let current = attributes.get(&gain.attribute).copied().unwrap_or(0.0) + amount;
+                attributes.insert(gain.attribute.clone(), current.min(maximum).max(0.0));
+            }
+            let class_table = ruleset_table_at_path(root, &["classes", class_id]);
+            let primary_gain = class_table
+                .and_then(|class| ruleset_table_at_path(class, &["progression", "level"]))
+                .map(|level| {
+                    rule_number(level, "primary_attribute_gain", 0.0)
+                        .round()
+                        .max(0.0)
+                })
+                .unwrap_or(0.0);
+            if primary_gain > 0.0 {
+                for attr in class_table
+                    .and_then(|class| class.get("primary_attributes"))
+                    .and_then(Value::as_array)
+                    .into_iter()
+                    .flatten()
+                    .filter_map(Value::as_str)
+                {
+                    *attributes.entry(attr.to_string()).or_insert(0.0) +=
+                        primary_gain * levels_gained;
+                }
+            }
+        }
+        attributes.extend(loadout.attributes.clone());
+
+        let equipment = match &loadout.equipment {
+            Some(equipment) => equipment.clone(),
+            None => class
+                .as_deref()
+                .and_then(|class| {
+                    ruleset_table_at_path(root, &["classes", class, "starting_loadout"])
+                })
+                .map(|loadout| {
+                    ["equipment", "weapons", "armor", "clothing"]
+                        .iter()
+                        .filter_map(|key| loadout.get(*key)?.as_array())
+                        .flatten()
+                        .filter_map(Value::as_str)
+                        .map(str::to_string)
+                        .collect()
+                })
+                .unwrap_or_default(),
+        };
+        let items = equipment
+            .iter()
+            .map(|id| equipped_item(root, id).map(|item| (id.clone(), item)))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut label = [class.as_deref(), race.as_deref()]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>()
+            .join("/");
+        if label.is_empty() {
+            label = "character".into();
+        }
+        label.push_str(&format!(" L{}", level));
+        if let Some((weapon, _)) = items.iter().find(|(_, item)| item.group == "weapons") {
+            label.push_str(&format!(" ({})", weapon));
+        }
+
+        Ok(Self {
+            label,
+            race,
+            class,
+            level_attribute,
+            attributes,
+            derived_stats: resolve_derived_stats(root)?,
+            items,
+        })
+    }
+
+    fn weapon(&self) -> Option<&EquippedItem> {
+        self.items
+            .iter()
+            .map(|(_, item)| item)
+            .find(|item| item.group == "weapons")
+    }
+
+    fn item_sum(&self, id: &str, groups: &[&str]) -> f32 {
+        self.items
+            .iter()
+            .filter(|(_, item)| groups.is_empty() || groups.contains(&item.group.as_str()))
+            .map(|(_, item)| item.attributes.get(id).copied().unwrap_or(0.0))
+            .sum()
+    }
+
+    fn effective_attribute(&self, id: &str, default: f32, visiting: &mut BTreeSet<String>) -> f32 {
+        let raw = self.attributes.get(id).copied().unwrap_or(default);
+        let key = id.to_ascii_lowercase();
+        if !visiting.insert(key.clone()) {
+            return raw;
+        }
+        let stat = self.derived_stats.get(id).or_else(|| {
+            self.derived_stats
+                .values()
+                .find(|stat| stat.id.eq_ignore_ascii_case(id))
+        });
+        let value = stat
+            .and_then(|stat| {
+                let value = evaluate_formula(&stat.formula, |name| match name {
+                    "base" => raw,
+                    "level" => self
+                        .attributes
+                        .get(&self.level_attribute)
+                        .copied()
+                        .unwrap_or(1.0),
+                    dependency => self.effective_attribute(dependency, 0.0, visiting),
+                })
+                .ok()?;
+                let value = stat.minimum.map_or(value, |minimum| value.max(minimum));
+                Some(stat.maximum.map_or(value, |maximum| value.min(maximum)))
+            })
+            .unwrap_or(raw);
+        visiting.remove(&key);
+        value
+    }
+}
+
+impl Combatant for SimulatedCombatant {
+    fn attribute(&self, id: &str, default: f32) -> f32 {
+        self.effective_attribute(id, default, &mut BTreeSet::new())
+    }
+
+    fn weapon_attribute(&self, id: &str) -> f32 {
+        self.item_sum(id, &["weapons"])
+    }
+
+    fn armor_attribute(&self, id: &str) -> f32 {
+        self.item_sum(id, &["armor", "clothing"])
+    }
+
+    fn equipped_attribute(&self, id: &str) -> f32 {
+        self.item_sum(id, &[])
+    }
+
+    fn race(&self) -> Option<String> {
+        self.race.clone()
+    }
+
+    fn class(&self) -> Option<String> {
+        self.class.clone()
+    }
+}
+
+fn section_label(section: &str) -> &'static str {
+    match section {
+        "races" => "Race",
+        _ => "Class",
+    }
+}
+
+fn equipped_item(root: &Table, item_id: &str) -> Result<EquippedItem, String> {
+    let (group, table) = ruleset_table_at_path(root, &["items"])
+        .into_iter()
+        .flatten()
+        .find_map(|(group, items)| {
+            let item = items.as_table()?.get(item_id)?.as_table()?;
+            Some((group.clone(), item))
+        })
+        .ok_or_else(|| format!("Item '{}' was not found.", item_id))?;
+    let item_attributes = table.get("attributes").and_then(Value::as_table);
+    let attributes = item_attributes
+        .into_iter()
+        .flatten()
+        .filter_map(|(key, value)| Some((key.clone(), value_number(value)?)))
+        .collect();
+    Ok(EquippedItem {
+        group,
+        attributes,
+        damage_kind: item_attributes
+            .and_then(|attributes| rule_string(attributes, "damage_kind"))
+            .map(str::to_string),
+        damage: table.get("damage").and_then(Value::as_table).cloned(),
+    })
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct DuelOptions {
+    pub duels: u32,
+    pub seed: u64,
+    /// Duels still running after this many seconds count as draws.
+    pub time_limit: f32,
+}
+
+impl Default for DuelOptions {
+    fn default() -> Self {
+        Self {
+            duels: 1000,
+            seed: 1,
+            time_limit: 300.0,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DuelSideReport {
+    pub label: String,
+    pub wins: u32,
+    /// Seconds from the first swing to the killing blow, one per win.
+    pub kill_times: Vec<f32>,
+    /// Damage of every landed hit, after reductions and criticals.
+    pub hit_damage: Vec<i32>,
+    pub critical_hits: u32,
+}
+
+impl DuelSideReport {
+    pub fn mean_kill_time(&self) -> Option<f32> {
+        (!self.kill_times.is_empty())
+            .then(|| self.kill_times.iter().sum::<f32>() / self.kill_times.len() as f32)
+    }
+
+    /// The kill time below which `percent` of the wins fall.
+    pub fn kill_time_percentile(&self, percent: f32) -> Option<f32> {
+        let mut times = self.kill_times.clone();
+        times.sort_by(f32::total_cmp);
+        let index = (times.len() as f32 * percent / 100.0).ceil() as usize;
+        times.get(index.clamp(1, times.len().max(1)) - 1).copied()
+    }
+
+    pub fn mean_hit_damage(&self) -> Option<f32> {
+        (!self.hit_damage.is_empty()).then(|| {
+            self.hit_damage
+                .iter()
+                .map(|damage| *damage as f32)
+                .sum::<f32>()
+                / self.hit_damage.len() as f32
+        })
+    }
+
+    /// How often each damage value was dealt, in ascending damage order.
+    pub fn hit_damage_histogram(&self) -> BTreeMap<i32, u32> {
+        let mut histogram = BTreeMap::new();
+        for damage in &self.hit_damage {
+            *histogram.entry(*damage).or_insert(0) += 1;
+        }
+        histogram
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DuelReport {
+    pub duels: u32,
+    pub draws: u32,
+    pub sides: [DuelSideReport; 2],
+}
+
+/// Run Monte-Carlo duels between two loadouts. Both sides swing at time zero
+/// and then on their attack cooldown. Initiative is rolled per duel and
+/// decides who strikes first when both swing at once; a fallen side does not
+/// strike back. Duels that outlast the time limit are draws.
+pub fn simulate_duels(
+    root: &Table,
+    first: &CombatLoadout,
+    second: &CombatLoadout,
+    options: &DuelOptions,
+) -> Result<DuelReport, String> {
+    let combatants = [
+        SimulatedCombatant::new(root, first)?,
+        SimulatedCombatant::new(root, second)?,
+    ];
+    let health_attribute = resolve_attribute_roles(root)?
+        .get("health")
+        .unwrap_or("HP")
+        .to_string();
+    let critical = resolve_critical(root)?;
+    let default_cooldown = resolve_action_catalogue(root)?
+        .action_for_intent("attack")
+        .map(|action| action.cooldown_seconds)
+        .filter(|cooldown| *cooldown > 0.0)
+        .or_else(|| {
+            ruleset_table_at_path(root, &["combat"])
+                .map(|combat| rule_number(combat, "default_attack_cooldown", 0.0))
+                .filter(|cooldown| *cooldown > 0.0)
+        })
+        .unwrap_or(1.0);
+    let attacks = [
+        DuelAttack::new(root, &combatants[0], default_cooldown)?,
+        DuelAttack::new(root, &combatants[1], default_cooldown)?,
+    ];
+    let health = combatants
+        .each_ref()
+        .map(|combatant| combatant.attribute(&health_attribute, 0.0));
+    if let Some(index) = health.iter().position(|health| *health <= 0.0) {
+        return Err(format!(
+            "{} starts with no {}.",
+            combatants[index].label, health_attribute
+        ));
+    }
+
+    let mut report = DuelReport {
+        duels: options.duels,
+        sides: combatants.each_ref().map(|combatant| DuelSideReport {
+            label: combatant.label.clone(),
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    let rng = RefCell::new(SeededRng::new(options.seed));
+    let roll = |dice: &RulesetDice| rng.borrow_mut().roll(dice);
+    for _ in 0..options.duels {
+        let mut health = health;
+        let mut next_swing = [0.0f32; 2];
+        let initiative = rng.borrow_mut().range(0, 1) as usize;
+        'duel: loop {
+            let now = next_swing[0].min(next_swing[1]);
+            if now > options.time_limit {
+                report.draws += 1;
+                break;
+            }
+            for side in [initiative, 1 - initiative] {
+                if next_swing[side] > now {
+                    continue;
+                }
+                let ctx = DamageContext {
+                    rules: root,
+                    level_attribute: &combatants[side].level_attribute,
+                    attacker: Some(&combatants[side]),
+                    defender: Some(&combatants[1 - side]),
+                    source: Some(&|attr: &str| combatants[side].weapon_attribute(attr)),
+                    roll: Some(&roll),
+                };
+                let (amount, kind) = attacks[side].roll(root, &combatants[side], &roll);
+                let mut hit = resolve_damage(&ctx, kind, amount);
+                if let Some(critical) = &critical
+                    && rng.borrow_mut().range(1, 20) >= critical.roll
+                {
+                    hit = critical.apply(hit);
+                    report.sides[side].critical_hits += 1;
+                }
+                report.sides[side].hit_damage.push(hit);
+                next_swing[side] += attacks[side].cooldown;
+                health[1 - side] -= hit as f32;
+                if health[1 - side] <= 0.0 {
+                    report.sides[side].wins += 1;
+                    report.sides[side].kill_times.push(now);
+                    break 'duel;
+                }
+            }
+        }
+    }
+    Ok(report)
+}
+
+/// How a combatant attacks: its weapon's damage roll, damage kind and swing
+/// cooldown. The damage itself is rolled by [`roll_attack_damage`] like on
+/// the server.
+struct DuelAttack {
+    weapon_damage: Option<Table>,
+    kind: String,
+    cooldown: f32,
+}
+
+impl DuelAttack {
+    fn new(
+        root: &Table,
+        combatant: &SimulatedCombatant,
+        default_cooldown: f32,
+    ) -> Result<Self, String> {
+        let weapon = combatant.weapon();
+        let weapon_damage = weapon.and_then(|weapon| weapon.damage.clone());
+        if let Some(table) = weapon_damage
+            .as_ref()
+            .or_else(|| ruleset_table_at_path(root, &["combat", "unarmed_damage"]))
+            && let Some(dice) = table.get("roll").and_then(Value::as_str)
+        {
+            parse_ruleset_dice(dice)?;
+        }
+        let cooldown = weapon
+            .and_then(|weapon| weapon.attributes.get("attack_cooldown").copied())
+            .filter(|cooldown| *cooldown > 0.0)
+            .unwrap_or(default_cooldown);
+        Ok(Self {
+            weapon_damage,
+            kind: weapon
+                .and_then(|weapon| weapon.damage_kind.clone())
+                .unwrap_or_else(|| "physical".into()),
+            cooldown,
+        })
+    }
+
+    fn roll(
+        &self,
+        root: &Table,
+        combatant: &SimulatedCombatant,
+        roll: &dyn Fn(&RulesetDice) -> f32,
+    ) -> (i32, &str) {
+        let amount = roll_attack_damage(
+            root,
+            Some(combatant),
+            self.weapon_damage.as_ref().map(|table| WeaponDamage {
+                table,
+                quality: 100.0,
+                condition: 100.0,
+            }),
+            || progression_damage(root, combatant),
+            |attribute| combatant.attributes.get(attribute).copied(),
+            roll,
+        );
+        (amount, &self.kind)
+    }
+}
+
+/// The `progression.damage` stat summed over the ruleset, race and class.
+fn progression_damage(root: &Table, combatant: &SimulatedCombatant) -> Option<f32> {
+    let mut tables = vec![ruleset_table_at_path(root, &["progression", "damage"])];
+    for (section, id) in [("races", &combatant.race), ("classes", &combatant.class)] {
+        tables.push(
+            id.as_deref().and_then(|id| {
+                ruleset_table_at_path(root, &[section, id, "progression", "damage"])
+            }),
+        );
+    }
+    let tables: Vec<&Table> = tables.into_iter().flatten().collect();
+    let level = combatant
+        .attributes
+        .get(&combatant.level_attribute)
+        .copied()
+        .unwrap_or(1.0)
+        .round()
+        .max(1.0);
+    progression_stat(&tables, level, |name| {
+        combatant.attributes.get(name).copied().unwrap_or(0.0)
+    })
+}
+
+fn value_number(value: &Value) -> Option<f32> {
+    match value {
+        Value::Integer(value) => Some(*value as f32),
+        Value::Float(value) => Some(*value as f32),
+        _ => None,
+    }
+}
+
+fn rule_number(table: &Table, key: &str, default: f32) -> f32 {
+    table.get(key).and_then(value_number).unwrap_or(default)
+}
+
+fn rule_string<'a>(table: &'a Table, key: &str) -> Option<&'a str> {
+    table
+        .get(key)
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{latest_official_ruleset, parse_ruleset_table};
+
+    fn official() -> Table {
+        parse_ruleset_table(latest_official_ruleset()).expect("official ruleset parses")
+    }
+
+    #[test]
+    fn structured_kinds_apply_bonuses_and_reductions() {
+        let rules = official();
+        let mage = SimulatedCombatant::new(
+            &rules,
+            &CombatLoadout::parse(&rules, "Warrior,race=Human,INT=18,POWER=2").unwrap(),
+        )
+        .unwrap();
+        let knight = SimulatedCombatant::new(
+            &rules,
+            &CombatLoadout::parse(&rules, "Warrior,race=Human,RESIST=1").unwrap(),
+        )
+        .unwrap();
+        let source = |attr: &str| if attr == "POWER" { 3.0 } else { 0.0 };
+        let ctx = DamageContext {
+            rules: &rules,
+            level_attribute: "LEVEL",
+            attacker: Some(&mage),
+            defender: Some(&knight),
+            source: Some(&source),
+            roll: None,
+        };
+
+        // 5 + source POWER 3 + attacker POWER (2 + floor((18 - 10) / 4)) + floor(18 / 4).
+        assert_eq!(
+            evaluate_damage_stage(&ctx, "arcane", 5, DamageStage::Outgoing),
+            Some(16)
+        );
+        assert_eq!(resolve_damage(&ctx, "arcane", 5), 15);
+        // Physical damage is reduced by ARMOR 1 plus 1 from the padded armor.
+        assert_eq!(resolve_damage(&ctx, "physical", 5), 3);
+        assert_eq!(resolve_damage(&ctx, "physical", 1), 0);
+    }
+
+    #[test]
+    fn formulas_replace_structured_tables() {
+        let mut rules = official();
+        let overlay: Table = toml::from_str(
+            r#"
+            [combat]
+            outgoing_damage = "value * 2"
+            [classes.Warrior.combat]
+            outgoing_damage = "value + attacker.LEVEL + weapon.DMG"
+            "#,
+        )
+        .unwrap();
+        crate::merge_toml_tables(&mut rules, overlay);
+        let warrior = SimulatedCombatant::new(
+            &rules,
+            &CombatLoadout::parse(&rules, "Warrior,level=3").unwrap(),
+        )
+        .unwrap();
+        let ctx = DamageContext {
+            rules: &rules,
+            level_attribute: "LEVEL",
+            attacker: Some(&warrior),
+            defender: None,
+            source: None,
+            roll: None,
+        };
+
+        // (4 * 2) + level 3 + training sword DMG 2.
+        assert_eq!(
+            evaluate_damage_stage(&ctx, "physical", 4, DamageStage::Outgoing),
+            Some(13)
+        );
+    }
+
+    #[test]
+    fn loadouts_follow_class_progression_and_starting_gear() {
+        let rules = official();
+        let warrior = SimulatedCombatant::new(
+            &rules,
+            &CombatLoadout::parse(&rules, "Warrior,race=Human,level=3").unwrap(),
+        )
+        .unwrap();
+
+        assert_eq!(warrior.attribute("MAX_HP", 0.0), 28.0);
+        assert_eq!(warrior.attribute("HP", 0.0), 28.0);
+        assert_eq!(warrior.attribute("STR", 0.0), 14.0);
+        assert_eq!(warrior.attribute("DMG", 0.0), 2.0);
+        assert_eq!(warrior.weapon_attribute("DMG"), 2.0);
+        assert_eq!(warrior.armor_attribute("ARMOR"), 1.0);
+        assert_eq!(warrior.label, "Warrior/Human L3 (training_sword)");
+
+        let bare = CombatLoadout::parse(&rules, "Orc,equipment=none").unwrap();
+        assert_eq!(bare.race.as_deref(), Some("Orc"));
+        assert_eq!(bare.equipment, Some(Vec::new()));
+        assert!(CombatLoadout::parse(&rules, "Dragon").is_err());
+        assert!(
+            SimulatedCombatant::new(
+                &rules,
+                &CombatLoadout::parse(&rules, "weapon=laser").unwrap()
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn duels_are_reproducible_and_favor_the_stronger_side() {
+        let rules = official();
+        let veteran = CombatLoadout::parse(&rules, "Warrior,race=Human,level=6").unwrap();
+        let recruit = CombatLoadout::parse(&rules, "Warrior,race=Human,equipment=none").unwrap();
+        let options = DuelOptions {
+            duels: 200,
+            ..Default::default()
+        };
+
+        let report = simulate_duels(&rules, &veteran, &recruit, &options).unwrap();
+        assert_eq!(
+            report,
+            simulate_duels(&rules, &veteran, &recruit, &options).unwrap()
+        );
+        assert_eq!(
+            report.sides[0].wins + report.sides[1].wins + report.draws,
+            options.duels
+        );
+        assert!(report.sides[0].wins > report.sides[1].wins * 4);
+        assert!(report.sides[0].mean_kill_time().unwrap() > 0.0);
+        assert!(report.sides[0].critical_hits > 0);
+        assert_eq!(
+            report.sides[0].hit_damage_histogram().values().sum::<u32>() as usize,
+            report.sides[0].hit_damage.len()
+        );
+    }
+
+    #[test]
+    fn resolves_critical_rules() {
+        let rules = official();
+        let critical = resolve_critical(&rules).unwrap().unwrap();
+        assert_eq!(critical.roll, 20);
+        assert_eq!(critical.chance(), 0.05);
+        assert_eq!(critical.apply(5), 8);
+
+        let broken: Table = toml::from_str("[combat]\ncritical_roll = \"d20\"").unwrap();
+        assert!(resolve_critical(&broken).is_err());
+        assert_eq!(resolve_critical(&Table::new()).unwrap(), None);
+    }
+}
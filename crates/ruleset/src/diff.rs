@@ -0,0 +1,446 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use toml::{Table, Value};
+
+use crate::{parse_release_version, ruleset_table_at_path};
+
+/// Ruleset sections compared entry by entry, with the depth of their entry
+/// tables below the section root (items are grouped by kind).
+const DIFF_SECTIONS: &[(&str, usize)] = &[
+    ("actions", 1),
+    ("items", 2),
+    ("classes", 1),
+    ("conditions", 1),
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum RulesetFieldChangeKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+impl RulesetFieldChangeKind {
+    pub fn marker(self) -> char {
+        match self {
+            Self::Added => '+',
+            Self::Removed => '-',
+            Self::Changed => '~',
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RulesetFieldChange {
+    /// Dotted path below the entry, e.g. `damage.roll`.
+    pub path: String,
+    pub kind: RulesetFieldChangeKind,
+    /// Removed fields and values that changed type break overrides and
+    /// scripts that read them.
+    pub breaking: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RulesetEntryChange {
+    pub section: String,
+    /// The entry id; items include their group, e.g. `weapons.hand_axe`.
+    pub id: String,
+    pub kind: RulesetFieldChangeKind,
+    pub fields: Vec<RulesetFieldChange>,
+    /// Places in the old ruleset that name a removed id.
+    pub referenced_by: Vec<String>,
+}
+
+impl RulesetEntryChange {
+    pub fn path(&self) -> String {
+        format!("{}.{}", self.section, self.id)
+    }
+
+    pub fn is_breaking(&self) -> bool {
+        self.kind == RulesetFieldChangeKind::Removed
+            || self.fields.iter().any(|field| field.breaking)
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RulesetVersionInfo {
+    pub id: Option<String>,
+    pub version: Option<String>,
+    pub schema_version: Option<String>,
+}
+
+impl RulesetVersionInfo {
+    fn from_table(table: &Table) -> Self {
+        let ruleset = ruleset_table_at_path(table, &["ruleset"]);
+        let field = |key: &str| {
+            ruleset
+                .and_then(|ruleset| ruleset.get(key))
+                .and_then(Value::as_str)
+                .map(str::to_string)
+        };
+        Self {
+            id: field("id"),
+            version: field("version"),
+            schema_version: field("schema_version"),
+        }
+    }
+}
+
+/// Added, removed and changed entries between two rulesets.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RulesetDiff {
+    pub from: RulesetVersionInfo,
+    pub to: RulesetVersionInfo,
+    pub changes: Vec<RulesetEntryChange>,
+}
+
+/// A project override entry that no longer lines up with the new ruleset.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RulesetOverrideConflict {
+    pub path: String,
+    pub message: String,
+}
+
+impl RulesetDiff {
+    pub fn breaking_changes(&self) -> impl Iterator<Item = &RulesetEntryChange> {
+        self.changes.iter().filter(|change| change.is_breaking())
+    }
+
+    /// A warning when breaking changes ship without a major version bump,
+    /// which `update_policy = "compatible"` projects would pick up silently.
+    pub fn version_warning(&self) -> Option<String> {
+        self.breaking_changes().next()?;
+        let from = self.from.version.as_deref()?;
+        let to = self.to.version.as_deref()?;
+        let from_release = parse_release_version(from, "Ruleset version").ok()?;
+        let to_release = parse_release_version(to, "Ruleset version").ok()?;
+        (to_release.0 <= from_release.0).then(|| {
+            format!(
+                "Breaking changes without a major version bump ({} -> {}); projects with update_policy = \"compatible\" would pick them up.",
+                from, to
+            )
+        })
+    }
+
+    /// Entries of `overrides` that patch removed entries or set fields the
+    /// new ruleset dropped. Such overrides no longer apply as written.
+    pub fn override_conflicts(&self, overrides: &Table) -> Vec<RulesetOverrideConflict> {
+        let mut conflicts = Vec::new();
+        for change in &self.changes {
+            let path = change.path();
+            let parts: Vec<&str> = path.split('.').collect();
+            let Some(entry) = value_at_path(overrides, &parts) else {
+                continue;
+            };
+            match change.kind {
+                RulesetFieldChangeKind::Removed => conflicts.push(RulesetOverrideConflict {
+                    message: format!(
+                        "overrides {}, which the new ruleset removed; the override now defines a partial entry.",
+                        path
+                    ),
+                    path,
+                }),
+                RulesetFieldChangeKind::Changed => {
+                    let Some(entry) = entry.as_table() else {
+                        continue;
+                    };
+                    for field in change
+                        .fields
+                        .iter()
+                        .filter(|field| field.kind == RulesetFieldChangeKind::Removed)
+                    {
+                        let field_parts: Vec<&str> = field.path.split('.').collect();
+                        if value_at_path(entry, &field_parts).is_some() {
+                            conflicts.push(RulesetOverrideConflict {
+                                path: format!("{}.{}", path, field.path),
+                                message: format!(
+                                    "sets {}.{}, which the new ruleset no longer defines.",
+                                    path, field.path
+                                ),
+                            });
+                        }
+                    }
+                }
+                RulesetFieldChangeKind::Added => {}
+            }
+        }
+        conflicts
+    }
+}
+
+pub fn diff_rulesets(from: &Table, to: &Table) -> RulesetDiff {
+    let mut changes = Vec::new();
+    for (section, depth) in DIFF_SECTIONS {
+        let old_entries = section_entries(from, section, *depth);
+        let new_entries = section_entries(to, section, *depth);
+        let ids: BTreeSet<&String> = old_entries.keys().chain(new_entries.keys()).collect();
+        for id in ids {
+            let change = match (old_entries.get(id), new_entries.get(id)) {
+                (None, Some(_)) => RulesetEntryChange {
+                    section: section.to_string(),
+                    id: id.clone(),
+                    kind: RulesetFieldChangeKind::Added,
+                    fields: Vec::new(),
+                    referenced_by: Vec::new(),
+                },
+                (Some(_), None) => {
+                    let bare_id = id.rsplit('.').next().unwrap_or(id);
+                    let own_path = format!("{}.{}", section, id);
+                    let mut referenced_by = Vec::new();
+                    collect_table_references(from, bare_id, &mut Vec::new(), &mut referenced_by);
+                    referenced_by.retain(|path| {
+                        path != &own_path && !path.starts_with(&format!("{}.", own_path))
+                    });
+                    RulesetEntryChange {
+                        section: section.to_string(),
+                        id: id.clone(),
+                        kind: RulesetFieldChangeKind::Removed,
+                        fields: Vec::new(),
+                        referenced_by,
+                    }
+                }
+                (Some(old), Some(new)) => {
+                    let mut fields = Vec::new();
+                    diff_values(old, new, "", &mut fields);
+                    if fields.is_empty() {
+                        continue;
+                    }
+                    RulesetEntryChange {
+                        section: section.to_string(),
+                        id: id.clone(),
+                        kind: RulesetFieldChangeKind::Changed,
+                        fields,
+                        referenced_by: Vec::new(),
+                    }
+                }
+                (None, None) => continue,
+            };
+            changes.push(change);
+        }
+    }
+    RulesetDiff {
+        from: RulesetVersionInfo::from_table(from),
+        to: RulesetVersionInfo::from_table(to),
+        changes,
+    }
+}
+
+fn section_entries<'a>(
+    root: &'a Table,
+    section: &str,
+    depth: usize,
+) -> BTreeMap<String, &'a Value> {
+    let mut entries = BTreeMap::new();
+    let Some(table) = ruleset_table_at_path(root, &[section]) else {
+        return entries;
+    };
+    if depth <= 1 {
+        for (id, value) in table {
+            if value.is_table() {
+                entries.insert(id.clone(), value);
+            }
+        }
+    } else {
+        for (group, items) in table {
+            for (id, value) in items.as_table().into_iter().flatten() {
+                if value.is_table() {
+                    entries.insert(format!("{}.{}", group, id), value);
+                }
+            }
+        }
+    }
+    entries
+}
+
+fn diff_values(old: &Value, new: &Value, path: &str, out: &mut Vec<RulesetFieldChange>) {
+    let child = |key: &str| {
+        if path.is_empty() {
+            key.to_string()
+        } else {
+            format!("{}.{}", path, key)
+        }
+    };
+    match (old, new) {
+        (Value::Table(old), Value::Table(new)) => {
+            let keys: BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+            for key in keys {
+                match (old.get(key), new.get(key)) {
+                    (Some(old), Some(new)) => diff_values(old, new, &child(key), out),
+                    (Some(_), None) => out.push(RulesetFieldChange {
+                        path: child(key),
+                        kind: RulesetFieldChangeKind::Removed,
+                        breaking: true,
+                    }),
+                    (None, Some(_)) => out.push(RulesetFieldChange {
+                        path: child(key),
+                        kind: RulesetFieldChangeKind::Added,
+                        breaking: false,
+                    }),
+                    (None, None) => {}
+                }
+            }
+        }
+        _ if old != new => out.push(RulesetFieldChange {
+            path: path.to_string(),
+            kind: RulesetFieldChangeKind::Changed,
+            breaking: !same_value_type(old, new),
+        }),
+        _ => {}
+    }
+}
+
+fn same_value_type(old: &Value, new: &Value) -> bool {
+    let numeric = |value: &Value| matches!(value, Value::Integer(_) | Value::Float(_));
+    std::mem::discriminant(old) == std::mem::discriminant(new) || (numeric(old) && numeric(new))
+}
+
+fn collect_references<'a>(
+    value: &'a Value,
+    id: &str,
+    path: &mut Vec<&'a str>,
+    out: &mut Vec<String>,
+) {
+    match value {
+        Value::String(text) if text == id => out.push(path.join(".")),
+        Value::Array(values) => {
+            if values.iter().any(|value| value.as_str() == Some(id)) {
+                out.push(path.join("."));
+            }
+            for value in values
+                .iter()
+                .filter(|value| value.is_table() || value.is_array())
+            {
+                collect_references(value, id, path, out);
+            }
+        }
+        Value::Table(table) => collect_table_references(table, id, path, out),
+        _ => {}
+    }
+}
+
+fn collect_table_references<'a>(
+    table: &'a Table,
+    id: &str,
+    path: &mut Vec<&'a str>,
+    out: &mut Vec<String>,
+) {
+    for (key, value) in table {
+        path.push(key);
+        collect_references(value, id, path, out);
+        path.pop();
+    }
+}
+
+fn value_at_path<'a>(table: &'a Table, path: &[&str]) -> Option<&'a Value> {
+    let (last, parents) = path.split_last()?;
+    let mut table = table;
+    for part in parents {
+        table = table.get(*part)?.as_table()?;
+    }
+    table.get(*last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{latest_official_ruleset, parse_ruleset_table};
+
+    fn official() -> Table {
+        parse_ruleset_table(latest_official_ruleset()).expect("official ruleset parses")
+    }
+
+    #[test]
+    fn identical_rulesets_have_no_changes() {
+        let rules = official();
+        let diff = diff_rulesets(&rules, &rules);
+        assert!(diff.changes.is_empty());
+        assert_eq!(diff.version_warning(), None);
+    }
+
+    #[test]
+    fn reports_added_removed_and_changed_entries() {
+        let old = official();
+        let mut new = old.clone();
+        let rules: &mut Table = &mut new;
+        rules["ruleset"]["version"] = Value::String("1.1.0".into());
+        let weapons = rules["items"]["weapons"].as_table_mut().unwrap();
+        weapons.remove("training_sword");
+        let actions = rules["actions"].as_table_mut().unwrap();
+        let basic_attack = actions["basic_attack"].as_table_mut().unwrap();
+        basic_attack.insert("cooldown".into(), Value::Float(0.5));
+        basic_attack.remove("intent");
+        actions.insert("taunt".into(), Value::Table(Table::new()));
+
+        let diff = diff_rulesets(&old, &new);
+        let find = |path: &str| {
+            diff.changes
+                .iter()
+                .find(|change| change.path() == path)
+                .unwrap_or_else(|| panic!("{} missing from the diff", path))
+        };
+
+        let removed = find("items.weapons.training_sword");
+        assert_eq!(removed.kind, RulesetFieldChangeKind::Removed);
+        assert!(removed.is_breaking());
+        assert!(
+            removed
+                .referenced_by
+                .contains(&"classes.Warrior.starting_loadout.weapons".to_string())
+        );
+
+        let changed = find("actions.basic_attack");
+        assert_eq!(changed.kind, RulesetFieldChangeKind::Changed);
+        assert!(changed.fields.contains(&RulesetFieldChange {
+            path: "cooldown".into(),
+            kind: RulesetFieldChangeKind::Changed,
+            breaking: false,
+        }));
+        assert!(changed.fields.contains(&RulesetFieldChange {
+            path: "intent".into(),
+            kind: RulesetFieldChangeKind::Removed,
+            breaking: true,
+        }));
+
+        assert_eq!(find("actions.taunt").kind, RulesetFieldChangeKind::Added);
+        assert!(!find("actions.taunt").is_breaking());
+        assert!(diff.version_warning().is_some());
+    }
+
+    #[test]
+    fn flags_overrides_of_removed_entries_and_fields() {
+        let old = official();
+        let mut new = old.clone();
+        new["items"]["weapons"]
+            .as_table_mut()
+            .unwrap()
+            .remove("training_sword");
+        new["actions"]["basic_attack"]
+            .as_table_mut()
+            .unwrap()
+            .remove("cooldown");
+        let overrides: Table = toml::from_str(
+            r#"
+            [items.weapons.training_sword.attributes]
+            DMG = 3
+            [actions.basic_attack]
+            cooldown = 2.0
+            [actions.guard]
+            cooldown = 2.0
+            "#,
+        )
+        .unwrap();
+
+        let conflicts = diff_rulesets(&old, &new).override_conflicts(&overrides);
+        let paths: Vec<&str> = conflicts
+            .iter()
+            .map(|conflict| conflict.path.as_str())
+            .collect();
+        assert_eq!(
+            paths,
+            vec![
+                "actions.basic_attack.cooldown",
+                "items.weapons.training_sword"
+            ]
+        );
+    }
+}
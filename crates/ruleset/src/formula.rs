@@ -1,247 +1,436 @@
-use std::collections::BTreeSet;
+use std::{collections::BTreeSet, fmt};
 
-pub fn evaluate_formula<F>(source: &str, resolve: F) -> Option<f32>
+use toml::{Table, Value};
+
+use crate::{RulesetDice, RulesetValidationReport, ruleset_table_at_path};
+
+/// Why a formula failed to parse or evaluate, with the byte offset into the
+/// source where it happened.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FormulaError {
+    pub position: usize,
+    pub kind: FormulaErrorKind,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FormulaErrorKind {
+    UnexpectedEnd,
+    UnexpectedCharacter(char),
+    UnterminatedString,
+    InvalidNumber,
+    InvalidDice(String),
+    UnknownFunction(String),
+    ArgumentCount {
+        function: String,
+        expected: &'static str,
+        found: usize,
+    },
+    /// A text value where a number is needed, e.g. `"undead" + 1`.
+    ExpectedNumber,
+    /// A number where text is compared, e.g. `if(STR > 1, "a", 2) == "a"`.
+    ExpectedText,
+    DivisionByZero,
+    InvalidClampRange,
+    NotFinite,
+}
+
+impl fmt::Display for FormulaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            FormulaErrorKind::UnexpectedEnd => write!(f, "unexpected end of formula")?,
+            FormulaErrorKind::UnexpectedCharacter(ch) => write!(f, "unexpected '{}'", ch)?,
+            FormulaErrorKind::UnterminatedString => write!(f, "unterminated string")?,
+            FormulaErrorKind::InvalidNumber => write!(f, "invalid number")?,
+            FormulaErrorKind::InvalidDice(err) => write!(f, "{}", err)?,
+            FormulaErrorKind::UnknownFunction(name) => write!(f, "unknown function '{}'", name)?,
+            FormulaErrorKind::ArgumentCount {
+                function,
+                expected,
+                found,
+            } => write!(
+                f,
+                "{}() takes {} argument{}, found {}",
+                function,
+                expected,
+                if *expected == "1" { "" } else { "s" },
+                found
+            )?,
+            FormulaErrorKind::ExpectedNumber => write!(f, "expected a number, found text")?,
+            FormulaErrorKind::ExpectedText => write!(f, "expected text, found a number")?,
+            FormulaErrorKind::DivisionByZero => write!(f, "division by zero")?,
+            FormulaErrorKind::InvalidClampRange => {
+                write!(f, "clamp() minimum is above its maximum")?
+            }
+            FormulaErrorKind::NotFinite => write!(f, "result is not a finite number")?,
+        }
+        write!(f, " at column {}", self.position + 1)
+    }
+}
+
+impl std::error::Error for FormulaError {}
+
+/// What a formula reads while it evaluates. Closures `FnMut(&str) -> f32`
+/// implement this for purely numeric formulas.
+pub trait FormulaContext {
+    /// The numeric value of an identifier such as `STR` or `attacker.LEVEL`.
+    fn number(&mut self, name: &str) -> f32;
+    /// The text value of an identifier compared against a string, such as
+    /// `defender.race == "Skeleton"`. Unknown text compares as empty.
+    fn text(&mut self, name: &str) -> Option<String> {
+        let _ = name;
+        None
+    }
+    /// Roll an inline dice term such as `1d6`. Defaults to the average roll.
+    fn roll(&mut self, dice: &RulesetDice) -> f32 {
+        dice.average()
+    }
+}
+
+impl<F> FormulaContext for F
 where
     F: FnMut(&str) -> f32,
 {
-    FormulaParser::new(source, resolve, false).parse()
+    fn number(&mut self, name: &str) -> f32 {
+        self(name)
+    }
+}
+
+/// Evaluate a numeric formula, resolving identifiers through `resolve`.
+/// Dice terms use their average roll.
+pub fn evaluate_formula<F>(source: &str, mut resolve: F) -> Result<f32, FormulaError>
+where
+    F: FnMut(&str) -> f32,
+{
+    evaluate_formula_with(source, &mut resolve)
+}
+
+/// Evaluate a formula against a context that can also provide text values
+/// and roll dice.
+pub fn evaluate_formula_with(
+    source: &str,
+    ctx: &mut dyn FormulaContext,
+) -> Result<f32, FormulaError> {
+    let expr = FormulaParser::new(source).parse()?;
+    Evaluator { ctx, check: false }.finite_number(&expr)
+}
+
+/// Parse a formula and type-check every branch, treating identifiers as 1
+/// and ignoring division by zero and empty clamp ranges, which depend on
+/// runtime values.
+pub fn check_formula(source: &str) -> Result<(), FormulaError> {
+    let expr = FormulaParser::new(source).parse()?;
+    let mut ctx = |_: &str| 1.0f32;
+    Evaluator {
+        ctx: &mut ctx,
+        check: true,
+    }
+    .finite_number(&expr)
+    .map(|_| ())
 }
 
 pub fn formula_is_valid(source: &str) -> bool {
-    FormulaParser::new(source, |_| 1.0, true).parse().is_some()
+    check_formula(source).is_ok()
 }
 
+/// The identifiers a formula reads, skipping function names, keywords and
+/// the contents of strings.
 pub fn formula_identifiers(source: &str) -> BTreeSet<String> {
-    let bytes = source.as_bytes();
+    let mut parser = FormulaParser::new(source);
     let mut identifiers = BTreeSet::new();
-    let mut index = 0;
-    while index < bytes.len() {
-        let ch = bytes[index];
-        if !ch.is_ascii_alphabetic() && ch != b'_' {
-            index += 1;
-            continue;
-        }
-        let start = index;
-        index += 1;
-        while index < bytes.len()
-            && (bytes[index].is_ascii_alphanumeric() || matches!(bytes[index], b'_' | b'.'))
-        {
-            index += 1;
-        }
-        let Ok(identifier) = std::str::from_utf8(&bytes[start..index]) else {
-            continue;
+    loop {
+        parser.skip_whitespace();
+        let Some(&ch) = parser.source.get(parser.index) else {
+            return identifiers;
         };
-        let mut next = index;
-        while next < bytes.len() && bytes[next].is_ascii_whitespace() {
-            next += 1;
+        if ch == b'"' || ch == b'\'' {
+            if parser.parse_string().is_err() {
+                return identifiers;
+            }
+        } else if ch.is_ascii_digit() || ch == b'.' {
+            let _ = parser.parse_number();
+        } else if ch.is_ascii_alphabetic() || ch == b'_' {
+            let identifier = parser.parse_identifier();
+            parser.skip_whitespace();
+            if parser.source.get(parser.index) != Some(&b'(')
+                && !matches!(identifier.as_str(), "true" | "false")
+            {
+                identifiers.insert(identifier);
+            }
+        } else {
+            parser.index += 1;
         }
-        if next < bytes.len() && bytes[next] == b'(' {
-            continue;
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum BinaryOp {
+    Or,
+    And,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum ExprKind {
+    Number(f32),
+    Dice(RulesetDice),
+    Text(String),
+    Identifier(String),
+    Negate(Box<Expr>),
+    Not(Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+    /// `cond ? then : else` and `if(cond, then, else)`.
+    Conditional(Box<Expr>, Box<Expr>, Box<Expr>),
+    Call(String, Vec<Expr>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Expr {
+    position: usize,
+    kind: ExprKind,
+}
+
+impl Expr {
+    fn new(position: usize, kind: ExprKind) -> Self {
+        Self { position, kind }
+    }
+
+    /// Whether the expression yields text, which makes `==`/`!=` compare
+    /// strings instead of numbers.
+    fn is_text(&self) -> bool {
+        match &self.kind {
+            ExprKind::Text(_) => true,
+            ExprKind::Conditional(_, then, otherwise) => then.is_text() || otherwise.is_text(),
+            _ => false,
         }
-        identifiers.insert(identifier.to_string());
     }
-    identifiers
 }
 
-struct FormulaParser<'a, F>
-where
-    F: FnMut(&str) -> f32,
-{
+fn error(position: usize, kind: FormulaErrorKind) -> FormulaError {
+    FormulaError { position, kind }
+}
+
+struct FormulaParser<'a> {
     source: &'a [u8],
     index: usize,
-    resolve: F,
-    validate_only: bool,
 }
 
-impl<'a, F> FormulaParser<'a, F>
-where
-    F: FnMut(&str) -> f32,
-{
-    fn new(source: &'a str, resolve: F, validate_only: bool) -> Self {
+impl<'a> FormulaParser<'a> {
+    fn new(source: &'a str) -> Self {
         Self {
             source: source.as_bytes(),
             index: 0,
-            resolve,
-            validate_only,
         }
     }
 
-    fn parse(mut self) -> Option<f32> {
-        let value = self.parse_or()?;
+    fn parse(mut self) -> Result<Expr, FormulaError> {
+        let expr = self.parse_conditional()?;
         self.skip_whitespace();
-        (self.index == self.source.len() && value.is_finite()).then_some(value)
+        match self.peek() {
+            None => Ok(expr),
+            Some(ch) => Err(self.unexpected(ch)),
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.source.get(self.index).copied()
+    }
+
+    fn unexpected(&self, ch: u8) -> FormulaError {
+        error(
+            self.index,
+            FormulaErrorKind::UnexpectedCharacter(ch as char),
+        )
     }
 
     fn skip_whitespace(&mut self) {
-        while self.index < self.source.len() && self.source[self.index].is_ascii_whitespace() {
+        while self.peek().is_some_and(|ch| ch.is_ascii_whitespace()) {
             self.index += 1;
         }
     }
 
-    fn consume(&mut self, ch: u8) -> bool {
+    fn consume(&mut self, token: &str) -> bool {
         self.skip_whitespace();
-        if self.index < self.source.len() && self.source[self.index] == ch {
-            self.index += 1;
+        if self.source[self.index..].starts_with(token.as_bytes()) {
+            self.index += token.len();
             true
         } else {
             false
         }
     }
 
-    fn parse_or(&mut self) -> Option<f32> {
-        let mut value = self.parse_and()?;
-        loop {
-            self.skip_whitespace();
-            if self.index + 1 < self.source.len()
-                && self.source[self.index] == b'|'
-                && self.source[self.index + 1] == b'|'
-            {
-                self.index += 2;
-                let rhs = self.parse_and()?;
-                value = (value != 0.0 || rhs != 0.0) as u8 as f32;
-            } else {
-                return Some(value);
+    fn expect(&mut self, ch: u8) -> Result<(), FormulaError> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(found) if found == ch => {
+                self.index += 1;
+                Ok(())
             }
+            Some(found) => Err(self.unexpected(found)),
+            None => Err(error(self.index, FormulaErrorKind::UnexpectedEnd)),
         }
     }
 
-    fn parse_and(&mut self) -> Option<f32> {
-        let mut value = self.parse_comparison()?;
-        loop {
-            self.skip_whitespace();
-            if self.index + 1 < self.source.len()
-                && self.source[self.index] == b'&'
-                && self.source[self.index + 1] == b'&'
-            {
-                self.index += 2;
-                let rhs = self.parse_comparison()?;
-                value = (value != 0.0 && rhs != 0.0) as u8 as f32;
-            } else {
-                return Some(value);
-            }
+    fn parse_conditional(&mut self) -> Result<Expr, FormulaError> {
+        let condition = self.parse_or()?;
+        if !self.consume("?") {
+            return Ok(condition);
         }
+        let then = self.parse_conditional()?;
+        self.expect(b':')?;
+        let otherwise = self.parse_conditional()?;
+        Ok(Expr::new(
+            condition.position,
+            ExprKind::Conditional(Box::new(condition), Box::new(then), Box::new(otherwise)),
+        ))
     }
 
-    fn parse_comparison(&mut self) -> Option<f32> {
-        let mut value = self.parse_expression()?;
-        loop {
-            self.skip_whitespace();
-            let pair = (self.index + 1 < self.source.len())
-                .then(|| (self.source[self.index], self.source[self.index + 1]));
-            let result = match pair {
-                Some((b'=', b'=')) => {
-                    self.index += 2;
-                    Some(((value - self.parse_expression()?).abs() <= f32::EPSILON) as u8 as f32)
-                }
-                Some((b'!', b'=')) => {
-                    self.index += 2;
-                    Some(((value - self.parse_expression()?).abs() > f32::EPSILON) as u8 as f32)
-                }
-                Some((b'<', b'=')) => {
-                    self.index += 2;
-                    Some((value <= self.parse_expression()?) as u8 as f32)
+    fn parse_binary(
+        &mut self,
+        operators: &[(&str, BinaryOp)],
+        next: fn(&mut Self) -> Result<Expr, FormulaError>,
+    ) -> Result<Expr, FormulaError> {
+        let mut lhs = next(self)?;
+        'operators: loop {
+            for (token, op) in operators {
+                if self.consume(token) {
+                    let rhs = next(self)?;
+                    lhs = Expr::new(
+                        lhs.position,
+                        ExprKind::Binary(*op, Box::new(lhs), Box::new(rhs)),
+                    );
+                    continue 'operators;
                 }
-                Some((b'>', b'=')) => {
-                    self.index += 2;
-                    Some((value >= self.parse_expression()?) as u8 as f32)
-                }
-                _ if self.consume(b'<') => Some((value < self.parse_expression()?) as u8 as f32),
-                _ if self.consume(b'>') => Some((value > self.parse_expression()?) as u8 as f32),
-                _ => None,
-            };
-            let Some(result) = result else {
-                return Some(value);
-            };
-            value = result;
+            }
+            return Ok(lhs);
         }
     }
 
-    fn parse_expression(&mut self) -> Option<f32> {
-        let mut value = self.parse_term()?;
-        loop {
-            if self.consume(b'+') {
-                value += self.parse_term()?;
-            } else if self.consume(b'-') {
-                value -= self.parse_term()?;
-            } else {
-                return Some(value);
-            }
-        }
+    fn parse_or(&mut self) -> Result<Expr, FormulaError> {
+        self.parse_binary(&[("||", BinaryOp::Or)], Self::parse_and)
     }
 
-    fn parse_term(&mut self) -> Option<f32> {
-        let mut value = self.parse_factor()?;
-        loop {
-            if self.consume(b'*') {
-                value *= self.parse_factor()?;
-            } else if self.consume(b'/') {
-                let divisor = self.parse_factor()?;
-                if divisor.abs() <= f32::EPSILON {
-                    if self.validate_only {
-                        value = 0.0;
-                        continue;
-                    }
-                    return None;
-                }
-                value /= divisor;
-            } else {
-                return Some(value);
-            }
-        }
+    fn parse_and(&mut self) -> Result<Expr, FormulaError> {
+        self.parse_binary(&[("&&", BinaryOp::And)], Self::parse_comparison)
+    }
+
+    fn parse_comparison(&mut self) -> Result<Expr, FormulaError> {
+        // Two-character operators first so `<=` is not read as `<`.
+        self.parse_binary(
+            &[
+                ("==", BinaryOp::Eq),
+                ("!=", BinaryOp::Ne),
+                ("<=", BinaryOp::Le),
+                (">=", BinaryOp::Ge),
+                ("<", BinaryOp::Lt),
+                (">", BinaryOp::Gt),
+            ],
+            Self::parse_expression,
+        )
+    }
+
+    fn parse_expression(&mut self) -> Result<Expr, FormulaError> {
+        self.parse_binary(
+            &[("+", BinaryOp::Add), ("-", BinaryOp::Sub)],
+            Self::parse_term,
+        )
+    }
+
+    fn parse_term(&mut self) -> Result<Expr, FormulaError> {
+        self.parse_binary(
+            &[("*", BinaryOp::Mul), ("/", BinaryOp::Div)],
+            Self::parse_factor,
+        )
     }
 
-    fn parse_factor(&mut self) -> Option<f32> {
-        if self.consume(b'+') {
+    fn parse_factor(&mut self) -> Result<Expr, FormulaError> {
+        self.skip_whitespace();
+        let position = self.index;
+        if self.consume("+") {
             return self.parse_factor();
         }
-        if self.consume(b'-') {
-            return self.parse_factor().map(|value| -value);
+        if self.consume("-") {
+            let value = self.parse_factor()?;
+            return Ok(Expr::new(position, ExprKind::Negate(Box::new(value))));
+        }
+        if !self.source[self.index..].starts_with(b"!=") && self.consume("!") {
+            let value = self.parse_factor()?;
+            return Ok(Expr::new(position, ExprKind::Not(Box::new(value))));
         }
         self.parse_primary()
     }
 
-    fn parse_primary(&mut self) -> Option<f32> {
+    fn parse_primary(&mut self) -> Result<Expr, FormulaError> {
         self.skip_whitespace();
-        if self.consume(b'(') {
-            let value = self.parse_or()?;
-            return self.consume(b')').then_some(value);
+        let position = self.index;
+        let Some(ch) = self.peek() else {
+            return Err(error(position, FormulaErrorKind::UnexpectedEnd));
+        };
+        if ch == b'(' {
+            self.index += 1;
+            let mut value = self.parse_conditional()?;
+            self.expect(b')')?;
+            value.position = position;
+            return Ok(value);
+        }
+        if ch == b'"' || ch == b'\'' {
+            return Ok(Expr::new(position, ExprKind::Text(self.parse_string()?)));
         }
-        let ch = *self.source.get(self.index)?;
         if ch.is_ascii_digit() || ch == b'.' {
             return self.parse_number();
         }
         if ch.is_ascii_alphabetic() || ch == b'_' {
-            let identifier = self.parse_identifier()?;
-            if self.consume(b'(') {
-                let value = self.parse_call(&identifier)?;
-                return self.consume(b')').then_some(value);
+            let identifier = self.parse_identifier();
+            if self.consume("(") {
+                return self.parse_call(position, identifier);
             }
-            return Some((self.resolve)(&identifier));
+            return Ok(Expr::new(
+                position,
+                match identifier.as_str() {
+                    "true" => ExprKind::Number(1.0),
+                    "false" => ExprKind::Number(0.0),
+                    _ => ExprKind::Identifier(identifier),
+                },
+            ));
         }
-        None
+        Err(self.unexpected(ch))
     }
 
-    fn parse_identifier(&mut self) -> Option<String> {
-        self.skip_whitespace();
+    fn parse_identifier(&mut self) -> String {
         let start = self.index;
-        while self.index < self.source.len()
-            && (self.source[self.index].is_ascii_alphanumeric()
-                || matches!(self.source[self.index], b'_' | b'.'))
+        while self
+            .peek()
+            .is_some_and(|ch| ch.is_ascii_alphanumeric() || matches!(ch, b'_' | b'.'))
         {
             self.index += 1;
         }
-        (self.index > start)
-            .then(|| std::str::from_utf8(&self.source[start..self.index]).ok())
-            .flatten()
-            .map(ToString::to_string)
+        String::from_utf8_lossy(&self.source[start..self.index]).into_owned()
     }
 
-    fn parse_number(&mut self) -> Option<f32> {
-        self.skip_whitespace();
+    fn parse_string(&mut self) -> Result<String, FormulaError> {
+        let start = self.index;
+        let quote = self.source[start];
+        let Some(length) = self.source[start + 1..].iter().position(|ch| *ch == quote) else {
+            self.index = self.source.len();
+            return Err(error(start, FormulaErrorKind::UnterminatedString));
+        };
+        self.index = start + 1 + length + 1;
+        Ok(String::from_utf8_lossy(&self.source[start + 1..start + 1 + length]).into_owned())
+    }
+
+    /// A number, or a dice term like `2d6` when digits run into a `d`.
+    fn parse_number(&mut self) -> Result<Expr, FormulaError> {
         let start = self.index;
         let mut seen_dot = false;
-        while self.index < self.source.len() {
-            let ch = self.source[self.index];
+        while let Some(ch) = self.peek() {
             if ch.is_ascii_digit() {
                 self.index += 1;
             } else if ch == b'.' && !seen_dot {
@@ -251,45 +440,251 @@ where
                 break;
             }
         }
-        std::str::from_utf8(&self.source[start..self.index])
-            .ok()?
+        let number = std::str::from_utf8(&self.source[start..self.index]).unwrap_or_default();
+        if !seen_dot && matches!(self.peek(), Some(b'd' | b'D')) {
+            self.index += 1;
+            while self.peek().is_some_and(|ch| ch.is_ascii_alphanumeric()) {
+                self.index += 1;
+            }
+            let term = std::str::from_utf8(&self.source[start..self.index]).unwrap_or_default();
+            return crate::parse_ruleset_dice(term)
+                .map(|dice| Expr::new(start, ExprKind::Dice(dice)))
+                .map_err(|err| error(start, FormulaErrorKind::InvalidDice(err)));
+        }
+        number
             .parse()
-            .ok()
+            .map(|value| Expr::new(start, ExprKind::Number(value)))
+            .map_err(|_| error(start, FormulaErrorKind::InvalidNumber))
     }
 
-    fn parse_arguments(&mut self) -> Option<Vec<f32>> {
+    fn parse_call(&mut self, position: usize, function: String) -> Result<Expr, FormulaError> {
         let mut arguments = Vec::new();
-        self.skip_whitespace();
-        if self.source.get(self.index) == Some(&b')') {
-            return Some(arguments);
+        if !self.consume(")") {
+            loop {
+                arguments.push(self.parse_conditional()?);
+                if self.consume(",") {
+                    continue;
+                }
+                self.expect(b')')?;
+                break;
+            }
+        }
+        let expected = match function.as_str() {
+            "min" | "max" => (arguments.len() < 2).then_some("at least 2"),
+            "clamp" | "if" => (arguments.len() != 3).then_some("3"),
+            "abs" | "floor" | "ceil" | "round" => (arguments.len() != 1).then_some("1"),
+            _ => return Err(error(position, FormulaErrorKind::UnknownFunction(function))),
+        };
+        if let Some(expected) = expected {
+            return Err(error(
+                position,
+                FormulaErrorKind::ArgumentCount {
+                    function,
+                    expected,
+                    found: arguments.len(),
+                },
+            ));
+        }
+        if function == "if" {
+            let mut arguments = arguments.into_iter().map(Box::new);
+            let (Some(condition), Some(then), Some(otherwise)) =
+                (arguments.next(), arguments.next(), arguments.next())
+            else {
+                unreachable!("if() arity is checked above");
+            };
+            return Ok(Expr::new(
+                position,
+                ExprKind::Conditional(condition, then, otherwise),
+            ));
+        }
+        Ok(Expr::new(position, ExprKind::Call(function, arguments)))
+    }
+}
+
+struct Evaluator<'a> {
+    ctx: &'a mut dyn FormulaContext,
+    /// Validation mode: evaluate every branch and tolerate runtime-only
+    /// failures.
+    check: bool,
+}
+
+impl Evaluator<'_> {
+    fn finite_number(&mut self, expr: &Expr) -> Result<f32, FormulaError> {
+        let value = self.number(expr)?;
+        if value.is_finite() {
+            Ok(value)
+        } else {
+            Err(error(expr.position, FormulaErrorKind::NotFinite))
         }
-        loop {
-            arguments.push(self.parse_expression()?);
-            if !self.consume(b',') {
-                return Some(arguments);
+    }
+
+    fn condition(&mut self, condition: &Expr) -> Result<Option<bool>, FormulaError> {
+        let value = self.number(condition)? != 0.0;
+        Ok((!self.check).then_some(value))
+    }
+
+    fn number(&mut self, expr: &Expr) -> Result<f32, FormulaError> {
+        let bool_value = |value: bool| value as u8 as f32;
+        match &expr.kind {
+            ExprKind::Number(value) => Ok(*value),
+            ExprKind::Dice(dice) => Ok(self.ctx.roll(dice)),
+            ExprKind::Text(_) => Err(error(expr.position, FormulaErrorKind::ExpectedNumber)),
+            ExprKind::Identifier(name) => Ok(self.ctx.number(name)),
+            ExprKind::Negate(value) => Ok(-self.number(value)?),
+            ExprKind::Not(value) => Ok(bool_value(self.number(value)? == 0.0)),
+            ExprKind::Conditional(condition, then, otherwise) => {
+                match self.condition(condition)? {
+                    Some(true) => self.number(then),
+                    Some(false) => self.number(otherwise),
+                    None => {
+                        self.number(then)?;
+                        self.number(otherwise)
+                    }
+                }
+            }
+            ExprKind::Binary(op @ (BinaryOp::Eq | BinaryOp::Ne), lhs, rhs)
+                if lhs.is_text() || rhs.is_text() =>
+            {
+                let equal = self.text(lhs)?.eq_ignore_ascii_case(&self.text(rhs)?);
+                Ok(bool_value(equal == (*op == BinaryOp::Eq)))
+            }
+            ExprKind::Binary(op, lhs, rhs) => {
+                let lhs_value = self.number(lhs)?;
+                // Short-circuit so the untaken side does not roll its dice.
+                match op {
+                    BinaryOp::Or if lhs_value != 0.0 && !self.check => return Ok(1.0),
+                    BinaryOp::And if lhs_value == 0.0 && !self.check => return Ok(0.0),
+                    _ => {}
+                }
+                let rhs_value = self.number(rhs)?;
+                Ok(match op {
+                    BinaryOp::Or => bool_value(lhs_value != 0.0 || rhs_value != 0.0),
+                    BinaryOp::And => bool_value(lhs_value != 0.0 && rhs_value != 0.0),
+                    BinaryOp::Eq => bool_value((lhs_value - rhs_value).abs() <= f32::EPSILON),
+                    BinaryOp::Ne => bool_value((lhs_value - rhs_value).abs() > f32::EPSILON),
+                    BinaryOp::Lt => bool_value(lhs_value < rhs_value),
+                    BinaryOp::Le => bool_value(lhs_value <= rhs_value),
+                    BinaryOp::Gt => bool_value(lhs_value > rhs_value),
+                    BinaryOp::Ge => bool_value(lhs_value >= rhs_value),
+                    BinaryOp::Add => lhs_value + rhs_value,
+                    BinaryOp::Sub => lhs_value - rhs_value,
+                    BinaryOp::Mul => lhs_value * rhs_value,
+                    BinaryOp::Div if rhs_value.abs() <= f32::EPSILON => {
+                        if self.check {
+                            0.0
+                        } else {
+                            return Err(error(rhs.position, FormulaErrorKind::DivisionByZero));
+                        }
+                    }
+                    BinaryOp::Div => lhs_value / rhs_value,
+                })
+            }
+            ExprKind::Call(function, arguments) => {
+                let arguments = arguments
+                    .iter()
+                    .map(|argument| self.number(argument))
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(match function.as_str() {
+                    "min" => arguments.into_iter().fold(f32::INFINITY, f32::min),
+                    "max" => arguments.into_iter().fold(f32::NEG_INFINITY, f32::max),
+                    "clamp" if arguments[1] <= arguments[2] => {
+                        arguments[0].clamp(arguments[1], arguments[2])
+                    }
+                    "clamp" if self.check => arguments[0],
+                    "clamp" => {
+                        return Err(error(expr.position, FormulaErrorKind::InvalidClampRange));
+                    }
+                    "abs" => arguments[0].abs(),
+                    "floor" => arguments[0].floor(),
+                    "ceil" => arguments[0].ceil(),
+                    _ => arguments[0].round(),
+                })
             }
         }
     }
 
-    fn parse_call(&mut self, identifier: &str) -> Option<f32> {
-        let arguments = self.parse_arguments()?;
-        match identifier {
-            "min" if arguments.len() == 2 => Some(arguments[0].min(arguments[1])),
-            "max" if arguments.len() == 2 => Some(arguments[0].max(arguments[1])),
-            "clamp" if arguments.len() == 3 => {
-                if arguments[1] <= arguments[2] {
-                    Some(arguments[0].clamp(arguments[1], arguments[2]))
-                } else if self.validate_only {
-                    Some(arguments[0])
-                } else {
-                    None
+    fn text(&mut self, expr: &Expr) -> Result<String, FormulaError> {
+        match &expr.kind {
+            ExprKind::Text(text) => Ok(text.clone()),
+            ExprKind::Identifier(name) => Ok(self.ctx.text(name).unwrap_or_default()),
+            ExprKind::Conditional(condition, then, otherwise) => {
+                match self.condition(condition)? {
+                    Some(true) => self.text(then),
+                    Some(false) => self.text(otherwise),
+                    None => {
+                        self.text(then)?;
+                        self.text(otherwise)
+                    }
                 }
             }
-            "abs" if arguments.len() == 1 => Some(arguments[0].abs()),
-            "floor" if arguments.len() == 1 => Some(arguments[0].floor()),
-            "ceil" if arguments.len() == 1 => Some(arguments[0].ceil()),
-            "round" if arguments.len() == 1 => Some(arguments[0].round()),
-            _ => None,
+            _ => Err(error(expr.position, FormulaErrorKind::ExpectedText)),
+        }
+    }
+}
+
+const COMBAT_FORMULA_KEYS: [&str; 3] = ["outgoing_damage", "incoming_damage", "received_damage"];
+
+/// Check every formula field that has no resolver of its own: combat damage
+/// stages, kill XP, and progression gains and XP curves, in the ruleset root
+/// and every race and class.
+pub(crate) fn validate_formula_rules(report: &mut RulesetValidationReport, root: &Table) {
+    let mut roots = vec![(String::new(), root)];
+    for section in ["races", "classes"] {
+        if let Some(entries) = ruleset_table_at_path(root, &[section]) {
+            for (id, entry) in entries {
+                if let Some(entry) = entry.as_table() {
+                    roots.push((format!("{}.{}.", section, id), entry));
+                }
+            }
+        }
+    }
+
+    let mut fields = Vec::new();
+    for (prefix, table) in roots {
+        if let Some(combat) = ruleset_table_at_path(table, &["combat"]) {
+            for key in COMBAT_FORMULA_KEYS {
+                fields.push((format!("{}combat.{}", prefix, key), combat.get(key)));
+            }
+            if let Some(kinds) = ruleset_table_at_path(combat, &["kinds"]) {
+                for (kind, kind_table) in kinds {
+                    let Some(kind_table) = kind_table.as_table() else {
+                        continue;
+                    };
+                    for key in COMBAT_FORMULA_KEYS {
+                        fields.push((
+                            format!("{}combat.kinds.{}.{}", prefix, kind, key),
+                            kind_table.get(key),
+                        ));
+                    }
+                }
+            }
+        }
+        let Some(progression) = ruleset_table_at_path(table, &["progression"]) else {
+            continue;
+        };
+        for (stat, stat_table) in progression {
+            let Some(stat_table) = stat_table.as_table() else {
+                continue;
+            };
+            let keys: &[&str] = if stat == "xp" {
+                &["kill"]
+            } else {
+                &["gain", "xp_for_level"]
+            };
+            for key in keys {
+                fields.push((
+                    format!("{}progression.{}.{}", prefix, stat, key),
+                    stat_table.get(*key),
+                ));
+            }
+        }
+    }
+
+    for (path, value) in fields {
+        if let Some(Value::String(formula)) = value
+            && let Err(err) = check_formula(formula)
+        {
+            report.error(path, format!("Formula '{}' is invalid: {}.", formula, err));
         }
     }
 }
@@ -308,13 +703,133 @@ mod tests {
                 _ => 0.0,
             },
         );
-        assert_eq!(value, Some(12.0));
+        assert_eq!(value, Ok(12.0));
         assert_eq!(
             formula_identifiers("base + max(POWER, WIS)"),
             BTreeSet::from(["POWER".into(), "WIS".into(), "base".into()])
         );
         assert!(formula_is_valid("1 / (CURRENT - LIMIT)"));
         assert!(!formula_is_valid("missing(1)"));
-        assert_eq!(evaluate_formula("1 / (CURRENT - LIMIT)", |_| 1.0), None);
+        assert_eq!(
+            evaluate_formula("1 / (CURRENT - LIMIT)", |_| 1.0),
+            Err(FormulaError {
+                position: 4,
+                kind: FormulaErrorKind::DivisionByZero,
+            })
+        );
+    }
+
+    struct Fighter {
+        rolls: Vec<RulesetDice>,
+    }
+
+    impl FormulaContext for Fighter {
+        fn number(&mut self, name: &str) -> f32 {
+            match name {
+                "value" => 10.0,
+                "attacker.STR" => 16.0,
+                _ => 0.0,
+            }
+        }
+
+        fn text(&mut self, name: &str) -> Option<String> {
+            (name == "defender.race").then(|| "Skeleton".into())
+        }
+
+        fn roll(&mut self, dice: &RulesetDice) -> f32 {
+            self.rolls.push(dice.clone());
+            dice.maximum()
+        }
+    }
+
+    #[test]
+    fn evaluates_conditionals_dice_and_text_comparisons() {
+        let mut fighter = Fighter { rolls: Vec::new() };
+        let mut eval = |source: &str| evaluate_formula_with(source, &mut fighter);
+        assert_eq!(
+            eval("defender.race == 'skeleton' ? value * 2 : value"),
+            Ok(20.0)
+        );
+        assert_eq!(eval("value + if(attacker.STR > 15, 1d6, 0)"), Ok(16.0));
+        assert_eq!(eval("value + (attacker.STR > 20 && 2d4)"), Ok(10.0));
+        assert_eq!(eval("defender.race != \"Orc\" && !false"), Ok(1.0));
+        assert_eq!(
+            fighter.rolls,
+            vec![RulesetDice { count: 1, sides: 6 }],
+            "untaken branches must not roll"
+        );
+
+        // Plain closures see dice at their average roll.
+        assert_eq!(evaluate_formula("2d6 + 1", |_| 0.0), Ok(8.0));
+        assert_eq!(
+            formula_identifiers("defender.race == \"Orc\" ? 1d6 : floor(STR)"),
+            BTreeSet::from(["STR".into(), "defender.race".into()])
+        );
+    }
+
+    #[test]
+    fn reports_errors_with_positions() {
+        let err = |source: &str| check_formula(source).unwrap_err();
+        assert_eq!(err("1 +").kind, FormulaErrorKind::UnexpectedEnd);
+        assert_eq!(err("1 +").position, 3);
+        assert_eq!(err("base $ 2").position, 5);
+        assert_eq!(
+            err("value + roll(2)").kind,
+            FormulaErrorKind::UnknownFunction("roll".into())
+        );
+        assert_eq!(
+            err("floor(1, 2)").to_string(),
+            "floor() takes 1 argument, found 2 at column 1"
+        );
+        assert_eq!(err("2 + 'undead'").position, 4);
+        assert_eq!(
+            err("STR > 1 ? 'a' : 2").kind,
+            FormulaErrorKind::ExpectedNumber
+        );
+        assert_eq!(
+            err("value + 0d6").kind,
+            FormulaErrorKind::InvalidDice("Dice roll '0d6' must use positive values".into())
+        );
+        assert_eq!(
+            err("race == 'orc").kind,
+            FormulaErrorKind::UnterminatedString
+        );
+        assert!(formula_is_valid("clamp(value, MAX, MIN)"));
+    }
+
+    #[test]
+    fn validation_checks_combat_and_progression_formulas() {
+        let mut rules = crate::parse_ruleset_table(crate::latest_official_ruleset()).unwrap();
+        let overlay = crate::parse_ruleset_table(
+            r#"
+            [combat.kinds.holy]
+            outgoing_damage = "defender.race == 'Skeleton' ? value * 2 : value"
+            [classes.Warrior.combat]
+            outgoing_damage = "value + if(attacker.STR > 15, 1d6)"
+            [races.Orc.progression.xp]
+            kill = "10 * (defender.LEVEL"
+            "#,
+        )
+        .unwrap();
+        crate::merge_toml_tables(&mut rules, overlay);
+
+        let report = crate::validate_ruleset(&rules);
+        let message = |path: &str| {
+            report
+                .issues
+                .iter()
+                .find(|issue| issue.path == path)
+                .map(|issue| issue.message.clone())
+        };
+        assert_eq!(message("combat.kinds.holy.outgoing_damage"), None);
+        assert!(
+            message("classes.Warrior.combat.outgoing_damage").is_some_and(
+                |message| message.contains("if() takes 3 arguments, found 2 at column 9")
+            )
+        );
+        assert!(
+            message("races.Orc.progression.xp.kill")
+                .is_some_and(|message| message.contains("unexpected end of formula at column 21"))
+        );
     }
 }
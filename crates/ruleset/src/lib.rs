@@ -6,8 +6,37 @@ use theframework::prelude::{TheColor, ThePalette};
 use toml::{Table, Value};
 
 pub mod cli;
+mod combat;
+mod diff;
 mod formula;
-pub use formula::{evaluate_formula, formula_identifiers, formula_is_valid};
+mod loot;
+mod migration;
+mod rng;
+pub use combat::{
+    CombatLoadout, Combatant, DamageContext, DamageStage, DuelOptions, DuelReport, DuelSideReport,
+    RulesetCritical, SimulatedCombatant, WeaponDamage, apply_item_quality_to_damage,
+    combat_attribute_bonus, combat_kind_table, combat_text_variable, combat_variable,
+    evaluate_damage_stage, item_quality_damage_multiplier, progression_stat, resolve_critical,
+    resolve_damage, roll_attack_damage, roll_damage_table, simulate_duels,
+};
+pub use diff::{
+    RulesetDiff, RulesetEntryChange, RulesetFieldChange, RulesetFieldChangeKind,
+    RulesetOverrideConflict, RulesetVersionInfo, diff_rulesets,
+};
+pub use formula::{
+    FormulaContext, FormulaError, FormulaErrorKind, check_formula, evaluate_formula,
+    evaluate_formula_with, formula_identifiers, formula_is_valid,
+};
+pub use loot::{
+    LootDrop, LootItemSample, LootSample, ResolvedLootEntry, ResolvedLootRarity,
+    ResolvedLootSource, ResolvedLootTable, RulesetLootQuantity, identity_loot_tables,
+    resolve_loot_rarities, resolve_loot_table, resolve_loot_tables, roll_loot_table,
+    ruleset_loot_table_refs, sample_loot_table,
+};
+pub use migration::{
+    RULESET_MIGRATIONS, RulesetMigration, RulesetMigrationReport, migrate_ruleset_override,
+    rename_ruleset_key, ruleset_override_schema_version,
+};
 
 pub const OFFICIAL_RULESET_ID: &str = "eldiron.official";
 pub const OFFICIAL_RULESET_VERSION: &str = "1.0.0";
@@ -45,6 +74,9 @@ const OFFICIAL_ELDIRON_V1_INVOCATIONS: &str =
 const OFFICIAL_ELDIRON_V1_CONDITIONS: &str = include_str!("../rulesets/eldiron/v1/conditions.toml");
 const OFFICIAL_ELDIRON_V1_ACTIONS: &str = include_str!("../rulesets/eldiron/v1/actions.toml");
 const OFFICIAL_ELDIRON_V1_RECIPES: &str = include_str!("../rulesets/eldiron/v1/recipes.toml");
+const OFFICIAL_ELDIRON_V1_QUESTS: &str = include_str!("../rulesets/eldiron/v1/quests.toml");
+const OFFICIAL_ELDIRON_V1_SHOPS: &str = include_str!("../rulesets/eldiron/v1/shops.toml");
+const OFFICIAL_ELDIRON_V1_LOOT: &str = include_str!("../rulesets/eldiron/v1/loot.toml");
 const OFFICIAL_ELDIRON_V1_ABILITIES_SPELLS: &str =
     include_str!("../rulesets/eldiron/v1/abilities_spells.toml");
 const OFFICIAL_ELDIRON_V1_RACES_CLASSES: &str =
@@ -73,6 +105,9 @@ static OFFICIAL_ELDIRON_V1: LazyLock<String> = LazyLock::new(|| {
         OFFICIAL_ELDIRON_V1_CONDITIONS,
         OFFICIAL_ELDIRON_V1_ACTIONS,
         OFFICIAL_ELDIRON_V1_RECIPES,
+        OFFICIAL_ELDIRON_V1_QUESTS,
+        OFFICIAL_ELDIRON_V1_SHOPS,
+        OFFICIAL_ELDIRON_V1_LOOT,
         OFFICIAL_ELDIRON_V1_ABILITIES_SPELLS,
         OFFICIAL_ELDIRON_V1_RACES_CLASSES,
     ]
@@ -266,6 +301,22 @@ impl ResolvedRuleset {
         resolve_derived_stats(&self.table)
     }
 
+    pub fn quest(&self, quest_id: &str) -> Result<Option<ResolvedQuest>, String> {
+        resolve_quest(&self.table, quest_id)
+    }
+
+    pub fn quests(&self) -> Result<BTreeMap<String, ResolvedQuest>, String> {
+        resolve_quests(&self.table)
+    }
+
+    pub fn shop(&self, shop_id: &str) -> Result<Option<ResolvedShop>, String> {
+        resolve_shop(&self.table, shop_id)
+    }
+
+    pub fn shops(&self) -> Result<BTreeMap<String, ResolvedShop>, String> {
+        resolve_shops(&self.table)
+    }
+
     pub fn identity_defaults(&self) -> Result<ResolvedIdentityDefaults, String> {
         resolve_identity_defaults(&self.table)
     }
@@ -1253,8 +1304,8 @@ impl ResolvedDerivedStat {
         let path = format!("derived_stats.{}", stat_id);
         let formula = table_string(table, "formula")
             .ok_or_else(|| format!("{}.formula is required.", path))?;
-        if !formula_is_valid(&formula) {
-            return Err(format!("{}.formula is invalid.", path));
+        if let Err(err) = check_formula(&formula) {
+            return Err(format!("{}.formula is invalid: {}.", path, err));
         }
         let minimum = optional_action_effect_number(table, "minimum", &path)?;
         let maximum = optional_action_effect_number(table, "maximum", &path)?;
@@ -3135,6 +3186,447 @@ pub fn resolve_class_resource_gains(
     Ok(resolved)
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ResolvedQuestObjectiveKind {
+    Kill,
+    Collect,
+    Reach,
+    Talk,
+}
+
+impl ResolvedQuestObjectiveKind {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "kill" => Some(Self::Kill),
+            "collect" => Some(Self::Collect),
+            "reach" => Some(Self::Reach),
+            "talk" => Some(Self::Talk),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Kill => "kill",
+            Self::Collect => "collect",
+            Self::Reach => "reach",
+            Self::Talk => "talk",
+        }
+    }
+
+    /// The objective key naming the target: a class, item, sector, or NPC.
+    fn target_key(self) -> &'static str {
+        match self {
+            Self::Kill => "class",
+            Self::Collect => "item",
+            Self::Reach => "sector",
+            Self::Talk => "npc",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ResolvedQuestObjective {
+    pub kind: ResolvedQuestObjectiveKind,
+    pub target: String,
+    pub count: u32,
+    /// Collected items are removed from the inventory when the quest completes.
+    pub consume: bool,
+    pub description: Option<String>,
+}
+
+impl ResolvedQuestObjective {
+    /// Journal text, the authored description or one derived from the kind.
+    pub fn label(&self) -> String {
+        if let Some(description) = &self.description {
+            return description.clone();
+        }
+        match self.kind {
+            ResolvedQuestObjectiveKind::Kill => format!("Defeat {} {}", self.count, self.target),
+            ResolvedQuestObjectiveKind::Collect => {
+                format!("Collect {} {}", self.count, self.target)
+            }
+            ResolvedQuestObjectiveKind::Reach => format!("Reach {}", self.target),
+            ResolvedQuestObjectiveKind::Talk => format!("Talk to {}", self.target),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ResolvedQuestStage {
+    pub id: String,
+    pub description: Option<String>,
+    pub objectives: Vec<ResolvedQuestObjective>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+pub struct ResolvedQuestRewards {
+    pub xp: i32,
+    pub currency: i64,
+    pub items: Vec<(String, u32)>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ResolvedQuest {
+    pub id: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub stages: Vec<ResolvedQuestStage>,
+    pub rewards: ResolvedQuestRewards,
+}
+
+impl ResolvedQuest {
+    pub fn stage_index(&self, stage_id: &str) -> Option<usize> {
+        self.stages
+            .iter()
+            .position(|stage| stage.id.eq_ignore_ascii_case(stage_id.trim()))
+    }
+}
+
+fn resolve_quest_objective(path: &str, value: &Value) -> Result<ResolvedQuestObjective, String> {
+    let objective = value
+        .as_table()
+        .ok_or_else(|| format!("{} must be a table.", path))?;
+    let kind_name = table_string(objective, "kind")
+        .ok_or_else(|| format!("{}.kind must be a non-empty string.", path))?;
+    let kind = ResolvedQuestObjectiveKind::parse(&kind_name).ok_or_else(|| {
+        format!(
+            "{}.kind '{}' must be kill, collect, reach, or talk.",
+            path, kind_name
+        )
+    })?;
+    let target = table_string(objective, kind.target_key())
+        .ok_or_else(|| format!("{}.{} must be a non-empty string.", path, kind.target_key()))?;
+    let count = match objective.get("count") {
+        None => 1,
+        Some(value) => match value.as_integer() {
+            Some(count) if count > 0 && count <= u32::MAX as i64 => count as u32,
+            _ => return Err(format!("{}.count must be a positive integer.", path)),
+        },
+    };
+    let consume = match objective.get("consume") {
+        None => false,
+        Some(value) => value
+            .as_bool()
+            .ok_or_else(|| format!("{}.consume must be a boolean.", path))?,
+    };
+    Ok(ResolvedQuestObjective {
+        kind,
+        target,
+        count,
+        consume,
+        description: table_string(objective, "description"),
+    })
+}
+
+fn resolve_quest_rewards(
+    root: &Table,
+    quest_id: &str,
+    quest: &Table,
+) -> Result<ResolvedQuestRewards, String> {
+    let Some(value) = quest.get("rewards") else {
+        return Ok(ResolvedQuestRewards::default());
+    };
+    let path = format!("quests.{}.rewards", quest_id);
+    let rewards = value
+        .as_table()
+        .ok_or_else(|| format!("{} must be a table.", path))?;
+    let xp = match rewards.get("xp") {
+        None => 0,
+        Some(Value::Integer(amount)) if *amount >= 0 && *amount <= i32::MAX as i64 => {
+            *amount as i32
+        }
+        Some(Value::String(key)) => ruleset_table_at_path(root, &["progression", "xp"])
+            .and_then(|xp| xp.get(key.trim()))
+            .and_then(Value::as_integer)
+            .filter(|amount| *amount >= 0 && *amount <= i32::MAX as i64)
+            .map(|amount| amount as i32)
+            .ok_or_else(|| format!("{}.xp '{}' is not an integer in progression.xp.", path, key))?,
+        Some(_) => {
+            return Err(format!(
+                "{}.xp must be a non-negative integer or a progression.xp key.",
+                path
+            ));
+        }
+    };
+    let currency = match rewards.get("currency") {
+        None => 0,
+        Some(Value::Integer(amount)) if *amount >= 0 => *amount,
+        Some(_) => return Err(format!("{}.currency must be a non-negative integer.", path)),
+    };
+    let mut items = Vec::new();
+    if let Some(entries) = rewards.get("items") {
+        let entries = entries
+            .as_array()
+            .ok_or_else(|| format!("{}.items must be an array.", path))?;
+        for (index, entry) in entries.iter().enumerate() {
+            let entry_path = format!("{}.items.{}", path, index);
+            let entry = entry
+                .as_table()
+                .ok_or_else(|| format!("{} must be a table.", entry_path))?;
+            let item = table_string(entry, "item")
+                .ok_or_else(|| format!("{}.item must be a non-empty string.", entry_path))?;
+            let quantity = match entry.get("quantity") {
+                None => 1,
+                Some(value) => match value.as_integer() {
+                    Some(quantity) if quantity > 0 && quantity <= u32::MAX as i64 => {
+                        quantity as u32
+                    }
+                    _ => {
+                        return Err(format!(
+                            "{}.quantity must be a positive integer.",
+                            entry_path
+                        ));
+                    }
+                },
+            };
+            items.push((item, quantity));
+        }
+    }
+    Ok(ResolvedQuestRewards {
+        xp,
+        currency,
+        items,
+    })
+}
+
+fn resolve_quest_table(
+    root: &Table,
+    quest_id: &str,
+    quest: &Table,
+) -> Result<ResolvedQuest, String> {
+    let path = format!("quests.{}", quest_id);
+    let stage_values = quest
+        .get("stages")
+        .and_then(Value::as_array)
+        .filter(|stages| !stages.is_empty())
+        .ok_or_else(|| format!("{}.stages must be a non-empty array.", path))?;
+    let mut stages: Vec<ResolvedQuestStage> = Vec::new();
+    for (index, value) in stage_values.iter().enumerate() {
+        let stage_path = format!("{}.stages.{}", path, index);
+        let stage = value
+            .as_table()
+            .ok_or_else(|| format!("{} must be a table.", stage_path))?;
+        let id = table_string(stage, "id").unwrap_or_else(|| (index + 1).to_string());
+        if stages
+            .iter()
+            .any(|existing| existing.id.eq_ignore_ascii_case(&id))
+        {
+            return Err(format!("{} duplicates stage id '{}'.", stage_path, id));
+        }
+        let objectives = match stage.get("objectives") {
+            None => Vec::new(),
+            Some(value) => value
+                .as_array()
+                .ok_or_else(|| format!("{}.objectives must be an array.", stage_path))?
+                .iter()
+                .enumerate()
+                .map(|(objective_index, objective)| {
+                    resolve_quest_objective(
+                        &format!("{}.objectives.{}", stage_path, objective_index),
+                        objective,
+                    )
+                })
+                .collect::<Result<Vec<_>, _>>()?,
+        };
+        stages.push(ResolvedQuestStage {
+            id,
+            description: table_string(stage, "description"),
+            objectives,
+        });
+    }
+    Ok(ResolvedQuest {
+        id: quest_id.to_string(),
+        name: table_string(quest, "name").unwrap_or_else(|| quest_id.to_string()),
+        description: table_string(quest, "description"),
+        stages,
+        rewards: resolve_quest_rewards(root, quest_id, quest)?,
+    })
+}
+
+pub fn resolve_quest(root: &Table, quest_id: &str) -> Result<Option<ResolvedQuest>, String> {
+    let Some(quests) = ruleset_table_at_path(root, &["quests"]) else {
+        return Ok(None);
+    };
+    let Some(value) = quests.get(quest_id.trim()) else {
+        return Ok(None);
+    };
+    let quest = value
+        .as_table()
+        .ok_or_else(|| format!("quests.{} must be a table.", quest_id.trim()))?;
+    resolve_quest_table(root, quest_id.trim(), quest).map(Some)
+}
+
+pub fn resolve_quests(root: &Table) -> Result<BTreeMap<String, ResolvedQuest>, String> {
+    let Some(quests) = ruleset_table_at_path(root, &["quests"]) else {
+        return Ok(BTreeMap::new());
+    };
+    quests
+        .iter()
+        .map(|(quest_id, value)| {
+            let quest = value
+                .as_table()
+                .ok_or_else(|| format!("quests.{} must be a table.", quest_id))?;
+            resolve_quest_table(root, quest_id, quest).map(|quest| (quest_id.clone(), quest))
+        })
+        .collect()
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ResolvedShopStock {
+    pub item: String,
+    pub quantity: u32,
+}
+
+/// A vendor's stock and price rules. Prices are formulas per unit in base
+/// currency, `buy_price` is what customers pay and `sell_price` what the
+/// vendor pays for goods it buys.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ResolvedShop {
+    pub id: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub stock: Vec<ResolvedShopStock>,
+    /// In-game minutes between restocks, 0 stocks the shop only once.
+    pub restock_minutes: f32,
+    /// Item ids, categories, or slots the vendor buys from customers.
+    pub buys: Vec<String>,
+    /// Prices are rounded to whole units of this currency.
+    pub currency: Option<String>,
+    /// Money the vendor has for buying after each restock; unlimited if unset.
+    pub funds: Option<i64>,
+    pub buy_price: String,
+    pub sell_price: String,
+}
+
+const DEFAULT_SHOP_BUY_PRICE: &str = "worth";
+const DEFAULT_SHOP_SELL_PRICE: &str = "worth / 2";
+
+fn shop_price_formula(
+    root: &Table,
+    shop: &Table,
+    path: &str,
+    key: &str,
+    default: &str,
+) -> Result<String, String> {
+    let (formula, formula_path) = match table_string(shop, key) {
+        Some(formula) => (formula, format!("{}.{}", path, key)),
+        None => (
+            ruleset_table_at_path(root, &["economy", "trade"])
+                .and_then(|trade| table_string(trade, key))
+                .unwrap_or_else(|| default.to_string()),
+            format!("economy.trade.{}", key),
+        ),
+    };
+    if let Err(err) = check_formula(&formula) {
+        return Err(format!("{} is not a valid formula: {}.", formula_path, err));
+    }
+    Ok(formula)
+}
+
+fn resolve_shop_table(root: &Table, shop_id: &str, shop: &Table) -> Result<ResolvedShop, String> {
+    let path = format!("shops.{}", shop_id);
+    let mut stock = Vec::new();
+    if let Some(entries) = shop.get("stock") {
+        let entries = entries
+            .as_array()
+            .ok_or_else(|| format!("{}.stock must be an array.", path))?;
+        for (index, entry) in entries.iter().enumerate() {
+            let entry_path = format!("{}.stock.{}", path, index);
+            let entry = entry
+                .as_table()
+                .ok_or_else(|| format!("{} must be a table.", entry_path))?;
+            let item = table_string(entry, "item")
+                .ok_or_else(|| format!("{}.item must be a non-empty string.", entry_path))?;
+            let quantity = match entry.get("quantity") {
+                None => 1,
+                Some(value) => match value.as_integer() {
+                    Some(quantity) if quantity > 0 && quantity <= u32::MAX as i64 => {
+                        quantity as u32
+                    }
+                    _ => {
+                        return Err(format!(
+                            "{}.quantity must be a positive integer.",
+                            entry_path
+                        ));
+                    }
+                },
+            };
+            stock.push(ResolvedShopStock { item, quantity });
+        }
+    }
+    let restock_minutes = match shop.get("restock_minutes") {
+        None => 0.0,
+        Some(value) => value
+            .as_float()
+            .or_else(|| value.as_integer().map(|minutes| minutes as f64))
+            .filter(|minutes| *minutes >= 0.0)
+            .map(|minutes| minutes as f32)
+            .ok_or_else(|| format!("{}.restock_minutes must be a non-negative number.", path))?,
+    };
+    let buys = match shop.get("buys") {
+        None => Vec::new(),
+        Some(value) => value
+            .as_array()
+            .ok_or_else(|| format!("{}.buys must be an array of strings.", path))?
+            .iter()
+            .map(|value| {
+                value
+                    .as_str()
+                    .map(str::trim)
+                    .filter(|value| !value.is_empty())
+                    .map(str::to_string)
+                    .ok_or_else(|| format!("{}.buys must be an array of strings.", path))
+            })
+            .collect::<Result<Vec<_>, _>>()?,
+    };
+    let funds = match shop.get("funds") {
+        None => None,
+        Some(Value::Integer(funds)) if *funds >= 0 => Some(*funds),
+        Some(_) => return Err(format!("{}.funds must be a non-negative integer.", path)),
+    };
+    Ok(ResolvedShop {
+        id: shop_id.to_string(),
+        name: table_string(shop, "name").unwrap_or_else(|| shop_id.to_string()),
+        description: table_string(shop, "description"),
+        stock,
+        restock_minutes,
+        buys,
+        currency: table_string(shop, "currency"),
+        funds,
+        buy_price: shop_price_formula(root, shop, &path, "buy_price", DEFAULT_SHOP_BUY_PRICE)?,
+        sell_price: shop_price_formula(root, shop, &path, "sell_price", DEFAULT_SHOP_SELL_PRICE)?,
+    })
+}
+
+pub fn resolve_shop(root: &Table, shop_id: &str) -> Result<Option<ResolvedShop>, String> {
+    let Some(shops) = ruleset_table_at_path(root, &["shops"]) else {
+        return Ok(None);
+    };
+    let Some(value) = shops.get(shop_id.trim()) else {
+        return Ok(None);
+    };
+    let shop = value
+        .as_table()
+        .ok_or_else(|| format!("shops.{} must be a table.", shop_id.trim()))?;
+    resolve_shop_table(root, shop_id.trim(), shop).map(Some)
+}
+
+pub fn resolve_shops(root: &Table) -> Result<BTreeMap<String, ResolvedShop>, String> {
+    let Some(shops) = ruleset_table_at_path(root, &["shops"]) else {
+        return Ok(BTreeMap::new());
+    };
+    shops
+        .iter()
+        .map(|(shop_id, value)| {
+            let shop = value
+                .as_table()
+                .ok_or_else(|| format!("shops.{} must be a table.", shop_id))?;
+            resolve_shop_table(root, shop_id, shop).map(|shop| (shop_id.clone(), shop))
+        })
+        .collect()
+}
+
 fn optional_identity_id(defaults: &Table, key: &str) -> Result<Option<String>, String> {
     let Some(value) = defaults.get(key) else {
         return Ok(None);
@@ -3653,6 +4145,8 @@ pub struct RulesetCatalog {
     pub skills: Vec<String>,
     pub resources: Vec<String>,
     pub recipes: Vec<String>,
+    pub quests: Vec<String>,
+    pub shops: Vec<String>,
     pub weapons: Vec<String>,
     pub armor: Vec<String>,
     pub clothing: Vec<String>,
@@ -3710,6 +4204,8 @@ pub fn ruleset_catalog(root: &Table) -> RulesetCatalog {
         skills: sorted_table_keys(root, &["skills"]),
         resources: sorted_table_keys(root, &["resources"]),
         recipes: sorted_table_keys(root, &["recipes"]),
+        quests: sorted_table_keys(root, &["quests"]),
+        shops: sorted_table_keys(root, &["shops"]),
         weapons: sorted_table_keys(root, &["items", "weapons"]),
         armor: sorted_table_keys(root, &["items", "armor"]),
         clothing: sorted_table_keys(root, &["items", "clothing"]),
@@ -3736,6 +4232,9 @@ fn section_path(section: &str) -> Option<&'static [&'static str]> {
         "skill" | "skills" => Some(&["skills"]),
         "resource" | "resources" => Some(&["resources"]),
         "recipe" | "recipes" => Some(&["recipes"]),
+        "quest" | "quests" => Some(&["quests"]),
+        "shop" | "shops" => Some(&["shops"]),
+        "loot" | "loot_table" | "loot_tables" => Some(&["loot", "tables"]),
         "weapon" | "weapons" => Some(&["items", "weapons"]),
         "armor" | "armors" => Some(&["items", "armor"]),
         "spell" | "spells" => Some(&["spells"]),
@@ -3755,7 +4254,7 @@ pub fn ruleset_section_ids_from_source(src: &str, section: &str) -> Result<Vec<S
     let root = parse_ruleset_table(src)?;
     let Some(path) = section_path(section) else {
         return Err(format!(
-            "Unknown ruleset section '{}'. Try races, classes, professions, skills, recipes, weapons, armor, spells, abilities, actions, or invocation_schemes.",
+            "Unknown ruleset section '{}'. Try races, classes, professions, skills, recipes, quests, shops, weapons, armor, spells, abilities, actions, or invocation_schemes.",
             section
         ));
     };
@@ -3877,6 +4376,25 @@ fn validate_string_reference(
     }
 }
 
+/// Quests and shops may also reference project item classes, so item ids which
+/// are not ruleset items only warn.
+fn validate_project_item_reference(
+    report: &mut RulesetValidationReport,
+    path: String,
+    item: &str,
+    known: &BTreeSet<String>,
+) {
+    if !known.contains(item) {
+        report.warning(
+            path,
+            format!(
+                "Item '{}' is not a ruleset item; it must be a project item class.",
+                item
+            ),
+        );
+    }
+}
+
 fn validate_string_array_references(
     report: &mut RulesetValidationReport,
     table: &Table,
@@ -3940,17 +4458,6 @@ fn validate_xp_table(report: &mut RulesetValidationReport, root: &Table) {
         }
         None => None,
     };
-    if let Some(expr) = level_table
-        .and_then(|level| level.get("xp_for_level"))
-        .and_then(Value::as_str)
-        && !formula_is_valid(expr)
-    {
-        report.error(
-            "progression.level.xp_for_level",
-            "XP-for-level formula is invalid.",
-        );
-    }
-
     let Some(xp_table) = ruleset_table_at_path(root, &["progression", "xp_table"]) else {
         if level_table
             .and_then(|level| level.get("xp_for_level"))
@@ -4693,6 +5200,109 @@ fn validate_resource_rules(report: &mut RulesetValidationReport, root: &Table) {
     }
 }
 
+fn validate_quest_rules(report: &mut RulesetValidationReport, root: &Table) {
+    let Some(quests) = ruleset_table_at_path(root, &["quests"]) else {
+        return;
+    };
+    let mut item_templates = BTreeSet::new();
+    for group in ruleset_item_group_names(root) {
+        item_templates.extend(table_key_set(root, &["items", &group]));
+    }
+
+    for (id, value) in quests {
+        let path = format!("quests.{}", id);
+        let Some(table) = value.as_table() else {
+            report.error(path, "Quest entry must be a table.");
+            continue;
+        };
+        let quest = match resolve_quest_table(root, id, table) {
+            Ok(quest) => quest,
+            Err(err) => {
+                report.error(path, err);
+                continue;
+            }
+        };
+        for (stage_index, stage) in quest.stages.iter().enumerate() {
+            for (index, objective) in stage.objectives.iter().enumerate() {
+                if objective.kind == ResolvedQuestObjectiveKind::Collect {
+                    validate_project_item_reference(
+                        report,
+                        format!("{}.stages.{}.objectives.{}.item", path, stage_index, index),
+                        &objective.target,
+                        &item_templates,
+                    );
+                }
+            }
+        }
+        for (index, (item, _)) in quest.rewards.items.iter().enumerate() {
+            validate_project_item_reference(
+                report,
+                format!("{}.rewards.items.{}.item", path, index),
+                item,
+                &item_templates,
+            );
+        }
+    }
+}
+
+fn validate_shop_rules(report: &mut RulesetValidationReport, root: &Table) {
+    if let Some(trade) = ruleset_table_at_path(root, &["economy", "trade"]) {
+        for key in ["buy_price", "sell_price"] {
+            if let Some(formula) = table_string(trade, key)
+                && let Err(err) = check_formula(&formula)
+            {
+                report.error(
+                    format!("economy.trade.{}", key),
+                    format!("Trade price must be a valid formula: {}.", err),
+                );
+            }
+        }
+    }
+    let Some(shops) = ruleset_table_at_path(root, &["shops"]) else {
+        return;
+    };
+    let mut item_templates = BTreeSet::new();
+    for group in ruleset_item_group_names(root) {
+        item_templates.extend(table_key_set(root, &["items", &group]));
+    }
+    let currencies = table_key_set(root, &["economy", "currencies"]);
+
+    for (id, value) in shops {
+        let path = format!("shops.{}", id);
+        let Some(table) = value.as_table() else {
+            report.error(path, "Shop entry must be a table.");
+            continue;
+        };
+        let shop = match resolve_shop_table(root, id, table) {
+            Ok(shop) => shop,
+            Err(err) => {
+                report.error(path, err);
+                continue;
+            }
+        };
+        for (index, stock) in shop.stock.iter().enumerate() {
+            validate_project_item_reference(
+                report,
+                format!("{}.stock.{}.item", path, index),
+                &stock.item,
+                &item_templates,
+            );
+        }
+        if let Some(currency) = &shop.currency
+            && !currencies.is_empty()
+            && !currencies.contains(currency)
+        {
+            report.error(
+                format!("{}.currency", path),
+                format!(
+                    "Currency '{}' is not defined in economy.currencies.",
+                    currency
+                ),
+            );
+        }
+    }
+}
+
 fn validate_class_rules(report: &mut RulesetValidationReport, root: &Table) {
     let weapon_categories = table_key_set(root, &["equipment", "weapon_categories"]);
     let armor_categories = table_key_set(root, &["equipment", "armor_categories"]);
@@ -5244,6 +5854,10 @@ pub fn validate_ruleset(root: &Table) -> RulesetValidationReport {
     validate_ability_and_spell_rules(&mut report, root, &damage_kinds);
     validate_recipe_rules(&mut report, root);
     validate_resource_rules(&mut report, root);
+    validate_quest_rules(&mut report, root);
+    validate_shop_rules(&mut report, root);
+    loot::validate_loot_rules(&mut report, root);
+    formula::validate_formula_rules(&mut report, root);
     validate_class_rules(&mut report, root);
     validate_invocation_rules(&mut report, root);
 
@@ -7419,6 +8033,120 @@ mod tests {
         );
     }
 
+    #[test]
+    fn quests_resolve_stages_objectives_and_rewards() {
+        let root = parse_ruleset_table(latest_official_ruleset()).unwrap();
+        let quest = resolve_quest(&root, "herbal_remedy").unwrap().unwrap();
+        assert_eq!(quest.name, "Herbal Remedy");
+        assert_eq!(quest.stages.len(), 2);
+        assert_eq!(quest.stage_index("deliver"), Some(1));
+        let gather = &quest.stages[0].objectives[0];
+        assert_eq!(gather.kind, ResolvedQuestObjectiveKind::Collect);
+        assert_eq!(gather.target, "wild_herb");
+        assert_eq!(gather.count, 3);
+        assert!(gather.consume);
+        assert_eq!(quest.stages[1].objectives[0].label(), "Talk to Healer");
+        // `quest_minor` resolves through progression.xp.
+        assert_eq!(quest.rewards.xp, 25);
+        assert_eq!(quest.rewards.currency, 25);
+        assert_eq!(quest.rewards.items, vec![("blessed_herb".to_string(), 1)]);
+        assert!(validate_ruleset(&root).is_ok());
+
+        let custom = parse_ruleset_table(
+            r#"
+            [quests.cull]
+            [[quests.cull.stages]]
+            objectives = [{ kind = "kill", class = "Wolf", count = 2 }, { kind = "reach", sector = "Den" }]
+            [quests.cull.rewards]
+            xp = 40
+            items = [{ item = "wolf_pelt" }]
+            "#,
+        )
+        .unwrap();
+        let quest = resolve_quest(&custom, "cull").unwrap().unwrap();
+        assert_eq!(quest.name, "cull");
+        assert_eq!(quest.stages[0].id, "1");
+        assert_eq!(quest.stages[0].objectives[0].label(), "Defeat 2 Wolf");
+        assert_eq!(quest.stages[0].objectives[1].count, 1);
+        assert_eq!(quest.rewards.xp, 40);
+        let report = validate_ruleset(&custom);
+        assert!(report.is_ok());
+        assert!(report.issues.iter().any(|issue| {
+            issue.severity == RulesetValidationSeverity::Warning
+                && issue.path == "quests.cull.rewards.items.0.item"
+        }));
+        assert!(resolve_quest(&custom, "missing").unwrap().is_none());
+
+        let broken = parse_ruleset_table(
+            r#"
+            [[quests.bad.stages]]
+            objectives = [{ kind = "escort", npc = "Guard" }]
+            [[quests.unknown_xp.stages]]
+            objectives = [{ kind = "talk", npc = "Guard" }]
+            [quests.unknown_xp.rewards]
+            xp = "quest_legendary"
+            "#,
+        )
+        .unwrap();
+        let report = validate_ruleset(&broken);
+        assert!(report.issues.iter().any(|issue| {
+            issue.path == "quests.bad" && issue.message.contains("kill, collect, reach, or talk")
+        }));
+        assert!(report.issues.iter().any(|issue| {
+            issue.path == "quests.unknown_xp" && issue.message.contains("quest_legendary")
+        }));
+        assert!(resolve_quests(&broken).is_err());
+    }
+
+    #[test]
+    fn shops_resolve_stock_and_trade_formulas() {
+        let root = parse_ruleset_table(latest_official_ruleset()).unwrap();
+        let shop = resolve_shop(&root, "herbalist").unwrap().unwrap();
+        assert_eq!(shop.name, "Herbalist");
+        assert_eq!(shop.restock_minutes, 120.0);
+        assert_eq!(shop.funds, Some(200));
+        assert!(shop.buys.iter().any(|entry| entry == "herb"));
+        assert!(shop.stock.contains(&ResolvedShopStock {
+            item: "wild_herb".into(),
+            quantity: 10,
+        }));
+        assert_eq!(shop.buy_price, "worth * (1 - disposition * 0.1)");
+
+        let custom = parse_ruleset_table(
+            r#"
+            [shops.fence]
+            stock = [{ item = "lockpick" }]
+            sell_price = "worth / 4"
+            "#,
+        )
+        .unwrap();
+        let fence = resolve_shop(&custom, "fence").unwrap().unwrap();
+        assert_eq!(fence.name, "fence");
+        assert_eq!(fence.stock[0].quantity, 1);
+        assert_eq!(fence.buy_price, "worth");
+        assert_eq!(fence.sell_price, "worth / 4");
+        assert_eq!(fence.restock_minutes, 0.0);
+
+        let broken = parse_ruleset_table(
+            r#"
+            [economy.currencies.copper]
+            value = 1
+            [shops.bad]
+            buy_price = "worth *"
+            [shops.odd]
+            currency = "pearls"
+            "#,
+        )
+        .unwrap();
+        let report = validate_ruleset(&broken);
+        assert!(report.issues.iter().any(|issue| {
+            issue.path == "shops.bad" && issue.message.contains("shops.bad.buy_price")
+        }));
+        assert!(report.issues.iter().any(|issue| {
+            issue.path == "shops.odd.currency" && issue.message.contains("pearls")
+        }));
+    }
+
     #[test]
     fn catalogs_and_shows_official_ruleset_paths() {
         let catalog = ruleset_catalog_from_source(latest_official_ruleset()).unwrap();
@@ -7440,6 +8168,8 @@ mod tests {
         assert!(catalog.resources.iter().any(|id| id == "green_wood_node"));
         assert!(catalog.recipes.iter().any(|id| id == "wooden_arrows"));
         assert!(catalog.recipes.iter().any(|id| id == "blessed_herb"));
+        assert!(catalog.quests.iter().any(|id| id == "herbal_remedy"));
+        assert!(catalog.shops.iter().any(|id| id == "herbalist"));
         assert!(catalog.actions.iter().any(|id| id == "basic_attack"));
         assert!(catalog.actions.iter().any(|id| id == "gather_feathers"));
         assert!(catalog.actions.iter().any(|id| id == "gather_herbs"));
@@ -0,0 +1,735 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use toml::{Table, Value};
+
+use crate::{
+    RulesetDice, RulesetValidationReport, parse_ruleset_dice, rng::SeededRng,
+    ruleset_item_group_names, ruleset_table_at_path, table_key_set, table_string,
+};
+
+/// Nested tables deeper than this are treated as a cycle.
+const MAX_LOOT_TABLE_DEPTH: usize = 16;
+
+/// A fixed count or a dice roll such as `2d4`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum RulesetLootQuantity {
+    Fixed(u32),
+    Dice(RulesetDice),
+}
+
+impl RulesetLootQuantity {
+    fn from_value(value: Option<&Value>, path: &str) -> Result<Self, String> {
+        match value {
+            None => Ok(Self::Fixed(1)),
+            Some(Value::Integer(count)) if *count >= 0 && *count <= u32::MAX as i64 => {
+                Ok(Self::Fixed(*count as u32))
+            }
+            Some(Value::String(roll)) => parse_ruleset_dice(roll)
+                .map(Self::Dice)
+                .map_err(|err| format!("{}: {}.", path, err)),
+            Some(_) => Err(format!(
+                "{} must be a non-negative integer or a dice roll like 1d4.",
+                path
+            )),
+        }
+    }
+
+    pub fn minimum(&self) -> u32 {
+        match self {
+            Self::Fixed(count) => *count,
+            Self::Dice(dice) => dice.count,
+        }
+    }
+
+    pub fn maximum(&self) -> u32 {
+        match self {
+            Self::Fixed(count) => *count,
+            Self::Dice(dice) => dice.count * dice.sides,
+        }
+    }
+
+    fn roll(&self, rng: &mut dyn FnMut(u32, u32) -> u32) -> u32 {
+        match self {
+            Self::Fixed(count) => *count,
+            Self::Dice(dice) => (0..dice.count).map(|_| rng(1, dice.sides)).sum(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ResolvedLootSource {
+    Item(String),
+    /// Roll another loot table in place of this entry.
+    Table(String),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ResolvedLootEntry {
+    pub source: ResolvedLootSource,
+    pub weight: f32,
+    pub quantity: RulesetLootQuantity,
+    /// Level gates compared against the level the table is rolled for.
+    pub min_level: Option<u32>,
+    pub max_level: Option<u32>,
+    /// Fixed rarity for the dropped item, skipping the rarity roll.
+    pub rarity: Option<String>,
+    /// Quality percentage range, overriding the rarity's range.
+    pub quality: Option<(i32, i32)>,
+}
+
+impl ResolvedLootEntry {
+    fn allows_level(&self, level: u32) -> bool {
+        self.min_level.is_none_or(|min| level >= min)
+            && self.max_level.is_none_or(|max| level <= max)
+    }
+}
+
+/// A `[loot.tables.<id>]` table: guaranteed drops plus `rolls` weighted
+/// picks from `entries`, where `empty_weight` is the weight of no drop.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ResolvedLootTable {
+    pub id: String,
+    pub rolls: RulesetLootQuantity,
+    pub empty_weight: f32,
+    /// Roll a rarity from `loot.rarities` for every drop.
+    pub roll_rarity: bool,
+    pub guaranteed: Vec<ResolvedLootEntry>,
+    pub entries: Vec<ResolvedLootEntry>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ResolvedLootRarity {
+    pub id: String,
+    pub weight: f32,
+    pub quality: Option<(i32, i32)>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LootDrop {
+    pub item: String,
+    pub quantity: u32,
+    pub rarity: Option<String>,
+    pub quality: Option<i32>,
+}
+
+fn loot_weight(table: &Table, key: &str, default: f32, path: &str) -> Result<f32, String> {
+    match table.get(key) {
+        None => Ok(default),
+        Some(value) => value
+            .as_float()
+            .map(|value| value as f32)
+            .or_else(|| value.as_integer().map(|value| value as f32))
+            .filter(|value| value.is_finite() && *value >= 0.0)
+            .ok_or_else(|| format!("{}.{} must be a non-negative number.", path, key)),
+    }
+}
+
+fn loot_level(table: &Table, key: &str, path: &str) -> Result<Option<u32>, String> {
+    match table.get(key) {
+        None => Ok(None),
+        Some(Value::Integer(level)) if *level >= 0 && *level <= u32::MAX as i64 => {
+            Ok(Some(*level as u32))
+        }
+        Some(_) => Err(format!("{}.{} must be a non-negative integer.", path, key)),
+    }
+}
+
+fn loot_quality(table: &Table, path: &str) -> Result<Option<(i32, i32)>, String> {
+    let Some(value) = table.get("quality") else {
+        return Ok(None);
+    };
+    let range = match value {
+        Value::Integer(quality) => Some((*quality, *quality)),
+        Value::Array(bounds) if bounds.len() == 2 => {
+            bounds[0].as_integer().zip(bounds[1].as_integer())
+        }
+        _ => None,
+    };
+    match range {
+        Some((low, high)) if 1 <= low && low <= high && high <= 100 => {
+            Ok(Some((low as i32, high as i32)))
+        }
+        _ => Err(format!(
+            "{}.quality must be a percentage or a [min, max] range from 1 to 100.",
+            path
+        )),
+    }
+}
+
+fn resolve_loot_entry(entry: &Value, path: &str) -> Result<ResolvedLootEntry, String> {
+    let entry = entry
+        .as_table()
+        .ok_or_else(|| format!("{} must be a table.", path))?;
+    let source = match (table_string(entry, "item"), table_string(entry, "table")) {
+        (Some(item), None) => ResolvedLootSource::Item(item),
+        (None, Some(table)) => ResolvedLootSource::Table(table),
+        _ => {
+            return Err(format!("{} must name exactly one of item or table.", path));
+        }
+    };
+    let entry_resolved = ResolvedLootEntry {
+        source,
+        weight: loot_weight(entry, "weight", 1.0, path)?,
+        quantity: RulesetLootQuantity::from_value(
+            entry.get("quantity"),
+            &format!("{}.quantity", path),
+        )?,
+        min_level: loot_level(entry, "min_level", path)?,
+        max_level: loot_level(entry, "max_level", path)?,
+        rarity: table_string(entry, "rarity"),
+        quality: loot_quality(entry, path)?,
+    };
+    if let (Some(min), Some(max)) = (entry_resolved.min_level, entry_resolved.max_level)
+        && min > max
+    {
+        return Err(format!("{}.min_level is above max_level.", path));
+    }
+    Ok(entry_resolved)
+}
+
+fn resolve_loot_entries(
+    table: &Table,
+    key: &str,
+    path: &str,
+) -> Result<Vec<ResolvedLootEntry>, String> {
+    let Some(entries) = table.get(key) else {
+        return Ok(Vec::new());
+    };
+    entries
+        .as_array()
+        .ok_or_else(|| format!("{}.{} must be an array.", path, key))?
+        .iter()
+        .enumerate()
+        .map(|(index, entry)| resolve_loot_entry(entry, &format!("{}.{}.{}", path, key, index)))
+        .collect()
+}
+
+fn resolve_loot_table_entry(table_id: &str, table: &Table) -> Result<ResolvedLootTable, String> {
+    let path = format!("loot.tables.{}", table_id);
+    let roll_rarity = match table.get("rarity") {
+        None => false,
+        Some(Value::Boolean(roll)) => *roll,
+        Some(_) => return Err(format!("{}.rarity must be true or false.", path)),
+    };
+    Ok(ResolvedLootTable {
+        id: table_id.to_string(),
+        rolls: RulesetLootQuantity::from_value(table.get("rolls"), &format!("{}.rolls", path))?,
+        empty_weight: loot_weight(table, "empty_weight", 0.0, &path)?,
+        roll_rarity,
+        guaranteed: resolve_loot_entries(table, "guaranteed", &path)?,
+        entries: resolve_loot_entries(table, "entries", &path)?,
+    })
+}
+
+pub fn resolve_loot_table(
+    root: &Table,
+    table_id: &str,
+) -> Result<Option<ResolvedLootTable>, String> {
+    let Some(value) = ruleset_table_at_path(root, &["loot", "tables"])
+        .and_then(|tables| tables.get(table_id.trim()))
+    else {
+        return Ok(None);
+    };
+    let table = value
+        .as_table()
+        .ok_or_else(|| format!("loot.tables.{} must be a table.", table_id.trim()))?;
+    resolve_loot_table_entry(table_id.trim(), table).map(Some)
+}
+
+pub fn resolve_loot_tables(root: &Table) -> Result<BTreeMap<String, ResolvedLootTable>, String> {
+    let Some(tables) = ruleset_table_at_path(root, &["loot", "tables"]) else {
+        return Ok(BTreeMap::new());
+    };
+    tables
+        .iter()
+        .map(|(table_id, value)| {
+            let table = value
+                .as_table()
+                .ok_or_else(|| format!("loot.tables.{} must be a table.", table_id))?;
+            resolve_loot_table_entry(table_id, table).map(|table| (table_id.clone(), table))
+        })
+        .collect()
+}
+
+pub fn resolve_loot_rarities(root: &Table) -> Result<Vec<ResolvedLootRarity>, String> {
+    let Some(rarities) = ruleset_table_at_path(root, &["loot", "rarities"]) else {
+        return Ok(Vec::new());
+    };
+    rarities
+        .iter()
+        .map(|(rarity_id, value)| {
+            let path = format!("loot.rarities.{}", rarity_id);
+            let rarity = value
+                .as_table()
+                .ok_or_else(|| format!("{} must be a table.", path))?;
+            Ok(ResolvedLootRarity {
+                id: rarity_id.clone(),
+                weight: loot_weight(rarity, "weight", 1.0, &path)?,
+                quality: loot_quality(rarity, &path)?,
+            })
+        })
+        .collect()
+}
+
+/// The loot tables a race, class, resource or container entry names in its
+/// `loot` key, as one id or a list of ids.
+pub fn ruleset_loot_table_refs(entry: &Table) -> Vec<String> {
+    match entry.get("loot") {
+        Some(Value::String(table)) => vec![table.trim().to_string()],
+        Some(Value::Array(tables)) => tables
+            .iter()
+            .filter_map(Value::as_str)
+            .map(|table| table.trim().to_string())
+            .collect(),
+        _ => Vec::new(),
+    }
+    .into_iter()
+    .filter(|table| !table.is_empty())
+    .collect()
+}
+
+/// The loot tables a character of this race and class drops on death.
+pub fn identity_loot_tables(root: &Table, race: Option<&str>, class: Option<&str>) -> Vec<String> {
+    let mut tables = Vec::new();
+    for (section, id) in [("races", race), ("classes", class)] {
+        if let Some(entry) = id.and_then(|id| ruleset_table_at_path(root, &[section, id.trim()])) {
+            tables.extend(ruleset_loot_table_refs(entry));
+        }
+    }
+    tables
+}
+
+fn pick_weighted<T>(options: &[(f32, T)], rng: &mut dyn FnMut(u32, u32) -> u32) -> Option<usize> {
+    let total: f32 = options.iter().map(|(weight, _)| weight).sum();
+    if total <= 0.0 {
+        return None;
+    }
+    let mut point = rng(0, u32::MAX - 1) as f64 / u32::MAX as f64 * total as f64;
+    for (index, (weight, _)) in options.iter().enumerate() {
+        if point < *weight as f64 {
+            return Some(index);
+        }
+        point -= *weight as f64;
+    }
+    options.iter().rposition(|(weight, _)| *weight > 0.0)
+}
+
+struct LootRoll<'a> {
+    root: &'a Table,
+    level: u32,
+    rarities: Vec<ResolvedLootRarity>,
+    drops: Vec<LootDrop>,
+}
+
+impl LootRoll<'_> {
+    fn roll_table(
+        &mut self,
+        table_id: &str,
+        depth: usize,
+        rng: &mut dyn FnMut(u32, u32) -> u32,
+    ) -> Result<(), String> {
+        if depth > MAX_LOOT_TABLE_DEPTH {
+            return Err(format!(
+                "Loot table '{}' nests too deeply; check for a cycle.",
+                table_id
+            ));
+        }
+        let table = resolve_loot_table(self.root, table_id)?
+            .ok_or_else(|| format!("Loot table '{}' was not found.", table_id))?;
+        let level = self.level;
+        for entry in table
+            .guaranteed
+            .iter()
+            .filter(|entry| entry.allows_level(level))
+        {
+            self.drop_entry(&table, entry, depth, rng)?;
+        }
+        let mut options: Vec<(f32, Option<&ResolvedLootEntry>)> = table
+            .entries
+            .iter()
+            .filter(|entry| entry.allows_level(level))
+            .map(|entry| (entry.weight, Some(entry)))
+            .collect();
+        options.push((table.empty_weight, None));
+        for _ in 0..table.rolls.roll(rng) {
+            if let Some(index) = pick_weighted(&options, rng)
+                && let Some(entry) = options[index].1
+            {
+                self.drop_entry(&table, entry, depth, rng)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn drop_entry(
+        &mut self,
+        table: &ResolvedLootTable,
+        entry: &ResolvedLootEntry,
+        depth: usize,
+        rng: &mut dyn FnMut(u32, u32) -> u32,
+    ) -> Result<(), String> {
+        match &entry.source {
+            ResolvedLootSource::Table(nested) => {
+                for _ in 0..entry.quantity.roll(rng) {
+                    self.roll_table(nested, depth + 1, rng)?;
+                }
+            }
+            ResolvedLootSource::Item(item) => {
+                let quantity = entry.quantity.roll(rng);
+                if quantity == 0 {
+                    return Ok(());
+                }
+                let rarity = match &entry.rarity {
+                    Some(rarity) => self
+                        .rarities
+                        .iter()
+                        .find(|candidate| &candidate.id == rarity),
+                    None if table.roll_rarity => {
+                        let options: Vec<(f32, &ResolvedLootRarity)> = self
+                            .rarities
+                            .iter()
+                            .map(|rarity| (rarity.weight, rarity))
+                            .collect();
+                        pick_weighted(&options, rng).map(|index| options[index].1)
+                    }
+                    None => None,
+                };
+                let quality = entry
+                    .quality
+                    .or_else(|| rarity.and_then(|rarity| rarity.quality))
+                    .map(|(low, high)| rng(low as u32, high as u32) as i32);
+                self.drops.push(LootDrop {
+                    item: item.clone(),
+                    quantity,
+                    rarity: entry
+                        .rarity
+                        .clone()
+                        .or_else(|| rarity.map(|rarity| rarity.id.clone())),
+                    quality,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Roll a loot table for a character or harvester of `level`. `rng` returns
+/// a uniform value in `low..=high`.
+pub fn roll_loot_table(
+    root: &Table,
+    table_id: &str,
+    level: u32,
+    rng: &mut dyn FnMut(u32, u32) -> u32,
+) -> Result<Vec<LootDrop>, String> {
+    let mut roll = LootRoll {
+        root,
+        level,
+        rarities: resolve_loot_rarities(root)?,
+        drops: Vec::new(),
+    };
+    roll.roll_table(table_id.trim(), 0, rng)?;
+    Ok(roll.drops)
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LootItemSample {
+    /// Samples in which the item dropped at least once.
+    pub samples_with_drop: u32,
+    pub total_quantity: u64,
+    pub min_quantity: u32,
+    pub max_quantity: u32,
+    pub rarities: BTreeMap<String, u32>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LootSample {
+    pub samples: u32,
+    pub empty_samples: u32,
+    pub items: BTreeMap<String, LootItemSample>,
+}
+
+/// Roll a table `samples` times with a seeded generator to show its drop
+/// distribution.
+pub fn sample_loot_table(
+    root: &Table,
+    table_id: &str,
+    level: u32,
+    samples: u32,
+    seed: u64,
+) -> Result<LootSample, String> {
+    let mut rng = SeededRng::new(seed);
+    let mut roll = |low, high| rng.range(low, high);
+    let mut sample = LootSample {
+        samples,
+        ..Default::default()
+    };
+    for _ in 0..samples {
+        let drops = roll_loot_table(root, table_id, level, &mut roll)?;
+        if drops.is_empty() {
+            sample.empty_samples += 1;
+        }
+        let mut quantities: BTreeMap<&str, u32> = BTreeMap::new();
+        for drop in &drops {
+            *quantities.entry(&drop.item).or_insert(0) += drop.quantity;
+            if let Some(rarity) = &drop.rarity {
+                *sample
+                    .items
+                    .entry(drop.item.clone())
+                    .or_default()
+                    .rarities
+                    .entry(rarity.clone())
+                    .or_insert(0) += 1;
+            }
+        }
+        for (item, quantity) in quantities {
+            let stats = sample.items.entry(item.to_string()).or_default();
+            if stats.samples_with_drop == 0 || quantity < stats.min_quantity {
+                stats.min_quantity = quantity;
+            }
+            stats.max_quantity = stats.max_quantity.max(quantity);
+            stats.samples_with_drop += 1;
+            stats.total_quantity += u64::from(quantity);
+        }
+    }
+    Ok(sample)
+}
+
+fn nested_loot_cycle(
+    tables: &BTreeMap<String, ResolvedLootTable>,
+    table_id: &str,
+    stack: &mut Vec<String>,
+) -> Option<Vec<String>> {
+    if let Some(start) = stack.iter().position(|id| id == table_id) {
+        let mut cycle = stack[start..].to_vec();
+        cycle.push(table_id.to_string());
+        return Some(cycle);
+    }
+    let table = tables.get(table_id)?;
+    stack.push(table_id.to_string());
+    for entry in table.guaranteed.iter().chain(&table.entries) {
+        if let ResolvedLootSource::Table(nested) = &entry.source
+            && let Some(cycle) = nested_loot_cycle(tables, nested, stack)
+        {
+            return Some(cycle);
+        }
+    }
+    stack.pop();
+    None
+}
+
+pub(crate) fn validate_loot_rules(report: &mut RulesetValidationReport, root: &Table) {
+    let mut item_templates = BTreeSet::new();
+    for group in ruleset_item_group_names(root) {
+        item_templates.extend(table_key_set(root, &["items", &group]));
+    }
+    let table_ids = table_key_set(root, &["loot", "tables"]);
+    let rarity_ids = table_key_set(root, &["loot", "rarities"]);
+    if let Err(err) = resolve_loot_rarities(root) {
+        report.error("loot.rarities", err);
+    }
+
+    let mut tables = BTreeMap::new();
+    for table_id in &table_ids {
+        let path = format!("loot.tables.{}", table_id);
+        let table = match resolve_loot_table(root, table_id) {
+            Ok(Some(table)) => table,
+            Ok(None) => continue,
+            Err(err) => {
+                report.error(path, err);
+                continue;
+            }
+        };
+        for (key, entries) in [
+            ("guaranteed", &table.guaranteed),
+            ("entries", &table.entries),
+        ] {
+            for (index, entry) in entries.iter().enumerate() {
+                let entry_path = format!("{}.{}.{}", path, key, index);
+                match &entry.source {
+                    ResolvedLootSource::Item(item) if !item_templates.contains(item) => report
+                        .warning(
+                            format!("{}.item", entry_path),
+                            format!(
+                                "Item '{}' is not a ruleset item; it must be a project item class.",
+                                item
+                            ),
+                        ),
+                    ResolvedLootSource::Table(nested) if !table_ids.contains(nested) => report
+                        .error(
+                            format!("{}.table", entry_path),
+                            format!("Loot table '{}' is not defined in loot.tables.", nested),
+                        ),
+                    _ => {}
+                }
+                if let Some(rarity) = &entry.rarity
+                    && !rarity_ids.contains(rarity)
+                {
+                    report.error(
+                        format!("{}.rarity", entry_path),
+                        format!("Rarity '{}' is not defined in loot.rarities.", rarity),
+                    );
+                }
+            }
+        }
+        if table.roll_rarity && rarity_ids.is_empty() {
+            report.error(
+                format!("{}.rarity", path),
+                "Rolling rarity needs loot.rarities entries.",
+            );
+        }
+        tables.insert(table_id.clone(), table);
+    }
+    let mut reported = BTreeSet::new();
+    for table_id in &table_ids {
+        if let Some(cycle) = nested_loot_cycle(&tables, table_id, &mut Vec::new())
+            && reported.insert(cycle[0].clone())
+        {
+            report.error(
+                format!("loot.tables.{}", cycle[0]),
+                format!("Loot tables nest in a cycle: {}.", cycle.join(" -> ")),
+            );
+        }
+    }
+
+    let mut owners = Vec::new();
+    for section in ["races", "classes", "resources"] {
+        for id in table_key_set(root, &[section]) {
+            owners.push(vec![section.to_string(), id]);
+        }
+    }
+    for id in table_key_set(root, &["items", "containers"]) {
+        owners.push(vec!["items".into(), "containers".into(), id]);
+    }
+    for owner in owners {
+        let parts: Vec<&str> = owner.iter().map(String::as_str).collect();
+        let Some(entry) = ruleset_table_at_path(root, &parts) else {
+            continue;
+        };
+        for table in ruleset_loot_table_refs(entry) {
+            if !table_ids.contains(&table) {
+                report.error(
+                    format!("{}.loot", owner.join(".")),
+                    format!("Loot table '{}' is not defined in loot.tables.", table),
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{latest_official_ruleset, parse_ruleset_table, validate_ruleset};
+
+    fn loot_rules() -> Table {
+        parse_ruleset_table(
+            r#"
+            [loot.rarities.common]
+            weight = 3
+            quality = [60, 80]
+            [loot.rarities.rare]
+            weight = 1
+            quality = [90, 100]
+
+            [loot.tables.purse]
+            rolls = "1d2"
+            entries = [{ item = "copper_coin", quantity = "2d4" }]
+
+            [loot.tables.orc]
+            rarity = true
+            empty_weight = 1
+            guaranteed = [{ table = "purse" }]
+            entries = [
+              { item = "hand_axe", weight = 1, min_level = 3 },
+              { item = "wild_herb", weight = 2, quantity = 2, quality = 50 },
+            ]
+            "#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn rolls_guaranteed_nested_and_weighted_entries() {
+        let rules = loot_rules();
+        let table = resolve_loot_table(&rules, "orc").unwrap().unwrap();
+        assert_eq!(
+            table.guaranteed[0].source,
+            ResolvedLootSource::Table("purse".into())
+        );
+        assert_eq!(table.entries[0].min_level, Some(3));
+
+        // Always the lowest value: one purse roll of 2 coins, then a hand axe
+        // if the level allows it. Only the orc table rolls rarity.
+        let mut lowest = |low: u32, _high: u32| low;
+        let drops = roll_loot_table(&rules, "orc", 3, &mut lowest).unwrap();
+        assert_eq!(
+            drops,
+            vec![
+                LootDrop {
+                    item: "copper_coin".into(),
+                    quantity: 2,
+                    rarity: None,
+                    quality: None,
+                },
+                LootDrop {
+                    item: "hand_axe".into(),
+                    quantity: 1,
+                    rarity: Some("common".into()),
+                    quality: Some(60),
+                },
+            ]
+        );
+        let drops = roll_loot_table(&rules, "orc", 1, &mut lowest).unwrap();
+        assert_eq!(drops[1].item, "wild_herb");
+        assert_eq!(drops[1].quantity, 2);
+        assert_eq!(drops[1].quality, Some(50));
+    }
+
+    #[test]
+    fn samples_distributions_reproducibly() {
+        let rules = loot_rules();
+        let sample = sample_loot_table(&rules, "orc", 5, 400, 3).unwrap();
+        assert_eq!(sample, sample_loot_table(&rules, "orc", 5, 400, 3).unwrap());
+        let coins = &sample.items["copper_coin"];
+        assert_eq!(coins.samples_with_drop, 400);
+        assert_eq!((coins.min_quantity, coins.max_quantity >= 9), (2, true));
+        assert!(coins.max_quantity <= 16);
+        let herbs = sample.items["wild_herb"].samples_with_drop;
+        let axes = sample.items["hand_axe"].samples_with_drop;
+        assert!(herbs > axes && axes > 50, "herbs {} axes {}", herbs, axes);
+        assert!(sample.items["hand_axe"].rarities.contains_key("rare"));
+    }
+
+    #[test]
+    fn validation_reports_broken_loot_references() {
+        let mut rules = parse_ruleset_table(latest_official_ruleset()).unwrap();
+        let broken = parse_ruleset_table(
+            r#"
+            [loot.tables.a]
+            entries = [{ table = "b" }, { table = "missing" }, { item = "hand_axe", rarity = "mythic" }]
+            [loot.tables.b]
+            guaranteed = [{ table = "a" }]
+            [loot.tables.c]
+            entries = [{ item = "hand_axe", table = "a" }]
+            [races.Orc]
+            loot = "nowhere"
+            "#,
+        )
+        .unwrap();
+        crate::merge_toml_tables(&mut rules, broken);
+
+        let report = validate_ruleset(&rules);
+        let has = |path: &str, text: &str| {
+            report
+                .issues
+                .iter()
+                .any(|issue| issue.path == path && issue.message.contains(text))
+        };
+        assert!(has("loot.tables.a", "a -> b -> a"));
+        assert!(has("loot.tables.a.entries.1.table", "missing"));
+        assert!(has("loot.tables.a.entries.2.rarity", "mythic"));
+        assert!(has("loot.tables.c", "exactly one of item or table"));
+        assert!(has("races.Orc.loot", "nowhere"));
+        assert!(roll_loot_table(&rules, "a", 1, &mut |low, _| low).is_err());
+    }
+}
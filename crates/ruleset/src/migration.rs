@@ -0,0 +1,213 @@
+use toml::{Table, Value};
+
+use crate::{SUPPORTED_RULESET_SCHEMA_VERSIONS, ruleset_table_at_path};
+
+/// Rewrites a project override table from one schema version to the next.
+pub struct RulesetMigration {
+    pub from: &'static str,
+    pub to: &'static str,
+    pub summary: &'static str,
+    /// Rewrites the table in place and records a note per change made.
+    pub apply: fn(&mut Table, &mut Vec<String>),
+}
+
+/// One migration per consecutive pair of `SUPPORTED_RULESET_SCHEMA_VERSIONS`.
+/// Schema 1 is the only schema so far; a schema bump appends its step here.
+pub const RULESET_MIGRATIONS: &[RulesetMigration] = &[];
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RulesetMigrationReport {
+    pub from: String,
+    pub to: String,
+    /// Summaries of the migrations applied, in order.
+    pub steps: Vec<String>,
+    pub notes: Vec<String>,
+}
+
+/// The schema an override declares in its own `[ruleset]` table, if any.
+pub fn ruleset_override_schema_version(overrides: &Table) -> Option<&str> {
+    ruleset_table_at_path(overrides, &["ruleset"])?
+        .get("schema_version")?
+        .as_str()
+}
+
+/// Migrate a project override table from schema `from` to schema `to`,
+/// running every registered step in between.
+pub fn migrate_ruleset_override(
+    overrides: &mut Table,
+    from: &str,
+    to: &str,
+) -> Result<RulesetMigrationReport, String> {
+    migrate_with(
+        SUPPORTED_RULESET_SCHEMA_VERSIONS,
+        RULESET_MIGRATIONS,
+        overrides,
+        from,
+        to,
+    )
+}
+
+fn migrate_with(
+    versions: &[&str],
+    migrations: &[RulesetMigration],
+    overrides: &mut Table,
+    from: &str,
+    to: &str,
+) -> Result<RulesetMigrationReport, String> {
+    let position = |version: &str| {
+        versions
+            .iter()
+            .position(|candidate| *candidate == version)
+            .ok_or_else(|| {
+                format!(
+                    "Schema version '{}' is not supported. This build supports: {}.",
+                    version,
+                    versions.join(", ")
+                )
+            })
+    };
+    let (start, end) = (position(from)?, position(to)?);
+    if start > end {
+        return Err(format!(
+            "Cannot migrate rules from schema {} back to schema {}.",
+            from, to
+        ));
+    }
+
+    let mut report = RulesetMigrationReport {
+        from: from.to_string(),
+        to: to.to_string(),
+        ..Default::default()
+    };
+    for pair in versions[start..=end].windows(2) {
+        let migration = migrations
+            .iter()
+            .find(|migration| migration.from == pair[0] && migration.to == pair[1])
+            .ok_or_else(|| format!("No migration from schema {} to {}.", pair[0], pair[1]))?;
+        (migration.apply)(overrides, &mut report.notes);
+        report.steps.push(format!(
+            "{} -> {}: {}",
+            migration.from, migration.to, migration.summary
+        ));
+    }
+    if let Some(ruleset) = overrides.get_mut("ruleset").and_then(Value::as_table_mut)
+        && ruleset.contains_key("schema_version")
+    {
+        ruleset.insert("schema_version".into(), Value::String(to.to_string()));
+    }
+    Ok(report)
+}
+
+/// Move the value at `path` to the sibling key `to`, for migrations that
+/// rename fields. Returns whether anything moved.
+pub fn rename_ruleset_key(
+    table: &mut Table,
+    path: &[&str],
+    to: &str,
+    notes: &mut Vec<String>,
+) -> bool {
+    let Some((key, parents)) = path.split_last() else {
+        return false;
+    };
+    let mut parent = table;
+    for part in parents {
+        let Some(next) = parent.get_mut(*part).and_then(Value::as_table_mut) else {
+            return false;
+        };
+        parent = next;
+    }
+    if parent.contains_key(to) {
+        return false;
+    }
+    let Some(value) = parent.remove(*key) else {
+        return false;
+    };
+    parent.insert(to.to_string(), value);
+    notes.push(format!("Renamed {} to {}.", path.join("."), to));
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rename_cooldowns(table: &mut Table, notes: &mut Vec<String>) {
+        let ids: Vec<String> = table
+            .get("actions")
+            .and_then(Value::as_table)
+            .map(|actions| actions.keys().cloned().collect())
+            .unwrap_or_default();
+        for id in ids {
+            rename_ruleset_key(
+                table,
+                &["actions", &id, "cooldown"],
+                "cooldown_seconds",
+                notes,
+            );
+        }
+    }
+
+    fn drop_legacy(table: &mut Table, notes: &mut Vec<String>) {
+        if table.remove("legacy").is_some() {
+            notes.push("Removed legacy.".into());
+        }
+    }
+
+    const MIGRATIONS: &[RulesetMigration] = &[
+        RulesetMigration {
+            from: "1",
+            to: "2",
+            summary: "rename action cooldowns",
+            apply: rename_cooldowns,
+        },
+        RulesetMigration {
+            from: "2",
+            to: "3",
+            summary: "drop legacy tables",
+            apply: drop_legacy,
+        },
+    ];
+
+    #[test]
+    fn migrations_chain_through_each_schema() {
+        let mut overrides: Table = toml::from_str(
+            r#"
+            legacy = true
+            [ruleset]
+            schema_version = "1"
+            [actions.guard]
+            cooldown = 2.0
+            "#,
+        )
+        .unwrap();
+
+        let report = migrate_with(&["1", "2", "3"], MIGRATIONS, &mut overrides, "1", "3").unwrap();
+        assert_eq!(report.steps.len(), 2);
+        assert_eq!(
+            report.notes,
+            vec![
+                "Renamed actions.guard.cooldown to cooldown_seconds.",
+                "Removed legacy."
+            ]
+        );
+        assert_eq!(ruleset_override_schema_version(&overrides), Some("3"));
+        assert_eq!(
+            overrides["actions"]["guard"]["cooldown_seconds"].as_float(),
+            Some(2.0)
+        );
+        assert!(!overrides.contains_key("legacy"));
+    }
+
+    #[test]
+    fn rejects_missing_steps_and_downgrades() {
+        let mut overrides = Table::new();
+        assert!(
+            migrate_with(&["1", "2", "3"], &MIGRATIONS[..1], &mut overrides, "1", "3").is_err()
+        );
+        assert!(migrate_with(&["1", "2"], MIGRATIONS, &mut overrides, "2", "1").is_err());
+        assert!(migrate_ruleset_override(&mut overrides, "1", "9").is_err());
+
+        let report = migrate_ruleset_override(&mut overrides, "1", "1").unwrap();
+        assert!(report.steps.is_empty());
+    }
+}
@@ -0,0 +1,29 @@
+use crate::RulesetDice;
+
+/// SplitMix64, so a seed always replays the same simulation.
+pub(crate) struct SeededRng(u64);
+
+impl SeededRng {
+    pub(crate) fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// A uniform value in `low..=high`.
+    pub(crate) fn range(&mut self, low: u32, high: u32) -> u32 {
+        low + (self.next_u64() % (u64::from(high - low) + 1)) as u32
+    }
+
+    pub(crate) fn roll(&mut self, dice: &RulesetDice) -> f32 {
+        (0..dice.count)
+            .map(|_| self.range(1, dice.sides) as f32)
+            .sum()
+    }
+}
@@ -0,0 +1,276 @@
+use crate::Ray;
+use vek::{Mat4, Vec2, Vec3};
+
+use super::D3Camera;
+
+/// Jumps larger than this (teleports, region changes) snap the boom instead of easing.
+const SNAP_DISTANCE: f32 = 8.0;
+
+/// Frame time `lag` is defined for.
+const LAG_REFERENCE_DT: f32 = 1.0 / 30.0;
+
+/// A chase camera which follows behind and above its target.
+///
+/// Set `forward` to the facing direction and then `target` to the look-at pivot
+/// each frame; the camera eases towards the end of its boom, `distance` behind and
+/// `height` above the pivot. `lag` (0..1) is the part of the way still left after
+/// 1/30 s, so the easing does not depend on the frame rate. Set `dt` to the frame
+/// time before `target`.
+#[derive(Clone)]
+pub struct D3ThirdPCamera {
+    pub position: Vec3<f32>,
+    pub center: Vec3<f32>,
+    pub forward: Vec3<f32>,
+
+    pub distance: f32,
+    pub height: f32,
+    pub lag: f32,
+    pub dt: f32,
+
+    pub fov: f32,
+    pub near: f32,
+    pub far: f32,
+
+    /// Eased boom end before collision pull-in.
+    follow: Option<Vec3<f32>>,
+}
+
+impl D3ThirdPCamera {
+    /// Part of the remaining way to the boom end covered this frame,
+    /// `1 - exp(-k * dt)` with `k` chosen so `lag` remains after 1/30 s.
+    fn follow_factor(&self) -> f32 {
+        if self.lag <= 0.0 {
+            return 1.0;
+        }
+        let k = -self.lag.ln() / LAG_REFERENCE_DT;
+        1.0 - (-k * self.dt).exp()
+    }
+}
+
+impl D3Camera for D3ThirdPCamera {
+    fn new() -> Self {
+        Self {
+            position: Vec3::zero(),
+            center: Vec3::zero(),
+            forward: Vec3::unit_z(),
+
+            distance: 4.0,
+            height: 1.0,
+            lag: 0.5,
+            dt: LAG_REFERENCE_DT,
+
+            fov: 70.0,
+            near: 0.01,
+            far: 100.0,
+
+            follow: None,
+        }
+    }
+
+    fn id(&self) -> String {
+        "thirdp".to_string()
+    }
+
+    fn fov(&self) -> f32 {
+        self.fov
+    }
+
+    fn distance(&self) -> f32 {
+        self.distance
+    }
+
+    fn view_matrix(&self) -> Mat4<f32> {
+        vek::Mat4::look_at_rh(self.position, self.center, Vec3::unit_y())
+    }
+
+    fn projection_matrix(&self, width: f32, height: f32) -> Mat4<f32> {
+        vek::Mat4::perspective_fov_rh_zo(self.fov.to_radians(), width, height, self.near, self.far)
+    }
+
+    fn zoom(&mut self, delta: f32) {
+        let zoom_sensitivity = 0.1;
+        self.distance = (self.distance - delta * zoom_sensitivity).clamp(1.0, 20.0);
+    }
+
+    fn get_parameter_f32(&mut self, key: &str) -> f32 {
+        match key {
+            "distance" => self.distance,
+            "height" => self.height,
+            "lag" => self.lag,
+            "fov" => self.fov,
+            _ => 0.0,
+        }
+    }
+
+    fn set_parameter_f32(&mut self, key: &str, value: f32) {
+        match key {
+            "distance" => {
+                self.distance = value.max(0.1);
+            }
+            "height" => {
+                self.height = value;
+            }
+            "lag" => {
+                self.lag = value.clamp(0.0, 0.99);
+            }
+            "dt" => {
+                self.dt = value.max(0.0);
+            }
+            "fov" => {
+                self.fov = value;
+            }
+            "near" => {
+                self.near = value;
+            }
+            "far" => {
+                self.far = value;
+            }
+            _ => {}
+        }
+    }
+
+    fn set_parameter_vec3(&mut self, key: &str, value: Vec3<f32>) {
+        match key {
+            "position" => {
+                self.position = value;
+            }
+            "center" => {
+                self.center = value;
+            }
+            "forward" => {
+                let flat = Vec3::new(value.x, 0.0, value.z);
+                if flat.magnitude_squared() > 1e-8 {
+                    self.forward = flat.normalized();
+                }
+            }
+            "target" => {
+                self.center = value;
+                let desired = value - self.forward * self.distance + Vec3::unit_y() * self.height;
+                let follow = match self.follow {
+                    Some(current) if current.distance(desired) <= SNAP_DISTANCE => {
+                        current + (desired - current) * self.follow_factor()
+                    }
+                    _ => desired,
+                };
+                self.follow = Some(follow);
+                self.position = follow;
+            }
+            _ => {}
+        }
+    }
+
+    fn position(&self) -> Vec3<f32> {
+        self.position
+    }
+
+    fn basis_vectors(&self) -> (Vec3<f32>, Vec3<f32>, Vec3<f32>) {
+        let eps = 1e-8_f32;
+
+        let mut forward = self.center - self.position;
+        if forward.magnitude_squared() < eps {
+            forward = self.forward;
+        }
+        forward = forward.normalized();
+
+        let mut right = forward.cross(Vec3::unit_y());
+        if right.magnitude_squared() < eps {
+            right = forward.cross(Vec3::unit_z());
+        }
+        right = right.normalized();
+
+        let up = right.cross(forward).normalized();
+
+        (forward, right, up)
+    }
+
+    fn create_ray(&self, uv: Vec2<f32>, screen: Vec2<f32>, offset: Vec2<f32>) -> Ray {
+        let aspect = screen.x / screen.y;
+        let pixel_size = Vec2::new(1.0 / screen.x, 1.0 / screen.y);
+
+        let half_height = (self.fov.to_radians() * 0.5).tan();
+        let half_width = half_height * aspect;
+
+        let (forward, right, up) = self.basis_vectors();
+
+        let lower_left = self.position + forward - right * half_width - up * half_height;
+
+        let horizontal = right * (2.0 * half_width);
+        let vertical = up * (2.0 * half_height);
+
+        let sample_pos = lower_left
+            + horizontal * (pixel_size.x * offset.x + uv.x)
+            + vertical * (pixel_size.y * offset.y + uv.y);
+
+        let dir = (sample_pos - self.position).normalized();
+
+        Ray {
+            origin: self.position,
+            dir,
+        }
+    }
+
+    /// Generate a SceneVM camera
+    fn as_scenevm_camera(&self) -> scenevm::Camera3D {
+        let basis = self.basis_vectors();
+        scenevm::Camera3D {
+            kind: scenevm::CameraKind::OrbitPersp,
+            pos: self.position,
+            forward: basis.0,
+            right: basis.1,
+            up: basis.2,
+            vfov_deg: self.fov,
+            near: self.near,
+            far: self.far,
+            ..Default::default()
+        }
+    }
+}
+
+/// Pull a chase camera in towards `pivot` when its boom passes through geometry.
+pub fn pull_in_camera(
+    camera: &mut dyn D3Camera,
+    pivot: Vec3<f32>,
+    collision: &crate::CollisionWorld,
+) {
+    const MARGIN: f32 = 0.2;
+    const MIN_DISTANCE: f32 = 0.3;
+
+    let position = camera.position();
+    if let Some(hit) = collision.raycast(pivot, position) {
+        let dir = (position - pivot).normalized();
+        let distance = (hit - MARGIN).max(MIN_DISTANCE);
+        camera.set_parameter_vec3("position", pivot + dir * distance);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn follow(camera: &mut D3ThirdPCamera, dt: f32, steps: usize, target: Vec3<f32>) {
+        camera.set_parameter_f32("dt", dt);
+        for _ in 0..steps {
+            camera.set_parameter_vec3("target", target);
+        }
+    }
+
+    #[test]
+    fn lag_does_not_depend_on_the_frame_rate() {
+        let mut slow = D3ThirdPCamera::new();
+        let mut fast = D3ThirdPCamera::new();
+        follow(&mut slow, LAG_REFERENCE_DT, 1, Vec3::zero());
+        follow(&mut fast, LAG_REFERENCE_DT, 1, Vec3::zero());
+
+        let target = Vec3::new(2.0, 0.0, 0.0);
+        follow(&mut slow, 1.0 / 30.0, 3, target);
+        follow(&mut fast, 1.0 / 120.0, 12, target);
+        assert!(slow.position.distance(fast.position) < 1e-4);
+
+        // Half of the way is left after one reference frame with the default lag.
+        let mut camera = D3ThirdPCamera::new();
+        follow(&mut camera, LAG_REFERENCE_DT, 1, Vec3::zero());
+        let start = camera.position;
+        follow(&mut camera, LAG_REFERENCE_DT, 1, target);
+        assert!((camera.position.x - start.x - 1.0).abs() < 1e-4);
+    }
+}
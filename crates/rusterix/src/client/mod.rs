@@ -416,6 +416,8 @@ pub struct Client {
     actions_panel_catalog_rules: String,
     actions_panel_catalog_class: Option<String>,
     actions_panel_catalog: Vec<rules_ui::ActionCatalogGroup>,
+    journal_requested: bool,
+    quest_events: Vec<crate::QuestEvent>,
 }
 
 impl Default for Client {
@@ -791,13 +793,13 @@ impl Client {
     }
 
     /// Returns the currently active game-widget camera mode if present.
-    /// Prioritizes first-person over iso over 2D when multiple game widgets exist.
+    /// Prioritizes first- and third-person over iso over 2D when multiple game widgets exist.
     pub fn active_game_widget_camera_mode(&self) -> Option<PlayerCamera> {
         let mut found_iso = false;
         let mut found_d2 = false;
         for widget in self.game_widgets.values() {
             match widget.camera {
-                PlayerCamera::D3FirstP | PlayerCamera::D3FirstPGrid => {
+                PlayerCamera::D3FirstP | PlayerCamera::D3FirstPGrid | PlayerCamera::D3ThirdP => {
                     return Some(widget.camera.clone());
                 }
                 PlayerCamera::D3Iso => found_iso = true,
@@ -832,6 +834,7 @@ impl Client {
             "iso_grid" => Some(PlayerCamera::D2Grid),
             "firstp" => Some(PlayerCamera::D3FirstP),
             "firstp_grid" => Some(PlayerCamera::D3FirstPGrid),
+            "thirdp" => Some(PlayerCamera::D3ThirdP),
             _ => None,
         }
     }
@@ -973,6 +976,8 @@ impl Client {
             actions_panel_catalog_rules: String::new(),
             actions_panel_catalog_class: None,
             actions_panel_catalog: Vec::new(),
+            journal_requested: false,
+            quest_events: Vec::new(),
         }
     }
 
@@ -2354,6 +2359,7 @@ impl Client {
         let mut debug_button_overlay = Duration::ZERO;
         let mut debug_misc = Duration::ZERO;
 
+        let messages = self.append_journal_messages(map, messages);
         scene_handler.vm.set_active_vm(0);
         // Keep scene timing in sync with config
         scene_handler.set_timings(self.target_fps as f32, self.game_tick_ms);
@@ -3123,6 +3129,7 @@ impl Client {
             self.overlay = TheRGBABuffer::new(TheDim::sized(w, h));
         }
         self.overlay.fill([0, 0, 0, 0]);
+        let messages = self.append_journal_messages(map, messages);
         let say_config = self.config.clone();
         let say_fallback_color = self.messages_font_color;
 
@@ -3743,11 +3750,42 @@ impl Client {
         &mut self,
         requests: Vec<crate::server::OpenContainerRequest>,
     ) {
-        for (item_id, owner_entity_id) in requests {
+        for (item_id, owner_entity_id, _) in requests {
             self.open_container_panel_at_anchor(item_id, owner_entity_id, None);
         }
     }
 
+    /// Keep the most recent quest events of the server for game UIs.
+    pub fn process_quest_events(&mut self, events: Vec<crate::QuestEvent>) {
+        const MAX_QUEST_EVENTS: usize = 32;
+        self.quest_events.extend(events);
+        let overflow = self.quest_events.len().saturating_sub(MAX_QUEST_EVENTS);
+        self.quest_events.drain(..overflow);
+    }
+
+    /// The most recent quest events, oldest first.
+    pub fn quest_events(&self) -> &[crate::QuestEvent] {
+        &self.quest_events
+    }
+
+    /// When the journal was requested, add it to the message widgets.
+    fn append_journal_messages(
+        &mut self,
+        map: &Map,
+        mut messages: Vec<crate::server::Message>,
+    ) -> Vec<crate::server::Message> {
+        if !std::mem::take(&mut self.journal_requested) {
+            return messages;
+        }
+        let actor = Self::resolve_party_entity(map, None);
+        let journal = rules_ui::describe_journal(actor);
+        let receiver = actor.map(|actor| actor.id).unwrap_or_default();
+        for line in std::iter::once(journal.title).chain(journal.lines) {
+            messages.push((None, None, receiver, line, "system".to_string()));
+        }
+        messages
+    }
+
     fn open_container_panel_at_anchor(
         &mut self,
         item_id: u32,
@@ -3817,6 +3855,10 @@ impl Client {
                 self.toggle_actions_panel();
                 true
             }
+            "journal" | "quests" => {
+                self.journal_requested = true;
+                true
+            }
             _ => false,
         }
     }
@@ -394,6 +394,9 @@ pub fn describe_command(
                 lines: Vec::new(),
             }
         }
+        ClientCommandBinding::Ui(command) if matches!(command.as_str(), "journal" | "quests") => {
+            describe_journal(actor)
+        }
         ClientCommandBinding::Ui(command) => RulesDescription {
             title: title_case(&command.replace('_', " ")),
             subtitle: Some("Interface".to_string()),
@@ -421,6 +424,21 @@ pub fn describe_command(
     }
 }
 
+/// The quest journal of the actor, active quests with their objectives first.
+pub fn describe_journal(actor: Option<&Entity>) -> RulesDescription {
+    let mut lines = actor
+        .map(|actor| actor.quests.journal_lines())
+        .unwrap_or_default();
+    if lines.is_empty() {
+        lines.push("No quests yet.".to_string());
+    }
+    RulesDescription {
+        title: "Journal".to_string(),
+        subtitle: Some("Quests".to_string()),
+        lines,
+    }
+}
+
 pub fn command_state(assets: &Assets, actor: Option<&Entity>, command: &str) -> CommandState {
     let Some(actor) = actor else {
         return CommandState::default();
@@ -528,6 +546,7 @@ fn effective_rules_attribute_inner(
                     visiting,
                 ),
             })
+            .ok()
             .map(|mut value| {
                 if let Some(minimum) = stat.minimum {
                     value = value.max(minimum);
@@ -6,6 +6,7 @@ pub enum TextCommand {
     Look(Option<String>),
     Inventory,
     Stats,
+    Journal,
     Move(String),
     Go(String),
     Intent {
@@ -19,6 +20,20 @@ pub enum TextCommand {
     Craft {
         recipe: String,
     },
+    /// List the wares of a nearby vendor.
+    List {
+        vendor: Option<String>,
+    },
+    Buy {
+        item: String,
+        quantity: u32,
+        vendor: Option<String>,
+    },
+    Sell {
+        item: String,
+        quantity: u32,
+        vendor: Option<String>,
+    },
     PutIn {
         item: String,
         container: String,
@@ -167,6 +182,8 @@ pub fn parse_text_command(
         "look" | "l" => return TextCommand::Look(None),
         "inventory" | "inv" => return TextCommand::Inventory,
         "stats" | "stat" => return TextCommand::Stats,
+        "journal" | "quests" => return TextCommand::Journal,
+        "list" | "wares" => return TextCommand::List { vendor: None },
         "help" => return TextCommand::Unknown,
         _ => {}
     }
@@ -203,6 +220,46 @@ pub fn parse_text_command(
         };
     }
 
+    if lower.starts_with("list ") {
+        let vendor = trimmed.get("list ".len()..).unwrap_or("").trim();
+        return TextCommand::List {
+            vendor: Some(vendor.to_string()),
+        };
+    }
+
+    for (verb, separator) in [("buy ", " from "), ("sell ", " to ")] {
+        if !lower.starts_with(verb) {
+            continue;
+        }
+        let payload = trimmed.get(verb.len()..).unwrap_or("").trim();
+        let (item, vendor) = match split_container_transfer(payload, &[separator]) {
+            Some((item, vendor)) => (item, Some(vendor)),
+            None => (payload.to_string(), None),
+        };
+        let (quantity, item) = match item.split_once(char::is_whitespace) {
+            Some((count, rest)) if count.parse::<u32>().is_ok_and(|count| count > 0) => {
+                (count.parse::<u32>().unwrap_or(1), rest.trim().to_string())
+            }
+            _ => (1, item),
+        };
+        if item.is_empty() {
+            return TextCommand::Unknown;
+        }
+        return if verb == "buy " {
+            TextCommand::Buy {
+                item,
+                quantity,
+                vendor,
+            }
+        } else {
+            TextCommand::Sell {
+                item,
+                quantity,
+                vendor,
+            }
+        };
+    }
+
     if lower.starts_with("put ") {
         let payload = trimmed.get("put ".len()..).unwrap_or("").trim();
         if let Some((item, container)) = split_container_transfer(payload, &[" in ", " into "]) {
@@ -486,4 +543,52 @@ mod tests {
             }
         );
     }
+
+    #[test]
+    fn parses_trade_commands() {
+        let parse = |input: &str| {
+            parse_text_command(
+                input,
+                &BTreeSet::new(),
+                &BTreeSet::new(),
+                &BTreeSet::new(),
+                &BTreeSet::new(),
+            )
+        };
+        assert_eq!(parse("list"), TextCommand::List { vendor: None });
+        assert_eq!(
+            parse("list Mira"),
+            TextCommand::List {
+                vendor: Some("Mira".into())
+            }
+        );
+        assert_eq!(
+            parse("buy 3 wild herb from Mira"),
+            TextCommand::Buy {
+                item: "wild herb".into(),
+                quantity: 3,
+                vendor: Some("Mira".into()),
+            }
+        );
+        assert_eq!(
+            parse("sell linen shirt"),
+            TextCommand::Sell {
+                item: "linen shirt".into(),
+                quantity: 1,
+                vendor: None,
+            }
+        );
+        assert_eq!(parse("buy"), TextCommand::Unknown);
+    }
+
+    #[test]
+    fn parses_journal_command() {
+        let none = BTreeSet::new();
+        for input in ["journal", "Quests"] {
+            assert_eq!(
+                parse_text_command(input, &none, &none, &none, &none),
+                TextCommand::Journal
+            );
+        }
+    }
 }
@@ -1,8 +1,10 @@
+use crate::camera::d3thirdp::pull_in_camera;
 use crate::client::draw2d::Draw2D;
 use crate::client::{apply_2d_visibility_mask, draw2d};
+use crate::collision_world::opening_geo_for_item;
 use crate::prelude::*;
 use crate::{
-    Assets, Map, MapMini, Pixel, PlayerCamera, Rect, SceneHandler, Value, WHITE,
+    Assets, CollisionWorld, Map, MapMini, Pixel, PlayerCamera, Rect, SceneHandler, Value, WHITE,
     avatar_builder::AvatarFrameStyle,
 };
 use crate::{ValueGroups, ValueTomlLoader};
@@ -48,6 +50,25 @@ fn render_debug_log(message: &str) {
     }
 }
 
+/// Mirror the door and opening states of the map's items into `collision`,
+/// like the server does when an item's `blocking` attribute changes.
+fn sync_opening_states(collision: &mut CollisionWorld, map: &Map) {
+    for item in &map.items {
+        let (Some(blocking), Some(geo_id)) = (
+            item.attributes.get_bool("blocking"),
+            opening_geo_for_item(item),
+        ) else {
+            continue;
+        };
+        if collision
+            .get_opening_state(&geo_id)
+            .is_none_or(|state| state.is_passable == blocking)
+        {
+            collision.set_opening_state(geo_id, !blocking);
+        }
+    }
+}
+
 pub struct GameWidget {
     pub name: String,
     pub scenemanager: SceneManager,
@@ -95,6 +116,12 @@ pub struct GameWidget {
     pub force_dynamics_rebuild: bool,
     pub firstp_eye_level: f32,
     pub(crate) firstp_camera_y: Option<f32>,
+    /// Static collision of the current map, built on demand for the chase camera.
+    pub(crate) chase_collision: Option<CollisionWorld>,
+    /// `map.changed` the chase collision was built for.
+    pub(crate) chase_collision_map_changed: u32,
+    /// Last chase camera update, for frame-rate independent easing.
+    pub(crate) chase_camera_updated_at: Option<Instant>,
     pub loaded_chunks: FxHashSet<(i32, i32)>,
     pub stream_load_radius_chunks: i32,
     pub stream_prefetch_radius_chunks: i32,
@@ -180,6 +207,9 @@ impl GameWidget {
             force_dynamics_rebuild: true,
             firstp_eye_level: 1.7,
             firstp_camera_y: None,
+            chase_collision: None,
+            chase_collision_map_changed: 0,
+            chase_camera_updated_at: None,
             loaded_chunks: FxHashSet::default(),
             stream_load_radius_chunks: 2,
             stream_prefetch_radius_chunks: 5,
@@ -230,6 +260,15 @@ impl GameWidget {
         }
     }
 
+    fn apply_thirdp_camera_overrides(&self, thirdp: &mut D3ThirdPCamera) {
+        if let Some(camera) = self.table.get("camera") {
+            for key in ["distance", "height", "lag", "fov"] {
+                let default = thirdp.get_parameter_f32(key);
+                thirdp.set_parameter_f32(key, camera.get_float_default(key, default));
+            }
+        }
+    }
+
     pub fn set_camera_mode(&mut self, camera: PlayerCamera) {
         self.camera = camera;
         self.force_dynamics_rebuild = true;
@@ -244,6 +283,11 @@ impl GameWidget {
             PlayerCamera::D3FirstP | PlayerCamera::D3FirstPGrid => {
                 self.camera_d3 = Box::new(D3FirstPCamera::new());
             }
+            PlayerCamera::D3ThirdP => {
+                let mut thirdp = D3ThirdPCamera::new();
+                self.apply_thirdp_camera_overrides(&mut thirdp);
+                self.camera_d3 = Box::new(thirdp);
+            }
         }
     }
 
@@ -263,7 +307,23 @@ impl GameWidget {
             visual_entity.apply_to_camera(&mut self.camera_d3, self.firstp_eye_level);
         } else {
             self.firstp_camera_y = None;
+            if self.camera_d3.id() == "thirdp" {
+                let now = Instant::now();
+                let dt = self
+                    .chase_camera_updated_at
+                    .map(|last| now.duration_since(last).as_secs_f32().min(0.25))
+                    .unwrap_or(0.0);
+                self.chase_camera_updated_at = Some(now);
+                self.camera_d3.set_parameter_f32("dt", dt);
+            }
             entity.apply_to_camera(&mut self.camera_d3, self.firstp_eye_level);
+            if self.camera_d3.id() == "thirdp"
+                && let Some(collision) = &self.chase_collision
+            {
+                let mut pivot = entity.position;
+                pivot.y += self.firstp_eye_level;
+                pull_in_camera(self.camera_d3.as_mut(), pivot, collision);
+            }
         }
     }
 
@@ -314,6 +374,7 @@ impl GameWidget {
                     "iso" => self.set_camera_mode(PlayerCamera::D3Iso),
                     "firstp" => self.set_camera_mode(PlayerCamera::D3FirstP),
                     "firstp_grid" => self.set_camera_mode(PlayerCamera::D3FirstPGrid),
+                    "thirdp" => self.set_camera_mode(PlayerCamera::D3ThirdP),
                     "2d_grid" => self.set_camera_mode(PlayerCamera::D2Grid),
                     _ => self.set_camera_mode(PlayerCamera::D2),
                 }
@@ -722,6 +783,7 @@ impl GameWidget {
             .set_focus_chunk(Some(self.player_chunk_origin(32)));
         self.build_region_name = map.name.clone();
         self.build_map_changed = map.changed;
+        self.chase_collision = None;
         self.iso_hidden_sectors.clear();
         self.iso_sector_fade.clear();
         self.iso_hidden_geometry_objects.clear();
@@ -755,6 +817,15 @@ impl GameWidget {
             self.force_dynamics_rebuild = false;
         }
 
+        if self.camera == PlayerCamera::D3ThirdP {
+            if self.chase_collision.is_none() || self.chase_collision_map_changed != map.changed {
+                self.chase_collision = Some(CollisionWorld::from_map(map, assets));
+                self.chase_collision_map_changed = map.changed;
+            }
+            if let Some(collision) = &mut self.chase_collision {
+                sync_opening_states(collision, map);
+            }
+        }
         self.update_player_context(map);
         let avatar_frame_styles = self.avatar_frame_styles(map, assets);
         if Self::is_2d_camera(&self.camera) {
@@ -1681,3 +1752,29 @@ impl GameWidget {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Item;
+    use scenevm::GeoId;
+
+    #[test]
+    fn chase_collision_follows_door_items() {
+        let mut map = Map::default();
+        let mut door = Item::default();
+        door.set_attribute("sector_id", Value::UInt(7));
+        door.set_attribute("blocking", Value::Bool(true));
+        map.items.push(door);
+
+        let mut collision = CollisionWorld::new(10);
+        sync_opening_states(&mut collision, &map);
+        let state = collision.get_opening_state(&GeoId::Sector(7)).unwrap();
+        assert!(!state.is_passable);
+
+        map.items[0].set_attribute("blocking", Value::Bool(false));
+        sync_opening_states(&mut collision, &map);
+        let state = collision.get_opening_state(&GeoId::Sector(7)).unwrap();
+        assert!(state.is_passable);
+    }
+}
@@ -166,7 +166,7 @@ impl MessagesWidget {
 
     fn accepts_choice(&self, choice: &Choice) -> bool {
         match choice {
-            Choice::ItemToSell(_, _, _, _, _) => self.handle_offer_inventory,
+            Choice::ItemToSell(..) => self.handle_offer_inventory,
             Choice::ScriptChoice(_, _, _, _, _, _, _) => self.handle_multiple_choice,
             Choice::DialogChoice(_) => self.handle_dialogs,
             Choice::Cancel(_, _, _, _) => true,
@@ -509,26 +509,15 @@ impl MessagesWidget {
                 let mut item_price = 0;
 
                 match choice {
-                    Choice::ItemToSell(item_id, seller_id, _, _, _) => {
-                        for entity in map.entities.iter() {
-                            if entity.id == *seller_id {
-                                for item in entity.inventory.iter() {
-                                    if let Some(item) = item {
-                                        if item.id == *item_id {
-                                            item_name = item
-                                                .get_attr_string("name")
-                                                .unwrap_or("".to_string());
-                                            item_price = item
-                                                .attributes
-                                                .get("worth")
-                                                .and_then(|worth| worth.to_i32())
-                                                .unwrap_or(0)
-                                                as i64;
-                                            break;
-                                        }
-                                    }
-                                }
-                            }
+                    Choice::ItemToSell(item_id, seller_id, _, _, _, price) => {
+                        item_price = *price;
+                        if let Some(item) = map
+                            .entities
+                            .iter()
+                            .find(|entity| entity.id == *seller_id)
+                            .and_then(|entity| entity.get_item(*item_id))
+                        {
+                            item_name = item.get_attr_string("name").unwrap_or_default();
                         }
                     }
                     Choice::ScriptChoice(
@@ -579,7 +568,7 @@ impl MessagesWidget {
                     }
                     _ => {}
                 }
-                let text = if matches!(choice, Choice::ItemToSell(_, _, _, _, _)) {
+                let text = if matches!(choice, Choice::ItemToSell(..)) {
                     let label = format!("{}) {}", index + 1, item_name);
                     let price = currencies.format_base_amount(item_price);
                     format!("{}{}{}", label, Self::CHOICE_COLUMN_SEPARATOR, price)
@@ -1,3 +1,4 @@
+use crate::{Item, Value};
 use pathfinding::prelude::astar;
 use rustc_hash::FxHashMap;
 use scenevm::GeoId;
@@ -226,6 +227,31 @@ impl CollisionWorld {
         }
     }
 
+    /// Build collision for every chunk covered by the map.
+    pub fn from_map(map: &crate::Map, assets: &crate::Assets) -> Self {
+        use crate::chunkbuilder::{ChunkBuilder, d3chunkbuilder::D3ChunkBuilder};
+
+        let mut world = Self::default();
+        if map.vertices.is_empty() && map.geometry_objects.is_empty() {
+            return world;
+        }
+
+        let mut chunk_builder = D3ChunkBuilder::new();
+        let chunk_size = world.chunk_size;
+        let bbox = map.bbox();
+        let min_chunk = world.world_to_chunk(bbox.min);
+        let max_chunk = world.world_to_chunk(bbox.max);
+        for cy in min_chunk.y..=max_chunk.y {
+            for cx in min_chunk.x..=max_chunk.x {
+                let chunk_origin = Vec2::new(cx, cy);
+                let collision =
+                    chunk_builder.build_collision(map, assets, chunk_origin, chunk_size);
+                world.update_chunk(chunk_origin, collision);
+            }
+        }
+        world
+    }
+
     fn geometry_collision_radius(radius: f32) -> f32 {
         (radius + WORLD_GEOMETRY_RADIUS_PADDING).max(0.0)
     }
@@ -274,6 +300,118 @@ impl CollisionWorld {
         false
     }
 
+    /// Cast a ray from `from` to `to` against static geometry and blocking openings,
+    /// returning the distance from `from` to the first hit. Geometry which already
+    /// contains `from` is ignored.
+    pub fn raycast(&self, from: Vec3<f32>, to: Vec3<f32>) -> Option<f32> {
+        let delta = to - from;
+        let length = delta.magnitude();
+        if length <= f32::EPSILON {
+            return None;
+        }
+
+        let min_chunk = self.world_to_chunk(Vec2::new(from.x.min(to.x), from.z.min(to.z)));
+        let max_chunk = self.world_to_chunk(Vec2::new(from.x.max(to.x), from.z.max(to.z)));
+        let mut nearest: Option<f32> = None;
+        let mut consider = |t: Option<f32>| {
+            if let Some(t) = t
+                && nearest.is_none_or(|best| t < best)
+            {
+                nearest = Some(t);
+            }
+        };
+
+        for cy in min_chunk.y - 1..=max_chunk.y + 1 {
+            for cx in min_chunk.x - 1..=max_chunk.x + 1 {
+                let Some(chunk_collision) = self.chunks.get(&Vec2::new(cx, cy)) else {
+                    continue;
+                };
+                for volume in &chunk_collision.static_volumes {
+                    consider(Self::ray_aabb(from, delta, volume.min, volume.max));
+                }
+                for barrier in &chunk_collision.static_barriers {
+                    consider(Self::ray_wall(
+                        from,
+                        delta,
+                        barrier.start,
+                        barrier.end,
+                        barrier.min_y,
+                        barrier.max_y,
+                    ));
+                }
+                for opening in &chunk_collision.dynamic_openings {
+                    if !self.opening_is_blocking(opening) || opening.boundary_2d.len() < 2 {
+                        continue;
+                    }
+                    let polygon = &opening.boundary_2d;
+                    for i in 0..polygon.len() {
+                        consider(Self::ray_wall(
+                            from,
+                            delta,
+                            polygon[i],
+                            polygon[(i + 1) % polygon.len()],
+                            opening.floor_height,
+                            opening.ceiling_height,
+                        ));
+                    }
+                }
+            }
+        }
+
+        nearest.map(|t| t * length)
+    }
+
+    /// Entry parameter (0..=1) of the segment `origin + delta * t` into an AABB.
+    fn ray_aabb(
+        origin: Vec3<f32>,
+        delta: Vec3<f32>,
+        min: Vec3<f32>,
+        max: Vec3<f32>,
+    ) -> Option<f32> {
+        let mut t_enter = f32::NEG_INFINITY;
+        let mut t_exit = f32::INFINITY;
+        for axis in 0..3 {
+            if delta[axis].abs() <= f32::EPSILON {
+                if origin[axis] < min[axis] || origin[axis] > max[axis] {
+                    return None;
+                }
+                continue;
+            }
+            let t0 = (min[axis] - origin[axis]) / delta[axis];
+            let t1 = (max[axis] - origin[axis]) / delta[axis];
+            t_enter = t_enter.max(t0.min(t1));
+            t_exit = t_exit.min(t0.max(t1));
+        }
+        (t_enter >= 0.0 && t_enter <= t_exit && t_enter <= 1.0).then_some(t_enter)
+    }
+
+    /// Hit parameter (0..=1) of the segment `origin + delta * t` against a vertical
+    /// wall spanning `start`..`end` in XZ and `min_y`..`max_y` in height.
+    fn ray_wall(
+        origin: Vec3<f32>,
+        delta: Vec3<f32>,
+        start: Vec2<f32>,
+        end: Vec2<f32>,
+        min_y: f32,
+        max_y: f32,
+    ) -> Option<f32> {
+        let p = Vec2::new(origin.x, origin.z);
+        let r = Vec2::new(delta.x, delta.z);
+        let s = end - start;
+        let denom = r.x * s.y - r.y * s.x;
+        if denom.abs() <= f32::EPSILON {
+            return None;
+        }
+        let diff = start - p;
+        let t = (diff.x * s.y - diff.y * s.x) / denom;
+        let u = (diff.x * r.y - diff.y * r.x) / denom;
+        if !(0.0..=1.0).contains(&t) || !(0.0..=1.0).contains(&u) {
+            return None;
+        }
+        let y = origin.y + delta.y * t;
+        (y >= min_y && y <= max_y).then_some(t)
+    }
+
     /// Move in the XZ plane with collision sliding, returning the new position and whether a collision occurred.
     pub fn move_distance(
         &self,
@@ -2318,6 +2456,35 @@ impl CollisionWorld {
     }
 }
 
+/// The dynamic opening a door or gate item controls: its geometry object,
+/// its sector or the profile hole it fills.
+pub(crate) fn opening_geo_for_item(item: &Item) -> Option<GeoId> {
+    if let Some(object_id) = item.attributes.get_id("geometry_object_id") {
+        return Some(GeoId::GeometryObject(object_id));
+    }
+
+    if let Some(sector_id) = match item.attributes.get("sector_id") {
+        Some(Value::UInt(v)) => Some(*v),
+        Some(Value::Int(v)) if *v >= 0 => Some(*v as u32),
+        Some(Value::Int64(v)) if *v >= 0 => Some(*v as u32),
+        _ => None,
+    } {
+        return Some(GeoId::Sector(sector_id));
+    }
+
+    let host_id = match item.attributes.get("profile_host_sector_id") {
+        Some(Value::UInt(v)) => Some(*v),
+        _ => None,
+    }?;
+
+    let profile_id = match item.attributes.get("profile_sector_id") {
+        Some(Value::UInt(v)) => Some(*v),
+        _ => None,
+    }?;
+
+    Some(GeoId::Hole(host_id, profile_id))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -2435,6 +2602,35 @@ mod tests {
         assert!(end.z > 0.7);
     }
 
+    #[test]
+    fn test_raycast_hits_nearest_wall() {
+        let mut world = CollisionWorld::new(10);
+        let mut chunk = ChunkCollision::new();
+        chunk.static_volumes.push(BlockingVolume {
+            geo_id: GeoId::Sector(1),
+            min: Vec3::new(4.0, 0.0, -2.0),
+            max: Vec3::new(4.5, 3.0, 2.0),
+        });
+        chunk.static_barriers.push(StaticBarrier {
+            geo_id: GeoId::Linedef(2),
+            start: Vec2::new(2.0, -2.0),
+            end: Vec2::new(2.0, 2.0),
+            min_y: 0.0,
+            max_y: 1.0,
+        });
+        world.update_chunk(Vec2::new(0, 0), chunk);
+
+        let from = Vec3::new(0.0, 1.5, 0.0);
+        let hit = world.raycast(from, Vec3::new(8.0, 1.5, 0.0)).unwrap();
+        assert!((hit - 4.0).abs() < 1e-4);
+
+        let low = world.raycast(Vec3::new(0.0, 0.5, 0.0), Vec3::new(8.0, 0.5, 0.0));
+        assert!((low.unwrap() - 2.0).abs() < 1e-4);
+
+        assert!(world.raycast(from, Vec3::new(3.0, 1.5, 0.0)).is_none());
+        assert!(world.raycast(from, Vec3::new(0.0, 1.5, 8.0)).is_none());
+    }
+
     #[test]
     fn test_floor_movement_steps_over_low_floor_edge() {
         let mut world = CollisionWorld::new(10);
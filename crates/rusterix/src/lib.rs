@@ -141,7 +141,10 @@ pub use crate::{
         AvatarPerspectiveCount, AvatarShadingOptions,
     },
     batch::{CullMode, GeometrySource, PrimitiveMode, batch2d::Batch2D, batch3d::Batch3D},
-    camera::{D3Camera, d3firstp::D3FirstPCamera, d3iso::D3IsoCamera, d3orbit::D3OrbitCamera},
+    camera::{
+        D3Camera, d3firstp::D3FirstPCamera, d3iso::D3IsoCamera, d3orbit::D3OrbitCamera,
+        d3thirdp::D3ThirdPCamera,
+    },
     chunk::{BillboardMetadata, Chunk},
     chunkbuilder::{
         ChunkBuilder,
@@ -211,6 +214,10 @@ pub use crate::{
         item::{Item, ItemUpdate},
         message::EntityAction,
         message::{Choice, MultipleChoice, PaletteRemap2DState, PlayerCamera, RegionMessage},
+        quest::{
+            QuestEvent, QuestEventKind, QuestLog, QuestObjectiveProgress, QuestProgress,
+            QuestStatus,
+        },
         region::RegionInstance,
         regionctx::RegionCtx,
     },
@@ -242,13 +249,14 @@ pub mod prelude {
     pub use crate::vm::{EldrinDebugEntry, EldrinDebugFrame, EldrinDebugModule, EldrinDebugTarget};
     pub use crate::{
         Assets, Choice, Currencies, Currency, Entity, EntityUpdate, Item, ItemUpdate,
-        MultipleChoice, PaletteRemap2DState, RegionInstance, RegionMessage, Server, Wallet,
+        MultipleChoice, PaletteRemap2DState, QuestEvent, QuestLog, RegionInstance, RegionMessage,
+        Server, Wallet,
     };
     pub use crate::{BLACK, Pixel, TRANSPARENT, WHITE};
     pub use crate::{Batch2D, Batch3D, CullMode, GeometrySource, PrimitiveMode};
     #[cfg(feature = "graphics")]
     pub use crate::{Command, Daylight, MsgParser, Tok};
-    pub use crate::{D3Camera, D3FirstPCamera, D3IsoCamera, D3OrbitCamera};
+    pub use crate::{D3Camera, D3FirstPCamera, D3IsoCamera, D3OrbitCamera, D3ThirdPCamera};
     pub use crate::{GridShader, Shader, VGrayGradientShader};
     pub use crate::{
         Keyform, Light, LightType, Map, MapMeta, MapToolType, NoiseTarget, OrganicBushCluster,
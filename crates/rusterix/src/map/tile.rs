@@ -388,12 +388,19 @@ impl Default for TileMaterialMeta {
     }
 }
 
+fn is_zero_fps(fps: &f32) -> bool {
+    *fps == 0.0
+}
+
 #[derive(Serialize, Deserialize, PartialEq, Clone, Debug, Default)]
 pub struct Tile {
     pub id: Uuid,
     pub role: TileRole,
     /// The textures of the tiles. Primary source.
     pub textures: Vec<Texture>,
+    /// Playback rate of an animated recipe tile, `0` for hand-made frames.
+    #[serde(default, skip_serializing_if = "is_zero_fps")]
+    pub fps: f32,
     /// The module if the textures are shader generated
     pub module: Option<serde_json::Value>,
     /// For top down 2D scenarios
@@ -474,6 +481,11 @@ impl Tile {
     /// Creator, and runtime clients for a procedural Tile recipe.
     pub fn apply_procedural_recipe_metadata(&mut self, recipe: &Recipe) {
         self.blocking = recipe.blocking;
+        self.fps = if recipe.animation.frames > 1 {
+            recipe.animation.fps
+        } else {
+            0.0
+        };
         self.procedural.coverage = recipe.coverage;
         self.recipe_placement = match recipe.placement {
             RecipePlacement::Surface => TileRecipePlacement::Surface,
@@ -558,6 +570,7 @@ impl Tile {
             id: Uuid::new_v4(),
             role: TileRole::ManMade,
             textures: vec![texture],
+            fps: 0.0,
             module: None,
             blocking: false,
             scale: 1.0,
@@ -674,6 +687,7 @@ impl Tile {
             id: self.id,
             role: self.role,
             textures: resized_textures,
+            fps: self.fps,
             module: self.module.clone(),
             blocking: self.blocking,
             scale: self.scale,
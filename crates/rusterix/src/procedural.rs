@@ -1,6 +1,7 @@
 use crate::{Map, MapCamera, PixelSource, Tile, TileRole, Value};
 use indexmap::IndexMap;
 use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
 use rand::{Rng, SeedableRng};
 use std::collections::HashMap;
 use theframework::prelude::*;
@@ -84,6 +85,14 @@ pub struct ProceduralConfig {
     pub room_count: i32,
     pub room_min_size: i32,
     pub room_max_size: i32,
+    /// Chance that a cell starts out solid in the `caves` generator.
+    pub cave_fill: f32,
+    /// Smoothing passes of the `caves` generator.
+    pub cave_steps: i32,
+    /// Share of the map the `drunkards_walk` generator opens up.
+    pub tunnel_coverage: f32,
+    /// Region names whose maps `prefab_rooms` uses as rooms.
+    pub prefabs: Vec<String>,
     item_choices: HashMap<String, Vec<ProceduralChoice>>,
     character_rules: HashMap<String, ProceduralCharacterRule>,
 }
@@ -103,6 +112,10 @@ impl Default for ProceduralConfig {
             room_count: 6,
             room_min_size: 6,
             room_max_size: 10,
+            cave_fill: 0.45,
+            cave_steps: 4,
+            tunnel_coverage: 0.3,
+            prefabs: Vec::new(),
             item_choices: HashMap::new(),
             character_rules: HashMap::new(),
         }
@@ -152,6 +165,21 @@ impl ProceduralConfig {
         if let Some(value) = get_value("procedural.door_randomness").and_then(value_as_f32) {
             self.door_randomness = value.clamp(0.0, 1.0);
         }
+        if let Some(value) = get_value("procedural.cave_fill").and_then(value_as_f32) {
+            self.cave_fill = value.clamp(0.1, 0.8);
+        }
+        if let Some(value) = get_value("procedural.cave_steps").and_then(value_as_i64) {
+            self.cave_steps = (value as i32).clamp(0, 12);
+        }
+        if let Some(value) = get_value("procedural.tunnel_coverage").and_then(value_as_f32) {
+            self.tunnel_coverage = value.clamp(0.05, 0.8);
+        }
+        if let Some(value) = get_value("procedural.generator").and_then(value_as_string) {
+            let value = value.trim().to_ascii_lowercase();
+            if is_supported_generator(&value) {
+                self.generator = value;
+            }
+        }
         if let Some(value) = get_value("procedural.door_placement").and_then(value_as_string) {
             let value = value.trim().to_ascii_lowercase();
             if matches!(value.as_str(), "entrances" | "exits" | "both") {
@@ -196,6 +224,85 @@ struct GeneratedGrid {
     rooms: Vec<Room>,
 }
 
+/// The generators understood by [`bake_procedural`].
+pub const PROCEDURAL_GENERATORS: &[&str] = &[
+    "connected_rooms",
+    "bsp_rooms",
+    "caves",
+    "drunkards_walk",
+    "prefab_rooms",
+];
+
+pub fn is_supported_generator(generator: &str) -> bool {
+    PROCEDURAL_GENERATORS
+        .iter()
+        .any(|name| name.eq_ignore_ascii_case(generator.trim()))
+}
+
+/// The floor footprint of an authored region, used as a room shape by the
+/// `prefab_rooms` generator. The footprint keeps a one cell empty border for
+/// the walls and doors.
+#[derive(Clone, Debug)]
+pub struct ProceduralPrefab {
+    pub name: String,
+    pub width: i32,
+    pub height: i32,
+    floor: Vec<bool>,
+}
+
+impl ProceduralPrefab {
+    /// Rasterize the sectors of a map into one cell per world unit. Sectors
+    /// tagged as procedural walls are skipped. Returns `None` for maps without
+    /// floor or which are too large to be used as a room.
+    pub fn from_map(map: &Map) -> Option<Self> {
+        let floors = map
+            .sectors
+            .iter()
+            .filter(|sector| sector.properties.get_str("procedural_kind") != Some("wall"))
+            .collect::<Vec<_>>();
+        let mut bbox = floors.first()?.bounding_box(map);
+        for sector in &floors[1..] {
+            bbox.expand_bbox(sector.bounding_box(map));
+        }
+        let min_x = bbox.min.x.floor();
+        let min_y = bbox.min.y.floor();
+        let columns = (bbox.max.x.ceil() - min_x) as i32;
+        let rows = (bbox.max.y.ceil() - min_y) as i32;
+        if columns < 1 || rows < 1 || columns > 48 || rows > 48 {
+            return None;
+        }
+
+        let width = columns + 2;
+        let height = rows + 2;
+        let mut floor = vec![false; (width * height) as usize];
+        for y in 0..rows {
+            for x in 0..columns {
+                let point = Vec2::new(min_x + x as f32 + 0.5, min_y + y as f32 + 0.5);
+                if floors.iter().any(|sector| sector.is_inside(map, point)) {
+                    floor[((y + 1) * width + x + 1) as usize] = true;
+                }
+            }
+        }
+        if !floor.contains(&true) {
+            return None;
+        }
+        Some(Self {
+            name: map.name.clone(),
+            width,
+            height,
+            floor,
+        })
+    }
+
+    fn is_floor(&self, x: i32, y: i32) -> bool {
+        x >= 0
+            && y >= 0
+            && x < self.width
+            && y < self.height
+            && self.floor[(y * self.width + x) as usize]
+    }
+}
+
 pub fn parse_procedural_config(config: &str) -> Option<ProceduralConfig> {
     let parsed: toml::Value = toml::from_str(config).ok()?;
     let section = parsed.get("procedural")?.as_table()?;
@@ -233,11 +340,8 @@ fn parse_procedural_section(
             cfg.door_placement = value;
         }
     }
-    if let Some(v) = section
-        .get("door_randomness")
-        .and_then(|v| v.as_float().or_else(|| v.as_integer().map(|i| i as f64)))
-    {
-        cfg.door_randomness = (v as f32).clamp(0.0, 1.0);
+    if let Some(v) = section.get("door_randomness").and_then(toml_as_f32) {
+        cfg.door_randomness = v.clamp(0.0, 1.0);
     }
     if let Some(v) = section.get("width").and_then(toml::Value::as_integer) {
         cfg.width = (v as i32).clamp(16, 256);
@@ -264,20 +368,40 @@ fn parse_procedural_section(
     {
         cfg.room_max_size = (v as i32).clamp(cfg.room_min_size, 48);
     }
+    if let Some(v) = section.get("cave_fill").and_then(toml_as_f32) {
+        cfg.cave_fill = v.clamp(0.1, 0.8);
+    }
+    if let Some(v) = section.get("cave_steps").and_then(toml::Value::as_integer) {
+        cfg.cave_steps = (v as i32).clamp(0, 12);
+    }
+    if let Some(v) = section.get("tunnel_coverage").and_then(toml_as_f32) {
+        cfg.tunnel_coverage = v.clamp(0.05, 0.8);
+    }
+    if let Some(prefabs) = section.get("prefabs").and_then(toml::Value::as_array) {
+        cfg.prefabs = prefabs
+            .iter()
+            .filter_map(toml::Value::as_str)
+            .map(|name| name.trim().to_string())
+            .filter(|name| !name.is_empty())
+            .collect();
+    }
     cfg.item_choices = parse_item_choices(section);
     cfg.character_rules = parse_character_rules(section);
 
     Some(cfg)
 }
 
-pub fn bake_connected_rooms(
+/// Clear the map and build it with the configured generator. `prefabs` are
+/// the room shapes for `prefab_rooms`, other generators ignore them.
+pub fn bake_procedural(
     map: &mut Map,
     tiles: &IndexMap<Uuid, Tile>,
     cfg: &ProceduralConfig,
+    prefabs: &[ProceduralPrefab],
 ) -> ProceduralBuildOutput {
     clear_map_for_build(map);
     let choices = tile_choices(tiles, &cfg.style);
-    let generated = generate_grid(cfg);
+    let generated = generate_grid(cfg, prefabs);
     let mut tile_rng = StdRng::seed_from_u64(cfg.seed ^ 0x9e37_79b9_7f4a_7c15);
     let mut item_rng = StdRng::seed_from_u64(cfg.seed ^ 0x517c_c1b7_b272_220a);
     let mut character_rng = StdRng::seed_from_u64(cfg.seed ^ 0x6942_758f_2d8f_d7b3);
@@ -296,7 +420,7 @@ pub fn bake_connected_rooms(
                     output.character_spawns.push(ProceduralSpawn {
                         kind: kind.clone(),
                         name: choice.name.clone(),
-                        position: room_spawn_position(*room, cfg),
+                        position: room_spawn_position(&generated.cells, *room, cfg),
                     });
                 }
             }
@@ -358,18 +482,47 @@ pub fn bake_connected_rooms(
     output
 }
 
+fn toml_as_f32(value: &toml::Value) -> Option<f32> {
+    value
+        .as_float()
+        .or_else(|| value.as_integer().map(|value| value as f64))
+        .map(|value| value as f32)
+}
+
+/// Weighted choices as `name`/`weight`, `names`/`weights` or a `choices`
+/// array of `{ name, weight }` tables.
 fn parse_weighted_choices(table: &toml::map::Map<String, toml::Value>) -> Vec<ProceduralChoice> {
+    let weight_of = |value: Option<&toml::Value>| {
+        value.and_then(toml::Value::as_integer).unwrap_or(1).max(1) as u32
+    };
     let mut choices = Vec::new();
     if let Some(name) = table.get("name").and_then(toml::Value::as_str) {
         choices.push(ProceduralChoice {
             name: name.trim().to_string(),
-            weight: table
-                .get("weight")
-                .and_then(toml::Value::as_integer)
-                .unwrap_or(1)
-                .max(1) as u32,
+            weight: weight_of(table.get("weight")),
         });
     }
+    if let Some(names) = table.get("names").and_then(toml::Value::as_array) {
+        let weights = table.get("weights").and_then(toml::Value::as_array);
+        for (index, name) in names.iter().enumerate() {
+            if let Some(name) = name.as_str() {
+                choices.push(ProceduralChoice {
+                    name: name.trim().to_string(),
+                    weight: weight_of(weights.and_then(|weights| weights.get(index))),
+                });
+            }
+        }
+    }
+    if let Some(tables) = table.get("choices").and_then(toml::Value::as_array) {
+        for choice in tables.iter().filter_map(toml::Value::as_table) {
+            if let Some(name) = choice.get("name").and_then(toml::Value::as_str) {
+                choices.push(ProceduralChoice {
+                    name: name.trim().to_string(),
+                    weight: weight_of(choice.get("weight")),
+                });
+            }
+        }
+    }
 
     choices.retain(|choice| !choice.name.is_empty());
     choices
@@ -612,8 +765,77 @@ fn choose_weighted_choice<'a>(
     choices.first()
 }
 
-fn generate_grid(cfg: &ProceduralConfig) -> GeneratedGrid {
+fn generate_grid(cfg: &ProceduralConfig, prefabs: &[ProceduralPrefab]) -> GeneratedGrid {
     let mut rng = StdRng::seed_from_u64(cfg.seed);
+    let (mut cells, rooms) = match cfg.generator.trim().to_ascii_lowercase().as_str() {
+        "bsp_rooms" => bsp_rooms_layout(cfg, &mut rng),
+        "caves" => cave_layout(cfg, &mut rng),
+        "drunkards_walk" => tunnel_layout(cfg, &mut rng),
+        "prefab_rooms" => prefab_rooms_layout(cfg, prefabs, &mut rng),
+        _ => connected_rooms_layout(cfg, &mut rng),
+    };
+
+    let entrance = rooms
+        .last()
+        .map(|room| room_floor_cell(&cells, cfg, *room, room.marker()));
+    let exit = rooms
+        .first()
+        .map(|room| room_floor_cell(&cells, cfg, *room, room.marker()));
+    if let Some((x, y)) = entrance
+        && in_bounds(cfg.width, cfg.height, x, y)
+    {
+        cells[(y * cfg.width + x) as usize] = ProcCell::Entrance;
+    }
+    if let Some((x, y)) = exit
+        && in_bounds(cfg.width, cfg.height, x, y)
+    {
+        cells[(y * cfg.width + x) as usize] = ProcCell::Exit;
+    }
+
+    let floorish = |cell: ProcCell| {
+        matches!(
+            cell,
+            ProcCell::Floor
+                | ProcCell::Corridor
+                | ProcCell::Door
+                | ProcCell::Entrance
+                | ProcCell::Exit
+        )
+    };
+    let snapshot = cells.clone();
+    for y in 0..cfg.height {
+        for x in 0..cfg.width {
+            let idx = (y * cfg.width + x) as usize;
+            if snapshot[idx] != ProcCell::Empty {
+                continue;
+            }
+            let mut adjacent = false;
+            for dy in -1..=1 {
+                for dx in -1..=1 {
+                    if dx == 0 && dy == 0 {
+                        continue;
+                    }
+                    let nx = x + dx;
+                    let ny = y + dy;
+                    if in_bounds(cfg.width, cfg.height, nx, ny)
+                        && floorish(snapshot[(ny * cfg.width + nx) as usize])
+                    {
+                        adjacent = true;
+                    }
+                }
+            }
+            if adjacent {
+                cells[idx] = ProcCell::Wall;
+            }
+        }
+    }
+
+    GeneratedGrid { cells, rooms }
+}
+
+/// A chain of rectangular rooms, each connected to the previous one by a
+/// corridor.
+fn connected_rooms_layout(cfg: &ProceduralConfig, rng: &mut StdRng) -> (Vec<ProcCell>, Vec<Room>) {
     let mut cells = vec![ProcCell::Empty; (cfg.width * cfg.height) as usize];
     let mut rooms: Vec<Room> = Vec::new();
 
@@ -645,7 +867,7 @@ fn generate_grid(cfg: &ProceduralConfig) -> GeneratedGrid {
             let h = rng.random_range(cfg.room_min_size..=cfg.room_max_size);
             let gap = rng.random_range(2..=6);
             let dir = rng.random_range(0..4);
-            let Some(candidate) = candidate_next_room(prev, w, h, gap, dir, &mut rng) else {
+            let Some(candidate) = candidate_next_room(prev, w, h, gap, dir, rng) else {
                 continue;
             };
 
@@ -665,25 +887,8 @@ fn generate_grid(cfg: &ProceduralConfig) -> GeneratedGrid {
                 continue;
             }
 
-            carve_corridor(&mut cells, cfg.width, cfg.height, &candidate.corridor);
             stamp_room(&mut cells, cfg.width, candidate.room);
-            cells[(candidate.prev_door.1 * cfg.width + candidate.prev_door.0) as usize] =
-                ProcCell::Floor;
-            cells[(candidate.next_door.1 * cfg.width + candidate.next_door.0) as usize] =
-                ProcCell::Floor;
-
-            if (cfg.door_placement == "exits" || cfg.door_placement == "both")
-                && should_place_door(cfg, &mut rng)
-            {
-                cells[(candidate.prev_door.1 * cfg.width + candidate.prev_door.0) as usize] =
-                    ProcCell::Door;
-            }
-            if (cfg.door_placement == "entrances" || cfg.door_placement == "both")
-                && should_place_door(cfg, &mut rng)
-            {
-                cells[(candidate.next_door.1 * cfg.width + candidate.next_door.0) as usize] =
-                    ProcCell::Door;
-            }
+            open_connection(cfg, &mut cells, &candidate, rng);
             rooms.push(candidate.room);
             placed = true;
             break;
@@ -694,58 +899,547 @@ fn generate_grid(cfg: &ProceduralConfig) -> GeneratedGrid {
         }
     }
 
-    let entrance = rooms.last().map(|room| room.marker());
-    let exit = rooms.first().map(|room| room.marker());
-    if let Some((x, y)) = entrance
-        && in_bounds(cfg.width, cfg.height, x, y)
+    (cells, rooms)
+}
+
+/// Open both ends of a room connection and carve its corridor. Doors are
+/// placed on the outgoing (`exits`) and incoming (`entrances`) side.
+fn open_connection(
+    cfg: &ProceduralConfig,
+    cells: &mut [ProcCell],
+    connection: &RoomCandidate,
+    rng: &mut StdRng,
+) {
+    carve_corridor(cells, cfg.width, cfg.height, &connection.corridor);
+    let prev_door = (connection.prev_door.1 * cfg.width + connection.prev_door.0) as usize;
+    let next_door = (connection.next_door.1 * cfg.width + connection.next_door.0) as usize;
+    cells[prev_door] = ProcCell::Floor;
+    cells[next_door] = ProcCell::Floor;
+
+    if (cfg.door_placement == "exits" || cfg.door_placement == "both")
+        && should_place_door(cfg, rng)
+        && !has_adjacent_door(
+            cells,
+            cfg.width,
+            cfg.height,
+            connection.prev_door.0,
+            connection.prev_door.1,
+        )
     {
-        cells[(y * cfg.width + x) as usize] = ProcCell::Entrance;
+        cells[prev_door] = ProcCell::Door;
     }
-    if let Some((x, y)) = exit
-        && in_bounds(cfg.width, cfg.height, x, y)
+    if (cfg.door_placement == "entrances" || cfg.door_placement == "both")
+        && should_place_door(cfg, rng)
+        && !has_adjacent_door(
+            cells,
+            cfg.width,
+            cfg.height,
+            connection.next_door.0,
+            connection.next_door.1,
+        )
     {
-        cells[(y * cfg.width + x) as usize] = ProcCell::Exit;
+        cells[next_door] = ProcCell::Door;
     }
+}
 
-    let floorish = |cell: ProcCell| {
-        matches!(
-            cell,
-            ProcCell::Floor
-                | ProcCell::Corridor
-                | ProcCell::Door
-                | ProcCell::Entrance
-                | ProcCell::Exit
-        )
+/// Rooms placed by binary space partitioning: the map is split into one leaf
+/// per room and sibling leaves are connected, so every room is reachable.
+fn bsp_rooms_layout(cfg: &ProceduralConfig, rng: &mut StdRng) -> (Vec<ProcCell>, Vec<Room>) {
+    let mut cells = vec![ProcCell::Empty; (cfg.width * cfg.height) as usize];
+    let mut rooms = Vec::new();
+    let area = Room {
+        x: 2,
+        y: 2,
+        w: cfg.width - 4,
+        h: cfg.height - 4,
     };
-    let snapshot = cells.clone();
+    bsp_split(
+        cfg,
+        area,
+        cfg.room_count.max(1),
+        &mut cells,
+        &mut rooms,
+        rng,
+    );
+    (cells, rooms)
+}
+
+/// Split `area` into leaves for up to `budget` rooms and connect the rooms of
+/// both halves. Returns the range of the rooms placed inside `area`.
+fn bsp_split(
+    cfg: &ProceduralConfig,
+    area: Room,
+    budget: i32,
+    cells: &mut [ProcCell],
+    rooms: &mut Vec<Room>,
+    rng: &mut StdRng,
+) -> std::ops::Range<usize> {
+    let start = rooms.len();
+    let min_leaf = cfg.room_min_size + 2;
+    let can_split_x = area.w >= min_leaf * 2;
+    let can_split_y = area.h >= min_leaf * 2;
+
+    if budget > 1 && (can_split_x || can_split_y) {
+        let split_x = if can_split_x && can_split_y {
+            if area.w * 4 >= area.h * 5 {
+                true
+            } else if area.h * 4 >= area.w * 5 {
+                false
+            } else {
+                rng.random_bool(0.5)
+            }
+        } else {
+            can_split_x
+        };
+        let (first, second) = if split_x {
+            let at = rng.random_range(min_leaf..=area.w - min_leaf);
+            (
+                Room { w: at, ..area },
+                Room {
+                    x: area.x + at,
+                    w: area.w - at,
+                    ..area
+                },
+            )
+        } else {
+            let at = rng.random_range(min_leaf..=area.h - min_leaf);
+            (
+                Room { h: at, ..area },
+                Room {
+                    y: area.y + at,
+                    h: area.h - at,
+                    ..area
+                },
+            )
+        };
+        let total = (area.w * area.h).max(1) as i64;
+        let first_budget = ((budget as i64 * (first.w * first.h) as i64 + total / 2) / total)
+            .clamp(1, budget as i64 - 1) as i32;
+        let first_rooms = bsp_split(cfg, first, first_budget, cells, rooms, rng);
+        let second_rooms = bsp_split(cfg, second, budget - first_budget, cells, rooms, rng);
+        if !first_rooms.is_empty() && !second_rooms.is_empty() {
+            let connection =
+                bsp_connection(&rooms[first_rooms], &rooms[second_rooms], split_x, rng);
+            open_connection(cfg, cells, &connection, rng);
+        }
+        return start..rooms.len();
+    }
+
+    let max_w = cfg.room_max_size.min(area.w - 2);
+    let max_h = cfg.room_max_size.min(area.h - 2);
+    if max_w < cfg.room_min_size || max_h < cfg.room_min_size {
+        return start..start;
+    }
+    let w = rng.random_range(cfg.room_min_size..=max_w);
+    let h = rng.random_range(cfg.room_min_size..=max_h);
+    let room = Room {
+        x: rng.random_range(area.x + 1..=area.x + area.w - 1 - w),
+        y: rng.random_range(area.y + 1..=area.y + area.h - 1 - h),
+        w,
+        h,
+    };
+    stamp_room(cells, cfg.width, room);
+    rooms.push(room);
+    start..rooms.len()
+}
+
+/// Connect the two rooms of sibling leaves which are closest to the split
+/// line. No other room of either half lies between them, so the corridor
+/// stays clear.
+fn bsp_connection(
+    first: &[Room],
+    second: &[Room],
+    split_x: bool,
+    rng: &mut StdRng,
+) -> RoomCandidate {
+    if split_x {
+        let a = *first.iter().max_by_key(|room| room.x + room.w).unwrap();
+        let b = *second.iter().min_by_key(|room| room.x).unwrap();
+        let ay = rng.random_range(a.y + 1..=a.y + a.h - 2);
+        let by = rng.random_range(b.y + 1..=b.y + b.h - 2);
+        RoomCandidate {
+            room: b,
+            prev_door: (a.x + a.w - 1, ay),
+            next_door: (b.x, by),
+            corridor: connector_path((a.x + a.w, ay), (b.x - 1, by), rng),
+        }
+    } else {
+        let a = *first.iter().max_by_key(|room| room.y + room.h).unwrap();
+        let b = *second.iter().min_by_key(|room| room.y).unwrap();
+        let ax = rng.random_range(a.x + 1..=a.x + a.w - 2);
+        let bx = rng.random_range(b.x + 1..=b.x + b.w - 2);
+        RoomCandidate {
+            room: b,
+            prev_door: (ax, a.y + a.h - 1),
+            next_door: (bx, b.y),
+            corridor: connector_path((ax, a.y + a.h), (bx, b.y - 1), rng),
+        }
+    }
+}
+
+fn is_interior(cfg: &ProceduralConfig, x: i32, y: i32) -> bool {
+    x >= 2 && y >= 2 && x < cfg.width - 2 && y < cfg.height - 2
+}
+
+/// Cellular automata caves: random noise smoothed into open areas, keeping
+/// the largest connected cave.
+fn cave_layout(cfg: &ProceduralConfig, rng: &mut StdRng) -> (Vec<ProcCell>, Vec<Room>) {
+    let mut open = vec![false; (cfg.width * cfg.height) as usize];
+    let min_open = ((cfg.width - 4) * (cfg.height - 4) / 5).max(1) as usize;
+    for _ in 0..8 {
+        for y in 0..cfg.height {
+            for x in 0..cfg.width {
+                open[(y * cfg.width + x) as usize] =
+                    is_interior(cfg, x, y) && rng.random::<f32>() >= cfg.cave_fill;
+            }
+        }
+        for _ in 0..cfg.cave_steps {
+            open = smooth_cave(cfg, &open);
+        }
+        keep_largest_area(cfg, &mut open);
+        if open.iter().filter(|open| **open).count() >= min_open {
+            break;
+        }
+    }
+    open_layout(cfg, open, rng)
+}
+
+/// One automata step: cells surrounded by rock fill up, cells with open
+/// surroundings open up.
+fn smooth_cave(cfg: &ProceduralConfig, open: &[bool]) -> Vec<bool> {
+    let mut next = open.to_vec();
     for y in 0..cfg.height {
         for x in 0..cfg.width {
             let idx = (y * cfg.width + x) as usize;
-            if snapshot[idx] != ProcCell::Empty {
+            if !is_interior(cfg, x, y) {
+                next[idx] = false;
                 continue;
             }
-            let mut adjacent = false;
+            let mut solid = 0;
             for dy in -1..=1 {
                 for dx in -1..=1 {
-                    if dx == 0 && dy == 0 {
-                        continue;
-                    }
-                    let nx = x + dx;
-                    let ny = y + dy;
-                    if in_bounds(cfg.width, cfg.height, nx, ny)
-                        && floorish(snapshot[(ny * cfg.width + nx) as usize])
-                    {
-                        adjacent = true;
+                    if (dx != 0 || dy != 0) && !open[((y + dy) * cfg.width + x + dx) as usize] {
+                        solid += 1;
                     }
                 }
             }
-            if adjacent {
-                cells[idx] = ProcCell::Wall;
+            if solid >= 5 {
+                next[idx] = false;
+            } else if solid <= 3 {
+                next[idx] = true;
             }
         }
     }
+    next
+}
 
-    GeneratedGrid { cells, rooms }
+/// Distances (in steps) of every open cell from `start`, `-1` if unreachable,
+/// and the cell each one was reached from.
+fn open_distances(cfg: &ProceduralConfig, open: &[bool], start: usize) -> (Vec<i32>, Vec<usize>) {
+    let mut distances = vec![-1; open.len()];
+    let mut parents = vec![usize::MAX; open.len()];
+    let mut queue = std::collections::VecDeque::new();
+    distances[start] = 0;
+    queue.push_back(start);
+    while let Some(idx) = queue.pop_front() {
+        let x = idx as i32 % cfg.width;
+        let y = idx as i32 / cfg.width;
+        for (dx, dy) in [(1, 0), (-1, 0), (0, 1), (0, -1)] {
+            let (nx, ny) = (x + dx, y + dy);
+            if !in_bounds(cfg.width, cfg.height, nx, ny) {
+                continue;
+            }
+            let next = (ny * cfg.width + nx) as usize;
+            if open[next] && distances[next] < 0 {
+                distances[next] = distances[idx] + 1;
+                parents[next] = idx;
+                queue.push_back(next);
+            }
+        }
+    }
+    (distances, parents)
+}
+
+fn keep_largest_area(cfg: &ProceduralConfig, open: &mut [bool]) {
+    let mut area = vec![usize::MAX; open.len()];
+    let mut largest = (usize::MAX, 0);
+    for idx in 0..open.len() {
+        if !open[idx] || area[idx] != usize::MAX {
+            continue;
+        }
+        let (distances, _) = open_distances(cfg, open, idx);
+        let mut size = 0;
+        for (cell, distance) in distances.iter().enumerate() {
+            if *distance >= 0 {
+                area[cell] = idx;
+                size += 1;
+            }
+        }
+        if size > largest.1 {
+            largest = (idx, size);
+        }
+    }
+    for (cell, open) in open.iter_mut().enumerate() {
+        *open = *open && area[cell] == largest.0;
+    }
+}
+
+const WALK_DIRECTIONS: [(i32, i32); 4] = [(1, 0), (-1, 0), (0, 1), (0, -1)];
+
+/// Drunkard's walk tunnels: a walker starts in the center and wanders,
+/// mostly keeping its direction, until enough of the map is open.
+fn tunnel_layout(cfg: &ProceduralConfig, rng: &mut StdRng) -> (Vec<ProcCell>, Vec<Room>) {
+    let mut open = vec![false; (cfg.width * cfg.height) as usize];
+    let target =
+        (((cfg.width - 4) * (cfg.height - 4)) as f32 * cfg.tunnel_coverage).max(1.0) as usize;
+    let (mut x, mut y) = (cfg.width / 2, cfg.height / 2);
+    let mut direction = rng.random_range(0..WALK_DIRECTIONS.len());
+    open[(y * cfg.width + x) as usize] = true;
+    let mut opened = 1;
+    for _ in 0..cfg.width * cfg.height * 40 {
+        if opened >= target {
+            break;
+        }
+        if rng.random_bool(0.35) {
+            direction = rng.random_range(0..WALK_DIRECTIONS.len());
+        }
+        let (dx, dy) = WALK_DIRECTIONS[direction];
+        if !is_interior(cfg, x + dx, y + dy) {
+            direction = rng.random_range(0..WALK_DIRECTIONS.len());
+            continue;
+        }
+        x += dx;
+        y += dy;
+        let idx = (y * cfg.width + x) as usize;
+        if !open[idx] {
+            open[idx] = true;
+            opened += 1;
+        }
+    }
+    open_layout(cfg, open, rng)
+}
+
+/// Floor cells and marker areas for the open generators. The exit and the
+/// entrance are the two open cells farthest apart; the areas in between are
+/// used for spawns and doors go into chokepoints along the way.
+fn open_layout(
+    cfg: &ProceduralConfig,
+    mut open: Vec<bool>,
+    rng: &mut StdRng,
+) -> (Vec<ProcCell>, Vec<Room>) {
+    let Some(start) = open.iter().position(|open| *open) else {
+        let center = (cfg.height / 2 * cfg.width + cfg.width / 2) as usize;
+        open[center] = true;
+        return open_layout(cfg, open, rng);
+    };
+    let farthest = |distances: &[i32]| {
+        (0..distances.len())
+            .max_by_key(|idx| (distances[*idx], usize::MAX - idx))
+            .unwrap_or(start)
+    };
+    let exit = farthest(&open_distances(cfg, &open, start).0);
+    let (distances, parents) = open_distances(cfg, &open, exit);
+    let entrance = farthest(&distances);
+    let cell_of = |idx: usize| (idx as i32 % cfg.width, idx as i32 / cfg.width);
+
+    let mut cells = open
+        .iter()
+        .map(|open| {
+            if *open {
+                ProcCell::Floor
+            } else {
+                ProcCell::Empty
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let mut candidates = (0..open.len()).filter(|idx| open[*idx]).collect::<Vec<_>>();
+    candidates.shuffle(rng);
+    let mut spots = vec![cell_of(exit), cell_of(entrance)];
+    for idx in candidates {
+        if spots.len() >= cfg.room_count.max(2) as usize {
+            break;
+        }
+        let (x, y) = cell_of(idx);
+        if spots
+            .iter()
+            .all(|(sx, sy)| (sx - x).abs() + (sy - y).abs() >= cfg.room_min_size)
+        {
+            spots.push((x, y));
+        }
+    }
+    let mut middle = spots.split_off(2);
+    middle.sort_by_key(|(x, y)| distances[(y * cfg.width + x) as usize]);
+    let area = |(x, y): (i32, i32)| Room {
+        x: x - 1,
+        y: y - 1,
+        w: 3,
+        h: 3,
+    };
+    let mut rooms = vec![area(spots[0])];
+    rooms.extend(middle.into_iter().map(area));
+    rooms.push(area(spots[1]));
+
+    let mut path = vec![];
+    let mut idx = entrance;
+    while idx != usize::MAX {
+        path.push(cell_of(idx));
+        idx = parents[idx];
+    }
+    place_chokepoint_doors(cfg, &mut cells, &path, rng);
+    (cells, rooms)
+}
+
+/// Doors in one cell wide passages along the path, at most one per
+/// `room_max_size` steps.
+fn place_chokepoint_doors(
+    cfg: &ProceduralConfig,
+    cells: &mut [ProcCell],
+    path: &[(i32, i32)],
+    rng: &mut StdRng,
+) {
+    let spacing = cfg.room_max_size.max(4) as usize;
+    let mut since_door = 0;
+    for (index, &(x, y)) in path.iter().enumerate() {
+        since_door += 1;
+        if index < 2 || index + 2 >= path.len() || since_door < spacing {
+            continue;
+        }
+        let solid = |dx: i32, dy: i32| {
+            !in_bounds(cfg.width, cfg.height, x + dx, y + dy)
+                || cells[((y + dy) * cfg.width + x + dx) as usize] == ProcCell::Empty
+        };
+        let chokepoint = (solid(-1, 0) && solid(1, 0) && !solid(0, -1) && !solid(0, 1))
+            || (solid(0, -1) && solid(0, 1) && !solid(-1, 0) && !solid(1, 0));
+        if chokepoint
+            && !has_adjacent_door(cells, cfg.width, cfg.height, x, y)
+            && should_place_door(cfg, rng)
+        {
+            cells[(y * cfg.width + x) as usize] = ProcCell::Door;
+            since_door = 0;
+        }
+    }
+}
+
+/// Connected rooms which take their shapes from authored regions. Without
+/// usable prefabs this is the same as `connected_rooms`.
+fn prefab_rooms_layout(
+    cfg: &ProceduralConfig,
+    prefabs: &[ProceduralPrefab],
+    rng: &mut StdRng,
+) -> (Vec<ProcCell>, Vec<Room>) {
+    let prefabs = prefabs
+        .iter()
+        .filter(|prefab| prefab.width + 6 < cfg.width && prefab.height + 6 < cfg.height)
+        .collect::<Vec<_>>();
+    if prefabs.is_empty() {
+        return connected_rooms_layout(cfg, rng);
+    }
+    let mut cells = vec![ProcCell::Empty; (cfg.width * cfg.height) as usize];
+    let mut rooms: Vec<Room> = Vec::new();
+
+    let prefab = prefabs[rng.random_range(0..prefabs.len())];
+    let room = Room {
+        x: rng.random_range(2..cfg.width - prefab.width - 1),
+        y: rng.random_range(2..cfg.height - prefab.height - 1),
+        w: prefab.width,
+        h: prefab.height,
+    };
+    stamp_prefab(&mut cells, cfg.width, room, prefab);
+    rooms.push(room);
+
+    while rooms.len() < cfg.room_count as usize {
+        let Some(prev) = rooms.last().copied() else {
+            break;
+        };
+        let mut placed = false;
+
+        for _ in 0..160 {
+            let prefab = prefabs[rng.random_range(0..prefabs.len())];
+            let gap = rng.random_range(2..=6);
+            let dir = rng.random_range(0..4);
+            let Some(candidate) =
+                candidate_next_room(prev, prefab.width, prefab.height, gap, dir, rng)
+            else {
+                continue;
+            };
+            if !room_in_bounds(cfg, candidate.room)
+                || rooms
+                    .iter()
+                    .any(|other| candidate.room.intersects(*other, 2))
+                || has_adjacent_door(
+                    &cells,
+                    cfg.width,
+                    cfg.height,
+                    candidate.prev_door.0,
+                    candidate.prev_door.1,
+                )
+                || !corridor_is_clear(&cells, cfg.width, cfg.height, &candidate.corridor)
+            {
+                continue;
+            }
+
+            stamp_prefab(&mut cells, cfg.width, candidate.room, prefab);
+            connect_to_floor(cfg, &mut cells, prev, candidate.prev_door);
+            connect_to_floor(cfg, &mut cells, candidate.room, candidate.next_door);
+            open_connection(cfg, &mut cells, &candidate, rng);
+            rooms.push(candidate.room);
+            placed = true;
+            break;
+        }
+
+        if !placed {
+            break;
+        }
+    }
+
+    (cells, rooms)
+}
+
+fn stamp_prefab(cells: &mut [ProcCell], width: i32, room: Room, prefab: &ProceduralPrefab) {
+    for y in 0..room.h {
+        for x in 0..room.w {
+            if prefab.is_floor(x, y) {
+                cells[((room.y + y) * width + room.x + x) as usize] = ProcCell::Floor;
+            }
+        }
+    }
+}
+
+/// Carve a path from a door on the room border to the closest floor cell of
+/// the room, prefab shapes do not always touch their border.
+fn connect_to_floor(cfg: &ProceduralConfig, cells: &mut [ProcCell], room: Room, door: (i32, i32)) {
+    let target = room_floor_cell(cells, cfg, room, door);
+    for (x, y) in line_cells(door, target) {
+        let idx = (y * cfg.width + x) as usize;
+        if cells[idx] == ProcCell::Empty {
+            cells[idx] = ProcCell::Floor;
+        }
+    }
+}
+
+/// The floor cell of the room closest to `target`.
+fn room_floor_cell(
+    cells: &[ProcCell],
+    cfg: &ProceduralConfig,
+    room: Room,
+    target: (i32, i32),
+) -> (i32, i32) {
+    let mut best = (target, i32::MAX);
+    for y in room.y..room.y + room.h {
+        for x in room.x..room.x + room.w {
+            if !in_bounds(cfg.width, cfg.height, x, y)
+                || cells[(y * cfg.width + x) as usize] != ProcCell::Floor
+            {
+                continue;
+            }
+            let distance = (x - target.0).abs() + (y - target.1).abs();
+            if distance < best.1 {
+                best = ((x, y), distance);
+            }
+        }
+    }
+    best.0
 }
 
 fn should_place_door(cfg: &ProceduralConfig, rng: &mut StdRng) -> bool {
@@ -944,8 +1638,8 @@ fn cell_world_position(x: i32, y: i32, cfg: &ProceduralConfig) -> Vec3<f32> {
     Vec3::new(ox + x as f32 + 0.5, 0.0, oy + y as f32 + 0.5)
 }
 
-fn room_spawn_position(room: Room, cfg: &ProceduralConfig) -> Vec3<f32> {
-    let (x, y) = room.center();
+fn room_spawn_position(cells: &[ProcCell], room: Room, cfg: &ProceduralConfig) -> Vec3<f32> {
+    let (x, y) = room_floor_cell(cells, cfg, room, room.center());
     cell_world_position(x, y, cfg)
 }
 
@@ -1017,7 +1711,7 @@ mod tests {
     use super::*;
 
     fn grid_fingerprint(cfg: &ProceduralConfig) -> Vec<u8> {
-        generate_grid(cfg)
+        generate_grid(cfg, &[])
             .cells
             .into_iter()
             .map(|cell| match cell {
@@ -1054,4 +1748,168 @@ mod tests {
 
         assert_ne!(grid_fingerprint(&first), grid_fingerprint(&second));
     }
+
+    fn generator_config(generator: &str, seed: u64) -> ProceduralConfig {
+        ProceduralConfig {
+            generator: generator.into(),
+            seed,
+            style: String::new(),
+            width: 48,
+            height: 48,
+            room_count: 6,
+            room_min_size: 5,
+            room_max_size: 9,
+            ..ProceduralConfig::default()
+        }
+    }
+
+    /// Every walkable cell must be reachable from the entrance.
+    fn assert_connected(cfg: &ProceduralConfig, cells: &[ProcCell]) {
+        let walkable = cells
+            .iter()
+            .map(|cell| !matches!(cell, ProcCell::Empty | ProcCell::Wall))
+            .collect::<Vec<_>>();
+        let entrance = cells
+            .iter()
+            .position(|cell| *cell == ProcCell::Entrance)
+            .expect("entrance");
+        assert!(
+            cells.contains(&ProcCell::Exit),
+            "{} has no exit",
+            cfg.generator
+        );
+        let (distances, _) = open_distances(cfg, &walkable, entrance);
+        for (idx, walkable) in walkable.iter().enumerate() {
+            assert!(
+                !walkable || distances[idx] >= 0,
+                "{} seed {} left cell {} unreachable",
+                cfg.generator,
+                cfg.seed,
+                idx
+            );
+        }
+    }
+
+    fn l_shaped_prefab() -> ProceduralPrefab {
+        let (width, height) = (9, 9);
+        let mut floor = vec![false; (width * height) as usize];
+        for y in 1..8 {
+            for x in 1..8 {
+                floor[(y * width + x) as usize] = x < 4 || y > 4;
+            }
+        }
+        ProceduralPrefab {
+            name: "L Hall".into(),
+            width,
+            height,
+            floor,
+        }
+    }
+
+    #[test]
+    fn all_generators_are_deterministic_and_connected() {
+        for generator in PROCEDURAL_GENERATORS {
+            for seed in 1..6 {
+                let cfg = generator_config(generator, seed);
+                let prefabs = [l_shaped_prefab()];
+                let grid = generate_grid(&cfg, &prefabs);
+                assert_connected(&cfg, &grid.cells);
+                assert!(grid.rooms.len() >= 2, "{generator} seed {seed}");
+                assert_eq!(grid.cells, generate_grid(&cfg, &prefabs).cells);
+            }
+            assert_ne!(
+                grid_fingerprint(&generator_config(generator, 1)),
+                grid_fingerprint(&generator_config(generator, 2)),
+                "{generator} ignores the seed"
+            );
+        }
+    }
+
+    #[test]
+    fn generators_respect_door_placement() {
+        for generator in ["bsp_rooms", "caves", "drunkards_walk"] {
+            let mut with_doors = generator_config(generator, 3);
+            with_doors.door_randomness = 1.0;
+            let doors = (1..6)
+                .map(|seed| {
+                    with_doors.seed = seed;
+                    generate_grid(&with_doors, &[])
+                        .cells
+                        .iter()
+                        .filter(|cell| **cell == ProcCell::Door)
+                        .count()
+                })
+                .sum::<usize>();
+            assert!(doors > 0, "{generator} placed no doors");
+
+            let mut without_doors = generator_config(generator, 3);
+            without_doors.door_randomness = 0.0;
+            assert!(
+                !generate_grid(&without_doors, &[])
+                    .cells
+                    .contains(&ProcCell::Door)
+            );
+        }
+    }
+
+    #[test]
+    fn bsp_rooms_do_not_overlap() {
+        let cfg = generator_config("bsp_rooms", 9);
+        let rooms = generate_grid(&cfg, &[]).rooms;
+        assert!(rooms.len() > 2);
+        for (index, room) in rooms.iter().enumerate() {
+            assert!(room_in_bounds(&cfg, *room));
+            for other in &rooms[index + 1..] {
+                assert!(!room.intersects(*other, 1));
+            }
+        }
+    }
+
+    #[test]
+    fn prefab_rooms_use_the_prefab_shape() {
+        let cfg = generator_config("prefab_rooms", 5);
+        let prefab = l_shaped_prefab();
+        let grid = generate_grid(&cfg, std::slice::from_ref(&prefab));
+        assert!(grid.rooms.len() > 1);
+        for room in &grid.rooms {
+            assert_eq!((room.w, room.h), (prefab.width, prefab.height));
+            // The cut out corner of the L stays solid.
+            let corner = ((room.y + 2) * cfg.width + room.x + 6) as usize;
+            assert!(matches!(
+                grid.cells[corner],
+                ProcCell::Empty | ProcCell::Wall
+            ));
+        }
+    }
+
+    #[test]
+    fn parses_generator_settings_and_weighted_names() {
+        let cfg = parse_procedural_config(
+            r#"
+            [procedural]
+            generator = "caves"
+            cave_fill = 0.5
+            cave_steps = 3
+            tunnel_coverage = 0.4
+            prefabs = ["Crypt", "Shrine"]
+
+            [procedural.items.door]
+            names = ["Wooden Door", "Iron Door"]
+            weights = [4, 1]
+
+            [procedural.characters.monster]
+            percentage = 40
+            choices = [{ name = "Skeleton", weight = 3 }]
+            "#,
+        )
+        .unwrap();
+        assert!(is_supported_generator(&cfg.generator));
+        assert_eq!((cfg.cave_fill, cfg.cave_steps), (0.5, 3));
+        assert_eq!(cfg.tunnel_coverage, 0.4);
+        assert_eq!(cfg.prefabs, vec!["Crypt", "Shrine"]);
+        assert_eq!(cfg.item_choices["door"].len(), 2);
+        assert_eq!(cfg.item_choices["door"][0].weight, 4);
+        assert_eq!(cfg.generated_character_names(), vec!["Skeleton"]);
+        assert_eq!(cfg.character_rules["monster"].chance, 0.4);
+    }
 }
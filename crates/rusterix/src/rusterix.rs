@@ -273,6 +273,9 @@ impl Rusterix {
                             PlayerCamera::D3FirstP | PlayerCamera::D3FirstPGrid => {
                                 self.client.camera_d3 = Box::new(D3FirstPCamera::new());
                             }
+                            PlayerCamera::D3ThirdP => {
+                                self.client.camera_d3 = Box::new(D3ThirdPCamera::new());
+                            }
                             PlayerCamera::D2 | PlayerCamera::D2Grid => {}
                         }
                     }
@@ -454,6 +457,8 @@ impl Rusterix {
         let open_container_requests = self.server.get_open_container_requests(&map.id);
         self.client
             .process_open_container_requests(open_container_requests);
+        let quest_events = self.server.get_quest_events(&map.id);
+        self.client.process_quest_events(quest_events);
         self.client.process_messages(map, says);
         self.client.draw_game(
             map,
@@ -503,6 +508,8 @@ impl Rusterix {
         let open_container_requests = self.server.get_open_container_requests(&map.id);
         self.client
             .process_open_container_requests(open_container_requests);
+        let quest_events = self.server.get_quest_events(&map.id);
+        self.client.process_quest_events(quest_events);
         self.client.process_messages(map, says);
         self.client.draw_game_with_widget_overlays(
             map,
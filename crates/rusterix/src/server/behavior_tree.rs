@@ -0,0 +1,674 @@
+use crate::server::perception::Awareness;
+use crate::server::region::RegionInstance;
+use crate::server::region_host::run_entity_host_call;
+use crate::server::regionctx::RegionCtx;
+use crate::vm::VMValue;
+use crate::{Entity, EntityAction, Value};
+use rand::Rng;
+use theframework::prelude::*;
+
+/// Trees referencing other trees deeper than this fail, which stops cycles.
+const MAX_TREE_DEPTH: usize = 32;
+
+/// A node of a data-driven NPC behavior tree.
+///
+/// Trees are written as TOML tables with exactly one node key, for example
+/// `{ selector = [...] }` or `{ action = "random_walk_in_sector", args = [1.0, 1.0, 4] }`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum BehaviorNode {
+    /// Runs the children in order until one does not fail.
+    Selector(Vec<BehaviorNode>),
+    /// Runs the children in order until one does not succeed.
+    Sequence(Vec<BehaviorNode>),
+    /// Swaps success and failure of the child.
+    Invert(Box<BehaviorNode>),
+    /// Turns a failure of the child into a success.
+    Succeed(Box<BehaviorNode>),
+    /// Fails for the given realtime seconds after the child succeeded.
+    Cooldown(f32, Box<BehaviorNode>),
+    /// Runs the child with the given probability, fails otherwise.
+    Chance(f32, Box<BehaviorNode>),
+    Condition(BehaviorCondition),
+    /// Calls a server command on the character.
+    Action(String, Vec<BehaviorArg>),
+    /// Runs a named tree from the `[behavior_trees]` game configuration.
+    Tree(String),
+}
+
+/// The checks a condition node can make.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum BehaviorCondition {
+    /// A player is in sight. The nearest one becomes the tree's target.
+    SeesPlayer,
+    /// A player is in sight or hearing range. The nearest one becomes the tree's target.
+    HearsPlayer,
+    /// The character's `target` attribute holds a living character.
+    HasTarget,
+    /// The target is within the distance.
+    TargetWithin(f32),
+    /// Health is below the fraction of the maximum health.
+    HealthBelow(f32),
+    /// The character's schedule activity matches.
+    Activity(String),
+    /// An attribute is set, or matches `equals`, or lies between `above` and `below`.
+    Attr {
+        key: String,
+        equals: Option<Value>,
+        above: Option<f32>,
+        below: Option<f32>,
+    },
+}
+
+/// An argument of an action node.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum BehaviorArg {
+    Number(f32),
+    Bool(bool),
+    Text(String),
+    /// `"target"`: the id of the tree's current target.
+    Target,
+}
+
+/// Per character tree state kept between ticks.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default)]
+pub struct BehaviorState {
+    /// Path of the action node whose entity action is still running.
+    pub running: Option<String>,
+    /// Tick at which each cooldown node becomes ready again, by path.
+    pub cooldowns: FxHashMap<String, i64>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BehaviorStatus {
+    Success,
+    Failure,
+    Running,
+}
+
+impl BehaviorNode {
+    pub fn from_toml(value: &toml::Value) -> Result<Self, String> {
+        let Some(table) = value.as_table() else {
+            return Err(format!("expected a node table, got `{value}`"));
+        };
+
+        let child = |key: &str| -> Result<Box<BehaviorNode>, String> {
+            let child = table
+                .get(key)
+                .ok_or_else(|| format!("missing `{key}` node"))?;
+            Ok(Box::new(Self::from_toml(child)?))
+        };
+        let children = |value: &toml::Value| -> Result<Vec<BehaviorNode>, String> {
+            value
+                .as_array()
+                .ok_or_else(|| "expected an array of nodes".to_string())?
+                .iter()
+                .map(Self::from_toml)
+                .collect()
+        };
+        let number = |key: &str| table.get(key).and_then(toml_number);
+
+        if let Some(value) = table.get("selector") {
+            Ok(Self::Selector(children(value)?))
+        } else if let Some(value) = table.get("sequence") {
+            Ok(Self::Sequence(children(value)?))
+        } else if table.contains_key("invert") {
+            Ok(Self::Invert(child("invert")?))
+        } else if table.contains_key("succeed") {
+            Ok(Self::Succeed(child("succeed")?))
+        } else if let Some(seconds) = number("cooldown") {
+            Ok(Self::Cooldown(seconds.max(0.0), child("child")?))
+        } else if let Some(chance) = number("chance") {
+            Ok(Self::Chance(chance.clamp(0.0, 1.0), child("child")?))
+        } else if let Some(name) = table.get("tree").and_then(toml::Value::as_str) {
+            Ok(Self::Tree(name.trim().to_string()))
+        } else if let Some(name) = table.get("action").and_then(toml::Value::as_str) {
+            let args = match table.get("args") {
+                Some(toml::Value::Array(args)) => args
+                    .iter()
+                    .map(BehaviorArg::from_toml)
+                    .collect::<Result<_, _>>()?,
+                Some(arg) => vec![BehaviorArg::from_toml(arg)?],
+                None => Vec::new(),
+            };
+            Ok(Self::Action(name.trim().to_string(), args))
+        } else if let Some(name) = table.get("condition").and_then(toml::Value::as_str) {
+            Ok(Self::Condition(BehaviorCondition::from_table(
+                name.trim(),
+                table,
+            )?))
+        } else {
+            Err(format!("unknown node `{value}`"))
+        }
+    }
+
+    /// The names of the trees referenced by this node which are not in `trees`.
+    pub fn unknown_trees(&self, trees: &FxHashMap<String, BehaviorNode>) -> Vec<String> {
+        let mut unknown = Vec::new();
+        self.collect_unknown_trees(trees, &mut unknown);
+        unknown
+    }
+
+    fn collect_unknown_trees(
+        &self,
+        trees: &FxHashMap<String, BehaviorNode>,
+        unknown: &mut Vec<String>,
+    ) {
+        match self {
+            Self::Selector(children) | Self::Sequence(children) => {
+                for child in children {
+                    child.collect_unknown_trees(trees, unknown);
+                }
+            }
+            Self::Invert(child)
+            | Self::Succeed(child)
+            | Self::Cooldown(_, child)
+            | Self::Chance(_, child) => child.collect_unknown_trees(trees, unknown),
+            Self::Tree(name) if !trees.contains_key(name) && !unknown.contains(name) => {
+                unknown.push(name.clone());
+            }
+            Self::Tree(_) | Self::Condition(_) | Self::Action(..) => {}
+        }
+    }
+}
+
+impl BehaviorCondition {
+    fn from_table(name: &str, table: &toml::Table) -> Result<Self, String> {
+        let number = |key: &str| {
+            table
+                .get(key)
+                .and_then(toml_number)
+                .ok_or_else(|| format!("condition `{name}` needs a number `{key}`"))
+        };
+        match name {
+            "sees_player" => Ok(Self::SeesPlayer),
+            "hears_player" => Ok(Self::HearsPlayer),
+            "has_target" => Ok(Self::HasTarget),
+            "target_within" => Ok(Self::TargetWithin(number("distance")?)),
+            "health_below" => Ok(Self::HealthBelow(number("value")?)),
+            "activity" => Ok(Self::Activity(
+                table
+                    .get("value")
+                    .and_then(toml::Value::as_str)
+                    .ok_or_else(|| "condition `activity` needs a text `value`".to_string())?
+                    .trim()
+                    .to_string(),
+            )),
+            "attr" => Ok(Self::Attr {
+                key: table
+                    .get("key")
+                    .and_then(toml::Value::as_str)
+                    .ok_or_else(|| "condition `attr` needs a `key`".to_string())?
+                    .trim()
+                    .to_string(),
+                equals: table.get("equals").and_then(|value| match value {
+                    toml::Value::String(text) => Some(Value::Str(text.clone())),
+                    toml::Value::Boolean(flag) => Some(Value::Bool(*flag)),
+                    value => toml_number(value).map(Value::Float),
+                }),
+                above: table.get("above").and_then(toml_number),
+                below: table.get("below").and_then(toml_number),
+            }),
+            _ => Err(format!("unknown condition `{name}`")),
+        }
+    }
+
+    fn matches(&self, tick: &mut BehaviorTick) -> bool {
+        match self {
+            Self::SeesPlayer => tick.perceive(|awareness| awareness == Awareness::Seen),
+            Self::HearsPlayer => tick.perceive(|_| true),
+            condition => condition.matches_entity(tick),
+        }
+    }
+
+    /// The conditions which only read the character and its target.
+    fn matches_entity(&self, tick: &BehaviorTick) -> bool {
+        let Some(entity) = tick.entity() else {
+            return false;
+        };
+        match self {
+            Self::SeesPlayer | Self::HearsPlayer => false,
+            Self::HasTarget => tick.target_entity().is_some(),
+            Self::TargetWithin(distance) => {
+                let position = entity.get_pos_xz();
+                tick.target_entity()
+                    .is_some_and(|target| target.get_pos_xz().distance(position) <= *distance)
+            }
+            Self::HealthBelow(fraction) => {
+                let health = entity.attributes.get_float(&tick.ctx.health_attr);
+                let max_health = entity.attributes.get_float(&tick.ctx.max_health_attr);
+                matches!((health, max_health), (Some(health), Some(max)) if max > 0.0 && health < max * fraction)
+            }
+            Self::Activity(activity) => entity
+                .attributes
+                .get_str("activity")
+                .is_some_and(|current| current.eq_ignore_ascii_case(activity)),
+            Self::Attr {
+                key,
+                equals,
+                above,
+                below,
+            } => {
+                let attributes = &entity.attributes;
+                if let Some(expected) = equals {
+                    return match expected {
+                        Value::Str(text) => attributes
+                            .get_str(key)
+                            .is_some_and(|value| value.eq_ignore_ascii_case(text)),
+                        Value::Bool(flag) => attributes.get_bool(key) == Some(*flag),
+                        Value::Float(number) => attributes
+                            .get_float(key)
+                            .is_some_and(|value| (value - number).abs() <= f32::EPSILON),
+                        _ => false,
+                    };
+                }
+                if above.is_some() || below.is_some() {
+                    return attributes.get_float(key).is_some_and(|value| {
+                        above.is_none_or(|above| value > above)
+                            && below.is_none_or(|below| value < below)
+                    });
+                }
+                match attributes.get(key) {
+                    Some(Value::Bool(flag)) => *flag,
+                    Some(Value::Str(text)) => !text.is_empty(),
+                    Some(_) => true,
+                    None => false,
+                }
+            }
+        }
+    }
+}
+
+impl BehaviorArg {
+    fn from_toml(value: &toml::Value) -> Result<Self, String> {
+        match value {
+            toml::Value::String(text) if text.trim().eq_ignore_ascii_case("target") => {
+                Ok(Self::Target)
+            }
+            toml::Value::String(text) => Ok(Self::Text(text.clone())),
+            toml::Value::Boolean(flag) => Ok(Self::Bool(*flag)),
+            value => toml_number(value)
+                .map(Self::Number)
+                .ok_or_else(|| format!("unsupported action argument `{value}`")),
+        }
+    }
+}
+
+fn toml_number(value: &toml::Value) -> Option<f32> {
+    value
+        .as_float()
+        .map(|value| value as f32)
+        .or_else(|| value.as_integer().map(|value| value as f32))
+}
+
+/// Parse the named trees of the `[behavior_trees]` game configuration.
+/// Trees that fail to parse are skipped and reported in the returned errors,
+/// as are references to trees which do not exist.
+pub fn behavior_trees_from_config(
+    config: &toml::Table,
+) -> (FxHashMap<String, BehaviorNode>, Vec<String>) {
+    let mut trees = FxHashMap::default();
+    let mut errors = Vec::new();
+    if let Some(table) = config.get("behavior_trees").and_then(toml::Value::as_table) {
+        for (name, value) in table {
+            match BehaviorNode::from_toml(value) {
+                Ok(tree) => {
+                    trees.insert(name.clone(), tree);
+                }
+                Err(err) => errors.push(format!("Behavior Tree `{name}`: {err}")),
+            }
+        }
+        for name in table.keys() {
+            if let Some(tree) = trees.get(name) {
+                for unknown in tree.unknown_trees(&trees) {
+                    errors.push(format!("Behavior Tree `{name}`: unknown tree `{unknown}`"));
+                }
+            }
+        }
+    }
+    (trees, errors)
+}
+
+/// Evaluation state of one character's tree for one tick.
+struct BehaviorTick<'a> {
+    ctx: &'a mut RegionCtx,
+    trees: &'a FxHashMap<String, BehaviorNode>,
+    entity_id: u32,
+    state: BehaviorState,
+    /// The character the tree currently acts against, passed as `"target"`.
+    target: Option<u32>,
+}
+
+impl BehaviorTick<'_> {
+    fn entity(&self) -> Option<&Entity> {
+        self.ctx
+            .map
+            .entities
+            .iter()
+            .find(|entity| entity.id == self.entity_id)
+    }
+
+    fn entity_action(&self) -> Option<EntityAction> {
+        self.entity().map(|entity| entity.action.clone())
+    }
+
+    fn target_entity(&self) -> Option<&Entity> {
+        let target = self.target?;
+        self.ctx
+            .map
+            .entities
+            .iter()
+            .find(|entity| entity.id == target && entity.get_mode() != "dead")
+    }
+
+    /// Make the nearest perceived player matching `filter` the target.
+    fn perceive(&mut self, filter: impl Fn(Awareness) -> bool) -> bool {
+        let Some(position) = self.entity().map(Entity::get_pos_xz) else {
+            return false;
+        };
+        let Some(awareness) = self.ctx.entity_awareness.get(&self.entity_id) else {
+            return false;
+        };
+        let nearest = self
+            .ctx
+            .map
+            .entities
+            .iter()
+            .filter(|entity| awareness.get(&entity.id).is_some_and(|a| filter(*a)))
+            .min_by(|a, b| {
+                let a = a.get_pos_xz().distance_squared(position);
+                let b = b.get_pos_xz().distance_squared(position);
+                a.partial_cmp(&b).unwrap_or(std::cmp::Ordering::Equal)
+            })
+            .map(|entity| entity.id);
+        if nearest.is_some() {
+            self.target = nearest;
+        }
+        nearest.is_some()
+    }
+
+    fn eval(&mut self, node: &BehaviorNode, path: &str, depth: usize) -> BehaviorStatus {
+        match node {
+            BehaviorNode::Selector(children) => {
+                for (index, child) in children.iter().enumerate() {
+                    let status = self.eval(child, &format!("{path}.{index}"), depth);
+                    if status != BehaviorStatus::Failure {
+                        return status;
+                    }
+                }
+                BehaviorStatus::Failure
+            }
+            BehaviorNode::Sequence(children) => {
+                for (index, child) in children.iter().enumerate() {
+                    let status = self.eval(child, &format!("{path}.{index}"), depth);
+                    if status != BehaviorStatus::Success {
+                        return status;
+                    }
+                }
+                BehaviorStatus::Success
+            }
+            BehaviorNode::Invert(child) => match self.eval(child, &format!("{path}.0"), depth) {
+                BehaviorStatus::Success => BehaviorStatus::Failure,
+                BehaviorStatus::Failure => BehaviorStatus::Success,
+                BehaviorStatus::Running => BehaviorStatus::Running,
+            },
+            BehaviorNode::Succeed(child) => match self.eval(child, &format!("{path}.0"), depth) {
+                BehaviorStatus::Failure => BehaviorStatus::Success,
+                status => status,
+            },
+            BehaviorNode::Cooldown(seconds, child) => {
+                if self
+                    .state
+                    .cooldowns
+                    .get(path)
+                    .is_some_and(|ready| self.ctx.ticks < *ready)
+                {
+                    return BehaviorStatus::Failure;
+                }
+                let status = self.eval(child, &format!("{path}.0"), depth);
+                if status == BehaviorStatus::Success {
+                    let ticks = RegionInstance::realtime_seconds_to_ticks(self.ctx, *seconds);
+                    self.state
+                        .cooldowns
+                        .insert(path.to_string(), self.ctx.ticks + ticks);
+                }
+                status
+            }
+            BehaviorNode::Chance(chance, child) => {
+                if crate::server::rng::sim_rng().random_range(0.0..1.0) >= *chance {
+                    return BehaviorStatus::Failure;
+                }
+                self.eval(child, &format!("{path}.0"), depth)
+            }
+            BehaviorNode::Condition(condition) => {
+                if condition.matches(self) {
+                    BehaviorStatus::Success
+                } else {
+                    BehaviorStatus::Failure
+                }
+            }
+            BehaviorNode::Action(name, args) => self.action(name, args, path),
+            BehaviorNode::Tree(name) => {
+                let trees = self.trees;
+                match trees.get(name) {
+                    Some(tree) if depth < MAX_TREE_DEPTH => {
+                        self.eval(tree, &format!("{path}/{name}"), depth + 1)
+                    }
+                    _ => BehaviorStatus::Failure,
+                }
+            }
+        }
+    }
+
+    /// Call the server command of an action node. Commands that start an
+    /// entity action keep running until the action finishes or another node
+    /// replaces it.
+    fn action(&mut self, name: &str, args: &[BehaviorArg], path: &str) -> BehaviorStatus {
+        let Some(before) = self.entity_action() else {
+            return BehaviorStatus::Failure;
+        };
+        if self.state.running.as_deref() == Some(path) {
+            if before != EntityAction::Off {
+                return BehaviorStatus::Running;
+            }
+            self.state.running = None;
+            return BehaviorStatus::Success;
+        }
+
+        let mut values = Vec::with_capacity(args.len());
+        for arg in args {
+            values.push(match arg {
+                BehaviorArg::Number(number) => VMValue::from_f32(*number),
+                BehaviorArg::Bool(flag) => VMValue::from_bool(*flag),
+                BehaviorArg::Text(text) => VMValue::from_string(text.clone()),
+                BehaviorArg::Target => match self.target {
+                    Some(target) => VMValue::from(target),
+                    None => return BehaviorStatus::Failure,
+                },
+            });
+        }
+
+        let result = run_entity_host_call(self.ctx, self.entity_id, name, &values);
+        if result.is_some_and(|value| !value.is_truthy() || value.x < 0.0) {
+            return BehaviorStatus::Failure;
+        }
+        match self.entity_action() {
+            Some(after) if after != EntityAction::Off && after != before => {
+                self.state.running = Some(path.to_string());
+                BehaviorStatus::Running
+            }
+            _ => BehaviorStatus::Success,
+        }
+    }
+}
+
+/// The character id in an entity's `target` attribute.
+fn target_attribute(entity: &Entity) -> Option<u32> {
+    match entity.attributes.get("target") {
+        Some(Value::UInt(id)) => Some(*id),
+        Some(Value::Int(id)) if *id >= 0 => Some(*id as u32),
+        Some(Value::Int64(id)) if *id >= 0 => Some(*id as u32),
+        Some(Value::Str(id)) => id.trim().parse::<u32>().ok(),
+        _ => None,
+    }
+}
+
+/// Tick the behavior tree of every character that has one.
+pub(crate) fn update_entity_behavior(ctx: &mut RegionCtx) {
+    let trees = std::mem::take(&mut ctx.behavior_trees);
+    let ids: Vec<u32> = ctx
+        .map
+        .entities
+        .iter()
+        .filter(|entity| {
+            entity.behavior_tree.is_some() && !entity.is_player() && entity.get_mode() != "dead"
+        })
+        .map(|entity| entity.id)
+        .collect();
+
+    for id in ids {
+        let Some(entity) = ctx.map.entities.iter_mut().find(|entity| entity.id == id) else {
+            continue;
+        };
+        let Some(tree) = entity.behavior_tree.take() else {
+            continue;
+        };
+        let state = std::mem::take(&mut entity.behavior_state);
+        let target = target_attribute(entity);
+
+        let mut tick = BehaviorTick {
+            ctx: &mut *ctx,
+            trees: &trees,
+            entity_id: id,
+            state,
+            target,
+        };
+        tick.eval(&tree, "0", 0);
+        let state = tick.state;
+
+        if let Some(entity) = ctx.map.entities.iter_mut().find(|entity| entity.id == id) {
+            entity.behavior_tree = Some(tree);
+            entity.behavior_state = state;
+        }
+    }
+
+    ctx.behavior_trees = trees;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GUARD: &str = r#"
+[behavior_trees.guard]
+selector = [
+    { sequence = [
+        { condition = "sees_player" },
+        { action = "follow_attack", args = ["target", 1.5] },
+    ] },
+    { action = "random_walk_in_sector", args = [1.0, 1.0, 4] },
+]
+
+[behavior_trees.broken]
+condition = "smells_player"
+"#;
+
+    fn guard_ctx() -> RegionCtx {
+        let mut ctx = RegionCtx::default();
+        let (trees, errors) = behavior_trees_from_config(&GUARD.parse().unwrap());
+        assert_eq!(errors.len(), 1);
+        ctx.behavior_trees = trees;
+
+        let mut guard = Entity::new();
+        guard.id = 1;
+        guard.behavior_tree = Some(BehaviorNode::Tree("guard".into()));
+        ctx.map.entities.push(guard);
+        let mut player = Entity::new();
+        player.id = 2;
+        player.set_attribute("player", Value::Bool(true));
+        ctx.map.entities.push(player);
+        ctx
+    }
+
+    #[test]
+    fn selector_switches_between_attack_and_wander() {
+        let mut ctx = guard_ctx();
+        update_entity_behavior(&mut ctx);
+        assert!(matches!(
+            ctx.map.entities[0].action,
+            EntityAction::RandomWalkInSector(..)
+        ));
+
+        ctx.entity_awareness
+            .insert(1, FxHashMap::from_iter([(2, Awareness::Seen)]));
+        update_entity_behavior(&mut ctx);
+        assert_eq!(
+            ctx.map.entities[0].action,
+            EntityAction::FollowAttack(2, 1.5, 0)
+        );
+        let running = ctx.map.entities[0].behavior_state.running.clone();
+        assert!(running.is_some());
+
+        update_entity_behavior(&mut ctx);
+        assert_eq!(ctx.map.entities[0].behavior_state.running, running);
+
+        ctx.entity_awareness.clear();
+        update_entity_behavior(&mut ctx);
+        assert!(matches!(
+            ctx.map.entities[0].action,
+            EntityAction::RandomWalkInSector(..)
+        ));
+    }
+
+    #[test]
+    fn config_reports_unknown_trees_and_arguments() {
+        let config = r#"
+[behavior_trees.patrol]
+sequence = [{ tree = "guard" }, { tree = "rest" }]
+
+[behavior_trees.guard]
+action = "random_walk_in_sector"
+args = [1.0, { speed = 2.0 }]
+"#;
+        let (trees, errors) = behavior_trees_from_config(&config.parse().unwrap());
+        assert_eq!(trees.keys().collect::<Vec<_>>(), vec!["patrol"]);
+        assert_eq!(errors.len(), 3);
+        assert!(errors[0].starts_with("Behavior Tree `guard`: unsupported action argument"));
+        assert_eq!(
+            errors[1..],
+            [
+                "Behavior Tree `patrol`: unknown tree `guard`",
+                "Behavior Tree `patrol`: unknown tree `rest`",
+            ]
+        );
+    }
+
+    #[test]
+    fn cooldown_blocks_the_child_until_ready() {
+        let mut ctx = RegionCtx::default();
+        let tree: toml::Value = toml::from_str(
+            r#"
+cooldown = 10
+child = { action = "toggle_attr", args = "alert" }
+"#,
+        )
+        .unwrap();
+        let mut entity = Entity::new();
+        entity.id = 1;
+        entity.behavior_tree = Some(BehaviorNode::from_toml(&tree).unwrap());
+        ctx.map.entities.push(entity);
+
+        let alert = |ctx: &RegionCtx| {
+            ctx.map.entities[0]
+                .attributes
+                .get_bool_default("alert", false)
+        };
+        update_entity_behavior(&mut ctx);
+        assert!(alert(&ctx));
+        update_entity_behavior(&mut ctx);
+        assert!(alert(&ctx));
+
+        ctx.ticks += RegionInstance::realtime_seconds_to_ticks(&ctx, 10.0);
+        update_entity_behavior(&mut ctx);
+        assert!(!alert(&ctx));
+    }
+}
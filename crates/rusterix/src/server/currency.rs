@@ -206,6 +206,29 @@ impl Currencies {
         Ok(base_amount)
     }
 
+    /// Round an amount in base currency to whole units of the given currency,
+    /// e.g. for vendors which only deal in silver.
+    pub fn round_base_amount_to(
+        &self,
+        base_amount: i64,
+        id_or_symbol: &str,
+        round_up: bool,
+    ) -> i64 {
+        let Some(unit) = self
+            .symbol_for(id_or_symbol)
+            .and_then(|symbol| self.get_currency(&symbol))
+            .map(|currency| currency.exchange_rate.round().max(1.0) as i64)
+        else {
+            return base_amount;
+        };
+        let units = if round_up {
+            (base_amount + unit - 1).div_euclid(unit)
+        } else {
+            base_amount.div_euclid(unit)
+        };
+        units * unit
+    }
+
     pub fn format_base_amount(&self, base_amount: i64) -> String {
         let mut amount = base_amount.max(0);
         if amount == 0 {
@@ -342,6 +365,15 @@ mod tests {
         assert_eq!(currencies.format_base_amount(0), "0c");
     }
 
+    #[test]
+    fn rounds_base_amounts_to_a_currency() {
+        let currencies = Currencies::official_default();
+        assert_eq!(currencies.round_base_amount_to(123, "silver", true), 130);
+        assert_eq!(currencies.round_base_amount_to(123, "s", false), 120);
+        assert_eq!(currencies.round_base_amount_to(200, "gold", true), 200);
+        assert_eq!(currencies.round_base_amount_to(7, "pearls", true), 7);
+    }
+
     #[test]
     fn wallet_stores_and_spends_base_units() {
         let currencies = Currencies::official_default();
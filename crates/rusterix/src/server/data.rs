@@ -1,6 +1,7 @@
 use crate::{
     Entity, Item, Light, LightType, PixelSource, PlayerCamera, Value,
-    server::entity::{EntitySequence, EntitySequenceStep},
+    server::behavior_tree::BehaviorNode,
+    server::entity::{EntityScheduleEntry, EntitySequence, EntitySequenceStep},
 };
 use indexmap::IndexMap;
 use std::sync::{LazyLock, RwLock};
@@ -51,6 +52,7 @@ fn player_camera_from_str(camera: &str) -> Option<PlayerCamera> {
         "iso_grid" => Some(PlayerCamera::D2Grid),
         "firstp" => Some(PlayerCamera::D3FirstP),
         "firstp_grid" => Some(PlayerCamera::D3FirstPGrid),
+        "thirdp" => Some(PlayerCamera::D3ThirdP),
         _ => None,
     }
 }
@@ -157,6 +159,106 @@ fn parse_entity_sequences_from_toml(map: &Table) -> IndexMap<String, EntitySeque
     sequences
 }
 
+/// Parse a schedule time, either `"HH:MM"` or a whole hour, into minutes
+/// since midnight.
+fn parse_schedule_time(value: &toml::Value) -> Option<i32> {
+    if let Some(hour) = value.as_integer() {
+        return (0..=24).contains(&hour).then_some(hour as i32 * 60 % 1440);
+    }
+    let time = TheTime::default()
+        .from_time_string(value.as_str()?.trim())
+        .ok()?;
+    Some(time.total_minutes())
+}
+
+fn parse_entity_schedule_from_toml(map: &Table) -> Vec<EntityScheduleEntry> {
+    let Some(entries) = map
+        .get("behavior")
+        .and_then(|value| value.as_table())
+        .and_then(|behavior| behavior.get("schedule"))
+        .and_then(|value| value.as_array())
+    else {
+        return Vec::new();
+    };
+
+    let text = |table: &Table, key: &str| {
+        table
+            .get(key)
+            .and_then(|value| value.as_str())
+            .map(|value| value.trim().to_string())
+            .unwrap_or_default()
+    };
+
+    let mut schedule = Vec::new();
+    for entry in entries {
+        let Some(table) = entry.as_table() else {
+            continue;
+        };
+        let (Some(from), Some(to)) = (
+            table.get("from").and_then(parse_schedule_time),
+            table.get("to").and_then(parse_schedule_time),
+        ) else {
+            continue;
+        };
+
+        let route = match table.get("route") {
+            Some(toml::Value::Array(values)) => values
+                .iter()
+                .filter_map(|value| value.as_str())
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty())
+                .collect(),
+            Some(toml::Value::String(value)) if !value.trim().is_empty() => {
+                vec![value.trim().to_string()]
+            }
+            _ => Vec::new(),
+        };
+        let speed = table.get("speed").and_then(|value| {
+            value
+                .as_float()
+                .map(|value| value as f32)
+                .or_else(|| value.as_integer().map(|value| value as f32))
+        });
+
+        schedule.push(EntityScheduleEntry {
+            from,
+            to,
+            activity: text(table, "activity"),
+            region: text(table, "region"),
+            sector: text(table, "sector"),
+            route,
+            sequence: text(table, "sequence"),
+            wander: table
+                .get("wander")
+                .and_then(|value| value.as_bool())
+                .unwrap_or(false),
+            speed,
+        });
+    }
+
+    schedule
+}
+
+/// The character's behavior tree: `[behavior] tree = "name"` for a tree from
+/// the game configuration or an inline `[behavior.tree]` node.
+pub(crate) fn parse_entity_behavior_tree_from_toml(
+    map: &Table,
+) -> Result<Option<BehaviorNode>, String> {
+    let Some(tree) = map
+        .get("behavior")
+        .and_then(|value| value.as_table())
+        .and_then(|behavior| behavior.get("tree"))
+    else {
+        return Ok(None);
+    };
+    match tree {
+        toml::Value::String(name) if !name.trim().is_empty() => {
+            Ok(Some(BehaviorNode::Tree(name.trim().to_string())))
+        }
+        tree => BehaviorNode::from_toml(tree).map(Some),
+    }
+}
+
 pub(crate) fn parse_tile_source_from_str(value: &str) -> Option<PixelSource> {
     let trimmed = value.trim();
     if let Ok(uuid) = Uuid::parse_str(trimmed) {
@@ -176,6 +278,9 @@ pub fn apply_entity_data(entity: &mut Entity, toml: &str) {
     match toml.parse::<Table>() {
         Ok(map) => {
             entity.sequences = parse_entity_sequences_from_toml(&map);
+            entity.schedule = parse_entity_schedule_from_toml(&map);
+            // Invalid trees are reported when the region starts.
+            entity.behavior_tree = parse_entity_behavior_tree_from_toml(&map).unwrap_or_default();
             for (attr, v) in map.iter() {
                 if attr == "attributes" {
                     if let Some(values) = v.as_table() {
@@ -554,4 +659,55 @@ appearance_tiling = [2.0, 3.0]
             Some([2.0, 3.0])
         );
     }
+
+    #[test]
+    fn entity_schedule_parses_times_and_destinations() {
+        let mut entity = Entity::new();
+
+        apply_entity_data(
+            &mut entity,
+            r#"
+[[behavior.schedule]]
+from = "08:00"
+to = "18:30"
+activity = "work"
+sector = "Shop"
+wander = true
+
+[[behavior.schedule]]
+from = "18:30"
+to = 8
+activity = "sleep"
+region = "Village"
+sector = "Home"
+
+[[behavior.schedule]]
+from = "25:00"
+to = "26:00"
+"#,
+        );
+
+        assert_eq!(entity.schedule.len(), 2);
+        let work = &entity.schedule[0];
+        assert_eq!((work.from, work.to), (480, 1110));
+        assert!(work.wander && work.contains(600) && !work.contains(1110));
+        let sleep = &entity.schedule[1];
+        assert_eq!(sleep.region, "Village");
+        assert!(sleep.contains(1380) && sleep.contains(60) && !sleep.contains(600));
+    }
+
+    #[test]
+    fn entity_behavior_tree_reports_invalid_inline_trees() {
+        let tree = |data: &str| parse_entity_behavior_tree_from_toml(&data.parse().unwrap());
+
+        assert_eq!(
+            tree("[behavior]\ntree = \"guard\""),
+            Ok(Some(BehaviorNode::Tree("guard".into())))
+        );
+        assert_eq!(tree("[attributes]\nname = \"Guard\""), Ok(None));
+        assert_eq!(
+            tree("[behavior.tree]\ncondition = \"smells_player\""),
+            Err("unknown condition `smells_player`".into())
+        );
+    }
 }
@@ -3,6 +3,7 @@ use rand::Rng;
 use theframework::prelude::*;
 use vek::{Vec2, Vec3};
 
+use crate::server::behavior_tree::{BehaviorNode, BehaviorState};
 use crate::{EntityAction, prelude::*};
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default)]
@@ -26,6 +27,44 @@ pub struct EntitySequence {
     pub steps: Vec<EntitySequenceStep>,
 }
 
+/// One entry of an entity's daily schedule. The entry is active from `from`
+/// until `to` (minutes since midnight) and may wrap around midnight.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default)]
+pub struct EntityScheduleEntry {
+    pub from: i32,
+    pub to: i32,
+    #[serde(default)]
+    pub activity: String,
+    /// Destination region, empty for the entity's home region.
+    #[serde(default)]
+    pub region: String,
+    /// Destination sector.
+    #[serde(default)]
+    pub sector: String,
+    /// Route to patrol, see the `route` attribute.
+    #[serde(default)]
+    pub route: Vec<String>,
+    /// Background sequence to run.
+    #[serde(default)]
+    pub sequence: String,
+    /// Random walk in the destination sector after arriving.
+    #[serde(default)]
+    pub wander: bool,
+    #[serde(default)]
+    pub speed: Option<f32>,
+}
+
+impl EntityScheduleEntry {
+    /// Whether the entry covers `minutes` since midnight.
+    pub fn contains(&self, minutes: i32) -> bool {
+        if self.from <= self.to {
+            minutes >= self.from && minutes < self.to
+        } else {
+            minutes >= self.from || minutes < self.to
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default)]
 pub struct EntitySequenceState {
     pub name: String,
@@ -71,6 +110,27 @@ pub struct Entity {
     #[serde(default)]
     pub paused_sequence: Option<EntitySequenceState>,
 
+    /// Daily schedule defined in entity data.
+    #[serde(default)]
+    pub schedule: Vec<EntityScheduleEntry>,
+
+    /// Index of the schedule entry the entity currently follows.
+    #[serde(default)]
+    pub active_schedule: Option<usize>,
+
+    /// Region the entity spawned in. Schedule entries without a region lead
+    /// back here.
+    #[serde(default)]
+    pub home_region: String,
+
+    /// Behavior tree defined in entity data.
+    #[serde(default)]
+    pub behavior_tree: Option<BehaviorNode>,
+
+    /// Running action and cooldowns of the behavior tree.
+    #[serde(default)]
+    pub behavior_state: BehaviorState,
+
     /// Dirty static attributes
     /// The `dirty_flags` field is a bitmask representing changes to various components of the entity.
     /// Each bit corresponds to a specific type of change:
@@ -80,6 +140,7 @@ pub struct Entity {
     /// - `0b00001000` (8): Inventory changed
     /// - `0b00010000` (16): Equipped items changed
     /// - `0b00100000` (32): Wallet changed
+    /// - `0b01000000` (64): Quest log changed
     pub dirty_flags: u8,
 
     /// Dirty Attributes
@@ -102,6 +163,10 @@ pub struct Entity {
     /// Wallet
     pub wallet: Wallet,
 
+    /// Quest journal
+    #[serde(default)]
+    pub quests: QuestLog,
+
     /// Client-side interpolation target for presentation smoothing.
     #[serde(skip)]
     pub interp_target_position: Option<Vec3<f32>>,
@@ -140,6 +205,13 @@ impl Entity {
             active_sequence: None,
             paused_sequence: None,
 
+            schedule: Vec::new(),
+            active_schedule: None,
+            home_region: String::new(),
+
+            behavior_tree: None,
+            behavior_state: BehaviorState::default(),
+
             dirty_flags: 0,
             dirty_attributes: FxHashSet::default(),
 
@@ -152,6 +224,8 @@ impl Entity {
 
             wallet: Wallet::default(),
 
+            quests: QuestLog::default(),
+
             interp_target_position: None,
             interp_remaining: 0.0,
             interp_duration: 0.0,
@@ -235,7 +309,16 @@ impl Entity {
         // println!("{} {}", self.position, self.orientation);
         let id = camera.id();
 
-        if id != "iso" {
+        if id == "thirdp" {
+            // The chase camera follows behind the facing direction, looking at eye level.
+            let mut pivot = self.position;
+            pivot.y += firstp_eye_level;
+            camera.set_parameter_vec3(
+                "forward",
+                Vec3::new(self.orientation.x, 0.0, self.orientation.y),
+            );
+            camera.set_parameter_vec3("target", pivot);
+        } else if id != "iso" {
             let mut cam_pos = self.position;
             let mut cam_center = self.camera_look_at();
             if id == "firstp" {
@@ -490,6 +573,11 @@ impl Entity {
         Ok(())
     }
 
+    /// Mark the quest log as changed.
+    pub fn mark_quests_dirty(&mut self) {
+        self.dirty_flags |= 0b1000000;
+    }
+
     /// Set a dynamic attribute and mark it as dirty
     pub fn set_attribute(&mut self, key: &str, value: Value) {
         self.attributes.set(key, value);
@@ -623,6 +711,11 @@ impl Entity {
             } else {
                 None
             },
+            quest_updates: if self.dirty_flags & 0b1000000 != 0 {
+                Some(self.quests.clone())
+            } else {
+                None
+            },
         }
     }
 
@@ -698,6 +791,11 @@ impl Entity {
             }
         }
 
+        // Apply quest log updates
+        if let Some(quest_updates) = update.quest_updates {
+            self.quests = quest_updates;
+        }
+
         rc
     }
 
@@ -782,7 +880,7 @@ impl Entity {
 
     /// Sets the orientation to face a random direction.
     pub fn face_random(&mut self) {
-        let mut rng = rand::rng();
+        let mut rng = crate::server::rng::sim_rng();
         let angle = rng.random_range(0.0..std::f32::consts::TAU); // TAU is 2π
         let direction = Vec2::new(angle.cos(), angle.sin());
         self.set_orientation(direction);
@@ -882,6 +980,8 @@ pub struct EntityUpdate {
     pub inventory_updates: Option<FxHashMap<usize, ItemUpdate>>,
     pub equipped_updates: Option<IndexMap<String, Item>>,
     pub wallet_updates: Option<FxHashMap<String, i64>>,
+    #[serde(default)]
+    pub quest_updates: Option<QuestLog>,
 }
 
 impl EntityUpdate {
@@ -905,6 +1005,7 @@ impl EntityUpdate {
             inventory_removals: None,
             equipped_updates: None,
             wallet_updates: None,
+            quest_updates: None,
         })
     }
 }
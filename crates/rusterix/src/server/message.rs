@@ -1,9 +1,9 @@
 use crate::vm::EldrinDebugModule;
-use crate::{Entity, Map, Value, ValueContainer};
+use crate::{Entity, Map, QuestEvent, Value, ValueContainer};
 use scenevm::PaletteRemap2DMode;
 use theframework::prelude::*;
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub enum AudioCommand {
     /// Play an audio asset on a bus/layer.
     Play {
@@ -101,8 +101,10 @@ pub enum RegionMessage {
     ItemsUpdate(u32, Vec<Vec<u8>>),
     /// Remove the given item from the Region
     RemoveItem(u32, u32),
-    /// Open a container panel for the local client: RegionId, ContainerItemId, OwnerEntityId
-    OpenContainer(u32, u32, Option<u32>),
+    /// Remove the given entity from the Region: RegionId, EntityId
+    RemoveEntity(u32, u32),
+    /// Open a container panel for a player: RegionId, ContainerItemId, OwnerEntityId, ReceiverId
+    OpenContainer(u32, u32, Option<u32>, u32),
     /// Log Message
     LogMessage(String),
     /// Time event of a Region
@@ -135,6 +137,12 @@ pub enum RegionMessage {
     SetWorldPostValue(String, Value),
     /// Send Eldrin source-line debug data.
     EldrinDebugData(EldrinDebugModule),
+    /// Ask the client to save the game into the given slot.
+    SaveGame(String),
+    /// Ask the client to load the game from the given slot.
+    LoadGame(String),
+    /// A quest state change of an entity: RegionId, Event
+    QuestEvent(u32, QuestEvent),
     /// Pause the server.
     Pause,
     /// Continue after pause
@@ -266,6 +274,7 @@ pub enum PlayerCamera {
     D3Iso,
     D3FirstP,
     D3FirstPGrid,
+    D3ThirdP,
 }
 
 use std::fmt;
@@ -333,8 +342,9 @@ impl TryFrom<i32> for EntityAction {
 pub enum Choice {
     // Cancels a multiple choice. from, to, expires_at_tick, max_distance
     Cancel(u32, u32, i64, f32),
-    /// An item to sell. item_id, seller_id, buyer_id, expires_at_tick, max_distance
-    ItemToSell(u32, u32, u32, i64, f32),
+    /// An item to sell. item_id, seller_id, buyer_id, expires_at_tick, max_distance, price.
+    /// The price is the quote shown to the buyer, the server charges its own price.
+    ItemToSell(u32, u32, u32, i64, f32, i64),
     /// A script-defined choice. label, choice_attr, from, to, index, expires_at_tick, max_distance
     ScriptChoice(String, String, u32, u32, u32, i64, f32),
     /// A TOML-authored dialog choice.
@@ -347,7 +357,7 @@ impl Choice {
             Choice::Cancel(from, to, expires_at_tick, max_distance) => {
                 (*from, *to, *expires_at_tick, *max_distance)
             }
-            Choice::ItemToSell(_, seller_id, buyer_id, expires_at_tick, max_distance) => {
+            Choice::ItemToSell(_, seller_id, buyer_id, expires_at_tick, max_distance, _) => {
                 (*seller_id, *buyer_id, *expires_at_tick, *max_distance)
             }
             Choice::ScriptChoice(_, _, from, to, _, expires_at_tick, max_distance) => {
@@ -378,7 +388,7 @@ pub struct DialogChoice {
 }
 
 /// Multiple choices for the player
-#[derive(Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct MultipleChoice {
     pub region: u32,
     pub from: u32,
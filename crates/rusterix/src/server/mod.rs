@@ -1,16 +1,23 @@
 pub mod assets;
+pub mod behavior_tree;
 pub mod currency;
 pub mod data;
 pub mod entity;
 pub mod item;
 pub mod message;
+pub mod net;
+pub mod perception;
 pub mod py_fn;
+pub mod quest;
 pub mod region;
 pub mod region_host;
 pub mod regionctx;
+pub mod rng;
+pub mod savegame;
+pub mod schedule;
 
 use crossbeam_channel::{Receiver, Sender};
-use instant::Instant;
+use instant::{Duration, Instant};
 use rayon::prelude::*;
 use toml::Table;
 
@@ -18,6 +25,9 @@ use crate::Command;
 use crate::EntityAction;
 use crate::prelude::*;
 use crate::server::message::{AudioCommand, PaletteRemap2DState, RuntimeRenderState};
+use crate::server::net::{ClientNetMessage, RemoteConnection, ServerNetMessage};
+use crate::server::regionctx::RegionCtx;
+use crate::server::savegame::{SAVE_GAME_VERSION, SaveGame};
 use std::sync::{Arc, LazyLock, Mutex, RwLock};
 use theframework::prelude::*;
 
@@ -34,8 +44,15 @@ static LOCAL_PLAYERS: LazyLock<Player> = LazyLock::new(|| Arc::new(RwLock::new(V
 pub type Message = (Option<u32>, Option<u32>, u32, String, String);
 // SenderEntityId, SenderItemId, Message, Category
 pub type Say = (Option<u32>, Option<u32>, String, String);
-// ContainerItemId, OwnerEntityId
-pub type OpenContainerRequest = (u32, Option<u32>);
+// ContainerItemId, OwnerEntityId, ReceiverId
+pub type OpenContainerRequest = (u32, Option<u32>, u32);
+
+/// A save or load request raised by a script, handled by the client which owns the files.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SaveGameRequest {
+    Save(String),
+    Load(String),
+}
 
 #[derive(Clone, Copy, PartialEq)]
 pub enum ServerState {
@@ -66,11 +83,19 @@ pub struct Server {
     pub open_container_requests: FxHashMap<u32, Vec<OpenContainerRequest>>,
     pub multiple_choice: FxHashMap<u32, Vec<MultipleChoice>>,
     pub audio_commands: FxHashMap<u32, Vec<AudioCommand>>,
+    pub quest_events: FxHashMap<u32, Vec<QuestEvent>>,
     pub world_render: RuntimeRenderState,
     pub region_render: FxHashMap<u32, RuntimeRenderState>,
     pub times: FxHashMap<u32, TheTime>,
     pub runtime_maps: FxHashMap<u32, Map>,
     pub runtime_map_position_guards: FxHashMap<u32, u8>,
+    pub save_game_requests: Vec<SaveGameRequest>,
+
+    /// World deltas for remote players, only collected while hosting.
+    pub net_outbox: Option<Vec<ServerNetMessage>>,
+    net_players: Vec<(u32, u32)>,
+    /// The connection to a dedicated server when running as a remote client.
+    remote: Option<RemoteConnection>,
 
     pub state: ServerState,
 
@@ -80,6 +105,9 @@ pub struct Server {
 
     pub instances: Vec<Arc<Mutex<RegionInstance>>>,
     last_visual_update_at: Instant,
+
+    /// Seed and redraw step of a deterministic run, see `set_deterministic`.
+    deterministic: Option<(u64, Duration)>,
 }
 
 impl Default for Server {
@@ -108,11 +136,17 @@ impl Server {
             open_container_requests: FxHashMap::default(),
             multiple_choice: FxHashMap::default(),
             audio_commands: FxHashMap::default(),
+            quest_events: FxHashMap::default(),
             world_render: RuntimeRenderState::default(),
             region_render: FxHashMap::default(),
             times: FxHashMap::default(),
             runtime_maps: FxHashMap::default(),
             runtime_map_position_guards: FxHashMap::default(),
+            save_game_requests: vec![],
+
+            net_outbox: None,
+            net_players: vec![],
+            remote: None,
 
             state: ServerState::Off,
 
@@ -122,6 +156,8 @@ impl Server {
 
             instances: vec![],
             last_visual_update_at: Instant::now(),
+
+            deterministic: None,
         }
     }
 
@@ -136,6 +172,19 @@ impl Server {
         self.log.clone()
     }
 
+    /// Append a message to the log.
+    pub fn log_message(&mut self, message: String) {
+        if self.print_log_messages {
+            println!("{}", message);
+        }
+        if self.log.is_empty() {
+            self.log = message;
+        } else {
+            self.log += &format!("{}{}", "\n", message);
+        }
+        self.log_changed = true;
+    }
+
     /// Set the server state.
     pub fn set_state(&mut self, state: ServerState) {
         self.state = state;
@@ -162,12 +211,34 @@ impl Server {
 
         self.from_region.push(region_instance.from_receiver.clone());
 
+        if let Some((seed, step)) = self.deterministic {
+            region_instance.set_deterministic(seed, step);
+        }
         region_instance.init(name, map, assets, config_toml, self.debug_mode);
         self.instances.push(Arc::new(Mutex::new(region_instance)));
     }
 
+    /// Run all regions created from now on deterministically: randomness is
+    /// seeded from `seed` and every redraw tick advances time by `step`. The
+    /// regions are ticked one after another so cross-region messages keep
+    /// their order. Used by headless simulations and replays.
+    pub fn set_deterministic(&mut self, seed: u64, step: Duration) {
+        self.deterministic = Some((seed, step));
+    }
+
+    /// The seed and redraw step of a deterministic run.
+    pub fn deterministic(&self) -> Option<(u64, Duration)> {
+        self.deterministic
+    }
+
     /// Send a system tick to all instances.
     pub fn system_tick(&self) {
+        if self.deterministic.is_some() {
+            for instance in &self.instances {
+                instance.lock().unwrap().system_tick();
+            }
+            return;
+        }
         self.instances.par_iter().for_each(|instance| {
             instance.lock().unwrap().system_tick();
         });
@@ -175,6 +246,12 @@ impl Server {
 
     /// Send a redraw tick to all instances.
     pub fn redraw_tick(&self) {
+        if self.deterministic.is_some() {
+            for instance in &self.instances {
+                instance.lock().unwrap().redraw_tick();
+            }
+            return;
+        }
         self.instances.par_iter().for_each(|instance| {
             instance.lock().unwrap().redraw_tick();
         });
@@ -296,6 +373,109 @@ impl Server {
         }
     }
 
+    /// Get queued quest events for a given region and clear them.
+    pub fn get_quest_events(&mut self, region_id: &Uuid) -> Vec<QuestEvent> {
+        if let Some(region_id) = self.region_id_map.get(region_id) {
+            self.quest_events.remove(region_id).unwrap_or_default()
+        } else {
+            vec![]
+        }
+    }
+
+    /// Get queued save / load requests from scripts and clear them.
+    pub fn get_save_game_requests(&mut self) -> Vec<SaveGameRequest> {
+        std::mem::take(&mut self.save_game_requests)
+    }
+
+    /// Capture the state of all running regions into a save game.
+    pub fn save_state(&self) -> SaveGame {
+        let mut save = SaveGame {
+            version: SAVE_GAME_VERSION,
+            next_id: crate::server::region::peek_global_id(),
+            world_state: RegionCtx::world_state(),
+            regions: vec![],
+        };
+        for instance in &self.instances {
+            if let Ok(instance) = instance.lock()
+                && let Some(state) = instance.save_state()
+            {
+                save.regions.push(state);
+            }
+        }
+        save
+    }
+
+    /// Restore a save game into the running regions. The regions have to be created
+    /// from the same game before loading. Returns the name of the region the local
+    /// player is in after loading.
+    pub fn load_state(&mut self, save: &SaveGame) -> Result<Option<String>, String> {
+        if save.version == 0 || save.version > SAVE_GAME_VERSION {
+            return Err(format!(
+                "Unsupported save game version {} (expected <= {}).",
+                save.version, SAVE_GAME_VERSION
+            ));
+        }
+        for region in &save.regions {
+            if !self.region_name_id_map.contains_key(&region.name) {
+                return Err(format!(
+                    "Save game region '{}' does not exist in this game.",
+                    region.name
+                ));
+            }
+        }
+
+        RegionCtx::set_world_state(save.world_state.clone());
+        crate::server::region::ensure_global_id_at_least(save.next_id);
+
+        let mut players = vec![];
+        let mut player_region = None;
+        for region in &save.regions {
+            let Some(region_id) = self.region_name_id_map.get(&region.name).copied() else {
+                continue;
+            };
+            for instance in &self.instances {
+                if let Ok(mut instance) = instance.lock()
+                    && instance.id == region_id
+                {
+                    instance.load_state(region);
+                }
+            }
+
+            let entities = region
+                .entities
+                .iter()
+                .cloned()
+                .map(|mut entity| {
+                    entity.inventory = region
+                        .inventories
+                        .get(&entity.id)
+                        .cloned()
+                        .unwrap_or_default();
+                    entity
+                })
+                .collect::<Vec<_>>();
+            for entity in entities.iter().filter(|entity| entity.is_player()) {
+                players.push((region_id, entity.id));
+                player_region = Some(region.name.clone());
+            }
+            self.entities.insert(region_id, entities);
+            self.items.insert(region_id, region.items.clone());
+            self.times.insert(region_id, region.time);
+        }
+
+        if let Ok(mut local_players) = LOCAL_PLAYERS.write() {
+            *local_players = players;
+        }
+
+        self.messages.clear();
+        self.says.clear();
+        self.multiple_choice.clear();
+        self.open_container_requests.clear();
+        self.quest_events.clear();
+
+        Ok(player_region)
+    }
+
     /// Get the current time for the given region.
     pub fn get_time(&self, region_id: &Uuid) -> Option<TheTime> {
         if let Some(region_id) = self.region_id_map.get(region_id) {
@@ -339,22 +519,35 @@ impl Server {
     /// Retrieves all messages from the regions. Returns the name of the new region should the
     /// players region change.
     pub fn update(&mut self, assets: &mut Assets) -> Option<String> {
+        if self.remote.is_some() {
+            return self.update_remote(assets);
+        }
+
         let mut rc: Option<String> = None;
         self.eldrin_debug.clear();
         let now = Instant::now();
-        let visual_dt = now
-            .saturating_duration_since(self.last_visual_update_at)
-            .as_secs_f32()
-            .clamp(0.0, 0.1);
+        let visual_dt = match self.deterministic {
+            Some((_, step)) => step.as_secs_f32().clamp(0.0, 0.1),
+            None => now
+                .saturating_duration_since(self.last_visual_update_at)
+                .as_secs_f32()
+                .clamp(0.0, 0.1),
+        };
         self.last_visual_update_at = now;
 
-        for receiver in &self.from_region {
+        // Regions are taken out while draining so that the handlers below can
+        // queue network messages through `&mut self`.
+        let from_region = std::mem::take(&mut self.from_region);
+        for receiver in &from_region {
             while let Ok(message) = receiver.try_recv() {
                 match message {
                     RegionMessage::RegisterPlayer(region_id, entity_id) => {
                         if let Ok(mut players) = LOCAL_PLAYERS.write() {
                             players.push((region_id, entity_id));
                         }
+                        if self.net_outbox.is_some() {
+                            self.net_players.push((region_id, entity_id));
+                        }
                         if let Ok(pipe) = REGIONPIPE.read()
                             && let Some(sender) = pipe.get(&region_id)
                         {
@@ -363,6 +556,9 @@ impl Server {
                         }
                     }
                     RegionMessage::EntitiesUpdate(id, serialized_updates) => {
+                        self.push_net_message(id, |map_id| {
+                            ServerNetMessage::EntitiesUpdate(map_id, serialized_updates.clone())
+                        });
                         let updates: Vec<EntityUpdate> = serialized_updates
                             .into_iter()
                             .map(|data| EntityUpdate::unpack(&data))
@@ -403,6 +599,9 @@ impl Server {
                         }
                     }
                     RegionMessage::ItemsUpdate(id, serialized_updates) => {
+                        self.push_net_message(id, |map_id| {
+                            ServerNetMessage::ItemsUpdate(map_id, serialized_updates.clone())
+                        });
                         let updates: Vec<ItemUpdate> = serialized_updates
                             .into_iter()
                             .map(|data| ItemUpdate::unpack(&data))
@@ -420,24 +619,30 @@ impl Server {
                         if let Some(items) = self.items.get_mut(&region_id) {
                             items.retain(|item| item.id != item_id);
                         }
+                        self.push_net_message(region_id, |map_id| {
+                            ServerNetMessage::RemoveItem(map_id, item_id)
+                        });
+                    }
+                    RegionMessage::RemoveEntity(region_id, entity_id) => {
+                        if let Some(entities) = self.entities.get_mut(&region_id) {
+                            entities.retain(|entity| entity.id != entity_id);
+                        }
+                        self.push_net_message(region_id, |map_id| {
+                            ServerNetMessage::RemoveEntity(map_id, entity_id)
+                        });
                     }
-                    RegionMessage::OpenContainer(region_id, item_id, owner_entity_id) => {
+                    RegionMessage::OpenContainer(
+                        region_id,
+                        item_id,
+                        owner_entity_id,
+                        receiver_id,
+                    ) => {
                         self.open_container_requests
                             .entry(region_id)
                             .or_default()
-                            .push((item_id, owner_entity_id));
-                    }
-                    RegionMessage::LogMessage(message) => {
-                        if self.print_log_messages {
-                            println!("{}", message);
-                        }
-                        if self.log.is_empty() {
-                            self.log = message;
-                        } else {
-                            self.log += &format!("{}{}", "\n", message);
-                        }
-                        self.log_changed = true;
+                            .push((item_id, owner_entity_id, receiver_id));
                     }
+                    RegionMessage::LogMessage(message) => self.log_message(message),
                     RegionMessage::Message(
                         id,
                         sender_entity,
@@ -490,10 +695,7 @@ impl Server {
                         }
                     }
                     RegionMessage::SetPaletteRemap2D(region_id, start_index, end_index, mode) => {
-                        let state = self
-                            .region_render
-                            .entry(region_id)
-                            .or_insert_with(RuntimeRenderState::default);
+                        let state = self.region_render.entry(region_id).or_default();
                         let palette = state
                             .palette_remap
                             .get_or_insert_with(PaletteRemap2DState::default);
@@ -502,10 +704,7 @@ impl Server {
                         palette.mode = mode;
                     }
                     RegionMessage::SetPaletteRemap2DBlend(region_id, blend) => {
-                        let state = self
-                            .region_render
-                            .entry(region_id)
-                            .or_insert_with(RuntimeRenderState::default);
+                        let state = self.region_render.entry(region_id).or_default();
                         let palette = state
                             .palette_remap
                             .get_or_insert_with(PaletteRemap2DState::default);
@@ -528,20 +727,14 @@ impl Server {
                         palette.blend = blend.clamp(0.0, 1.0);
                     }
                     RegionMessage::SetRenderValue(region_id, name, value) => {
-                        let state = self
-                            .region_render
-                            .entry(region_id)
-                            .or_insert_with(RuntimeRenderState::default);
+                        let state = self.region_render.entry(region_id).or_default();
                         state.render.set(&name, value);
                     }
                     RegionMessage::SetWorldRenderValue(name, value) => {
                         self.world_render.render.set(&name, value);
                     }
                     RegionMessage::SetPostValue(region_id, name, value) => {
-                        let state = self
-                            .region_render
-                            .entry(region_id)
-                            .or_insert_with(RuntimeRenderState::default);
+                        let state = self.region_render.entry(region_id).or_default();
                         state.post.set(&name, value);
                     }
                     RegionMessage::SetWorldPostValue(name, value) => {
@@ -549,6 +742,7 @@ impl Server {
                     }
                     RegionMessage::Time(id, time) => {
                         self.times.insert(id, time);
+                        self.push_net_message(id, |map_id| ServerNetMessage::Time(map_id, time));
                     }
                     RegionMessage::TransferEntity(
                         from_region_id,
@@ -564,10 +758,10 @@ impl Server {
 
                         let mut removed_local: Option<Entity> = None;
                         // Remove entity from the old region
-                        if let Some(entities) = self.entities.get_mut(&from_region_id) {
-                            if let Some(pos) = entities.iter().position(|e| e.id == entity.id) {
-                                removed_local = Some(entities.remove(pos));
-                            }
+                        if let Some(entities) = self.entities.get_mut(&from_region_id)
+                            && let Some(pos) = entities.iter().position(|e| e.id == entity.id)
+                        {
+                            removed_local = Some(entities.remove(pos));
                         }
 
                         // Add entity to the dest region
@@ -582,6 +776,16 @@ impl Server {
                                 removed_local.set_pos_xz(dest_center);
                                 removed_local.mark_all_dirty();
                             }
+                            if self.net_outbox.is_some() {
+                                let moved = removed_local.clone();
+                                self.push_net_message(from_region_id, |map_id| {
+                                    ServerNetMessage::RemoveEntity(map_id, moved.id)
+                                });
+                                self.push_net_message(dest_id, |map_id| {
+                                    let inventory = moved.inventory.clone();
+                                    ServerNetMessage::AddEntity(map_id, Box::new(moved), inventory)
+                                });
+                            }
                             if let Some(entities) = self.entities.get_mut(&dest_id) {
                                 entities.push(removed_local);
                             } else {
@@ -624,18 +828,18 @@ impl Server {
                             }
                         }
 
-                        if let Ok(pipe) = REGIONPIPE.read() {
-                            if let Some(sender) = pipe.get(&dest_id) {
-                                match sender.send(RegionMessage::TransferEntity(
-                                    dest_id,
-                                    entity.clone(),
-                                    dest_region_name.clone(),
-                                    dest_sector_name.clone(),
-                                )) {
-                                    Ok(_) => {}
-                                    Err(err) => {
-                                        println!("{:?}", err.to_string());
-                                    }
+                        if let Ok(pipe) = REGIONPIPE.read()
+                            && let Some(sender) = pipe.get(&dest_id)
+                        {
+                            match sender.send(RegionMessage::TransferEntity(
+                                dest_id,
+                                entity.clone(),
+                                dest_region_name.clone(),
+                                dest_sector_name.clone(),
+                            )) {
+                                Ok(_) => {}
+                                Err(err) => {
+                                    println!("{:?}", err.to_string());
                                 }
                             }
                         }
@@ -646,6 +850,13 @@ impl Server {
                             self.items.insert(region_id, map.items.clone());
                             self.runtime_maps.insert(region_id, map.clone());
                             self.runtime_map_position_guards.insert(region_id, 4);
+                            let snapshot = ServerNetMessage::Snapshot {
+                                map_id: map.id,
+                                entities: map.entities.clone(),
+                                inventories: FxHashMap::default(),
+                                items: map.items.clone(),
+                            };
+                            self.push_net_message(region_id, |_| snapshot);
                         }
                         assets.maps.insert(map.name.clone(), map.clone());
                         rc = Some(map.name.clone());
@@ -653,10 +864,20 @@ impl Server {
                     RegionMessage::EldrinDebugData(data) => {
                         self.eldrin_debug.merge(&data);
                     }
+                    RegionMessage::SaveGame(slot) => {
+                        self.save_game_requests.push(SaveGameRequest::Save(slot));
+                    }
+                    RegionMessage::LoadGame(slot) => {
+                        self.save_game_requests.push(SaveGameRequest::Load(slot));
+                    }
+                    RegionMessage::QuestEvent(region_id, event) => {
+                        self.quest_events.entry(region_id).or_default().push(event);
+                    }
                     _ => {}
                 }
             }
         }
+        self.from_region = from_region;
 
         for entities in self.entities.values_mut() {
             for entity in entities.iter_mut() {
@@ -787,6 +1008,10 @@ impl Server {
 
     /// Send a local player event to the registered players
     pub fn local_player_event(&mut self, event: String, value: Value) {
+        if let Some(remote) = self.remote.as_mut() {
+            remote.send(&ClientNetMessage::Event(event, value));
+            return;
+        }
         if let Ok(local_players) = LOCAL_PLAYERS.read() {
             if let Ok(pipe) = REGIONPIPE.read() {
                 for (region_id, entity_id) in local_players.iter() {
@@ -809,6 +1034,10 @@ impl Server {
 
     /// Send a local player action to the registered players
     pub fn local_player_action(&mut self, action: EntityAction) {
+        if let Some(remote) = self.remote.as_mut() {
+            remote.send(&ClientNetMessage::Action(action));
+            return;
+        }
         if let Ok(local_players) = LOCAL_PLAYERS.read() {
             if let Ok(pipe) = REGIONPIPE.read() {
                 for (region_id, entity_id) in local_players.iter() {
@@ -827,6 +1056,10 @@ impl Server {
 
     /// Instantly move all registered local players to a sector, optionally in another region.
     pub fn local_player_teleport(&mut self, sector_name: String, region_name: String) {
+        if let Some(remote) = self.remote.as_mut() {
+            remote.send(&ClientNetMessage::Teleport(sector_name, region_name));
+            return;
+        }
         if let Ok(local_players) = LOCAL_PLAYERS.read() {
             if let Ok(pipe) = REGIONPIPE.read() {
                 for (region_id, entity_id) in local_players.iter() {
@@ -849,6 +1082,10 @@ impl Server {
 
     /// Instantly move all registered local players to a position in their current region.
     pub fn local_player_teleport_pos(&mut self, position: Vec2<f32>) {
+        if let Some(remote) = self.remote.as_mut() {
+            remote.send(&ClientNetMessage::TeleportPos(position));
+            return;
+        }
         if let Ok(local_players) = LOCAL_PLAYERS.read() {
             if let Ok(pipe) = REGIONPIPE.read() {
                 for (region_id, entity_id) in local_players.iter() {
@@ -865,6 +1102,54 @@ impl Server {
         }
     }
 
+    /// Send a message to the region of a registered player.
+    fn send_to_player_region(&self, entity_id: u32, message: RegionMessage) {
+        if let Some(region_id) = self.player_region(entity_id)
+            && let Ok(pipe) = REGIONPIPE.read()
+            && let Some(sender) = pipe.get(&region_id)
+            && let Err(err) = sender.send(message)
+        {
+            println!("{:?}", err.to_string());
+        }
+    }
+
+    /// Send an action to a single registered player.
+    pub fn player_action(&mut self, entity_id: u32, action: EntityAction) {
+        self.send_to_player_region(entity_id, RegionMessage::UserAction(entity_id, action));
+    }
+
+    /// Send a user event to a single registered player.
+    pub fn player_event(&mut self, entity_id: u32, event: String, value: Value) {
+        self.send_to_player_region(entity_id, RegionMessage::UserEvent(entity_id, event, value));
+    }
+
+    /// Instantly move a single registered player to a sector, optionally in another region.
+    pub fn player_teleport(&mut self, entity_id: u32, sector_name: String, region_name: String) {
+        self.send_to_player_region(
+            entity_id,
+            RegionMessage::TeleportEntity(entity_id, sector_name, region_name),
+        );
+    }
+
+    /// Instantly move a single registered player to a position in their current region.
+    pub fn player_teleport_pos(&mut self, entity_id: u32, position: Vec2<f32>) {
+        self.send_to_player_region(
+            entity_id,
+            RegionMessage::TeleportEntityPos(entity_id, position),
+        );
+    }
+
+    /// Unregister a player and remove its entity from the world.
+    pub fn remove_player(&mut self, entity_id: u32) {
+        let Some(region_id) = self.player_region(entity_id) else {
+            return;
+        };
+        self.send_to_player_region(entity_id, RegionMessage::RemoveEntity(region_id, entity_id));
+        if let Ok(mut players) = LOCAL_PLAYERS.write() {
+            players.retain(|(_, id)| *id != entity_id);
+        }
+    }
+
     /// Pause all region instances.
     pub fn pause(&mut self) {
         if let Ok(pipes) = REGIONPIPE.read() {
@@ -897,6 +1182,11 @@ impl Server {
 
     /// Shuts down all region instances.
     pub fn clear(&mut self) {
+        if let Some(mut remote) = self.remote.take() {
+            remote.send(&ClientNetMessage::Leave);
+        }
+        self.net_outbox = None;
+        self.net_players.clear();
         if let Ok(mut pipes) = REGIONPIPE.write() {
             pipes.clear();
         }
@@ -909,11 +1199,13 @@ impl Server {
         self.says.clear();
         self.open_container_requests.clear();
         self.audio_commands.clear();
+        self.quest_events.clear();
         self.world_render = RuntimeRenderState::default();
         self.region_render.clear();
         self.runtime_maps.clear();
         self.runtime_map_position_guards.clear();
         self.multiple_choice.clear();
+        self.save_game_requests.clear();
         self.id_gen = 1;
         self.region_id_map.clear();
         self.region_name_id_map.clear();
@@ -0,0 +1,977 @@
+use crate::server::message::AudioCommand;
+use crate::server::{LOCAL_PLAYERS, Message, OpenContainerRequest, Say};
+use crate::{Command, Entity, EntityAction, Item, MultipleChoice, QuestEvent, Server, Value};
+use crossbeam_channel::{Receiver, Sender, TrySendError, bounded, unbounded};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::collections::VecDeque;
+use std::io::{BufRead, BufReader, ErrorKind, Read, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::time::Duration;
+use theframework::prelude::*;
+
+/// The version of the network protocol. Clients with a different version are rejected.
+pub const NET_PROTOCOL_VERSION: u32 = 1;
+
+/// The default port of the dedicated server.
+pub const DEFAULT_NET_PORT: u16 = 7878;
+
+/// The maximum length of a single message line sent by a client.
+pub const MAX_CLIENT_MESSAGE_LEN: u64 = 64 * 1024;
+
+/// The maximum length of a single message line sent by the server (snapshots).
+pub const MAX_SERVER_MESSAGE_LEN: u64 = 64 * 1024 * 1024;
+
+/// Outgoing messages queued per client before the client is considered stalled.
+const MAX_PENDING_MESSAGES: usize = 4096;
+
+/// How long a write to a client may block before the client is dropped.
+const NET_WRITE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Messages sent from a remote client to the server.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub enum ClientNetMessage {
+    /// Join the game with a new player entity.
+    Join { version: u32, name: String },
+    /// A player action (movement, clicks, intents, choices).
+    Action(EntityAction),
+    /// A player user event.
+    Event(String, Value),
+    /// Teleport the player to a sector, optionally in another region.
+    Teleport(String, String),
+    /// Teleport the player to a position in the current region.
+    TeleportPos(Vec2<f32>),
+    /// Leave the game and remove the player entity.
+    Leave,
+}
+
+/// Messages sent from the server to remote clients. Regions are identified by
+/// their map id, which is identical for all processes running the same game.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub enum ServerNetMessage {
+    /// The join was accepted, the player controls the given entity.
+    Welcome {
+        entity_id: u32,
+        region: String,
+    },
+    /// The join was rejected.
+    Rejected(String),
+    /// The full state of a region.
+    Snapshot {
+        map_id: Uuid,
+        entities: Vec<Entity>,
+        /// Inventories are not part of the entity serialization.
+        inventories: FxHashMap<u32, Vec<Option<Item>>>,
+        items: Vec<Item>,
+    },
+    /// Packed entity deltas of a region.
+    EntitiesUpdate(Uuid, Vec<Vec<u8>>),
+    /// Packed item deltas of a region.
+    ItemsUpdate(Uuid, Vec<Vec<u8>>),
+    /// An entity entered the region (after a region transfer).
+    AddEntity(Uuid, Box<Entity>, Vec<Option<Item>>),
+    RemoveEntity(Uuid, u32),
+    RemoveItem(Uuid, u32),
+    Time(Uuid, TheTime),
+    Message(Uuid, Message),
+    Say(Uuid, Say),
+    MultipleChoice(Uuid, MultipleChoice),
+    QuestEvent(Uuid, QuestEvent),
+    OpenContainer(Uuid, OpenContainerRequest),
+    Audio(Uuid, AudioCommand),
+    /// The player of this client moved into the given region.
+    RegionChanged(String),
+}
+
+/// Encode a message as a single line of JSON, including the line break.
+pub fn encode_net_message<T: Serialize>(message: &T) -> Result<String, String> {
+    let mut json = serde_json::to_string(message)
+        .map_err(|err| format!("Failed to encode network message: {}", err))?;
+    json.push('\n');
+    Ok(json)
+}
+
+/// Write a message as a single line of JSON.
+pub fn write_net_message<T: Serialize>(writer: &mut impl Write, message: &T) -> Result<(), String> {
+    let json = encode_net_message(message)?;
+    writer
+        .write_all(json.as_bytes())
+        .and_then(|_| writer.flush())
+        .map_err(|err| format!("Failed to send network message: {}", err))
+}
+
+/// Read the next JSON line message. Returns `None` when the connection was closed
+/// and an error when a line is longer than `max_len` bytes.
+pub fn read_net_message<T: DeserializeOwned>(
+    reader: &mut impl BufRead,
+    max_len: u64,
+) -> Result<Option<T>, String> {
+    let mut line = String::new();
+    loop {
+        line.clear();
+        let read = reader
+            .by_ref()
+            .take(max_len)
+            .read_line(&mut line)
+            .map_err(|err| format!("Failed to receive network message: {}", err))?;
+        if read == 0 {
+            return Ok(None);
+        }
+        if read as u64 >= max_len && !line.ends_with('\n') {
+            return Err(format!(
+                "Network message exceeds the maximum length of {} bytes.",
+                max_len
+            ));
+        }
+        if !line.trim().is_empty() {
+            break;
+        }
+    }
+    serde_json::from_str(line.trim())
+        .map(Some)
+        .map_err(|err| format!("Failed to decode network message: {}", err))
+}
+
+/// Forward all messages of a stream into a channel until the connection closes.
+/// A final `None` signals the disconnect, also after a malformed or oversized line.
+fn spawn_reader<T: DeserializeOwned + Send + 'static, K: Copy + Send + 'static>(
+    stream: TcpStream,
+    key: K,
+    max_len: u64,
+    sender: Sender<(K, Option<T>)>,
+) {
+    std::thread::spawn(move || {
+        let mut reader = BufReader::new(stream);
+        while let Ok(Some(message)) = read_net_message::<T>(&mut reader, max_len) {
+            if sender.send((key, Some(message))).is_err() {
+                return;
+            }
+        }
+        let _ = sender.send((key, None));
+    });
+}
+
+/// Write queued lines to a stream until the queue closes or a write fails, so
+/// that a slow client never blocks the simulation.
+fn spawn_writer(mut stream: TcpStream, outgoing: Receiver<String>) {
+    std::thread::spawn(move || {
+        while let Ok(line) = outgoing.recv() {
+            if stream
+                .write_all(line.as_bytes())
+                .and_then(|_| stream.flush())
+                .is_err()
+            {
+                let _ = stream.shutdown(std::net::Shutdown::Both);
+                return;
+            }
+        }
+    });
+}
+
+/// The client side of a network connection. Installed into a [`Server`] via
+/// [`Server::connect`], after which the server mirrors the remote world instead
+/// of running local region instances.
+pub struct RemoteConnection {
+    stream: TcpStream,
+    incoming: Receiver<((), Option<ServerNetMessage>)>,
+    /// The entity controlled by this client, known after the welcome.
+    pub entity_id: Option<u32>,
+    pub connected: bool,
+}
+
+impl RemoteConnection {
+    /// Connect to a dedicated server and request to join.
+    pub fn connect(addr: impl ToSocketAddrs, name: &str) -> Result<Self, String> {
+        let mut stream =
+            TcpStream::connect(addr).map_err(|err| format!("Failed to connect: {}", err))?;
+        let _ = stream.set_nodelay(true);
+        let reader = stream
+            .try_clone()
+            .map_err(|err| format!("Failed to connect: {}", err))?;
+
+        let (sender, incoming) = unbounded();
+        spawn_reader(reader, (), MAX_SERVER_MESSAGE_LEN, sender);
+
+        write_net_message(
+            &mut stream,
+            &ClientNetMessage::Join {
+                version: NET_PROTOCOL_VERSION,
+                name: name.to_string(),
+            },
+        )?;
+
+        Ok(Self {
+            stream,
+            incoming,
+            entity_id: None,
+            connected: true,
+        })
+    }
+
+    /// Send a message to the server.
+    pub fn send(&mut self, message: &ClientNetMessage) {
+        if self.connected && write_net_message(&mut self.stream, message).is_err() {
+            self.connected = false;
+        }
+    }
+
+    /// Drain all received messages. Marks the connection closed on disconnect.
+    pub fn receive(&mut self) -> Vec<ServerNetMessage> {
+        let mut messages = vec![];
+        while let Ok(((), message)) = self.incoming.try_recv() {
+            match message {
+                Some(message) => messages.push(message),
+                None => self.connected = false,
+            }
+        }
+        messages
+    }
+}
+
+struct NetClient {
+    stream: TcpStream,
+    /// Encoded lines waiting for the writer thread.
+    outgoing: Sender<String>,
+    name: String,
+    entity_id: Option<u32>,
+    region_id: Option<u32>,
+}
+
+/// Hosts a [`Server`] for remote players over TCP. Each joining client gets its
+/// own player entity, created from the given template in the start region.
+pub struct NetHost {
+    listener: TcpListener,
+    sender: Sender<(u32, Option<ClientNetMessage>)>,
+    incoming: Receiver<(u32, Option<ClientNetMessage>)>,
+    clients: FxHashMap<u32, NetClient>,
+    pending_joins: VecDeque<u32>,
+    next_client_id: u32,
+
+    start_region: Uuid,
+    player_template: Entity,
+}
+
+impl NetHost {
+    /// Listen on the given address. Enables the network outbox of the server.
+    pub fn bind(
+        addr: impl ToSocketAddrs,
+        server: &mut Server,
+        start_region: Uuid,
+        player_template: Entity,
+    ) -> Result<Self, String> {
+        let listener = TcpListener::bind(addr).map_err(|err| format!("Failed to bind: {}", err))?;
+        listener
+            .set_nonblocking(true)
+            .map_err(|err| format!("Failed to bind: {}", err))?;
+        server.net_outbox = Some(vec![]);
+
+        let (sender, incoming) = unbounded();
+        Ok(Self {
+            listener,
+            sender,
+            incoming,
+            clients: FxHashMap::default(),
+            pending_joins: VecDeque::new(),
+            next_client_id: 1,
+            start_region,
+            player_template,
+        })
+    }
+
+    /// The local address the host is listening on.
+    pub fn local_addr(&self) -> Option<std::net::SocketAddr> {
+        self.listener.local_addr().ok()
+    }
+
+    /// The number of connected players.
+    pub fn player_count(&self) -> usize {
+        self.clients
+            .values()
+            .filter(|client| client.entity_id.is_some())
+            .count()
+    }
+
+    /// Accept connections, process client messages and send the server state to
+    /// all players. Call after [`Server::update`]. Returns log lines about
+    /// joins and leaves.
+    pub fn update(&mut self, server: &mut Server) -> Vec<String> {
+        let mut log = vec![];
+        self.accept();
+
+        // Client messages
+        while let Ok((client_id, message)) = self.incoming.try_recv() {
+            match message {
+                Some(ClientNetMessage::Join { version, name }) => {
+                    if version != NET_PROTOCOL_VERSION {
+                        self.send(
+                            client_id,
+                            &ServerNetMessage::Rejected(format!(
+                                "Protocol version {} is not supported (expected {}).",
+                                version, NET_PROTOCOL_VERSION
+                            )),
+                        );
+                        continue;
+                    }
+                    let Some(client) = self.clients.get_mut(&client_id) else {
+                        continue;
+                    };
+                    if client.entity_id.is_some() || self.pending_joins.contains(&client_id) {
+                        continue;
+                    }
+                    client.name = name;
+                    self.pending_joins.push_back(client_id);
+                    let mut entity = self.player_template.clone();
+                    if !client.name.trim().is_empty() {
+                        entity.set_attribute("_start_name", Value::Str(client.name.clone()));
+                    }
+                    server.process_client_commands(vec![Command::CreateEntity(
+                        self.start_region,
+                        entity,
+                    )]);
+                }
+                Some(message) => {
+                    let Some(entity_id) = self
+                        .clients
+                        .get(&client_id)
+                        .and_then(|client| client.entity_id)
+                    else {
+                        continue;
+                    };
+                    match message {
+                        ClientNetMessage::Action(action) => server.player_action(entity_id, action),
+                        ClientNetMessage::Event(event, value) => {
+                            server.player_event(entity_id, event, value)
+                        }
+                        ClientNetMessage::Teleport(sector, region) => {
+                            server.player_teleport(entity_id, sector, region)
+                        }
+                        ClientNetMessage::TeleportPos(position) => {
+                            server.player_teleport_pos(entity_id, position)
+                        }
+                        ClientNetMessage::Leave => {
+                            if let Some(line) = self.disconnect(client_id, server) {
+                                log.push(line);
+                            }
+                        }
+                        ClientNetMessage::Join { .. } => {}
+                    }
+                }
+                None => {
+                    if let Some(line) = self.disconnect(client_id, server) {
+                        log.push(line);
+                    }
+                }
+            }
+        }
+
+        // Player entities created for pending joins
+        for (region_id, entity_id) in server.take_net_players() {
+            let Some(client_id) = self.pending_joins.pop_front() else {
+                continue;
+            };
+            let region = server.region_name(region_id).unwrap_or_default();
+            let Some(client) = self.clients.get_mut(&client_id) else {
+                server.remove_player(entity_id);
+                continue;
+            };
+            client.entity_id = Some(entity_id);
+            client.region_id = Some(region_id);
+            log.push(format!("{} joined as entity {}.", client.name, entity_id));
+
+            self.send(
+                client_id,
+                &ServerNetMessage::Welcome {
+                    entity_id,
+                    region: region.clone(),
+                },
+            );
+            for snapshot in server.net_snapshots() {
+                self.send(client_id, &snapshot);
+            }
+        }
+
+        // World deltas go to all players
+        for message in server.take_net_outbox() {
+            self.broadcast(&message);
+        }
+
+        // Messages, says, choices, containers and audio
+        let regions: Vec<Uuid> = server.region_id_map.keys().copied().collect();
+        for map_id in regions {
+            for message in server.get_messages(&map_id) {
+                if let Some(client_id) = self.client_for_entity(message.2) {
+                    self.send(client_id, &ServerNetMessage::Message(map_id, message));
+                }
+            }
+            let region_id = server.region_id_map.get(&map_id).copied();
+            for say in server.get_says(&map_id) {
+                if let Some(region_id) = region_id {
+                    self.broadcast_region(server, region_id, &ServerNetMessage::Say(map_id, say));
+                }
+            }
+            for choice in server.get_choices(&map_id) {
+                if let Some(client_id) = self.client_for_entity(choice.to) {
+                    self.send(client_id, &ServerNetMessage::MultipleChoice(map_id, choice));
+                }
+            }
+            for event in server.get_quest_events(&map_id) {
+                if let Some(client_id) = self.client_for_entity(event.entity_id) {
+                    self.send(client_id, &ServerNetMessage::QuestEvent(map_id, event));
+                }
+            }
+            for request in server.get_open_container_requests(&map_id) {
+                if let Some(client_id) = self.client_for_entity(request.2) {
+                    self.send(client_id, &ServerNetMessage::OpenContainer(map_id, request));
+                }
+            }
+            for command in server.get_audio_commands(&map_id) {
+                if let Some(region_id) = region_id {
+                    self.broadcast_region(
+                        server,
+                        region_id,
+                        &ServerNetMessage::Audio(map_id, command),
+                    );
+                }
+            }
+        }
+
+        // Region changes
+        let mut changes = vec![];
+        for (client_id, client) in self.clients.iter_mut() {
+            if let Some(entity_id) = client.entity_id
+                && let Some(region_id) = server.player_region(entity_id)
+                && client.region_id != Some(region_id)
+            {
+                client.region_id = Some(region_id);
+                if let Some(region) = server.region_name(region_id) {
+                    changes.push((*client_id, region));
+                }
+            }
+        }
+        for (client_id, region) in changes {
+            self.send(client_id, &ServerNetMessage::RegionChanged(region));
+        }
+
+        log
+    }
+
+    fn accept(&mut self) {
+        loop {
+            match self.listener.accept() {
+                Ok((stream, _)) => {
+                    let _ = stream.set_nonblocking(false);
+                    let _ = stream.set_nodelay(true);
+                    let _ = stream.set_write_timeout(Some(NET_WRITE_TIMEOUT));
+                    let (Ok(reader), Ok(writer)) = (stream.try_clone(), stream.try_clone()) else {
+                        continue;
+                    };
+                    let client_id = self.next_client_id;
+                    self.next_client_id += 1;
+                    spawn_reader(
+                        reader,
+                        client_id,
+                        MAX_CLIENT_MESSAGE_LEN,
+                        self.sender.clone(),
+                    );
+                    let (outgoing, queue) = bounded(MAX_PENDING_MESSAGES);
+                    spawn_writer(writer, queue);
+                    self.clients.insert(
+                        client_id,
+                        NetClient {
+                            stream,
+                            outgoing,
+                            name: String::new(),
+                            entity_id: None,
+                            region_id: None,
+                        },
+                    );
+                }
+                Err(err) if err.kind() == ErrorKind::WouldBlock => break,
+                Err(_) => break,
+            }
+        }
+    }
+
+    /// Drop a client and remove its player entity from the world.
+    fn disconnect(&mut self, client_id: u32, server: &mut Server) -> Option<String> {
+        self.pending_joins.retain(|id| *id != client_id);
+        let client = self.clients.remove(&client_id)?;
+        let _ = client.stream.shutdown(std::net::Shutdown::Both);
+        let entity_id = client.entity_id?;
+        server.remove_player(entity_id);
+        Some(format!("{} left (entity {}).", client.name, entity_id))
+    }
+
+    fn client_for_entity(&self, entity_id: u32) -> Option<u32> {
+        self.clients
+            .iter()
+            .find(|(_, client)| client.entity_id == Some(entity_id))
+            .map(|(client_id, _)| *client_id)
+    }
+
+    fn send(&mut self, client_id: u32, message: &ServerNetMessage) {
+        if let Ok(line) = encode_net_message(message) {
+            self.send_line(client_id, line);
+        }
+    }
+
+    /// Queue an encoded line for a client. A client whose queue is full is
+    /// stalled and gets dropped; its reader then reports the disconnect.
+    fn send_line(&mut self, client_id: u32, line: String) {
+        if let Some(client) = self.clients.get_mut(&client_id)
+            && let Err(TrySendError::Full(_) | TrySendError::Disconnected(_)) =
+                client.outgoing.try_send(line)
+        {
+            let _ = client.stream.shutdown(std::net::Shutdown::Both);
+        }
+    }
+
+    fn broadcast(&mut self, message: &ServerNetMessage) {
+        self.broadcast_filtered(message, |_| true);
+    }
+
+    /// Send a message to all players in the given region.
+    fn broadcast_region(&mut self, server: &Server, region_id: u32, message: &ServerNetMessage) {
+        self.broadcast_filtered(message, |entity_id| {
+            server.player_region(entity_id) == Some(region_id)
+        });
+    }
+
+    fn broadcast_filtered(&mut self, message: &ServerNetMessage, filter: impl Fn(u32) -> bool) {
+        let Ok(line) = encode_net_message(message) else {
+            return;
+        };
+        let ids: Vec<u32> = self
+            .clients
+            .iter()
+            .filter(|(_, client)| client.entity_id.is_some_and(&filter))
+            .map(|(client_id, _)| *client_id)
+            .collect();
+        for client_id in ids {
+            self.send_line(client_id, line.clone());
+        }
+    }
+}
+
+impl Server {
+    /// Connect to a dedicated server. From now on the server mirrors the remote
+    /// world and forwards local player input over the network.
+    pub fn connect(&mut self, addr: impl ToSocketAddrs, name: &str) -> Result<(), String> {
+        self.clear();
+        self.remote = Some(RemoteConnection::connect(addr, name)?);
+        self.state = crate::ServerState::Running;
+        Ok(())
+    }
+
+    /// Returns true if this server mirrors a remote dedicated server.
+    pub fn is_remote(&self) -> bool {
+        self.remote.is_some()
+    }
+
+    /// The player entity of a remote connection, once the server accepted the join.
+    pub fn remote_player_id(&self) -> Option<u32> {
+        self.remote.as_ref().and_then(|remote| remote.entity_id)
+    }
+
+    /// Take the queued world deltas for remote players.
+    pub fn take_net_outbox(&mut self) -> Vec<ServerNetMessage> {
+        self.net_outbox
+            .as_mut()
+            .map(std::mem::take)
+            .unwrap_or_default()
+    }
+
+    /// Take the players registered since the last call (region id, entity id).
+    pub fn take_net_players(&mut self) -> Vec<(u32, u32)> {
+        std::mem::take(&mut self.net_players)
+    }
+
+    /// Full snapshots of all regions, sent to newly joined players.
+    pub fn net_snapshots(&self) -> Vec<ServerNetMessage> {
+        let mut snapshots = vec![];
+        for (map_id, region_id) in &self.region_id_map {
+            let entities = self.entities.get(region_id).cloned().unwrap_or_default();
+            let inventories = entities
+                .iter()
+                .filter(|entity| !entity.inventory.is_empty())
+                .map(|entity| (entity.id, entity.inventory.clone()))
+                .collect();
+            snapshots.push(ServerNetMessage::Snapshot {
+                map_id: *map_id,
+                entities,
+                inventories,
+                items: self.items.get(region_id).cloned().unwrap_or_default(),
+            });
+            if let Some(time) = self.times.get(region_id) {
+                snapshots.push(ServerNetMessage::Time(*map_id, *time));
+            }
+        }
+        snapshots
+    }
+
+    /// The region a registered player is currently in.
+    pub fn player_region(&self, entity_id: u32) -> Option<u32> {
+        LOCAL_PLAYERS.read().ok().and_then(|players| {
+            players
+                .iter()
+                .find(|(_, id)| *id == entity_id)
+                .map(|(region_id, _)| *region_id)
+        })
+    }
+
+    /// The name of the region with the given id.
+    pub fn region_name(&self, region_id: u32) -> Option<String> {
+        self.region_name_id_map
+            .iter()
+            .find(|(_, id)| **id == region_id)
+            .map(|(name, _)| name.clone())
+    }
+
+    /// The map id of the region with the given id.
+    pub(crate) fn region_map_id(&self, region_id: u32) -> Option<Uuid> {
+        self.region_id_map
+            .iter()
+            .find(|(_, id)| **id == region_id)
+            .map(|(map_id, _)| *map_id)
+    }
+
+    /// Queue a world delta for remote players when hosting.
+    pub(crate) fn push_net_message(
+        &mut self,
+        region_id: u32,
+        message: impl FnOnce(Uuid) -> ServerNetMessage,
+    ) {
+        if self.net_outbox.is_some()
+            && let Some(map_id) = self.region_map_id(region_id)
+            && let Some(outbox) = self.net_outbox.as_mut()
+        {
+            outbox.push(message(map_id));
+        }
+    }
+
+    /// The local region id of a remote region, registered on first use.
+    fn remote_region_id(&mut self, map_id: Uuid) -> u32 {
+        if let Some(region_id) = self.region_id_map.get(&map_id) {
+            return *region_id;
+        }
+        let region_id = self.get_next_id();
+        self.region_id_map.insert(map_id, region_id);
+        region_id
+    }
+
+    /// Apply the messages of the remote server to the local mirror. Returns the
+    /// name of the new region should the players region change.
+    pub(crate) fn update_remote(&mut self, assets: &mut crate::Assets) -> Option<String> {
+        let mut rc = None;
+        let remote = self.remote.as_mut()?;
+        let was_connected = remote.connected;
+        let messages = remote.receive();
+        let disconnected = was_connected && !remote.connected;
+
+        for message in messages {
+            match message {
+                ServerNetMessage::Welcome { entity_id, region } => {
+                    if let Some(remote) = self.remote.as_mut() {
+                        remote.entity_id = Some(entity_id);
+                    }
+                    rc = Some(region);
+                }
+                ServerNetMessage::Rejected(reason) => {
+                    self.push_log(format!("Server rejected the connection: {}", reason));
+                }
+                ServerNetMessage::Snapshot {
+                    map_id,
+                    mut entities,
+                    mut inventories,
+                    items,
+                } => {
+                    let region_id = self.remote_region_id(map_id);
+                    for entity in entities.iter_mut() {
+                        if let Some(inventory) = inventories.remove(&entity.id) {
+                            entity.inventory = inventory;
+                        }
+                        entity.mark_all_dirty();
+                    }
+                    self.entities.insert(region_id, entities);
+                    self.items.insert(region_id, items);
+                }
+                ServerNetMessage::EntitiesUpdate(map_id, serialized_updates) => {
+                    let region_id = self.remote_region_id(map_id);
+                    let updates = serialized_updates
+                        .into_iter()
+                        .map(|data| crate::EntityUpdate::unpack(&data))
+                        .collect();
+                    let entities = self.entities.entry(region_id).or_default();
+                    Self::process_entity_updates(entities, updates, assets, None, false);
+                }
+                ServerNetMessage::ItemsUpdate(map_id, serialized_updates) => {
+                    let region_id = self.remote_region_id(map_id);
+                    let updates = serialized_updates
+                        .into_iter()
+                        .map(|data| crate::ItemUpdate::unpack(&data))
+                        .collect();
+                    let items = self.items.entry(region_id).or_default();
+                    Self::process_item_updates(items, updates);
+                }
+                ServerNetMessage::AddEntity(map_id, mut entity, inventory) => {
+                    let region_id = self.remote_region_id(map_id);
+                    entity.inventory = inventory;
+                    entity.mark_all_dirty();
+                    let entities = self.entities.entry(region_id).or_default();
+                    entities.retain(|existing| existing.id != entity.id);
+                    entities.push(*entity);
+                }
+                ServerNetMessage::RemoveEntity(map_id, entity_id) => {
+                    let region_id = self.remote_region_id(map_id);
+                    if let Some(entities) = self.entities.get_mut(&region_id) {
+                        entities.retain(|entity| entity.id != entity_id);
+                    }
+                }
+                ServerNetMessage::RemoveItem(map_id, item_id) => {
+                    let region_id = self.remote_region_id(map_id);
+                    if let Some(items) = self.items.get_mut(&region_id) {
+                        items.retain(|item| item.id != item_id);
+                    }
+                }
+                ServerNetMessage::Time(map_id, time) => {
+                    let region_id = self.remote_region_id(map_id);
+                    self.times.insert(region_id, time);
+                }
+                ServerNetMessage::Message(map_id, message) => {
+                    let region_id = self.remote_region_id(map_id);
+                    self.messages.entry(region_id).or_default().push(message);
+                }
+                ServerNetMessage::Say(map_id, say) => {
+                    let region_id = self.remote_region_id(map_id);
+                    self.says.entry(region_id).or_default().push(say);
+                }
+                ServerNetMessage::MultipleChoice(map_id, mut choice) => {
+                    let region_id = self.remote_region_id(map_id);
+                    choice.region = region_id;
+                    self.multiple_choice
+                        .entry(region_id)
+                        .or_default()
+                        .push(choice);
+                }
+                ServerNetMessage::QuestEvent(map_id, event) => {
+                    let region_id = self.remote_region_id(map_id);
+                    self.quest_events.entry(region_id).or_default().push(event);
+                }
+                ServerNetMessage::OpenContainer(map_id, request) => {
+                    let region_id = self.remote_region_id(map_id);
+                    self.open_container_requests
+                        .entry(region_id)
+                        .or_default()
+                        .push(request);
+                }
+                ServerNetMessage::Audio(map_id, command) => {
+                    let region_id = self.remote_region_id(map_id);
+                    self.audio_commands
+                        .entry(region_id)
+                        .or_default()
+                        .push(command);
+                }
+                ServerNetMessage::RegionChanged(region) => {
+                    rc = Some(region);
+                }
+            }
+        }
+
+        if disconnected {
+            self.push_log("Disconnected from the server.".into());
+        }
+
+        // Only the own entity is the player on this client, other players are
+        // shown like any other character.
+        let player_id = self.remote_player_id();
+        for entities in self.entities.values_mut() {
+            for entity in entities.iter_mut() {
+                if entity.is_player() && Some(entity.id) != player_id {
+                    entity.attributes.set("player", Value::Bool(false));
+                }
+            }
+        }
+
+        rc
+    }
+
+    fn push_log(&mut self, message: String) {
+        if self.print_log_messages {
+            println!("{}", message);
+        }
+        if self.log.is_empty() {
+            self.log = message;
+        } else {
+            self.log += &format!("{}{}", "\n", message);
+        }
+        self.log_changed = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn net_messages_round_trip_as_json_lines() {
+        let entity = Entity {
+            id: 3,
+            inventory: vec![None],
+            ..Default::default()
+        };
+        let mut buffer = vec![];
+        write_net_message(
+            &mut buffer,
+            &ServerNetMessage::Snapshot {
+                map_id: Uuid::nil(),
+                entities: vec![entity],
+                inventories: FxHashMap::from_iter([(3, vec![None])]),
+                items: vec![],
+            },
+        )
+        .unwrap();
+        write_net_message(
+            &mut buffer,
+            &ClientNetMessage::Action(EntityAction::Forward),
+        )
+        .unwrap();
+
+        let mut reader = Cursor::new(buffer);
+        let snapshot =
+            read_net_message::<ServerNetMessage>(&mut reader, MAX_SERVER_MESSAGE_LEN).unwrap();
+        assert!(matches!(
+            snapshot,
+            Some(ServerNetMessage::Snapshot { ref entities, ref inventories, .. })
+                if entities[0].id == 3 && inventories[&3].len() == 1
+        ));
+        let action =
+            read_net_message::<ClientNetMessage>(&mut reader, MAX_CLIENT_MESSAGE_LEN).unwrap();
+        assert!(matches!(
+            action,
+            Some(ClientNetMessage::Action(EntityAction::Forward))
+        ));
+        assert!(
+            read_net_message::<ClientNetMessage>(&mut reader, MAX_CLIENT_MESSAGE_LEN)
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn oversized_net_messages_are_rejected() {
+        let mut buffer = vec![];
+        write_net_message(
+            &mut buffer,
+            &ClientNetMessage::Join {
+                version: NET_PROTOCOL_VERSION,
+                name: "x".repeat(128),
+            },
+        )
+        .unwrap();
+
+        let mut reader = Cursor::new(buffer.clone());
+        assert!(read_net_message::<ClientNetMessage>(&mut reader, 64).is_err());
+        let mut reader = Cursor::new(buffer);
+        assert!(matches!(
+            read_net_message::<ClientNetMessage>(&mut reader, MAX_CLIENT_MESSAGE_LEN),
+            Ok(Some(ClientNetMessage::Join { .. }))
+        ));
+    }
+
+    #[test]
+    fn remote_mirror_only_keeps_own_player() {
+        let mut server = Server::new();
+        let mut assets = crate::Assets::default();
+        let (sender, incoming) = unbounded();
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let stream = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        server.remote = Some(RemoteConnection {
+            stream,
+            incoming,
+            entity_id: None,
+            connected: true,
+        });
+
+        let map_id = Uuid::new_v4();
+        let mut own = Entity {
+            id: 1,
+            ..Default::default()
+        };
+        own.set_attribute("player", Value::Bool(true));
+        let mut other = own.clone();
+        other.id = 2;
+        for message in [
+            ServerNetMessage::Welcome {
+                entity_id: 1,
+                region: "Town".into(),
+            },
+            ServerNetMessage::Snapshot {
+                map_id,
+                entities: vec![own, other],
+                inventories: FxHashMap::default(),
+                items: vec![],
+            },
+            ServerNetMessage::Say(map_id, (Some(2), None, "Hello".into(), String::new())),
+        ] {
+            sender.send(((), Some(message))).unwrap();
+        }
+
+        assert_eq!(server.update(&mut assets), Some("Town".into()));
+        assert_eq!(server.remote_player_id(), Some(1));
+        let region_id = server.region_id_map[&map_id];
+        let players: Vec<u32> = server.entities[&region_id]
+            .iter()
+            .filter(|entity| entity.is_player())
+            .map(|entity| entity.id)
+            .collect();
+        assert_eq!(players, vec![1]);
+        assert_eq!(server.get_says(&map_id).len(), 1);
+    }
+
+    #[test]
+    fn host_routes_containers_to_their_player_and_audio_to_the_region() {
+        let mut server = Server::new();
+        let mut host =
+            NetHost::bind("127.0.0.1:0", &mut server, Uuid::nil(), Entity::default()).unwrap();
+        let mut client = Server::new();
+        client.remote =
+            Some(RemoteConnection::connect(host.local_addr().unwrap(), "Tester").unwrap());
+
+        let deadline = std::time::Instant::now() + Duration::from_secs(5);
+        while host.clients.is_empty() && std::time::Instant::now() < deadline {
+            host.update(&mut server);
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        let client_id = *host.clients.keys().next().unwrap();
+
+        let (map_id, region_id, entity_id) = (Uuid::new_v4(), 90_001, 90_002);
+        host.clients.get_mut(&client_id).unwrap().entity_id = Some(entity_id);
+        server.region_id_map.insert(map_id, region_id);
+        LOCAL_PLAYERS.write().unwrap().push((region_id, entity_id));
+        server.open_container_requests.insert(
+            region_id,
+            vec![(5, Some(entity_id), entity_id), (6, None, entity_id + 1)],
+        );
+        server
+            .audio_commands
+            .insert(region_id, vec![AudioCommand::ClearAll]);
+        host.update(&mut server);
+        LOCAL_PLAYERS
+            .write()
+            .unwrap()
+            .retain(|(_, id)| *id != entity_id);
+
+        let mut assets = crate::Assets::default();
+        let mut requests = vec![];
+        let mut commands = vec![];
+        while commands.is_empty() && std::time::Instant::now() < deadline {
+            client.update(&mut assets);
+            requests.extend(client.get_open_container_requests(&map_id));
+            commands.extend(client.get_audio_commands(&map_id));
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(requests, vec![(5, Some(entity_id), entity_id)]);
+        assert_eq!(commands, vec![AudioCommand::ClearAll]);
+    }
+}
@@ -0,0 +1,348 @@
+use crate::server::regionctx::RegionCtx;
+use crate::vm::VMValue;
+use crate::{Entity, Value};
+use theframework::prelude::*;
+use vek::Vec2;
+
+/// How much a character currently perceives of another one.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Awareness {
+    /// Within hearing range but not in sight.
+    Heard,
+    Seen,
+}
+
+/// Game wide detection tuning from the `[perception]` config section.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PerceptionConfig {
+    /// Light level everywhere without a light source, `0.0` (dark) to `1.0`.
+    /// Region scripts can override it at runtime with `region.ambient_light = ..`.
+    pub ambient_light: f32,
+    /// Fraction of its sight range a character keeps in complete darkness.
+    pub dark_sight: f32,
+    /// Sight range multiplier against sneaking targets.
+    pub sneak_sight: f32,
+    /// Hearing radius multiplier against sneaking targets.
+    pub sneak_hearing: f32,
+}
+
+impl Default for PerceptionConfig {
+    fn default() -> Self {
+        Self {
+            ambient_light: 1.0,
+            dark_sight: 0.25,
+            sneak_sight: 0.5,
+            sneak_hearing: 0.25,
+        }
+    }
+}
+
+impl PerceptionConfig {
+    pub fn from_ctx(ctx: &RegionCtx) -> Self {
+        let mut config = Self::default();
+        if let Some(table) = ctx.config.get("perception").and_then(toml::Value::as_table) {
+            let float = |key: &str, default: f32| {
+                table
+                    .get(key)
+                    .and_then(|value| {
+                        value
+                            .as_float()
+                            .or_else(|| value.as_integer().map(|v| v as f64))
+                    })
+                    .map(|value| (value as f32).clamp(0.0, 1.0))
+                    .unwrap_or(default)
+            };
+            config.ambient_light = float("ambient_light", config.ambient_light);
+            config.dark_sight = float("dark_sight", config.dark_sight);
+            config.sneak_sight = float("sneak_sight", config.sneak_sight);
+            config.sneak_hearing = float("sneak_hearing", config.sneak_hearing);
+        }
+        if let Some(ambient) = ctx.region_state.get_float("ambient_light") {
+            config.ambient_light = ambient.clamp(0.0, 1.0);
+        }
+        config
+    }
+}
+
+/// The perception attributes of a character. Characters without a
+/// `sight_range` or `hearing_radius` do not perceive anything.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Senses {
+    sight_range: f32,
+    /// Half the field of view cone, in radians.
+    half_fov: f32,
+    hearing_radius: f32,
+}
+
+impl Senses {
+    fn of(entity: &Entity) -> Option<Self> {
+        let sight_range = entity.attributes.get_float_default("sight_range", 0.0);
+        let hearing_radius = entity.attributes.get_float_default("hearing_radius", 0.0);
+        if sight_range <= 0.0 && hearing_radius <= 0.0 {
+            return None;
+        }
+        let fov = entity.attributes.get_float_default("sight_fov", 120.0);
+        Some(Self {
+            sight_range: sight_range.max(0.0),
+            half_fov: fov.clamp(0.0, 360.0).to_radians() / 2.0,
+            hearing_radius: hearing_radius.max(0.0),
+        })
+    }
+}
+
+/// Whether the entity is sneaking, either through the `sneaking` attribute or
+/// a `"sneaking"` mode set by a ruleset action.
+pub fn is_sneaking(entity: &Entity) -> bool {
+    entity.attributes.get_bool_default("sneaking", false) || entity.get_mode() == "sneaking"
+}
+
+/// The light level at `position`: the ambient light or the brightest light
+/// source reaching it, whichever is higher.
+pub fn light_level_at(ctx: &RegionCtx, config: &PerceptionConfig, position: Vec2<f32>) -> f32 {
+    let mut level = config.ambient_light;
+    let mut add_light = |origin: Vec2<f32>, start: f32, end: f32| {
+        let distance = origin.distance(position);
+        if distance > end || !ctx.mapmini.is_visible(origin, position) {
+            return;
+        }
+        let light = if distance <= start {
+            1.0
+        } else {
+            1.0 - (distance - start) / (end - start).max(f32::EPSILON)
+        };
+        level = level.max(light);
+    };
+
+    for light in ctx.map.lights.iter().filter(|light| light.active) {
+        add_light(
+            light.position_2d(),
+            light.get_start_distance(),
+            light.get_end_distance(),
+        );
+    }
+    for item in &ctx.map.items {
+        if let Some(Value::Light(light)) = item.attributes.get("light")
+            && light.active
+        {
+            add_light(
+                item.get_pos_xz(),
+                light.get_start_distance(),
+                light.get_end_distance(),
+            );
+        }
+    }
+    for entity in &ctx.map.entities {
+        let carried = std::iter::once(entity.attributes.get("light")).chain(
+            entity
+                .iter_inventory()
+                .map(|(_, item)| item.attributes.get("light")),
+        );
+        for light in carried {
+            if let Some(Value::Light(light)) = light
+                && light.active
+            {
+                add_light(
+                    entity.get_pos_xz(),
+                    light.get_start_distance(),
+                    light.get_end_distance(),
+                );
+            }
+        }
+    }
+    level.clamp(0.0, 1.0)
+}
+
+/// What `observer` perceives of `target`, standing in `light`, this tick.
+fn perceive(
+    ctx: &RegionCtx,
+    config: &PerceptionConfig,
+    senses: &Senses,
+    observer: &Entity,
+    target: &Entity,
+    light: f32,
+) -> Option<Awareness> {
+    if target.get_mode() == "dead" {
+        return None;
+    }
+    let from = observer.get_pos_xz();
+    let to = target.get_pos_xz();
+    let distance = from.distance(to);
+    let sneaking = is_sneaking(target);
+
+    let mut sight_range = senses.sight_range;
+    if sneaking {
+        sight_range *= config.sneak_sight;
+    }
+    if target.attributes.get_bool_default("visible", true)
+        && distance <= sight_range
+        && in_view_cone(observer, to - from, senses.half_fov)
+        && ctx
+            .mapmini
+            .is_tile_visible(from.floor().as_::<i32>(), to.floor().as_::<i32>())
+        && ctx.mapmini.is_visible(from, to)
+    {
+        let lit_range = sight_range * (config.dark_sight + (1.0 - config.dark_sight) * light);
+        if distance <= lit_range {
+            return Some(Awareness::Seen);
+        }
+    }
+
+    let mut hearing_radius = senses.hearing_radius;
+    if sneaking {
+        hearing_radius *= config.sneak_hearing;
+    }
+    (distance <= hearing_radius).then_some(Awareness::Heard)
+}
+
+fn in_view_cone(observer: &Entity, direction: Vec2<f32>, half_fov: f32) -> bool {
+    let radius = observer.attributes.get_float_default("radius", 0.5);
+    if direction.magnitude() <= radius || half_fov >= std::f32::consts::PI {
+        return true;
+    }
+    let facing = observer.orientation;
+    if facing.magnitude_squared() <= f32::EPSILON {
+        return true;
+    }
+    let cos = facing.normalized().dot(direction.normalized());
+    cos >= half_fov.cos()
+}
+
+/// Update what every perceiving character sees and hears of the players and
+/// queue `seen`, `heard` and `lost_sight` events for the changes.
+pub(crate) fn update_entity_perception(ctx: &mut RegionCtx) {
+    let config = PerceptionConfig::from_ctx(ctx);
+    let mut events = Vec::new();
+    let mut awareness = FxHashMap::default();
+
+    // The light on each living player, shared by all observers.
+    let players = ctx
+        .map
+        .entities
+        .iter()
+        .filter(|target| target.is_player())
+        .map(|target| {
+            let light = if target.get_mode() == "dead" {
+                0.0
+            } else {
+                light_level_at(ctx, &config, target.get_pos_xz())
+            };
+            (target, light)
+        })
+        .collect::<Vec<_>>();
+
+    for observer in &ctx.map.entities {
+        if observer.is_player() || observer.get_mode() == "dead" {
+            continue;
+        }
+        let Some(senses) = Senses::of(observer) else {
+            continue;
+        };
+        let previous = ctx.entity_awareness.get(&observer.id);
+        let mut current = FxHashMap::default();
+        for &(target, light) in &players {
+            let before = previous
+                .and_then(|previous| previous.get(&target.id))
+                .copied();
+            let now = perceive(ctx, &config, &senses, observer, target, light);
+            let event = match (before, now) {
+                (Some(Awareness::Seen), Some(Awareness::Seen)) => None,
+                (_, Some(Awareness::Seen)) => Some("seen"),
+                (Some(Awareness::Seen), _) => Some("lost_sight"),
+                (None, Some(Awareness::Heard)) => Some("heard"),
+                _ => None,
+            };
+            if let Some(event) = event {
+                events.push((observer.id, event.to_string(), VMValue::from(target.id)));
+            }
+            if let Some(now) = now {
+                current.insert(target.id, now);
+            }
+        }
+        if !current.is_empty() {
+            awareness.insert(observer.id, current);
+        }
+    }
+
+    ctx.entity_awareness = awareness;
+    ctx.to_execute_entity.extend(events);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Light, LightType};
+    use vek::Vec3;
+
+    fn add_entity(ctx: &mut RegionCtx, id: u32, x: f32, player: bool) {
+        let mut entity = Entity::new();
+        entity.id = id;
+        entity.position = Vec3::new(x, 0.0, 0.5);
+        entity.set_attribute("player", Value::Bool(player));
+        ctx.map.entities.push(entity);
+    }
+
+    fn guard_ctx() -> RegionCtx {
+        let mut ctx = RegionCtx::default();
+        add_entity(&mut ctx, 1, 0.5, false);
+        let guard = &mut ctx.map.entities[0];
+        guard.set_attribute("sight_range", Value::Float(8.0));
+        guard.set_attribute("hearing_radius", Value::Float(4.0));
+        guard.set_orientation(Vec2::new(1.0, 0.0));
+        add_entity(&mut ctx, 2, 6.5, true);
+        ctx
+    }
+
+    fn move_player(ctx: &mut RegionCtx, x: f32) {
+        ctx.map.entities[1].position.x = x;
+    }
+
+    fn drain_events(ctx: &mut RegionCtx) -> Vec<(u32, String, u32)> {
+        ctx.to_execute_entity
+            .drain(..)
+            .map(|(id, event, value)| (id, event, value.x as u32))
+            .collect()
+    }
+
+    #[test]
+    fn seen_and_lost_sight_fire_on_changes_only() {
+        let mut ctx = guard_ctx();
+        update_entity_perception(&mut ctx);
+        assert_eq!(drain_events(&mut ctx), vec![(1, "seen".into(), 2)]);
+        update_entity_perception(&mut ctx);
+        assert!(drain_events(&mut ctx).is_empty());
+
+        ctx.mapmini.blocked_tiles.insert(Vec2::new(3, 0));
+        update_entity_perception(&mut ctx);
+        assert_eq!(drain_events(&mut ctx), vec![(1, "lost_sight".into(), 2)]);
+
+        move_player(&mut ctx, 3.5 - 6.0);
+        update_entity_perception(&mut ctx);
+        assert_eq!(drain_events(&mut ctx), vec![(1, "heard".into(), 2)]);
+        assert_eq!(ctx.entity_awareness[&1][&2], Awareness::Heard);
+    }
+
+    #[test]
+    fn sneaking_and_darkness_shrink_detection() {
+        let mut ctx = guard_ctx();
+        ctx.map.entities[1].set_attribute("sneaking", Value::Bool(true));
+        update_entity_perception(&mut ctx);
+        assert!(drain_events(&mut ctx).is_empty());
+
+        move_player(&mut ctx, 3.5);
+        update_entity_perception(&mut ctx);
+        assert_eq!(drain_events(&mut ctx), vec![(1, "seen".into(), 2)]);
+
+        ctx.region_state.set("ambient_light", Value::Float(0.0));
+        update_entity_perception(&mut ctx);
+        assert_eq!(drain_events(&mut ctx), vec![(1, "lost_sight".into(), 2)]);
+
+        ctx.map.lights.push(
+            Light::new(LightType::Point)
+                .with_position(Vec3::new(3.5, 1.0, 0.5))
+                .with_start_distance(1.0)
+                .with_end_distance(3.0),
+        );
+        update_entity_perception(&mut ctx);
+        assert_eq!(drain_events(&mut ctx), vec![(1, "seen".into(), 2)]);
+    }
+}
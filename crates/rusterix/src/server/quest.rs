@@ -0,0 +1,196 @@
+use indexmap::IndexMap;
+use theframework::prelude::*;
+
+/// The state of a quest in an entity's journal.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QuestStatus {
+    Active,
+    Completed,
+    Failed,
+}
+
+impl QuestStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            QuestStatus::Active => "active",
+            QuestStatus::Completed => "completed",
+            QuestStatus::Failed => "failed",
+        }
+    }
+}
+
+/// Progress of a single objective of the current stage.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct QuestObjectiveProgress {
+    /// Journal text of the objective, e.g. "Collect 3 wild_herb".
+    pub label: String,
+    pub current: u32,
+    pub required: u32,
+}
+
+impl QuestObjectiveProgress {
+    pub fn is_done(&self) -> bool {
+        self.current >= self.required
+    }
+}
+
+/// A quest in an entity's journal. The ruleset owns the quest definition, the
+/// progress only keeps what the journal and the clients need to display it.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct QuestProgress {
+    pub id: String,
+    pub name: String,
+    pub status: QuestStatus,
+    /// Index of the current stage in the ruleset definition.
+    pub stage: usize,
+    pub stage_id: String,
+    pub stage_description: Option<String>,
+    pub objectives: Vec<QuestObjectiveProgress>,
+}
+
+impl QuestProgress {
+    /// True if the current stage has objectives and all of them are met.
+    pub fn stage_done(&self) -> bool {
+        !self.objectives.is_empty() && self.objectives.iter().all(|o| o.is_done())
+    }
+}
+
+/// The quests an entity has started, in the order they were started.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct QuestLog {
+    pub quests: IndexMap<String, QuestProgress>,
+}
+
+impl QuestLog {
+    pub fn is_empty(&self) -> bool {
+        self.quests.is_empty()
+    }
+
+    pub fn get(&self, quest_id: &str) -> Option<&QuestProgress> {
+        self.quests.get(quest_id)
+    }
+
+    pub fn get_mut(&mut self, quest_id: &str) -> Option<&mut QuestProgress> {
+        self.quests.get_mut(quest_id)
+    }
+
+    /// Returns true if any quest is still active.
+    pub fn has_active(&self) -> bool {
+        self.quests
+            .values()
+            .any(|quest| quest.status == QuestStatus::Active)
+    }
+
+    /// Render the journal as text lines: active quests with their current
+    /// stage and objectives first, then completed and failed quests.
+    pub fn journal_lines(&self) -> Vec<String> {
+        let mut lines = vec![];
+        for quest in self.quests.values() {
+            if quest.status != QuestStatus::Active {
+                continue;
+            }
+            match &quest.stage_description {
+                Some(description) => lines.push(format!("{}: {}", quest.name, description)),
+                None => lines.push(quest.name.clone()),
+            }
+            for objective in &quest.objectives {
+                let mark = if objective.is_done() { "x" } else { " " };
+                if objective.required > 1 {
+                    lines.push(format!(
+                        "  [{}] {} ({}/{})",
+                        mark,
+                        objective.label,
+                        objective.current.min(objective.required),
+                        objective.required
+                    ));
+                } else {
+                    lines.push(format!("  [{}] {}", mark, objective.label));
+                }
+            }
+        }
+        for quest in self.quests.values() {
+            if quest.status != QuestStatus::Active {
+                lines.push(format!("{} ({})", quest.name, quest.status.as_str()));
+            }
+        }
+        lines
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QuestEventKind {
+    Started,
+    /// The quest moved to a new stage.
+    Advanced,
+    /// An objective of the current stage made progress.
+    Progress,
+    Completed,
+    Failed,
+}
+
+/// A quest state change, sent to the client which owns the entity.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct QuestEvent {
+    pub entity_id: u32,
+    pub quest_id: String,
+    pub kind: QuestEventKind,
+    /// The localized message shown to the player for this event.
+    pub text: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn objective(label: &str, current: u32, required: u32) -> QuestObjectiveProgress {
+        QuestObjectiveProgress {
+            label: label.into(),
+            current,
+            required,
+        }
+    }
+
+    #[test]
+    fn journal_lists_active_quests_before_finished_ones() {
+        let mut log = QuestLog::default();
+        log.quests.insert(
+            "old".into(),
+            QuestProgress {
+                id: "old".into(),
+                name: "Old Debts".into(),
+                status: QuestStatus::Completed,
+                stage: 0,
+                stage_id: "pay".into(),
+                stage_description: None,
+                objectives: vec![],
+            },
+        );
+        log.quests.insert(
+            "herbs".into(),
+            QuestProgress {
+                id: "herbs".into(),
+                name: "Herbal Remedy".into(),
+                status: QuestStatus::Active,
+                stage: 0,
+                stage_id: "gather".into(),
+                stage_description: Some("Gather wild herbs.".into()),
+                objectives: vec![
+                    objective("Collect 3 wild_herb", 1, 3),
+                    objective("Talk to Healer", 1, 1),
+                ],
+            },
+        );
+
+        assert!(log.has_active());
+        assert!(!log.get("herbs").unwrap().stage_done());
+        assert_eq!(
+            log.journal_lines(),
+            vec![
+                "Herbal Remedy: Gather wild herbs.",
+                "  [ ] Collect 3 wild_herb (1/3)",
+                "  [x] Talk to Healer",
+                "Old Debts (completed)",
+            ]
+        );
+    }
+}
@@ -1,22 +1,30 @@
+use crate::server::behavior_tree::{behavior_trees_from_config, update_entity_behavior};
 use crate::server::message::DialogChoice;
+use crate::server::perception::update_entity_perception;
 use crate::server::py_fn::*;
 use crate::server::region_host::{run_client_fn, run_server_fn, run_server_named_fn};
+use crate::server::savegame::RegionSaveState;
+use crate::server::schedule::update_entity_schedules;
 use crate::vm::*;
 use crate::{
     Assets, Choice, Currencies, Entity, EntityAction, Item, Map, MultipleChoice, ParticleEmitter,
-    PixelSource, PlayerCamera, RegionCtx, Value, ValueContainer,
+    PixelSource, PlayerCamera, QuestEvent, QuestEventKind, QuestObjectiveProgress, QuestProgress,
+    QuestStatus, RegionCtx, Value, ValueContainer,
 };
 use crossbeam_channel::{Receiver, Sender, unbounded};
 use eldiron_ruleset::{
-    ResolvedAction, ResolvedActionAttributePredicate, ResolvedActionEffect,
-    ResolvedActionEffectRecipient, ResolvedActionEffectValue, ResolvedActionItemSource,
-    ResolvedActionKind, ResolvedActionModification, ResolvedActionModificationField,
-    ResolvedActionPredicateValue, ResolvedActionRange, ResolvedActionRequirement,
-    ResolvedActionTarget, ResolvedActionValueSource, ResolvedCondition,
-    ResolvedConditionPeriodicEffect, ResolvedConditionStacking, evaluate_formula,
+    Combatant, DamageContext, ResolvedAction, ResolvedActionAttributePredicate,
+    ResolvedActionEffect, ResolvedActionEffectRecipient, ResolvedActionEffectValue,
+    ResolvedActionItemSource, ResolvedActionKind, ResolvedActionModification,
+    ResolvedActionModificationField, ResolvedActionPredicateValue, ResolvedActionRange,
+    ResolvedActionRequirement, ResolvedActionTarget, ResolvedActionValueSource, ResolvedCondition,
+    ResolvedConditionPeriodicEffect, ResolvedConditionStacking, ResolvedQuest,
+    ResolvedQuestObjective, ResolvedQuestObjectiveKind, RulesetDice, WeaponDamage, combat_variable,
+    evaluate_formula, progression_stat, resolve_critical, resolve_damage,
 };
 use instant::{Duration, Instant};
 use pathfinding::prelude::astar;
+use rand::rngs::StdRng;
 use rand::seq::SliceRandom;
 use rand::*;
 
@@ -1398,6 +1406,13 @@ mod ruleset_progression_tests {
 
     #[test]
     fn item_quality_and_condition_scale_weapon_damage() {
+        let apply_item_quality_condition_to_damage = |value: f32, item: &Item| {
+            eldiron_ruleset::apply_item_quality_to_damage(
+                value,
+                item_percent_attr(item, "quality"),
+                item_percent_attr(item, "condition"),
+            )
+        };
         let mut item = Item::new();
         item.set_attribute("quality", Value::Int(50));
         item.set_attribute("condition", Value::Int(50));
@@ -2213,6 +2228,54 @@ mod ruleset_progression_tests {
         assert!(!ctx.map.entities[1].attributes.contains("HP"));
     }
 
+    #[test]
+    fn attack_damage_applies_ruleset_criticals() {
+        let mut ctx = RegionCtx::default();
+        ctx.set_rules(
+            r#"
+            [attributes]
+            resources = ["VITAL", "VITAL_CAP"]
+
+            [attributes.roles]
+            health = "VITAL"
+            max_health = "VITAL_CAP"
+
+            [actions.strike]
+            name = "Strike"
+            kind = "attack"
+            intent = "attack"
+            target = "hostile_or_neutral_entity"
+            result = { damage = "weapon" }
+
+            [combat]
+            critical_roll = 1
+            critical_multiplier = 3.0
+
+            [combat.unarmed_damage]
+            roll = "1d1"
+            "#
+            .parse::<toml::Table>()
+            .unwrap(),
+        )
+        .unwrap();
+
+        let mut attacker = Entity::new();
+        attacker.id = 1;
+        let mut target = Entity::new();
+        target.id = 2;
+        target.set_attribute("VITAL", Value::Int(5));
+        target.set_attribute("VITAL_CAP", Value::Int(5));
+        target.set_attribute("autodamage", Value::Bool(true));
+        ctx.map.entities.extend([attacker, target]);
+
+        queue_entity_attack_damage(&mut ctx, 1, 2);
+
+        assert_eq!(
+            ctx.map.entities[1].attributes.get_int_default("VITAL", 0),
+            2
+        );
+    }
+
     #[test]
     fn resolved_action_cache_refreshes_when_region_rules_change() {
         let mut ctx = RegionCtx::default();
@@ -2247,6 +2310,31 @@ mod ruleset_progression_tests {
         );
     }
 
+    #[test]
+    fn resolved_quest_cache_refreshes_when_region_rules_change() {
+        let mut ctx = RegionCtx::default();
+        let quest = |name: &str| {
+            format!(
+                r#"
+                [quests.cull]
+                name = "{}"
+                [[quests.cull.stages]]
+                id = "hunt"
+                objectives = [{{ kind = "kill", class = "Wolf", count = 2 }}]
+                "#,
+                name
+            )
+            .parse::<toml::Table>()
+            .unwrap()
+        };
+        ctx.set_rules(quest("First Hunt")).unwrap();
+        assert_eq!(ctx.resolved_quest("cull").unwrap().name, "First Hunt");
+        assert!(ctx.resolved_quest("missing").is_none());
+
+        ctx.set_rules(quest("Second Hunt")).unwrap();
+        assert_eq!(ctx.resolved_quest("cull").unwrap().name, "Second Hunt");
+    }
+
     #[test]
     fn ruleset_runtime_cache_rejects_unknown_condition_references() {
         let mut ctx = RegionCtx::default();
@@ -3487,6 +3575,246 @@ mod ruleset_progression_tests {
         assert_eq!(RegionInstance::game_minutes_to_ticks(&ctx, 2.0), 20);
     }
 
+    #[test]
+    fn ruleset_quests_track_objectives_and_grant_rewards() {
+        let mut ctx = RegionCtx::default();
+        let (from_sender, from_receiver) = unbounded();
+        let _ = ctx.from_sender.set(from_sender);
+        ctx.currencies = Currencies::official_default();
+        ctx.level_attr = "LEVEL".into();
+        ctx.experience_attr = "EXP".into();
+        ctx.set_rules(
+            r#"
+            [attributes]
+            progression = ["LEVEL", "EXP"]
+            [attributes.roles]
+            level = "LEVEL"
+            experience = "EXP"
+
+            [progression.level]
+            xp_for_level = "level * 100"
+            [progression.xp]
+            quest_minor = 25
+
+            [items.materials.wolf_pelt]
+            name = "Wolf Pelt"
+            slot = "material"
+            [items.materials.wolf_pelt.attributes]
+            stackable = true
+            max_stack = 20
+
+            [quests.cull]
+            name = "Cull the Pack"
+            [[quests.cull.stages]]
+            id = "hunt"
+            objectives = [{ kind = "kill", class = "Wolf", count = 2 }]
+            [[quests.cull.stages]]
+            id = "pelts"
+            objectives = [{ kind = "collect", item = "wolf_pelt", count = 2, consume = true }]
+            [quests.cull.rewards]
+            xp = "quest_minor"
+            currency = 30
+            "#
+            .parse::<toml::Table>()
+            .unwrap(),
+        )
+        .unwrap();
+        let mut hunter = Entity::new();
+        hunter.id = 1;
+        hunter.inventory.resize(4, None);
+        hunter.set_attribute("LEVEL", Value::Int(1));
+        hunter.set_attribute("EXP", Value::Int(0));
+        ctx.map.entities.push(hunter);
+        ctx.entity_classes.insert(2, "Wolf".into());
+        ctx.entity_classes.insert(3, "Bear".into());
+
+        assert!(start_quest(&mut ctx, 1, "cull"));
+        assert!(!start_quest(&mut ctx, 1, "cull"));
+        assert!(!start_quest(&mut ctx, 1, "missing"));
+        assert_eq!(quest_status(&ctx, 1, "cull"), "active");
+        assert_eq!(quest_stage(&ctx, 1, "cull"), "hunt");
+
+        let kill = |target: u32| VMValue::broadcast(target as f32);
+        observe_quest_event(&mut ctx, 1, "kill", &kill(3));
+        observe_quest_event(&mut ctx, 1, "kill", &kill(2));
+        assert_eq!(
+            ctx.map.entities[0].quests.get("cull").unwrap().objectives[0].current,
+            1
+        );
+        observe_quest_event(&mut ctx, 1, "kill", &kill(2));
+        assert_eq!(quest_stage(&ctx, 1, "cull"), "pelts");
+
+        let pelts = ruleset_item_from_table(&ctx.rules, "wolf_pelt", 3).unwrap();
+        ctx.map.entities[0].add_item(pelts).unwrap();
+        update_quest_progress(&mut ctx);
+
+        let hunter = &ctx.map.entities[0];
+        assert_eq!(quest_status(&ctx, 1, "cull"), "completed");
+        assert_eq!(entity_inventory_item_count(hunter, "wolf_pelt"), 1);
+        assert_eq!(hunter.wallet.get_balance(&ctx.currencies), 30);
+        assert_eq!(hunter.attributes.get_float_default("EXP", 0.0), 25.0);
+        assert!(hunter.dirty_flags & 0b1000000 != 0);
+
+        let kinds = from_receiver
+            .try_iter()
+            .filter_map(|message| match message {
+                RegionMessage::QuestEvent(_, event) => Some(event.kind),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            vec![
+                QuestEventKind::Started,
+                QuestEventKind::Progress,
+                QuestEventKind::Progress,
+                QuestEventKind::Advanced,
+                QuestEventKind::Progress,
+                QuestEventKind::Completed,
+            ]
+        );
+        assert!(!fail_quest(&mut ctx, 1, "cull"));
+    }
+
+    #[test]
+    fn shops_restock_and_trade_at_formula_prices() {
+        let mut ctx = RegionCtx::default();
+        let (from_sender, _from_receiver) = unbounded();
+        let _ = ctx.from_sender.set(from_sender);
+        ctx.currencies = Currencies::official_default();
+        ctx.ticks_per_minute = 4;
+        ctx.set_rules(
+            r#"
+            [dispositions]
+            friendly = 1
+            neutral = 0
+            hostile = -1
+
+            [economy.trade]
+            buy_price = "worth * (1 - disposition * 0.5)"
+            sell_price = "worth / 2"
+
+            [items.materials.wild_herb]
+            name = "Wild Herb"
+            category = "herb"
+            slot = "material"
+            worth = 4
+            max_stack = 50
+            [items.materials.wild_herb.attributes]
+            stackable = true
+
+            [items.clothing.linen_shirt]
+            name = "Linen Shirt"
+            category = "cloth"
+            slot = "torso"
+            worth = 10
+
+            [shops.herbalist]
+            restock_minutes = 10
+            buys = ["herb"]
+            funds = 20
+            stock = [{ item = "wild_herb", quantity = 5 }]
+            "#
+            .parse::<toml::Table>()
+            .unwrap(),
+        )
+        .unwrap();
+        let mut vendor = Entity::new();
+        vendor.id = 1;
+        vendor.inventory.resize(8, None);
+        vendor.set_attribute("name", Value::Str("Mira".into()));
+        vendor.set_attribute("shop", Value::Str("herbalist".into()));
+        vendor.set_attribute("faction", Value::Str("guild".into()));
+        let mut customer = Entity::new();
+        customer.id = 2;
+        customer.inventory.resize(8, None);
+        customer
+            .add_base_currency(100, &ctx.currencies.clone())
+            .unwrap();
+        ctx.map.entities.push(vendor);
+        ctx.map.entities.push(customer);
+
+        restock_shops(&mut ctx);
+        let listing = shop_listing(&ctx, 1, 2);
+        assert_eq!(listing.len(), 1);
+        assert_eq!(
+            (listing[0].1.as_str(), listing[0].2, listing[0].3),
+            ("Wild Herb", 5, 4)
+        );
+        assert_eq!(ctx.map.entities[0].wallet.get_balance(&ctx.currencies), 20);
+
+        let trade = buy_item_from_vendor(&mut ctx, 2, 1, listing[0].0, 3).unwrap();
+        assert_eq!((trade.quantity, trade.price), (3, 12));
+        assert_eq!(
+            entity_inventory_item_count(&ctx.map.entities[0], "wild_herb"),
+            2
+        );
+        assert_eq!(
+            entity_inventory_item_count(&ctx.map.entities[1], "wild_herb"),
+            3
+        );
+        assert_eq!(ctx.map.entities[1].wallet.get_balance(&ctx.currencies), 88);
+        assert_eq!(ctx.map.entities[0].wallet.get_balance(&ctx.currencies), 32);
+
+        // Restocking waits for the timer and only tops the stock up.
+        restock_shops(&mut ctx);
+        assert_eq!(
+            entity_inventory_item_count(&ctx.map.entities[0], "wild_herb"),
+            2
+        );
+        ctx.ticks += 40;
+        restock_shops(&mut ctx);
+        assert_eq!(
+            entity_inventory_item_count(&ctx.map.entities[0], "wild_herb"),
+            5
+        );
+
+        // Friendly customers get the disposition discount.
+        ctx.map.entities[1].set_attribute("faction", Value::Str("guild".into()));
+        assert_eq!(shop_listing(&ctx, 1, 2)[0].3, 2);
+
+        let shirt = ruleset_item_from_table(&ctx.rules, "linen_shirt", 1).unwrap();
+        let shirt_id = shirt.id;
+        ctx.map.entities[1].add_item(shirt).unwrap();
+        assert_eq!(
+            sell_item_to_vendor(&mut ctx, 2, 1, shirt_id, 1),
+            Err(TradeError::NotWanted)
+        );
+        let herbs = find_trade_item(&ctx.map.entities[1], "wild herb").unwrap();
+
+        // A vendor without room refuses the goods and nothing changes hands.
+        let vendor_slots = ctx.map.entities[0].inventory.clone();
+        for slot in ctx.map.entities[0].inventory.iter_mut() {
+            *slot = ruleset_item_from_table(&ctx.rules, "linen_shirt", 1);
+        }
+        assert_eq!(
+            sell_item_to_vendor(&mut ctx, 2, 1, herbs, 10),
+            Err(TradeError::InventoryFull)
+        );
+        ctx.map.entities[0].inventory = vendor_slots;
+        assert_eq!(
+            entity_inventory_item_count(&ctx.map.entities[1], "wild_herb"),
+            3
+        );
+        assert_eq!(ctx.map.entities[1].wallet.get_balance(&ctx.currencies), 88);
+
+        let trade = sell_item_to_vendor(&mut ctx, 2, 1, herbs, 10).unwrap();
+        assert_eq!((trade.quantity, trade.price), (3, 6));
+        assert_eq!(
+            entity_inventory_item_count(&ctx.map.entities[1], "wild_herb"),
+            0
+        );
+        assert_eq!(ctx.map.entities[0].wallet.get_balance(&ctx.currencies), 26);
+
+        let currencies = ctx.currencies.clone();
+        ctx.map.entities[1].spend_currency(94, &currencies).unwrap();
+        let herbs = find_trade_item(&ctx.map.entities[0], "Wild Herb").unwrap();
+        assert_eq!(
+            buy_item_from_vendor(&mut ctx, 2, 1, herbs, 1),
+            Err(TradeError::CantAfford)
+        );
+    }
+
     #[test]
     fn formula_progression_cannot_level_past_the_declared_cap() {
         let mut ctx = RegionCtx::default();
@@ -3614,6 +3942,16 @@ pub fn reset_global_id_gen() {
     GLOBAL_ID_GEN.store(1, Ordering::Relaxed);
 }
 
+/// The next id the global generator will hand out.
+pub fn peek_global_id() -> u32 {
+    GLOBAL_ID_GEN.load(Ordering::Relaxed)
+}
+
+/// Make sure the global generator never hands out an id below `next_id`.
+pub fn ensure_global_id_at_least(next_id: u32) {
+    GLOBAL_ID_GEN.fetch_max(next_id.max(1), Ordering::Relaxed);
+}
+
 fn map_spawn_height(map: &Map, pos: Vec2<f32>, preferred_y: Option<f32>) -> f32 {
     // Spawn on a walkable floor, not on overlapping roof sectors.
     if let Some(pref_y) = preferred_y {
@@ -3759,7 +4097,7 @@ fn sector_floor_height_below_or_nearest(
 }
 use EntityAction::*;
 
-use super::data::{apply_entity_data, apply_item_data};
+use super::data::{apply_entity_data, apply_item_data, parse_entity_behavior_tree_from_toml};
 use super::{AudioCommand, RegionMessage};
 use crate::server::regionctx::{ChoiceSession, ScriptScope};
 use RegionMessage::*;
@@ -3796,6 +4134,15 @@ enum ContainerTransferError {
     ItemNotInContainer,
 }
 
+/// A clock which only advances with the redraw ticks. Deterministic runs use
+/// it instead of the wall clock.
+#[derive(Clone, Copy, Debug)]
+struct FixedClock {
+    start: Instant,
+    elapsed: Duration,
+    step: Duration,
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 enum DragContainerLocation {
     InventorySlot { entity_index: usize, slot: usize },
@@ -3840,6 +4187,10 @@ pub struct RegionInstance {
     pending_system_steps: u32,
     pending_redraw_steps: u32,
     movement_units_per_sec: f32,
+
+    /// Gameplay randomness of a deterministic run, see `set_deterministic`.
+    seeded_rng: Option<StdRng>,
+    fixed_clock: Option<FixedClock>,
 }
 
 impl RegionInstance {
@@ -3930,10 +4281,10 @@ impl RegionInstance {
         DynamicCollisionProbe { blocking_collision }
     }
 
-    fn is_first_person_camera(player_camera: &PlayerCamera) -> bool {
+    fn is_facing_relative_camera(player_camera: &PlayerCamera) -> bool {
         matches!(
             player_camera,
-            PlayerCamera::D3FirstP | PlayerCamera::D3FirstPGrid
+            PlayerCamera::D3FirstP | PlayerCamera::D3FirstPGrid | PlayerCamera::D3ThirdP
         )
     }
 
@@ -5476,6 +5827,20 @@ impl RegionInstance {
                     );
                 }
             }
+            TextCommand::Journal => {
+                let lines = ctx
+                    .map
+                    .entities
+                    .iter()
+                    .find(|entity| entity.id == entity_id)
+                    .map(|entity| entity.quests.journal_lines())
+                    .unwrap_or_default();
+                if lines.is_empty() {
+                    Self::send_text_command_feedback(ctx, entity_id, "system.journal_empty", &[]);
+                } else {
+                    send_message(ctx, entity_id, lines.join("\n"), "system");
+                }
+            }
             TextCommand::Move(_) | TextCommand::Go(_) => {
                 Self::send_text_command_feedback(ctx, entity_id, "system.text_movement_only", &[]);
             }
@@ -5575,6 +5940,87 @@ impl RegionInstance {
                     );
                 }
             }
+            TextCommand::List { vendor } => {
+                let Some(vendor_id) = nearby_shop_vendor(ctx, entity_id, vendor.as_deref()) else {
+                    send_trade_feedback(ctx, entity_id, None, Err(TradeError::NoVendor), true);
+                    return;
+                };
+                let wares = shop_listing(ctx, vendor_id, entity_id);
+                let vendor = ctx
+                    .map
+                    .entities
+                    .iter()
+                    .find(|entity| entity.id == vendor_id)
+                    .and_then(|entity| entity.attributes.get_str("name"))
+                    .unwrap_or_default()
+                    .to_string();
+                if wares.is_empty() {
+                    Self::send_text_command_feedback(
+                        ctx,
+                        entity_id,
+                        "shops.sold_out",
+                        &[("vendor", vendor)],
+                    );
+                    return;
+                }
+                let key = ruleset_message_key(ctx, "shops", "wares", "shops.wares");
+                let mut lines = vec![localized_message(ctx, &key, &[("vendor", vendor)])];
+                for (_, name, quantity, price) in wares {
+                    let price = ctx.currencies.format_base_amount(price);
+                    if quantity > 1 {
+                        lines.push(format!("  {} ({}) - {}", name, quantity, price));
+                    } else {
+                        lines.push(format!("  {} - {}", name, price));
+                    }
+                }
+                send_message(ctx, entity_id, lines.join("\n"), "system");
+            }
+            TextCommand::Buy {
+                item,
+                quantity,
+                vendor,
+            } => {
+                let Some(vendor_id) = nearby_shop_vendor(ctx, entity_id, vendor.as_deref()) else {
+                    send_trade_feedback(ctx, entity_id, None, Err(TradeError::NoVendor), true);
+                    return;
+                };
+                let result = match ctx
+                    .map
+                    .entities
+                    .iter()
+                    .find(|entity| entity.id == vendor_id)
+                    .and_then(|vendor| find_trade_item(vendor, &item))
+                {
+                    Some(item_id) => {
+                        buy_item_from_vendor(ctx, entity_id, vendor_id, item_id, quantity)
+                    }
+                    None => Err(TradeError::NotForSale),
+                };
+                send_trade_feedback(ctx, entity_id, Some(vendor_id), result, true);
+            }
+            TextCommand::Sell {
+                item,
+                quantity,
+                vendor,
+            } => {
+                let Some(vendor_id) = nearby_shop_vendor(ctx, entity_id, vendor.as_deref()) else {
+                    send_trade_feedback(ctx, entity_id, None, Err(TradeError::NoVendor), false);
+                    return;
+                };
+                let result = match ctx
+                    .map
+                    .entities
+                    .iter()
+                    .find(|entity| entity.id == entity_id)
+                    .and_then(|customer| find_trade_item(customer, &item))
+                {
+                    Some(item_id) => {
+                        sell_item_to_vendor(ctx, entity_id, vendor_id, item_id, quantity)
+                    }
+                    None => Err(TradeError::ItemNotFound),
+                };
+                send_trade_feedback(ctx, entity_id, Some(vendor_id), result, false);
+            }
             TextCommand::PutIn { item, container } => {
                 match Self::put_inventory_item_in_container(ctx, entity_id, &item, &container) {
                     Ok((item, container)) => Self::send_text_command_feedback(
@@ -5672,6 +6118,7 @@ impl RegionInstance {
                                 ctx.region_id,
                                 item_id,
                                 Some(entity_id),
+                                entity_id,
                             ));
                         }
                     }
@@ -5697,6 +6144,7 @@ impl RegionInstance {
                                 ctx.region_id,
                                 item_id,
                                 Some(entity_id),
+                                entity_id,
                             ));
                         }
                     }
@@ -5714,11 +6162,13 @@ impl RegionInstance {
                             send_drag_too_far_message(ctx, entity_id);
                             return;
                         }
+                        fill_ruleset_container_loot(ctx, item_id, entity_id);
                         if let Some(sender) = ctx.from_sender.get() {
                             let _ = sender.send(RegionMessage::OpenContainer(
                                 ctx.region_id,
                                 item_id,
                                 None,
+                                entity_id,
                             ));
                         }
                     }
@@ -5943,9 +6393,9 @@ impl RegionInstance {
             return true;
         }
         if Self::is_click_like_step_action(action)
-            && self
-                .last_external_step_request_at
-                .is_some_and(|last| last.elapsed() < Duration::from_millis(150))
+            && self.last_external_step_request_at.is_some_and(|last| {
+                self.now().saturating_duration_since(last) < Duration::from_millis(150)
+            })
         {
             return false;
         }
@@ -6071,7 +6521,7 @@ impl RegionInstance {
     fn queue_simulation_step(&mut self) {
         self.pending_system_steps = self.pending_system_steps.saturating_add(1);
         self.pending_redraw_steps = self.pending_redraw_steps.saturating_add(1);
-        self.last_simulation_advance_at = Instant::now();
+        self.last_simulation_advance_at = self.now();
     }
 
     fn grant_simulation_steps_if_due(&mut self, ctx: &RegionCtx) {
@@ -6084,7 +6534,9 @@ impl RegionInstance {
                 }
             }
             crate::server::regionctx::SimulationMode::Hybrid => {
-                let timeout_elapsed = self.last_simulation_advance_at.elapsed()
+                let timeout_elapsed = self
+                    .now()
+                    .saturating_duration_since(self.last_simulation_advance_at)
                     >= Duration::from_millis(ctx.turn_timeout_ms.max(1) as u64);
                 if self.simulation_step_pending || timeout_elapsed {
                     self.simulation_step_pending = false;
@@ -6153,7 +6605,7 @@ impl RegionInstance {
         match ctx.simulation_mode {
             crate::server::regionctx::SimulationMode::Realtime => {
                 self.current_frame_has_turn_step = true;
-                self.last_simulation_advance_at = Instant::now();
+                self.last_simulation_advance_at = self.now();
                 redraw_dt
             }
             crate::server::regionctx::SimulationMode::TurnBased => {
@@ -6315,7 +6767,7 @@ impl RegionInstance {
 
         match desired {
             EntityAction::Forward => {
-                if Self::is_first_person_camera(player_camera) {
+                if Self::is_facing_relative_camera(player_camera) {
                     let facing = Self::snapped_cardinal_direction(entity.orientation);
                     let target = entity.get_pos_xz() + facing;
                     self.queue_step_to_with_speed(
@@ -6337,7 +6789,7 @@ impl RegionInstance {
                 true
             }
             EntityAction::Backward => {
-                if Self::is_first_person_camera(player_camera) {
+                if Self::is_facing_relative_camera(player_camera) {
                     let facing = Self::snapped_cardinal_direction(entity.orientation);
                     let target = entity.get_pos_xz() - facing;
                     self.queue_step_to_with_speed(
@@ -6359,7 +6811,7 @@ impl RegionInstance {
                 true
             }
             EntityAction::Left => {
-                if Self::is_first_person_camera(player_camera) {
+                if Self::is_facing_relative_camera(player_camera) {
                     self.rotate_grid_left(entity);
                 } else {
                     entity.face_west();
@@ -6374,7 +6826,7 @@ impl RegionInstance {
                 true
             }
             EntityAction::Right => {
-                if Self::is_first_person_camera(player_camera) {
+                if Self::is_facing_relative_camera(player_camera) {
                     self.rotate_grid_right(entity);
                 } else {
                     entity.face_east();
@@ -6388,39 +6840,29 @@ impl RegionInstance {
                 }
                 true
             }
-            EntityAction::StrafeLeft => {
-                if Self::is_first_person_camera(player_camera) {
-                    let facing = Self::snapped_cardinal_direction(entity.orientation);
-                    let step = Vec2::new(facing.y, -facing.x);
-                    let target = entity.get_pos_xz() + step;
-                    self.queue_step_to_with_speed(
-                        entity,
-                        target,
-                        facing,
-                        Self::grid_hold_speed(entity),
-                    );
-                    true
-                } else {
-                    entity.action = EntityAction::Off;
-                    false
-                }
+            EntityAction::StrafeLeft if Self::is_facing_relative_camera(player_camera) => {
+                let facing = Self::snapped_cardinal_direction(entity.orientation);
+                let step = Vec2::new(facing.y, -facing.x);
+                let target = entity.get_pos_xz() + step;
+                self.queue_step_to_with_speed(
+                    entity,
+                    target,
+                    facing,
+                    Self::grid_hold_speed(entity),
+                );
+                true
             }
-            EntityAction::StrafeRight => {
-                if Self::is_first_person_camera(player_camera) {
-                    let facing = Self::snapped_cardinal_direction(entity.orientation);
-                    let step = Vec2::new(-facing.y, facing.x);
-                    let target = entity.get_pos_xz() + step;
-                    self.queue_step_to_with_speed(
-                        entity,
-                        target,
-                        facing,
-                        Self::grid_hold_speed(entity),
-                    );
-                    true
-                } else {
-                    entity.action = EntityAction::Off;
-                    false
-                }
+            EntityAction::StrafeRight if Self::is_facing_relative_camera(player_camera) => {
+                let facing = Self::snapped_cardinal_direction(entity.orientation);
+                let step = Vec2::new(-facing.y, facing.x);
+                let target = entity.get_pos_xz() + step;
+                self.queue_step_to_with_speed(
+                    entity,
+                    target,
+                    facing,
+                    Self::grid_hold_speed(entity),
+                );
+                true
             }
             _ => {
                 entity.action = EntityAction::Off;
@@ -6942,9 +7384,46 @@ impl RegionInstance {
             pending_system_steps: 0,
             pending_redraw_steps: 0,
             movement_units_per_sec: 4.0,
+
+            seeded_rng: None,
+            fixed_clock: None,
+        }
+    }
+
+    /// Make the region deterministic: gameplay randomness is drawn from a
+    /// generator seeded with `seed` and every redraw tick advances the clock
+    /// by `step` instead of following the wall clock. Call before `init` so
+    /// that startup scripts are covered too.
+    pub fn set_deterministic(&mut self, seed: u64, step: Duration) {
+        self.seeded_rng = Some(crate::server::rng::seeded(seed, self.id as u64));
+        let start = Instant::now();
+        self.fixed_clock = Some(FixedClock {
+            start,
+            elapsed: Duration::ZERO,
+            step,
+        });
+        self.last_redraw_at = start;
+        self.last_simulation_advance_at = start;
+        self.last_external_step_request_at = None;
+    }
+
+    /// The current time of the region, fixed in deterministic runs.
+    fn now(&self) -> Instant {
+        match self.fixed_clock {
+            Some(clock) => clock.start + clock.elapsed,
+            None => Instant::now(),
         }
     }
 
+    /// Run `f` with the region's seeded generator installed, if any.
+    fn with_seeded_rng<T>(&mut self, f: impl FnOnce(&mut Self) -> T) -> T {
+        let rng = self.seeded_rng.take();
+        let previous = crate::server::rng::install(rng);
+        let result = f(self);
+        self.seeded_rng = crate::server::rng::install(previous);
+        result
+    }
+
     /// Initializes the Python bases classes, sets the map and applies entities
     pub fn init(
         &mut self,
@@ -6953,6 +7432,19 @@ impl RegionInstance {
         assets: &Assets,
         config_toml: String,
         debug_mode: bool,
+    ) {
+        self.with_seeded_rng(|region| {
+            region.init_region(name, map, assets, config_toml, debug_mode)
+        });
+    }
+
+    fn init_region(
+        &mut self,
+        name: String,
+        map: Map,
+        assets: &Assets,
+        config_toml: String,
+        debug_mode: bool,
     ) {
         self.name = name.clone();
 
@@ -6962,6 +7454,12 @@ impl RegionInstance {
         if let Ok(toml) = config_toml.parse::<toml::Table>() {
             ctx.config = toml;
         }
+        let (behavior_trees, errors) = behavior_trees_from_config(&ctx.config);
+        ctx.behavior_trees = behavior_trees;
+        for err in errors {
+            ctx.startup_errors
+                .push(format!("[warning] {}: {}", self.name, err));
+        }
         if !assets.rules.trim().is_empty() {
             match assets.rules.parse::<toml::Table>() {
                 Ok(toml) => {
@@ -7027,6 +7525,21 @@ impl RegionInstance {
             // Store entity classes which handle player
             match entity_data.parse::<toml::Table>() {
                 Ok(data) => {
+                    match parse_entity_behavior_tree_from_toml(&data) {
+                        Ok(Some(tree)) => {
+                            for unknown in tree.unknown_trees(&ctx.behavior_trees) {
+                                ctx.startup_errors.push(format!(
+                                    "[warning] {}: Behavior Tree of Character '{}': unknown tree `{}`",
+                                    self.name, name, unknown,
+                                ));
+                            }
+                        }
+                        Ok(None) => {}
+                        Err(err) => ctx.startup_errors.push(format!(
+                            "[warning] {}: Behavior Tree of Character '{}': {}",
+                            self.name, name, err,
+                        )),
+                    }
                     if let Some(game) = data.get("attributes").and_then(toml::Value::as_table) {
                         if let Some(value) = game.get("player") {
                             if let Some(v) = value.as_bool() {
@@ -7380,6 +7893,7 @@ impl RegionInstance {
             if let Some(class_name) = entity.get_attr_string("class_name") {
                 if let Some(data) = ctx.entity_class_data.get(&class_name) {
                     let rules = ctx.rules.clone();
+                    let home_region = ctx.map.name.clone();
                     let ground_y =
                         ctx_spawn_height(&ctx, entity.get_pos_xz(), Some(entity.position.y));
                     let mut spawn_entity_id: Option<u32> = None;
@@ -7388,6 +7902,9 @@ impl RegionInstance {
                             let start_class =
                                 e.attributes.get_str("_start_class").map(str::to_string);
                             apply_entity_data(e, data);
+                            if e.home_region.is_empty() {
+                                e.home_region = home_region.clone();
+                            }
                             if let Some(class) = start_class {
                                 e.set_attribute("class", Value::Str(class));
                                 e.attributes.remove("_start_class");
@@ -7626,6 +8143,10 @@ impl RegionInstance {
 
     /// System tick
     pub fn system_tick(&mut self) {
+        self.with_seeded_rng(|region| region.run_system_tick());
+    }
+
+    fn run_system_tick(&mut self) {
         let mut ticks = 0;
         let mut should_advance = true;
 
@@ -7646,6 +8167,8 @@ impl RegionInstance {
             ctx.ticks += 1;
             ticks = ctx.ticks;
             update_ruleset_resource_regen(ctx);
+            update_quest_progress(ctx);
+            restock_shops(ctx);
 
             let mins = ctx.time.total_minutes();
             ctx.time = TheTime::from_ticks(ticks, ctx.ticks_per_minute);
@@ -7920,11 +8443,25 @@ impl RegionInstance {
                     ));
                 }
             }
+
+            update_entity_perception(ctx);
+
+            update_entity_schedules(ctx);
+            flush_pending_entity_transfers(ctx);
+
+            update_entity_behavior(ctx);
         });
     }
 
     /// Redraw tick
     pub fn redraw_tick(&mut self) {
+        if let Some(clock) = &mut self.fixed_clock {
+            clock.elapsed += clock.step;
+        }
+        self.with_seeded_rng(|region| region.run_redraw_tick());
+    }
+
+    fn run_redraw_tick(&mut self) {
         // Catch up with the server messages
         while let Ok(msg) = self.to_receiver.try_recv() {
             match msg {
@@ -8008,7 +8545,7 @@ impl RegionInstance {
                                     && Self::entity_has_active_continuous_motion(entity))
                             {
                                 if Self::is_click_like_step_action(&action) {
-                                    self.last_external_step_request_at = Some(Instant::now());
+                                    self.last_external_step_request_at = Some(self.now());
                                 }
                                 self.note_simulation_step_request();
                             }
@@ -8723,6 +9260,9 @@ impl RegionInstance {
                                 if !container_exists {
                                     return;
                                 }
+                                if owner_entity_id.is_none() {
+                                    fill_ruleset_container_loot(ctx, item_id, entity_id);
+                                }
                                 ctx.active_container_sessions
                                     .insert(entity_id, (item_id, owner_entity_id));
                                 if let Some(sender) = ctx.from_sender.get() {
@@ -8730,6 +9270,7 @@ impl RegionInstance {
                                         ctx.region_id,
                                         item_id,
                                         owner_entity_id,
+                                        entity_id,
                                     ));
                                 }
                             });
@@ -8840,6 +9381,7 @@ impl RegionInstance {
                                 buyer_id,
                                 expires_at_tick,
                                 max_distance,
+                                _,
                             ) => {
                                 with_regionctx(self.id, |ctx: &mut RegionCtx| {
                                     clear_choice_session(ctx, *seller_id, *buyer_id);
@@ -8861,59 +9403,16 @@ impl RegionInstance {
                                         return;
                                     }
 
-                                    let mut msg_to_buyer: Option<String> = None;
-                                    let mut msg_role = "system";
-
-                                    // Get the price of the item.
-                                    let mut price = 0;
-                                    let mut can_afford = false;
-                                    if let Some(entity) = get_entity_mut(&mut ctx.map, *seller_id) {
-                                        if let Some(item) = entity.get_item(*item_id) {
-                                            if let Some(w) = item.get_attribute("worth") {
-                                                if let Some(worth) = w.to_i32() {
-                                                    price = worth as i64;
-                                                }
-                                            }
-                                        }
-                                    }
-
-                                    // Check if the buyer can afford
-                                    if let Some(entity) = get_entity_mut(&mut ctx.map, *buyer_id) {
-                                        can_afford =
-                                            entity.wallet.can_afford(price, &ctx.currencies);
-                                    }
-
-                                    if can_afford {
-                                        let mut item_to_sell: Option<Item> = None;
-                                        if let Some(entity) =
-                                            get_entity_mut(&mut ctx.map, *seller_id)
-                                        {
-                                            if let Some(item) = entity.remove_item(*item_id) {
-                                                item_to_sell = Some(item);
-                                                _ = entity
-                                                    .add_base_currency(price, &ctx.currencies);
-                                            }
-                                        }
-                                        if let Some(item) = item_to_sell {
-                                            if let Some(entity) =
-                                                get_entity_mut(&mut ctx.map, *buyer_id)
-                                            {
-                                                msg_to_buyer = Some(format!(
-                                                    "{{system.you_bought}} {{I:{}.name, article=indef, case=lower}}",
-                                                    item.id
-                                                ));
-                                                _ = entity.add_item(item);
-                                                _ = entity.spend_currency(price, &ctx.currencies);
-                                            }
-                                        }
-                                    } else {
-                                        msg_to_buyer = Some("{system.cant_afford}".into());
-                                        msg_role = "warning";
-                                    }
-
-                                    if let Some(msg_to_buyer) = msg_to_buyer {
-                                        send_message(ctx, *buyer_id, msg_to_buyer, msg_role);
-                                    }
+                                    let result = buy_item_from_vendor(
+                                        ctx, *buyer_id, *seller_id, *item_id, 1,
+                                    );
+                                    send_trade_feedback(
+                                        ctx,
+                                        *buyer_id,
+                                        Some(*seller_id),
+                                        result,
+                                        true,
+                                    );
                                 });
                             }
                             Choice::Cancel(from_id, to_id, _, _) => {
@@ -9158,6 +9657,19 @@ impl RegionInstance {
                         receive_entity(ctx, entity, dest_sector_name);
                     });
                 }
+                RemoveEntity(_region_id, entity_id) => {
+                    with_regionctx(self.id, |ctx: &mut RegionCtx| {
+                        ctx.map.entities.retain(|entity| entity.id != entity_id);
+                        ctx.entity_classes.remove(&entity_id);
+                        ctx.entity_state_data.remove(&entity_id);
+                        ctx.entity_proximity_alerts.remove(&entity_id);
+                        ctx.notifications_entities
+                            .retain(|(id, _, _)| *id != entity_id);
+                    });
+                    let _ = self
+                        .from_sender
+                        .send(RegionMessage::RemoveEntity(self.id, entity_id));
+                }
                 Time(_id, time) => {
                     // User manually set the server time
                     with_regionctx(self.id, |ctx: &mut RegionCtx| {
@@ -9174,7 +9686,7 @@ impl RegionInstance {
 
         // ---
 
-        let now = Instant::now();
+        let now = self.now();
         let redraw_dt = now
             .saturating_duration_since(self.last_redraw_at)
             .as_secs_f32()
@@ -9272,7 +9784,7 @@ impl RegionInstance {
                                 entity.attributes.get("player_camera")
                             {
                                 if Self::is_grid_camera(player_camera) {
-                                    if Self::is_first_person_camera(player_camera) {
+                                    if Self::is_facing_relative_camera(player_camera) {
                                         let facing =
                                             Self::snapped_cardinal_direction(entity.orientation);
                                         let target = entity.get_pos_xz() + facing;
@@ -9283,7 +9795,7 @@ impl RegionInstance {
                                         self.queue_step_to(entity, target, Vec2::new(0.0, -1.0));
                                     }
                                 } else {
-                                    if !Self::is_first_person_camera(player_camera) {
+                                    if !Self::is_facing_relative_camera(player_camera) {
                                         entity.face_north();
                                     }
                                     self.move_entity(entity, 1.0, self.entity_block_mode);
@@ -9307,14 +9819,14 @@ impl RegionInstance {
                                 entity.attributes.get("player_camera")
                             {
                                 if Self::is_grid_camera(player_camera) {
-                                    if Self::is_first_person_camera(player_camera) {
+                                    if Self::is_facing_relative_camera(player_camera) {
                                         self.rotate_grid_left(entity);
                                     } else {
                                         entity.face_west();
                                         let target = entity.get_pos_xz() + Vec2::new(-1.0, 0.0);
                                         self.queue_step_to(entity, target, Vec2::new(-1.0, 0.0));
                                     }
-                                } else if !Self::is_first_person_camera(player_camera) {
+                                } else if !Self::is_facing_relative_camera(player_camera) {
                                     entity.face_west();
                                     self.move_entity(entity, 1.0, self.entity_block_mode);
                                 } else {
@@ -9340,14 +9852,14 @@ impl RegionInstance {
                                 entity.attributes.get("player_camera")
                             {
                                 if Self::is_grid_camera(player_camera) {
-                                    if Self::is_first_person_camera(player_camera) {
+                                    if Self::is_facing_relative_camera(player_camera) {
                                         self.rotate_grid_right(entity);
                                     } else {
                                         entity.face_east();
                                         let target = entity.get_pos_xz() + Vec2::new(1.0, 0.0);
                                         self.queue_step_to(entity, target, Vec2::new(1.0, 0.0));
                                     }
-                                } else if !Self::is_first_person_camera(player_camera) {
+                                } else if !Self::is_facing_relative_camera(player_camera) {
                                     entity.face_east();
                                     self.move_entity(entity, 1.0, self.entity_block_mode);
                                 } else {
@@ -9372,7 +9884,7 @@ impl RegionInstance {
                                 entity.attributes.get("player_camera")
                             {
                                 if Self::is_grid_camera(player_camera) {
-                                    if Self::is_first_person_camera(player_camera) {
+                                    if Self::is_facing_relative_camera(player_camera) {
                                         let facing =
                                             Self::snapped_cardinal_direction(entity.orientation);
                                         let target = entity.get_pos_xz() - facing;
@@ -9382,7 +9894,7 @@ impl RegionInstance {
                                         let target = entity.get_pos_xz() + Vec2::new(0.0, 1.0);
                                         self.queue_step_to(entity, target, Vec2::new(0.0, 1.0));
                                     }
-                                } else if !Self::is_first_person_camera(player_camera) {
+                                } else if !Self::is_facing_relative_camera(player_camera) {
                                     entity.face_south();
                                     self.move_entity(entity, 1.0, self.entity_block_mode);
                                 } else {
@@ -9406,7 +9918,7 @@ impl RegionInstance {
                             if let Some(Value::PlayerCamera(player_camera)) =
                                 entity.attributes.get("player_camera")
                             {
-                                if Self::is_first_person_camera(player_camera) {
+                                if Self::is_facing_relative_camera(player_camera) {
                                     if Self::is_grid_camera(player_camera) {
                                         let facing =
                                             Self::snapped_cardinal_direction(entity.orientation);
@@ -9440,7 +9952,7 @@ impl RegionInstance {
                             if let Some(Value::PlayerCamera(player_camera)) =
                                 entity.attributes.get("player_camera")
                             {
-                                if Self::is_first_person_camera(player_camera) {
+                                if Self::is_facing_relative_camera(player_camera) {
                                     if Self::is_grid_camera(player_camera) {
                                         let facing =
                                             Self::snapped_cardinal_direction(entity.orientation);
@@ -9476,7 +9988,7 @@ impl RegionInstance {
                             {
                                 if Self::is_grid_camera(player_camera) {
                                     self.activate_grid_desired_action(entity);
-                                } else if !Self::is_first_person_camera(player_camera) {
+                                } else if !Self::is_facing_relative_camera(player_camera) {
                                     entity.set_orientation(vek::Vec2::new(-1.0, 1.0).normalized());
                                     self.move_entity(entity, 1.0, self.entity_block_mode);
                                 } else {
@@ -9502,7 +10014,7 @@ impl RegionInstance {
                             {
                                 if Self::is_grid_camera(player_camera) {
                                     self.activate_grid_desired_action(entity);
-                                } else if !Self::is_first_person_camera(player_camera) {
+                                } else if !Self::is_facing_relative_camera(player_camera) {
                                     entity.set_orientation(vek::Vec2::new(1.0, 1.0).normalized());
                                     self.move_entity(entity, 1.0, self.entity_block_mode);
                                 } else {
@@ -9528,7 +10040,7 @@ impl RegionInstance {
                             {
                                 if Self::is_grid_camera(player_camera) {
                                     self.activate_grid_desired_action(entity);
-                                } else if !Self::is_first_person_camera(player_camera) {
+                                } else if !Self::is_facing_relative_camera(player_camera) {
                                     entity.set_orientation(vek::Vec2::new(-1.0, -1.0).normalized());
                                     self.move_entity(entity, 1.0, self.entity_block_mode);
                                 } else {
@@ -9554,7 +10066,7 @@ impl RegionInstance {
                             {
                                 if Self::is_grid_camera(player_camera) {
                                     self.activate_grid_desired_action(entity);
-                                } else if !Self::is_first_person_camera(player_camera) {
+                                } else if !Self::is_facing_relative_camera(player_camera) {
                                     entity.set_orientation(vek::Vec2::new(1.0, -1.0).normalized());
                                     self.move_entity(entity, 1.0, self.entity_block_mode);
                                 } else {
@@ -10521,7 +11033,7 @@ impl RegionInstance {
                                 }
                             }
 
-                            let mut rng = rand::rng();
+                            let mut rng = crate::server::rng::sim_rng();
                             center_candidates.shuffle(&mut rng);
 
                             if let Some(candidate) = center_candidates.into_iter().next() {
@@ -10563,7 +11075,7 @@ impl RegionInstance {
                         // State 1: Walk towards
                         if target.distance(entity.get_pos_xz()) < 0.1 {
                             // Arrived, Sleep
-                            let mut rng = rand::rng();
+                            let mut rng = crate::server::rng::sim_rng();
                             entity.action = self.create_sleep_switch_action(
                                 rng.random_range(*max_sleep / 2..=*max_sleep) as u32,
                                 RandomWalk(*distance, *speed, *max_sleep, 0, *target),
@@ -10761,7 +11273,7 @@ impl RegionInstance {
                                     || stall_ticks >= 8
                                 {
                                     entity.attributes.set("__rw_stall_ticks", Value::Int(0));
-                                    let mut rng = rand::rng();
+                                    let mut rng = crate::server::rng::sim_rng();
                                     let min_sleep = (max_sleep / 2).max(1);
                                     let max_sleep_guard = max_sleep.max(1);
                                     let sleep_minutes =
@@ -10847,7 +11359,7 @@ impl RegionInstance {
                         // State 1: Walk towards
                         if target.distance(entity.get_pos_xz()) < 0.1 {
                             // Arrived, Sleep
-                            let mut rng = rand::rng();
+                            let mut rng = crate::server::rng::sim_rng();
                             entity.action = self.create_sleep_switch_action(
                                 rng.random_range(*max_sleep / 2..=*max_sleep) as u32,
                                 RandomWalkInSector(*distance, *speed, *max_sleep, 0, *target),
@@ -11056,7 +11568,7 @@ impl RegionInstance {
 
                                 if arrived {
                                     entity.attributes.set("__rwis_stall_ticks", Value::Int(0));
-                                    let mut rng = rand::rng();
+                                    let mut rng = crate::server::rng::sim_rng();
                                     let min_sleep = (max_sleep / 2).max(1);
                                     let max_sleep_guard = max_sleep.max(1);
                                     let sleep_minutes =
@@ -11073,7 +11585,7 @@ impl RegionInstance {
                                 {
                                     // Stuck against geometry/obstacle: pause, then pick a fresh target.
                                     entity.attributes.set("__rwis_stall_ticks", Value::Int(0));
-                                    let mut rng = rand::rng();
+                                    let mut rng = crate::server::rng::sim_rng();
                                     let min_sleep = (max_sleep / 2).max(1);
                                     let max_sleep_guard = max_sleep.max(1);
                                     let sleep_minutes =
@@ -11279,6 +11791,11 @@ impl RegionInstance {
                 });
                 continue;
             }
+            if todo.1 == "kill" || todo.1 == "intent" {
+                with_regionctx(self.id, |ctx| {
+                    observe_quest_event(ctx, todo.0, &todo.1, &todo.2);
+                });
+            }
 
             let mut ticks = 0;
             let mut state_data = FxHashMap::default();
@@ -11619,6 +12136,7 @@ impl RegionInstance {
                 // Setting the data for the entity
                 if let Some(data) = ctx.entity_class_data.get(&class_name) {
                     let rules = ctx.rules.clone();
+                    let home_region = ctx.map.name.clone();
                     let ground_y =
                         ctx_spawn_height(&ctx, entity.get_pos_xz(), Some(entity.position.y));
                     let mut spawn_entity_id: Option<u32> = None;
@@ -11629,6 +12147,9 @@ impl RegionInstance {
                             let start_name =
                                 e.attributes.get_str("_start_name").map(str::to_string);
                             apply_entity_data(e, data);
+                            if e.home_region.is_empty() {
+                                e.home_region = home_region.clone();
+                            }
                             if let Some(class) = start_class {
                                 e.set_attribute("class", Value::Str(class));
                                 e.attributes.remove("_start_class");
@@ -11749,6 +12270,100 @@ impl RegionInstance {
         );
     }
 
+    /// Capture the runtime state of this region for a save game.
+    pub fn save_state(&self) -> Option<RegionSaveState> {
+        with_regionctx(self.id, |ctx: &mut RegionCtx| RegionSaveState {
+            name: self.name.clone(),
+            map_id: ctx.map.id,
+            time: ctx.time,
+            ticks: ctx.ticks,
+            entities: ctx.map.entities.clone(),
+            items: ctx.map.items.clone(),
+            inventories: ctx
+                .map
+                .entities
+                .iter()
+                .map(|entity| (entity.id, entity.inventory.clone()))
+                .collect(),
+            entity_state_data: ctx.entity_state_data.clone(),
+            item_state_data: ctx.item_state_data.clone(),
+            entity_respawn_snapshots: ctx.entity_respawn_snapshots.clone(),
+            entity_proximity_alerts: ctx.entity_proximity_alerts.clone(),
+            item_proximity_alerts: ctx.item_proximity_alerts.clone(),
+            entity_awareness: ctx.entity_awareness.clone(),
+            notifications_entities: ctx.notifications_entities.clone(),
+            notifications_items: ctx.notifications_items.clone(),
+            region_state: ctx.region_state.clone(),
+        })
+    }
+
+    /// Replace the runtime state of this region with a saved state. All entities and
+    /// items are marked dirty so the next redraw tick retransmits them to the server.
+    pub fn load_state(&mut self, state: &RegionSaveState) {
+        with_regionctx(self.id, |ctx: &mut RegionCtx| {
+            ctx.ticks = state.ticks;
+            ctx.time = state.time;
+
+            ctx.map.entities = state.entities.clone();
+            for entity in ctx.map.entities.iter_mut() {
+                entity.inventory = state
+                    .inventories
+                    .get(&entity.id)
+                    .cloned()
+                    .unwrap_or_default();
+                entity.action = EntityAction::Off;
+                entity.mark_all_dirty();
+                entity.snap_position_update = true;
+            }
+            ctx.map.items = state.items.clone();
+            for item in ctx.map.items.iter_mut() {
+                item.mark_all_dirty();
+            }
+
+            ctx.entity_classes = ctx
+                .map
+                .entities
+                .iter()
+                .filter_map(|entity| {
+                    entity
+                        .get_attr_string("class_name")
+                        .map(|class_name| (entity.id, class_name))
+                })
+                .collect();
+            ctx.item_classes = ctx
+                .map
+                .items
+                .iter()
+                .filter_map(|item| {
+                    item.get_attr_string("class_name")
+                        .map(|class_name| (item.id, class_name))
+                })
+                .collect();
+
+            ctx.entity_state_data = state.entity_state_data.clone();
+            ctx.item_state_data = state.item_state_data.clone();
+            ctx.entity_respawn_snapshots = state.entity_respawn_snapshots.clone();
+            ctx.entity_proximity_alerts = state.entity_proximity_alerts.clone();
+            ctx.item_proximity_alerts = state.item_proximity_alerts.clone();
+            ctx.entity_awareness = state.entity_awareness.clone();
+            ctx.notifications_entities = state.notifications_entities.clone();
+            ctx.notifications_items = state.notifications_items.clone();
+            ctx.region_state = state.region_state.clone();
+
+            // Transient interaction state does not survive a load.
+            ctx.active_choice_sessions.clear();
+            ctx.active_container_sessions.clear();
+            ctx.to_execute_entity.clear();
+            ctx.to_execute_item.clear();
+            ctx.to_execute_world.clear();
+            ctx.pending_entity_transfers.clear();
+
+            let _ = self
+                .from_sender
+                .send(RegionMessage::Time(self.id, ctx.time));
+        });
+    }
+
     /// Get the name of the entity with the given id.
     fn get_entity_name(&self, id: u32) -> String {
         let mut name = "Unknown".to_string();
@@ -13675,6 +14290,7 @@ fn effective_entity_attribute_inner(
                     effective_entity_attribute_inner(ctx, entity, dependency, 0.0, visiting)
                 }
             })
+            .ok()
             .map(|mut value| {
                 if let Some(minimum) = stat.minimum {
                     value = value.max(minimum);
@@ -15110,178 +15726,1139 @@ pub(crate) fn craft_ruleset_recipe(ctx: &mut RegionCtx, crafter_id: u32, recipe_
     true
 }
 
-struct AttackAmmunition {
-    item_id: String,
-    quantity: usize,
+fn quest_stage_progress(quest: &ResolvedQuest, stage: usize) -> QuestProgress {
+    let definition = quest.stages.get(stage);
+    QuestProgress {
+        id: quest.id.clone(),
+        name: quest.name.clone(),
+        status: QuestStatus::Active,
+        stage,
+        stage_id: definition.map(|stage| stage.id.clone()).unwrap_or_default(),
+        stage_description: definition.and_then(|stage| stage.description.clone()),
+        objectives: definition
+            .map(|stage| {
+                stage
+                    .objectives
+                    .iter()
+                    .map(|objective| QuestObjectiveProgress {
+                        label: objective.label(),
+                        current: 0,
+                        required: objective.count,
+                    })
+                    .collect()
+            })
+            .unwrap_or_default(),
+    }
 }
 
-fn attack_required_ammunition(
+fn quest_message(ctx: &RegionCtx, name: &str, params: &[(&str, String)]) -> String {
+    let fallback = format!("quests.{}", name);
+    let key = ruleset_message_key(ctx, "quests", name, &fallback);
+    localized_message(ctx, &key, params)
+}
+
+/// Tell the player about a quest change and queue the event for its client.
+fn send_quest_event(
     ctx: &RegionCtx,
-    attacker_id: u32,
-    source_item_id: Option<u32>,
-) -> Option<AttackAmmunition> {
-    let source_item_id = source_item_id?;
-    let attacker = ctx
-        .map
+    entity_id: u32,
+    quest_id: &str,
+    kind: QuestEventKind,
+    text: String,
+) {
+    send_message(ctx, entity_id, text.clone(), "system");
+    let event = QuestEvent {
+        entity_id,
+        quest_id: quest_id.to_string(),
+        kind,
+        text,
+    };
+    ctx.from_sender
+        .get()
+        .unwrap()
+        .send(RegionMessage::QuestEvent(ctx.region_id, event))
+        .unwrap();
+}
+
+fn active_quest_stage(ctx: &RegionCtx, entity_id: u32, quest_id: &str) -> Option<usize> {
+    ctx.map
         .entities
         .iter()
-        .find(|entity| entity.id == attacker_id)?;
-    let source = entity_item_by_id(attacker, source_item_id)?;
-    let item_id = source
-        .attributes
-        .get_str("ammunition")
-        .map(str::trim)
-        .filter(|value| !value.is_empty())
-        .map(str::to_string)?;
-    let quantity = source
-        .attributes
-        .get_int_default("ammunition_quantity", 1)
-        .max(1) as usize;
-    Some(AttackAmmunition { item_id, quantity })
+        .find(|entity| entity.id == entity_id)?
+        .quests
+        .get(quest_id)
+        .filter(|quest| quest.status == QuestStatus::Active)
+        .map(|quest| quest.stage)
 }
 
-pub(crate) fn has_attack_ammunition_or_message(
-    ctx: &mut RegionCtx,
-    attacker_id: u32,
-    source_item_id: Option<u32>,
-    action_name: &str,
-) -> bool {
-    let Some(ammunition) = attack_required_ammunition(ctx, attacker_id, source_item_id) else {
-        return true;
+/// Start a ruleset quest for the entity. Failed quests can be started again.
+pub(crate) fn start_quest(ctx: &mut RegionCtx, entity_id: u32, quest_id: &str) -> bool {
+    let Some(quest) = ctx.resolved_quest(quest_id) else {
+        return false;
     };
-    let Some(attacker) = ctx
-        .map
-        .entities
-        .iter()
-        .find(|entity| entity.id == attacker_id)
-    else {
+    let Some(entity) = get_entity_mut(&mut ctx.map, entity_id) else {
         return false;
     };
-    if entity_inventory_item_count(attacker, &ammunition.item_id) >= ammunition.quantity {
-        return true;
+    if entity
+        .quests
+        .get(&quest.id)
+        .is_some_and(|progress| progress.status != QuestStatus::Failed)
+    {
+        return false;
     }
-    send_ruleset_message(
+    entity
+        .quests
+        .quests
+        .insert(quest.id.clone(), quest_stage_progress(&quest, 0));
+    entity.mark_quests_dirty();
+
+    let text = quest_message(ctx, "started", &[("quest", quest.name.clone())]);
+    send_quest_event(ctx, entity_id, &quest.id, QuestEventKind::Started, text);
+    refresh_quest_state_objectives(ctx, entity_id);
+    true
+}
+
+/// Move an active quest to its next stage, completing it after the last one.
+pub(crate) fn advance_quest(ctx: &mut RegionCtx, entity_id: u32, quest_id: &str) -> bool {
+    let Some(quest) = ctx.resolved_quest(quest_id) else {
+        return false;
+    };
+    let Some(stage) = active_quest_stage(ctx, entity_id, &quest.id) else {
+        return false;
+    };
+    if stage + 1 >= quest.stages.len() {
+        return complete_quest(ctx, entity_id, &quest.id);
+    }
+    let progress = quest_stage_progress(&quest, stage + 1);
+    let stage_text = progress
+        .stage_description
+        .clone()
+        .unwrap_or_else(|| progress.stage_id.clone());
+    if let Some(entity) = get_entity_mut(&mut ctx.map, entity_id) {
+        entity.quests.quests.insert(quest.id.clone(), progress);
+        entity.mark_quests_dirty();
+    }
+
+    let text = quest_message(
         ctx,
-        attacker_id,
-        "actions",
-        "missing_item",
-        "actions.missing_item",
-        &[
-            ("item", ammunition.item_id.replace('_', " ")),
-            ("action", action_name.to_string()),
-        ],
-        "warning",
+        "advanced",
+        &[("quest", quest.name.clone()), ("stage", stage_text)],
     );
-    false
+    send_quest_event(ctx, entity_id, &quest.id, QuestEventKind::Advanced, text);
+    refresh_quest_state_objectives(ctx, entity_id);
+    true
 }
 
-pub(crate) fn consume_attack_ammunition_for_source(
-    ctx: &mut RegionCtx,
-    attacker_id: u32,
-    source_item_id: Option<u32>,
-) -> bool {
-    let Some(ammunition) = attack_required_ammunition(ctx, attacker_id, source_item_id) else {
-        return true;
-    };
-    let Some(attacker) = ctx
-        .map
-        .entities
-        .iter_mut()
-        .find(|entity| entity.id == attacker_id)
-    else {
+/// Complete an active quest: consume collected items marked `consume` and
+/// grant the rewards through the regular currency, item, and XP paths.
+pub(crate) fn complete_quest(ctx: &mut RegionCtx, entity_id: u32, quest_id: &str) -> bool {
+    let Some(quest) = ctx.resolved_quest(quest_id) else {
         return false;
     };
-    let mut remaining = ammunition.quantity;
-    for slot in 0..attacker.inventory.len() {
-        if remaining == 0 {
-            break;
-        }
-        let matches = attacker
-            .inventory
-            .get(slot)
-            .and_then(|item| item.as_ref())
-            .is_some_and(|item| ruleset_item_matches_id(item, &ammunition.item_id));
-        if !matches {
-            continue;
-        }
+    if active_quest_stage(ctx, entity_id, &quest.id).is_none() {
+        return false;
+    }
 
-        let quantity = attacker
-            .inventory
-            .get(slot)
-            .and_then(|item| item.as_ref())
-            .map(Item::stack_quantity)
-            .unwrap_or(1)
-            .max(0) as usize;
-        if quantity == 0 {
-            let _ = attacker.remove_item_from_slot(slot);
-            continue;
-        }
-        if quantity > remaining {
-            if let Some(Some(item)) = attacker.inventory.get_mut(slot) {
-                item.set_stack_quantity((quantity - remaining) as i32);
-            }
-            attacker.dirty_flags |= 0b1000;
-            remaining = 0;
+    let consumes = quest
+        .stages
+        .iter()
+        .flat_map(|stage| stage.objectives.iter())
+        .filter(|objective| {
+            objective.kind == ResolvedQuestObjectiveKind::Collect && objective.consume
+        })
+        .map(|objective| (objective.target.clone(), objective.count as usize))
+        .collect::<Vec<_>>();
+    let mut reward_items = vec![];
+    for (item_id, quantity) in &quest.rewards.items {
+        if let Some(item) = ruleset_item_from_table(&ctx.rules, item_id, *quantity as usize) {
+            reward_items.push(item);
         } else {
-            let _ = attacker.remove_item_from_slot(slot);
-            remaining = remaining.saturating_sub(quantity);
+            // Project item classes are not stackable, create one item each.
+            for _ in 0..*quantity {
+                if let Some(item) = ctx.create_item(item_id.clone()) {
+                    reward_items.push(item);
+                }
+            }
         }
     }
-    remaining == 0
-}
 
-fn consume_action_items(ctx: &mut RegionCtx, entity_id: u32, consumes: &[(String, usize)]) {
-    if consumes.is_empty() {
-        return;
+    let currencies = ctx.currencies.clone();
+    let Some(entity) = get_entity_mut(&mut ctx.map, entity_id) else {
+        return false;
+    };
+    consume_entity_items(entity, &consumes);
+    for item in reward_items {
+        let _ = entity.add_item(item);
     }
-    if let Some(entity) = ctx
-        .map
-        .entities
-        .iter_mut()
-        .find(|entity| entity.id == entity_id)
-    {
-        consume_entity_items(entity, consumes);
+    if quest.rewards.currency > 0 {
+        let _ = entity.add_base_currency(quest.rewards.currency, &currencies);
     }
-}
-
-fn table_string_array_contains(table: &toml::value::Table, key: &str, needle: &str) -> bool {
-    table
-        .get(key)
-        .and_then(toml::Value::as_array)
-        .is_some_and(|values| {
-            values
-                .iter()
-                .filter_map(toml::Value::as_str)
-                .any(|value| value.trim() == needle)
-        })
-}
+    if let Some(progress) = entity.quests.get_mut(&quest.id) {
+        progress.status = QuestStatus::Completed;
+    }
+    entity.mark_quests_dirty();
 
-fn ruleset_ability_table(ctx: &RegionCtx, ability_id: &str) -> Option<toml::value::Table> {
-    ctx.rules
-        .get("abilities")
-        .and_then(toml::Value::as_table)
-        .and_then(|abilities| abilities.get(ability_id))
-        .and_then(toml::Value::as_table)
-        .cloned()
+    let text = quest_message(ctx, "completed", &[("quest", quest.name.clone())]);
+    send_quest_event(ctx, entity_id, &quest.id, QuestEventKind::Completed, text);
+    grant_experience(ctx, entity_id, quest.rewards.xp);
+    true
 }
 
-fn entity_ability_attr_contains(entity: &Entity, ability_id: &str) -> bool {
-    match entity.attributes.get("abilities") {
-        Some(Value::StrArray(values)) => values.iter().any(|value| value.trim() == ability_id),
-        Some(Value::Str(value)) => value
-            .split(',')
-            .map(str::trim)
-            .any(|value| value == ability_id),
-        _ => false,
+pub(crate) fn fail_quest(ctx: &mut RegionCtx, entity_id: u32, quest_id: &str) -> bool {
+    if active_quest_stage(ctx, entity_id, quest_id).is_none() {
+        return false;
     }
-}
-
-fn class_unlocks_ability(class: &toml::value::Table, ability_id: &str, level: f32) -> bool {
-    let Some(unlocks) = class.get("unlocks").and_then(toml::Value::as_table) else {
+    let Some(entity) = get_entity_mut(&mut ctx.map, entity_id) else {
         return false;
     };
-    for (key, value) in unlocks {
-        let Some(unlock_level) = key
+    let Some(progress) = entity.quests.get_mut(quest_id) else {
+        return false;
+    };
+    progress.status = QuestStatus::Failed;
+    let name = progress.name.clone();
+    entity.mark_quests_dirty();
+
+    let text = quest_message(ctx, "failed", &[("quest", name)]);
+    send_quest_event(ctx, entity_id, quest_id, QuestEventKind::Failed, text);
+    true
+}
+
+/// The quest status ("active", "completed", "failed"), or "" if not started.
+pub(crate) fn quest_status(ctx: &RegionCtx, entity_id: u32, quest_id: &str) -> String {
+    ctx.map
+        .entities
+        .iter()
+        .find(|entity| entity.id == entity_id)
+        .and_then(|entity| entity.quests.get(quest_id))
+        .map(|quest| quest.status.as_str().to_string())
+        .unwrap_or_default()
+}
+
+/// The id of the current stage of an active quest, or "".
+pub(crate) fn quest_stage(ctx: &RegionCtx, entity_id: u32, quest_id: &str) -> String {
+    ctx.map
+        .entities
+        .iter()
+        .find(|entity| entity.id == entity_id)
+        .and_then(|entity| entity.quests.get(quest_id))
+        .filter(|quest| quest.status == QuestStatus::Active)
+        .map(|quest| quest.stage_id.clone())
+        .unwrap_or_default()
+}
+
+/// Update the objectives of the current stage of all active quests of the
+/// entity. `progress` returns the new count of an objective. Quests whose
+/// stage is done advance.
+fn update_quest_objectives<F>(ctx: &mut RegionCtx, entity_id: u32, mut progress: F)
+where
+    F: FnMut(&Entity, &ResolvedQuestObjective, u32) -> u32,
+{
+    let Some(entity) = ctx
+        .map
+        .entities
+        .iter()
+        .find(|entity| entity.id == entity_id)
+    else {
+        return;
+    };
+    let mut updates = vec![];
+    for quest in entity.quests.quests.values() {
+        if quest.status != QuestStatus::Active {
+            continue;
+        }
+        let Some(definition) = ctx.resolved_quest(&quest.id) else {
+            continue;
+        };
+        let Some(stage) = definition.stages.get(quest.stage) else {
+            continue;
+        };
+        let counts = stage
+            .objectives
+            .iter()
+            .zip(&quest.objectives)
+            .map(|(objective, state)| {
+                progress(entity, objective, state.current).min(state.required)
+            })
+            .collect::<Vec<_>>();
+        if counts
+            .iter()
+            .zip(&quest.objectives)
+            .any(|(count, state)| *count != state.current)
+        {
+            updates.push((quest.id.clone(), counts));
+        }
+    }
+    if updates.is_empty() {
+        return;
+    }
+
+    let mut messages = vec![];
+    let mut finished = vec![];
+    if let Some(entity) = get_entity_mut(&mut ctx.map, entity_id) {
+        for (quest_id, counts) in updates {
+            let Some(quest) = entity.quests.get_mut(&quest_id) else {
+                continue;
+            };
+            for (state, count) in quest.objectives.iter_mut().zip(counts) {
+                if count > state.current {
+                    messages.push((
+                        quest_id.clone(),
+                        vec![
+                            ("quest", quest.name.clone()),
+                            ("objective", state.label.clone()),
+                            ("current", count.to_string()),
+                            ("required", state.required.to_string()),
+                        ],
+                    ));
+                }
+                state.current = count;
+            }
+            if quest.stage_done() {
+                finished.push(quest_id);
+            }
+        }
+        entity.mark_quests_dirty();
+    }
+
+    for (quest_id, params) in messages {
+        let text = quest_message(ctx, "objective", &params);
+        send_quest_event(ctx, entity_id, &quest_id, QuestEventKind::Progress, text);
+    }
+    for quest_id in finished {
+        advance_quest(ctx, entity_id, &quest_id);
+    }
+}
+
+/// Names an entity can be referenced by in kill and talk objectives: its
+/// character class and its name.
+fn quest_target_names(ctx: &RegionCtx, entity_id: u32) -> Vec<String> {
+    let mut names = vec![];
+    if let Some(class_name) = ctx.entity_classes.get(&entity_id) {
+        names.push(class_name.clone());
+    }
+    if let Some(name) = ctx
+        .map
+        .entities
+        .iter()
+        .find(|entity| entity.id == entity_id)
+        .and_then(|entity| entity.attributes.get_str("name"))
+    {
+        names.push(name.to_string());
+    }
+    names
+}
+
+/// Count kill and talk objectives from the entity's `kill` and `intent` events.
+pub(crate) fn observe_quest_event(
+    ctx: &mut RegionCtx,
+    entity_id: u32,
+    event: &str,
+    value: &VMValue,
+) {
+    let has_active = ctx
+        .map
+        .entities
+        .iter()
+        .any(|entity| entity.id == entity_id && entity.quests.has_active());
+    if !has_active {
+        return;
+    }
+    let kind = match event {
+        "kill" => ResolvedQuestObjectiveKind::Kill,
+        "intent"
+            if value
+                .as_string()
+                .is_some_and(|intent| intent.trim().eq_ignore_ascii_case("talk")) =>
+        {
+            ResolvedQuestObjectiveKind::Talk
+        }
+        _ => return,
+    };
+    let names = quest_target_names(ctx, value.x as u32);
+    update_quest_objectives(ctx, entity_id, |_, objective, current| {
+        let matches = objective.kind == kind
+            && names
+                .iter()
+                .any(|name| name.trim().eq_ignore_ascii_case(&objective.target));
+        if !matches {
+            current
+        } else if kind == ResolvedQuestObjectiveKind::Kill {
+            current + 1
+        } else {
+            objective.count
+        }
+    });
+}
+
+/// Refresh the objectives which track entity state: collected items are
+/// counted from the inventory and reached sectors stay reached.
+fn refresh_quest_state_objectives(ctx: &mut RegionCtx, entity_id: u32) {
+    update_quest_objectives(
+        ctx,
+        entity_id,
+        |entity, objective, current| match objective.kind {
+            ResolvedQuestObjectiveKind::Collect => {
+                entity_inventory_item_count(entity, &objective.target).min(u32::MAX as usize) as u32
+            }
+            ResolvedQuestObjectiveKind::Reach => {
+                let sector = entity.attributes.get_str("sector").unwrap_or_default();
+                if sector.trim().eq_ignore_ascii_case(&objective.target) {
+                    objective.count
+                } else {
+                    current
+                }
+            }
+            _ => current,
+        },
+    );
+}
+
+pub(crate) fn update_quest_progress(ctx: &mut RegionCtx) {
+    let entity_ids = ctx
+        .map
+        .entities
+        .iter()
+        .filter(|entity| entity.quests.has_active())
+        .map(|entity| entity.id)
+        .collect::<Vec<_>>();
+    for entity_id in entity_ids {
+        refresh_quest_state_objectives(ctx, entity_id);
+    }
+}
+
+/// Why a trade did not go through.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum TradeError {
+    NoVendor,
+    NotForSale,
+    NotWanted,
+    ItemNotFound,
+    CantAfford,
+    VendorCantAfford,
+    InventoryFull,
+}
+
+impl TradeError {
+    fn message_name(self) -> &'static str {
+        match self {
+            TradeError::NoVendor => "no_vendor",
+            TradeError::NotForSale => "not_for_sale",
+            TradeError::NotWanted => "not_wanted",
+            TradeError::ItemNotFound => "item_not_found",
+            TradeError::CantAfford => "cant_afford",
+            TradeError::VendorCantAfford => "vendor_cant_afford",
+            TradeError::InventoryFull => "inventory_full",
+        }
+    }
+}
+
+/// A completed trade: the item name, the quantity, and the total price in
+/// base currency.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Trade {
+    pub item: String,
+    pub quantity: u32,
+    pub price: i64,
+}
+
+/// The shop of a vendor, from its `shop` attribute.
+pub(crate) fn entity_shop(
+    ctx: &RegionCtx,
+    vendor_id: u32,
+) -> Option<eldiron_ruleset::ResolvedShop> {
+    let shop_id = ctx
+        .map
+        .entities
+        .iter()
+        .find(|entity| entity.id == vendor_id)?
+        .attributes
+        .get_str("shop")?
+        .trim()
+        .to_string();
+    eldiron_ruleset::resolve_shop(&ctx.rules, &shop_id)
+        .ok()
+        .flatten()
+}
+
+fn shop_restock_due(ctx: &RegionCtx, vendor_id: u32) -> bool {
+    match ctx
+        .entity_state_data
+        .get(&vendor_id)
+        .and_then(|state| state.get("shop_restock_tick"))
+    {
+        Some(Value::Int64(tick)) => ctx.ticks >= *tick,
+        Some(_) => false,
+        None => true,
+    }
+}
+
+/// Fill up the stock and funds of every vendor whose restock time has come.
+/// Vendors stock up on the first tick; shops without `restock_minutes` never
+/// restock after that.
+pub(crate) fn restock_shops(ctx: &mut RegionCtx) {
+    let vendor_ids = ctx
+        .map
+        .entities
+        .iter()
+        .filter(|entity| entity.attributes.get_str("shop").is_some())
+        .map(|entity| entity.id)
+        .collect::<Vec<_>>();
+    for vendor_id in vendor_ids {
+        if !shop_restock_due(ctx, vendor_id) {
+            continue;
+        }
+        let Some(shop) = entity_shop(ctx, vendor_id) else {
+            continue;
+        };
+        restock_shop(ctx, vendor_id, &shop);
+        let next_tick = if shop.restock_minutes > 0.0 {
+            ctx.ticks + (shop.restock_minutes * ctx.ticks_per_minute as f32).max(1.0) as i64
+        } else {
+            i64::MAX
+        };
+        ctx.entity_state_data
+            .entry(vendor_id)
+            .or_default()
+            .set("shop_restock_tick", Value::Int64(next_tick));
+    }
+}
+
+fn restock_shop(ctx: &mut RegionCtx, vendor_id: u32, shop: &eldiron_ruleset::ResolvedShop) {
+    let Some(vendor) = ctx
+        .map
+        .entities
+        .iter()
+        .find(|entity| entity.id == vendor_id)
+    else {
+        return;
+    };
+    let missing = shop
+        .stock
+        .iter()
+        .map(|stock| {
+            let have = entity_inventory_item_count(vendor, &stock.item);
+            (
+                stock.item.clone(),
+                (stock.quantity as usize).saturating_sub(have),
+            )
+        })
+        .filter(|(_, missing)| *missing > 0)
+        .collect::<Vec<_>>();
+
+    let mut items = vec![];
+    for (item_id, quantity) in missing {
+        if let Some(item) = ruleset_item_from_table(&ctx.rules, &item_id, quantity) {
+            items.push(item);
+        } else {
+            for _ in 0..quantity {
+                if let Some(item) = ctx.create_item(item_id.clone()) {
+                    items.push(item);
+                }
+            }
+        }
+    }
+
+    let currencies = ctx.currencies.clone();
+    let Some(vendor) = get_entity_mut(&mut ctx.map, vendor_id) else {
+        return;
+    };
+    for item in items {
+        let _ = vendor.add_item(item);
+    }
+    if let Some(funds) = shop.funds {
+        let balance = vendor.wallet.get_balance(&currencies);
+        if balance < funds {
+            let _ = vendor.add_base_currency(funds - balance, &currencies);
+        }
+    }
+}
+
+fn disposition_value(ctx: &RegionCtx, disposition: &str) -> f32 {
+    ctx.rules
+        .get("dispositions")
+        .and_then(toml::Value::as_table)
+        .and_then(|dispositions| dispositions.get(disposition))
+        .map(|value| progression_number(Some(value), 0.0))
+        .unwrap_or(match disposition {
+            "friendly" => 1.0,
+            "hostile" => -1.0,
+            _ => 0.0,
+        })
+}
+
+/// The price of one unit of an item, in base currency. `buying` is from the
+/// customer's side: true when the customer buys from the vendor. `shop` is the
+/// vendor's resolved shop, see `entity_shop`.
+pub(crate) fn trade_unit_price(
+    ctx: &RegionCtx,
+    shop: Option<&eldiron_ruleset::ResolvedShop>,
+    vendor_id: u32,
+    customer_id: u32,
+    item: &Item,
+    buying: bool,
+) -> i64 {
+    let worth = item.attributes.get_float_default("worth", 0.0).max(0.0);
+    let (key, default) = if buying {
+        ("buy_price", "worth")
+    } else {
+        ("sell_price", "worth / 2")
+    };
+    let formula = shop
+        .map(|shop| {
+            if buying {
+                shop.buy_price.clone()
+            } else {
+                shop.sell_price.clone()
+            }
+        })
+        .or_else(|| {
+            ctx.rules
+                .get("economy")
+                .and_then(toml::Value::as_table)
+                .and_then(|economy| economy.get("trade"))
+                .and_then(toml::Value::as_table)
+                .and_then(|trade| rule_string(trade, key))
+                .map(str::to_string)
+        })
+        .unwrap_or_else(|| default.to_string());
+
+    let vendor = ctx
+        .map
+        .entities
+        .iter()
+        .find(|entity| entity.id == vendor_id);
+    let customer = ctx
+        .map
+        .entities
+        .iter()
+        .find(|entity| entity.id == customer_id);
+    let price = evaluate_formula(&formula, |name| match name {
+        "worth" => worth,
+        "disposition" => vendor
+            .zip(customer)
+            .map(|(vendor, customer)| {
+                disposition_value(ctx, &entity_disposition(ctx, vendor, customer))
+            })
+            .unwrap_or(0.0),
+        "reputation" => vendor
+            .zip(customer)
+            .map(|(vendor, customer)| {
+                reputation_for_target_race(ctx, vendor, &entity_race_for_rules(ctx, customer))
+            })
+            .unwrap_or(0.0),
+        _ => customer
+            .map(|customer| resolve_progression_var(ctx, customer, name))
+            .unwrap_or(0.0),
+    })
+    .unwrap_or(worth)
+    .max(0.0);
+
+    let price = if buying {
+        let price = price.ceil() as i64;
+        if worth > 0.0 { price.max(1) } else { price }
+    } else {
+        price.floor() as i64
+    };
+    match shop.and_then(|shop| shop.currency.as_deref()) {
+        Some(currency) => ctx.currencies.round_base_amount_to(price, currency, buying),
+        None => price,
+    }
+}
+
+/// True if the item is in the list of item ids, categories, or slots.
+fn item_matches_trade_list(item: &Item, list: &[String]) -> bool {
+    list.iter().any(|entry| {
+        ruleset_item_matches_id(item, entry)
+            || ["category", "slot"].iter().any(|key| {
+                item.attributes
+                    .get_str(key)
+                    .is_some_and(|value| value.trim().eq_ignore_ascii_case(entry))
+            })
+    })
+}
+
+/// Find an inventory item by name or ruleset id.
+fn find_trade_item(entity: &Entity, query: &str) -> Option<u32> {
+    let query = query.trim();
+    let normalized = crate::text_command::normalize_ruleset_id(query);
+    entity
+        .iter_inventory()
+        .find(|(_, item)| {
+            ruleset_item_matches_id(item, query)
+                || item
+                    .attributes
+                    .get_str("ruleset_id")
+                    .is_some_and(|id| id.eq_ignore_ascii_case(&normalized))
+                || item
+                    .attributes
+                    .get_str("name")
+                    .is_some_and(|name| name.trim().eq_ignore_ascii_case(query))
+        })
+        .map(|(_, item)| item.id)
+}
+
+/// The number of units of an item which can be traded: the stack size of a
+/// stackable item, otherwise one.
+fn trade_units_of(item: &Item) -> u32 {
+    if item.is_stackable() {
+        item.stack_quantity().max(1) as u32
+    } else {
+        1
+    }
+}
+
+/// True if `units` units of the item fit into the inventory, either into a
+/// free slot or onto stacks of the same item.
+fn inventory_has_room(entity: &Entity, item: &Item, units: u32) -> bool {
+    if entity.inventory.iter().any(Option::is_none) {
+        return true;
+    }
+    item.is_stackable()
+        && entity
+            .inventory
+            .iter()
+            .flatten()
+            .filter(|existing| existing.can_stack_with(item))
+            .map(|existing| (existing.max_stack() - existing.stack_quantity()).max(0) as u32)
+            .sum::<u32>()
+            >= units
+}
+
+/// Take up to `quantity` units of an item out of an inventory, splitting
+/// stacks. Returns the removed item.
+fn take_trade_units(entity: &mut Entity, item_id: u32, quantity: u32) -> Option<Item> {
+    let item = entity.get_item(item_id)?;
+    let available = trade_units_of(item);
+    if quantity >= available {
+        return entity.remove_item(item_id);
+    }
+    let mut units = item.clone();
+    units.id = get_global_id();
+    units.set_stack_quantity(quantity as i32);
+    let item = entity.get_item_mut(item_id)?;
+    item.set_stack_quantity((available - quantity) as i32);
+    entity.dirty_flags |= 0b1000;
+    Some(units)
+}
+
+fn item_trade_name(item: &Item) -> String {
+    item.attributes
+        .get_str("name")
+        .map(str::to_string)
+        .unwrap_or_else(|| item.item_type.clone())
+}
+
+/// The customer buys units of an item from the vendor's inventory.
+pub(crate) fn buy_item_from_vendor(
+    ctx: &mut RegionCtx,
+    customer_id: u32,
+    vendor_id: u32,
+    item_id: u32,
+    quantity: u32,
+) -> Result<Trade, TradeError> {
+    let unit_price = {
+        let vendor = ctx
+            .map
+            .entities
+            .iter()
+            .find(|entity| entity.id == vendor_id)
+            .ok_or(TradeError::NoVendor)?;
+        let item = vendor.get_item(item_id).ok_or(TradeError::NotForSale)?;
+        let shop = entity_shop(ctx, vendor_id);
+        trade_unit_price(ctx, shop.as_ref(), vendor_id, customer_id, item, true)
+    };
+    let currencies = ctx.currencies.clone();
+    let vendor = get_entity_mut(&mut ctx.map, vendor_id).ok_or(TradeError::NoVendor)?;
+    let available = vendor
+        .get_item(item_id)
+        .map(trade_units_of)
+        .ok_or(TradeError::NotForSale)?;
+    let quantity = quantity.clamp(1, available);
+    let price = unit_price * quantity as i64;
+
+    let can_afford = ctx
+        .map
+        .entities
+        .iter()
+        .find(|entity| entity.id == customer_id)
+        .is_some_and(|customer| customer.wallet.can_afford(price, &currencies));
+    if !can_afford {
+        return Err(TradeError::CantAfford);
+    }
+
+    let vendor = get_entity_mut(&mut ctx.map, vendor_id).ok_or(TradeError::NoVendor)?;
+    let units = take_trade_units(vendor, item_id, quantity).ok_or(TradeError::NotForSale)?;
+    let name = item_trade_name(&units);
+    let customer = get_entity_mut(&mut ctx.map, customer_id).ok_or(TradeError::NoVendor)?;
+    if customer.add_item(units.clone()).is_err() {
+        if let Some(vendor) = get_entity_mut(&mut ctx.map, vendor_id) {
+            let _ = vendor.add_item(units);
+        }
+        return Err(TradeError::InventoryFull);
+    }
+    let _ = customer.spend_currency(price, &currencies);
+    if let Some(vendor) = get_entity_mut(&mut ctx.map, vendor_id) {
+        let _ = vendor.add_base_currency(price, &currencies);
+    }
+    Ok(Trade {
+        item: name,
+        quantity,
+        price,
+    })
+}
+
+/// The customer sells units of an item to the vendor.
+pub(crate) fn sell_item_to_vendor(
+    ctx: &mut RegionCtx,
+    customer_id: u32,
+    vendor_id: u32,
+    item_id: u32,
+    quantity: u32,
+) -> Result<Trade, TradeError> {
+    let shop = entity_shop(ctx, vendor_id).ok_or(TradeError::NoVendor)?;
+    let (unit_price, available) = {
+        let customer = ctx
+            .map
+            .entities
+            .iter()
+            .find(|entity| entity.id == customer_id)
+            .ok_or(TradeError::ItemNotFound)?;
+        let item = customer.get_item(item_id).ok_or(TradeError::ItemNotFound)?;
+        if !item_matches_trade_list(item, &shop.buys) {
+            return Err(TradeError::NotWanted);
+        }
+        (
+            trade_unit_price(ctx, Some(&shop), vendor_id, customer_id, item, false),
+            trade_units_of(item),
+        )
+    };
+    let quantity = quantity.clamp(1, available);
+    let price = unit_price * quantity as i64;
+    let currencies = ctx.currencies.clone();
+    if shop.funds.is_some()
+        && !ctx
+            .map
+            .entities
+            .iter()
+            .find(|entity| entity.id == vendor_id)
+            .is_some_and(|vendor| vendor.wallet.can_afford(price, &currencies))
+    {
+        return Err(TradeError::VendorCantAfford);
+    }
+
+    // Bought goods become part of the vendor's stock, so the vendor needs room.
+    let has_room = ctx
+        .map
+        .entities
+        .iter()
+        .find(|entity| entity.id == customer_id)
+        .and_then(|customer| customer.get_item(item_id))
+        .zip(
+            ctx.map
+                .entities
+                .iter()
+                .find(|entity| entity.id == vendor_id),
+        )
+        .is_some_and(|(item, vendor)| inventory_has_room(vendor, item, quantity));
+    if !has_room {
+        return Err(TradeError::InventoryFull);
+    }
+
+    let customer = get_entity_mut(&mut ctx.map, customer_id).ok_or(TradeError::ItemNotFound)?;
+    let units = take_trade_units(customer, item_id, quantity).ok_or(TradeError::ItemNotFound)?;
+    let name = item_trade_name(&units);
+    let _ = customer.add_base_currency(price, &currencies);
+    if let Some(vendor) = get_entity_mut(&mut ctx.map, vendor_id) {
+        let _ = vendor.add_item(units);
+        if shop.funds.is_some() {
+            let _ = vendor.spend_currency(price, &currencies);
+        }
+    }
+    Ok(Trade {
+        item: name,
+        quantity,
+        price,
+    })
+}
+
+/// The vendor's wares as (item id, name, quantity, unit price).
+pub(crate) fn shop_listing(
+    ctx: &RegionCtx,
+    vendor_id: u32,
+    customer_id: u32,
+) -> Vec<(u32, String, u32, i64)> {
+    let Some(vendor) = ctx
+        .map
+        .entities
+        .iter()
+        .find(|entity| entity.id == vendor_id)
+    else {
+        return vec![];
+    };
+    let shop = entity_shop(ctx, vendor_id);
+    vendor
+        .iter_inventory()
+        .map(|(_, item)| {
+            (
+                item.id,
+                item_trade_name(item),
+                trade_units_of(item),
+                trade_unit_price(ctx, shop.as_ref(), vendor_id, customer_id, item, true),
+            )
+        })
+        .collect()
+}
+
+/// The closest vendor with a shop within talking distance of the customer,
+/// optionally matching a name or class.
+fn nearby_shop_vendor(ctx: &RegionCtx, customer_id: u32, name: Option<&str>) -> Option<u32> {
+    let customer = ctx
+        .map
+        .entities
+        .iter()
+        .find(|entity| entity.id == customer_id)?;
+    let position = customer.get_pos_xz();
+    ctx.map
+        .entities
+        .iter()
+        .filter(|entity| entity.id != customer_id && entity.attributes.get_str("shop").is_some())
+        .filter(|entity| {
+            name.is_none_or(|name| {
+                ["name", "class_name"].iter().any(|key| {
+                    entity
+                        .attributes
+                        .get_str(key)
+                        .is_some_and(|value| value.trim().eq_ignore_ascii_case(name.trim()))
+                })
+            })
+        })
+        .filter_map(|entity| {
+            let distance = position.distance(entity.get_pos_xz());
+            let reach = entity_intent_distance_limit(ctx, entity.id, "talk")
+                .unwrap_or(2.0)
+                .max(0.0);
+            (distance <= reach).then_some((entity.id, distance))
+        })
+        .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal))
+        .map(|(id, _)| id)
+}
+
+fn send_trade_feedback(
+    ctx: &RegionCtx,
+    customer_id: u32,
+    vendor_id: Option<u32>,
+    result: Result<Trade, TradeError>,
+    bought: bool,
+) {
+    let vendor = vendor_id
+        .and_then(|id| ctx.map.entities.iter().find(|entity| entity.id == id))
+        .and_then(|vendor| vendor.attributes.get_str("name"))
+        .unwrap_or_default()
+        .to_string();
+    match result {
+        Ok(trade) => {
+            let name = if bought { "bought" } else { "sold" };
+            send_ruleset_message(
+                ctx,
+                customer_id,
+                "shops",
+                name,
+                &format!("shops.{}", name),
+                &[
+                    ("item", trade.item),
+                    ("quantity", trade.quantity.to_string()),
+                    ("price", ctx.currencies.format_base_amount(trade.price)),
+                    ("vendor", vendor),
+                ],
+                "system",
+            );
+        }
+        Err(err) => {
+            let name = err.message_name();
+            send_ruleset_message(
+                ctx,
+                customer_id,
+                "shops",
+                name,
+                &format!("shops.{}", name),
+                &[("vendor", vendor)],
+                "warning",
+            );
+        }
+    }
+}
+
+struct AttackAmmunition {
+    item_id: String,
+    quantity: usize,
+}
+
+fn attack_required_ammunition(
+    ctx: &RegionCtx,
+    attacker_id: u32,
+    source_item_id: Option<u32>,
+) -> Option<AttackAmmunition> {
+    let source_item_id = source_item_id?;
+    let attacker = ctx
+        .map
+        .entities
+        .iter()
+        .find(|entity| entity.id == attacker_id)?;
+    let source = entity_item_by_id(attacker, source_item_id)?;
+    let item_id = source
+        .attributes
+        .get_str("ammunition")
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(str::to_string)?;
+    let quantity = source
+        .attributes
+        .get_int_default("ammunition_quantity", 1)
+        .max(1) as usize;
+    Some(AttackAmmunition { item_id, quantity })
+}
+
+pub(crate) fn has_attack_ammunition_or_message(
+    ctx: &mut RegionCtx,
+    attacker_id: u32,
+    source_item_id: Option<u32>,
+    action_name: &str,
+) -> bool {
+    let Some(ammunition) = attack_required_ammunition(ctx, attacker_id, source_item_id) else {
+        return true;
+    };
+    let Some(attacker) = ctx
+        .map
+        .entities
+        .iter()
+        .find(|entity| entity.id == attacker_id)
+    else {
+        return false;
+    };
+    if entity_inventory_item_count(attacker, &ammunition.item_id) >= ammunition.quantity {
+        return true;
+    }
+    send_ruleset_message(
+        ctx,
+        attacker_id,
+        "actions",
+        "missing_item",
+        "actions.missing_item",
+        &[
+            ("item", ammunition.item_id.replace('_', " ")),
+            ("action", action_name.to_string()),
+        ],
+        "warning",
+    );
+    false
+}
+
+pub(crate) fn consume_attack_ammunition_for_source(
+    ctx: &mut RegionCtx,
+    attacker_id: u32,
+    source_item_id: Option<u32>,
+) -> bool {
+    let Some(ammunition) = attack_required_ammunition(ctx, attacker_id, source_item_id) else {
+        return true;
+    };
+    let Some(attacker) = ctx
+        .map
+        .entities
+        .iter_mut()
+        .find(|entity| entity.id == attacker_id)
+    else {
+        return false;
+    };
+    let mut remaining = ammunition.quantity;
+    for slot in 0..attacker.inventory.len() {
+        if remaining == 0 {
+            break;
+        }
+        let matches = attacker
+            .inventory
+            .get(slot)
+            .and_then(|item| item.as_ref())
+            .is_some_and(|item| ruleset_item_matches_id(item, &ammunition.item_id));
+        if !matches {
+            continue;
+        }
+
+        let quantity = attacker
+            .inventory
+            .get(slot)
+            .and_then(|item| item.as_ref())
+            .map(Item::stack_quantity)
+            .unwrap_or(1)
+            .max(0) as usize;
+        if quantity == 0 {
+            let _ = attacker.remove_item_from_slot(slot);
+            continue;
+        }
+        if quantity > remaining {
+            if let Some(Some(item)) = attacker.inventory.get_mut(slot) {
+                item.set_stack_quantity((quantity - remaining) as i32);
+            }
+            attacker.dirty_flags |= 0b1000;
+            remaining = 0;
+        } else {
+            let _ = attacker.remove_item_from_slot(slot);
+            remaining = remaining.saturating_sub(quantity);
+        }
+    }
+    remaining == 0
+}
+
+fn consume_action_items(ctx: &mut RegionCtx, entity_id: u32, consumes: &[(String, usize)]) {
+    if consumes.is_empty() {
+        return;
+    }
+    if let Some(entity) = ctx
+        .map
+        .entities
+        .iter_mut()
+        .find(|entity| entity.id == entity_id)
+    {
+        consume_entity_items(entity, consumes);
+    }
+}
+
+fn table_string_array_contains(table: &toml::value::Table, key: &str, needle: &str) -> bool {
+    table
+        .get(key)
+        .and_then(toml::Value::as_array)
+        .is_some_and(|values| {
+            values
+                .iter()
+                .filter_map(toml::Value::as_str)
+                .any(|value| value.trim() == needle)
+        })
+}
+
+fn ruleset_ability_table(ctx: &RegionCtx, ability_id: &str) -> Option<toml::value::Table> {
+    ctx.rules
+        .get("abilities")
+        .and_then(toml::Value::as_table)
+        .and_then(|abilities| abilities.get(ability_id))
+        .and_then(toml::Value::as_table)
+        .cloned()
+}
+
+fn entity_ability_attr_contains(entity: &Entity, ability_id: &str) -> bool {
+    match entity.attributes.get("abilities") {
+        Some(Value::StrArray(values)) => values.iter().any(|value| value.trim() == ability_id),
+        Some(Value::Str(value)) => value
+            .split(',')
+            .map(str::trim)
+            .any(|value| value == ability_id),
+        _ => false,
+    }
+}
+
+fn class_unlocks_ability(class: &toml::value::Table, ability_id: &str, level: f32) -> bool {
+    let Some(unlocks) = class.get("unlocks").and_then(toml::Value::as_table) else {
+        return false;
+    };
+    for (key, value) in unlocks {
+        let Some(unlock_level) = key
             .strip_prefix("level_")
             .and_then(|suffix| suffix.parse::<f32>().ok())
         else {
@@ -15605,7 +17182,7 @@ fn execute_ruleset_resource_action(
         .get_str("resource_action")
         .map(str::to_string);
     let target_respawn = target.attributes.get_float_default("respawn", 0.0);
-    let (resource_action, output, respawn) = {
+    let (resource_action, output, respawn, loot_tables) = {
         let resource = resource_table(ctx, &resource_id);
         let resource_action = resource
             .and_then(|resource| rule_string(resource, "action"))
@@ -15616,7 +17193,10 @@ fn execute_ruleset_resource_action(
             .map(|resource| rule_number(resource, "respawn", 0.0))
             .unwrap_or(target_respawn)
             .max(0.0);
-        (resource_action, output, respawn)
+        let loot_tables = resource
+            .map(eldiron_ruleset::ruleset_loot_table_refs)
+            .unwrap_or_default();
+        (resource_action, output, respawn, loot_tables)
     };
     if resource_action.is_some_and(|expected| !expected.eq_ignore_ascii_case(&action.id)) {
         send_message(ctx, actor_id, "{system.cant_do_that}".into(), "warning");
@@ -15630,7 +17210,7 @@ fn execute_ruleset_resource_action(
         return false;
     }
 
-    let Some((item_id, quantity)) = output else {
+    if output.is_none() && loot_tables.is_empty() {
         send_ruleset_message(
             ctx,
             actor_id,
@@ -15641,15 +17221,35 @@ fn execute_ruleset_resource_action(
             "system",
         );
         return false;
+    }
+    let output_item = match &output {
+        Some((item_id, quantity)) => {
+            let Some(item) = ruleset_item_from_table(&ctx.rules, item_id, *quantity) else {
+                return false;
+            };
+            Some(item)
+        }
+        None => None,
     };
-    let Some(output_item) = ruleset_item_from_table(&ctx.rules, &item_id, quantity) else {
-        return false;
-    };
-    let output_name = output_item
-        .attributes
-        .get_str("name")
-        .map(str::to_string)
-        .unwrap_or_else(|| item_id.replace('_', " "));
+    let loot_level = progression_level_for_entity(ctx, &ctx.map.entities[actor_index]) as u32;
+    let loot_items = roll_ruleset_loot_items(ctx, &loot_tables, loot_level);
+    let gathered = output_item
+        .iter()
+        .chain(&loot_items)
+        .map(|item| {
+            let name = item
+                .attributes
+                .get_str("name")
+                .map(str::to_string)
+                .unwrap_or_else(|| {
+                    item.attributes
+                        .get_str("ruleset_id")
+                        .unwrap_or_default()
+                        .replace('_', " ")
+                });
+            (name, item.stack_quantity().max(1))
+        })
+        .collect::<Vec<_>>();
 
     let consumes = action
         .item_costs
@@ -15658,10 +17258,24 @@ fn execute_ruleset_resource_action(
         .collect::<Vec<_>>();
     let mut entity = ctx.map.entities[actor_index].clone();
     consume_entity_items(&mut entity, &consumes);
-    if entity.add_item(output_item).is_err() {
+    if let Some(output_item) = output_item
+        && entity.add_item(output_item).is_err()
+    {
         return false;
     }
+    // Bonus loot that does not fit in the inventory lands at the gatherer's feet.
+    let mut dropped = Vec::new();
+    for mut item in loot_items {
+        if entity.inventory.iter().any(Option::is_none) {
+            let _ = entity.add_item(item);
+        } else {
+            item.position = entity.position;
+            item.mark_all_dirty();
+            dropped.push(item);
+        }
+    }
     ctx.map.entities[actor_index] = entity;
+    ctx.map.items.extend(dropped);
 
     let target = &mut ctx.map.items[item_index];
     target.set_attribute("resource_depleted", Value::Bool(true));
@@ -15671,19 +17285,32 @@ fn execute_ruleset_resource_action(
     }
 
     set_action_cooldown(ctx, actor_id, &action.id, action.cooldown_seconds.max(0.0));
-    send_ruleset_message(
-        ctx,
-        actor_id,
-        "actions",
-        "gathered",
-        "actions.gathered",
-        &[
-            ("item", output_name),
-            ("quantity", quantity.to_string()),
-            ("action", action.name.clone()),
-        ],
-        "success",
-    );
+    if gathered.is_empty() {
+        send_ruleset_message(
+            ctx,
+            actor_id,
+            "actions",
+            "no_effect",
+            "actions.no_effect",
+            &[("action", action.name.clone())],
+            "system",
+        );
+    }
+    for (name, quantity) in gathered {
+        send_ruleset_message(
+            ctx,
+            actor_id,
+            "actions",
+            "gathered",
+            "actions.gathered",
+            &[
+                ("item", name),
+                ("quantity", quantity.to_string()),
+                ("action", action.name.clone()),
+            ],
+            "success",
+        );
+    }
     true
 }
 
@@ -17485,7 +19112,7 @@ fn cast_spell_for_entity(
     }
 
     let success_pct = success_pct.clamp(0.0, 100.0);
-    let mut rng = rand::rng();
+    let mut rng = crate::server::rng::sim_rng();
     let roll = rng.random_range(0.0..100.0);
     if roll >= success_pct {
         ctx.to_execute_entity
@@ -17507,7 +19134,7 @@ fn cast_spell_for_entity(
     let caster_is_firstp = matches!(
         caster.attributes.get("player_camera"),
         Some(Value::PlayerCamera(
-            PlayerCamera::D3FirstP | PlayerCamera::D3FirstPGrid
+            PlayerCamera::D3FirstP | PlayerCamera::D3FirstPGrid | PlayerCamera::D3ThirdP
         ))
     );
     let target_pos = target.position;
@@ -17632,7 +19259,7 @@ fn cast_spell_for_entity_to_pos(
     }
 
     let success_pct = success_pct.clamp(0.0, 100.0);
-    let mut rng = rand::rng();
+    let mut rng = crate::server::rng::sim_rng();
     let roll = rng.random_range(0.0..100.0);
     if roll >= success_pct {
         ctx.to_execute_entity
@@ -17651,7 +19278,7 @@ fn cast_spell_for_entity_to_pos(
     let caster_is_firstp = matches!(
         caster.attributes.get("player_camera"),
         Some(Value::PlayerCamera(
-            PlayerCamera::D3FirstP | PlayerCamera::D3FirstPGrid
+            PlayerCamera::D3FirstP | PlayerCamera::D3FirstPGrid | PlayerCamera::D3ThirdP
         ))
     );
     let had_cast_height = spell_item.attributes.contains("spell_cast_height");
@@ -18071,6 +19698,10 @@ pub(crate) fn apply_damage_direct(
         }
     }
 
+    if kill {
+        add_entity_death_loot(ctx, target_id);
+    }
+
     if kill && should_autodrop {
         drop_all_items_for_entity(ctx, target_id);
     }
@@ -18122,88 +19753,6 @@ pub(crate) fn apply_damage_direct(
     applied
 }
 
-fn combat_rule_expr_from_root<'a>(
-    root: &'a toml::value::Table,
-    kind: &str,
-    key: &str,
-) -> Option<&'a str> {
-    let kind_key = if key == "incoming_damage" {
-        Some(["incoming_damage", "received_damage"])
-    } else {
-        None
-    };
-    if !kind.is_empty()
-        && let Some(expr) = root
-            .get("combat")
-            .and_then(toml::Value::as_table)
-            .and_then(|combat| combat.get("kinds"))
-            .and_then(toml::Value::as_table)
-            .and_then(|kinds| kinds.get(kind))
-            .and_then(toml::Value::as_table)
-            .and_then(|kind_table| {
-                if let Some(keys) = kind_key {
-                    keys.iter().find_map(|key| kind_table.get(*key))
-                } else {
-                    kind_table.get(key)
-                }
-            })
-            .and_then(toml::Value::as_str)
-    {
-        return Some(expr);
-    }
-    root.get("combat")
-        .and_then(toml::Value::as_table)
-        .and_then(|combat| {
-            if let Some(keys) = kind_key {
-                keys.iter().find_map(|key| combat.get(*key))
-            } else {
-                combat.get(key)
-            }
-        })
-        .and_then(toml::Value::as_str)
-}
-
-fn combat_rule_expr<'a>(ctx: &'a RegionCtx, kind: &str, key: &str) -> Option<&'a str> {
-    combat_rule_expr_from_root(&ctx.rules, kind, key)
-}
-
-fn combat_rule_exprs<'a>(
-    ctx: &'a RegionCtx,
-    attacker: Option<&Entity>,
-    kind: &str,
-    key: &str,
-) -> Vec<&'a str> {
-    let mut exprs = Vec::new();
-    if let Some(expr) = combat_rule_expr(ctx, kind, key) {
-        exprs.push(expr);
-    }
-    if let Some(attacker) = attacker
-        && let Some(root) = race_rule_root(ctx, attacker)
-        && let Some(expr) = combat_rule_expr_from_root(root, kind, key)
-    {
-        exprs.push(expr);
-    }
-    if let Some(attacker) = attacker
-        && let Some(root) = class_rule_root(ctx, attacker)
-        && let Some(expr) = combat_rule_expr_from_root(root, kind, key)
-    {
-        exprs.push(expr);
-    }
-    exprs
-}
-
-fn combat_kind_table_from_root<'a>(
-    root: &'a toml::value::Table,
-    kind: &str,
-) -> Option<&'a toml::value::Table> {
-    root.get("combat")
-        .and_then(toml::Value::as_table)
-        .and_then(|combat| combat.get("kinds"))
-        .and_then(toml::Value::as_table)
-        .and_then(|kinds| kinds.get(kind))
-        .and_then(toml::Value::as_table)
-}
-
 fn active_locale(ctx: &RegionCtx) -> &str {
     let configured = ctx
         .config
@@ -19557,7 +21106,7 @@ fn progression_kill_xp(ctx: &RegionCtx, from_id: u32, target_id: u32) -> i32 {
 
     let mut current_value = 0.0;
     for expr in exprs {
-        let Some(parsed) = evaluate_formula(expr, |name| {
+        let Ok(parsed) = evaluate_formula(expr, |name| {
             resolve_combat_var(ctx, name, current_value, attacker, defender, None)
         }) else {
             return 0;
@@ -19851,111 +21400,95 @@ fn armor_equipped_attr(ctx: &RegionCtx, entity: &Entity, attr: &str) -> f32 {
         .sum()
 }
 
-fn resolve_combat_var(
-    ctx: &RegionCtx,
-    name: &str,
-    value: f32,
-    attacker: Option<&Entity>,
-    defender: Option<&Entity>,
-    source_item: Option<&Item>,
-) -> f32 {
-    if name == "value" {
-        return value;
-    }
-    if let Some(attr) = name.strip_prefix("attacker.source.") {
-        return source_item.map_or(0.0, |item| item.attributes.get_float_default(attr, 0.0));
-    }
-    if let Some(attr) = name.strip_prefix("source.") {
-        return source_item.map_or(0.0, |item| item.attributes.get_float_default(attr, 0.0));
-    }
-    if let Some(attr) = name.strip_prefix("attacker.equipped.") {
-        return attacker.map_or(0.0, |entity| all_equipped_attr(entity, attr));
-    }
-    if let Some(attr) = name.strip_prefix("defender.equipped.") {
-        return defender.map_or(0.0, |entity| all_equipped_attr(entity, attr));
-    }
-    if let Some(attr) = name.strip_prefix("equipped.") {
-        return attacker.map_or(0.0, |entity| all_equipped_attr(entity, attr));
-    }
-    if let Some(attr) = name.strip_prefix("attacker.armor.") {
-        return attacker.map_or(0.0, |entity| armor_equipped_attr(ctx, entity, attr));
-    }
-    if let Some(attr) = name.strip_prefix("defender.armor.") {
-        return defender.map_or(0.0, |entity| armor_equipped_attr(ctx, entity, attr));
+/// The server's view of an entity for the shared combat rules.
+struct RegionCombatant<'a> {
+    ctx: &'a RegionCtx,
+    entity: &'a Entity,
+}
+
+impl Combatant for RegionCombatant<'_> {
+    fn attribute(&self, id: &str, default: f32) -> f32 {
+        effective_entity_attribute(self.ctx, self.entity, id, default)
     }
-    if let Some(attr) = name.strip_prefix("armor.") {
-        return defender.map_or(0.0, |entity| armor_equipped_attr(ctx, entity, attr));
+
+    fn weapon_attribute(&self, id: &str) -> f32 {
+        equipped_attr(self.ctx, self.entity, id)
     }
-    if let Some(attr) = name.strip_prefix("attacker.weapon.") {
-        return attacker.map_or(0.0, |entity| equipped_attr(ctx, entity, attr));
+
+    fn armor_attribute(&self, id: &str) -> f32 {
+        armor_equipped_attr(self.ctx, self.entity, id)
     }
-    if let Some(attr) = name.strip_prefix("defender.weapon.") {
-        return defender.map_or(0.0, |entity| equipped_attr(ctx, entity, attr));
+
+    fn equipped_attribute(&self, id: &str) -> f32 {
+        all_equipped_attr(self.entity, id)
     }
-    if let Some(attr) = name.strip_prefix("weapon.") {
-        return attacker.map_or(0.0, |entity| equipped_attr(ctx, entity, attr));
+
+    fn race(&self) -> Option<String> {
+        entity_rule_identity(self.entity, "race")
     }
-    if let Some(attr) = name.strip_prefix("attacker.") {
-        return attacker.map_or(0.0, |entity| {
-            let default = if attr == ctx.level_attr { 1.0 } else { 0.0 };
-            effective_entity_attribute(ctx, entity, attr, default)
-        });
+
+    fn class(&self) -> Option<String> {
+        entity_rule_identity(self.entity, "class")
     }
-    if let Some(attr) = name.strip_prefix("defender.") {
-        return defender.map_or(0.0, |entity| {
-            let default = if attr == ctx.level_attr { 1.0 } else { 0.0 };
-            effective_entity_attribute(ctx, entity, attr, default)
-        });
+
+    fn text_attribute(&self, id: &str) -> Option<String> {
+        match id {
+            "race" => self.race(),
+            "class" => self.class(),
+            _ => self.entity.attributes.get_str(id).map(str::to_string),
+        }
     }
-    0.0
 }
 
-fn structured_attribute_bonus(
+fn with_damage_context<R>(
     ctx: &RegionCtx,
-    entity: Option<&Entity>,
-    table: &toml::value::Table,
-) -> f32 {
-    let mut bonus = 0.0;
-    if let Some(attr) = rule_string(table, "bonus_attribute") {
-        let every = rule_number(table, "bonus_every", 1.0).max(1.0);
-        bonus += entity
-            .map(|entity| (effective_entity_attribute(ctx, entity, attr, 0.0) / every).floor())
-            .unwrap_or(0.0);
-    }
-    if let Some(attrs) = table
-        .get("bonus_attributes")
-        .and_then(toml::Value::as_array)
-    {
-        let every = rule_number(table, "bonus_every", 1.0).max(1.0);
-        for attr in attrs.iter().filter_map(toml::Value::as_str) {
-            bonus += entity
-                .map(|entity| (effective_entity_attribute(ctx, entity, attr, 0.0) / every).floor())
-                .unwrap_or(0.0);
-        }
-    }
-    bonus
+    attacker: Option<&Entity>,
+    defender: Option<&Entity>,
+    source_item: Option<&Item>,
+    f: impl FnOnce(&DamageContext) -> R,
+) -> R {
+    let attacker = attacker.map(|entity| RegionCombatant { ctx, entity });
+    let defender = defender.map(|entity| RegionCombatant { ctx, entity });
+    let source =
+        |attr: &str| source_item.map_or(0.0, |item| item.attributes.get_float_default(attr, 0.0));
+    let roll = |dice: &RulesetDice| roll_dice(dice.count, dice.sides);
+    f(&DamageContext {
+        rules: &ctx.rules,
+        level_attribute: &ctx.level_attr,
+        attacker: attacker
+            .as_ref()
+            .map(|combatant| combatant as &dyn Combatant),
+        defender: defender
+            .as_ref()
+            .map(|combatant| combatant as &dyn Combatant),
+        source: source_item
+            .is_some()
+            .then_some(&source as &dyn Fn(&str) -> f32),
+        roll: Some(&roll),
+    })
 }
 
-fn parse_ruleset_dice(input: &str) -> Option<(u32, u32)> {
-    let value = input.trim().to_ascii_lowercase();
-    let (count, sides) = value.split_once('d')?;
-    let count = if count.trim().is_empty() {
-        1
-    } else {
-        count.trim().parse::<u32>().ok()?
-    };
-    let sides = sides.trim().parse::<u32>().ok()?;
-    (count > 0 && sides > 0).then_some((count, sides))
+fn resolve_combat_var(
+    ctx: &RegionCtx,
+    name: &str,
+    value: f32,
+    attacker: Option<&Entity>,
+    defender: Option<&Entity>,
+    source_item: Option<&Item>,
+) -> f32 {
+    with_damage_context(ctx, attacker, defender, source_item, |damage| {
+        combat_variable(damage, name, value)
+    })
 }
 
-fn roll_ruleset_dice(input: &str) -> Option<f32> {
-    let (count, sides) = parse_ruleset_dice(input)?;
-    let mut rng = rand::rng();
+/// Roll `count` dice with `sides` sides on the simulation rng.
+fn roll_dice(count: u32, sides: u32) -> f32 {
+    let mut rng = crate::server::rng::sim_rng();
     let mut total = 0u32;
     for _ in 0..count {
         total += rng.random_range(1..=sides);
     }
-    Some(total as f32)
+    total as f32
 }
 
 fn roll_damage_table(
@@ -19963,11 +21496,14 @@ fn roll_damage_table(
     entity: Option<&Entity>,
     table: &toml::value::Table,
 ) -> Option<f32> {
-    let roll = rule_string(table, "roll")?;
-    let mut value = roll_ruleset_dice(roll)?;
-    value += rule_number(table, "bonus", 0.0);
-    value += structured_attribute_bonus(ctx, entity, table);
-    Some(value.max(0.0))
+    let combatant = entity.map(|entity| RegionCombatant { ctx, entity });
+    eldiron_ruleset::roll_damage_table(
+        table,
+        combatant
+            .as_ref()
+            .map(|combatant| combatant as &dyn Combatant),
+        &|dice| roll_dice(dice.count, dice.sides),
+    )
 }
 
 fn item_percent_attr(item: &Item, key: &str) -> f32 {
@@ -19977,136 +21513,6 @@ fn item_percent_attr(item: &Item, key: &str) -> f32 {
         .clamp(1.0, 100.0)
 }
 
-fn item_quality_damage_multiplier(percent: f32) -> f32 {
-    0.75 + percent.clamp(1.0, 100.0) / 400.0
-}
-
-fn apply_item_quality_condition_to_damage(value: f32, item: &Item) -> f32 {
-    let quality = item_quality_damage_multiplier(item_percent_attr(item, "quality"));
-    let condition = item_quality_damage_multiplier(item_percent_attr(item, "condition"));
-    let scaled = value * quality * condition;
-    if value > 0.0 { scaled.max(1.0) } else { 0.0 }
-}
-
-fn apply_structured_combat_table(
-    ctx: &RegionCtx,
-    amount: f32,
-    key: &str,
-    attacker: Option<&Entity>,
-    defender: Option<&Entity>,
-    source_item: Option<&Item>,
-    kind_table: &toml::value::Table,
-) -> Option<f32> {
-    if key == "outgoing_damage" {
-        let damage = kind_table.get("damage").and_then(toml::Value::as_table)?;
-        let mut value = amount + rule_number(damage, "bonus", 0.0);
-        if let Some(attr) = rule_string(damage, "source_bonus_attribute") {
-            value += source_item.map_or(0.0, |item| item.attributes.get_float_default(attr, 0.0));
-        }
-        if let Some(attr) = rule_string(damage, "attacker_bonus_attribute") {
-            value += attacker.map_or(0.0, |entity| {
-                effective_entity_attribute(ctx, entity, attr, 0.0)
-            });
-        }
-        value += structured_attribute_bonus(ctx, attacker, damage);
-        return Some(value.max(0.0));
-    }
-
-    if key == "incoming_damage" {
-        let reduction = kind_table
-            .get("reduction")
-            .and_then(toml::Value::as_table)?;
-        let mut value = amount - rule_number(reduction, "bonus", 0.0);
-        if let Some(attr) = rule_string(reduction, "attribute") {
-            value -= defender.map_or(0.0, |entity| {
-                effective_entity_attribute(ctx, entity, attr, 0.0)
-            });
-        }
-        if let Some(attr) = rule_string(reduction, "equipped_armor_attribute") {
-            value -= defender.map_or(0.0, |entity| armor_equipped_attr(ctx, entity, attr));
-        }
-        value -= structured_attribute_bonus(ctx, defender, reduction);
-        return Some(value.max(0.0));
-    }
-
-    None
-}
-
-fn evaluate_structured_damage_rule(
-    ctx: &RegionCtx,
-    target_id: u32,
-    from_id: u32,
-    amount: i32,
-    kind: &str,
-    source_item_id: u32,
-    key: &str,
-) -> Option<i32> {
-    let attacker = ctx.map.entities.iter().find(|entity| entity.id == from_id);
-    let defender = ctx
-        .map
-        .entities
-        .iter()
-        .find(|entity| entity.id == target_id);
-    let source_item = attacker.and_then(|entity| {
-        if source_item_id > 0 {
-            entity_item_by_id(entity, source_item_id)
-        } else {
-            None
-        }
-    });
-    let mut current_value = amount as f32;
-    let mut applied = false;
-
-    if let Some(table) = combat_kind_table_from_root(&ctx.rules, kind)
-        && let Some(value) = apply_structured_combat_table(
-            ctx,
-            current_value,
-            key,
-            attacker,
-            defender,
-            source_item,
-            table,
-        )
-    {
-        current_value = value;
-        applied = true;
-    }
-    if let Some(attacker_entity) = attacker
-        && let Some(root) = race_rule_root(ctx, attacker_entity)
-        && let Some(table) = combat_kind_table_from_root(root, kind)
-        && let Some(value) = apply_structured_combat_table(
-            ctx,
-            current_value,
-            key,
-            attacker,
-            defender,
-            source_item,
-            table,
-        )
-    {
-        current_value = value;
-        applied = true;
-    }
-    if let Some(attacker_entity) = attacker
-        && let Some(root) = class_rule_root(ctx, attacker_entity)
-        && let Some(table) = combat_kind_table_from_root(root, kind)
-        && let Some(value) = apply_structured_combat_table(
-            ctx,
-            current_value,
-            key,
-            attacker,
-            defender,
-            source_item,
-            table,
-        )
-    {
-        current_value = value;
-        applied = true;
-    }
-
-    applied.then_some(current_value.round().max(0.0) as i32)
-}
-
 fn progression_stat_table<'a>(ctx: &'a RegionCtx, stat: &str) -> Option<&'a toml::value::Table> {
     ctx.rules
         .get("progression")
@@ -20272,7 +21678,7 @@ pub(crate) fn progression_xp_for_level(ctx: &RegionCtx, entity_id: u32, level: u
             resolve_progression_var(ctx, entity, name)
         }
     })
-    .filter(|value| value.is_finite())
+    .ok()
     .map(|value| value.max(0.0))
 }
 
@@ -20282,34 +21688,11 @@ pub(crate) fn progression_stat_value(ctx: &RegionCtx, entity_id: u32, stat: &str
         .entities
         .iter()
         .find(|entity| entity.id == entity_id)?;
-    let tables = progression_stat_tables(ctx, entity, stat);
-    if tables.is_empty() {
-        return None;
-    }
-    let base = tables
-        .iter()
-        .map(|table| progression_number(table.get("base"), 0.0))
-        .sum::<f32>();
-    let per_level = tables
-        .iter()
-        .map(|table| progression_number(table.get("per_level"), 0.0))
-        .sum::<f32>();
-    let level = progression_level_for_entity(ctx, entity);
-    let levels_gained = (level - 1.0).max(0.0);
-    let gain = tables
-        .iter()
-        .map(|table| {
-            table
-                .get("gain")
-                .and_then(toml::Value::as_str)
-                .and_then(|expr| {
-                    evaluate_formula(expr, |name| resolve_progression_var(ctx, entity, name))
-                })
-                .unwrap_or(0.0)
-        })
-        .sum::<f32>();
-
-    Some((base + levels_gained * (per_level + gain)).max(0.0))
+    progression_stat(
+        &progression_stat_tables(ctx, entity, stat),
+        progression_level_for_entity(ctx, entity),
+        |name| resolve_progression_var(ctx, entity, name),
+    )
 }
 
 fn item_numeric_attr(item: &Item, attr: &str) -> f32 {
@@ -20428,51 +21811,41 @@ fn item_ruleset_damage_table(ctx: &RegionCtx, item: &Item) -> Option<toml::value
         .or_else(|| root.get("damage").and_then(toml::Value::as_table).cloned())
 }
 
-fn unarmed_damage_table(ctx: &RegionCtx) -> Option<&toml::value::Table> {
-    ctx.rules
-        .get("combat")
-        .and_then(toml::Value::as_table)
-        .and_then(|combat| combat.get("unarmed_damage"))
-        .and_then(toml::Value::as_table)
-}
-
 pub(crate) fn current_attack_base_damage_for_entity(ctx: &RegionCtx, entity_id: u32) -> i32 {
     let entity = ctx
         .map
         .entities
         .iter()
         .find(|entity| entity.id == entity_id);
+    let weapon = entity.and_then(|entity| current_attack_weapon_for_entity(ctx, entity));
+    let weapon_damage = weapon.and_then(|item| item_ruleset_damage_table(ctx, item));
+    let combatant = entity.map(|entity| RegionCombatant { ctx, entity });
 
-    if let Some(entity) = entity
-        && let Some(source_item) = current_attack_weapon_for_entity(ctx, entity)
-        && let Some(table) = item_ruleset_damage_table(ctx, source_item)
-        && let Some(value) = roll_damage_table(ctx, Some(entity), &table)
-    {
-        return apply_item_quality_condition_to_damage(value, source_item)
-            .round()
-            .max(0.0) as i32;
-    }
+    eldiron_ruleset::roll_attack_damage(
+        &ctx.rules,
+        combatant
+            .as_ref()
+            .map(|combatant| combatant as &dyn Combatant),
+        weapon
+            .zip(weapon_damage.as_ref())
+            .map(|(item, table)| WeaponDamage {
+                table,
+                quality: item_percent_attr(item, "quality"),
+                condition: item_percent_attr(item, "condition"),
+            }),
+        || progression_stat_value(ctx, entity_id, "damage"),
+        |attribute| entity.map(|entity| entity.attributes.get_float_default(attribute, 1.0)),
+        &|dice| roll_dice(dice.count, dice.sides),
+    )
+}
 
-    if let Some(table) = unarmed_damage_table(ctx)
-        && let Some(value) = roll_damage_table(ctx, entity, table)
-    {
-        return value.round().max(0.0) as i32;
+/// Apply `combat.critical_roll` and `combat.critical_multiplier` to the
+/// resolved damage of an attack hit.
+pub(crate) fn apply_attack_critical(ctx: &RegionCtx, dmg: i32) -> i32 {
+    match resolve_critical(&ctx.rules) {
+        Ok(Some(critical)) if roll_dice(1, 20) as u32 >= critical.roll => critical.apply(dmg),
+        _ => dmg,
     }
-
-    progression_stat_value(ctx, entity_id, "damage")
-        .or_else(|| {
-            let attribute = eldiron_ruleset::resolve_attribute_roles(&ctx.rules)
-                .ok()
-                .and_then(|roles| roles.get("weapon_damage").map(str::to_string))?;
-            ctx.map
-                .entities
-                .iter()
-                .find(|entity| entity.id == entity_id)
-                .map(|entity| entity.attributes.get_float_default(&attribute, 1.0))
-        })
-        .unwrap_or(1.0)
-        .round()
-        .max(0.0) as i32
 }
 
 fn current_attack_kind_for_entity(
@@ -20562,6 +21935,7 @@ fn queue_entity_damage(
         kind,
         source_item_id.unwrap_or(0),
     );
+    let dmg = apply_attack_critical(ctx, dmg);
 
     if dmg > 0
         && let Some(attacker) = ctx.map.entities.iter_mut().find(|e| e.id == attacker_id)
@@ -20627,33 +22001,19 @@ fn evaluate_intent_allowed(
     evaluate_formula(expr, |name| {
         resolve_intent_rule_var(ctx, name, distance, subject, target_entity, target_item)
     })
-    .filter(|value| value.is_finite())
     .map(|value| value != 0.0)
     .unwrap_or(false)
 }
 
-fn evaluate_damage_rule(
+pub(crate) fn apply_damage_rules(
     ctx: &RegionCtx,
     target_id: u32,
     from_id: u32,
     amount: i32,
     kind: &str,
     source_item_id: u32,
-    key: &str,
-) -> Option<i32> {
+) -> i32 {
     let attacker = ctx.map.entities.iter().find(|entity| entity.id == from_id);
-    let exprs = combat_rule_exprs(ctx, attacker, kind, key);
-    if exprs.is_empty() {
-        return evaluate_structured_damage_rule(
-            ctx,
-            target_id,
-            from_id,
-            amount,
-            kind,
-            source_item_id,
-            key,
-        );
-    }
     let defender = ctx
         .map
         .entities
@@ -20666,51 +22026,9 @@ fn evaluate_damage_rule(
             None
         }
     });
-    let mut current_value = amount as f32;
-
-    for expr in exprs {
-        let parsed = evaluate_formula(expr, |name| {
-            resolve_combat_var(ctx, name, current_value, attacker, defender, source_item)
-        })?;
-        if !parsed.is_finite() {
-            return None;
-        }
-        current_value = parsed.max(0.0);
-    }
-
-    Some(current_value.round().max(0.0) as i32)
-}
-
-pub(crate) fn apply_damage_rules(
-    ctx: &RegionCtx,
-    target_id: u32,
-    from_id: u32,
-    amount: i32,
-    kind: &str,
-    source_item_id: u32,
-) -> i32 {
-    let amount = amount.max(0);
-    let outgoing = evaluate_damage_rule(
-        ctx,
-        target_id,
-        from_id,
-        amount,
-        kind,
-        source_item_id,
-        "outgoing_damage",
-    )
-    .unwrap_or(amount);
-    evaluate_damage_rule(
-        ctx,
-        target_id,
-        from_id,
-        outgoing,
-        kind,
-        source_item_id,
-        "incoming_damage",
-    )
-    .unwrap_or(outgoing)
-    .max(0)
+    with_damage_context(ctx, attacker, defender, source_item, |damage| {
+        resolve_damage(damage, kind, amount)
+    })
 }
 
 pub(crate) fn drop_all_items_for_entity(ctx: &mut RegionCtx, entity_id: u32) {
@@ -20779,6 +22097,157 @@ fn format_loot_text(template: &str, entity_name: &str) -> String {
     template.replace("{name}", entity_name)
 }
 
+/// Roll ruleset loot tables into items for a victim, harvester or opener of
+/// `level`. Drops naming items the ruleset does not define are skipped.
+fn roll_ruleset_loot_items(ctx: &mut RegionCtx, tables: &[String], level: u32) -> Vec<Item> {
+    let mut rng = crate::server::rng::sim_rng();
+    let mut roll = |low: u32, high: u32| rng.random_range(low..=high);
+    let mut items = Vec::new();
+    let mut warnings = Vec::new();
+    for table in tables {
+        let drops = match eldiron_ruleset::roll_loot_table(&ctx.rules, table, level, &mut roll) {
+            Ok(drops) => drops,
+            Err(err) => {
+                warnings.push(format!(
+                    "[warning] {}: Loot table '{}': {}",
+                    ctx.map.name, table, err
+                ));
+                continue;
+            }
+        };
+        for drop in drops {
+            let Some(mut item) =
+                ruleset_item_from_table(&ctx.rules, &drop.item, drop.quantity as usize)
+            else {
+                continue;
+            };
+            if let Some(rarity) = drop.rarity {
+                item.set_attribute("rarity", Value::Str(rarity));
+            }
+            if let Some(quality) = drop.quality {
+                item.set_attribute("quality", Value::Int(quality));
+            }
+            items.push(item);
+        }
+    }
+    for warning in warnings {
+        ctx.send_log_message(warning);
+    }
+    items
+}
+
+/// The loot tables a dead NPC still has to roll. Players never roll race or
+/// class loot, and each death rolls only once until the NPC respawns.
+fn take_entity_death_loot(ctx: &mut RegionCtx, entity_index: usize) -> (Vec<String>, u32) {
+    let entity = &ctx.map.entities[entity_index];
+    let entity_id = entity.id;
+    if entity.is_player()
+        || ctx
+            .entity_state_data
+            .get(&entity_id)
+            .is_some_and(|state| state.get_bool_default("__loot_rolled", false))
+    {
+        return (Vec::new(), 1);
+    }
+    let tables = eldiron_ruleset::identity_loot_tables(
+        &ctx.rules,
+        entity.get_attr_string("race").as_deref(),
+        entity.get_attr_string("class").as_deref(),
+    );
+    let level = progression_level_for_entity(ctx, entity) as u32;
+    if !tables.is_empty() {
+        ctx.entity_state_data
+            .entry(entity_id)
+            .or_default()
+            .set("__loot_rolled", Value::Bool(true));
+    }
+    (tables, level)
+}
+
+/// Roll a dead NPC's race and class loot into its inventory, so it drops with
+/// its carried items whether or not corpses are enabled. Loot that does not
+/// fit is placed on the ground.
+fn add_entity_death_loot(ctx: &mut RegionCtx, entity_id: u32) {
+    let Some(entity_index) = ctx
+        .map
+        .entities
+        .iter()
+        .position(|entity| entity.id == entity_id)
+    else {
+        return;
+    };
+    let (loot_tables, loot_level) = take_entity_death_loot(ctx, entity_index);
+    let rolled_loot = roll_ruleset_loot_items(ctx, &loot_tables, loot_level);
+
+    let entity = &mut ctx.map.entities[entity_index];
+    let entity_position = entity.position;
+    let mut dropped_items = Vec::new();
+    for mut item in rolled_loot {
+        item.position = entity_position;
+        item.mark_all_dirty();
+        if entity.inventory.iter().any(Option::is_none) {
+            let _ = entity.add_item(item);
+        } else {
+            dropped_items.push(item);
+        }
+    }
+
+    if !dropped_items.is_empty() {
+        let count = dropped_items.len();
+        ctx.map.items.extend(dropped_items);
+        ctx.send_item_drop_message_for_position(
+            Vec2::new(entity_position.x, entity_position.z),
+            count,
+        );
+    }
+}
+
+/// Roll a world container's ruleset loot tables the first time it is opened,
+/// for the level of the entity opening it. Loot that does not fit is placed
+/// next to the container.
+fn fill_ruleset_container_loot(ctx: &mut RegionCtx, item_id: u32, opener_id: u32) {
+    let Some(index) = ctx.map.items.iter().position(|item| item.id == item_id) else {
+        return;
+    };
+    let container = &ctx.map.items[index];
+    if container.attributes.get_bool_default("loot_rolled", false) {
+        return;
+    }
+    let tables = container
+        .attributes
+        .get_str("ruleset_id")
+        .and_then(|id| ruleset_item_table_by_id(&ctx.rules, id))
+        .filter(|(group, _)| *group == "containers")
+        .map(|(_, table)| eldiron_ruleset::ruleset_loot_table_refs(table))
+        .unwrap_or_default();
+    if tables.is_empty() {
+        return;
+    }
+    let level = ctx
+        .map
+        .entities
+        .iter()
+        .find(|entity| entity.id == opener_id)
+        .map(|entity| progression_level_for_entity(ctx, entity) as u32)
+        .unwrap_or(1);
+    let loot = roll_ruleset_loot_items(ctx, &tables, level);
+
+    let container = &mut ctx.map.items[index];
+    container.set_attribute("loot_rolled", Value::Bool(true));
+    let position = container.position;
+    let mut overflow = Vec::new();
+    for item in loot {
+        if container.add_item_to_container(item.clone()).is_err() {
+            let mut item = item;
+            item.position = position;
+            item.mark_all_dirty();
+            overflow.push(item);
+        }
+    }
+    container.mark_all_dirty();
+    ctx.map.items.extend(overflow);
+}
+
 fn fallback_loot_container() -> Item {
     let mut item = Item::new();
     item.id = get_global_id();
@@ -20829,11 +22298,27 @@ pub(crate) fn drop_items_into_ruleset_loot_container(
     let health_attr = ctx.health_attr.clone();
     let include_equipped = rule_bool(corpse_rules, "include_equipped", true);
     let create_empty = rule_bool(corpse_rules, "create_empty", false);
-    let corpse_item_id = rule_string(corpse_rules, "item").unwrap_or("loot_corpse");
-    let name_template = rule_string(corpse_rules, "name").unwrap_or("{name}'s Remains");
+    let corpse_item_id = rule_string(corpse_rules, "item")
+        .unwrap_or("loot_corpse")
+        .to_string();
+    let name_template = rule_string(corpse_rules, "name")
+        .unwrap_or("{name}'s Remains")
+        .to_string();
     let description_template = rule_string(corpse_rules, "description")
-        .unwrap_or("What remains of {name}. Open it to search the carried loot.");
+        .unwrap_or("What remains of {name}. Open it to search the carried loot.")
+        .to_string();
     let corpse_despawn_at_tick = corpse_despawn_tick_for_entity(ctx, entity_id, corpse_rules);
+    let dead = {
+        let entity = &ctx.map.entities[entity_index];
+        entity.attributes.get_str_default("mode", "active".into()) == "dead"
+            || entity.attributes.get_int_default(&health_attr, 1) <= 0
+    };
+    let (loot_tables, loot_level) = if dead && filter.is_empty() {
+        take_entity_death_loot(ctx, entity_index)
+    } else {
+        (Vec::new(), 1)
+    };
+    let rolled_loot = roll_ruleset_loot_items(ctx, &loot_tables, loot_level);
 
     let (entity_position, entity_name, mut removed_items) = {
         let entity = &mut ctx.map.entities[entity_index];
@@ -20883,21 +22368,25 @@ pub(crate) fn drop_items_into_ruleset_loot_container(
 
         (entity_position, entity_name, removed_items)
     };
+    for mut item in rolled_loot {
+        item.position = entity_position;
+        removed_items.push(item);
+    }
 
     if removed_items.is_empty() && !create_empty {
         return true;
     }
 
-    let mut corpse = ruleset_item_from_table(&ctx.rules, corpse_item_id, 1)
+    let mut corpse = ruleset_item_from_table(&ctx.rules, &corpse_item_id, 1)
         .unwrap_or_else(fallback_loot_container);
     corpse.position = entity_position;
     corpse.set_attribute(
         "name",
-        Value::Str(format_loot_text(name_template, &entity_name)),
+        Value::Str(format_loot_text(&name_template, &entity_name)),
     );
     corpse.set_attribute(
         "description",
-        Value::Str(format_loot_text(description_template, &entity_name)),
+        Value::Str(format_loot_text(&description_template, &entity_name)),
     );
     corpse.set_attribute("corpse_entity_id", Value::UInt(entity_id));
     corpse.set_attribute("corpse_owner", Value::Str(entity_name));
@@ -21094,6 +22583,8 @@ pub(crate) fn return_entity_to_spawn(ctx: &mut RegionCtx, entity_id: u32) -> boo
     entity.action = EntityAction::Off;
     entity.active_sequence = None;
     entity.paused_sequence = None;
+    entity.active_schedule = None;
+    entity.behavior_state = Default::default();
     entity.snap_position_update = true;
     entity.mark_all_dirty();
 
@@ -21673,6 +23164,8 @@ fn respawn_npc_entity(ctx: &mut RegionCtx, entity_id: u32) {
         entity.action = EntityAction::Off;
         entity.active_sequence = None;
         entity.paused_sequence = None;
+        entity.active_schedule = None;
+        entity.behavior_state = Default::default();
         if let Some(snapshot) = snapshot {
             entity.attributes = snapshot.attributes;
             entity.inventory = snapshot.inventory;
@@ -21711,6 +23204,7 @@ fn respawn_npc_entity(ctx: &mut RegionCtx, entity_id: u32) {
             ctx.entity_proximity_alerts.insert(entity_id, distance);
         }
         state.remove("__respawn_at_tick");
+        state.remove("__loot_rolled");
     }
 
     if clear_corpse {
@@ -23339,9 +24833,24 @@ fn offer_inventory(to: u32, filter: String, vm: &VirtualMachine) {
                 expires_at_tick,
                 max_distance,
             });
+            let shop = entity_shop(ctx, entity_id);
             for item_id in matching_item_ids {
-                let choice =
-                    Choice::ItemToSell(item_id, entity_id, to, expires_at_tick, max_distance);
+                let price = ctx
+                    .map
+                    .entities
+                    .iter()
+                    .find(|entity| entity.id == entity_id)
+                    .and_then(|entity| entity.get_item(item_id))
+                    .map(|item| trade_unit_price(ctx, shop.as_ref(), entity_id, to, item, true))
+                    .unwrap_or(0);
+                let choice = Choice::ItemToSell(
+                    item_id,
+                    entity_id,
+                    to,
+                    expires_at_tick,
+                    max_distance,
+                    price,
+                );
                 choices.add(choice);
             }
 
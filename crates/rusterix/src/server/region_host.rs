@@ -1,14 +1,17 @@
+use crate::collision_world::opening_geo_for_item;
 use crate::server::message::{AudioCommand, RegionMessage};
 use crate::server::region::{
-    RegionInstance, add_debug_value, apply_damage_direct, apply_damage_rules,
-    apply_spell_default_attrs, consume_attack_ammunition_for_source, craft_ruleset_recipe,
+    RegionInstance, add_debug_value, advance_quest, apply_attack_critical, apply_damage_direct,
+    apply_damage_rules, apply_spell_default_attrs, complete_quest,
+    consume_attack_ammunition_for_source, craft_ruleset_recipe,
     current_attack_base_damage_for_entity, current_attack_cooldown_for_entity,
     current_attack_weapon_for_entity, drop_items_into_ruleset_loot_container,
-    entity_disposition_by_id, entity_is_hostile_by_id, entity_item_by_id,
-    equip_inventory_item_for_entity, execute_ruleset_action_with_source, grant_experience,
-    has_attack_ammunition_or_message, is_spell_on_cooldown, open_dialog_node,
-    queue_applied_damage_event, return_entity_to_spawn, set_entity_cooldown_attrs,
-    set_spell_cooldown, trigger_avatar_attack_animation,
+    entity_disposition_by_id, entity_is_hostile_by_id, entity_item_by_id, entity_shop,
+    equip_inventory_item_for_entity, execute_ruleset_action_with_source, fail_quest,
+    grant_experience, has_attack_ammunition_or_message, is_spell_on_cooldown, open_dialog_node,
+    quest_stage, quest_status, queue_applied_damage_event, return_entity_to_spawn,
+    set_entity_cooldown_attrs, set_spell_cooldown, start_quest, trade_unit_price,
+    trigger_avatar_attack_animation,
 };
 use crate::server::regionctx::{ChoiceSession, ScriptScope};
 use crate::vm::*;
@@ -17,7 +20,7 @@ use crate::{
     Value, ValueContainer,
 };
 use rand::Rng;
-use scenevm::{GeoId, PaletteRemap2DMode};
+use scenevm::PaletteRemap2DMode;
 use theframework::prelude::TheValue;
 use vek::{Vec2, Vec3};
 
@@ -75,36 +78,144 @@ fn join_entity_party(ctx: &mut RegionCtx, companion_id: u32, leader_id: u32) ->
     Some(slot)
 }
 
-enum SpellTargetArg {
-    Entity(u32),
-    Position(Vec3<f32>),
+/// The route names in the `route` attribute, a string or a string array.
+fn parse_route_names(attrs: &ValueContainer) -> Vec<String> {
+    if let Some(Value::StrArray(values)) = attrs.get("route") {
+        return values
+            .iter()
+            .map(|s| s.trim())
+            .filter(|s| !s.is_empty())
+            .map(ToString::to_string)
+            .collect();
+    }
+    if let Some(value) = attrs.get_str("route") {
+        let value = value.trim();
+        if !value.is_empty() {
+            return vec![value.to_string()];
+        }
+    }
+    Vec::new()
 }
 
-fn opening_geo_for_item(item: &Item) -> Option<GeoId> {
-    if let Some(object_id) = item.attributes.get_id("geometry_object_id") {
-        return Some(GeoId::GeometryObject(object_id));
+/// Chain the linedefs of the named routes into a list of patrol points,
+/// starting at the end closest to `start_pos`.
+pub(crate) fn resolve_route_points(
+    map: &Map,
+    route_names: &[String],
+    start_pos: Vec2<f32>,
+) -> Vec<Vec2<f32>> {
+    #[derive(Clone)]
+    struct Segment {
+        start_id: u32,
+        end_id: u32,
+        start: Vec2<f32>,
+        end: Vec2<f32>,
     }
 
-    if let Some(sector_id) = match item.attributes.get("sector_id") {
-        Some(Value::UInt(v)) => Some(*v),
-        Some(Value::Int(v)) if *v >= 0 => Some(*v as u32),
-        Some(Value::Int64(v)) if *v >= 0 => Some(*v as u32),
-        _ => None,
-    } {
-        return Some(GeoId::Sector(sector_id));
+    let mut points: Vec<Vec2<f32>> = Vec::new();
+    let mut anchor = start_pos;
+
+    for route_name in route_names {
+        let name = route_name.trim();
+        if name.is_empty() {
+            continue;
+        }
+
+        let mut segments: Vec<Segment> = map
+            .linedefs
+            .iter()
+            .filter(|ld| ld.name.eq_ignore_ascii_case(name))
+            .filter_map(|ld| {
+                let a = map.get_vertex(ld.start_vertex)?;
+                let b = map.get_vertex(ld.end_vertex)?;
+                Some(Segment {
+                    start_id: ld.start_vertex,
+                    end_id: ld.end_vertex,
+                    start: a,
+                    end: b,
+                })
+            })
+            .collect();
+
+        while !segments.is_empty() {
+            let mut best_idx = 0usize;
+            let mut best_dist = f32::MAX;
+            let mut best_from_start = true;
+
+            for (idx, seg) in segments.iter().enumerate() {
+                let ds = seg.start.distance_squared(anchor);
+                if ds < best_dist {
+                    best_dist = ds;
+                    best_idx = idx;
+                    best_from_start = true;
+                }
+                let de = seg.end.distance_squared(anchor);
+                if de < best_dist {
+                    best_dist = de;
+                    best_idx = idx;
+                    best_from_start = false;
+                }
+            }
+
+            let seed = segments.swap_remove(best_idx);
+            let (mut current_vid, seed_start, seed_end) = if best_from_start {
+                (seed.end_id, seed.start, seed.end)
+            } else {
+                (seed.start_id, seed.end, seed.start)
+            };
+
+            if points
+                .last()
+                .is_none_or(|last| last.distance_squared(seed_start) > 1e-8)
+            {
+                points.push(seed_start);
+            }
+            points.push(seed_end);
+            anchor = seed_end;
+
+            while let Some(next_idx) = segments
+                .iter()
+                .position(|seg| seg.start_id == current_vid || seg.end_id == current_vid)
+            {
+                let seg = segments.swap_remove(next_idx);
+                let next_point;
+                if seg.start_id == current_vid {
+                    current_vid = seg.end_id;
+                    next_point = seg.end;
+                } else {
+                    current_vid = seg.start_id;
+                    next_point = seg.start;
+                }
+                if points
+                    .last()
+                    .is_none_or(|last| last.distance_squared(next_point) > 1e-8)
+                {
+                    points.push(next_point);
+                }
+                anchor = next_point;
+            }
+        }
     }
 
-    let host_id = match item.attributes.get("profile_host_sector_id") {
-        Some(Value::UInt(v)) => Some(*v),
-        _ => None,
-    }?;
+    points
+}
 
-    let profile_id = match item.attributes.get("profile_sector_id") {
-        Some(Value::UInt(v)) => Some(*v),
-        _ => None,
-    }?;
+pub(crate) fn nearest_point_index(from: Vec2<f32>, points: &[Vec2<f32>]) -> usize {
+    let mut best_idx = 0usize;
+    let mut best_dist = f32::MAX;
+    for (idx, point) in points.iter().enumerate() {
+        let d = from.distance_squared(*point);
+        if d < best_dist {
+            best_dist = d;
+            best_idx = idx;
+        }
+    }
+    best_idx
+}
 
-    Some(GeoId::Hole(host_id, profile_id))
+enum SpellTargetArg {
+    Entity(u32),
+    Position(Vec3<f32>),
 }
 
 fn apply_geometry_object_item_attr(
@@ -181,7 +292,7 @@ fn rebuild_procedural_region(ctx: &mut RegionCtx, seed_arg: i64) -> bool {
     };
     cfg.apply_runtime_overrides(|key| ctx.get_region_value(key));
     if !cfg.enabled
-        || !cfg.generator.eq_ignore_ascii_case("connected_rooms")
+        || !crate::procedural::is_supported_generator(&cfg.generator)
         || !cfg.mode.eq_ignore_ascii_case("2d")
     {
         ctx.send_log_message(format!(
@@ -212,8 +323,9 @@ fn rebuild_procedural_region(ctx: &mut RegionCtx, seed_arg: i64) -> bool {
         );
     }
     ctx.send_log_message(format!(
-        "[Procedural] {}: rebuilding connected_rooms seed={} run={} depth={} rooms={} skeleton_pct={} size={}x{} room_size={}..{}",
+        "[Procedural] {}: rebuilding {} seed={} run={} depth={} rooms={} skeleton_pct={} size={}x{} room_size={}..{}",
         ctx.map.name,
+        cfg.generator,
         cfg.seed,
         run,
         debug_context_value(ctx, "dungeon.depth"),
@@ -269,7 +381,14 @@ fn rebuild_procedural_region(ctx: &mut RegionCtx, seed_arg: i64) -> bool {
         !removed_entity_ids.contains(&session.from) && !removed_entity_ids.contains(&session.to)
     });
 
-    let output = crate::procedural::bake_connected_rooms(&mut ctx.map, &ctx.assets.tiles, &cfg);
+    let prefabs = cfg
+        .prefabs
+        .iter()
+        .filter_map(|name| ctx.assets.maps.get(name))
+        .filter_map(crate::procedural::ProceduralPrefab::from_map)
+        .collect::<Vec<_>>();
+    let output =
+        crate::procedural::bake_procedural(&mut ctx.map, &ctx.assets.tiles, &cfg, &prefabs);
     let item_spawn_count = output.item_spawns.len();
     let character_spawn_count = output.character_spawns.len();
 
@@ -303,6 +422,7 @@ fn rebuild_procedural_region(ctx: &mut RegionCtx, seed_arg: i64) -> bool {
         );
         entity.set_attribute("procedural_generated", Value::Bool(true));
         entity.set_attribute("procedural_kind", Value::Str(spawn.kind));
+        entity.home_region = ctx.map.name.clone();
         if let Some(data) = ctx.entity_class_data.get(&spawn.name) {
             crate::server::data::apply_entity_data(&mut entity, data);
             crate::server::region::apply_ruleset_character_defaults(&ctx.rules, &mut entity);
@@ -481,142 +601,6 @@ impl<'a> RegionHost<'a> {
         Some(vm)
     }
 
-    fn parse_route_names(attrs: &ValueContainer) -> Vec<String> {
-        if let Some(Value::StrArray(values)) = attrs.get("route") {
-            return values
-                .iter()
-                .map(|s| s.trim())
-                .filter(|s| !s.is_empty())
-                .map(ToString::to_string)
-                .collect();
-        }
-        if let Some(value) = attrs.get_str("route") {
-            let value = value.trim();
-            if !value.is_empty() {
-                return vec![value.to_string()];
-            }
-        }
-        Vec::new()
-    }
-
-    fn resolve_route_points(
-        map: &Map,
-        route_names: &[String],
-        start_pos: Vec2<f32>,
-    ) -> Vec<Vec2<f32>> {
-        #[derive(Clone)]
-        struct Segment {
-            start_id: u32,
-            end_id: u32,
-            start: Vec2<f32>,
-            end: Vec2<f32>,
-        }
-
-        let mut points: Vec<Vec2<f32>> = Vec::new();
-        let mut anchor = start_pos;
-
-        for route_name in route_names {
-            let name = route_name.trim();
-            if name.is_empty() {
-                continue;
-            }
-
-            let mut segments: Vec<Segment> = map
-                .linedefs
-                .iter()
-                .filter(|ld| ld.name.eq_ignore_ascii_case(name))
-                .filter_map(|ld| {
-                    let a = map.get_vertex(ld.start_vertex)?;
-                    let b = map.get_vertex(ld.end_vertex)?;
-                    Some(Segment {
-                        start_id: ld.start_vertex,
-                        end_id: ld.end_vertex,
-                        start: a,
-                        end: b,
-                    })
-                })
-                .collect();
-
-            while !segments.is_empty() {
-                let mut best_idx = 0usize;
-                let mut best_dist = f32::MAX;
-                let mut best_from_start = true;
-
-                for (idx, seg) in segments.iter().enumerate() {
-                    let ds = seg.start.distance_squared(anchor);
-                    if ds < best_dist {
-                        best_dist = ds;
-                        best_idx = idx;
-                        best_from_start = true;
-                    }
-                    let de = seg.end.distance_squared(anchor);
-                    if de < best_dist {
-                        best_dist = de;
-                        best_idx = idx;
-                        best_from_start = false;
-                    }
-                }
-
-                let seed = segments.swap_remove(best_idx);
-                let (mut current_vid, seed_start, seed_end) = if best_from_start {
-                    (seed.end_id, seed.start, seed.end)
-                } else {
-                    (seed.start_id, seed.end, seed.start)
-                };
-
-                if points
-                    .last()
-                    .is_none_or(|last| last.distance_squared(seed_start) > 1e-8)
-                {
-                    points.push(seed_start);
-                }
-                points.push(seed_end);
-                anchor = seed_end;
-
-                loop {
-                    let Some(next_idx) = segments
-                        .iter()
-                        .position(|seg| seg.start_id == current_vid || seg.end_id == current_vid)
-                    else {
-                        break;
-                    };
-
-                    let seg = segments.swap_remove(next_idx);
-                    let next_point;
-                    if seg.start_id == current_vid {
-                        current_vid = seg.end_id;
-                        next_point = seg.end;
-                    } else {
-                        current_vid = seg.start_id;
-                        next_point = seg.start;
-                    }
-                    if points
-                        .last()
-                        .is_none_or(|last| last.distance_squared(next_point) > 1e-8)
-                    {
-                        points.push(next_point);
-                    }
-                    anchor = next_point;
-                }
-            }
-        }
-
-        points
-    }
-
-    fn nearest_point_index(from: Vec2<f32>, points: &[Vec2<f32>]) -> usize {
-        let mut best_idx = 0usize;
-        let mut best_dist = f32::MAX;
-        for (idx, point) in points.iter().enumerate() {
-            let d = from.distance_squared(*point);
-            if d < best_dist {
-                best_dist = d;
-                best_idx = idx;
-            }
-        }
-        best_idx
-    }
-
     fn parse_target_arg_id(arg: &VMValue) -> Option<u32> {
         if let Some(s) = arg.as_string() {
             if let Ok(id) = s.parse::<u32>() {
@@ -943,6 +927,7 @@ impl<'a> RegionHost<'a> {
             }
             let source_item_id = source_item_id.unwrap_or(0);
             let dmg = apply_damage_rules(self.ctx, id, attacker_id, base_dmg, kind, source_item_id);
+            let dmg = apply_attack_critical(self.ctx, dmg);
             if self.ctx.curr_item_id.is_none() && dmg > 0 {
                 if let Some(attacker) = self.ctx.get_current_entity_mut() {
                     trigger_avatar_attack_animation(attacker);
@@ -1376,6 +1361,21 @@ impl<'a> HostHandler for RegionHost<'a> {
                     let _ = sender.send(RegionMessage::AudioCmd(self.ctx.region_id, cmd));
                 }
             }
+            "save_game" | "load_game" => {
+                let slot = args
+                    .first()
+                    .and_then(|v| v.as_string())
+                    .unwrap_or_default()
+                    .to_string();
+                let msg = if name == "save_game" {
+                    RegionMessage::SaveGame(slot)
+                } else {
+                    RegionMessage::LoadGame(slot)
+                };
+                if let Some(sender) = self.ctx.from_sender.get() {
+                    let _ = sender.send(msg);
+                }
+            }
             "set_audio_bus_volume" => {
                 if let (Some(bus), Some(volume)) =
                     (args.first().and_then(|v| v.as_string()), args.get(1))
@@ -1399,7 +1399,7 @@ impl<'a> HostHandler for RegionHost<'a> {
                     }
 
                     let success_pct = args.get(2).map(|v| v.x).unwrap_or(100.0).clamp(0.0, 100.0);
-                    let mut rng = rand::rng();
+                    let mut rng = crate::server::rng::sim_rng();
                     let roll = rng.random_range(0.0..100.0);
                     if roll >= success_pct {
                         // Optional event for scripts reacting to failed casts.
@@ -1444,7 +1444,9 @@ impl<'a> HostHandler for RegionHost<'a> {
                         is_firstp = matches!(
                             entity.attributes.get("player_camera"),
                             Some(Value::PlayerCamera(
-                                PlayerCamera::D3FirstP | PlayerCamera::D3FirstPGrid
+                                PlayerCamera::D3FirstP
+                                    | PlayerCamera::D3FirstPGrid
+                                    | PlayerCamera::D3ThirdP
                             ))
                         );
                     }
@@ -1571,6 +1573,7 @@ impl<'a> HostHandler for RegionHost<'a> {
                             "iso_grid" => PlayerCamera::D2Grid,
                             "firstp" => PlayerCamera::D3FirstP,
                             "firstp_grid" => PlayerCamera::D3FirstPGrid,
+                            "thirdp" => PlayerCamera::D3ThirdP,
                             _ => PlayerCamera::D2,
                         };
                         entity.set_attribute("player_camera", Value::PlayerCamera(player_camera));
@@ -1868,14 +1871,14 @@ impl<'a> HostHandler for RegionHost<'a> {
                     if lo > hi {
                         std::mem::swap(&mut lo, &mut hi);
                     }
-                    let mut rng = rand::rng();
+                    let mut rng = crate::server::rng::sim_rng();
                     let r: i32 = rng.random_range(lo..=hi);
                     if self.ctx.debug_mode {
                         add_debug_value(&mut self.ctx, TheValue::Int(r), false);
                     }
                     return self.debug_return(VMValue::broadcast(r as f32));
                 } else {
-                    let r: f32 = rand::random();
+                    let r: f32 = crate::server::rng::sim_rng().random();
                     if self.ctx.debug_mode {
                         add_debug_value(&mut self.ctx, TheValue::Float(r), false);
                     }
@@ -1944,17 +1947,17 @@ impl<'a> HostHandler for RegionHost<'a> {
                                 .attributes
                                 .get_str_default("route_mode", "loop".to_string())
                                 .to_ascii_lowercase(),
-                            Self::parse_route_names(&entity.attributes),
+                            parse_route_names(&entity.attributes),
                             entity.get_pos_xz(),
                         )
                     })
                     .unwrap_or_else(|| ("loop".to_string(), Vec::new(), Vec2::zero()));
-                let points = Self::resolve_route_points(&self.ctx.map, &route_names, current_pos);
+                let points = resolve_route_points(&self.ctx.map, &route_names, current_pos);
                 if let Some(entity) = self.ctx.get_current_entity_mut() {
                     if points.is_empty() {
                         entity.action = EntityAction::Off;
                     } else {
-                        let point_index = Self::nearest_point_index(current_pos, &points);
+                        let point_index = nearest_point_index(current_pos, &points);
                         entity.action = EntityAction::Patrol {
                             points,
                             route_wait,
@@ -2251,17 +2254,7 @@ impl<'a> HostHandler for RegionHost<'a> {
                         })
                         .map(|(_, i)| i.id)
                         .collect();
-                    let ids_str: Vec<String> = ids.iter().map(|i| i.to_string()).collect();
-                    let mut v = VMValue::zero();
-                    if let Some(id0) = ids.get(0) {
-                        v.x = *id0 as f32;
-                    }
-                    if let Some(id1) = ids.get(1) {
-                        v.y = *id1 as f32;
-                    }
-                    v.z = ids.len() as f32;
-                    v.string = Some(ids_str.join(","));
-                    return self.debug_return(v);
+                    return self.debug_return(VMValue::from_id_list(&ids));
                 }
             }
             "inventory_items_of" => {
@@ -2289,17 +2282,7 @@ impl<'a> HostHandler for RegionHost<'a> {
                             })
                             .map(|(_, i)| i.id)
                             .collect();
-                        let ids_str: Vec<String> = ids.iter().map(|i| i.to_string()).collect();
-                        let mut v = VMValue::zero();
-                        if let Some(id0) = ids.get(0) {
-                            v.x = *id0 as f32;
-                        }
-                        if let Some(id1) = ids.get(1) {
-                            v.y = *id1 as f32;
-                        }
-                        v.z = ids.len() as f32;
-                        v.string = Some(ids_str.join(","));
-                        return self.debug_return(v);
+                        return self.debug_return(VMValue::from_id_list(&ids));
                     }
                 }
             }
@@ -2344,22 +2327,19 @@ impl<'a> HostHandler for RegionHost<'a> {
                     }
                 }
 
-                // Pack result: x/y first two ids, z = count, string = comma list
-                let ids_str: Vec<String> = ids.iter().map(|i| i.to_string()).collect();
-                let mut v = VMValue::zero();
-                if let Some(id0) = ids.get(0) {
-                    v.x = *id0 as f32;
-                }
-                if let Some(id1) = ids.get(1) {
-                    v.y = *id1 as f32;
-                }
-                v.z = ids.len() as f32;
-                v.string = Some(ids_str.join(","));
-                return self.debug_return(v);
+                // A list of ids, x/y also hold the first two ids and z the count
+                return self.debug_return(VMValue::from_id_list(&ids));
             }
             "list_get" => {
-                // list is arg0 (comma-separated string), index is arg1
+                // list is arg0 (a list or a legacy comma-separated string), index is arg1
                 let idx = args.get(1).map(|v| v.x as i32).unwrap_or(0);
+                if let Some(list) = args.first().and_then(|v| v.as_list()) {
+                    if list.is_empty() {
+                        return self.debug_return(VMValue::zero());
+                    }
+                    let clamped = (idx.max(0) as usize).min(list.len() - 1);
+                    return self.debug_return(list[clamped].clone());
+                }
                 if let Some(list_str) = args.get(0).and_then(|v| v.as_string()) {
                     let parts: Vec<&str> = list_str.split(',').filter(|s| !s.is_empty()).collect();
                     if parts.is_empty() {
@@ -2529,6 +2509,33 @@ impl<'a> HostHandler for RegionHost<'a> {
                 }
                 return self.debug_return_bool(false);
             }
+            "start_quest" | "advance_quest" | "complete_quest" | "fail_quest" => {
+                if let (Some(entity), Some(quest_id)) =
+                    (args.first(), args.get(1).and_then(VMValue::as_string))
+                {
+                    let entity_id = entity.x as u32;
+                    let ok = match name {
+                        "start_quest" => start_quest(self.ctx, entity_id, quest_id),
+                        "advance_quest" => advance_quest(self.ctx, entity_id, quest_id),
+                        "complete_quest" => complete_quest(self.ctx, entity_id, quest_id),
+                        _ => fail_quest(self.ctx, entity_id, quest_id),
+                    };
+                    return self.debug_return_bool(ok);
+                }
+                return self.debug_return_bool(false);
+            }
+            "quest_status" | "quest_stage" => {
+                let value = match (args.first(), args.get(1).and_then(VMValue::as_string)) {
+                    (Some(entity), Some(quest_id)) if name == "quest_status" => {
+                        quest_status(self.ctx, entity.x as u32, quest_id)
+                    }
+                    (Some(entity), Some(quest_id)) => {
+                        quest_stage(self.ctx, entity.x as u32, quest_id)
+                    }
+                    _ => String::new(),
+                };
+                return self.debug_return(VMValue::from_string(value));
+            }
             "craft" => {
                 if let Some(recipe_id) = args.first().and_then(VMValue::as_string) {
                     let ok = craft_ruleset_recipe(self.ctx, self.ctx.curr_entity_id, recipe_id);
@@ -2678,13 +2685,26 @@ impl<'a> HostHandler for RegionHost<'a> {
                         expires_at_tick,
                         max_distance,
                     );
+                    let shop = entity_shop(self.ctx, entity_id);
                     for item_id in matching_item_ids {
+                        let price = self
+                            .ctx
+                            .map
+                            .entities
+                            .iter()
+                            .find(|entity| entity.id == entity_id)
+                            .and_then(|entity| entity.get_item(item_id))
+                            .map(|item| {
+                                trade_unit_price(self.ctx, shop.as_ref(), entity_id, to, item, true)
+                            })
+                            .unwrap_or(0);
                         let choice = Choice::ItemToSell(
                             item_id,
                             entity_id,
                             to,
                             expires_at_tick,
                             max_distance,
+                            price,
                         );
                         choices.add(choice);
                     }
@@ -4214,6 +4234,124 @@ mod tests {
         );
     }
 
+    #[test]
+    fn dead_npc_rolls_race_loot_tables_into_corpse_once() {
+        let mut arena = HeadlessRulesArena::with_rules(
+            r#"
+            [loot.corpse]
+            enabled = true
+            item = "loot_corpse"
+
+            [loot.tables.orc_remains]
+            guaranteed = [{ item = "orc_tooth", quantity = 2 }]
+            entries = [{ item = "orc_tusk", min_level = 5 }]
+
+            [races.Orc]
+            loot = "orc_remains"
+
+            [items.materials.orc_tooth]
+            name = "Orc Tooth"
+            max_stack = 10
+
+            [items.materials.orc_tusk]
+            name = "Orc Tusk"
+
+            [items.containers.loot_corpse]
+            name = "Loot Corpse"
+            category = "corpse"
+            slot = "container"
+
+            [items.containers.loot_corpse.attributes]
+            container = true
+            container_slots = 8
+            "#,
+        );
+        arena.add_entity(2, "Orc", 0, 0, None);
+        arena.set_entity_attr(2, "race", Value::Str("Orc".into()));
+        arena.set_entity_attr(2, "mode", Value::Str("dead".into()));
+
+        assert!(drop_items_into_ruleset_loot_container(
+            &mut arena.ctx,
+            2,
+            ""
+        ));
+        assert_eq!(arena.ctx.map.items.len(), 1);
+        let contents = arena.ctx.map.items[0]
+            .container
+            .as_ref()
+            .expect("corpse contents");
+        assert_eq!(contents.len(), 1);
+        assert_eq!(
+            contents[0].attributes.get_str("ruleset_id"),
+            Some("orc_tooth")
+        );
+        assert_eq!(contents[0].stack_quantity(), 2);
+
+        assert!(drop_items_into_ruleset_loot_container(
+            &mut arena.ctx,
+            2,
+            ""
+        ));
+        assert_eq!(arena.ctx.map.items.len(), 1);
+    }
+
+    #[test]
+    fn killed_npc_rolls_race_loot_without_corpses() {
+        let mut arena = HeadlessRulesArena::with_rules(
+            r#"
+            [attributes.roles]
+            health = "HP"
+            max_health = "MAX_HP"
+
+            [loot.tables.orc_remains]
+            guaranteed = [
+                { item = "orc_tooth", quantity = 2 },
+                { item = "orc_hide" },
+            ]
+
+            [races.Orc]
+            loot = "orc_remains"
+
+            [items.materials.orc_tooth]
+            name = "Orc Tooth"
+            max_stack = 10
+
+            [items.materials.orc_hide]
+            name = "Orc Hide"
+            "#,
+        );
+        arena.add_entity(2, "Orc", 5, 0, None);
+        arena.set_entity_attr(2, "race", Value::Str("Orc".into()));
+        arena.ctx.map.entities[0].inventory = vec![None];
+
+        assert!(apply_damage_direct(
+            &mut arena.ctx,
+            2,
+            9,
+            10,
+            "physical",
+            None
+        ));
+
+        assert_eq!(arena.mode(2), "dead");
+        let carried = arena
+            .entity(2)
+            .iter_inventory()
+            .map(|(_, item)| item)
+            .collect::<Vec<_>>();
+        assert_eq!(carried.len(), 1);
+        assert_eq!(
+            carried[0].attributes.get_str("ruleset_id"),
+            Some("orc_tooth")
+        );
+        assert_eq!(carried[0].stack_quantity(), 2);
+        assert_eq!(arena.ctx.map.items.len(), 1);
+        assert_eq!(
+            arena.ctx.map.items[0].attributes.get_str("ruleset_id"),
+            Some("orc_hide")
+        );
+    }
+
     #[test]
     fn dead_npc_respawns_at_full_health_and_removes_corpse() {
         let mut arena = HeadlessRulesArena::with_rules(
@@ -6664,6 +6802,30 @@ pub fn run_server_named_fn(
     }
 }
 
+/// Run a host function for a character outside of a script, e.g. for the
+/// action nodes of behavior trees.
+pub fn run_entity_host_call(
+    region_ctx: &mut RegionCtx,
+    entity_id: u32,
+    name: &str,
+    args: &[VMValue],
+) -> Option<VMValue> {
+    let prev_entity_id = region_ctx.curr_entity_id;
+    let prev_item_id = region_ctx.curr_item_id;
+    let prev_scope = region_ctx.current_script_scope;
+    region_ctx.curr_entity_id = entity_id;
+    region_ctx.curr_item_id = None;
+    region_ctx.current_script_scope = ScriptScope::Entity;
+
+    let mut host = RegionHost { ctx: region_ctx };
+    let ret = host.on_host_call(name, args);
+
+    region_ctx.curr_entity_id = prev_entity_id;
+    region_ctx.curr_item_id = prev_item_id;
+    region_ctx.current_script_scope = prev_scope;
+    ret
+}
+
 // Run an event
 pub fn run_server_fn(
     exec: &mut Execution,
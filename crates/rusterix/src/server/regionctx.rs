@@ -1,10 +1,12 @@
 use crate::prelude::*;
+use crate::server::behavior_tree::BehaviorNode;
+use crate::server::perception::Awareness;
 use crate::vm::{Program, VMValue};
 use crate::{CollisionWorld, Entity, MapMini, PlayerCamera};
 use crossbeam_channel::{Receiver, Sender};
 use eldiron_ruleset::{
     ResolvedAction, ResolvedActionCatalogue, ResolvedActionEffect, ResolvedCondition,
-    ResolvedDerivedStat, ResolvedEquipmentPolicy, ResolvedInvocationScheme,
+    ResolvedDerivedStat, ResolvedEquipmentPolicy, ResolvedInvocationScheme, ResolvedQuest,
 };
 use std::collections::BTreeMap;
 use std::sync::{Arc, LazyLock, OnceLock, RwLock};
@@ -71,6 +73,8 @@ pub(crate) struct ResolvedRulesCache {
     conditions: BTreeMap<String, ResolvedCondition>,
     derived_stats: BTreeMap<String, ResolvedDerivedStat>,
     equipment: ResolvedEquipmentPolicy,
+    /// Quests are resolved on first use, `None` for unknown or invalid quests.
+    quests: FxHashMap<String, Option<Arc<ResolvedQuest>>>,
     error: Option<String>,
 }
 
@@ -152,6 +156,10 @@ pub struct RegionCtx {
 
     pub entity_proximity_alerts: FxHashMap<u32, f32>,
     pub item_proximity_alerts: FxHashMap<u32, f32>,
+    /// What each perceiving character currently sees or hears, by target id.
+    pub entity_awareness: FxHashMap<u32, FxHashMap<u32, Awareness>>,
+    /// Named behavior trees from the `[behavior_trees]` game configuration.
+    pub behavior_trees: FxHashMap<String, BehaviorNode>,
 
     pub entity_state_data: FxHashMap<u32, ValueContainer>,
     pub item_state_data: FxHashMap<u32, ValueContainer>,
@@ -268,6 +276,7 @@ impl RegionCtx {
                 .as_ref()
                 .map(|(_, _, _, equipment)| equipment.clone())
                 .unwrap_or_default(),
+            quests: FxHashMap::default(),
             error: resolved.err(),
         };
         cache.error.clone().map_or(Ok(()), Err)
@@ -288,6 +297,24 @@ impl RegionCtx {
         Ok(cache.catalogue.action(action_id).cloned())
     }
 
+    /// A ruleset quest, resolved once per region until the rules change.
+    pub(crate) fn resolved_quest(&self, quest_id: &str) -> Option<Arc<ResolvedQuest>> {
+        let quest_id = quest_id.trim();
+        if let Ok(cache) = self.resolved_rules.read()
+            && let Some(quest) = cache.quests.get(quest_id)
+        {
+            return quest.clone();
+        }
+        let quest = eldiron_ruleset::resolve_quest(&self.rules, quest_id)
+            .ok()
+            .flatten()
+            .map(Arc::new);
+        if let Ok(mut cache) = self.resolved_rules.write() {
+            cache.quests.insert(quest_id.to_string(), quest.clone());
+        }
+        quest
+    }
+
     pub fn resolved_condition(
         &self,
         condition_id: &str,
@@ -425,6 +452,21 @@ impl RegionCtx {
         }
     }
 
+    /// A copy of the complete world state.
+    pub fn world_state() -> ValueContainer {
+        WORLD_STATE
+            .read()
+            .map(|state| state.clone())
+            .unwrap_or_default()
+    }
+
+    /// Replace the complete world state.
+    pub fn set_world_state(world_state: ValueContainer) {
+        if let Ok(mut state) = WORLD_STATE.write() {
+            *state = world_state;
+        }
+    }
+
     pub fn get_world_value(key: &str) -> Option<Value> {
         WORLD_STATE
             .read()
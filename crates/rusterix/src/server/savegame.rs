@@ -0,0 +1,237 @@
+use crate::server::perception::Awareness;
+use crate::{Entity, Item, ValueContainer};
+use std::path::{Path, PathBuf};
+use theframework::prelude::*;
+
+/// The current save game format version. Bump this whenever the layout of
+/// [`SaveGame`] changes in a way older readers cannot handle.
+pub const SAVE_GAME_VERSION: u32 = 1;
+
+/// A snapshot of a running game which can be written to disk and restored
+/// into a freshly started [`crate::Server`].
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SaveGame {
+    pub version: u32,
+    /// The next free id of the global entity / item id generator.
+    pub next_id: u32,
+    /// The shared Eldrin world state (`set_world_value`).
+    #[serde(default)]
+    pub world_state: ValueContainer,
+    #[serde(default)]
+    pub regions: Vec<RegionSaveState>,
+}
+
+/// The runtime state of a single region instance.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RegionSaveState {
+    /// The region name, used to match the state to a region instance on load.
+    pub name: String,
+    pub map_id: Uuid,
+
+    pub time: TheTime,
+    pub ticks: i64,
+
+    pub entities: Vec<Entity>,
+    pub items: Vec<Item>,
+    /// Entity inventories are not part of the entity serialization and are
+    /// stored separately by entity id.
+    #[serde(default)]
+    pub inventories: FxHashMap<u32, Vec<Option<Item>>>,
+
+    /// Conditions, cooldowns and other script state by entity id.
+    #[serde(default)]
+    pub entity_state_data: FxHashMap<u32, ValueContainer>,
+    #[serde(default)]
+    pub item_state_data: FxHashMap<u32, ValueContainer>,
+    #[serde(default)]
+    pub entity_respawn_snapshots: FxHashMap<u32, Entity>,
+    #[serde(default)]
+    pub entity_proximity_alerts: FxHashMap<u32, f32>,
+    #[serde(default)]
+    pub item_proximity_alerts: FxHashMap<u32, f32>,
+    #[serde(default)]
+    pub entity_awareness: FxHashMap<u32, FxHashMap<u32, Awareness>>,
+    /// Pending timers: (id, tick, notification)
+    #[serde(default)]
+    pub notifications_entities: Vec<(u32, i64, String)>,
+    #[serde(default)]
+    pub notifications_items: Vec<(u32, i64, String)>,
+
+    /// The Eldrin region state (`set_region_value`).
+    #[serde(default)]
+    pub region_state: ValueContainer,
+}
+
+impl Default for SaveGame {
+    fn default() -> Self {
+        Self {
+            version: SAVE_GAME_VERSION,
+            next_id: 1,
+            world_state: ValueContainer::default(),
+            regions: vec![],
+        }
+    }
+}
+
+impl SaveGame {
+    /// Serialize the save game to JSON.
+    pub fn to_json(&self) -> Result<String, String> {
+        serde_json::to_string(self).map_err(|err| format!("Failed to write save game: {}", err))
+    }
+
+    /// Deserialize a save game from JSON and check the format version.
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let save: SaveGame = serde_json::from_str(json)
+            .map_err(|err| format!("Failed to read save game: {}", err))?;
+        if save.version == 0 || save.version > SAVE_GAME_VERSION {
+            return Err(format!(
+                "Unsupported save game version {} (expected <= {}).",
+                save.version, SAVE_GAME_VERSION
+            ));
+        }
+        Ok(save)
+    }
+
+    /// Write the save game to the given path, creating parent directories.
+    pub fn write(&self, path: &Path) -> Result<(), String> {
+        if let Some(parent) = path.parent()
+            && !parent.as_os_str().is_empty()
+        {
+            std::fs::create_dir_all(parent)
+                .map_err(|err| format!("Failed to create {}: {}", parent.display(), err))?;
+        }
+        std::fs::write(path, self.to_json()?)
+            .map_err(|err| format!("Failed to write {}: {}", path.display(), err))
+    }
+
+    /// Read a save game from the given path.
+    pub fn read(path: &Path) -> Result<Self, String> {
+        let json = std::fs::read_to_string(path)
+            .map_err(|err| format!("Failed to read {}: {}", path.display(), err))?;
+        Self::from_json(&json)
+    }
+
+    /// Get the state of the region with the given name.
+    pub fn region(&self, name: &str) -> Option<&RegionSaveState> {
+        self.regions.iter().find(|region| region.name == name)
+    }
+}
+
+/// Sanitize a save slot name into a file system friendly name.
+pub fn save_slot_name(slot: &str) -> String {
+    let name = slot
+        .trim()
+        .chars()
+        .map(|ch| {
+            if ch.is_ascii_alphanumeric() || ch == '-' || ch == '_' {
+                ch
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>();
+    if name.is_empty() {
+        "quicksave".into()
+    } else {
+        name
+    }
+}
+
+/// The path of a save slot for the given game file: `<dir>/saves/<stem>-<slot>.json`.
+pub fn save_slot_path(game_path: &Path, slot: &str) -> PathBuf {
+    let stem = game_path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_else(|| "game".into());
+    game_path
+        .parent()
+        .unwrap_or_else(|| Path::new(""))
+        .join("saves")
+        .join(format!("{}-{}.json", stem, save_slot_name(slot)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Value;
+
+    #[test]
+    fn save_game_round_trips_inventories_and_state() {
+        let mut entity = Entity {
+            id: 4,
+            ..Default::default()
+        };
+        entity.set_attribute("name", Value::Str("Hero".into()));
+        let mut sword = Item {
+            id: 9,
+            ..Default::default()
+        };
+        sword.attributes.set("name", Value::Str("Sword".into()));
+
+        let mut state = ValueContainer::default();
+        state.set("__condition_remaining:poisoned", Value::Float(3.5));
+
+        let mut save = SaveGame {
+            next_id: 10,
+            ..Default::default()
+        };
+        save.world_state.set("quest", Value::Int(2));
+        save.regions.push(RegionSaveState {
+            name: "Town".into(),
+            map_id: Uuid::new_v4(),
+            time: TheTime::default(),
+            ticks: 42,
+            entities: vec![entity],
+            items: vec![],
+            inventories: FxHashMap::from_iter([(4, vec![Some(sword), None])]),
+            entity_state_data: FxHashMap::from_iter([(4, state)]),
+            item_state_data: FxHashMap::default(),
+            entity_respawn_snapshots: FxHashMap::default(),
+            entity_proximity_alerts: FxHashMap::default(),
+            item_proximity_alerts: FxHashMap::default(),
+            entity_awareness: FxHashMap::default(),
+            notifications_entities: vec![(4, 50, "wake_up".into())],
+            notifications_items: vec![],
+            region_state: ValueContainer::default(),
+        });
+
+        let loaded = SaveGame::from_json(&save.to_json().unwrap()).unwrap();
+        assert_eq!(loaded.next_id, 10);
+        assert_eq!(loaded.world_state.get_int("quest"), Some(2));
+        let region = loaded.region("Town").unwrap();
+        assert_eq!(region.ticks, 42);
+        assert_eq!(
+            region.entities[0].get_attr_string("name").as_deref(),
+            Some("Hero")
+        );
+        assert_eq!(region.inventories[&4].len(), 2);
+        assert_eq!(
+            region.inventories[&4][0].as_ref().map(|item| item.id),
+            Some(9)
+        );
+        assert_eq!(
+            region.entity_state_data[&4].get_float("__condition_remaining:poisoned"),
+            Some(3.5)
+        );
+        assert_eq!(
+            region.notifications_entities,
+            vec![(4, 50, "wake_up".into())]
+        );
+    }
+
+    #[test]
+    fn save_game_rejects_future_versions() {
+        let save = SaveGame {
+            version: SAVE_GAME_VERSION + 1,
+            ..Default::default()
+        };
+        assert!(SaveGame::from_json(&save.to_json().unwrap()).is_err());
+    }
+
+    #[test]
+    fn save_slot_paths_live_next_to_the_game() {
+        let path = save_slot_path(Path::new("/games/quest.eldiron"), "slot 1");
+        assert_eq!(path, PathBuf::from("/games/saves/quest-slot_1.json"));
+        assert_eq!(save_slot_name(""), "quicksave");
+    }
+}
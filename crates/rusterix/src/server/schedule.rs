@@ -0,0 +1,268 @@
+use crate::server::entity::{EntityScheduleEntry, EntitySequenceState};
+use crate::server::region_host::{nearest_point_index, resolve_route_points};
+use crate::server::regionctx::RegionCtx;
+use crate::vm::VMValue;
+use crate::{Entity, EntityAction, Value};
+use vek::Vec2;
+
+/// The index of the schedule entry covering `minutes` since midnight. Later
+/// entries win when entries overlap.
+pub fn active_schedule_entry(schedule: &[EntityScheduleEntry], minutes: i32) -> Option<usize> {
+    schedule.iter().rposition(|entry| entry.contains(minutes))
+}
+
+/// Switch characters between their schedule entries as world time passes.
+///
+/// When an entry becomes active the character gets its `activity` attribute
+/// and a `schedule` event, then walks to the entry's sector, patrols its route
+/// or runs its sequence. Entries in another region move the character there
+/// through a pending entity transfer; the destination region starts the entry
+/// once the character arrives. Entries without a region belong to the
+/// character's home region.
+pub(crate) fn update_entity_schedules(ctx: &mut RegionCtx) {
+    let minutes = ctx.time.total_minutes();
+    let mut events = Vec::new();
+    let mut transfers = Vec::new();
+
+    // Take the entities out so routes and sectors can be resolved on the map.
+    let mut entities = std::mem::take(&mut ctx.map.entities);
+    for entity in entities.iter_mut() {
+        if entity.schedule.is_empty() || entity.is_player() || entity.get_mode() == "dead" {
+            continue;
+        }
+
+        let current = active_schedule_entry(&entity.schedule, minutes);
+        if current == entity.active_schedule {
+            if let Some(index) = current {
+                let entry = &entity.schedule[index];
+                let (wander, speed) = (entry.wander, entry.speed);
+                wander_on_arrival(entity, wander, speed);
+            }
+            continue;
+        }
+
+        let Some(index) = current else {
+            entity.active_schedule = None;
+            entity.set_attribute("activity", Value::Str(String::new()));
+            continue;
+        };
+        let entry = entity.schedule[index].clone();
+        let region = if entry.region.is_empty() {
+            &entity.home_region
+        } else {
+            &entry.region
+        };
+        if !region.is_empty() && !region.eq_ignore_ascii_case(&ctx.map.name) {
+            transfers.push((entity.id, region.clone(), entry.sector.clone()));
+            entity.action = EntityAction::Off;
+            entity.active_sequence = None;
+            continue;
+        }
+
+        entity.active_schedule = Some(index);
+        entity.set_attribute("activity", Value::Str(entry.activity.clone()));
+        events.push((
+            entity.id,
+            "schedule".to_string(),
+            VMValue::from_string(entry.activity.clone()),
+        ));
+        start_entry(&ctx.map, entity, &entry);
+    }
+
+    ctx.map.entities = entities;
+    ctx.pending_entity_transfers.extend(transfers);
+    ctx.to_execute_entity.extend(events);
+}
+
+fn start_entry(map: &crate::Map, entity: &mut Entity, entry: &EntityScheduleEntry) {
+    let speed = entry.speed.unwrap_or(1.0).max(0.0);
+    entity.action = EntityAction::Off;
+    entity.active_sequence = None;
+    entity.paused_sequence = None;
+
+    if !entry.sequence.is_empty() && entity.sequences.contains_key(&entry.sequence) {
+        entity.active_sequence = Some(EntitySequenceState {
+            name: entry.sequence.clone(),
+            step_index: 0,
+            wait_until_tick: None,
+        });
+    } else if !entry.route.is_empty() {
+        let position = entity.get_pos_xz();
+        let points = resolve_route_points(map, &entry.route, position);
+        if !points.is_empty() {
+            entity.set_attribute("route", Value::StrArray(entry.route.clone()));
+            entity.action = EntityAction::Patrol {
+                point_index: nearest_point_index(position, &points),
+                points,
+                route_wait: 1.0,
+                route_speed: speed,
+                route_mode: entity
+                    .attributes
+                    .get_str_default("route_mode", "loop".to_string())
+                    .to_ascii_lowercase(),
+                forward: true,
+                wait_until_tick: 0,
+            };
+        }
+    } else if !entry.sector.is_empty()
+        && let Some(center) = map.named_area_center_3d(&entry.sector)
+    {
+        let target = Vec2::new(center.x, center.z);
+        if entity.get_pos_xz().distance(target) > 0.1 {
+            entity.action = EntityAction::Goto(target, speed);
+        }
+    }
+    wander_on_arrival(entity, entry.wander, entry.speed);
+}
+
+/// Start random walking once a wandering entry reached its sector, like
+/// `random_walk_in_sector(1.0, speed, 4)`.
+fn wander_on_arrival(entity: &mut Entity, wander: bool, speed: Option<f32>) {
+    if wander && entity.action == EntityAction::Off && entity.active_sequence.is_none() {
+        entity.action = EntityAction::RandomWalkInSector(
+            1.0,
+            speed.unwrap_or(1.0).max(0.0),
+            4,
+            0,
+            Vec2::zero(),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::entity::EntitySequence;
+    use theframework::prelude::TheTime;
+
+    fn entry(from: i32, to: i32, activity: &str) -> EntityScheduleEntry {
+        EntityScheduleEntry {
+            from: from * 60,
+            to: to * 60,
+            activity: activity.into(),
+            ..Default::default()
+        }
+    }
+
+    fn shopkeeper_ctx() -> RegionCtx {
+        let mut ctx = RegionCtx::default();
+        ctx.map.name = "Town".into();
+        let mut entity = Entity::new();
+        entity.id = 1;
+        entity.home_region = "Town".into();
+        entity.schedule = vec![
+            EntityScheduleEntry {
+                sector: "Shop".into(),
+                wander: true,
+                ..entry(8, 18, "work")
+            },
+            EntityScheduleEntry {
+                region: "Village".into(),
+                sector: "Home".into(),
+                ..entry(18, 8, "sleep")
+            },
+        ];
+        ctx.map.entities.push(entity);
+        ctx
+    }
+
+    fn drain_events(ctx: &mut RegionCtx) -> Vec<(u32, String, String)> {
+        ctx.to_execute_entity
+            .drain(..)
+            .map(|(id, event, value)| (id, event, value.as_string().unwrap_or("").into()))
+            .collect()
+    }
+
+    #[test]
+    fn entries_switch_as_world_time_passes() {
+        let mut ctx = shopkeeper_ctx();
+        ctx.time = TheTime::new_time(9, 0).unwrap();
+        update_entity_schedules(&mut ctx);
+        assert_eq!(
+            drain_events(&mut ctx),
+            vec![(1, "schedule".into(), "work".into())]
+        );
+        let shopkeeper = &ctx.map.entities[0];
+        assert_eq!(shopkeeper.active_schedule, Some(0));
+        assert_eq!(shopkeeper.attributes.get_str("activity"), Some("work"));
+        assert!(matches!(
+            shopkeeper.action,
+            EntityAction::RandomWalkInSector(..)
+        ));
+
+        update_entity_schedules(&mut ctx);
+        assert!(drain_events(&mut ctx).is_empty());
+
+        ctx.time = TheTime::new_time(19, 0).unwrap();
+        update_entity_schedules(&mut ctx);
+        assert!(drain_events(&mut ctx).is_empty());
+        assert_eq!(
+            ctx.pending_entity_transfers,
+            vec![(1, "Village".into(), "Home".into())]
+        );
+
+        // The transfer delivers the shopkeeper to the village for the night.
+        let mut village = RegionCtx::default();
+        village.map.name = "Village".into();
+        village.map.entities = std::mem::take(&mut ctx.map.entities);
+        village.time = ctx.time;
+        update_entity_schedules(&mut village);
+        assert_eq!(
+            drain_events(&mut village),
+            vec![(1, "schedule".into(), "sleep".into())]
+        );
+        assert_eq!(village.map.entities[0].active_schedule, Some(1));
+        assert!(village.pending_entity_transfers.is_empty());
+
+        // The work entry has no region, so the morning leads back home.
+        village.time = TheTime::new_time(8, 0).unwrap();
+        update_entity_schedules(&mut village);
+        assert_eq!(
+            village.pending_entity_transfers,
+            vec![(1, "Town".into(), "Shop".into())]
+        );
+
+        let mut town = shopkeeper_ctx();
+        town.map.entities = std::mem::take(&mut village.map.entities);
+        town.time = village.time;
+        update_entity_schedules(&mut town);
+        assert_eq!(
+            drain_events(&mut town),
+            vec![(1, "schedule".into(), "work".into())]
+        );
+        assert_eq!(town.map.entities[0].active_schedule, Some(0));
+        assert!(town.pending_entity_transfers.is_empty());
+    }
+
+    #[test]
+    fn sequence_entries_start_and_gaps_clear_the_activity() {
+        let mut ctx = RegionCtx::default();
+        let mut entity = Entity::new();
+        entity.id = 1;
+        entity
+            .sequences
+            .insert("open_shop".into(), EntitySequence::default());
+        entity.schedule = vec![EntityScheduleEntry {
+            sequence: "open_shop".into(),
+            ..entry(8, 9, "opening")
+        }];
+        ctx.map.entities.push(entity);
+
+        ctx.time = TheTime::new_time(8, 30).unwrap();
+        update_entity_schedules(&mut ctx);
+        let entity = &ctx.map.entities[0];
+        assert_eq!(
+            entity
+                .active_sequence
+                .as_ref()
+                .map(|state| state.name.as_str()),
+            Some("open_shop")
+        );
+
+        ctx.time = TheTime::new_time(10, 0).unwrap();
+        update_entity_schedules(&mut ctx);
+        let entity = &ctx.map.entities[0];
+        assert_eq!(entity.active_schedule, None);
+        assert_eq!(entity.attributes.get_str("activity"), Some(""));
+    }
+}
@@ -63,6 +63,7 @@ pub enum Stmt {
         Box<Stmt>,
         Location,
     ),
+    ForIn(String, Box<Expr>, Box<Stmt>, Location),
     Import(Option<Module>, Location),
     FunctionDeclaration(FunctionD, Location),
     Print(Box<Expr>, Location),
@@ -96,6 +97,8 @@ pub enum Expr {
     ),
     FunctionCall(Box<Expr>, Vec<u8>, Vec<String>, Vec<Box<Expr>>, Location),
     Ternary(Box<Expr>, Box<Expr>, Box<Expr>, Location),
+    Index(Box<Expr>, Box<Expr>, Location),
+    IndexAssignment(String, Box<Expr>, AssignmentOperator, Box<Expr>, Location),
 }
 
 /// Assignment operators in the AST
@@ -378,6 +381,15 @@ pub trait Visitor {
         ctx: &mut Context,
     ) -> Result<ASTValue, RuntimeError>;
 
+    fn for_in_stmt(
+        &mut self,
+        name: &str,
+        iterable: &Expr,
+        body_stmt: &Stmt,
+        loc: &Location,
+        ctx: &mut Context,
+    ) -> Result<ASTValue, RuntimeError>;
+
     fn logical_expr(
         &mut self,
         left: &Expr,
@@ -395,6 +407,24 @@ pub trait Visitor {
         loc: &Location,
         ctx: &mut Context,
     ) -> Result<ASTValue, RuntimeError>;
+
+    fn index(
+        &mut self,
+        target: &Expr,
+        index: &Expr,
+        loc: &Location,
+        ctx: &mut Context,
+    ) -> Result<ASTValue, RuntimeError>;
+
+    fn index_assignment(
+        &mut self,
+        name: &str,
+        index: &Expr,
+        op: &AssignmentOperator,
+        expression: &Expr,
+        loc: &Location,
+        ctx: &mut Context,
+    ) -> Result<ASTValue, RuntimeError>;
 }
 
 impl Stmt {
@@ -411,6 +441,9 @@ impl Stmt {
             Stmt::For(init, cond, incr, body, loc) => {
                 visitor.for_stmt(init, cond, incr, body, loc, ctx)
             }
+            Stmt::ForIn(name, iterable, body, loc) => {
+                visitor.for_in_stmt(name, iterable, body, loc, ctx)
+            }
             Stmt::Print(expression, loc) => visitor.print(expression, loc, ctx),
             Stmt::Block(list, loc) => visitor.block(list, loc, ctx),
             Stmt::Expression(expression, loc) => visitor.expression(expression, loc, ctx),
@@ -459,6 +492,10 @@ impl Expr {
             Expr::Ternary(cond, then_expr, else_expr, loc) => {
                 visitor.ternary(cond, then_expr, else_expr, loc, ctx)
             }
+            Expr::Index(target, index, loc) => visitor.index(target, index, loc, ctx),
+            Expr::IndexAssignment(name, index, op, expr, loc) => {
+                visitor.index_assignment(name, index, op, expr, loc, ctx)
+            }
         }
     }
 
@@ -11,6 +11,8 @@ pub enum ASTValue {
     Float4(Box<Expr>, Box<Expr>, Box<Expr>, Box<Expr>),
     String(String),
     Function(String, Vec<ASTValue>, Box<ASTValue>),
+    List(Vec<Box<Expr>>),
+    Map(Vec<(Box<Expr>, Box<Expr>)>),
 }
 
 impl ASTValue {
@@ -31,6 +33,8 @@ impl ASTValue {
             ASTValue::Float3(_, _, _) => true,
             ASTValue::Float4(_, _, _, _) => true,
             ASTValue::String(s) => !s.is_empty(),
+            ASTValue::List(items) => !items.is_empty(),
+            ASTValue::Map(entries) => !entries.is_empty(),
             _ => false,
         }
     }
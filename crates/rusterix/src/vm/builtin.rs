@@ -65,6 +65,10 @@ impl Default for Builtins {
         b.insert("sqrt", 1, NodeOp::Sqrt);
         b.insert("log", 1, NodeOp::Log);
         b.insert("pow", 2, NodeOp::Pow);
+        b.insert("len", 1, NodeOp::Len);
+        b.insert("push", 2, NodeOp::ListPush);
+        b.insert("remove", 2, NodeOp::Remove);
+        b.insert("keys", 1, NodeOp::Keys);
         // print is variadic; arity handled in compiler
         b.insert("print", 0, NodeOp::Print(0));
         b.insert(
@@ -452,6 +456,70 @@ impl Default for Builtins {
                 argc: 0,
             },
         );
+        b.insert(
+            "save_game",
+            1,
+            NodeOp::HostCall {
+                name: "save_game".into(),
+                argc: 1,
+            },
+        );
+        b.insert(
+            "load_game",
+            1,
+            NodeOp::HostCall {
+                name: "load_game".into(),
+                argc: 1,
+            },
+        );
+        b.insert(
+            "start_quest",
+            2,
+            NodeOp::HostCall {
+                name: "start_quest".into(),
+                argc: 2,
+            },
+        );
+        b.insert(
+            "advance_quest",
+            2,
+            NodeOp::HostCall {
+                name: "advance_quest".into(),
+                argc: 2,
+            },
+        );
+        b.insert(
+            "complete_quest",
+            2,
+            NodeOp::HostCall {
+                name: "complete_quest".into(),
+                argc: 2,
+            },
+        );
+        b.insert(
+            "fail_quest",
+            2,
+            NodeOp::HostCall {
+                name: "fail_quest".into(),
+                argc: 2,
+            },
+        );
+        b.insert(
+            "quest_status",
+            2,
+            NodeOp::HostCall {
+                name: "quest_status".into(),
+                argc: 2,
+            },
+        );
+        b.insert(
+            "quest_stage",
+            2,
+            NodeOp::HostCall {
+                name: "quest_stage".into(),
+                argc: 2,
+            },
+        );
         b.insert(
             "set_audio_bus_volume",
             2,
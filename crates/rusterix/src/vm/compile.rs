@@ -68,6 +68,30 @@ impl CompileVisitor {
         matches!(name, "world" | "region") && !field_path.is_empty()
     }
 
+    /// Emit the store op for a local or global variable. Returns false if unknown.
+    fn emit_store_variable(&self, name: &str, ctx: &mut Context) -> bool {
+        if let Some(index) = self.locals.get_index_of(name) {
+            ctx.emit(NodeOp::StoreLocal(index));
+        } else if let Some(&index) = ctx.globals.get(name) {
+            ctx.emit(NodeOp::StoreGlobal(index as usize));
+        } else {
+            return false;
+        }
+        true
+    }
+
+    /// Emit the load op for a local or global variable. Returns false if unknown.
+    fn emit_load_variable(&self, name: &str, ctx: &mut Context) -> bool {
+        if let Some(index) = self.locals.get_index_of(name) {
+            ctx.emit(NodeOp::LoadLocal(index));
+        } else if let Some(&index) = ctx.globals.get(name) {
+            ctx.emit(NodeOp::LoadGlobal(index as usize));
+        } else {
+            return false;
+        }
+        true
+    }
+
     fn context_path(name: &str, field_path: &[String]) -> String {
         let mut path = String::from(name);
         for segment in field_path {
@@ -591,6 +615,19 @@ impl Visitor for CompileVisitor {
             ASTValue::String(s) => {
                 ctx.emit(NodeOp::Push(VMValue::from_string(s.clone())));
             }
+            ASTValue::List(items) => {
+                for item in items {
+                    _ = item.accept(self, ctx)?;
+                }
+                ctx.emit(NodeOp::NewList(items.len()));
+            }
+            ASTValue::Map(entries) => {
+                for (key, value) in entries {
+                    _ = key.accept(self, ctx)?;
+                    _ = value.accept(self, ctx)?;
+                }
+                ctx.emit(NodeOp::NewMap(entries.len()));
+            }
             _ => {}
         };
 
@@ -894,6 +931,46 @@ impl Visitor for CompileVisitor {
                             loc,
                         ));
                     }
+                } else if name == "push" || name == "remove" {
+                    // Collections are values: write the result back when called on a variable.
+                    if args.len() == 2 {
+                        let not_assignable = match args[0].as_ref() {
+                            Expr::Variable(_, swz, field_path, _) => {
+                                !swz.is_empty() || !field_path.is_empty()
+                            }
+                            Expr::Index(..) => true,
+                            _ => false,
+                        };
+                        if not_assignable {
+                            return Err(RuntimeError::new(
+                                format!(
+                                    "The first argument of '{}' must be a variable, copy the collection into a variable first",
+                                    name,
+                                ),
+                                loc,
+                            ));
+                        }
+                        for arg in args {
+                            _ = arg.accept(self, ctx)?;
+                        }
+                        ctx.emit(func.op.clone());
+                        if let Expr::Variable(var, swz, field_path, _) = args[0].as_ref()
+                            && swz.is_empty()
+                            && field_path.is_empty()
+                        {
+                            ctx.emit(NodeOp::Dup);
+                            self.emit_store_variable(var, ctx);
+                        }
+                    } else {
+                        return Err(RuntimeError::new(
+                            format!(
+                                "Wrong amount of arguments for '{}', expected '2' got '{}'",
+                                name,
+                                args.len(),
+                            ),
+                            loc,
+                        ));
+                    }
                 } else if name == "patrol" {
                     if args.len() <= 2 {
                         for arg in args {
@@ -1195,6 +1272,45 @@ impl Visitor for CompileVisitor {
         Ok(ASTValue::None)
     }
 
+    fn for_in_stmt(
+        &mut self,
+        name: &str,
+        iterable: &Expr,
+        body_stmt: &Stmt,
+        loc: &Location,
+        ctx: &mut Context,
+    ) -> Result<ASTValue, RuntimeError> {
+        Self::emit_debug_line(ctx, loc);
+        if self.in_function && !self.locals.contains(name) {
+            self.locals.insert(name.to_string());
+        }
+
+        let mut store_code = vec![];
+        ctx.add_custom_target();
+        Self::emit_debug_value(ctx, loc, name);
+        if !self.emit_store_variable(name, ctx) {
+            return Err(RuntimeError::new(
+                format!("Unknown identifier '{}'", name),
+                loc,
+            ));
+        }
+        if let Some(code) = ctx.take_last_custom_target() {
+            store_code = code;
+        }
+
+        let mut body_code = vec![];
+        ctx.add_custom_target();
+        body_stmt.accept(self, ctx)?;
+        if let Some(code) = ctx.take_last_custom_target() {
+            body_code = code;
+        }
+
+        _ = iterable.accept(self, ctx)?;
+        ctx.emit(NodeOp::ForIn(store_code, body_code));
+
+        Ok(ASTValue::None)
+    }
+
     fn while_stmt(
         &mut self,
         _cond: &Expr,
@@ -1251,6 +1367,58 @@ impl Visitor for CompileVisitor {
         Ok(ASTValue::None)
     }
 
+    fn index(
+        &mut self,
+        target: &Expr,
+        index: &Expr,
+        _loc: &Location,
+        ctx: &mut Context,
+    ) -> Result<ASTValue, RuntimeError> {
+        _ = target.accept(self, ctx)?;
+        _ = index.accept(self, ctx)?;
+        ctx.emit(NodeOp::GetIndex);
+
+        Ok(ASTValue::None)
+    }
+
+    fn index_assignment(
+        &mut self,
+        name: &str,
+        index: &Expr,
+        op: &AssignmentOperator,
+        expression: &Expr,
+        loc: &Location,
+        ctx: &mut Context,
+    ) -> Result<ASTValue, RuntimeError> {
+        Self::emit_debug_line(ctx, loc);
+        if !self.emit_load_variable(name, ctx) {
+            return Err(RuntimeError::new(
+                format!("Unknown identifier '{}'", name),
+                loc,
+            ));
+        }
+        _ = index.accept(self, ctx)?; // t, i
+        if *op != AssignmentOperator::Assign {
+            // t[i] = t[i] (op) rhs
+            self.emit_load_variable(name, ctx);
+            _ = index.accept(self, ctx)?;
+            ctx.emit(NodeOp::GetIndex); // t, i, a
+        }
+        _ = expression.accept(self, ctx)?;
+        match op {
+            AssignmentOperator::Assign => {}
+            AssignmentOperator::AddAssign => ctx.emit(NodeOp::Add),
+            AssignmentOperator::SubtractAssign => ctx.emit(NodeOp::Sub),
+            AssignmentOperator::MultiplyAssign => ctx.emit(NodeOp::Mul),
+            AssignmentOperator::DivideAssign => ctx.emit(NodeOp::Div),
+        }
+        ctx.emit(NodeOp::SetIndex); // t'
+        Self::emit_debug_value(ctx, loc, name);
+        self.emit_store_variable(name, ctx);
+
+        Ok(ASTValue::None)
+    }
+
     fn logical_expr(
         &mut self,
         left: &Expr,
@@ -35,7 +35,7 @@ pub use self::{
     parser::Parser,
     renderbuffer::RenderBuffer,
     scanner::{Scanner, Token, TokenType},
-    value::VMValue,
+    value::{VMCollection, VMValue},
 };
 
 use rustc_hash::FxHashMap;
@@ -382,6 +382,91 @@ mod tests {
         );
     }
 
+    #[test]
+    fn list_literal_index_and_len() {
+        let mut script = VM::default();
+        let result = script.execute_string(
+            r#"let items = [4, 5, 6]; items[1] + items[-1] + len(items);"#,
+            &ThePalette::default(),
+        );
+        assert_eq!(result.unwrap().x, 14.0);
+    }
+
+    #[test]
+    fn map_literal_assignment_and_keys() {
+        let mut script = VM::default();
+        let result = script.execute_string(
+            r#"
+            let prices = { "sword": 10, "shield": 4 };
+            prices["shield"] += 2;
+            prices["potion"] = 1;
+            format("{} {} {}", keys(prices), prices["shield"], prices["missing"]);
+            "#,
+            &ThePalette::default(),
+        );
+        assert_eq!(
+            result.unwrap().as_string(),
+            Some("[sword, shield, potion] 6 0")
+        );
+    }
+
+    #[test]
+    fn for_in_push_and_remove_inside_function() {
+        let mut script = VM::default();
+        let module = script
+            .parse_str(
+                r#"
+                fn total(event, value) {
+                    let queue = [];
+                    push(queue, 1);
+                    push(queue, 2);
+                    push(queue, 3);
+                    remove(queue, 0);
+                    let sum = 0;
+                    for item in queue {
+                        sum += item;
+                    }
+                    return sum * 10 + len(queue);
+                }
+                "#,
+            )
+            .unwrap();
+        script.compile(&module).unwrap();
+
+        let func_index = script
+            .context
+            .program
+            .user_functions_name_map
+            .get("total")
+            .copied()
+            .unwrap();
+        let mut exec = Execution::new(script.context.globals.len());
+        exec.reset(script.context.globals.len());
+        let args = [VMValue::zero(), VMValue::zero()];
+        let result = exec.execute_function(&args, func_index, &script.context.program);
+        assert_eq!(result.x, 52.0);
+    }
+
+    #[test]
+    fn push_and_remove_reject_non_variable_targets() {
+        for source in [
+            "fn f(event, value) { push(value.items, 1); }",
+            "fn f(event, value) { let lists = [[]]; remove(lists[0], 0); }",
+        ] {
+            let mut script = VM::default();
+            let module = script.parse_str(source).unwrap();
+            assert!(script.compile(&module).is_err(), "{}", source);
+        }
+    }
+
+    #[test]
+    fn id_lists_keep_legacy_fields() {
+        let value = VMValue::from_id_list(&[4, 7, 9]);
+        assert_eq!(value.as_list().map(|items| items.len()), Some(3));
+        assert_eq!((value.x, value.y, value.z), (4.0, 7.0, 3.0));
+        assert_eq!(value.string.as_deref(), Some("4,7,9"));
+    }
+
     #[test]
     fn format_variadic() {
         let mut script = VM::default();
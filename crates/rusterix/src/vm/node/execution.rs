@@ -257,6 +257,22 @@ impl Execution {
                     }
                 }
             }
+            NodeOp::ForIn(store, body) => {
+                let iterable = self.stack.pop().unwrap_or_else(VMValue::zero);
+                let base = self.stack.len();
+                for value in iterable.iter_values() {
+                    self.stack.push(value);
+                    self.execute(store, program);
+                    self.stack.truncate(base);
+
+                    self.execute(body, program);
+                    self.stack.truncate(base);
+
+                    if self.return_value.is_some() {
+                        break;
+                    }
+                }
+            }
             NodeOp::If {
                 then_code,
                 else_code,
@@ -503,7 +519,9 @@ impl Execution {
             NodeOp::Eq => {
                 let b = self.stack.pop().unwrap();
                 let a = self.stack.pop().unwrap();
-                let equals = if let (Some(sa), Some(sb)) =
+                let equals = if a.collection.is_some() || b.collection.is_some() {
+                    a.collection == b.collection
+                } else if let (Some(sa), Some(sb)) =
                     (vm_value_payload_string(&a), vm_value_payload_string(&b))
                 {
                     sa == sb
@@ -516,7 +534,9 @@ impl Execution {
             NodeOp::Ne => {
                 let b = self.stack.pop().unwrap();
                 let a = self.stack.pop().unwrap();
-                let not_equals = if let (Some(sa), Some(sb)) =
+                let not_equals = if a.collection.is_some() || b.collection.is_some() {
+                    a.collection != b.collection
+                } else if let (Some(sa), Some(sb)) =
                     (vm_value_payload_string(&a), vm_value_payload_string(&b))
                 {
                     sa != sb
@@ -713,6 +733,52 @@ impl Execution {
             NodeOp::Time => {
                 self.stack.push(self.time.clone());
             }
+            NodeOp::NewList(count) => {
+                let start = self.stack.len().saturating_sub(*count);
+                let items = self.stack.split_off(start);
+                self.stack.push(VMValue::from_list(items));
+            }
+            NodeOp::NewMap(count) => {
+                let start = self.stack.len().saturating_sub(*count * 2);
+                let pairs = self.stack.split_off(start);
+                let entries = pairs
+                    .chunks_exact(2)
+                    .map(|pair| (pair[0].to_key(), pair[1].clone()))
+                    .collect();
+                self.stack.push(VMValue::from_map(entries));
+            }
+            NodeOp::GetIndex => {
+                let index = self.stack.pop().unwrap();
+                let target = self.stack.pop().unwrap();
+                self.stack.push(target.get_index(&index));
+            }
+            NodeOp::SetIndex => {
+                let value = self.stack.pop().unwrap();
+                let index = self.stack.pop().unwrap();
+                let mut target = self.stack.pop().unwrap();
+                target.set_index(&index, value);
+                self.stack.push(target);
+            }
+            NodeOp::Len => {
+                let a = self.stack.pop().unwrap();
+                self.stack.push(VMValue::from_u32(a.len() as u32));
+            }
+            NodeOp::ListPush => {
+                let value = self.stack.pop().unwrap();
+                let mut target = self.stack.pop().unwrap();
+                target.push_item(value);
+                self.stack.push(target);
+            }
+            NodeOp::Remove => {
+                let index = self.stack.pop().unwrap();
+                let mut target = self.stack.pop().unwrap();
+                target.remove_index(&index);
+                self.stack.push(target);
+            }
+            NodeOp::Keys => {
+                let a = self.stack.pop().unwrap();
+                self.stack.push(a.keys());
+            }
         }
     }
 
@@ -803,6 +869,22 @@ impl Execution {
                     }
                 }
             }
+            NodeOp::ForIn(store, body) => {
+                let iterable = self.stack.pop().unwrap_or_else(VMValue::zero);
+                let base = self.stack.len();
+                for value in iterable.iter_values() {
+                    self.stack.push(value);
+                    self.execute_host(store, program, host);
+                    self.stack.truncate(base);
+
+                    self.execute_host(body, program, host);
+                    self.stack.truncate(base);
+
+                    if self.return_value.is_some() {
+                        break;
+                    }
+                }
+            }
             NodeOp::If {
                 line,
                 then_code,
@@ -960,7 +1042,9 @@ impl Execution {
 }
 
 fn vm_value_to_string(val: &VMValue) -> String {
-    if let Some(s) = vm_value_payload_string(val) {
+    if val.collection.is_some() {
+        val.to_string()
+    } else if let Some(s) = vm_value_payload_string(val) {
         s.to_string()
     } else if val.y == val.x && val.z == val.x {
         format!("{}", val.x)
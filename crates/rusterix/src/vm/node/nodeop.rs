@@ -28,6 +28,8 @@ pub enum NodeOp {
         else_code: Option<Vec<NodeOp>>,
     },
     For(Vec<NodeOp>, Vec<NodeOp>, Vec<NodeOp>, Vec<NodeOp>),
+    /// Iterate the collection on the stack: (store, body).
+    ForIn(Vec<NodeOp>, Vec<NodeOp>),
     Push(VMValue),
     FunctionCall(u8, u8, usize),
     Return,
@@ -93,4 +95,13 @@ pub enum NodeOp {
     Not,
     Neg,
     Time,
+    // Collections
+    NewList(usize),
+    NewMap(usize),
+    GetIndex,
+    SetIndex,
+    Len,
+    ListPush,
+    Remove,
+    Keys,
 }
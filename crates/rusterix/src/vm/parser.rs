@@ -374,6 +374,9 @@ impl Parser {
 
     fn for_statement(&mut self) -> Result<Stmt, ParseError> {
         let line = self.current_line;
+        if self.check(TokenType::Identifier) && self.check_next(TokenType::In) {
+            return self.for_in_statement();
+        }
         self.consume(TokenType::LeftParen, "Expect '(' after 'for'", line)?;
 
         let mut inits: Vec<Box<Stmt>> = vec![];
@@ -436,6 +439,35 @@ impl Parser {
         ))
     }
 
+    /// `for item in collection { ... }`, iterates list elements or map keys.
+    fn for_in_statement(&mut self) -> Result<Stmt, ParseError> {
+        let line = self.current_line;
+        let var_name = self
+            .consume(TokenType::Identifier, "Expect loop variable name", line)?
+            .lexeme;
+        self.consume(TokenType::In, "Expect 'in' after loop variable", line)?;
+
+        if self.scope == VariableScope::Global {
+            _ = self.verifier.define_var(&var_name, false)?;
+            if !self.globals_map.contains_key(&var_name) {
+                self.globals_map
+                    .insert(var_name.clone(), self.globals_map.len() as u32);
+            }
+        } else if !self.locals_map.contains_key(&var_name) {
+            self.locals_map.insert(var_name.clone(), None);
+        }
+
+        let iterable = self.expression()?;
+        let body = self.statement()?;
+
+        Ok(Stmt::ForIn(
+            var_name,
+            Box::new(iterable),
+            Box::new(body),
+            self.create_loc(line),
+        ))
+    }
+
     fn expression_statement(&mut self) -> Result<Stmt, ParseError> {
         let value: Expr = self.expression()?;
         let line = self.current_line;
@@ -582,121 +614,70 @@ impl Parser {
     fn assignment(&mut self) -> Result<Expr, ParseError> {
         let expr = self.or()?;
 
-        if self.check(TokenType::Plus)
+        let op = if self.check(TokenType::Plus)
             && self.match_token(vec![TokenType::Plus])
             && self.match_token(vec![TokenType::Equal])
         {
-            let equals = self.previous().unwrap();
-            let value = self.assignment()?;
-
-            if let Expr::Variable(name, swizzle, field_path, _loc) = expr {
-                return Ok(Expr::VariableAssignment(
-                    name,
-                    AssignmentOperator::AddAssign,
-                    swizzle.clone(),
-                    field_path.clone(),
-                    Box::new(value),
-                    self.create_loc(equals.line),
-                ));
-            }
-
-            return Err(ParseError::new(
-                format!("Invalid assignment target: '{:?}'", equals.lexeme),
-                equals.line,
-                &self.path,
-            ));
+            AssignmentOperator::AddAssign
         } else if self.check(TokenType::Minus)
             && self.match_token(vec![TokenType::Minus])
             && self.match_token(vec![TokenType::Equal])
         {
-            let equals = self.previous().unwrap();
-            let value = self.assignment()?;
-
-            if let Expr::Variable(name, swizzle, field_path, _loc) = expr {
-                return Ok(Expr::VariableAssignment(
-                    name,
-                    AssignmentOperator::SubtractAssign,
-                    swizzle.clone(),
-                    field_path.clone(),
-                    Box::new(value),
-                    self.create_loc(equals.line),
-                ));
-            }
-
-            return Err(ParseError::new(
-                format!("Invalid assignment target: '{:?}'", equals.lexeme),
-                equals.line,
-                &self.path,
-            ));
+            AssignmentOperator::SubtractAssign
         } else if self.check(TokenType::Star)
             && self.match_token(vec![TokenType::Star])
             && self.match_token(vec![TokenType::Equal])
         {
-            let equals = self.previous().unwrap();
-            let value = self.assignment()?;
-
-            if let Expr::Variable(name, swizzle, field_path, _loc) = expr {
-                return Ok(Expr::VariableAssignment(
-                    name,
-                    AssignmentOperator::MultiplyAssign,
-                    swizzle.clone(),
-                    field_path.clone(),
-                    Box::new(value),
-                    self.create_loc(equals.line),
-                ));
-            }
-
-            return Err(ParseError::new(
-                format!("Invalid assignment target: '{:?}'", equals.lexeme),
-                equals.line,
-                &self.path,
-            ));
+            AssignmentOperator::MultiplyAssign
         } else if self.check(TokenType::Slash)
             && self.match_token(vec![TokenType::Slash])
             && self.match_token(vec![TokenType::Equal])
         {
-            let equals = self.previous().unwrap();
-            let value = self.assignment()?;
-
-            if let Expr::Variable(name, swizzle, field_path, _loc) = expr {
-                return Ok(Expr::VariableAssignment(
-                    name,
-                    AssignmentOperator::DivideAssign,
-                    swizzle.clone(),
-                    field_path.clone(),
-                    Box::new(value),
-                    self.create_loc(equals.line),
-                ));
-            }
-
-            return Err(ParseError::new(
-                format!("Invalid assignment target: '{:?}'", equals.lexeme),
-                equals.line,
-                &self.path,
-            ));
+            AssignmentOperator::DivideAssign
         } else if self.match_token(vec![TokenType::Equal]) {
-            let equals = self.previous().unwrap();
-            let value = self.assignment()?;
+            AssignmentOperator::Assign
+        } else {
+            return Ok(expr);
+        };
 
-            if let Expr::Variable(name, swizzle, field_path, _loc) = expr {
-                return Ok(Expr::VariableAssignment(
-                    name,
-                    AssignmentOperator::Assign,
-                    swizzle.clone(),
-                    field_path.clone(),
-                    Box::new(value),
-                    self.create_loc(equals.line),
-                ));
+        let equals = self.previous().unwrap();
+        let value = self.assignment()?;
+
+        match expr {
+            Expr::Variable(name, swizzle, field_path, _loc) => Ok(Expr::VariableAssignment(
+                name,
+                op,
+                swizzle,
+                field_path,
+                Box::new(value),
+                self.create_loc(equals.line),
+            )),
+            Expr::Index(target, index, _loc) => {
+                if let Expr::Variable(name, swizzle, field_path, _) = *target
+                    && swizzle.is_empty()
+                    && field_path.is_empty()
+                {
+                    Ok(Expr::IndexAssignment(
+                        name,
+                        index,
+                        op,
+                        Box::new(value),
+                        self.create_loc(equals.line),
+                    ))
+                } else {
+                    Err(ParseError::new(
+                        "Only variables can be indexed in an assignment",
+                        equals.line,
+                        &self.path,
+                    ))
+                }
             }
-
-            return Err(ParseError::new(
+            _ => Err(ParseError::new(
                 format!("Invalid assignment target: '{:?}'", equals.lexeme),
                 equals.line,
                 &self.path,
-            ));
+            )),
         }
-
-        Ok(expr)
     }
 
     fn or(&mut self) -> Result<Expr, ParseError> {
@@ -869,6 +850,11 @@ impl Parser {
         loop {
             if self.match_token(vec![TokenType::LeftParen]) {
                 expr = self.finish_call(expr)?;
+            } else if self.match_token(vec![TokenType::LeftBracket]) {
+                let line = self.current_line;
+                let index = self.expression()?;
+                self.consume(TokenType::RightBracket, "Expect ']' after index", line)?;
+                expr = Expr::Index(Box::new(expr), Box::new(index), self.create_loc(line));
             } else {
                 break;
             }
@@ -1079,6 +1065,51 @@ impl Parser {
                     ))
                 }
             }
+            TokenType::LeftBracket => {
+                self.advance();
+                let mut items = vec![];
+                while !self.check(TokenType::RightBracket) && !self.is_at_end() {
+                    items.push(Box::new(self.expression()?));
+                    if !self.match_token(vec![TokenType::Comma]) {
+                        break;
+                    }
+                }
+                self.consume(
+                    TokenType::RightBracket,
+                    "Expected ']' after list elements",
+                    token.line,
+                )?;
+                Ok(Expr::Value(
+                    ASTValue::List(items),
+                    vec![],
+                    vec![],
+                    self.create_loc(token.line),
+                ))
+            }
+            TokenType::LeftBrace => {
+                self.advance();
+                let mut entries = vec![];
+                while !self.check(TokenType::RightBrace) && !self.is_at_end() {
+                    let key = self.expression()?;
+                    self.consume(TokenType::Colon, "Expected ':' after map key", token.line)?;
+                    let value = self.expression()?;
+                    entries.push((Box::new(key), Box::new(value)));
+                    if !self.match_token(vec![TokenType::Comma]) {
+                        break;
+                    }
+                }
+                self.consume(
+                    TokenType::RightBrace,
+                    "Expected '}' after map entries",
+                    token.line,
+                )?;
+                Ok(Expr::Value(
+                    ASTValue::Map(entries),
+                    vec![],
+                    vec![],
+                    self.create_loc(token.line),
+                ))
+            }
             TokenType::LeftParen => {
                 self.advance();
                 let expr = self.expression()?;
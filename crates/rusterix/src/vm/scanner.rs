@@ -7,6 +7,8 @@ pub enum TokenType {
     RightParen,
     LeftBrace,
     RightBrace,
+    LeftBracket,
+    RightBracket,
     Comma,
     Dot,
     Minus,
@@ -174,6 +176,8 @@ impl Scanner {
             b')' => self.make_token(TokenType::RightParen),
             b'{' => self.make_token(TokenType::LeftBrace),
             b'}' => self.make_token(TokenType::RightBrace),
+            b'[' => self.make_token(TokenType::LeftBracket),
+            b']' => self.make_token(TokenType::RightBracket),
             b'$' => self.make_token(TokenType::Dollar),
             b';' => self.make_token(TokenType::Semicolon),
             b',' => self.make_token(TokenType::Comma),
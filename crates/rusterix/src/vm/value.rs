@@ -1,13 +1,24 @@
 use crate::value::Value;
+use indexmap::IndexMap;
 use std::ops::{Add, Div, Mul, Neg, Sub};
+use std::sync::Arc;
 use vek::Vec3;
 
+/// A list or map held by a [`VMValue`]. Collections are shared on clone and
+/// copied on write, so passing them around the VM stays cheap.
+#[derive(Clone, Debug, PartialEq)]
+pub enum VMCollection {
+    List(Vec<VMValue>),
+    Map(IndexMap<String, VMValue>),
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct VMValue {
     pub x: f32,
     pub y: f32,
     pub z: f32,
     pub string: Option<String>,
+    pub collection: Option<Arc<VMCollection>>,
 }
 
 impl VMValue {
@@ -17,6 +28,7 @@ impl VMValue {
             y,
             z,
             string: None,
+            collection: None,
         }
     }
 
@@ -27,6 +39,7 @@ impl VMValue {
             y,
             z,
             string: Some(s.into()),
+            collection: None,
         }
     }
 
@@ -36,6 +49,7 @@ impl VMValue {
             y: v,
             z: v,
             string: None,
+            collection: None,
         }
     }
 
@@ -70,6 +84,7 @@ impl VMValue {
             y: v.y,
             z: v.z,
             string: None,
+            collection: None,
         }
     }
 
@@ -83,6 +98,7 @@ impl VMValue {
             y: 0.0,
             z: 0.0,
             string: Some(s.into()),
+            collection: None,
         }
     }
 
@@ -90,6 +106,169 @@ impl VMValue {
         self.string.as_deref()
     }
 
+    /// Create a list value.
+    pub fn from_list(items: Vec<VMValue>) -> Self {
+        Self::from_collection(VMCollection::List(items))
+    }
+
+    /// Create a map value, keys keep their insertion order.
+    pub fn from_map(entries: IndexMap<String, VMValue>) -> Self {
+        Self::from_collection(VMCollection::Map(entries))
+    }
+
+    pub fn from_collection(collection: VMCollection) -> Self {
+        Self {
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+            string: None,
+            collection: Some(Arc::new(collection)),
+        }
+    }
+
+    /// Create a list of entity / item ids. For older scripts the first two ids
+    /// are also stored in x and y, the count in z (`.count`) and all ids as a
+    /// comma-separated string.
+    pub fn from_id_list(ids: &[u32]) -> Self {
+        let mut value = Self::from_list(ids.iter().map(|id| Self::from_u32(*id)).collect());
+        value.x = ids.first().copied().unwrap_or(0) as f32;
+        value.y = ids.get(1).copied().unwrap_or(0) as f32;
+        value.z = ids.len() as f32;
+        value.string = Some(
+            ids.iter()
+                .map(|id| id.to_string())
+                .collect::<Vec<_>>()
+                .join(","),
+        );
+        value
+    }
+
+    pub fn as_collection(&self) -> Option<&VMCollection> {
+        self.collection.as_deref()
+    }
+
+    pub fn as_list(&self) -> Option<&[VMValue]> {
+        match self.as_collection()? {
+            VMCollection::List(items) => Some(items),
+            VMCollection::Map(_) => None,
+        }
+    }
+
+    pub fn as_map(&self) -> Option<&IndexMap<String, VMValue>> {
+        match self.as_collection()? {
+            VMCollection::Map(entries) => Some(entries),
+            VMCollection::List(_) => None,
+        }
+    }
+
+    /// Mutable access to the collection, cloning it if it is shared.
+    pub fn collection_mut(&mut self) -> Option<&mut VMCollection> {
+        self.collection.as_mut().map(Arc::make_mut)
+    }
+
+    /// The key used when this value indexes a map.
+    pub fn to_key(&self) -> String {
+        match self.as_string() {
+            Some(s) if !Self::is_type_hint_str(s) => s.to_string(),
+            _ => Self::format_scalar(self.x),
+        }
+    }
+
+    /// The number of elements of a collection, or the length of a string.
+    pub fn len(&self) -> usize {
+        match self.as_collection() {
+            Some(VMCollection::List(items)) => items.len(),
+            Some(VMCollection::Map(entries)) => entries.len(),
+            None => match self.as_string() {
+                Some(s) if !Self::is_type_hint_str(s) => s.chars().count(),
+                _ => 0,
+            },
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Look up a list element by index (negative indices count from the end)
+    /// or a map entry by key. Returns zero when the element does not exist.
+    pub fn get_index(&self, index: &VMValue) -> VMValue {
+        match self.as_collection() {
+            Some(VMCollection::List(items)) => Self::list_index(items.len(), index.x)
+                .and_then(|i| items.get(i))
+                .cloned()
+                .unwrap_or_else(Self::zero),
+            Some(VMCollection::Map(entries)) => entries
+                .get(&index.to_key())
+                .cloned()
+                .unwrap_or_else(Self::zero),
+            None => Self::zero(),
+        }
+    }
+
+    /// Set a list element or map entry. Writing one past the end of a list appends.
+    pub fn set_index(&mut self, index: &VMValue, value: VMValue) {
+        match self.collection_mut() {
+            Some(VMCollection::List(items)) => {
+                if let Some(i) = Self::list_index(items.len(), index.x) {
+                    items[i] = value;
+                } else if index.x as usize == items.len() {
+                    items.push(value);
+                }
+            }
+            Some(VMCollection::Map(entries)) => {
+                entries.insert(index.to_key(), value);
+            }
+            None => {}
+        }
+    }
+
+    /// Append a value to a list.
+    pub fn push_item(&mut self, value: VMValue) {
+        if let Some(VMCollection::List(items)) = self.collection_mut() {
+            items.push(value);
+        }
+    }
+
+    /// Remove a list element by index or a map entry by key.
+    pub fn remove_index(&mut self, index: &VMValue) -> Option<VMValue> {
+        match self.collection_mut()? {
+            VMCollection::List(items) => {
+                let i = Self::list_index(items.len(), index.x)?;
+                Some(items.remove(i))
+            }
+            VMCollection::Map(entries) => entries.shift_remove(&index.to_key()),
+        }
+    }
+
+    /// The keys of a map, or the indices of a list, as a list.
+    pub fn keys(&self) -> VMValue {
+        match self.as_collection() {
+            Some(VMCollection::Map(entries)) => {
+                Self::from_list(entries.keys().map(Self::from_string).collect())
+            }
+            Some(VMCollection::List(items)) => {
+                Self::from_list((0..items.len()).map(|i| Self::from_u32(i as u32)).collect())
+            }
+            None => Self::from_list(vec![]),
+        }
+    }
+
+    /// The values iterated by `for x in value`: list elements or map keys.
+    pub fn iter_values(&self) -> Vec<VMValue> {
+        match self.as_collection() {
+            Some(VMCollection::List(items)) => items.clone(),
+            Some(VMCollection::Map(entries)) => entries.keys().map(Self::from_string).collect(),
+            None => vec![],
+        }
+    }
+
+    fn list_index(len: usize, index: f32) -> Option<usize> {
+        let index = index as i64;
+        let index = if index < 0 { len as i64 + index } else { index };
+        (0..len as i64).contains(&index).then_some(index as usize)
+    }
+
     pub fn from_value(value: &Value) -> Self {
         match value {
             Value::NoValue => VMValue::zero(),
@@ -102,13 +281,20 @@ impl VMValue {
             Value::Vec3(v) => VMValue::new(v[0], v[1], v[2]),
             Value::Vec4(v) => VMValue::new(v[0], v[1], v[2]),
             Value::Str(s) => VMValue::from_string(s.clone()),
+            Value::StrArray(items) => {
+                VMValue::from_list(items.iter().map(VMValue::from_string).collect())
+            }
             _ => VMValue::zero(),
         }
     }
 
     /// Convert into a generic runtime Value.
     pub fn to_value(&self) -> Value {
-        if let Some(s) = self.as_string() {
+        if let Some(list) = self.as_list() {
+            Value::StrArray(list.iter().map(|v| v.to_string()).collect())
+        } else if self.collection.is_some() {
+            Value::Str(self.to_string())
+        } else if let Some(s) = self.as_string() {
             Value::Str(s.to_string())
         } else if self.x == self.y && self.x == self.z {
             Value::Float(self.x)
@@ -119,6 +305,9 @@ impl VMValue {
 
     /// Convert into a Value using an optional type hint and/or inline string tag (e.g. "bool").
     pub fn to_value_with_hint(&self, hint: Option<&Value>) -> Value {
+        if self.collection.is_some() {
+            return self.to_value();
+        }
         // String payload can act as an explicit type hint.
         if let Some(s) = self.as_string() {
             let s_trim = s.trim();
@@ -194,7 +383,9 @@ impl VMValue {
     }
 
     pub fn is_truthy(&self) -> bool {
-        if let Some(s) = &self.string {
+        if self.collection.is_some() {
+            !self.is_empty()
+        } else if let Some(s) = &self.string {
             !s.is_empty()
         } else {
             self.x != 0.0 || self.y != 0.0 || self.z != 0.0
@@ -292,6 +483,21 @@ impl VMValue {
 
 impl std::fmt::Display for VMValue {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.as_collection() {
+            Some(VMCollection::List(items)) => {
+                let items: Vec<String> = items.iter().map(|v| v.to_string()).collect();
+                return write!(f, "[{}]", items.join(", "));
+            }
+            Some(VMCollection::Map(entries)) => {
+                let entries: Vec<String> = entries
+                    .iter()
+                    .map(|(k, v)| format!("{}: {}", k, v))
+                    .collect();
+                return write!(f, "{{{}}}", entries.join(", "));
+            }
+            None => {}
+        }
+
         if let Some(s) = &self.string {
             let tag = s.trim();
             let tag_l = tag.to_ascii_lowercase();
@@ -347,6 +553,9 @@ impl Add for VMValue {
     type Output = VMValue;
 
     fn add(self, rhs: VMValue) -> Self::Output {
+        if let (Some(a), Some(b)) = (self.as_list(), rhs.as_list()) {
+            return VMValue::from_list(a.iter().chain(b).cloned().collect());
+        }
         let (ax, ay, az) = (self.x, self.y, self.z);
         let (bx, by, bz) = (rhs.x, rhs.y, rhs.z);
         match (
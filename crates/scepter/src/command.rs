@@ -1,3 +1,4 @@
+use crate::ScepterPlan;
 use serde::{Deserialize, Serialize};
 
 /// A command capability that an adapter can use for permissions and UI warnings.
@@ -14,6 +15,8 @@ pub enum ScepterCapability {
     AttributeWrite,
     ScriptRead,
     ScriptWrite,
+    RulesRead,
+    RulesWrite,
     Preview,
     Undo,
     Export,
@@ -387,6 +390,80 @@ pub struct AttributesPatch {
     pub validate: bool,
 }
 
+/// A new character or item template.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TemplateCreate {
+    pub name: String,
+    /// Eldrin source. Defaults to the source Creator gives new templates.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    /// TOML data. Defaults to the data Creator gives new templates.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<String>,
+    /// Values set under [attributes] after `data` is applied.
+    #[serde(default, skip_serializing_if = "serde_json::Map::is_empty")]
+    pub attributes: serde_json::Map<String, serde_json::Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TemplateList {
+    /// List the instances placed in this region instead of the templates.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub region: Option<RegionRef>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DialogGet {
+    pub target: ScriptTarget,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DialogPatch {
+    pub target: ScriptTarget,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start: Option<String>,
+    /// Dialog nodes to insert or replace, keyed by node name.
+    #[serde(default, skip_serializing_if = "serde_json::Map::is_empty")]
+    pub nodes: serde_json::Map<String, serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub remove: Vec<String>,
+    #[serde(default)]
+    pub validate: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RulesGet {
+    /// Dotted path such as `combat.damage`. Returns the whole ruleset when empty.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    /// Read the resolved ruleset (official ruleset plus project overrides)
+    /// instead of the project overrides only.
+    #[serde(default)]
+    pub effective: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RulesPatch {
+    /// Values to set, keyed by dotted path.
+    #[serde(default, skip_serializing_if = "serde_json::Map::is_empty")]
+    pub values: serde_json::Map<String, serde_json::Value>,
+    /// Dotted paths to remove.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub remove: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LocalePatch {
+    /// Locale name such as `en`.
+    pub locale: String,
+    /// Translations to set, keyed by dotted message key.
+    #[serde(default, skip_serializing_if = "serde_json::Map::is_empty")]
+    pub values: serde_json::Map<String, serde_json::Value>,
+    /// Message keys to remove.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub remove: Vec<String>,
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct GeometryCreateRoom {
     pub region: RegionRef,
@@ -480,10 +557,34 @@ pub enum ScepterCommand {
     AttributesGet(AttributesGet),
     #[serde(rename = "attributes.patch")]
     AttributesPatch(AttributesPatch),
+    #[serde(rename = "character.create")]
+    CharacterCreate(TemplateCreate),
+    #[serde(rename = "character.list")]
+    CharacterList(TemplateList),
+    #[serde(rename = "item.create")]
+    ItemCreate(TemplateCreate),
+    #[serde(rename = "item.list")]
+    ItemList(TemplateList),
+    #[serde(rename = "dialog.get")]
+    DialogGet(DialogGet),
+    #[serde(rename = "dialog.patch")]
+    DialogPatch(DialogPatch),
+    #[serde(rename = "rules.get")]
+    RulesGet(RulesGet),
+    #[serde(rename = "rules.patch")]
+    RulesPatch(RulesPatch),
+    #[serde(rename = "locale.patch")]
+    LocalePatch(LocalePatch),
     #[serde(rename = "geometry.create_room")]
     GeometryCreateRoom(GeometryCreateRoom),
     #[serde(rename = "geometry.place_builder_asset")]
     GeometryPlaceBuilderAsset(GeometryPlaceBuilderAsset),
+    #[serde(rename = "plan.validate")]
+    PlanValidate(ScepterPlan),
+    #[serde(rename = "plan.preview")]
+    PlanPreview(ScepterPlan),
+    #[serde(rename = "plan.apply")]
+    PlanApply(ScepterPlan),
 }
 
 impl ScepterCommand {
@@ -523,8 +624,29 @@ impl ScepterCommand {
             Self::ScriptValidate(_) => "script.validate",
             Self::AttributesGet(_) => "attributes.get",
             Self::AttributesPatch(_) => "attributes.patch",
+            Self::CharacterCreate(_) => "character.create",
+            Self::CharacterList(_) => "character.list",
+            Self::ItemCreate(_) => "item.create",
+            Self::ItemList(_) => "item.list",
+            Self::DialogGet(_) => "dialog.get",
+            Self::DialogPatch(_) => "dialog.patch",
+            Self::RulesGet(_) => "rules.get",
+            Self::RulesPatch(_) => "rules.patch",
+            Self::LocalePatch(_) => "locale.patch",
             Self::GeometryCreateRoom(_) => "geometry.create_room",
             Self::GeometryPlaceBuilderAsset(_) => "geometry.place_builder_asset",
+            Self::PlanValidate(_) => "plan.validate",
+            Self::PlanPreview(_) => "plan.preview",
+            Self::PlanApply(_) => "plan.apply",
         }
     }
+
+    /// True for the `plan.*` commands, which carry a nested plan and can not
+    /// appear inside another plan.
+    pub fn is_plan(&self) -> bool {
+        matches!(
+            self,
+            Self::PlanValidate(_) | Self::PlanPreview(_) | Self::PlanApply(_)
+        )
+    }
 }
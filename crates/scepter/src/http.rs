@@ -0,0 +1,35 @@
+//! Request helpers shared by the Scepter HTTP endpoints of Creator and the
+//! headless server.
+
+/// Split an HTTP request line into its method and path.
+pub fn parse_request_line(line: &str) -> Option<(&str, &str)> {
+    let mut parts = line.split_whitespace();
+    let method = parts.next()?;
+    let path = parts.next()?;
+    Some((method, path))
+}
+
+/// The request path without its query string.
+pub fn path_without_query(path: &str) -> &str {
+    path.split_once('?').map(|(path, _)| path).unwrap_or(path)
+}
+
+/// The message of a `/ping` request: the `message` query key, the `message`
+/// field of a JSON body, or the plain body.
+pub fn ping_message(path: &str, body: &str) -> String {
+    if let Some(query) = path.split_once('?').map(|(_, query)| query) {
+        for part in query.split('&') {
+            if let Some(value) = part.strip_prefix("message=") {
+                return value.replace('+', " ");
+            }
+        }
+    }
+
+    if let Ok(value) = serde_json::from_str::<serde_json::Value>(body)
+        && let Some(message) = value.get("message").and_then(serde_json::Value::as_str)
+    {
+        return message.to_string();
+    }
+
+    body.trim().to_string()
+}
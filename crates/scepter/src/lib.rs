@@ -2,12 +2,19 @@
 //!
 //! This crate intentionally starts as a small, platform-neutral core. Creator,
 //! Eldrin automation, JSON-RPC, CLI tools, and AI integrations should all be
-//! adapters over these command and Lorebook definitions.
+//! adapters over these command and Lorebook definitions. The `project` feature
+//! adds the project summaries both Creator and the headless server report.
 
 mod command;
+mod http;
 mod lorebook;
 mod plan;
+#[cfg(feature = "project")]
+mod summary;
 
 pub use command::*;
+pub use http::*;
 pub use lorebook::*;
 pub use plan::*;
+#[cfg(feature = "project")]
+pub use summary::*;
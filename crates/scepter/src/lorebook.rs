@@ -629,6 +629,208 @@ pub fn built_in_commands() -> Vec<ScepterCommandMeta> {
                 "validate": true
             }
         })]),
+        ScepterCommandMeta::new(
+            "character.create",
+            "Create a character template (an NPC class). Source and data default to Creator's new character template.",
+        )
+        .params(template_create_params("character"))
+        .capabilities(vec![ProjectWrite, ScriptWrite, AttributeWrite])
+        .undoable()
+        .examples(vec![json!({
+            "command": "character.create",
+            "params": {
+                "name": "Harbor Guard",
+                "attributes": {
+                    "faction": "dock_watch",
+                    "hp": 12
+                }
+            }
+        })]),
+        ScepterCommandMeta::new(
+            "character.list",
+            "List character templates, or the characters placed in a region.",
+        )
+        .params(template_list_params())
+        .capabilities(vec![ProjectRead])
+        .examples(vec![json!({
+            "command": "character.list",
+            "params": { "region": { "name": "Harbor" } }
+        })]),
+        ScepterCommandMeta::new(
+            "item.create",
+            "Create an item template. Source and data default to Creator's new item template.",
+        )
+        .params(template_create_params("item"))
+        .capabilities(vec![ProjectWrite, ScriptWrite, AttributeWrite])
+        .undoable()
+        .examples(vec![json!({
+            "command": "item.create",
+            "params": {
+                "name": "Rusty Key",
+                "attributes": {
+                    "worth": 2,
+                    "slot": "belt"
+                }
+            }
+        })]),
+        ScepterCommandMeta::new(
+            "item.list",
+            "List item templates, or the items placed in a region.",
+        )
+        .params(template_list_params())
+        .capabilities(vec![ProjectRead])
+        .examples(vec![json!({
+            "command": "item.list",
+            "params": {}
+        })]),
+        ScepterCommandMeta::new(
+            "dialog.get",
+            "Read the [dialog] tree of a character template or instance, with its start node and nodes.",
+        )
+        .params(vec![ScepterParamMeta::new(
+            "target",
+            "Character target. Add region to select a placed instance.",
+            true,
+            "ScriptTarget",
+        )])
+        .capabilities(vec![AttributeRead])
+        .examples(vec![json!({
+            "command": "dialog.get",
+            "params": {
+                "target": { "kind": "character", "name": "Harbor Guard" }
+            }
+        })]),
+        ScepterCommandMeta::new(
+            "dialog.patch",
+            "Insert, replace, or remove dialog nodes and set the start node of a character's [dialog] tree.",
+        )
+        .params(vec![
+            ScepterParamMeta::new(
+                "target",
+                "Character target. Add region to select a placed instance.",
+                true,
+                "ScriptTarget",
+            ),
+            ScepterParamMeta::new(
+                "start",
+                "Node opened by dialog(entity, \"\").",
+                false,
+                "string",
+            ),
+            ScepterParamMeta::new(
+                "nodes",
+                "Nodes to insert or replace, keyed by name. Each node has text and a choices array of { label, next, event, end, if, unless } tables.",
+                false,
+                "object",
+            ),
+            ScepterParamMeta::new("remove", "Node names to remove.", false, "string[]"),
+            ScepterParamMeta::new(
+                "validate",
+                "Reject the patch if the start node or a choice's next node does not exist.",
+                false,
+                "boolean",
+            ),
+        ])
+        .capabilities(vec![AttributeRead, AttributeWrite])
+        .previewable()
+        .undoable()
+        .examples(vec![json!({
+            "command": "dialog.patch",
+            "params": {
+                "target": { "kind": "character", "name": "Harbor Guard" },
+                "start": "greeting",
+                "nodes": {
+                    "greeting": {
+                        "text": "{dialog.guard.greeting}",
+                        "choices": [
+                            { "label": "{dialog.guard.work}", "next": "work" },
+                            { "label": "{dialog.goodbye}", "end": true }
+                        ]
+                    },
+                    "work": {
+                        "text": "{dialog.guard.work_text}",
+                        "choices": [
+                            { "label": "{dialog.accept}", "event": "accept_rat_quest", "end": true },
+                            { "label": "{dialog.back}", "next": "greeting" }
+                        ]
+                    }
+                },
+                "validate": true
+            }
+        })]),
+        ScepterCommandMeta::new(
+            "rules.get",
+            "Read the project rules overrides or the resolved ruleset, optionally at a dotted path.",
+        )
+        .params(vec![
+            ScepterParamMeta::new(
+                "path",
+                "Dotted path such as combat.damage.",
+                false,
+                "string",
+            ),
+            ScepterParamMeta::new(
+                "effective",
+                "Read the resolved ruleset (official ruleset plus project overrides) instead of the project overrides.",
+                false,
+                "boolean",
+            ),
+        ])
+        .capabilities(vec![RulesRead])
+        .examples(vec![json!({
+            "command": "rules.get",
+            "params": { "path": "progression", "effective": true }
+        })]),
+        ScepterCommandMeta::new(
+            "rules.patch",
+            "Set or remove values in the project rules by dotted path. The resolved ruleset is validated and the patch is rejected when it has errors.",
+        )
+        .params(vec![
+            ScepterParamMeta::new(
+                "values",
+                "Values to set, keyed by dotted path. JSON values are converted to TOML.",
+                false,
+                "object",
+            ),
+            ScepterParamMeta::new("remove", "Dotted paths to remove.", false, "string[]"),
+        ])
+        .capabilities(vec![RulesRead, RulesWrite])
+        .previewable()
+        .undoable()
+        .examples(vec![json!({
+            "command": "rules.patch",
+            "params": {
+                "values": {
+                    "progression.max_level": 20
+                }
+            }
+        })]),
+        ScepterCommandMeta::new(
+            "locale.patch",
+            "Set or remove translations of one locale in the project locales.",
+        )
+        .params(vec![
+            ScepterParamMeta::new("locale", "Locale name such as en.", true, "string"),
+            ScepterParamMeta::new(
+                "values",
+                "Translations keyed by dotted message key, for example dialog.guard.greeting.",
+                false,
+                "object",
+            ),
+            ScepterParamMeta::new("remove", "Message keys to remove.", false, "string[]"),
+        ])
+        .capabilities(vec![ProjectWrite])
+        .undoable()
+        .examples(vec![json!({
+            "command": "locale.patch",
+            "params": {
+                "locale": "en",
+                "values": {
+                    "dialog.guard.greeting": "Halt. State your business at the docks.",
+                    "dialog.guard.work": "Any work for me?"
+                }
+            }
+        })]),
         ScepterCommandMeta::new(
             "geometry.create_room",
             "Create a high-level 3D room primitive with optional floor and wall tiles.",
@@ -643,5 +845,108 @@ pub fn built_in_commands() -> Vec<ScepterCommandMeta> {
         .capabilities(vec![RegionWrite, ProjectRead])
         .previewable()
         .undoable(),
+        ScepterCommandMeta::new(
+            "plan.validate",
+            "Check every command of a plan without changing the project: regions and tiles must resolve and scripts must compile. Commands are checked in order against the result of the commands before them.",
+        )
+        .params(plan_params())
+        .capabilities(vec![ProjectRead, Preview])
+        .previewable()
+        .examples(vec![plan_example("plan.validate")]),
+        ScepterCommandMeta::new(
+            "plan.preview",
+            "Dry-run a plan and report the regions, tiles, and scripts it would change. The project is not modified.",
+        )
+        .params(plan_params())
+        .capabilities(vec![ProjectRead, Preview])
+        .previewable()
+        .examples(vec![plan_example("plan.preview")]),
+        ScepterCommandMeta::new(
+            "plan.apply",
+            "Validate and apply a plan atomically. If any command fails nothing is changed; otherwise the whole plan becomes a single undo step.",
+        )
+        .params(plan_params())
+        .capabilities(vec![ProjectWrite, Undo])
+        .previewable()
+        .undoable()
+        .examples(vec![plan_example("plan.apply")]),
+    ]
+}
+
+fn template_create_params(kind: &str) -> Vec<ScepterParamMeta> {
+    vec![
+        ScepterParamMeta::new(
+            "name",
+            format!("Unique {kind} template name."),
+            true,
+            "string",
+        ),
+        ScepterParamMeta::new(
+            "source",
+            "Eldrin source. Defaults to Creator's template source.",
+            false,
+            "string",
+        ),
+        ScepterParamMeta::new(
+            "data",
+            "TOML data. Defaults to Creator's template data.",
+            false,
+            "string",
+        ),
+        ScepterParamMeta::new(
+            "attributes",
+            "Values set under [attributes] after data is applied.",
+            false,
+            "object",
+        ),
+    ]
+}
+
+fn template_list_params() -> Vec<ScepterParamMeta> {
+    vec![ScepterParamMeta::new(
+        "region",
+        "List the instances placed in this region instead of the templates.",
+        false,
+        "RegionRef",
+    )]
+}
+
+fn plan_params() -> Vec<ScepterParamMeta> {
+    vec![
+        ScepterParamMeta::new("name", "Optional plan name.", false, "string"),
+        ScepterParamMeta::new("description", "Optional plan description.", false, "string"),
+        ScepterParamMeta::new(
+            "commands",
+            "Commands to run in order. plan.*, project.undo and project.redo are not allowed inside a plan.",
+            true,
+            "ScepterCommand[]",
+        ),
     ]
 }
+
+fn plan_example(command: &str) -> serde_json::Value {
+    json!({
+        "command": command,
+        "params": {
+            "name": "Small Stone Room",
+            "commands": [
+                {
+                    "command": "region.paint_rect",
+                    "params": {
+                        "region": { "name": "Harbor" },
+                        "tile": { "style": "stone", "kind": "floor" },
+                        "rect": [0, 0, 8, 6]
+                    }
+                },
+                {
+                    "command": "region.paint_outline",
+                    "params": {
+                        "region": { "name": "Harbor" },
+                        "tile": { "alias": "stone_wall" },
+                        "rect": [0, 0, 8, 6]
+                    }
+                }
+            ]
+        }
+    })
+}
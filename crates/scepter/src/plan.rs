@@ -71,6 +71,18 @@ mod tests {
             "tileset.import_batch",
             "script.validate",
             "geometry.create_room",
+            "character.create",
+            "character.list",
+            "item.create",
+            "item.list",
+            "dialog.get",
+            "dialog.patch",
+            "rules.get",
+            "rules.patch",
+            "locale.patch",
+            "plan.validate",
+            "plan.preview",
+            "plan.apply",
         ] {
             assert!(
                 lorebook.describe_command(command).is_some(),
@@ -100,4 +112,20 @@ mod tests {
         assert_eq!(json["name"], "Small Stone Room");
         assert_eq!(json["commands"][0]["command"], "region.paint_rect");
     }
+
+    #[test]
+    fn plan_commands_carry_the_plan_as_params() {
+        let mut plan = ScepterPlan::new("Floor");
+        plan.push(ScepterCommand::RegionList);
+        let command = ScepterCommand::PlanApply(plan);
+
+        let json = serde_json::to_value(&command).unwrap();
+        assert_eq!(json["command"], "plan.apply");
+        assert_eq!(json["params"]["name"], "Floor");
+        assert_eq!(json["params"]["commands"][0]["command"], "region.list");
+
+        let decoded: ScepterCommand = serde_json::from_value(json).unwrap();
+        assert!(decoded.is_plan());
+        assert_eq!(decoded, command);
+    }
 }
@@ -0,0 +1,555 @@
+//! JSON summaries of project tiles, pixel sources and regions, shared by the
+//! Creator Scepter endpoints and the headless Scepter server.
+
+use rusterix::{PixelSource, TileRole, Value};
+use serde_json::json;
+use shared::prelude::{Project, Region};
+use std::collections::HashMap;
+use theframework::prelude::Uuid;
+
+const AUTHORING_NOTES: &str = "2D origin uses x right, negative y up, positive y down";
+
+/// Notes on the 2D authoring conventions, included in project and region snapshots.
+pub fn authoring_notes() -> serde_json::Value {
+    json!({
+        "primary_2d_surface_source_key": "source",
+        "coordinate_system": AUTHORING_NOTES,
+        "ceiling_source": "screen/button selected-state legacy usage; not current 2D map authoring",
+        "terrain": "deprecated in current form; defer Scepter terrain commands until the replacement terrain system exists",
+    })
+}
+
+/// Describe a project tile: alias, role, frames and procedural tags.
+pub fn tile_summary(project: &Project, tile_id: &Uuid) -> Option<serde_json::Value> {
+    project.tiles.get(tile_id).map(|tile| {
+        let first_frame = tile.textures.first().map(|texture| {
+            json!({
+                "width": texture.width,
+                "height": texture.height,
+            })
+        });
+
+        json!({
+            "id": tile.id.to_string(),
+            "alias": tile.alias,
+            "role": tile.role.to_string(),
+            "blocking": tile.blocking,
+            "frame_count": tile.textures.len(),
+            "first_frame": first_frame,
+            "procedural": {
+                "style": tile.procedural.style,
+                "kind": tile.procedural.kind,
+                "weight": tile.procedural.weight,
+            },
+        })
+    })
+}
+
+/// Describe a pixel source, including the tile it resolves to.
+pub fn source_summary(project: &Project, source: &PixelSource) -> serde_json::Value {
+    let mut summary = match source {
+        PixelSource::Off => json!({ "kind": "off" }),
+        PixelSource::TileId(tile_id) => json!({
+            "kind": "tile_id",
+            "tile_id": tile_id.to_string(),
+        }),
+        PixelSource::TileGroup(group_id) => {
+            let group = project.tile_groups.get(group_id);
+            json!({
+                "kind": "tile_group",
+                "group_id": group_id.to_string(),
+                "group": group.map(|group| json!({
+                    "id": group.id.to_string(),
+                    "name": group.name,
+                    "width": group.width,
+                    "height": group.height,
+                    "member_count": group.members.len(),
+                    "tags": group.tags,
+                })),
+            })
+        }
+        PixelSource::TileGroupMember {
+            group_id,
+            member_index,
+        } => {
+            let member = project
+                .tile_groups
+                .get(group_id)
+                .and_then(|group| group.members.get(*member_index as usize));
+            json!({
+                "kind": "tile_group_member",
+                "group_id": group_id.to_string(),
+                "member_index": member_index,
+                "tile_id": member.map(|member| member.tile_id.to_string()),
+                "member": member.map(|member| json!({
+                    "x": member.x,
+                    "y": member.y,
+                })),
+            })
+        }
+        PixelSource::ProceduralTile(tile_id) => json!({
+            "kind": "procedural_tile",
+            "tile_id": tile_id.to_string(),
+        }),
+        PixelSource::PaletteIndex(index) => json!({
+            "kind": "palette_index",
+            "index": index,
+        }),
+        PixelSource::MaterialId(material_id) => json!({
+            "kind": "material_id",
+            "material_id": material_id.to_string(),
+        }),
+        PixelSource::Sequence(name) => json!({
+            "kind": "sequence",
+            "name": name,
+        }),
+        PixelSource::EntityTile(entity_id, tile_index) => json!({
+            "kind": "entity_tile",
+            "entity_id": entity_id,
+            "tile_index": tile_index,
+        }),
+        PixelSource::ItemTile(item_id, tile_index) => json!({
+            "kind": "item_tile",
+            "item_id": item_id,
+            "tile_index": tile_index,
+        }),
+        PixelSource::Color(color) => json!({
+            "kind": "color",
+            "rgba": color.to_u8_array(),
+        }),
+        PixelSource::LegacyShapeFXGraphId(graph_id) => json!({
+            "kind": "legacy_shape_fx_graph_id",
+            "graph_id": graph_id.to_string(),
+        }),
+        PixelSource::StaticTileIndex(index) => json!({
+            "kind": "static_tile_index",
+            "index": index,
+        }),
+        PixelSource::DynamicTileIndex(index) => json!({
+            "kind": "dynamic_tile_index",
+            "index": index,
+        }),
+        PixelSource::Pixel(_) => json!({
+            "kind": "pixel",
+        }),
+    };
+
+    if let Some(tile_id) = resolved_tile_id(project, source)
+        && let Some(tile) = tile_summary(project, &tile_id)
+        && let Some(object) = summary.as_object_mut()
+    {
+        object.insert("resolved_tile".to_string(), tile);
+    }
+
+    summary
+}
+
+/// The project tile a pixel source draws, if it draws one.
+pub fn resolved_tile_id(project: &Project, source: &PixelSource) -> Option<Uuid> {
+    match source {
+        PixelSource::TileId(tile_id)
+        | PixelSource::ProceduralTile(tile_id)
+        | PixelSource::MaterialId(tile_id) => Some(*tile_id),
+        PixelSource::TileGroupMember {
+            group_id,
+            member_index,
+        } => project
+            .tile_groups
+            .get(group_id)
+            .and_then(|group| group.members.get(*member_index as usize))
+            .map(|member| member.tile_id),
+        _ => None,
+    }
+}
+
+/// The ASCII overview character of a pixel source, from its tile kind or role.
+pub fn source_overview_char(project: &Project, source: Option<&PixelSource>) -> char {
+    let Some(source) = source else {
+        return ' ';
+    };
+    let Some(tile_id) = resolved_tile_id(project, source) else {
+        return ' ';
+    };
+    let Some(tile) = project.tiles.get(&tile_id) else {
+        return '?';
+    };
+
+    match tile.procedural.kind.as_str() {
+        "entrance" => 'E',
+        "exit" => 'X',
+        "wall" => '#',
+        "floor" => '.',
+        "door" => 'D',
+        _ => match tile.role {
+            TileRole::Water => '~',
+            TileRole::Mountain => '^',
+            TileRole::Road => '=',
+            TileRole::Nature => {
+                if tile.blocking {
+                    'T'
+                } else {
+                    ','
+                }
+            }
+            TileRole::ManMade | TileRole::Dungeon => {
+                if tile.blocking {
+                    '#'
+                } else {
+                    '.'
+                }
+            }
+            _ => '?',
+        },
+    }
+}
+
+/// The vertex bounds of a region map as (min_x, max_x, min_y, max_y).
+pub fn map_bounds(map: &rusterix::Map) -> Option<(f32, f32, f32, f32)> {
+    if map.vertices.is_empty() {
+        return None;
+    }
+    let min_x = map
+        .vertices
+        .iter()
+        .map(|vertex| vertex.x)
+        .fold(f32::INFINITY, f32::min);
+    let max_x = map
+        .vertices
+        .iter()
+        .map(|vertex| vertex.x)
+        .fold(f32::NEG_INFINITY, f32::max);
+    let min_y = map
+        .vertices
+        .iter()
+        .map(|vertex| vertex.y)
+        .fold(f32::INFINITY, f32::min);
+    let max_y = map
+        .vertices
+        .iter()
+        .map(|vertex| vertex.y)
+        .fold(f32::NEG_INFINITY, f32::max);
+    Some((min_x, max_x, min_y, max_y))
+}
+
+fn sort_by_count_desc(entries: &mut [serde_json::Value]) {
+    entries.sort_by(|a, b| {
+        b["count"]
+            .as_u64()
+            .unwrap_or_default()
+            .cmp(&a["count"].as_u64().unwrap_or_default())
+    });
+}
+
+/// A compact AI-oriented summary of a 2D region with an ASCII overview.
+pub fn region_summary(
+    project: &Project,
+    region: &Region,
+    include_ascii: bool,
+) -> serde_json::Value {
+    let bounds = map_bounds(&region.map);
+
+    let mut layer_counts: HashMap<String, usize> = HashMap::new();
+    let mut sector_source_counts: HashMap<Uuid, usize> = HashMap::new();
+    let mut linedef_source_counts: HashMap<Uuid, usize> = HashMap::new();
+    let mut role_counts: HashMap<String, (usize, usize)> = HashMap::new();
+    let mut kind_counts: HashMap<String, usize> = HashMap::new();
+    let mut off_sector_count = 0usize;
+    let mut named_sectors = Vec::new();
+    let mut procedural_sectors: HashMap<String, usize> = HashMap::new();
+
+    for sector in &region.map.sectors {
+        *layer_counts
+            .entry(
+                sector
+                    .layer
+                    .map(|layer| layer.to_string())
+                    .unwrap_or_else(|| "none".to_string()),
+            )
+            .or_default() += 1;
+
+        if let Some(Value::Source(source)) = sector.properties.get("source") {
+            if let Some(tile_id) = resolved_tile_id(project, source) {
+                *sector_source_counts.entry(tile_id).or_default() += 1;
+                if let Some(tile) = project.tiles.get(&tile_id) {
+                    let entry = role_counts
+                        .entry(tile.role.to_string().to_string())
+                        .or_default();
+                    entry.0 += 1;
+                    if tile.blocking {
+                        entry.1 += 1;
+                    }
+                    if !tile.procedural.kind.is_empty() {
+                        *kind_counts.entry(tile.procedural.kind.clone()).or_default() += 1;
+                    }
+                }
+            } else if matches!(source, PixelSource::Off) {
+                off_sector_count += 1;
+            }
+        }
+
+        if sector
+            .properties
+            .get_bool_default("procedural_generated", false)
+        {
+            let kind = sector
+                .properties
+                .get_str("procedural_kind")
+                .unwrap_or("unknown")
+                .to_string();
+            *procedural_sectors.entry(kind).or_default() += 1;
+        }
+
+        if !sector.name.is_empty() {
+            let bbox = sector.bounding_box(&region.map);
+            let center = sector.center(&region.map);
+            named_sectors.push(json!({
+                "id": sector.id,
+                "name": sector.name,
+                "layer": sector.layer,
+                "bbox": {
+                    "min": [bbox.min.x, bbox.min.y],
+                    "max": [bbox.max.x, bbox.max.y],
+                },
+                "center": center.map(|center| json!([center.x, center.y])),
+                "source": sector
+                    .properties
+                    .get("source")
+                    .and_then(|value| value.to_source())
+                    .map(|source| source_summary(project, source)),
+                "data": sector.properties.get_str("data"),
+            }));
+        }
+    }
+
+    for linedef in &region.map.linedefs {
+        for key in [
+            "source",
+            "row1_source",
+            "row2_source",
+            "row3_source",
+            "row4_source",
+        ] {
+            if let Some(Value::Source(source)) = linedef.properties.get(key)
+                && let Some(tile_id) = resolved_tile_id(project, source)
+            {
+                *linedef_source_counts.entry(tile_id).or_default() += 1;
+            }
+        }
+    }
+
+    let source_usage = |counts: HashMap<Uuid, usize>| {
+        let mut usage = counts
+            .into_iter()
+            .map(|(tile_id, count)| {
+                json!({
+                    "tile_id": tile_id.to_string(),
+                    "count": count,
+                    "tile": tile_summary(project, &tile_id),
+                })
+            })
+            .collect::<Vec<_>>();
+        sort_by_count_desc(&mut usage);
+        usage.truncate(30);
+        usage
+    };
+
+    let mut layers = layer_counts
+        .into_iter()
+        .map(|(layer, count)| json!({ "layer": layer, "count": count }))
+        .collect::<Vec<_>>();
+    layers.sort_by(|a, b| {
+        a["layer"]
+            .as_str()
+            .unwrap_or_default()
+            .cmp(b["layer"].as_str().unwrap_or_default())
+    });
+
+    let mut roles = role_counts
+        .into_iter()
+        .map(|(role, (count, blocking_count))| {
+            json!({
+                "role": role,
+                "count": count,
+                "blocking": blocking_count,
+                "walkable": count.saturating_sub(blocking_count),
+            })
+        })
+        .collect::<Vec<_>>();
+    sort_by_count_desc(&mut roles);
+
+    let mut kinds = kind_counts
+        .into_iter()
+        .map(|(kind, count)| json!({ "kind": kind, "count": count }))
+        .collect::<Vec<_>>();
+    sort_by_count_desc(&mut kinds);
+
+    let mut procedural = procedural_sectors
+        .into_iter()
+        .map(|(kind, count)| json!({ "kind": kind, "count": count }))
+        .collect::<Vec<_>>();
+    sort_by_count_desc(&mut procedural);
+
+    let characters = region
+        .characters
+        .values()
+        .map(|character| {
+            json!({
+                "id": character.id.to_string(),
+                "name": character.name,
+                "position": [character.position.x, character.position.y, character.position.z],
+                "orientation": [character.orientation.x, character.orientation.y],
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let items = region
+        .items
+        .values()
+        .map(|item| {
+            json!({
+                "id": item.id.to_string(),
+                "name": item.name,
+                "position": [item.position.x, item.position.y, item.position.z],
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let overview = if include_ascii {
+        bounds.map(|bounds| ascii_overview(project, region, bounds))
+    } else {
+        None
+    };
+
+    json!({
+        "id": region.id.to_string(),
+        "name": region.name,
+        "map": {
+            "id": region.map.id.to_string(),
+            "name": region.map.name,
+            "camera": format!("{:?}", region.map.camera),
+            "grid_size": region.map.grid_size,
+            "subdivisions": region.map.subdivisions,
+            "bounds": bounds.map(|(min_x, max_x, min_y, max_y)| json!({
+                "min": [min_x, min_y],
+                "max": [max_x, max_y],
+                "size": [max_x - min_x, max_y - min_y],
+            })),
+            "counts": {
+                "vertices": region.map.vertices.len(),
+                "linedefs": region.map.linedefs.len(),
+                "sectors": region.map.sectors.len(),
+                "geometry_objects": region.map.geometry_objects.len(),
+                "characters": region.characters.len(),
+                "items": region.items.len(),
+                "off_sectors": off_sector_count,
+            },
+            "layers": layers,
+            "tile_roles": roles,
+            "procedural_kinds": kinds,
+            "procedural_sectors": procedural,
+            "sector_source_usage": source_usage(sector_source_counts),
+            "linedef_source_usage": source_usage(linedef_source_counts),
+            "named_sectors": named_sectors,
+            "characters": characters,
+            "items": items,
+            "overview": overview,
+            "authoring_notes": authoring_notes(),
+        },
+    })
+}
+
+fn ascii_overview(
+    project: &Project,
+    region: &Region,
+    (min_x, max_x, min_y, max_y): (f32, f32, f32, f32),
+) -> serde_json::Value {
+    let min_x_i = min_x.floor() as i32;
+    let max_x_i = max_x.ceil() as i32;
+    let min_y_i = min_y.floor() as i32;
+    let max_y_i = max_y.ceil() as i32;
+    let width = (max_x_i - min_x_i).max(0) as usize;
+    let height = (max_y_i - min_y_i).max(0) as usize;
+    let omitted = width > 100 || height > 100;
+
+    let mut rows = Vec::new();
+    if !omitted {
+        let mut grid = vec![vec![' '; width]; height];
+        for sector in &region.map.sectors {
+            let bbox = sector.bounding_box(&region.map);
+            let ch = source_overview_char(
+                project,
+                sector
+                    .properties
+                    .get("source")
+                    .and_then(|value| value.to_source()),
+            );
+            let sx0 = (bbox.min.x.floor() as i32).max(min_x_i);
+            let sx1 = (bbox.max.x.ceil() as i32).min(max_x_i);
+            let sy0 = (bbox.min.y.floor() as i32).max(min_y_i);
+            let sy1 = (bbox.max.y.ceil() as i32).min(max_y_i);
+            for y in sy0..sy1 {
+                for x in sx0..sx1 {
+                    let gx = (x - min_x_i) as usize;
+                    let gy = (y - min_y_i) as usize;
+                    if let Some(row) = grid.get_mut(gy)
+                        && let Some(cell) = row.get_mut(gx)
+                    {
+                        *cell = ch;
+                    }
+                }
+            }
+        }
+
+        for character in region.characters.values() {
+            let x = character.position.x.floor() as i32 - min_x_i;
+            let y = character.position.z.floor() as i32 - min_y_i;
+            if let Some(row) = grid.get_mut(y as usize)
+                && let Some(cell) = row.get_mut(x as usize)
+            {
+                *cell = if character.name == "Player" { 'P' } else { 'C' };
+            }
+        }
+
+        for item in region.items.values() {
+            let x = item.position.x.floor() as i32 - min_x_i;
+            let y = item.position.z.floor() as i32 - min_y_i;
+            if let Some(row) = grid.get_mut(y as usize)
+                && let Some(cell) = row.get_mut(x as usize)
+            {
+                *cell = if item.name == "Door" { 'D' } else { 'i' };
+            }
+        }
+
+        rows = grid
+            .into_iter()
+            .map(|row| row.into_iter().collect::<String>())
+            .collect();
+    }
+
+    json!({
+        "bounds": {
+            "min": [min_x_i, min_y_i],
+            "max": [max_x_i, max_y_i],
+            "width": width,
+            "height": height,
+            "orientation": "first row is most negative y/up; later rows move downward toward positive y",
+        },
+        "legend": {
+            "~": "water",
+            "^": "mountain/blocking terrain",
+            "#": "blocking wall or manmade blocker",
+            ".": "floor/manmade walkable",
+            ",": "nature walkable",
+            "T": "blocking nature",
+            "=": "road/path",
+            "E": "entrance",
+            "X": "exit",
+            "P": "player",
+            "C": "character",
+            "D": "door item or door tile",
+            "i": "item"
+        },
+        "rows": rows,
+        "omitted": omitted,
+    })
+}
@@ -0,0 +1,519 @@
+use crate::inspect::resolve_region_index;
+use crate::script::{json_to_toml, patch_data_source};
+use eldiron_scepter::{LocalePatch, RulesGet, RulesPatch, TemplateCreate, TemplateList};
+use serde_json::json;
+use shared::prelude::{Character, Item, Project};
+use shared::rulesets::{
+    RulesetValidationSeverity, resolve_project_locales, resolve_project_rules,
+    validate_ruleset_from_source,
+};
+
+/// The source and data Creator gives new templates.
+const CHARACTER_SOURCE: &str = include_str!("../../../creator/embedded/eldrin/character.eldrin");
+const CHARACTER_DATA: &str = include_str!("../../../creator/embedded/toml/character.toml");
+const ITEM_SOURCE: &str = include_str!("../../../creator/embedded/eldrin/item.eldrin");
+const ITEM_DATA: &str = include_str!("../../../creator/embedded/toml/item.toml");
+
+/// Source and data for a new template, with the requested attributes applied.
+fn template_texts(
+    command: TemplateCreate,
+    default_source: &str,
+    default_data: &str,
+) -> Result<(String, String), String> {
+    let source = command.source.unwrap_or_else(|| default_source.to_string());
+    let data = command.data.unwrap_or_else(|| default_data.to_string());
+    if command.attributes.is_empty() {
+        return Ok((source, data));
+    }
+    let (data, _, _) = patch_data_source(&data, command.attributes, &[])?;
+    Ok((source, data))
+}
+
+fn template_attributes(data: &str) -> serde_json::Value {
+    data.parse::<toml::Table>()
+        .ok()
+        .and_then(|table| table.get("attributes").cloned())
+        .map(|attributes| json!(attributes))
+        .unwrap_or_else(|| json!({}))
+}
+
+pub fn character_create(project: &mut Project, command: TemplateCreate) -> serde_json::Value {
+    if command.name.trim().is_empty() {
+        return json!({ "ok": false, "error": "character name must not be empty" });
+    }
+    if project
+        .characters
+        .values()
+        .any(|character| character.name.eq_ignore_ascii_case(&command.name))
+    {
+        return json!({
+            "ok": false,
+            "error": format!("character template already exists: {}", command.name),
+        });
+    }
+
+    let name = command.name.clone();
+    let (source, data) = match template_texts(command, CHARACTER_SOURCE, CHARACTER_DATA) {
+        Ok(texts) => texts,
+        Err(error) => return json!({ "ok": false, "error": error }),
+    };
+    let character = Character {
+        name,
+        source_debug: source.clone(),
+        source,
+        data,
+        ..Default::default()
+    };
+    let result = json!({
+        "ok": true,
+        "command": "character.create",
+        "id": character.id.to_string(),
+        "name": character.name,
+        "attributes": template_attributes(&character.data),
+    });
+    project.add_character(character);
+
+    result
+}
+
+pub fn item_create(project: &mut Project, command: TemplateCreate) -> serde_json::Value {
+    if command.name.trim().is_empty() {
+        return json!({ "ok": false, "error": "item name must not be empty" });
+    }
+    if project
+        .items
+        .values()
+        .any(|item| item.name.eq_ignore_ascii_case(&command.name))
+    {
+        return json!({
+            "ok": false,
+            "error": format!("item template already exists: {}", command.name),
+        });
+    }
+
+    let name = command.name.clone();
+    let (source, data) = match template_texts(command, ITEM_SOURCE, ITEM_DATA) {
+        Ok(texts) => texts,
+        Err(error) => return json!({ "ok": false, "error": error }),
+    };
+    let item = Item {
+        name,
+        source_debug: source.clone(),
+        source,
+        data,
+        ..Default::default()
+    };
+    let result = json!({
+        "ok": true,
+        "command": "item.create",
+        "id": item.id.to_string(),
+        "name": item.name,
+        "attributes": template_attributes(&item.data),
+    });
+    project.add_item(item);
+
+    result
+}
+
+fn has_dialog(data: &str) -> bool {
+    data.parse::<toml::Table>()
+        .is_ok_and(|table| table.get("dialog").is_some_and(toml::Value::is_table))
+}
+
+pub fn character_list(project: &Project, command: &TemplateList) -> serde_json::Value {
+    if let Some(region) = &command.region {
+        let index = match resolve_region_index(project, region) {
+            Ok(index) => index,
+            Err(error) => return json!({ "ok": false, "error": error }),
+        };
+        let region = &project.regions[index];
+        let characters = region
+            .characters
+            .values()
+            .map(|character| {
+                let template = project
+                    .characters
+                    .get(&character.character_id)
+                    .map(|template| &template.name);
+                let position = character.position;
+                json!({
+                    "id": character.id.to_string(),
+                    "name": character.name,
+                    "template_id": character.character_id.to_string(),
+                    "template": template,
+                    "position": [position.x, position.y, position.z],
+                    "has_dialog": has_dialog(&character.data),
+                })
+            })
+            .collect::<Vec<_>>();
+        return json!({
+            "ok": true,
+            "scope": "region_instance",
+            "region": region.name,
+            "characters": characters,
+        });
+    }
+
+    let characters = project
+        .characters
+        .values()
+        .map(|character| {
+            let instances = project
+                .regions
+                .iter()
+                .flat_map(|region| region.characters.values())
+                .filter(|instance| instance.character_id == character.id)
+                .count();
+            json!({
+                "id": character.id.to_string(),
+                "name": character.name,
+                "instances": instances,
+                "has_dialog": has_dialog(&character.data),
+                "attributes": template_attributes(&character.data),
+            })
+        })
+        .collect::<Vec<_>>();
+    json!({
+        "ok": true,
+        "scope": "template",
+        "characters": characters,
+    })
+}
+
+pub fn item_list(project: &Project, command: &TemplateList) -> serde_json::Value {
+    if let Some(region) = &command.region {
+        let index = match resolve_region_index(project, region) {
+            Ok(index) => index,
+            Err(error) => return json!({ "ok": false, "error": error }),
+        };
+        let region = &project.regions[index];
+        let items = region
+            .items
+            .values()
+            .map(|item| {
+                let template = project
+                    .items
+                    .get(&item.item_id)
+                    .map(|template| &template.name);
+                let position = item.position;
+                json!({
+                    "id": item.id.to_string(),
+                    "name": item.name,
+                    "template_id": item.item_id.to_string(),
+                    "template": template,
+                    "position": [position.x, position.y, position.z],
+                })
+            })
+            .collect::<Vec<_>>();
+        return json!({
+            "ok": true,
+            "scope": "region_instance",
+            "region": region.name,
+            "items": items,
+        });
+    }
+
+    let items = project
+        .items
+        .values()
+        .map(|item| {
+            let instances = project
+                .regions
+                .iter()
+                .flat_map(|region| region.items.values())
+                .filter(|instance| instance.item_id == item.id)
+                .count();
+            json!({
+                "id": item.id.to_string(),
+                "name": item.name,
+                "instances": instances,
+                "attributes": template_attributes(&item.data),
+            })
+        })
+        .collect::<Vec<_>>();
+    json!({
+        "ok": true,
+        "scope": "template",
+        "items": items,
+    })
+}
+
+fn parse_path(path: &str) -> Result<Vec<&str>, String> {
+    let keys = path.split('.').map(str::trim).collect::<Vec<_>>();
+    if keys.iter().any(|key| key.is_empty()) {
+        return Err(format!("invalid dotted path: {path}"));
+    }
+    Ok(keys)
+}
+
+fn parse_table(source: &str, what: &str) -> Result<toml::Table, String> {
+    if source.trim().is_empty() {
+        return Ok(toml::Table::new());
+    }
+    source
+        .parse::<toml::Table>()
+        .map_err(|err| format!("{what} TOML is invalid: {err}"))
+}
+
+/// Set a value by dotted path, creating intermediate tables.
+fn set_path(table: &mut toml::Table, path: &str, value: toml::Value) -> Result<(), String> {
+    let keys = parse_path(path)?;
+    let (last, parents) = keys.split_last().ok_or("empty path")?;
+    let mut current = table;
+    for key in parents {
+        current = current
+            .entry(key.to_string())
+            .or_insert_with(|| toml::Value::Table(toml::Table::new()))
+            .as_table_mut()
+            .ok_or_else(|| format!("{path}: {key} is not a table"))?;
+    }
+    current.insert(last.to_string(), value);
+    Ok(())
+}
+
+/// Remove a value by dotted path. Returns false when nothing was there.
+fn remove_path(table: &mut toml::Table, path: &str) -> Result<bool, String> {
+    let keys = parse_path(path)?;
+    let (last, parents) = keys.split_last().ok_or("empty path")?;
+    let mut current = table;
+    for key in parents {
+        let Some(next) = current.get_mut(*key).and_then(toml::Value::as_table_mut) else {
+            return Ok(false);
+        };
+        current = next;
+    }
+    Ok(current.remove(*last).is_some())
+}
+
+pub fn rules_get(project: &Project, command: &RulesGet) -> serde_json::Value {
+    let source = if command.effective {
+        match resolve_project_rules(&project.config, &project.rules) {
+            Ok(source) => source,
+            Err(error) => return json!({ "ok": false, "error": error }),
+        }
+    } else {
+        project.rules.clone()
+    };
+    let table = match parse_table(&source, "rules") {
+        Ok(table) => table,
+        Err(error) => return json!({ "ok": false, "error": error }),
+    };
+
+    let Some(path) = &command.path else {
+        return json!({
+            "ok": true,
+            "effective": command.effective,
+            "source": source,
+            "rules": table,
+        });
+    };
+    let keys = match parse_path(path) {
+        Ok(keys) => keys,
+        Err(error) => return json!({ "ok": false, "error": error }),
+    };
+    let mut value = None;
+    let mut current = &table;
+    for (index, key) in keys.iter().enumerate() {
+        let Some(next) = current.get(*key) else {
+            break;
+        };
+        if index + 1 == keys.len() {
+            value = Some(next);
+        } else if let Some(next) = next.as_table() {
+            current = next;
+        } else {
+            break;
+        }
+    }
+
+    match value {
+        Some(value) => json!({
+            "ok": true,
+            "effective": command.effective,
+            "path": path,
+            "value": value,
+        }),
+        None => json!({
+            "ok": false,
+            "effective": command.effective,
+            "error": format!("rules path not found: {path}"),
+        }),
+    }
+}
+
+pub fn rules_patch(project: &mut Project, command: RulesPatch) -> serde_json::Value {
+    let mut table = match parse_table(&project.rules, "project rules") {
+        Ok(table) => table,
+        Err(error) => return json!({ "ok": false, "error": error }),
+    };
+
+    let mut changed = Vec::new();
+    for (path, value) in command.values {
+        let value = match json_to_toml(value) {
+            Ok(value) => value,
+            Err(error) => return json!({ "ok": false, "error": format!("{path}: {error}") }),
+        };
+        if let Err(error) = set_path(&mut table, &path, value) {
+            return json!({ "ok": false, "error": error });
+        }
+        changed.push(path);
+    }
+    let mut removed = Vec::new();
+    for path in &command.remove {
+        match remove_path(&mut table, path) {
+            Ok(true) => removed.push(path.clone()),
+            Ok(false) => {}
+            Err(error) => return json!({ "ok": false, "error": error }),
+        }
+    }
+
+    let rules = match toml::to_string_pretty(&table) {
+        Ok(rules) => rules,
+        Err(err) => {
+            return json!({ "ok": false, "error": format!("could not serialize rules: {err}") });
+        }
+    };
+    let report = match resolve_project_rules(&project.config, &rules)
+        .and_then(|effective| validate_ruleset_from_source(&effective))
+    {
+        Ok(report) => report,
+        Err(error) => return json!({ "ok": false, "error": error }),
+    };
+    let issues = report
+        .issues
+        .iter()
+        .map(|issue| {
+            json!({
+                "severity": match issue.severity {
+                    RulesetValidationSeverity::Error => "error",
+                    RulesetValidationSeverity::Warning => "warning",
+                },
+                "path": issue.path,
+                "message": issue.message,
+            })
+        })
+        .collect::<Vec<_>>();
+    if !report.is_ok() {
+        return json!({
+            "ok": false,
+            "error": format!("patched ruleset has {} validation errors", report.error_count()),
+            "issues": issues,
+        });
+    }
+
+    project.rules = rules;
+    let synced_items = match project.sync_ruleset_items() {
+        Ok(count) => count,
+        Err(error) => return json!({ "ok": false, "error": error }),
+    };
+
+    json!({
+        "ok": true,
+        "command": "rules.patch",
+        "changed": changed,
+        "removed": removed,
+        "warnings": report.warning_count(),
+        "issues": issues,
+        "synced_items": synced_items,
+    })
+}
+
+pub fn locale_patch(project: &mut Project, command: LocalePatch) -> serde_json::Value {
+    if !parse_path(&command.locale).is_ok_and(|keys| keys.len() == 1) {
+        return json!({ "ok": false, "error": format!("invalid locale name: {}", command.locale) });
+    }
+    let mut table = match parse_table(&project.locales, "project locales") {
+        Ok(table) => table,
+        Err(error) => return json!({ "ok": false, "error": error }),
+    };
+    let Some(locale) = table
+        .entry(command.locale.clone())
+        .or_insert_with(|| toml::Value::Table(toml::Table::new()))
+        .as_table_mut()
+    else {
+        return json!({
+            "ok": false,
+            "error": format!("locale {} exists but is not a TOML table", command.locale),
+        });
+    };
+
+    let mut changed = Vec::new();
+    for (key, value) in command.values {
+        let Some(text) = value.as_str() else {
+            return json!({ "ok": false, "error": format!("{key}: translations must be strings") });
+        };
+        if let Err(error) = set_path(locale, &key, toml::Value::String(text.to_string())) {
+            return json!({ "ok": false, "error": error });
+        }
+        changed.push(key);
+    }
+    let mut removed = Vec::new();
+    for key in &command.remove {
+        match remove_path(locale, key) {
+            Ok(true) => removed.push(key.clone()),
+            Ok(false) => {}
+            Err(error) => return json!({ "ok": false, "error": error }),
+        }
+    }
+
+    let locales = match toml::to_string_pretty(&table) {
+        Ok(locales) => locales,
+        Err(err) => {
+            return json!({ "ok": false, "error": format!("could not serialize locales: {err}") });
+        }
+    };
+    if let Err(error) = resolve_project_locales(&project.config, &locales) {
+        return json!({ "ok": false, "error": error });
+    }
+    project.locales = locales;
+
+    json!({
+        "ok": true,
+        "command": "locale.patch",
+        "locale": command.locale,
+        "changed": changed,
+        "removed": removed,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn patch(values: serde_json::Value) -> RulesPatch {
+        RulesPatch {
+            values: serde_json::from_value(values).unwrap(),
+            remove: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn rules_patch_validates_before_applying() {
+        let mut project = Project::new();
+        let original = project.rules.clone();
+
+        let rejected = rules_patch(&mut project, patch(json!({ "spells.ember": 5 })));
+        assert_eq!(rejected["ok"], false, "{rejected}");
+        assert!(
+            rejected["issues"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .any(|issue| issue["path"] == "spells.ember" && issue["severity"] == "error"),
+            "{rejected}"
+        );
+        assert_eq!(project.rules, original);
+
+        let applied = rules_patch(
+            &mut project,
+            patch(json!({ "spells.ember.damage_kind": "fire" })),
+        );
+        assert_eq!(applied["ok"], true, "{applied}");
+        assert_eq!(applied["changed"], json!(["spells.ember.damage_kind"]));
+        let rules = project.rules.parse::<toml::Table>().unwrap();
+        assert_eq!(
+            rules["spells"]["ember"]["damage_kind"].as_str(),
+            Some("fire")
+        );
+    }
+}
@@ -0,0 +1,761 @@
+use crate::inspect::{
+    base64_decode, parse_tile_role, resolve_region_index, resolve_tile_selector, tile_summary,
+};
+use eldiron_scepter::{
+    GeometryCreateRoom, GeometryPlaceBuilderAsset, GridPoint, RegionCreateSector, RegionPaintCells,
+    RegionPaintOutline, RegionPaintRect, RegionPlaceCharacter, RegionPlaceItem, RegionRef,
+    TileCreateFromRgba, TileGroupCreate, TileMetadataPatch, TileSelector,
+};
+use rusterix::{Map, PixelSource, Texture, TileGroup, TileGroupMemberRef, Value};
+use serde_json::json;
+use shared::buildergraph::{BuilderDocument, BuilderOutputTarget};
+use shared::prelude::{Character, Item, Project};
+use theframework::prelude::Vec3;
+
+fn sector_points(map: &Map, sector_id: u32) -> Option<Vec<(f32, f32)>> {
+    let sector = map.find_sector(sector_id)?;
+    sector
+        .linedefs
+        .iter()
+        .map(|linedef_id| {
+            let linedef = map.find_linedef(*linedef_id)?;
+            let vertex = map.find_vertex(linedef.start_vertex)?;
+            Some((vertex.x, vertex.y))
+        })
+        .collect()
+}
+
+fn find_rect_sector(map: &Map, expected: &[(f32, f32); 4]) -> Option<u32> {
+    let mut expected = expected.to_vec();
+    expected.sort_by(|a, b| a.0.total_cmp(&b.0).then_with(|| a.1.total_cmp(&b.1)));
+    expected.dedup();
+
+    map.sectors
+        .iter()
+        .map(|sector| sector.id)
+        .find(|sector_id| {
+            let Some(mut points) = sector_points(map, *sector_id) else {
+                return false;
+            };
+            points.sort_by(|a, b| a.0.total_cmp(&b.0).then_with(|| a.1.total_cmp(&b.1)));
+            points.dedup();
+            points == expected
+        })
+}
+
+fn find_cell_replacement_sectors(map: &Map, x: i32, y: i32) -> Vec<u32> {
+    let x0 = x as f32;
+    let y0 = y as f32;
+    let x1 = x0 + 1.0;
+    let y1 = y0 + 1.0;
+    let epsilon = 0.0001;
+
+    map.sectors
+        .iter()
+        .filter_map(|sector| {
+            let source = sector.properties.get_default_source()?;
+            if matches!(source, PixelSource::Off) {
+                return None;
+            }
+
+            let points = sector_points(map, sector.id)?;
+            let min_x = points.iter().map(|p| p.0).fold(f32::MAX, f32::min);
+            let min_y = points.iter().map(|p| p.1).fold(f32::MAX, f32::min);
+            let max_x = points.iter().map(|p| p.0).fold(f32::MIN, f32::max);
+            let max_y = points.iter().map(|p| p.1).fold(f32::MIN, f32::max);
+
+            let overlaps = min_x < x1 - epsilon
+                && max_x > x0 + epsilon
+                && min_y < y1 - epsilon
+                && max_y > y0 + epsilon;
+
+            overlaps.then_some(sector.id)
+        })
+        .collect()
+}
+
+/// Draw a closed polygon with manual linedefs and turn it into a sector.
+fn create_polygon_sector(map: &mut Map, points: &[(f32, f32)]) -> Option<u32> {
+    let vertices = points
+        .iter()
+        .map(|(x, y)| map.add_vertex_at(*x, *y))
+        .collect::<Vec<_>>();
+
+    map.possible_polygon.clear();
+    for (index, start) in vertices.iter().enumerate() {
+        let end = vertices[(index + 1) % vertices.len()];
+        let _ = map.create_linedef_manual(*start, end);
+    }
+
+    map.close_polygon_manual()
+}
+
+fn create_cell_sector(
+    map: &mut Map,
+    x: i32,
+    y: i32,
+    source: PixelSource,
+    layer: u8,
+) -> Option<u32> {
+    let x0 = x as f32;
+    let y0 = y as f32;
+    let x1 = x0 + 1.0;
+    let y1 = y0 + 1.0;
+    let expected = [(x0, y0), (x0, y1), (x1, y1), (x1, y0)];
+
+    let sector_id =
+        create_polygon_sector(map, &expected).or_else(|| find_rect_sector(map, &expected))?;
+
+    if let Some(sector) = map.find_sector_mut(sector_id) {
+        sector.properties.set("rect", Value::Bool(true));
+        sector.properties.set("source", Value::Source(source));
+        sector.layer = Some(layer);
+    }
+
+    Some(sector_id)
+}
+
+fn parse_layer(layer: Option<&str>) -> u8 {
+    layer
+        .and_then(|layer| layer.parse::<u8>().ok())
+        .unwrap_or(1)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn paint_cells_batch(
+    project: &mut Project,
+    region: &RegionRef,
+    tile: &TileSelector,
+    cells: &[GridPoint],
+    layer: Option<&str>,
+    select: bool,
+    replace_existing: bool,
+    command_name: &str,
+) -> serde_json::Value {
+    let region_index = match resolve_region_index(project, region) {
+        Ok(index) => index,
+        Err(error) => return json!({ "ok": false, "error": error }),
+    };
+    let tile_id = match resolve_tile_selector(project, tile) {
+        Ok(tile_id) => tile_id,
+        Err(error) => return json!({ "ok": false, "error": error }),
+    };
+
+    if cells.is_empty() {
+        return json!({
+            "ok": false,
+            "error": format!("{command_name} requires at least one cell"),
+        });
+    }
+
+    let layer = parse_layer(layer);
+    let source = PixelSource::TileId(tile_id);
+    let region_id = project.regions[region_index].id;
+
+    let mut created_sector_ids = Vec::new();
+    let mut replaced_sector_ids = Vec::new();
+    {
+        let map = &mut project.regions[region_index].map;
+
+        for [x, y] in cells {
+            if replace_existing {
+                let sectors = find_cell_replacement_sectors(map, *x, *y);
+                if !sectors.is_empty() {
+                    let linedefs = sectors
+                        .iter()
+                        .filter_map(|sector_id| map.find_sector(*sector_id))
+                        .flat_map(|sector| sector.linedefs.clone())
+                        .collect::<Vec<_>>();
+                    map.delete_elements(&[], &linedefs, &sectors);
+                    replaced_sector_ids.extend(sectors);
+                }
+            }
+
+            match create_cell_sector(map, *x, *y, source.clone(), layer) {
+                Some(sector_id) => created_sector_ids.push(sector_id),
+                None => {
+                    return json!({
+                        "ok": false,
+                        "error": format!("could not create cell sector at [{x}, {y}]"),
+                        "created_sector_ids": created_sector_ids,
+                        "replaced_sector_ids": replaced_sector_ids,
+                    });
+                }
+            }
+        }
+
+        if select {
+            map.selected_vertices.clear();
+            map.selected_linedefs.clear();
+            map.selected_sectors = created_sector_ids.clone();
+        }
+        map.changed = map.changed.saturating_add(created_sector_ids.len() as u32);
+    }
+
+    json!({
+        "ok": true,
+        "command": command_name,
+        "region_id": region_id.to_string(),
+        "sector_ids": created_sector_ids,
+        "replaced_sector_ids": replaced_sector_ids,
+        "tile_id": tile_id.to_string(),
+        "cell_count": cells.len(),
+        "layer": layer,
+        "replace_existing": replace_existing,
+        "tile": tile_summary(project, &tile_id),
+    })
+}
+
+/// Normalize a grid rect with possibly negative extents into min/max corners.
+fn rect_corners([x, y, width, height]: [i32; 4]) -> (i32, i32, i32, i32) {
+    (
+        x.min(x + width),
+        y.min(y + height),
+        x.max(x + width),
+        y.max(y + height),
+    )
+}
+
+pub fn paint_rect(project: &mut Project, command: RegionPaintRect) -> serde_json::Value {
+    let [_, _, width, height] = command.rect;
+    if width == 0 || height == 0 {
+        return json!({
+            "ok": false,
+            "error": "region.paint_rect requires non-zero width and height",
+        });
+    }
+
+    let (x0, y0, x1, y1) = rect_corners(command.rect);
+    let mut cells = Vec::new();
+    for y in y0..y1 {
+        for x in x0..x1 {
+            cells.push([x, y]);
+        }
+    }
+
+    paint_cells_batch(
+        project,
+        &command.region,
+        &command.tile,
+        &cells,
+        command.layer.as_deref(),
+        command.select.unwrap_or(false),
+        command.replace_existing.unwrap_or(true),
+        "region.paint_rect",
+    )
+}
+
+pub fn paint_outline(project: &mut Project, command: RegionPaintOutline) -> serde_json::Value {
+    let [_, _, width, height] = command.rect;
+    if width == 0 || height == 0 {
+        return json!({
+            "ok": false,
+            "error": "region.paint_outline requires non-zero width and height",
+        });
+    }
+
+    let (x0, y0, x1, y1) = rect_corners(command.rect);
+    let mut cells = Vec::new();
+    for y in y0..y1 {
+        for x in x0..x1 {
+            if x == x0 || x == x1 - 1 || y == y0 || y == y1 - 1 {
+                cells.push([x, y]);
+            }
+        }
+    }
+
+    paint_cells_batch(
+        project,
+        &command.region,
+        &command.tile,
+        &cells,
+        command.layer.as_deref(),
+        false,
+        true,
+        "region.paint_outline",
+    )
+}
+
+pub fn paint_cells(project: &mut Project, command: RegionPaintCells) -> serde_json::Value {
+    paint_cells_batch(
+        project,
+        &command.region,
+        &command.tile,
+        &command.cells,
+        command.layer.as_deref(),
+        command.select.unwrap_or(false),
+        command.replace_existing.unwrap_or(true),
+        "region.paint_cells",
+    )
+}
+
+pub fn create_sector(project: &mut Project, command: RegionCreateSector) -> serde_json::Value {
+    let region_index = match resolve_region_index(project, &command.region) {
+        Ok(index) => index,
+        Err(error) => return json!({ "ok": false, "error": error }),
+    };
+    if command.polygon.len() < 3 {
+        return json!({
+            "ok": false,
+            "error": "region.create_sector requires a polygon with at least three points",
+        });
+    }
+    if command.name.trim().is_empty() {
+        return json!({ "ok": false, "error": "region.create_sector requires a name" });
+    }
+
+    let points = command
+        .polygon
+        .iter()
+        .map(|[x, y]| (*x as f32, *y as f32))
+        .collect::<Vec<_>>();
+    let layer = parse_layer(command.layer.as_deref());
+    let region = &mut project.regions[region_index];
+    let Some(sector_id) = create_polygon_sector(&mut region.map, &points) else {
+        return json!({
+            "ok": false,
+            "error": "could not close the polygon into a sector",
+        });
+    };
+
+    if let Some(sector) = region.map.find_sector_mut(sector_id) {
+        sector.name = command.name.clone();
+        sector.layer = Some(layer);
+    }
+    region.map.changed = region.map.changed.saturating_add(1);
+
+    json!({
+        "ok": true,
+        "command": "region.create_sector",
+        "region_id": region.id.to_string(),
+        "sector_id": sector_id,
+        "name": command.name,
+        "layer": layer,
+    })
+}
+
+/// Region-local position of a grid cell, matching where Eldiron Source places
+/// entities.
+fn cell_position([x, y]: GridPoint) -> Vec3<f32> {
+    Vec3::new(x as f32 + 0.5, 1.0, y as f32 + 0.5)
+}
+
+pub fn place_item(project: &mut Project, command: RegionPlaceItem) -> serde_json::Value {
+    let region_index = match resolve_region_index(project, &command.region) {
+        Ok(index) => index,
+        Err(error) => return json!({ "ok": false, "error": error }),
+    };
+    let Some((template_id, template)) = project
+        .items
+        .iter()
+        .find(|(_, item)| item.name.eq_ignore_ascii_case(&command.template))
+    else {
+        return json!({
+            "ok": false,
+            "error": format!("item template not found: {}", command.template),
+        });
+    };
+
+    let item = Item {
+        item_id: *template_id,
+        name: command.name.unwrap_or_else(|| template.name.clone()),
+        position: cell_position(command.at),
+        ..Default::default()
+    };
+    let result = json!({
+        "ok": true,
+        "command": "region.place_item",
+        "region_id": project.regions[region_index].id.to_string(),
+        "id": item.id.to_string(),
+        "template_id": template_id.to_string(),
+        "name": item.name,
+        "position": [item.position.x, item.position.y, item.position.z],
+    });
+    project.regions[region_index].items.insert(item.id, item);
+    shared::rusterix_utils::insert_content_into_maps(project);
+
+    result
+}
+
+pub fn place_character(project: &mut Project, command: RegionPlaceCharacter) -> serde_json::Value {
+    let region_index = match resolve_region_index(project, &command.region) {
+        Ok(index) => index,
+        Err(error) => return json!({ "ok": false, "error": error }),
+    };
+    let Some((template_id, template)) = project
+        .characters
+        .iter()
+        .find(|(_, character)| character.name.eq_ignore_ascii_case(&command.template))
+    else {
+        return json!({
+            "ok": false,
+            "error": format!("character template not found: {}", command.template),
+        });
+    };
+
+    let character = Character {
+        character_id: *template_id,
+        name: command.name.unwrap_or_else(|| template.name.clone()),
+        position: cell_position(command.at),
+        ..Default::default()
+    };
+    let result = json!({
+        "ok": true,
+        "command": "region.place_character",
+        "region_id": project.regions[region_index].id.to_string(),
+        "id": character.id.to_string(),
+        "template_id": template_id.to_string(),
+        "name": character.name,
+        "position": [character.position.x, character.position.y, character.position.z],
+    });
+    project.regions[region_index]
+        .characters
+        .insert(character.id, character);
+    shared::rusterix_utils::insert_content_into_maps(project);
+
+    result
+}
+
+pub fn tile_create_from_rgba(
+    project: &mut Project,
+    command: TileCreateFromRgba,
+) -> serde_json::Value {
+    if command.width == 0 || command.height == 0 {
+        return json!({
+            "ok": false,
+            "error": "tile.create_from_rgba requires non-zero width and height",
+        });
+    }
+    let data = match base64_decode(&command.rgba_base64) {
+        Ok(data) => data,
+        Err(error) => return json!({ "ok": false, "error": error }),
+    };
+    let expected = command.width as usize * command.height as usize * 4;
+    if data.len() != expected {
+        return json!({
+            "ok": false,
+            "error": format!(
+                "rgba_base64 decodes to {} bytes but {}x{} RGBA8 needs {expected}",
+                data.len(),
+                command.width,
+                command.height
+            ),
+        });
+    }
+    let role = match command.role.as_deref().map(parse_tile_role).transpose() {
+        Ok(role) => role,
+        Err(error) => return json!({ "ok": false, "error": error }),
+    };
+
+    let mut texture = Texture::new(data, command.width as usize, command.height as usize);
+    texture.generate_normals(true);
+    let mut tile = rusterix::Tile::from_texture(texture);
+    tile.alias = command.alias.unwrap_or(command.name);
+    if let Some(role) = role {
+        tile.role = role;
+    }
+    if let Some(kind) = command.procedural_kind {
+        tile.procedural.kind = kind;
+    }
+    if let Some(style) = command.procedural_style {
+        tile.procedural.style = style;
+    }
+    tile.blocking = command.blocking;
+    tile.set_default_materials();
+
+    let tile_id = tile.id;
+    project.tiles.insert(tile_id, tile);
+
+    json!({
+        "ok": true,
+        "command": "tile.create_from_rgba",
+        "tile_id": tile_id.to_string(),
+        "tile": tile_summary(project, &tile_id),
+    })
+}
+
+pub fn tile_set_meta(project: &mut Project, command: TileMetadataPatch) -> serde_json::Value {
+    let tile_id = match resolve_tile_selector(project, &command.tile) {
+        Ok(tile_id) => tile_id,
+        Err(error) => return json!({ "ok": false, "error": error }),
+    };
+    let role = match command.role.as_deref().map(parse_tile_role).transpose() {
+        Ok(role) => role,
+        Err(error) => return json!({ "ok": false, "error": error }),
+    };
+    let Some(tile) = project.tiles.get_mut(&tile_id) else {
+        return json!({ "ok": false, "error": format!("tile id not found: {tile_id}") });
+    };
+
+    let mut changed = Vec::new();
+    if let Some(alias) = command.alias {
+        tile.alias = alias;
+        changed.push("alias");
+    }
+    if let Some(role) = role {
+        tile.role = role;
+        changed.push("role");
+    }
+    if let Some(kind) = command.procedural_kind {
+        tile.procedural.kind = kind;
+        changed.push("procedural_kind");
+    }
+    if let Some(style) = command.procedural_style {
+        tile.procedural.style = style;
+        changed.push("procedural_style");
+    }
+    if let Some(weight) = command.procedural_weight {
+        tile.procedural.weight = weight;
+        changed.push("procedural_weight");
+    }
+    if let Some(blocking) = command.blocking {
+        tile.blocking = blocking;
+        changed.push("blocking");
+    }
+
+    json!({
+        "ok": true,
+        "command": "tile.set_meta",
+        "tile_id": tile_id.to_string(),
+        "changed": changed,
+        "tile": tile_summary(project, &tile_id),
+    })
+}
+
+pub fn tile_group_create(project: &mut Project, command: TileGroupCreate) -> serde_json::Value {
+    let [width, height] = command.size;
+    if width <= 0 || height <= 0 || width > u16::MAX as i32 || height > u16::MAX as i32 {
+        return json!({
+            "ok": false,
+            "error": "tile_group.create requires a positive size",
+        });
+    }
+
+    let mut group = TileGroup::new(width as u16, height as u16);
+    group.name = command.name;
+    group.tags = command.tags.join(", ");
+    for member in &command.members {
+        let [x, y] = member.at;
+        if x < 0 || y < 0 || x >= width || y >= height {
+            return json!({
+                "ok": false,
+                "error": format!("tile_group.create member [{x}, {y}] is outside the group size"),
+            });
+        }
+        let tile_id = match resolve_tile_selector(project, &member.tile) {
+            Ok(tile_id) => tile_id,
+            Err(error) => return json!({ "ok": false, "error": error }),
+        };
+        group.members.push(TileGroupMemberRef {
+            tile_id,
+            x: x as u16,
+            y: y as u16,
+        });
+    }
+
+    let group_id = group.id;
+    let result = json!({
+        "ok": true,
+        "command": "tile_group.create",
+        "group_id": group_id.to_string(),
+        "name": group.name,
+        "width": group.width,
+        "height": group.height,
+        "member_count": group.members.len(),
+        "tags": group.tags,
+    });
+    project.add_tile_group(group);
+
+    result
+}
+
+/// Build a 2D room: a named floor sector whose boundary linedefs carry the
+/// wall height and wall tile used by the 3D chunk builder.
+pub fn geometry_create_room(
+    project: &mut Project,
+    command: GeometryCreateRoom,
+) -> serde_json::Value {
+    let region_index = match resolve_region_index(project, &command.region) {
+        Ok(index) => index,
+        Err(error) => return json!({ "ok": false, "error": error }),
+    };
+    let [_, _, width, height] = command.rect;
+    if width == 0 || height == 0 {
+        return json!({
+            "ok": false,
+            "error": "geometry.create_room requires non-zero width and height",
+        });
+    }
+    if command.height <= 0.0 {
+        return json!({
+            "ok": false,
+            "error": "geometry.create_room requires a positive wall height",
+        });
+    }
+    let floor = match command
+        .floor_tile
+        .as_ref()
+        .map(|tile| resolve_tile_selector(project, tile))
+        .transpose()
+    {
+        Ok(tile_id) => tile_id,
+        Err(error) => return json!({ "ok": false, "error": error }),
+    };
+    let wall = match command
+        .wall_tile
+        .as_ref()
+        .map(|tile| resolve_tile_selector(project, tile))
+        .transpose()
+    {
+        Ok(tile_id) => tile_id,
+        Err(error) => return json!({ "ok": false, "error": error }),
+    };
+
+    let (x0, y0, x1, y1) = rect_corners(command.rect);
+    let (x0, y0, x1, y1) = (x0 as f32, y0 as f32, x1 as f32, y1 as f32);
+    let region = &mut project.regions[region_index];
+    let map = &mut region.map;
+    let Some(sector_id) = create_polygon_sector(map, &[(x0, y0), (x0, y1), (x1, y1), (x1, y0)])
+    else {
+        return json!({
+            "ok": false,
+            "error": "could not create the room sector",
+        });
+    };
+
+    let mut linedef_ids = Vec::new();
+    if let Some(sector) = map.find_sector_mut(sector_id) {
+        sector.name = command.name.clone();
+        sector.properties.set("rect", Value::Bool(true));
+        if let Some(tile_id) = floor {
+            sector
+                .properties
+                .set("source", Value::Source(PixelSource::TileId(tile_id)));
+        }
+        linedef_ids = sector.linedefs.clone();
+    }
+    for linedef_id in &linedef_ids {
+        if let Some(linedef) = map.find_linedef_mut(*linedef_id) {
+            linedef
+                .properties
+                .set("wall_height", Value::Float(command.height));
+            if let Some(tile_id) = wall {
+                linedef
+                    .properties
+                    .set("row1_source", Value::Source(PixelSource::TileId(tile_id)));
+            }
+        }
+    }
+    map.changed = map.changed.saturating_add(1);
+
+    json!({
+        "ok": true,
+        "command": "geometry.create_room",
+        "region_id": region.id.to_string(),
+        "sector_id": sector_id,
+        "linedef_ids": linedef_ids,
+        "name": command.name,
+        "height": command.height,
+        "floor_tile_id": floor.map(|id| id.to_string()),
+        "wall_tile_id": wall.map(|id| id.to_string()),
+    })
+}
+
+/// Attach a sector builder graph to a host sector, found by name through `on`
+/// or as the sector under the `at` position.
+pub fn geometry_place_builder_asset(
+    project: &mut Project,
+    command: GeometryPlaceBuilderAsset,
+) -> serde_json::Value {
+    let region_index = match resolve_region_index(project, &command.region) {
+        Ok(index) => index,
+        Err(error) => return json!({ "ok": false, "error": error }),
+    };
+    let Some(asset) = project.builder_graphs.values().find(|asset| {
+        asset.graph_name.eq_ignore_ascii_case(&command.asset)
+            || asset.id.to_string() == command.asset
+    }) else {
+        return json!({
+            "ok": false,
+            "error": format!("builder asset not found: {}", command.asset),
+        });
+    };
+    let document = match BuilderDocument::from_text(&asset.graph_data) {
+        Ok(document) => document,
+        Err(error) => {
+            return json!({
+                "ok": false,
+                "error": format!("builder asset does not parse: {error}"),
+            });
+        }
+    };
+    let spec = document.output_spec();
+    if spec.target != BuilderOutputTarget::Sector {
+        return json!({
+            "ok": false,
+            "error": "only sector builder assets can be placed headlessly; place linedef and vertex assets in Creator",
+        });
+    }
+    let (asset_id, graph_name, graph_data) =
+        (asset.id, asset.graph_name.clone(), asset.graph_data.clone());
+    let hide_host = document.hides_host();
+
+    let region = &mut project.regions[region_index];
+    let map = &mut region.map;
+    let host = match &command.on {
+        Some(name) => map
+            .sectors
+            .iter()
+            .find(|sector| sector.name.eq_ignore_ascii_case(name))
+            .map(|sector| sector.id),
+        None => {
+            let [x, _, z] = command.at;
+            map.sectors
+                .iter()
+                .find(|sector| {
+                    let bbox = sector.bounding_box(map);
+                    x >= bbox.min.x && x <= bbox.max.x && z >= bbox.min.y && z <= bbox.max.y
+                })
+                .map(|sector| sector.id)
+        }
+    };
+    let Some(sector) = host.and_then(|sector_id| map.find_sector_mut(sector_id)) else {
+        return json!({
+            "ok": false,
+            "error": "no host sector found for the builder asset",
+        });
+    };
+
+    sector
+        .properties
+        .set("builder_graph_id", Value::Id(asset_id));
+    sector
+        .properties
+        .set("builder_graph_name", Value::Str(graph_name.clone()));
+    sector
+        .properties
+        .set("builder_graph_data", Value::Str(graph_data));
+    sector
+        .properties
+        .set("builder_graph_target", Value::Str("sector".to_string()));
+    sector
+        .properties
+        .set("builder_surface_mode", Value::Str("overlay".to_string()));
+    sector
+        .properties
+        .set("builder_hide_host", Value::Bool(hide_host));
+    sector
+        .properties
+        .set("builder_graph_host_refs", Value::Int(spec.host_refs as i32));
+    let sector_id = sector.id;
+    map.changed = map.changed.saturating_add(1);
+
+    json!({
+        "ok": true,
+        "command": "geometry.place_builder_asset",
+        "region_id": region.id.to_string(),
+        "sector_id": sector_id,
+        "asset_id": asset_id.to_string(),
+        "asset": graph_name,
+    })
+}
@@ -0,0 +1,817 @@
+use eldiron_scepter::{
+    RegionRef, RegionRenderPreview, TileContactSheet, TileList, TileSelector, authoring_notes,
+    map_bounds, source_summary,
+};
+pub use eldiron_scepter::{resolved_tile_id, tile_summary};
+use rusterix::{TileRole, Value, ValueContainer};
+use serde_json::json;
+use shared::prelude::{Project, Region};
+use std::path::Path;
+use theframework::prelude::Uuid;
+
+/// Describe the open project: its path, regions and templates.
+pub fn project_snapshot(project: &Project, path: &Path, dirty: bool) -> serde_json::Value {
+    let characters = project
+        .characters
+        .values()
+        .map(|character| {
+            json!({
+                "id": character.id.to_string(),
+                "name": character.name,
+                "source_len": character.source.len(),
+                "data_len": character.data.len(),
+                "has_authoring": !character.authoring.trim().is_empty(),
+                "has_preview_rigging": !character.preview_rigging.trim().is_empty(),
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let items = project
+        .items
+        .values()
+        .map(|item| {
+            json!({
+                "id": item.id.to_string(),
+                "name": item.name,
+                "source_len": item.source.len(),
+                "data_len": item.data.len(),
+                "has_authoring": !item.authoring.trim().is_empty(),
+            })
+        })
+        .collect::<Vec<_>>();
+
+    json!({
+        "name": project.name,
+        "path": path.display().to_string(),
+        "dirty": dirty,
+        "current_region": project.regions.first().map(region_entry),
+        "regions": region_list(project),
+        "characters": characters,
+        "items": items,
+        "counts": {
+            "regions": project.regions.len(),
+            "tiles": project.tiles.len(),
+            "tile_groups": project.tile_groups.len(),
+            "tilesets": project.tilemaps.len(),
+            "characters": project.characters.len(),
+            "items": project.items.len(),
+            "screens": project.screens.len(),
+            "assets": project.assets.len(),
+        }
+    })
+}
+
+fn region_entry(region: &Region) -> serde_json::Value {
+    json!({
+        "id": region.id.to_string(),
+        "name": region.name,
+        "map_name": region.map.name,
+        "camera": format!("{:?}", region.map.camera),
+        "sectors": region.map.sectors.len(),
+        "items": region.items.len(),
+        "characters": region.characters.len(),
+    })
+}
+
+/// List the regions of the project in project order.
+pub fn region_list(project: &Project) -> serde_json::Value {
+    serde_json::Value::Array(project.regions.iter().map(region_entry).collect())
+}
+
+/// List tile metadata, optionally filtered by role, kind and style.
+pub fn tiles_snapshot(project: &Project, filter: Option<&TileList>) -> serde_json::Value {
+    let role = filter
+        .and_then(|filter| filter.role.as_deref())
+        .map(normalize_match_text);
+    let kind = filter
+        .and_then(|filter| filter.kind.as_deref())
+        .map(normalize_match_text);
+    let style = filter
+        .and_then(|filter| filter.style.as_deref())
+        .map(normalize_match_text);
+
+    let mut tiles = project
+        .tiles
+        .values()
+        .filter(|tile| {
+            role.as_ref()
+                .is_none_or(|role| normalize_match_text(tile.role.to_string()) == *role)
+                && kind
+                    .as_ref()
+                    .is_none_or(|kind| normalize_match_text(&tile.procedural.kind) == *kind)
+                && style
+                    .as_ref()
+                    .is_none_or(|style| normalize_match_text(&tile.procedural.style) == *style)
+        })
+        .map(|tile| {
+            let first_frame = tile.textures.first().map(|texture| {
+                json!({
+                    "width": texture.width,
+                    "height": texture.height,
+                })
+            });
+
+            json!({
+                "id": tile.id.to_string(),
+                "alias": tile.alias,
+                "role": tile.role.to_string(),
+                "blocking": tile.blocking,
+                "scale": tile.scale,
+                "frame_count": tile.textures.len(),
+                "first_frame": first_frame,
+                "procedural": {
+                    "style": tile.procedural.style,
+                    "kind": tile.procedural.kind,
+                    "weight": tile.procedural.weight,
+                },
+                "has_module": tile.module.is_some(),
+                "has_particle_emitter": tile.particle_emitter.is_some(),
+                "has_light_emitter": tile.light_emitter.is_some(),
+            })
+        })
+        .collect::<Vec<_>>();
+    tiles.sort_by(|a, b| {
+        let alias_a = a["alias"].as_str().unwrap_or_default();
+        let alias_b = b["alias"].as_str().unwrap_or_default();
+        alias_a.cmp(alias_b).then_with(|| {
+            a["id"]
+                .as_str()
+                .unwrap_or_default()
+                .cmp(b["id"].as_str().unwrap_or_default())
+        })
+    });
+
+    let mut tile_groups = project
+        .tile_groups
+        .values()
+        .map(|group| {
+            let members = group
+                .members
+                .iter()
+                .map(|member| {
+                    json!({
+                        "tile_id": member.tile_id.to_string(),
+                        "x": member.x,
+                        "y": member.y,
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            json!({
+                "id": group.id.to_string(),
+                "name": group.name,
+                "width": group.width,
+                "height": group.height,
+                "tags": group.tags,
+                "members": members,
+            })
+        })
+        .collect::<Vec<_>>();
+    tile_groups.sort_by(|a, b| {
+        let name_a = a["name"].as_str().unwrap_or_default();
+        let name_b = b["name"].as_str().unwrap_or_default();
+        name_a.cmp(name_b).then_with(|| {
+            a["id"]
+                .as_str()
+                .unwrap_or_default()
+                .cmp(b["id"].as_str().unwrap_or_default())
+        })
+    });
+
+    let roles = TileRole::iterator()
+        .map(|role| role.to_string())
+        .collect::<Vec<_>>();
+
+    json!({
+        "roles": roles,
+        "tiles": tiles,
+        "tile_groups": tile_groups,
+        "counts": {
+            "tiles": project.tiles.len(),
+            "tile_groups": project.tile_groups.len(),
+        }
+    })
+}
+
+fn value_snapshot(project: &Project, value: &Value) -> serde_json::Value {
+    match value {
+        Value::Source(source) => json!({
+            "type": "source",
+            "source": source_summary(project, source),
+        }),
+        Value::TileOverrides(tiles) => {
+            let mut entries = tiles
+                .iter()
+                .map(|((x, y), source)| {
+                    json!({
+                        "cell": [x, y],
+                        "source": source_summary(project, source),
+                    })
+                })
+                .collect::<Vec<_>>();
+            entries.sort_by_key(|entry| {
+                (
+                    entry["cell"][0].as_i64().unwrap_or_default(),
+                    entry["cell"][1].as_i64().unwrap_or_default(),
+                )
+            });
+            json!({
+                "type": "tile_overrides",
+                "entries": entries,
+            })
+        }
+        Value::BlendOverrides(blend_tiles) => {
+            let mut entries = blend_tiles
+                .iter()
+                .map(|((x, y), (preset, source))| {
+                    json!({
+                        "cell": [x, y],
+                        "preset": serde_json::to_value(preset)
+                            .unwrap_or_else(|_| json!(format!("{preset:?}"))),
+                        "source": source_summary(project, source),
+                    })
+                })
+                .collect::<Vec<_>>();
+            entries.sort_by_key(|entry| {
+                (
+                    entry["cell"][0].as_i64().unwrap_or_default(),
+                    entry["cell"][1].as_i64().unwrap_or_default(),
+                )
+            });
+            json!({
+                "type": "blend_overrides",
+                "entries": entries,
+            })
+        }
+        _ => serde_json::to_value(value).unwrap_or_else(|_| json!(value.to_string())),
+    }
+}
+
+fn properties_snapshot(project: &Project, properties: &ValueContainer) -> serde_json::Value {
+    let mut values = serde_json::Map::new();
+    for key in properties.keys_sorted() {
+        if let Some(value) = properties.get(key) {
+            values.insert(key.clone(), value_snapshot(project, value));
+        }
+    }
+    serde_json::Value::Object(values)
+}
+
+/// The region a read command targets; without a reference this is the first
+/// region, which Creator opens by default.
+pub fn find_region<'a>(project: &'a Project, region: Option<&RegionRef>) -> Option<&'a Region> {
+    match region {
+        Some(region) => resolve_region_index(project, region)
+            .ok()
+            .map(|index| &project.regions[index]),
+        None => project.regions.first(),
+    }
+}
+
+fn region_not_found(region: Option<&RegionRef>) -> serde_json::Value {
+    json!({
+        "error": "region not found",
+        "request": region,
+    })
+}
+
+/// A normalized 2D authoring snapshot of a region.
+pub fn region_snapshot(
+    project: &Project,
+    region: Option<&RegionRef>,
+    include_tiles: bool,
+) -> serde_json::Value {
+    let Some(found) = find_region(project, region) else {
+        return region_not_found(region);
+    };
+    let region = found;
+
+    let vertices = region
+        .map
+        .vertices
+        .iter()
+        .map(|vertex| {
+            json!({
+                "id": vertex.id,
+                "name": vertex.name,
+                "position": [vertex.x, vertex.y, vertex.z],
+                "properties": properties_snapshot(project, &vertex.properties),
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let linedefs = region
+        .map
+        .linedefs
+        .iter()
+        .map(|linedef| {
+            let start = region.map.find_vertex(linedef.start_vertex);
+            let end = region.map.find_vertex(linedef.end_vertex);
+            json!({
+                "id": linedef.id,
+                "creator_id": linedef.creator_id.to_string(),
+                "name": linedef.name,
+                "start_vertex": linedef.start_vertex,
+                "end_vertex": linedef.end_vertex,
+                "start": start.map(|vertex| json!([vertex.x, vertex.y, vertex.z])),
+                "end": end.map(|vertex| json!([vertex.x, vertex.y, vertex.z])),
+                "sector_ids": linedef.sector_ids,
+                "length": linedef.length(&region.map),
+                "properties": properties_snapshot(project, &linedef.properties),
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let sectors = region
+        .map
+        .sectors
+        .iter()
+        .map(|sector| {
+            let polygon = sector
+                .linedefs
+                .iter()
+                .filter_map(|linedef_id| {
+                    let linedef = region.map.find_linedef(*linedef_id)?;
+                    let vertex = region.map.find_vertex(linedef.start_vertex)?;
+                    Some(json!([vertex.x, vertex.y, vertex.z]))
+                })
+                .collect::<Vec<_>>();
+            let bbox = sector.bounding_box(&region.map);
+            let center = sector.center(&region.map);
+
+            json!({
+                "id": sector.id,
+                "creator_id": sector.creator_id.to_string(),
+                "name": sector.name,
+                "layer": sector.layer,
+                "linedefs": sector.linedefs,
+                "polygon": polygon,
+                "bbox": {
+                    "min": [bbox.min.x, bbox.min.y],
+                    "max": [bbox.max.x, bbox.max.y],
+                },
+                "center": center.map(|center| json!([center.x, center.y])),
+                "area": sector.area(&region.map),
+                "properties": properties_snapshot(project, &sector.properties),
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let characters = region
+        .characters
+        .values()
+        .map(|character| {
+            json!({
+                "id": character.id.to_string(),
+                "template_id": character.character_id.to_string(),
+                "name": character.name,
+                "position": [character.position.x, character.position.y, character.position.z],
+                "orientation": [character.orientation.x, character.orientation.y],
+                "source_len": character.source.len(),
+                "data_len": character.data.len(),
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let items = region
+        .items
+        .values()
+        .map(|item| {
+            json!({
+                "id": item.id.to_string(),
+                "template_id": item.item_id.to_string(),
+                "name": item.name,
+                "position": [item.position.x, item.position.y, item.position.z],
+                "source_len": item.source.len(),
+                "data_len": item.data.len(),
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let mut body = json!({
+        "id": region.id.to_string(),
+        "name": region.name,
+        "map": {
+            "id": region.map.id.to_string(),
+            "name": region.map.name,
+            "camera": format!("{:?}", region.map.camera),
+            "grid_size": region.map.grid_size,
+            "subdivisions": region.map.subdivisions,
+            "authoring_notes": authoring_notes(),
+            "vertices": vertices,
+            "linedefs": linedefs,
+            "sectors": sectors,
+            "characters": characters,
+            "items": items,
+            "counts": {
+                "vertices": region.map.vertices.len(),
+                "linedefs": region.map.linedefs.len(),
+                "sectors": region.map.sectors.len(),
+                "geometry_objects": region.map.geometry_objects.len(),
+                "lights": region.map.lights.len(),
+                "entities": region.map.entities.len(),
+                "items": region.map.items.len(),
+                "region_characters": region.characters.len(),
+                "region_items": region.items.len(),
+            },
+        },
+    });
+
+    if include_tiles && let Some(object) = body.as_object_mut() {
+        object.insert("tile_lookup".to_string(), tiles_snapshot(project, None));
+    }
+
+    body
+}
+
+/// A compact AI-oriented summary of a 2D region with an ASCII overview.
+pub fn region_summary(
+    project: &Project,
+    region: Option<&RegionRef>,
+    include_ascii: bool,
+) -> serde_json::Value {
+    match find_region(project, region) {
+        Some(found) => eldiron_scepter::region_summary(project, found, include_ascii),
+        None => region_not_found(region),
+    }
+}
+
+pub fn base64_encode(bytes: &[u8]) -> String {
+    const TABLE: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+
+    for chunk in bytes.chunks(3) {
+        let b0 = chunk[0];
+        let b1 = chunk.get(1).copied().unwrap_or(0);
+        let b2 = chunk.get(2).copied().unwrap_or(0);
+        let triple = ((b0 as u32) << 16) | ((b1 as u32) << 8) | b2 as u32;
+
+        encoded.push(TABLE[((triple >> 18) & 0x3f) as usize] as char);
+        encoded.push(TABLE[((triple >> 12) & 0x3f) as usize] as char);
+        if chunk.len() > 1 {
+            encoded.push(TABLE[((triple >> 6) & 0x3f) as usize] as char);
+        } else {
+            encoded.push('=');
+        }
+        if chunk.len() > 2 {
+            encoded.push(TABLE[(triple & 0x3f) as usize] as char);
+        } else {
+            encoded.push('=');
+        }
+    }
+
+    encoded
+}
+
+pub fn base64_decode(text: &str) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::with_capacity(text.len() / 4 * 3);
+    let mut buffer = 0u32;
+    let mut bits = 0u32;
+
+    for ch in text.chars().filter(|ch| !ch.is_whitespace()) {
+        if ch == '=' {
+            break;
+        }
+        let value = match ch {
+            'A'..='Z' => ch as u32 - 'A' as u32,
+            'a'..='z' => ch as u32 - 'a' as u32 + 26,
+            '0'..='9' => ch as u32 - '0' as u32 + 52,
+            '+' | '-' => 62,
+            '/' | '_' => 63,
+            _ => return Err(format!("invalid base64 character '{ch}'")),
+        };
+        buffer = (buffer << 6) | value;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+
+    Ok(bytes)
+}
+
+fn encode_png(rgb: &[u8], width: usize, height: usize) -> Result<Vec<u8>, String> {
+    let mut png_data = Vec::new();
+    {
+        let mut encoder = png::Encoder::new(&mut png_data, width as u32, height as u32);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder
+            .write_header()
+            .map_err(|error| format!("could not write preview PNG header: {error}"))?;
+        writer
+            .write_image_data(rgb)
+            .map_err(|error| format!("could not encode preview PNG: {error}"))?;
+    }
+    Ok(png_data)
+}
+
+/// Blend the first frame of a tile into an RGB image cell.
+fn blit_tile(
+    rgb: &mut [u8],
+    image_width: usize,
+    texture: &rusterix::Texture,
+    origin: (usize, usize),
+    cell_pixels: usize,
+) {
+    for py in 0..cell_pixels {
+        let ty = py * texture.height / cell_pixels;
+        for px in 0..cell_pixels {
+            let tx = px * texture.width / cell_pixels;
+            let source_index = (ty * texture.width + tx) * 4;
+            if source_index + 3 >= texture.data.len() {
+                continue;
+            }
+
+            let alpha = texture.data[source_index + 3] as u16;
+            if alpha == 0 {
+                continue;
+            }
+            let target_index = ((origin.1 + py) * image_width + origin.0 + px) * 3;
+            for channel in 0..3 {
+                let src = texture.data[source_index + channel] as u16;
+                let dst = rgb[target_index + channel] as u16;
+                rgb[target_index + channel] = ((src * alpha + dst * (255 - alpha)) / 255) as u8;
+            }
+        }
+    }
+}
+
+/// Render the tile sources of a region's sectors into a base64 PNG.
+pub fn region_render_preview(
+    project: &Project,
+    command: &RegionRenderPreview,
+) -> serde_json::Value {
+    let region_index = match resolve_region_index(project, &command.region) {
+        Ok(index) => index,
+        Err(error) => return json!({ "ok": false, "error": error }),
+    };
+    let region = &project.regions[region_index];
+    let map = &region.map;
+
+    let (min_x, max_x, min_y, max_y) = if let Some([x, y, width, height]) = command.bounds {
+        if width == 0 || height == 0 {
+            return json!({
+                "ok": false,
+                "error": "region.render_preview bounds require non-zero width and height",
+            });
+        }
+        (
+            x.min(x + width),
+            x.max(x + width),
+            y.min(y + height),
+            y.max(y + height),
+        )
+    } else if let Some((min_x, max_x, min_y, max_y)) = map_bounds(map) {
+        (
+            min_x.floor() as i32,
+            max_x.ceil() as i32,
+            min_y.floor() as i32,
+            max_y.ceil() as i32,
+        )
+    } else {
+        return json!({
+            "ok": false,
+            "error": "region has no geometry to render",
+        });
+    };
+
+    let grid_width = (max_x - min_x).max(0) as usize;
+    let grid_height = (max_y - min_y).max(0) as usize;
+    if grid_width == 0 || grid_height == 0 {
+        return json!({
+            "ok": false,
+            "error": "region.render_preview resolved empty bounds",
+        });
+    }
+    if grid_width > 128 || grid_height > 128 {
+        return json!({
+            "ok": false,
+            "error": "region.render_preview bounds exceed 128x128 cells",
+            "bounds": {
+                "min": [min_x, min_y],
+                "max": [max_x, max_y],
+                "size": [grid_width, grid_height],
+            }
+        });
+    }
+
+    let zoom = command.zoom.unwrap_or(2).clamp(1, 8) as usize;
+    let cell_pixels = 16usize * zoom;
+    let width = grid_width * cell_pixels;
+    let height = grid_height * cell_pixels;
+    let mut rgb = vec![16u8; width * height * 3];
+
+    for sector in &map.sectors {
+        let Some(texture) = sector
+            .properties
+            .get("source")
+            .and_then(|value| value.to_source())
+            .and_then(|source| resolved_tile_id(project, source))
+            .and_then(|tile_id| project.tiles.get(&tile_id))
+            .and_then(|tile| tile.textures.first())
+        else {
+            continue;
+        };
+
+        let bbox = sector.bounding_box(map);
+        let sx0 = (bbox.min.x.floor() as i32).max(min_x);
+        let sx1 = (bbox.max.x.ceil() as i32).min(max_x);
+        let sy0 = (bbox.min.y.floor() as i32).max(min_y);
+        let sy1 = (bbox.max.y.ceil() as i32).min(max_y);
+
+        for cell_y in sy0..sy1 {
+            for cell_x in sx0..sx1 {
+                let origin = (
+                    (cell_x - min_x) as usize * cell_pixels,
+                    (cell_y - min_y) as usize * cell_pixels,
+                );
+                blit_tile(&mut rgb, width, texture, origin, cell_pixels);
+            }
+        }
+    }
+
+    let png_data = match encode_png(&rgb, width, height) {
+        Ok(png_data) => png_data,
+        Err(error) => return json!({ "ok": false, "error": error }),
+    };
+
+    json!({
+        "ok": true,
+        "region": {
+            "id": region.id.to_string(),
+            "name": region.name,
+        },
+        "bounds": {
+            "min": [min_x, min_y],
+            "max": [max_x, max_y],
+            "size": [grid_width, grid_height],
+            "coordinate_system": "x right, negative y up; first image row is min_y/up",
+        },
+        "image": {
+            "mime": "image/png",
+            "encoding": "base64",
+            "data": base64_encode(&png_data),
+            "width": width,
+            "height": height,
+            "grid_width": grid_width,
+            "grid_height": grid_height,
+            "cell_pixels": cell_pixels,
+            "bytes": png_data.len(),
+        }
+    })
+}
+
+/// Render the selected tiles side by side so a client can pick tiles by
+/// appearance. Tiles appear in request order, row by row.
+pub fn tile_contact_sheet(project: &Project, command: &TileContactSheet) -> serde_json::Value {
+    if command.tiles.is_empty() {
+        return json!({ "ok": false, "error": "tile.contact_sheet requires at least one tile" });
+    }
+    if command.tiles.len() > 256 {
+        return json!({ "ok": false, "error": "tile.contact_sheet is limited to 256 tiles" });
+    }
+
+    let mut tile_ids = Vec::with_capacity(command.tiles.len());
+    for selector in &command.tiles {
+        match resolve_tile_selector(project, selector) {
+            Ok(tile_id) => tile_ids.push(tile_id),
+            Err(error) => return json!({ "ok": false, "error": error }),
+        }
+    }
+
+    let columns = command.columns.unwrap_or(8).clamp(1, 32) as usize;
+    let rows = tile_ids.len().div_ceil(columns);
+    let cell_pixels = 32usize;
+    let spacing = 2usize;
+    let stride = cell_pixels + spacing;
+    let width = columns * stride + spacing;
+    let height = rows * stride + spacing;
+    let mut rgb = vec![16u8; width * height * 3];
+
+    let mut entries = Vec::with_capacity(tile_ids.len());
+    for (index, tile_id) in tile_ids.iter().enumerate() {
+        let column = index % columns;
+        let row = index / columns;
+        if let Some(texture) = project
+            .tiles
+            .get(tile_id)
+            .and_then(|tile| tile.textures.first())
+        {
+            let origin = (spacing + column * stride, spacing + row * stride);
+            blit_tile(&mut rgb, width, texture, origin, cell_pixels);
+        }
+        entries.push(json!({
+            "index": index,
+            "cell": [column, row],
+            "tile": tile_summary(project, tile_id),
+        }));
+    }
+
+    let png_data = match encode_png(&rgb, width, height) {
+        Ok(png_data) => png_data,
+        Err(error) => return json!({ "ok": false, "error": error }),
+    };
+
+    json!({
+        "ok": true,
+        "columns": columns,
+        "rows": rows,
+        "tiles": entries,
+        "image": {
+            "mime": "image/png",
+            "encoding": "base64",
+            "data": base64_encode(&png_data),
+            "width": width,
+            "height": height,
+            "cell_pixels": cell_pixels,
+            "bytes": png_data.len(),
+        }
+    })
+}
+
+pub fn resolve_region_index(project: &Project, region: &RegionRef) -> Result<usize, String> {
+    match region {
+        RegionRef::Id { id } => {
+            let id = Uuid::parse_str(id).map_err(|err| format!("invalid region id: {err}"))?;
+            project
+                .regions
+                .iter()
+                .position(|region| region.id == id)
+                .ok_or_else(|| format!("region id not found: {id}"))
+        }
+        RegionRef::Name { name } => project
+            .regions
+            .iter()
+            .position(|region| region.name.eq_ignore_ascii_case(name))
+            .ok_or_else(|| format!("region name not found: {name}")),
+    }
+}
+
+pub fn normalize_match_text(value: &str) -> String {
+    value
+        .chars()
+        .filter(|ch| !ch.is_whitespace() && *ch != '_' && *ch != '-')
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+pub fn resolve_tile_selector(project: &Project, selector: &TileSelector) -> Result<Uuid, String> {
+    if let Some(id) = &selector.id {
+        let id = Uuid::parse_str(id).map_err(|err| format!("invalid tile id: {err}"))?;
+        if project.tiles.contains_key(&id) {
+            return Ok(id);
+        }
+        return Err(format!("tile id not found: {id}"));
+    }
+
+    if let Some(alias) = &selector.alias
+        && let Some(tile) = project
+            .tiles
+            .values()
+            .find(|tile| tile.alias.eq_ignore_ascii_case(alias))
+    {
+        return Ok(tile.id);
+    }
+
+    let role = selector.role.as_deref().map(normalize_match_text);
+    let kind = selector.kind.as_deref().map(normalize_match_text);
+    let style = selector.style.as_deref().map(normalize_match_text);
+    let tags = selector
+        .tags
+        .iter()
+        .map(|tag| normalize_match_text(tag))
+        .collect::<Vec<_>>();
+
+    project
+        .tiles
+        .values()
+        .find(|tile| {
+            role.as_ref()
+                .is_none_or(|role| normalize_match_text(tile.role.to_string()) == *role)
+                && kind
+                    .as_ref()
+                    .is_none_or(|kind| normalize_match_text(&tile.procedural.kind) == *kind)
+                && style
+                    .as_ref()
+                    .is_none_or(|style| normalize_match_text(&tile.procedural.style) == *style)
+                && tags.iter().all(|tag| {
+                    let alias = normalize_match_text(&tile.alias);
+                    let kind = normalize_match_text(&tile.procedural.kind);
+                    let style = normalize_match_text(&tile.procedural.style);
+                    alias.contains(tag) || kind.contains(tag) || style.contains(tag)
+                })
+        })
+        .map(|tile| tile.id)
+        .ok_or_else(|| format!("no tile matched selector: {selector:?}"))
+}
+
+/// Parse a tile role from its display name, ignoring case and separators.
+pub fn parse_tile_role(role: &str) -> Result<TileRole, String> {
+    let wanted = normalize_match_text(role);
+    TileRole::iterator()
+        .find(|candidate| normalize_match_text(candidate.to_string()) == wanted)
+        .ok_or_else(|| format!("unknown tile role: {role}"))
+}
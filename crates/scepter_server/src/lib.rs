@@ -0,0 +1,17 @@
+//! Headless Eldiron Scepter server.
+//!
+//! Loads a `.eldiron` project file and executes Scepter commands and plans
+//! against it without Creator, with undo history and the same JSON protocol
+//! Creator serves. The `eldiron-scepter` binary exposes it over stdio and TCP.
+
+mod content;
+mod edit;
+mod inspect;
+mod plan;
+mod script;
+pub mod serve;
+mod session;
+mod tileset;
+
+pub use serve::{SaveMode, SharedSession, serve_stdio, serve_tcp};
+pub use session::{SERVICE, ScepterResponse, ScepterSession};
@@ -0,0 +1,203 @@
+use clap::{Parser, Subcommand};
+use eldiron_scepter::{ScepterCommand, ScepterLorebook, ScepterPlan};
+use eldiron_scepter_server::{SaveMode, ScepterSession, serve_stdio, serve_tcp};
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+#[derive(Parser)]
+#[command(
+    name = "eldiron-scepter",
+    version,
+    about = "Headless Eldiron Scepter server for .eldiron projects.",
+    long_about = "Eldiron Scepter executes Scepter commands and plans directly against a .eldiron project file, without Creator. It speaks the same JSON protocol as Creator's Scepter listener on stdio and TCP, keeps an undo history, and writes changes back to the project file.",
+    after_help = "Examples:\n  eldiron-scepter serve game.eldiron\n  eldiron-scepter serve game.eldiron --tcp --port 37687\n  eldiron-scepter run game.eldiron plan.json\n  echo '{\"command\":\"region.list\"}' | eldiron-scepter run game.eldiron -\n  eldiron-scepter lorebook\n\nRun `eldiron-scepter help <command>` for command-specific help."
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Commands,
+}
+
+#[derive(Subcommand)]
+enum Commands {
+    /// Serve Scepter commands for a project on stdio, TCP, or both.
+    Serve {
+        /// The .eldiron project to edit.
+        project: PathBuf,
+
+        /// Read line-delimited JSON commands from stdin. This is the default
+        /// when --tcp is not given.
+        #[arg(long)]
+        stdio: bool,
+
+        /// Serve Creator's HTTP endpoints on a TCP port.
+        #[arg(long)]
+        tcp: bool,
+
+        /// Address to bind the TCP listener to.
+        #[arg(long, default_value = "127.0.0.1")]
+        host: String,
+
+        /// Port for the TCP listener.
+        #[arg(long, default_value_t = 37687)]
+        port: u16,
+
+        /// Keep changes in memory instead of saving after every edit.
+        #[arg(long)]
+        no_save: bool,
+    },
+
+    /// Run a plan, a command, or a list of commands once and print the result.
+    Run {
+        /// The .eldiron project to edit.
+        project: PathBuf,
+
+        /// JSON file with a plan, a single command, or an array of commands.
+        /// Use - to read from stdin.
+        commands: PathBuf,
+
+        /// Write the result to this file instead of the input project.
+        #[arg(long)]
+        output: Option<PathBuf>,
+
+        /// Preview the changes without writing any file.
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Print the built-in Scepter Lorebook as JSON.
+    Lorebook,
+}
+
+fn main() {
+    if let Err(err) = run() {
+        eprintln!("eldiron-scepter: {err}");
+        std::process::exit(1);
+    }
+}
+
+fn run() -> Result<(), String> {
+    let cli = Cli::parse();
+    match cli.command {
+        Commands::Serve {
+            project,
+            stdio,
+            tcp,
+            host,
+            port,
+            no_save,
+        } => serve(&project, stdio, tcp, host, port, no_save),
+        Commands::Run {
+            project,
+            commands,
+            output,
+            dry_run,
+        } => run_plan(&project, &commands, output.as_deref(), dry_run),
+        Commands::Lorebook => {
+            let lorebook = serde_json::to_string_pretty(&ScepterLorebook::built_in())
+                .map_err(|err| err.to_string())?;
+            println!("{lorebook}");
+            Ok(())
+        }
+    }
+}
+
+fn serve(
+    project: &Path,
+    stdio: bool,
+    tcp: bool,
+    host: String,
+    port: u16,
+    no_save: bool,
+) -> Result<(), String> {
+    let session = Arc::new(Mutex::new(ScepterSession::open(project)?));
+    let save = if no_save {
+        SaveMode::Never
+    } else {
+        SaveMode::Autosave
+    };
+    eprintln!("eldiron-scepter: opened {}", project.display());
+
+    match (stdio || !tcp, tcp) {
+        (true, true) => {
+            let tcp_session = session.clone();
+            let listener = thread::spawn(move || serve_tcp(tcp_session, &host, port, save));
+            serve_stdio(session, save)?;
+            // Keep answering TCP clients once stdin closes.
+            listener
+                .join()
+                .map_err(|_| "TCP listener panicked".to_string())?
+        }
+        (true, false) => serve_stdio(session, save),
+        (false, _) => serve_tcp(session, &host, port, save),
+    }
+}
+
+/// Parse a plan, a single command, or an array of commands into one plan.
+fn parse_plan(text: &str) -> Result<ScepterPlan, String> {
+    let value = serde_json::from_str::<serde_json::Value>(text)
+        .map_err(|err| format!("commands are not JSON: {err}"))?;
+    if value.is_array() {
+        let commands = serde_json::from_value::<Vec<ScepterCommand>>(value)
+            .map_err(|err| format!("not a valid command list: {err}"))?;
+        return Ok(ScepterPlan {
+            commands,
+            ..ScepterPlan::default()
+        });
+    }
+    if value.get("commands").is_some() {
+        return serde_json::from_value::<ScepterPlan>(value)
+            .map_err(|err| format!("not a valid ScepterPlan: {err}"));
+    }
+    let command = serde_json::from_value::<ScepterCommand>(value)
+        .map_err(|err| format!("not a valid ScepterCommand: {err}"))?;
+    Ok(ScepterPlan {
+        commands: vec![command],
+        ..ScepterPlan::default()
+    })
+}
+
+fn run_plan(
+    project: &Path,
+    commands: &Path,
+    output: Option<&Path>,
+    dry_run: bool,
+) -> Result<(), String> {
+    let text = if commands == Path::new("-") {
+        let mut text = String::new();
+        std::io::stdin()
+            .read_to_string(&mut text)
+            .map_err(|err| format!("stdin: {err}"))?;
+        text
+    } else {
+        fs::read_to_string(commands).map_err(|err| format!("{}: {err}", commands.display()))?
+    };
+    let plan = parse_plan(&text)?;
+
+    let mut session = ScepterSession::open(project)?;
+    let response = if dry_run {
+        session.preview_plan(&plan)
+    } else {
+        session.apply_plan(&plan)
+    };
+    let body = serde_json::to_string_pretty(&response.body).map_err(|err| err.to_string())?;
+    println!("{body}");
+
+    if !response.succeeded() {
+        return Err("plan failed; the project was not written".to_string());
+    }
+    if dry_run || !session.is_dirty() {
+        return Ok(());
+    }
+    match output {
+        Some(output) => session.save_to(output)?,
+        None => session.save()?,
+    }
+    eprintln!(
+        "eldiron-scepter: wrote {}",
+        output.unwrap_or(project).display()
+    );
+    Ok(())
+}
@@ -0,0 +1,453 @@
+use crate::inspect::{resolve_region_index, resolve_tile_selector, tile_summary};
+use crate::script;
+use crate::tileset::find_tileset_index;
+use eldiron_scepter::{RegionRef, ScepterCommand, ScriptGet, ScriptTarget, TileSelector};
+use serde::Serialize;
+use serde_json::json;
+use shared::prelude::{Project, Region};
+use std::collections::{BTreeMap, HashMap};
+
+/// Problems that would stop `command` from running against `project`. An
+/// empty list means everything the command refers to exists and its scripts
+/// compile, not that the command will succeed.
+pub fn command_issues(project: &Project, command: &ScepterCommand) -> Vec<String> {
+    let mut issues = Vec::new();
+    let mut regions: Vec<&RegionRef> = Vec::new();
+    let mut tiles: Vec<&TileSelector> = Vec::new();
+    let mut tilesets: Vec<&str> = Vec::new();
+    let mut targets: Vec<&ScriptTarget> = Vec::new();
+    let mut scripts: Vec<(&ScriptTarget, &str)> = Vec::new();
+
+    match command {
+        ScepterCommand::PlanValidate(_)
+        | ScepterCommand::PlanPreview(_)
+        | ScepterCommand::PlanApply(_) => {
+            issues.push(format!("{} can not be nested in a plan", command.name()));
+        }
+        ScepterCommand::ProjectUndo | ScepterCommand::ProjectRedo => {
+            issues.push(format!(
+                "{} can not run inside a plan; the whole plan is one undo step",
+                command.name()
+            ));
+        }
+        ScepterCommand::RegionSnapshot(params) => regions.extend(params.region.as_ref()),
+        ScepterCommand::RegionSummary(params) => regions.extend(params.region.as_ref()),
+        ScepterCommand::RegionRenderPreview(command) => regions.push(&command.region),
+        ScepterCommand::RegionPaintRect(command) => {
+            regions.push(&command.region);
+            tiles.push(&command.tile);
+        }
+        ScepterCommand::RegionPaintOutline(command) => {
+            regions.push(&command.region);
+            tiles.push(&command.tile);
+        }
+        ScepterCommand::RegionPaintCells(command) => {
+            regions.push(&command.region);
+            tiles.push(&command.tile);
+        }
+        ScepterCommand::RegionCreateSector(command) => regions.push(&command.region),
+        ScepterCommand::RegionPlaceItem(command) => {
+            regions.push(&command.region);
+            if !project
+                .items
+                .values()
+                .any(|item| item.name.eq_ignore_ascii_case(&command.template))
+            {
+                issues.push(format!("item template not found: {}", command.template));
+            }
+        }
+        ScepterCommand::RegionPlaceCharacter(command) => {
+            regions.push(&command.region);
+            if !project
+                .characters
+                .values()
+                .any(|character| character.name.eq_ignore_ascii_case(&command.template))
+            {
+                issues.push(format!(
+                    "character template not found: {}",
+                    command.template
+                ));
+            }
+        }
+        ScepterCommand::TileContactSheet(command) => tiles.extend(&command.tiles),
+        ScepterCommand::TileSetMeta(command) => tiles.push(&command.tile),
+        ScepterCommand::TileGroupCreate(command) => {
+            tiles.extend(command.members.iter().map(|member| &member.tile));
+        }
+        ScepterCommand::TilesetInspect(command) => tilesets.extend(command.tileset.as_deref()),
+        ScepterCommand::TilesetGridDetect(command) => tilesets.push(&command.tileset),
+        ScepterCommand::TilesetListUnimported(command) => tilesets.push(&command.tileset),
+        ScepterCommand::TilesetImportTile(command) => tilesets.push(&command.tileset),
+        ScepterCommand::TilesetImportAnim(command) => tilesets.push(&command.tileset),
+        ScepterCommand::TilesetImportMulti(command) => tilesets.push(&command.tileset),
+        ScepterCommand::TilesetImportBatch(command) => tilesets.push(&command.tileset),
+        ScepterCommand::ScriptGet(command) => targets.push(&command.target),
+        ScepterCommand::ScriptPatch(command) => {
+            targets.push(&command.target);
+            scripts.push((&command.target, &command.patch));
+        }
+        ScepterCommand::ScriptValidate(command) => {
+            scripts.push((&command.target, &command.source));
+        }
+        ScepterCommand::AttributesGet(command) => targets.push(&command.target),
+        ScepterCommand::AttributesPatch(command) => targets.push(&command.target),
+        ScepterCommand::CharacterList(command) => regions.extend(command.region.as_ref()),
+        ScepterCommand::ItemList(command) => regions.extend(command.region.as_ref()),
+        ScepterCommand::DialogGet(command) => targets.push(&command.target),
+        ScepterCommand::DialogPatch(command) => targets.push(&command.target),
+        ScepterCommand::GeometryCreateRoom(command) => {
+            regions.push(&command.region);
+            tiles.extend(command.wall_tile.as_ref());
+            tiles.extend(command.floor_tile.as_ref());
+        }
+        ScepterCommand::GeometryPlaceBuilderAsset(command) => regions.push(&command.region),
+        ScepterCommand::ScepterHelp
+        | ScepterCommand::ScepterListCommands
+        | ScepterCommand::ScepterDescribeCommand { .. }
+        | ScepterCommand::ProjectDescribe
+        | ScepterCommand::RegionList
+        | ScepterCommand::TileList(_)
+        | ScepterCommand::TilesetList
+        | ScepterCommand::TileCreateFromRgba(_)
+        | ScepterCommand::CharacterCreate(_)
+        | ScepterCommand::ItemCreate(_)
+        | ScepterCommand::RulesGet(_)
+        | ScepterCommand::RulesPatch(_)
+        | ScepterCommand::LocalePatch(_) => {}
+    }
+
+    issues.extend(
+        regions
+            .into_iter()
+            .filter_map(|region| resolve_region_index(project, region).err()),
+    );
+    issues.extend(
+        tiles
+            .into_iter()
+            .filter_map(|tile| resolve_tile_selector(project, tile).err()),
+    );
+    issues.extend(
+        tilesets
+            .into_iter()
+            .filter_map(|tileset| find_tileset_index(project, tileset).err()),
+    );
+    for target in targets {
+        let script = script::get_script(
+            project,
+            &ScriptGet {
+                target: target.clone(),
+            },
+        );
+        if script["ok"] != json!(true) {
+            issues.push(
+                script["error"]
+                    .as_str()
+                    .unwrap_or("script target not found")
+                    .to_string(),
+            );
+        }
+    }
+    for (target, source) in scripts {
+        let validation = script::validate_eldrin_source(target, source);
+        for diagnostic in validation["diagnostics"].as_array().into_iter().flatten() {
+            issues.push(format!(
+                "script does not compile: line {}: {}",
+                diagnostic["line"],
+                diagnostic["message"].as_str().unwrap_or_default()
+            ));
+        }
+    }
+
+    issues
+}
+
+fn serialized<T: Serialize>(value: &T) -> serde_json::Value {
+    serde_json::to_value(value).unwrap_or_default()
+}
+
+/// Added, removed and changed keys between two keyed collections.
+fn entry_changes(
+    before: &BTreeMap<String, serde_json::Value>,
+    after: &BTreeMap<String, serde_json::Value>,
+) -> serde_json::Value {
+    let added: Vec<&String> = after
+        .keys()
+        .filter(|key| !before.contains_key(*key))
+        .collect();
+    let removed: Vec<&String> = before
+        .keys()
+        .filter(|key| !after.contains_key(*key))
+        .collect();
+    let changed: Vec<&String> = after
+        .iter()
+        .filter(|(key, value)| before.get(*key).is_some_and(|old| old != *value))
+        .map(|(key, _)| key)
+        .collect();
+    json!({
+        "added": added,
+        "removed": removed,
+        "changed": changed,
+    })
+}
+
+fn is_unchanged(changes: &serde_json::Value) -> bool {
+    ["added", "removed", "changed"]
+        .iter()
+        .all(|key| changes[key].as_array().is_none_or(Vec::is_empty))
+}
+
+/// Serialize keyed entries so two versions can be compared.
+fn keyed<'a, K: ToString, V: Serialize + 'a>(
+    entries: impl IntoIterator<Item = (K, &'a V)>,
+) -> BTreeMap<String, serde_json::Value> {
+    entries
+        .into_iter()
+        .map(|(key, value)| (key.to_string(), serialized(value)))
+        .collect()
+}
+
+fn region_changes(before: &Region, after: &Region) -> Option<serde_json::Value> {
+    let geometry = |region: &Region| {
+        let map = &region.map;
+        [
+            keyed(map.sectors.iter().map(|sector| (sector.id, sector))),
+            keyed(map.linedefs.iter().map(|linedef| (linedef.id, linedef))),
+            keyed(map.vertices.iter().map(|vertex| (vertex.id, vertex))),
+        ]
+    };
+    let [old_sectors, old_linedefs, old_vertices] = geometry(before);
+    let [new_sectors, new_linedefs, new_vertices] = geometry(after);
+    let sectors = entry_changes(&old_sectors, &new_sectors);
+    let linedefs = entry_changes(&old_linedefs, &new_linedefs);
+    let vertices = entry_changes(&old_vertices, &new_vertices);
+    let characters = entry_changes(&keyed(&before.characters), &keyed(&after.characters));
+    let items = entry_changes(&keyed(&before.items), &keyed(&after.items));
+    let properties_changed =
+        serialized(&before.map.properties) != serialized(&after.map.properties);
+
+    if [&sectors, &linedefs, &vertices, &characters, &items]
+        .into_iter()
+        .all(is_unchanged)
+        && !properties_changed
+    {
+        return None;
+    }
+
+    Some(json!({
+        "id": after.id.to_string(),
+        "name": after.name,
+        "status": "changed",
+        "sectors": sectors,
+        "linedefs": linedefs,
+        "vertices": vertices,
+        "characters": characters,
+        "items": items,
+        "properties_changed": properties_changed,
+    }))
+}
+
+fn regions_diff(before: &Project, after: &Project) -> Vec<serde_json::Value> {
+    let mut changes = Vec::new();
+    for region in &after.regions {
+        match before.regions.iter().find(|old| old.id == region.id) {
+            Some(old) => changes.extend(region_changes(old, region)),
+            None => changes.push(json!({
+                "id": region.id.to_string(),
+                "name": region.name,
+                "status": "added",
+            })),
+        }
+    }
+    for region in &before.regions {
+        if !after.regions.iter().any(|new| new.id == region.id) {
+            changes.push(json!({
+                "id": region.id.to_string(),
+                "name": region.name,
+                "status": "removed",
+            }));
+        }
+    }
+    changes
+}
+
+/// Tiles are compared by their metadata and frame sizes; serializing every
+/// texture for a preview would cost more than the edit itself.
+fn tiles_diff(before: &Project, after: &Project) -> serde_json::Value {
+    let summaries = |project: &Project| {
+        project
+            .tiles
+            .keys()
+            .filter_map(|id| Some((id.to_string(), tile_summary(project, id)?)))
+            .collect::<BTreeMap<_, _>>()
+    };
+    let old = summaries(before);
+    let new = summaries(after);
+    let changes = entry_changes(&old, &new);
+    let describe = |key: &str| {
+        changes[key]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|id| id.as_str())
+            .filter_map(|id| new.get(id).or_else(|| old.get(id)).cloned())
+            .collect::<Vec<_>>()
+    };
+    json!({
+        "added": describe("added"),
+        "removed": describe("removed"),
+        "changed": describe("changed"),
+    })
+}
+
+fn tile_groups_diff(before: &Project, after: &Project) -> serde_json::Value {
+    entry_changes(&keyed(&before.tile_groups), &keyed(&after.tile_groups))
+}
+
+fn tilesets_diff(before: &Project, after: &Project) -> Vec<serde_json::Value> {
+    let mut changes = Vec::new();
+    for tilemap in &after.tilemaps {
+        let tiles_before = before
+            .tilemaps
+            .iter()
+            .find(|old| old.id == tilemap.id)
+            .map(|old| old.tiles.len());
+        if tiles_before != Some(tilemap.tiles.len()) {
+            changes.push(json!({
+                "id": tilemap.id.to_string(),
+                "name": tilemap.name,
+                "status": if tiles_before.is_some() { "changed" } else { "added" },
+                "tiles_before": tiles_before.unwrap_or(0),
+                "tiles_after": tilemap.tiles.len(),
+            }));
+        }
+    }
+    for tilemap in &before.tilemaps {
+        if !after.tilemaps.iter().any(|new| new.id == tilemap.id) {
+            changes.push(json!({
+                "id": tilemap.id.to_string(),
+                "name": tilemap.name,
+                "status": "removed",
+            }));
+        }
+    }
+    changes
+}
+
+/// Every script and attribute block of a project, keyed by owner.
+fn script_sources(project: &Project) -> BTreeMap<String, (serde_json::Value, &str, &str)> {
+    let mut sources = BTreeMap::new();
+    sources.insert(
+        "world".to_string(),
+        (
+            json!({ "kind": "world" }),
+            project.world_source.as_str(),
+            "",
+        ),
+    );
+    for (id, character) in &project.characters {
+        sources.insert(
+            format!("character:{id}"),
+            (
+                json!({ "kind": "character", "scope": "template", "id": id.to_string(), "name": character.name }),
+                character.source.as_str(),
+                character.data.as_str(),
+            ),
+        );
+    }
+    for (id, item) in &project.items {
+        sources.insert(
+            format!("item:{id}"),
+            (
+                json!({ "kind": "item", "scope": "template", "id": id.to_string(), "name": item.name }),
+                item.source.as_str(),
+                item.data.as_str(),
+            ),
+        );
+    }
+    for region in &project.regions {
+        sources.insert(
+            format!("region:{}", region.id),
+            (
+                json!({ "kind": "region", "id": region.id.to_string(), "name": region.name }),
+                region.source.as_str(),
+                "",
+            ),
+        );
+        for (id, character) in &region.characters {
+            sources.insert(
+                format!("region:{}:character:{id}", region.id),
+                (
+                    json!({ "kind": "character", "scope": "instance", "region": region.name, "id": id.to_string(), "name": character.name }),
+                    character.source.as_str(),
+                    character.data.as_str(),
+                ),
+            );
+        }
+        for (id, item) in &region.items {
+            sources.insert(
+                format!("region:{}:item:{id}", region.id),
+                (
+                    json!({ "kind": "item", "scope": "instance", "region": region.name, "id": id.to_string(), "name": item.name }),
+                    item.source.as_str(),
+                    item.data.as_str(),
+                ),
+            );
+        }
+    }
+    sources
+}
+
+/// Lines added and removed, ignoring order.
+fn line_changes(before: &str, after: &str) -> (usize, usize) {
+    let mut counts: HashMap<&str, i64> = HashMap::new();
+    for line in before.lines() {
+        *counts.entry(line).or_default() -= 1;
+    }
+    for line in after.lines() {
+        *counts.entry(line).or_default() += 1;
+    }
+    let added = counts.values().filter(|count| **count > 0).sum::<i64>();
+    let removed = counts.values().filter(|count| **count < 0).sum::<i64>();
+    (added as usize, removed.unsigned_abs() as usize)
+}
+
+fn scripts_diff(before: &Project, after: &Project) -> Vec<serde_json::Value> {
+    let old = script_sources(before);
+    let new = script_sources(after);
+    let mut changes = Vec::new();
+    for (key, (target, source, data)) in &new {
+        let (old_source, old_data) = old
+            .get(key)
+            .map(|(_, source, data)| (*source, *data))
+            .unwrap_or_default();
+        for (field, before, after) in [
+            ("source", old_source, *source),
+            ("attributes", old_data, *data),
+        ] {
+            if before == after {
+                continue;
+            }
+            let (lines_added, lines_removed) = line_changes(before, after);
+            changes.push(json!({
+                "target": target,
+                "field": field,
+                "lines_added": lines_added,
+                "lines_removed": lines_removed,
+            }));
+        }
+    }
+    changes
+}
+
+/// Summarize what changed between two versions of a project: regions with
+/// their geometry and placed entities, tiles, tile groups, tilesets, and
+/// scripts.
+pub fn project_diff(before: &Project, after: &Project) -> serde_json::Value {
+    json!({
+        "regions": regions_diff(before, after),
+        "tiles": tiles_diff(before, after),
+        "tile_groups": tile_groups_diff(before, after),
+        "tilesets": tilesets_diff(before, after),
+        "scripts": scripts_diff(before, after),
+    })
+}
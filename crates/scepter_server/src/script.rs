@@ -0,0 +1,797 @@
+use crate::inspect::resolve_region_index;
+use eldiron_scepter::{
+    AttributesGet, AttributesPatch, DialogGet, DialogPatch, RegionRef, ScriptGet, ScriptPatch,
+    ScriptTarget, ScriptTargetKind,
+};
+use rusterix::vm::{Parser, VM};
+use serde_json::json;
+use shared::prelude::Project;
+use std::panic::{AssertUnwindSafe, catch_unwind};
+use std::path::PathBuf;
+use theframework::prelude::Uuid;
+
+/// The region a script target lives in. Region targets without an explicit
+/// region fall back to the first region of the project.
+pub fn script_target_region_index(
+    project: &Project,
+    target: &ScriptTarget,
+) -> Result<Option<usize>, String> {
+    if let Some(region) = &target.region {
+        return resolve_region_index(project, region).map(Some);
+    }
+
+    if target.kind == ScriptTargetKind::Region {
+        if let Some(id) = &target.id {
+            return resolve_region_index(project, &RegionRef::Id { id: id.clone() }).map(Some);
+        }
+        if let Some(name) = &target.name {
+            return resolve_region_index(project, &RegionRef::Name { name: name.clone() })
+                .map(Some);
+        }
+        if project.regions.is_empty() {
+            return Err("project has no regions".to_string());
+        }
+        return Ok(Some(0));
+    }
+
+    Ok(None)
+}
+
+fn target_match(id: &Uuid, name: &str, target: &ScriptTarget) -> Result<bool, String> {
+    if let Some(target_id) = &target.id {
+        let target_id =
+            Uuid::parse_str(target_id).map_err(|err| format!("invalid target id: {err}"))?;
+        return Ok(*id == target_id);
+    }
+
+    if let Some(target_name) = &target.name {
+        return Ok(name.eq_ignore_ascii_case(target_name));
+    }
+
+    Ok(false)
+}
+
+fn target_missing_error(target: &ScriptTarget) -> String {
+    match target.kind {
+        ScriptTargetKind::World => "world target not found".to_string(),
+        ScriptTargetKind::Region => "region target not found".to_string(),
+        ScriptTargetKind::Character => {
+            "character target requires an id or name and must exist".to_string()
+        }
+        ScriptTargetKind::Item => "item target requires an id or name and must exist".to_string(),
+    }
+}
+
+/// Where a character or item target was found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EntityScope {
+    /// An instance placed in a region.
+    RegionInstance(usize, Uuid),
+    /// A project template.
+    Template(Uuid),
+}
+
+impl EntityScope {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::RegionInstance(..) => "region_instance",
+            Self::Template(_) => "template",
+        }
+    }
+
+    fn id(&self) -> Uuid {
+        match self {
+            Self::RegionInstance(_, id) | Self::Template(id) => *id,
+        }
+    }
+}
+
+/// Find a character or item target, preferring region instances over
+/// templates the same way Creator does.
+fn find_entity(project: &Project, target: &ScriptTarget) -> Result<Option<EntityScope>, String> {
+    let region_index = script_target_region_index(project, target)?;
+
+    match target.kind {
+        ScriptTargetKind::Character => {
+            if let Some(index) = region_index
+                && let Some(character) =
+                    project.regions[index]
+                        .characters
+                        .values()
+                        .find(|character| {
+                            target_match(&character.id, &character.name, target).unwrap_or(false)
+                        })
+            {
+                return Ok(Some(EntityScope::RegionInstance(index, character.id)));
+            }
+
+            Ok(project
+                .characters
+                .values()
+                .find(|character| {
+                    target_match(&character.id, &character.name, target).unwrap_or(false)
+                })
+                .map(|character| EntityScope::Template(character.id)))
+        }
+        ScriptTargetKind::Item => {
+            if let Some(index) = region_index
+                && let Some(item) = project.regions[index]
+                    .items
+                    .values()
+                    .find(|item| target_match(&item.id, &item.name, target).unwrap_or(false))
+            {
+                return Ok(Some(EntityScope::RegionInstance(index, item.id)));
+            }
+
+            Ok(project
+                .items
+                .values()
+                .find(|item| target_match(&item.id, &item.name, target).unwrap_or(false))
+                .map(|item| EntityScope::Template(item.id)))
+        }
+        ScriptTargetKind::World | ScriptTargetKind::Region => Ok(None),
+    }
+}
+
+/// Mutable access to the text fields of a found character or item.
+struct EntityFields<'a> {
+    name: &'a str,
+    source: &'a mut String,
+    source_debug: &'a mut String,
+    data: &'a mut String,
+}
+
+fn entity_fields<'a>(
+    project: &'a mut Project,
+    kind: ScriptTargetKind,
+    scope: EntityScope,
+) -> Option<EntityFields<'a>> {
+    match (kind, scope) {
+        (ScriptTargetKind::Character, EntityScope::RegionInstance(index, id)) => project.regions
+            [index]
+            .characters
+            .get_mut(&id)
+            .map(|character| EntityFields {
+                name: &character.name,
+                source: &mut character.source,
+                source_debug: &mut character.source_debug,
+                data: &mut character.data,
+            }),
+        (ScriptTargetKind::Character, EntityScope::Template(id)) => project
+            .characters
+            .get_mut(&id)
+            .map(|character| EntityFields {
+                name: &character.name,
+                source: &mut character.source,
+                source_debug: &mut character.source_debug,
+                data: &mut character.data,
+            }),
+        (ScriptTargetKind::Item, EntityScope::RegionInstance(index, id)) => project.regions[index]
+            .items
+            .get_mut(&id)
+            .map(|item| EntityFields {
+                name: &item.name,
+                source: &mut item.source,
+                source_debug: &mut item.source_debug,
+                data: &mut item.data,
+            }),
+        (ScriptTargetKind::Item, EntityScope::Template(id)) => {
+            project.items.get_mut(&id).map(|item| EntityFields {
+                name: &item.name,
+                source: &mut item.source,
+                source_debug: &mut item.source_debug,
+                data: &mut item.data,
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Read access to the name, source, debug source and data of a found
+/// character or item.
+fn entity_view(
+    project: &Project,
+    kind: ScriptTargetKind,
+    scope: EntityScope,
+) -> Option<(&str, &str, &str, &str)> {
+    match (kind, scope) {
+        (ScriptTargetKind::Character, EntityScope::RegionInstance(index, id)) => {
+            project.regions[index].characters.get(&id).map(|c| {
+                (
+                    c.name.as_str(),
+                    c.source.as_str(),
+                    c.source_debug.as_str(),
+                    c.data.as_str(),
+                )
+            })
+        }
+        (ScriptTargetKind::Character, EntityScope::Template(id)) => {
+            project.characters.get(&id).map(|c| {
+                (
+                    c.name.as_str(),
+                    c.source.as_str(),
+                    c.source_debug.as_str(),
+                    c.data.as_str(),
+                )
+            })
+        }
+        (ScriptTargetKind::Item, EntityScope::RegionInstance(index, id)) => {
+            project.regions[index].items.get(&id).map(|i| {
+                (
+                    i.name.as_str(),
+                    i.source.as_str(),
+                    i.source_debug.as_str(),
+                    i.data.as_str(),
+                )
+            })
+        }
+        (ScriptTargetKind::Item, EntityScope::Template(id)) => project.items.get(&id).map(|i| {
+            (
+                i.name.as_str(),
+                i.source.as_str(),
+                i.source_debug.as_str(),
+                i.data.as_str(),
+            )
+        }),
+        _ => None,
+    }
+}
+
+fn kind_name(kind: ScriptTargetKind) -> &'static str {
+    match kind {
+        ScriptTargetKind::World => "world",
+        ScriptTargetKind::Region => "region",
+        ScriptTargetKind::Character => "character",
+        ScriptTargetKind::Item => "item",
+    }
+}
+
+fn script_payload(
+    kind: &str,
+    scope: &str,
+    id: Option<Uuid>,
+    name: &str,
+    source: &str,
+    source_debug: &str,
+) -> serde_json::Value {
+    json!({
+        "ok": true,
+        "kind": kind,
+        "scope": scope,
+        "id": id.map(|id| id.to_string()),
+        "name": name,
+        "source": source,
+        "source_debug": source_debug,
+        "source_len": source.len(),
+    })
+}
+
+pub fn get_script(project: &Project, command: &ScriptGet) -> serde_json::Value {
+    let target = &command.target;
+    match target.kind {
+        ScriptTargetKind::World => script_payload(
+            "world",
+            "project",
+            None,
+            "World",
+            &project.world_source,
+            &project.world_source_debug,
+        ),
+        ScriptTargetKind::Region => {
+            let region_index = match script_target_region_index(project, target) {
+                Ok(Some(index)) => index,
+                Ok(None) => return json!({ "ok": false, "error": "region not found" }),
+                Err(error) => return json!({ "ok": false, "error": error }),
+            };
+            let region = &project.regions[region_index];
+            script_payload(
+                "region",
+                "project",
+                Some(region.id),
+                &region.name,
+                &region.source,
+                &region.source_debug,
+            )
+        }
+        ScriptTargetKind::Character | ScriptTargetKind::Item => {
+            let scope = match find_entity(project, target) {
+                Ok(Some(scope)) => scope,
+                Ok(None) => return json!({ "ok": false, "error": target_missing_error(target) }),
+                Err(error) => return json!({ "ok": false, "error": error }),
+            };
+            let Some((name, source, source_debug, _)) = entity_view(project, target.kind, scope)
+            else {
+                return json!({ "ok": false, "error": target_missing_error(target) });
+            };
+            script_payload(
+                kind_name(target.kind),
+                scope.name(),
+                Some(scope.id()),
+                name,
+                source,
+                source_debug,
+            )
+        }
+    }
+}
+
+/// Parse and compile Eldrin source the way the server does when it loads a
+/// script. The VM stops at the first error, which is returned with its
+/// 1-based line.
+fn compile_eldrin(name: &str, source: &str) -> Result<(), (usize, String)> {
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        let mut parser = Parser::new();
+        let module = parser
+            .compile_module(
+                name.to_string(),
+                source.to_string(),
+                PathBuf::from(format!("{name}.eldrin")),
+            )
+            .map_err(|err| (err.line, err.message))?;
+        let mut vm = VM::default();
+        vm.compile(&module).map_err(|err| (err.line, err.message))
+    }));
+    result.unwrap_or_else(|_| {
+        Err((
+            source.lines().count(),
+            "unexpected end of script".to_string(),
+        ))
+    })
+}
+
+pub fn validate_eldrin_source(target: &ScriptTarget, source: &str) -> serde_json::Value {
+    let name = target.name.as_deref().unwrap_or(kind_name(target.kind));
+    let diagnostics = match compile_eldrin(name, source) {
+        Ok(()) => vec![],
+        Err((line, message)) => vec![json!({ "line": line, "message": message })],
+    };
+    json!({
+        "ok": true,
+        "valid": diagnostics.is_empty(),
+        "target": {
+            "kind": kind_name(target.kind),
+            "id": target.id,
+            "name": target.name,
+            "region": target.region,
+        },
+        "source_len": source.len(),
+        "diagnostics": diagnostics,
+    })
+}
+
+pub fn apply_script_patch(project: &mut Project, command: ScriptPatch) -> serde_json::Value {
+    if command.validate {
+        let validation = validate_eldrin_source(&command.target, &command.patch);
+        if !validation
+            .get("valid")
+            .and_then(|value| value.as_bool())
+            .unwrap_or(false)
+        {
+            return json!({
+                "ok": false,
+                "error": "script does not compile",
+                "validation": validation,
+            });
+        }
+    }
+
+    let target = command.target;
+    let new_source = command.patch;
+    let (scope, name) = match target.kind {
+        ScriptTargetKind::World => {
+            project.world_source = new_source.clone();
+            project.world_source_debug = new_source.clone();
+            ("project", "World".to_string())
+        }
+        ScriptTargetKind::Region => {
+            let region_index = match script_target_region_index(project, &target) {
+                Ok(Some(index)) => index,
+                Ok(None) => return json!({ "ok": false, "error": "region not found" }),
+                Err(error) => return json!({ "ok": false, "error": error }),
+            };
+            let region = &mut project.regions[region_index];
+            region.source = new_source.clone();
+            region.source_debug = new_source.clone();
+            ("project", region.name.clone())
+        }
+        ScriptTargetKind::Character | ScriptTargetKind::Item => {
+            let scope = match find_entity(project, &target) {
+                Ok(Some(scope)) => scope,
+                Ok(None) => return json!({ "ok": false, "error": target_missing_error(&target) }),
+                Err(error) => return json!({ "ok": false, "error": error }),
+            };
+            let Some(fields) = entity_fields(project, target.kind, scope) else {
+                return json!({ "ok": false, "error": target_missing_error(&target) });
+            };
+            *fields.source = new_source.clone();
+            *fields.source_debug = new_source.clone();
+            (scope.name(), fields.name.to_string())
+        }
+    };
+
+    shared::rusterix_utils::insert_content_into_maps(project);
+
+    json!({
+        "ok": true,
+        "command": "script.patch",
+        "mode": "replace_source",
+        "kind": kind_name(target.kind),
+        "scope": scope,
+        "name": name,
+        "source_len": new_source.len(),
+    })
+}
+
+pub fn json_to_toml(value: serde_json::Value) -> Result<toml::Value, String> {
+    match value {
+        serde_json::Value::Null => {
+            Err("null is not a TOML value; use remove for deletion".to_string())
+        }
+        serde_json::Value::Bool(value) => Ok(toml::Value::Boolean(value)),
+        serde_json::Value::Number(value) => {
+            if let Some(value) = value.as_i64() {
+                Ok(toml::Value::Integer(value))
+            } else if let Some(value) = value.as_f64() {
+                Ok(toml::Value::Float(value))
+            } else {
+                Err("number is outside TOML's supported range".to_string())
+            }
+        }
+        serde_json::Value::String(value) => Ok(toml::Value::String(value)),
+        serde_json::Value::Array(values) => values
+            .into_iter()
+            .map(json_to_toml)
+            .collect::<Result<Vec<_>, _>>()
+            .map(toml::Value::Array),
+        serde_json::Value::Object(values) => {
+            let mut table = toml::Table::new();
+            for (key, value) in values {
+                table.insert(key, json_to_toml(value)?);
+            }
+            Ok(toml::Value::Table(table))
+        }
+    }
+}
+
+fn attributes_payload(
+    kind: &str,
+    scope: &str,
+    id: Uuid,
+    name: &str,
+    data: &str,
+) -> serde_json::Value {
+    let (attributes, parse_error) = match data.parse::<toml::Table>() {
+        Ok(table) => (
+            table
+                .get("attributes")
+                .and_then(toml::Value::as_table)
+                .cloned()
+                .unwrap_or_default(),
+            None,
+        ),
+        Err(error) => (toml::Table::new(), Some(error.to_string())),
+    };
+
+    json!({
+        "ok": parse_error.is_none(),
+        "kind": kind,
+        "scope": scope,
+        "id": id.to_string(),
+        "name": name,
+        "data": data,
+        "attributes": attributes,
+        "parse_error": parse_error,
+    })
+}
+
+pub fn get_attributes(project: &Project, command: &AttributesGet) -> serde_json::Value {
+    let target = &command.target;
+    if matches!(
+        target.kind,
+        ScriptTargetKind::World | ScriptTargetKind::Region
+    ) {
+        return json!({
+            "ok": false,
+            "error": "attributes.get currently supports character and item targets",
+        });
+    }
+
+    let scope = match find_entity(project, target) {
+        Ok(Some(scope)) => scope,
+        Ok(None) => return json!({ "ok": false, "error": target_missing_error(target) }),
+        Err(error) => return json!({ "ok": false, "error": error }),
+    };
+    let Some((name, _, _, data)) = entity_view(project, target.kind, scope) else {
+        return json!({ "ok": false, "error": target_missing_error(target) });
+    };
+    attributes_payload(kind_name(target.kind), scope.name(), scope.id(), name, data)
+}
+
+pub fn patch_data_source(
+    data: &str,
+    values: serde_json::Map<String, serde_json::Value>,
+    remove: &[String],
+) -> Result<(String, Vec<String>, Vec<String>), String> {
+    let mut table = if data.trim().is_empty() {
+        toml::Table::new()
+    } else {
+        data.parse::<toml::Table>()
+            .map_err(|err| format!("existing TOML data is invalid: {err}"))?
+    };
+
+    let attributes = table
+        .entry("attributes".to_string())
+        .or_insert_with(|| toml::Value::Table(toml::Table::new()))
+        .as_table_mut()
+        .ok_or_else(|| "[attributes] exists but is not a TOML table".to_string())?;
+
+    let mut changed = Vec::new();
+    for (key, value) in values {
+        attributes.insert(key.clone(), json_to_toml(value)?);
+        changed.push(key);
+    }
+
+    let mut removed = Vec::new();
+    for key in remove {
+        if attributes.remove(key).is_some() {
+            removed.push(key.clone());
+        }
+    }
+
+    let source = toml::to_string_pretty(&table)
+        .map_err(|err| format!("could not serialize TOML data: {err}"))?;
+    Ok((source, changed, removed))
+}
+
+pub fn apply_attributes_patch(
+    project: &mut Project,
+    command: AttributesPatch,
+) -> serde_json::Value {
+    let target = command.target;
+    if matches!(
+        target.kind,
+        ScriptTargetKind::World | ScriptTargetKind::Region
+    ) {
+        return json!({
+            "ok": false,
+            "error": "attributes.patch currently supports character and item targets",
+        });
+    }
+
+    let scope = match find_entity(project, &target) {
+        Ok(Some(scope)) => scope,
+        Ok(None) => return json!({ "ok": false, "error": target_missing_error(&target) }),
+        Err(error) => return json!({ "ok": false, "error": error }),
+    };
+    let Some(fields) = entity_fields(project, target.kind, scope) else {
+        return json!({ "ok": false, "error": target_missing_error(&target) });
+    };
+
+    let (data, changed, removed) =
+        match patch_data_source(fields.data, command.values, &command.remove) {
+            Ok(patched) => patched,
+            Err(error) => return json!({ "ok": false, "error": error }),
+        };
+    if command.validate
+        && let Err(err) = data.parse::<toml::Table>()
+    {
+        return json!({ "ok": false, "error": format!("patched TOML is invalid: {err}") });
+    }
+    *fields.data = data;
+    let name = fields.name.to_string();
+
+    shared::rusterix_utils::insert_content_into_maps(project);
+
+    json!({
+        "ok": true,
+        "command": "attributes.patch",
+        "kind": kind_name(target.kind),
+        "scope": scope.name(),
+        "name": name,
+        "changed": changed,
+        "removed": removed,
+    })
+}
+
+/// The nodes of a `[dialog]` table. Nodes normally live under
+/// `[dialog.nodes]`; the runtime also accepts node tables directly under
+/// `[dialog]`, which are listed when no `nodes` table shadows them.
+fn dialog_nodes(dialog: &toml::Table) -> toml::Table {
+    let mut nodes = dialog
+        .iter()
+        .filter(|(key, value)| key.as_str() != "nodes" && value.is_table())
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .collect::<toml::Table>();
+    if let Some(table) = dialog.get("nodes").and_then(toml::Value::as_table) {
+        nodes.extend(
+            table
+                .iter()
+                .map(|(key, value)| (key.clone(), value.clone())),
+        );
+    }
+    nodes
+}
+
+/// Broken references in a dialog: a missing start node and choices that
+/// continue to a node that does not exist.
+fn dialog_issues(dialog: &toml::Table) -> Vec<String> {
+    let nodes = dialog_nodes(dialog);
+    let mut issues = Vec::new();
+    let start = dialog
+        .get("start")
+        .and_then(toml::Value::as_str)
+        .unwrap_or("start");
+    if !nodes.contains_key(start) {
+        issues.push(format!("start node not found: {start}"));
+    }
+    for (name, node) in &nodes {
+        let choices = node.get("choices").and_then(toml::Value::as_array);
+        for (index, choice) in choices.into_iter().flatten().enumerate() {
+            let Some(choice) = choice.as_table() else {
+                issues.push(format!("{name}: choice {index} is not a table"));
+                continue;
+            };
+            if let Some(next) = choice.get("next").and_then(toml::Value::as_str)
+                && !nodes.contains_key(next)
+            {
+                issues.push(format!(
+                    "{name}: choice {index} continues to missing node {next}"
+                ));
+            }
+        }
+    }
+    issues
+}
+
+fn dialog_table(data: &str) -> Result<toml::Table, String> {
+    if data.trim().is_empty() {
+        return Ok(toml::Table::new());
+    }
+    data.parse::<toml::Table>()
+        .map_err(|err| format!("existing TOML data is invalid: {err}"))
+}
+
+pub fn get_dialog(project: &Project, command: &DialogGet) -> serde_json::Value {
+    let target = &command.target;
+    if target.kind != ScriptTargetKind::Character {
+        return json!({ "ok": false, "error": "dialogs belong to character targets" });
+    }
+
+    let scope = match find_entity(project, target) {
+        Ok(Some(scope)) => scope,
+        Ok(None) => return json!({ "ok": false, "error": target_missing_error(target) }),
+        Err(error) => return json!({ "ok": false, "error": error }),
+    };
+    let Some((name, _, _, data)) = entity_view(project, target.kind, scope) else {
+        return json!({ "ok": false, "error": target_missing_error(target) });
+    };
+    let table = match dialog_table(data) {
+        Ok(table) => table,
+        Err(error) => return json!({ "ok": false, "error": error }),
+    };
+    let Some(dialog) = table.get("dialog").and_then(toml::Value::as_table) else {
+        return json!({
+            "ok": true,
+            "kind": "character",
+            "scope": scope.name(),
+            "id": scope.id().to_string(),
+            "name": name,
+            "has_dialog": false,
+            "nodes": {},
+        });
+    };
+
+    json!({
+        "ok": true,
+        "kind": "character",
+        "scope": scope.name(),
+        "id": scope.id().to_string(),
+        "name": name,
+        "has_dialog": true,
+        "start": dialog.get("start").and_then(toml::Value::as_str).unwrap_or("start"),
+        "nodes": dialog_nodes(dialog),
+        "issues": dialog_issues(dialog),
+    })
+}
+
+pub fn apply_dialog_patch(project: &mut Project, command: DialogPatch) -> serde_json::Value {
+    let target = command.target;
+    if target.kind != ScriptTargetKind::Character {
+        return json!({ "ok": false, "error": "dialogs belong to character targets" });
+    }
+
+    let scope = match find_entity(project, &target) {
+        Ok(Some(scope)) => scope,
+        Ok(None) => return json!({ "ok": false, "error": target_missing_error(&target) }),
+        Err(error) => return json!({ "ok": false, "error": error }),
+    };
+    let Some(fields) = entity_fields(project, target.kind, scope) else {
+        return json!({ "ok": false, "error": target_missing_error(&target) });
+    };
+    let mut table = match dialog_table(fields.data) {
+        Ok(table) => table,
+        Err(error) => return json!({ "ok": false, "error": error }),
+    };
+
+    let Some(dialog) = table
+        .entry("dialog".to_string())
+        .or_insert_with(|| toml::Value::Table(toml::Table::new()))
+        .as_table_mut()
+    else {
+        return json!({ "ok": false, "error": "[dialog] exists but is not a TOML table" });
+    };
+    if let Some(start) = &command.start {
+        dialog.insert("start".to_string(), toml::Value::String(start.clone()));
+    }
+
+    let mut removed = Vec::new();
+    for name in &command.remove {
+        let in_nodes = dialog
+            .get_mut("nodes")
+            .and_then(toml::Value::as_table_mut)
+            .and_then(|nodes| nodes.remove(name))
+            .is_some();
+        let legacy =
+            dialog.get(name).is_some_and(toml::Value::is_table) && dialog.remove(name).is_some();
+        if in_nodes || legacy {
+            removed.push(name.clone());
+        }
+    }
+
+    let mut changed = Vec::new();
+    for (name, node) in command.nodes {
+        let node = match json_to_toml(node) {
+            Ok(node @ toml::Value::Table(_)) => node,
+            Ok(_) => {
+                return json!({ "ok": false, "error": format!("dialog node {name} must be an object") });
+            }
+            Err(error) => return json!({ "ok": false, "error": format!("{name}: {error}") }),
+        };
+        let Some(nodes) = dialog
+            .entry("nodes".to_string())
+            .or_insert_with(|| toml::Value::Table(toml::Table::new()))
+            .as_table_mut()
+        else {
+            return json!({ "ok": false, "error": "[dialog.nodes] exists but is not a TOML table" });
+        };
+        nodes.insert(name.clone(), node);
+        changed.push(name);
+    }
+
+    let issues = dialog_issues(dialog);
+    if command.validate && !issues.is_empty() {
+        return json!({
+            "ok": false,
+            "error": "dialog has broken node references",
+            "issues": issues,
+        });
+    }
+    let start = dialog
+        .get("start")
+        .and_then(toml::Value::as_str)
+        .unwrap_or("start")
+        .to_string();
+
+    let data = match toml::to_string_pretty(&table) {
+        Ok(data) => data,
+        Err(err) => {
+            return json!({ "ok": false, "error": format!("could not serialize TOML data: {err}") });
+        }
+    };
+    *fields.data = data;
+    let name = fields.name.to_string();
+
+    shared::rusterix_utils::insert_content_into_maps(project);
+
+    json!({
+        "ok": true,
+        "command": "dialog.patch",
+        "kind": "character",
+        "scope": scope.name(),
+        "name": name,
+        "start": start,
+        "changed": changed,
+        "removed": removed,
+        "issues": issues,
+    })
+}
@@ -0,0 +1,293 @@
+use crate::session::{SERVICE, ScepterResponse, ScepterSession};
+use eldiron_scepter::{
+    ScepterCommand, ScepterLorebook, ScepterPlan, parse_request_line, path_without_query,
+    ping_message,
+};
+use serde_json::json;
+use std::io::{BufRead, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+
+/// HTTP endpoints served over TCP. The first seven match Creator's listener.
+pub const ENDPOINTS: [&str; 8] = [
+    "/scepter/ping",
+    "/scepter/lorebook",
+    "/scepter/command",
+    "/scepter/project",
+    "/scepter/region",
+    "/scepter/region/summary",
+    "/scepter/tiles",
+    "/scepter/plan",
+];
+
+/// Largest request body accepted over TCP.
+const MAX_BODY: usize = 64 * 1024 * 1024;
+
+/// How the server persists changes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SaveMode {
+    /// Write the project file after every command that changed it.
+    Autosave,
+    /// Keep all changes in memory.
+    Never,
+}
+
+pub type SharedSession = Arc<Mutex<ScepterSession>>;
+
+/// A request line on stdio: either a single command or a plan.
+fn execute_json(session: &mut ScepterSession, request: &str) -> ScepterResponse {
+    let value = match serde_json::from_str::<serde_json::Value>(request) {
+        Ok(value) => value,
+        Err(err) => return ScepterResponse::error(400, format!("request is not JSON: {err}")),
+    };
+
+    if value.get("commands").is_some() {
+        return match serde_json::from_value::<ScepterPlan>(value) {
+            Ok(plan) => session.apply_plan(&plan),
+            Err(err) => {
+                ScepterResponse::error(400, format!("request is not a valid ScepterPlan: {err}"))
+            }
+        };
+    }
+
+    match serde_json::from_value::<ScepterCommand>(value) {
+        Ok(command) => session.execute(command),
+        Err(err) => {
+            ScepterResponse::error(400, format!("request is not a valid ScepterCommand: {err}"))
+        }
+    }
+}
+
+/// Save the project when a request changed it, reporting write failures in
+/// the response so clients never assume a change reached disk.
+fn persist(
+    session: &mut ScepterSession,
+    revision: u64,
+    save: SaveMode,
+    response: &mut ScepterResponse,
+) {
+    if save == SaveMode::Never || session.revision() == revision || !session.is_dirty() {
+        return;
+    }
+    let saved = session.save();
+    if let Some(object) = response.body.as_object_mut() {
+        match saved {
+            Ok(()) => {
+                object.insert(
+                    "saved".to_string(),
+                    json!(session.path().display().to_string()),
+                );
+            }
+            Err(error) => {
+                object.insert("save_error".to_string(), json!(error));
+            }
+        }
+    }
+}
+
+fn handle_request(session: &SharedSession, request: &str, save: SaveMode) -> ScepterResponse {
+    let mut session = match session.lock() {
+        Ok(session) => session,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+    let revision = session.revision();
+    let mut response = execute_json(&mut session, request);
+    persist(&mut session, revision, save, &mut response);
+    response
+}
+
+/// Serve line-delimited JSON on stdin/stdout. Every line is a command or a
+/// plan; every response is written as one compact JSON line.
+pub fn serve_stdio(session: SharedSession, save: SaveMode) -> Result<(), String> {
+    let stdin = std::io::stdin();
+    let mut stdout = std::io::stdout();
+    for line in stdin.lock().lines() {
+        let line = line.map_err(|err| format!("stdin: {err}"))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let response = handle_request(&session, &line, save);
+        let mut body = response.body;
+        if let Some(object) = body.as_object_mut() {
+            object.insert("status".to_string(), json!(response.status));
+        }
+        writeln!(stdout, "{body}").map_err(|err| format!("stdout: {err}"))?;
+        stdout.flush().map_err(|err| format!("stdout: {err}"))?;
+    }
+    Ok(())
+}
+
+/// Serve Creator's HTTP endpoints plus `POST /scepter/plan` on a blocking
+/// TCP listener, one connection at a time.
+pub fn serve_tcp(
+    session: SharedSession,
+    host: &str,
+    port: u16,
+    save: SaveMode,
+) -> Result<(), String> {
+    let listener = TcpListener::bind((host, port))
+        .map_err(|err| format!("could not bind {host}:{port}: {err}"))?;
+    eprintln!("eldiron-scepter: listening on http://{host}:{port}/scepter");
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => handle_stream(stream, &session, save),
+            Err(err) => eprintln!("eldiron-scepter: connection failed: {err}"),
+        }
+    }
+    Ok(())
+}
+
+/// Read one HTTP request, waiting for the full body announced by
+/// Content-Length.
+fn read_request(stream: &mut TcpStream) -> std::io::Result<(String, String, String)> {
+    let mut data = Vec::new();
+    let mut buffer = [0_u8; 16 * 1024];
+    let header_end = loop {
+        let size = stream.read(&mut buffer)?;
+        if size == 0 {
+            break None;
+        }
+        data.extend_from_slice(&buffer[..size]);
+        if let Some(end) = data.windows(4).position(|window| window == b"\r\n\r\n") {
+            break Some(end + 4);
+        }
+        if data.len() > MAX_BODY {
+            break None;
+        }
+    };
+    let header_end = header_end.unwrap_or(data.len());
+    let head = String::from_utf8_lossy(&data[..header_end]).to_string();
+    let content_length = head
+        .lines()
+        .filter_map(|line| line.split_once(':'))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, value)| value.trim().parse::<usize>().ok())
+        .unwrap_or(0)
+        .min(MAX_BODY);
+    while data.len() < header_end + content_length {
+        let size = stream.read(&mut buffer)?;
+        if size == 0 {
+            break;
+        }
+        data.extend_from_slice(&buffer[..size]);
+    }
+
+    let (method, path) = head
+        .lines()
+        .next()
+        .and_then(parse_request_line)
+        .unwrap_or(("GET", "/"));
+    let body = String::from_utf8_lossy(&data[header_end.min(data.len())..]).to_string();
+    Ok((method.to_string(), path.to_string(), body))
+}
+
+fn handle_stream(mut stream: TcpStream, session: &SharedSession, save: SaveMode) {
+    let peer = stream
+        .peer_addr()
+        .map(|addr| addr.to_string())
+        .unwrap_or_else(|_| "local".to_string());
+    let (method, path, body) = match read_request(&mut stream) {
+        Ok(request) => request,
+        Err(err) => {
+            let _ = write_json(&mut stream, &ScepterResponse::error(400, err.to_string()));
+            return;
+        }
+    };
+
+    let response = match (method.as_str(), path_without_query(&path)) {
+        ("GET", "/scepter/ping") | ("POST", "/scepter/ping") => ScepterResponse {
+            status: 200,
+            body: json!({
+                "ok": true,
+                "service": SERVICE,
+                "message": "pong",
+                "received": ping_message(&path, &body),
+                "peer": peer,
+            }),
+        },
+        ("GET", "/scepter/lorebook") => ScepterResponse {
+            status: 200,
+            body: json!(ScepterLorebook::built_in()),
+        },
+        ("POST", "/scepter/command") | ("POST", "/scepter/plan") => {
+            handle_request(session, &body, save)
+        }
+        ("GET", "/scepter/project") => {
+            handle_request(session, r#"{"command":"project.describe"}"#, save)
+        }
+        ("GET", "/scepter/region") => {
+            let command = json!({ "command": "region.snapshot", "params": region_params(&path) });
+            handle_request(session, &command.to_string(), save)
+        }
+        ("GET", "/scepter/region/summary") => {
+            let command = json!({ "command": "region.summary", "params": region_params(&path) });
+            handle_request(session, &command.to_string(), save)
+        }
+        ("GET", "/scepter/tiles") => {
+            handle_request(session, r#"{"command":"tile.list","params":{}}"#, save)
+        }
+        _ => ScepterResponse {
+            status: 404,
+            body: json!({
+                "ok": false,
+                "error": "unknown Scepter endpoint",
+                "endpoints": ENDPOINTS,
+            }),
+        },
+    };
+
+    let _ = write_json(&mut stream, &response);
+}
+
+/// Region snapshot parameters from the `id`, `name`, `include_tiles` and
+/// `include_ascii` query keys Creator accepts.
+fn region_params(path: &str) -> serde_json::Value {
+    let mut params = serde_json::Map::new();
+    let Some(query) = path.split_once('?').map(|(_, query)| query) else {
+        return serde_json::Value::Object(params);
+    };
+
+    for part in query.split('&') {
+        if let Some(value) = part.strip_prefix("id=") {
+            params.insert(
+                "region".to_string(),
+                json!({ "id": value.replace('+', " ") }),
+            );
+        } else if let Some(value) = part.strip_prefix("name=") {
+            params.insert(
+                "region".to_string(),
+                json!({ "name": value.replace('+', " ") }),
+            );
+        } else if let Some(value) = part.strip_prefix("include_tiles=") {
+            params.insert(
+                "include_tiles".to_string(),
+                json!(matches!(value, "1" | "true" | "yes")),
+            );
+        } else if let Some(value) = part.strip_prefix("include_ascii=") {
+            params.insert(
+                "include_ascii".to_string(),
+                json!(matches!(value, "1" | "true" | "yes")),
+            );
+        }
+    }
+
+    serde_json::Value::Object(params)
+}
+
+fn write_json(stream: &mut TcpStream, response: &ScepterResponse) -> std::io::Result<()> {
+    let reason = match response.status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        501 => "Not Implemented",
+        _ => "Internal Server Error",
+    };
+    let body = serde_json::to_string_pretty(&response.body).unwrap_or_else(|_| "{}".to_string());
+    let http = format!(
+        "HTTP/1.1 {} {reason}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        response.status,
+        body.len(),
+        body
+    );
+    stream.write_all(http.as_bytes())
+}
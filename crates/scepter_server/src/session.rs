@@ -0,0 +1,728 @@
+use crate::{content, edit, inspect, plan, script, tileset};
+use eldiron_scepter::{ScepterCommand, ScepterLorebook, ScepterPlan};
+use serde_json::json;
+use shared::prelude::Project;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Service name reported in every response, shared with Creator's listener.
+pub const SERVICE: &str = "eldiron_scepter";
+
+/// Number of project snapshots kept for `project.undo`.
+const UNDO_LIMIT: usize = 64;
+
+/// A protocol response: an HTTP-style status plus the JSON body Creator
+/// would send for the same command.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScepterResponse {
+    pub status: u16,
+    pub body: serde_json::Value,
+}
+
+impl ScepterResponse {
+    /// A successful response carrying `value` under `key`.
+    pub fn ok(key: &str, value: serde_json::Value) -> Self {
+        let mut body = json!({
+            "ok": true,
+            "service": SERVICE,
+        });
+        if let Some(object) = body.as_object_mut() {
+            object.insert(key.to_string(), value);
+        }
+        Self { status: 200, body }
+    }
+
+    pub fn error(status: u16, error: impl Into<String>) -> Self {
+        Self {
+            status,
+            body: json!({
+                "ok": false,
+                "service": SERVICE,
+                "error": error.into(),
+            }),
+        }
+    }
+
+    /// True when the request was served and the command itself reported
+    /// success. Creator wraps command failures in a 200 response with a
+    /// nested `"ok": false`, so both levels are checked.
+    pub fn succeeded(&self) -> bool {
+        if self.status != 200 || self.body["ok"] != json!(true) {
+            return false;
+        }
+        self.body
+            .as_object()
+            .into_iter()
+            .flat_map(|object| object.values())
+            .all(|value| value.get("ok").is_none_or(|ok| *ok == json!(true)))
+    }
+}
+
+/// A plan executed against a scratch copy of the project.
+struct PlanRun {
+    project: Project,
+    results: Vec<serde_json::Value>,
+    /// Index of the first command that failed.
+    failed: Option<usize>,
+    /// Number of commands that changed the scratch project.
+    edits: u64,
+}
+
+/// The error message of a failed response, which Creator reports either at
+/// the top level or inside the nested result.
+fn response_error(body: &serde_json::Value) -> Option<String> {
+    body.get("error")
+        .or_else(|| {
+            body.as_object()?
+                .values()
+                .find_map(|value| value.get("error"))
+        })
+        .and_then(serde_json::Value::as_str)
+        .map(str::to_string)
+}
+
+/// An open `.eldiron` project with the undo history of the commands applied
+/// to it.
+pub struct ScepterSession {
+    project: Project,
+    path: PathBuf,
+    undo: Vec<Project>,
+    redo: Vec<Project>,
+    dirty: bool,
+    revision: u64,
+}
+
+impl ScepterSession {
+    /// Load a project file, applying the same migrations Creator runs on open.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        let contents =
+            fs::read_to_string(path).map_err(|err| format!("{}: {err}", path.display()))?;
+        let mut project = serde_json::from_str::<Project>(&contents)
+            .map_err(|err| format!("{}: not a valid Eldiron project: {err}", path.display()))?;
+        project.migrate_default_ruleset();
+        project.migrate_button_commands();
+        let _ = project.sync_ruleset_items();
+
+        Ok(Self::new(project, path))
+    }
+
+    /// Wrap an in-memory project. Nothing is written until `save` is called.
+    pub fn new(project: Project, path: impl Into<PathBuf>) -> Self {
+        Self {
+            project,
+            path: path.into(),
+            undo: Vec::new(),
+            redo: Vec::new(),
+            dirty: false,
+            revision: 0,
+        }
+    }
+
+    pub fn project(&self) -> &Project {
+        &self.project
+    }
+
+    /// Give up the session and return the edited project.
+    pub fn into_project(self) -> Project {
+        self.project
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// True when the project changed since it was opened or last saved.
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// Increases with every edit, undo and redo.
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// Write the project back to the file it was opened from.
+    pub fn save(&mut self) -> Result<(), String> {
+        let path = self.path.clone();
+        self.save_to(&path)?;
+        self.dirty = false;
+        Ok(())
+    }
+
+    /// Write the project to another file without changing the session path.
+    pub fn save_to(&self, path: &Path) -> Result<(), String> {
+        let json = serde_json::to_string(&self.project)
+            .map_err(|err| format!("could not serialize project: {err}"))?;
+        fs::write(path, json).map_err(|err| format!("{}: {err}", path.display()))
+    }
+
+    /// Execute one command against the project.
+    pub fn execute(&mut self, command: ScepterCommand) -> ScepterResponse {
+        let project = &self.project;
+        match command {
+            ScepterCommand::ScepterHelp => {
+                let lorebook = ScepterLorebook::built_in();
+                ScepterResponse {
+                    status: 200,
+                    body: json!({
+                        "ok": true,
+                        "service": SERVICE,
+                        "help": "Eldiron Scepter is Eldiron's automation API. This headless server edits a .eldiron project file directly; use the Lorebook to discover commands, schemas, capabilities, and examples.",
+                        "endpoints": crate::serve::ENDPOINTS,
+                        "commands": lorebook.list_commands(),
+                    }),
+                }
+            }
+            ScepterCommand::ScepterListCommands => ScepterResponse::ok(
+                "commands",
+                json!(ScepterLorebook::built_in().list_commands()),
+            ),
+            ScepterCommand::ScepterDescribeCommand { name } => {
+                let lorebook = ScepterLorebook::built_in();
+                match lorebook.describe_command(&name) {
+                    Some(command) => ScepterResponse::ok("command", json!(command)),
+                    None => ScepterResponse::error(404, format!("unknown Scepter command: {name}")),
+                }
+            }
+            ScepterCommand::ProjectDescribe => ScepterResponse::ok(
+                "project",
+                inspect::project_snapshot(project, &self.path, self.dirty),
+            ),
+            ScepterCommand::ProjectUndo => ScepterResponse::ok("result", self.undo()),
+            ScepterCommand::ProjectRedo => ScepterResponse::ok("result", self.redo()),
+            ScepterCommand::RegionList => {
+                ScepterResponse::ok("regions", inspect::region_list(project))
+            }
+            ScepterCommand::RegionSnapshot(params) => ScepterResponse::ok(
+                "region",
+                inspect::region_snapshot(project, params.region.as_ref(), params.include_tiles),
+            ),
+            ScepterCommand::RegionSummary(params) => ScepterResponse::ok(
+                "summary",
+                inspect::region_summary(project, params.region.as_ref(), params.include_ascii),
+            ),
+            ScepterCommand::RegionRenderPreview(command) => {
+                ScepterResponse::ok("preview", inspect::region_render_preview(project, &command))
+            }
+            ScepterCommand::TileList(filter) => {
+                ScepterResponse::ok("tiles", inspect::tiles_snapshot(project, Some(&filter)))
+            }
+            ScepterCommand::TileContactSheet(command) => {
+                ScepterResponse::ok("preview", inspect::tile_contact_sheet(project, &command))
+            }
+            ScepterCommand::TilesetList => {
+                ScepterResponse::ok("tilesets", tileset::tileset_list(project))
+            }
+            ScepterCommand::TilesetInspect(command) => {
+                ScepterResponse::ok("tilesets", tileset::tileset_inspect(project, &command))
+            }
+            ScepterCommand::TilesetGridDetect(command) => {
+                ScepterResponse::ok("grid", tileset::tileset_grid_detect(project, &command))
+            }
+            ScepterCommand::TilesetListUnimported(command) => {
+                ScepterResponse::ok("cells", tileset::tileset_list_unimported(project, &command))
+            }
+            ScepterCommand::ScriptGet(command) => {
+                ScepterResponse::ok("script", script::get_script(project, &command))
+            }
+            ScepterCommand::ScriptValidate(command) => ScepterResponse::ok(
+                "validation",
+                script::validate_eldrin_source(&command.target, &command.source),
+            ),
+            ScepterCommand::AttributesGet(command) => {
+                ScepterResponse::ok("attributes", script::get_attributes(project, &command))
+            }
+            ScepterCommand::CharacterList(command) => {
+                ScepterResponse::ok("characters", content::character_list(project, &command))
+            }
+            ScepterCommand::ItemList(command) => {
+                ScepterResponse::ok("items", content::item_list(project, &command))
+            }
+            ScepterCommand::DialogGet(command) => {
+                ScepterResponse::ok("dialog", script::get_dialog(project, &command))
+            }
+            ScepterCommand::RulesGet(command) => {
+                ScepterResponse::ok("rules", content::rules_get(project, &command))
+            }
+            ScepterCommand::RegionPaintRect(command) => {
+                self.edit(|project| edit::paint_rect(project, command))
+            }
+            ScepterCommand::RegionPaintOutline(command) => {
+                self.edit(|project| edit::paint_outline(project, command))
+            }
+            ScepterCommand::RegionPaintCells(command) => {
+                self.edit(|project| edit::paint_cells(project, command))
+            }
+            ScepterCommand::RegionCreateSector(command) => {
+                self.edit(|project| edit::create_sector(project, command))
+            }
+            ScepterCommand::RegionPlaceItem(command) => {
+                self.edit(|project| edit::place_item(project, command))
+            }
+            ScepterCommand::RegionPlaceCharacter(command) => {
+                self.edit(|project| edit::place_character(project, command))
+            }
+            ScepterCommand::TileCreateFromRgba(command) => {
+                self.edit(|project| edit::tile_create_from_rgba(project, command))
+            }
+            ScepterCommand::TileSetMeta(command) => {
+                self.edit(|project| edit::tile_set_meta(project, command))
+            }
+            ScepterCommand::TileGroupCreate(command) => {
+                self.edit(|project| edit::tile_group_create(project, command))
+            }
+            ScepterCommand::TilesetImportTile(command) => {
+                self.edit(|project| tileset::tileset_import_tile(project, command))
+            }
+            ScepterCommand::TilesetImportAnim(command) => {
+                self.edit(|project| tileset::tileset_import_anim(project, command))
+            }
+            ScepterCommand::TilesetImportMulti(command) => {
+                self.edit(|project| tileset::tileset_import_multi(project, command))
+            }
+            ScepterCommand::TilesetImportBatch(command) => {
+                self.edit(|project| tileset::tileset_import_batch(project, command))
+            }
+            ScepterCommand::ScriptPatch(command) => {
+                self.edit(|project| script::apply_script_patch(project, command))
+            }
+            ScepterCommand::AttributesPatch(command) => {
+                self.edit(|project| script::apply_attributes_patch(project, command))
+            }
+            ScepterCommand::CharacterCreate(command) => {
+                self.edit(|project| content::character_create(project, command))
+            }
+            ScepterCommand::ItemCreate(command) => {
+                self.edit(|project| content::item_create(project, command))
+            }
+            ScepterCommand::DialogPatch(command) => {
+                self.edit(|project| script::apply_dialog_patch(project, command))
+            }
+            ScepterCommand::RulesPatch(command) => {
+                self.edit(|project| content::rules_patch(project, command))
+            }
+            ScepterCommand::LocalePatch(command) => {
+                self.edit(|project| content::locale_patch(project, command))
+            }
+            ScepterCommand::GeometryCreateRoom(command) => {
+                self.edit(|project| edit::geometry_create_room(project, command))
+            }
+            ScepterCommand::GeometryPlaceBuilderAsset(command) => {
+                self.edit(|project| edit::geometry_place_builder_asset(project, command))
+            }
+            ScepterCommand::PlanValidate(plan) => self.validate_plan(&plan),
+            ScepterCommand::PlanPreview(plan) => self.preview_plan(&plan),
+            ScepterCommand::PlanApply(plan) => self.apply_plan(&plan),
+        }
+    }
+
+    /// Run a plan against a scratch copy of the project. Every command is
+    /// checked against the result of the commands before it, so a plan can
+    /// import a tile and paint with it in the next step.
+    fn run_plan(&self, plan: &ScepterPlan, stop_at_failure: bool) -> PlanRun {
+        let mut scratch = ScepterSession::new(self.project.clone(), self.path.clone());
+        let mut results = Vec::with_capacity(plan.commands.len());
+        let mut failed = None;
+
+        for (index, command) in plan.commands.iter().enumerate() {
+            let issues = plan::command_issues(&scratch.project, command);
+            let mut result = json!({
+                "index": index,
+                "command": command.name(),
+            });
+            let ok = if issues.is_empty() {
+                let response = scratch.execute(command.clone());
+                if let Some(error) = response_error(&response.body) {
+                    result["error"] = json!(error);
+                }
+                result["status"] = json!(response.status);
+                result["response"] = response.body.clone();
+                response.succeeded()
+            } else {
+                result["issues"] = json!(issues);
+                false
+            };
+            result["ok"] = json!(ok);
+            results.push(result);
+
+            if !ok {
+                failed.get_or_insert(index);
+                if stop_at_failure {
+                    break;
+                }
+            }
+        }
+
+        PlanRun {
+            edits: scratch.revision,
+            project: scratch.project,
+            results,
+            failed,
+        }
+    }
+
+    fn plan_failure(plan: &ScepterPlan, run: PlanRun, message: &str) -> ScepterResponse {
+        let index = run.failed.unwrap_or_default();
+        let name = plan
+            .commands
+            .get(index)
+            .map(ScepterCommand::name)
+            .unwrap_or_default();
+        ScepterResponse {
+            status: 200,
+            body: json!({
+                "ok": false,
+                "service": SERVICE,
+                "plan": plan.name,
+                "error": format!("{message} at command {index} ({name})"),
+                "results": run.results,
+            }),
+        }
+    }
+
+    /// Check every command of a plan without changing the project. Unlike
+    /// preview and apply, validation continues past failures and reports all
+    /// of them.
+    pub fn validate_plan(&self, plan: &ScepterPlan) -> ScepterResponse {
+        let run = self.run_plan(plan, false);
+        let results: Vec<serde_json::Value> = run
+            .results
+            .into_iter()
+            .map(|mut result| {
+                if let Some(object) = result.as_object_mut() {
+                    object.remove("response");
+                }
+                result
+            })
+            .collect();
+        let valid = run.failed.is_none();
+        ScepterResponse::ok(
+            "validation",
+            json!({
+                "ok": valid,
+                "valid": valid,
+                "name": plan.name,
+                "commands": plan.commands.len(),
+                "failed": results.iter().filter(|result| result["ok"] != json!(true)).count(),
+                "results": results,
+            }),
+        )
+    }
+
+    /// Dry-run a plan and report what it would change. The project is not
+    /// modified.
+    pub fn preview_plan(&self, plan: &ScepterPlan) -> ScepterResponse {
+        let run = self.run_plan(plan, true);
+        if run.failed.is_some() {
+            return Self::plan_failure(plan, run, "plan would fail");
+        }
+        ScepterResponse::ok(
+            "preview",
+            json!({
+                "ok": true,
+                "name": plan.name,
+                "commands": plan.commands.len(),
+                "changes": plan::project_diff(&self.project, &run.project),
+                "results": run.results,
+            }),
+        )
+    }
+
+    /// Apply a plan atomically. If any command fails the project is left
+    /// untouched; otherwise the whole plan becomes a single undo step.
+    pub fn apply_plan(&mut self, plan: &ScepterPlan) -> ScepterResponse {
+        let run = self.run_plan(plan, true);
+        if run.failed.is_some() {
+            return Self::plan_failure(plan, run, "plan rolled back");
+        }
+
+        let changes = plan::project_diff(&self.project, &run.project);
+        if run.edits > 0 {
+            self.undo
+                .push(std::mem::replace(&mut self.project, run.project));
+            if self.undo.len() > UNDO_LIMIT {
+                self.undo.remove(0);
+            }
+            self.redo.clear();
+            self.dirty = true;
+            self.revision += 1;
+        }
+
+        ScepterResponse::ok(
+            "plan",
+            json!({
+                "ok": true,
+                "name": plan.name,
+                "applied": run.results.len(),
+                "changes": changes,
+                "undo_depth": self.undo.len(),
+                "results": run.results,
+            }),
+        )
+    }
+
+    /// Run a mutating command against the project. Failed commands leave the
+    /// project untouched; successful ones become one undo step.
+    fn edit(&mut self, apply: impl FnOnce(&mut Project) -> serde_json::Value) -> ScepterResponse {
+        let before = self.project.clone();
+        let result = apply(&mut self.project);
+
+        if result.get("ok") == Some(&json!(true)) {
+            self.undo.push(before);
+            if self.undo.len() > UNDO_LIMIT {
+                self.undo.remove(0);
+            }
+            self.redo.clear();
+            self.dirty = true;
+            self.revision += 1;
+        } else {
+            self.project = before;
+        }
+
+        ScepterResponse::ok("result", result)
+    }
+
+    fn undo(&mut self) -> serde_json::Value {
+        let Some(previous) = self.undo.pop() else {
+            return json!({
+                "ok": false,
+                "command": "project.undo",
+                "message": "nothing to undo",
+            });
+        };
+        self.redo
+            .push(std::mem::replace(&mut self.project, previous));
+        self.dirty = true;
+        self.revision += 1;
+        json!({
+            "ok": true,
+            "command": "project.undo",
+            "dirty": self.dirty,
+            "undo_depth": self.undo.len(),
+            "redo_depth": self.redo.len(),
+        })
+    }
+
+    fn redo(&mut self) -> serde_json::Value {
+        let Some(next) = self.redo.pop() else {
+            return json!({
+                "ok": false,
+                "command": "project.redo",
+                "message": "nothing to redo",
+            });
+        };
+        self.undo.push(std::mem::replace(&mut self.project, next));
+        self.dirty = true;
+        self.revision += 1;
+        json!({
+            "ok": true,
+            "command": "project.redo",
+            "dirty": self.dirty,
+            "undo_depth": self.undo.len(),
+            "redo_depth": self.redo.len(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use eldiron_scepter::{
+        DialogGet, DialogPatch, LocalePatch, RegionPaintRect, RegionRef, ScriptTarget,
+        ScriptTargetKind, TemplateCreate, TileSelector,
+    };
+    use rusterix::{Texture, Tile};
+
+    fn session() -> ScepterSession {
+        let mut project = Project::new();
+        project.regions[0].name = "Harbor".to_string();
+        let mut tile = Tile::from_texture(Texture::new(vec![255; 4 * 4 * 4], 4, 4));
+        tile.alias = "stone_floor".to_string();
+        project.tiles.insert(tile.id, tile);
+        ScepterSession::new(project, "test.eldiron")
+    }
+
+    fn paint(rect: [i32; 4]) -> ScepterCommand {
+        ScepterCommand::RegionPaintRect(RegionPaintRect {
+            region: RegionRef::name("Harbor"),
+            tile: TileSelector::alias("stone_floor"),
+            rect,
+            layer: None,
+            select: None,
+            replace_existing: None,
+        })
+    }
+
+    #[test]
+    fn paint_is_undoable_and_redoable() {
+        let mut session = session();
+        let response = session.execute(paint([0, 0, 2, 2]));
+        assert!(response.succeeded(), "{}", response.body);
+        assert_eq!(session.project().regions[0].map.sectors.len(), 4);
+        assert!(session.is_dirty());
+
+        assert!(session.execute(ScepterCommand::ProjectUndo).succeeded());
+        assert!(session.project().regions[0].map.sectors.is_empty());
+
+        assert!(session.execute(ScepterCommand::ProjectRedo).succeeded());
+        assert_eq!(session.project().regions[0].map.sectors.len(), 4);
+        assert_eq!(session.revision(), 3);
+    }
+
+    #[test]
+    fn failed_command_leaves_project_unchanged() {
+        let mut session = session();
+        let response = session.execute(ScepterCommand::RegionPaintRect(RegionPaintRect {
+            tile: TileSelector::style_kind("missing", "missing"),
+            ..match paint([0, 0, 1, 1]) {
+                ScepterCommand::RegionPaintRect(command) => command,
+                _ => unreachable!(),
+            }
+        }));
+        assert!(!response.succeeded());
+        assert!(!session.is_dirty());
+        assert!(!session.execute(ScepterCommand::ProjectUndo).succeeded());
+    }
+
+    #[test]
+    fn failed_plan_rolls_back_every_command() {
+        let mut session = session();
+        let mut plan = ScepterPlan::new("Floor");
+        plan.push(paint([0, 0, 1, 1]));
+        plan.push(paint([0, 0, 0, 1]));
+        plan.push(paint([4, 4, 1, 1]));
+
+        let response = session.apply_plan(&plan);
+        assert!(!response.succeeded());
+        assert_eq!(response.body["results"].as_array().unwrap().len(), 2);
+        assert!(session.project().regions[0].map.sectors.is_empty());
+        assert!(!session.is_dirty());
+        assert!(!session.execute(ScepterCommand::ProjectUndo).succeeded());
+    }
+
+    #[test]
+    fn applied_plan_is_one_undo_step() {
+        let mut session = session();
+        let mut plan = ScepterPlan::new("Floor");
+        plan.push(paint([0, 0, 2, 1]));
+        plan.push(paint([0, 1, 2, 1]));
+
+        let response = session.execute(ScepterCommand::PlanApply(plan));
+        assert!(response.succeeded(), "{}", response.body);
+        assert_eq!(session.project().regions[0].map.sectors.len(), 4);
+        assert_eq!(session.revision(), 1);
+        let regions = &response.body["plan"]["changes"]["regions"];
+        assert_eq!(regions[0]["sectors"]["added"].as_array().unwrap().len(), 4);
+
+        assert!(session.execute(ScepterCommand::ProjectUndo).succeeded());
+        assert!(session.project().regions[0].map.sectors.is_empty());
+    }
+
+    #[test]
+    fn validate_and_preview_leave_project_untouched() {
+        let mut session = session();
+        let mut plan = ScepterPlan::new("Floor");
+        plan.push(paint([0, 0, 1, 1]));
+        plan.push(ScepterCommand::ProjectUndo);
+        plan.push(ScepterCommand::RegionPaintRect(RegionPaintRect {
+            tile: TileSelector::style_kind("missing", "missing"),
+            ..match paint([1, 1, 1, 1]) {
+                ScepterCommand::RegionPaintRect(command) => command,
+                _ => unreachable!(),
+            }
+        }));
+
+        let validation = session.execute(ScepterCommand::PlanValidate(plan.clone()));
+        assert!(!validation.succeeded());
+        assert_eq!(validation.body["validation"]["failed"], 2);
+
+        let preview = session.execute(ScepterCommand::PlanPreview(plan));
+        assert!(!preview.succeeded());
+
+        let mut plan = ScepterPlan::new("Floor");
+        plan.push(paint([0, 0, 1, 1]));
+        let preview = session.execute(ScepterCommand::PlanPreview(plan));
+        assert!(preview.succeeded(), "{}", preview.body);
+        assert_eq!(
+            preview.body["preview"]["changes"]["regions"][0]["name"],
+            "Harbor"
+        );
+
+        assert!(session.project().regions[0].map.sectors.is_empty());
+        assert!(!session.is_dirty());
+        assert_eq!(session.revision(), 0);
+    }
+
+    #[test]
+    fn character_dialog_round_trip() {
+        let mut session = session();
+        let created = session.execute(ScepterCommand::CharacterCreate(TemplateCreate {
+            name: "Harbor Guard".to_string(),
+            source: None,
+            data: None,
+            attributes: serde_json::from_value(json!({ "faction": "dock_watch" })).unwrap(),
+        }));
+        assert!(created.succeeded(), "{}", created.body);
+        assert_eq!(
+            created.body["result"]["attributes"]["faction"],
+            "dock_watch"
+        );
+
+        let target = ScriptTarget {
+            kind: ScriptTargetKind::Character,
+            region: None,
+            name: Some("Harbor Guard".to_string()),
+            id: None,
+        };
+        let nodes = json!({
+            "greeting": {
+                "text": "{dialog.guard.greeting}",
+                "choices": [{ "label": "{dialog.guard.work}", "next": "work" }]
+            }
+        });
+        let broken = session.execute(ScepterCommand::DialogPatch(DialogPatch {
+            target: target.clone(),
+            start: Some("greeting".to_string()),
+            nodes: serde_json::from_value(nodes.clone()).unwrap(),
+            remove: Vec::new(),
+            validate: true,
+        }));
+        assert!(!broken.succeeded());
+        assert_eq!(session.revision(), 1);
+
+        let mut nodes = nodes;
+        nodes["work"] = json!({ "text": "{dialog.guard.work_text}" });
+        let patched = session.execute(ScepterCommand::DialogPatch(DialogPatch {
+            target: target.clone(),
+            start: Some("greeting".to_string()),
+            nodes: serde_json::from_value(nodes).unwrap(),
+            remove: Vec::new(),
+            validate: true,
+        }));
+        assert!(patched.succeeded(), "{}", patched.body);
+
+        let dialog = session.execute(ScepterCommand::DialogGet(DialogGet { target }));
+        assert_eq!(dialog.body["dialog"]["start"], "greeting");
+        assert_eq!(
+            dialog.body["dialog"]["nodes"]["greeting"]["choices"][0]["next"],
+            "work"
+        );
+    }
+
+    #[test]
+    fn locale_patch_sets_nested_keys() {
+        let mut session = session();
+        let response = session.execute(ScepterCommand::LocalePatch(LocalePatch {
+            locale: "en".to_string(),
+            values: serde_json::from_value(json!({ "dialog.guard.greeting": "Halt." })).unwrap(),
+            remove: Vec::new(),
+        }));
+        assert!(response.succeeded(), "{}", response.body);
+        let locales = session.project().locales.parse::<toml::Table>().unwrap();
+        assert_eq!(
+            locales["en"]["dialog"]["guard"]["greeting"].as_str(),
+            Some("Halt.")
+        );
+    }
+}
@@ -0,0 +1,561 @@
+use crate::inspect::{normalize_match_text, parse_tile_role, tile_summary};
+use eldiron_scepter::{
+    GridPoint, GridRect, TilesetGridDetect, TilesetImportAnim, TilesetImportBatch,
+    TilesetImportMulti, TilesetImportSpec, TilesetImportTile, TilesetInspect,
+    TilesetListUnimported, TilesetTileMeta,
+};
+use rusterix::{TileGroup, TileGroupMemberRef, TileRole};
+use serde_json::json;
+use shared::prelude::{Project, Tilemap};
+use std::collections::HashSet;
+use theframework::prelude::{TheRGBARegion, TheRGBARegionSequence, Uuid};
+
+/// Common tile sizes tried by `tileset.grid_detect`, smallest first.
+const GRID_CANDIDATES: [i32; 8] = [8, 12, 16, 24, 32, 48, 64, 128];
+
+/// Find a tileset by id, display name or filename, ignoring case and file
+/// extension.
+pub fn find_tileset_index(project: &Project, tileset: &str) -> Result<usize, String> {
+    if let Ok(id) = Uuid::parse_str(tileset)
+        && let Some(index) = project.tilemaps.iter().position(|tilemap| tilemap.id == id)
+    {
+        return Ok(index);
+    }
+
+    let wanted = normalize_match_text(tileset);
+    let wanted_stem = normalize_match_text(
+        tileset
+            .rsplit_once('.')
+            .map(|(stem, _)| stem)
+            .unwrap_or(tileset),
+    );
+    project
+        .tilemaps
+        .iter()
+        .position(|tilemap| {
+            let name = normalize_match_text(&tilemap.name);
+            let stem = normalize_match_text(
+                tilemap
+                    .name
+                    .rsplit_once('.')
+                    .map(|(stem, _)| stem)
+                    .unwrap_or(&tilemap.name),
+            );
+            name == wanted || stem == wanted_stem
+        })
+        .ok_or_else(|| format!("tileset not found: {tileset}"))
+}
+
+fn tileset_size(tilemap: &Tilemap) -> (i32, i32) {
+    let dim = tilemap.buffer.dim();
+    (dim.width, dim.height)
+}
+
+fn grid_dims(tilemap: &Tilemap, cell_size: (i32, i32)) -> (i32, i32) {
+    let (width, height) = tileset_size(tilemap);
+    if cell_size.0 <= 0 || cell_size.1 <= 0 {
+        return (0, 0);
+    }
+    (width / cell_size.0, height / cell_size.1)
+}
+
+/// The grid cells each imported tile of the tileset covers.
+fn imported_cells(tilemap: &Tilemap, cell_size: (i32, i32)) -> HashSet<(i32, i32)> {
+    let mut cells = HashSet::new();
+    if cell_size.0 <= 0 || cell_size.1 <= 0 {
+        return cells;
+    }
+    for tile in &tilemap.tiles {
+        for region in &tile.sequence.regions {
+            let x0 = region.x as i32 / cell_size.0;
+            let y0 = region.y as i32 / cell_size.1;
+            let x1 = (region.x + region.width) as i32 / cell_size.0;
+            let y1 = (region.y + region.height) as i32 / cell_size.1;
+            for y in y0..y1.max(y0 + 1) {
+                for x in x0..x1.max(x0 + 1) {
+                    cells.insert((x, y));
+                }
+            }
+        }
+    }
+    cells
+}
+
+fn cell_is_empty(tilemap: &Tilemap, cell: (i32, i32), cell_size: (i32, i32)) -> bool {
+    let dim = tilemap.buffer.dim();
+    let pixels = tilemap.buffer.pixels();
+    let stride = dim.width as usize * 4;
+    for y in 0..cell_size.1 {
+        for x in 0..cell_size.0 {
+            let px = (cell.0 * cell_size.0 + x) as usize;
+            let py = (cell.1 * cell_size.1 + y) as usize;
+            if pixels.get(py * stride + px * 4 + 3).copied().unwrap_or(0) != 0 {
+                return false;
+            }
+        }
+    }
+    true
+}
+
+fn tileset_entry(tilemap: &Tilemap) -> serde_json::Value {
+    let (width, height) = tileset_size(tilemap);
+    let cell_size = (tilemap.grid_size, tilemap.grid_size);
+    let (columns, rows) = grid_dims(tilemap, cell_size);
+    json!({
+        "id": tilemap.id.to_string(),
+        "name": tilemap.name,
+        "width": width,
+        "height": height,
+        "grid_size": tilemap.grid_size,
+        "columns": columns,
+        "rows": rows,
+        "tile_count": tilemap.tiles.len(),
+    })
+}
+
+pub fn tileset_list(project: &Project) -> serde_json::Value {
+    json!({
+        "ok": true,
+        "tilesets": project.tilemaps.iter().map(tileset_entry).collect::<Vec<_>>(),
+    })
+}
+
+pub fn tileset_inspect(project: &Project, command: &TilesetInspect) -> serde_json::Value {
+    let indices = match &command.tileset {
+        Some(tileset) => match find_tileset_index(project, tileset) {
+            Ok(index) => vec![index],
+            Err(error) => return json!({ "ok": false, "error": error }),
+        },
+        None => (0..project.tilemaps.len()).collect(),
+    };
+
+    let tilesets = indices
+        .into_iter()
+        .map(|index| {
+            let tilemap = &project.tilemaps[index];
+            let cell_size = (tilemap.grid_size, tilemap.grid_size);
+            let (columns, rows) = grid_dims(tilemap, cell_size);
+            let imported = imported_cells(tilemap, cell_size);
+            let tiles = tilemap
+                .tiles
+                .iter()
+                .map(|tile| {
+                    let cells = tile
+                        .sequence
+                        .regions
+                        .iter()
+                        .map(|region| {
+                            json!([
+                                region.x as i32 / cell_size.0.max(1),
+                                region.y as i32 / cell_size.1.max(1)
+                            ])
+                        })
+                        .collect::<Vec<_>>();
+                    json!({
+                        "id": tile.id.to_string(),
+                        "name": tile.name,
+                        "role": tile.role.to_string(),
+                        "blocking": tile.blocking,
+                        "frame_count": tile.sequence.regions.len(),
+                        "cells": cells,
+                        "project_tile": tile_summary(project, &tile.id),
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            let mut entry = tileset_entry(tilemap);
+            if let Some(object) = entry.as_object_mut() {
+                object.insert(
+                    "coverage".to_string(),
+                    json!({
+                        "cells": columns * rows,
+                        "imported_cells": imported.len(),
+                    }),
+                );
+                object.insert("tiles".to_string(), json!(tiles));
+            }
+            entry
+        })
+        .collect::<Vec<_>>();
+
+    json!({
+        "ok": true,
+        "tilesets": tilesets,
+    })
+}
+
+/// Score a candidate grid by how many of its cells hold pixels on every
+/// border row and column; sprite sheets with padding or gutters score low on
+/// grids that cut through tiles.
+fn grid_candidate(tilemap: &Tilemap, size: i32) -> Option<serde_json::Value> {
+    let (width, height) = tileset_size(tilemap);
+    if size > width || size > height || width % size != 0 || height % size != 0 {
+        return None;
+    }
+    let columns = width / size;
+    let rows = height / size;
+    let mut occupied = 0;
+    for y in 0..rows {
+        for x in 0..columns {
+            if !cell_is_empty(tilemap, (x, y), (size, size)) {
+                occupied += 1;
+            }
+        }
+    }
+    Some(json!({
+        "grid_size": size,
+        "columns": columns,
+        "rows": rows,
+        "occupied_cells": occupied,
+    }))
+}
+
+pub fn tileset_grid_detect(project: &Project, command: &TilesetGridDetect) -> serde_json::Value {
+    let index = match find_tileset_index(project, &command.tileset) {
+        Ok(index) => index,
+        Err(error) => return json!({ "ok": false, "error": error }),
+    };
+    let tilemap = &project.tilemaps[index];
+    let candidates = GRID_CANDIDATES
+        .iter()
+        .filter_map(|size| grid_candidate(tilemap, *size))
+        .collect::<Vec<_>>();
+
+    // Keep the configured grid when it fits, otherwise suggest the largest
+    // candidate that still tiles the image exactly.
+    let configured_fits = candidates
+        .iter()
+        .any(|candidate| candidate["grid_size"].as_i64() == Some(tilemap.grid_size as i64));
+    let suggested = if configured_fits {
+        Some(tilemap.grid_size as i64)
+    } else {
+        candidates
+            .iter()
+            .filter_map(|candidate| candidate["grid_size"].as_i64())
+            .filter(|size| *size <= 32)
+            .max()
+    };
+
+    json!({
+        "ok": true,
+        "tileset": tileset_entry(tilemap),
+        "configured_grid_size": tilemap.grid_size,
+        "suggested_grid_size": suggested,
+        "candidates": candidates,
+    })
+}
+
+pub fn tileset_list_unimported(
+    project: &Project,
+    command: &TilesetListUnimported,
+) -> serde_json::Value {
+    let index = match find_tileset_index(project, &command.tileset) {
+        Ok(index) => index,
+        Err(error) => return json!({ "ok": false, "error": error }),
+    };
+    let tilemap = &project.tilemaps[index];
+    let cell_size = (tilemap.grid_size, tilemap.grid_size);
+    let (columns, rows) = grid_dims(tilemap, cell_size);
+    let imported = imported_cells(tilemap, cell_size);
+
+    let mut cells = Vec::new();
+    let mut empty_cells = 0;
+    for y in 0..rows {
+        for x in 0..columns {
+            if imported.contains(&(x, y)) {
+                continue;
+            }
+            if cell_is_empty(tilemap, (x, y), cell_size) {
+                empty_cells += 1;
+            } else {
+                cells.push(json!([x, y]));
+            }
+        }
+    }
+
+    json!({
+        "ok": true,
+        "tileset": tileset_entry(tilemap),
+        "unimported": cells,
+        "unimported_count": cells.len(),
+        "imported_count": imported.len(),
+        "empty_cells": empty_cells,
+    })
+}
+
+/// Import a sequence of cells as one tile. The tileset keeps the source
+/// regions; the project tile gets one texture per frame, like Creator's
+/// tilemap dock.
+fn import_cells(
+    project: &mut Project,
+    tileset_index: usize,
+    cells: &[GridPoint],
+    cell_size: (i32, i32),
+    meta: Option<&TilesetTileMeta>,
+) -> Result<Uuid, String> {
+    if cells.is_empty() {
+        return Err("tileset import requires at least one cell".to_string());
+    }
+    let role = meta
+        .and_then(|meta| meta.role.as_deref())
+        .map(parse_tile_role)
+        .transpose()?;
+
+    let tilemap = &mut project.tilemaps[tileset_index];
+    let (columns, rows) = grid_dims(tilemap, cell_size);
+    let mut sequence = TheRGBARegionSequence::new();
+    for [x, y] in cells {
+        if *x < 0 || *y < 0 || *x >= columns || *y >= rows {
+            return Err(format!(
+                "cell [{x}, {y}] is outside the {columns}x{rows} tileset grid"
+            ));
+        }
+        sequence.regions.push(TheRGBARegion::new(
+            (*x * cell_size.0) as usize,
+            (*y * cell_size.1) as usize,
+            cell_size.0 as usize,
+            cell_size.1 as usize,
+        ));
+    }
+
+    let mut tile = shared::prelude::Tile::new();
+    tile.role = role.unwrap_or(TileRole::ManMade);
+    tile.sequence = sequence;
+    if let Some(meta) = meta {
+        tile.name = meta.alias.clone().unwrap_or_default();
+        tile.blocking = meta.blocking;
+    }
+
+    let textures = tilemap
+        .buffer
+        .extract_sequence(&tile.sequence)
+        .iter()
+        .map(|buffer| {
+            let mut texture = rusterix::Texture::new(
+                buffer.pixels().to_vec(),
+                buffer.dim().width as usize,
+                buffer.dim().height as usize,
+            );
+            texture.generate_normals(true);
+            texture
+        })
+        .collect::<Vec<_>>();
+
+    let mut project_tile = rusterix::Tile::from_textures(textures);
+    project_tile.id = tile.id;
+    project_tile.role = tile.role;
+    project_tile.blocking = tile.blocking;
+    project_tile.scale = tile.scale;
+    project_tile.alias = tile.name.clone();
+    if let Some(meta) = meta {
+        if let Some(kind) = &meta.procedural_kind {
+            project_tile.procedural.kind = kind.clone();
+        }
+        if let Some(style) = &meta.procedural_style {
+            project_tile.procedural.style = style.clone();
+        }
+        if let Some(weight) = meta.procedural_weight {
+            project_tile.procedural.weight = weight;
+        }
+    }
+    project_tile.set_default_materials();
+
+    let tile_id = tile.id;
+    tilemap.tiles.push(tile);
+    project.tiles.insert(tile_id, project_tile);
+
+    Ok(tile_id)
+}
+
+/// Import every cell of a rect as its own tile and group them.
+fn import_multi(
+    project: &mut Project,
+    tileset_index: usize,
+    rect: GridRect,
+    name: &str,
+    tags: &[String],
+    cell_size: (i32, i32),
+) -> Result<(Uuid, Vec<Uuid>), String> {
+    let [x, y, width, height] = rect;
+    if width <= 0 || height <= 0 || width > u16::MAX as i32 || height > u16::MAX as i32 {
+        return Err("tileset.import_multi requires a positive rect size".to_string());
+    }
+
+    let mut group = TileGroup::new(width as u16, height as u16);
+    group.name = name.to_string();
+    group.tags = tags.join(", ");
+    let mut tile_ids = Vec::new();
+    for gy in 0..height {
+        for gx in 0..width {
+            let meta = TilesetTileMeta {
+                alias: Some(format!("{name}_{gx}_{gy}")),
+                role: None,
+                procedural_kind: None,
+                procedural_style: None,
+                procedural_weight: None,
+                blocking: false,
+                tags: Vec::new(),
+            };
+            let tile_id = import_cells(
+                project,
+                tileset_index,
+                &[[x + gx, y + gy]],
+                cell_size,
+                Some(&meta),
+            )?;
+            group.members.push(TileGroupMemberRef {
+                tile_id,
+                x: gx as u16,
+                y: gy as u16,
+            });
+            tile_ids.push(tile_id);
+        }
+    }
+
+    let group_id = group.id;
+    project.add_tile_group(group);
+    Ok((group_id, tile_ids))
+}
+
+fn configured_cell_size(project: &Project, tileset_index: usize) -> (i32, i32) {
+    let grid_size = project.tilemaps[tileset_index].grid_size;
+    (grid_size, grid_size)
+}
+
+fn import_result(
+    project: &Project,
+    command_name: &str,
+    result: Result<Uuid, String>,
+) -> serde_json::Value {
+    match result {
+        Ok(tile_id) => json!({
+            "ok": true,
+            "command": command_name,
+            "tile_id": tile_id.to_string(),
+            "tile": tile_summary(project, &tile_id),
+        }),
+        Err(error) => json!({ "ok": false, "error": error }),
+    }
+}
+
+pub fn tileset_import_tile(project: &mut Project, command: TilesetImportTile) -> serde_json::Value {
+    let index = match find_tileset_index(project, &command.tileset) {
+        Ok(index) => index,
+        Err(error) => return json!({ "ok": false, "error": error }),
+    };
+    let cell_size = configured_cell_size(project, index);
+    let result = import_cells(
+        project,
+        index,
+        &[command.cell],
+        cell_size,
+        command.meta.as_ref(),
+    );
+    import_result(project, "tileset.import_tile", result)
+}
+
+pub fn tileset_import_anim(project: &mut Project, command: TilesetImportAnim) -> serde_json::Value {
+    let index = match find_tileset_index(project, &command.tileset) {
+        Ok(index) => index,
+        Err(error) => return json!({ "ok": false, "error": error }),
+    };
+    let cell_size = configured_cell_size(project, index);
+    let result = import_cells(
+        project,
+        index,
+        &command.cells,
+        cell_size,
+        command.meta.as_ref(),
+    );
+    import_result(project, "tileset.import_anim", result)
+}
+
+pub fn tileset_import_multi(
+    project: &mut Project,
+    command: TilesetImportMulti,
+) -> serde_json::Value {
+    let index = match find_tileset_index(project, &command.tileset) {
+        Ok(index) => index,
+        Err(error) => return json!({ "ok": false, "error": error }),
+    };
+    let cell_size = configured_cell_size(project, index);
+    match import_multi(
+        project,
+        index,
+        command.rect,
+        &command.name,
+        &command.tags,
+        cell_size,
+    ) {
+        Ok((group_id, tile_ids)) => json!({
+            "ok": true,
+            "command": "tileset.import_multi",
+            "group_id": group_id.to_string(),
+            "tile_ids": tile_ids.iter().map(|id| id.to_string()).collect::<Vec<_>>(),
+        }),
+        Err(error) => json!({ "ok": false, "error": error }),
+    }
+}
+
+/// Run every import of a batch in order. The batch stops at the first failing
+/// import; the session rolls the whole command back in that case.
+pub fn tileset_import_batch(
+    project: &mut Project,
+    command: TilesetImportBatch,
+) -> serde_json::Value {
+    let index = match find_tileset_index(project, &command.tileset) {
+        Ok(index) => index,
+        Err(error) => return json!({ "ok": false, "error": error }),
+    };
+    let cell_size = match command.grid_size {
+        Some([width, height]) if width > 0 && height > 0 => (width, height),
+        Some(_) => {
+            return json!({
+                "ok": false,
+                "error": "tileset.import_batch grid_size must be positive",
+            });
+        }
+        None => configured_cell_size(project, index),
+    };
+
+    let mut imported = Vec::new();
+    for (import_index, spec) in command.imports.into_iter().enumerate() {
+        let entry = match spec {
+            TilesetImportSpec::Tile { cell, meta } => {
+                import_cells(project, index, &[cell], cell_size, meta.as_ref())
+                    .map(|tile_id| json!({ "kind": "tile", "tile_id": tile_id.to_string() }))
+            }
+            TilesetImportSpec::Anim { cells, meta } => {
+                import_cells(project, index, &cells, cell_size, meta.as_ref())
+                    .map(|tile_id| json!({ "kind": "anim", "tile_id": tile_id.to_string() }))
+            }
+            TilesetImportSpec::Multi { rect, name, tags } => import_multi(
+                project, index, rect, &name, &tags, cell_size,
+            )
+            .map(|(group_id, tile_ids)| {
+                json!({
+                    "kind": "multi",
+                    "group_id": group_id.to_string(),
+                    "tile_ids": tile_ids.iter().map(|id| id.to_string()).collect::<Vec<_>>(),
+                })
+            }),
+        };
+        match entry {
+            Ok(entry) => imported.push(entry),
+            Err(error) => {
+                return json!({
+                    "ok": false,
+                    "error": format!("import {import_index} failed: {error}"),
+                    "imported": imported,
+                });
+            }
+        }
+    }
+
+    json!({
+        "ok": true,
+        "command": "tileset.import_batch",
+        "grid_size": [cell_size.0, cell_size.1],
+        "imported": imported,
+    })
+}
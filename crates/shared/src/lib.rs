@@ -9,10 +9,11 @@ pub mod iso_paint_render;
 pub mod item;
 pub mod project;
 pub mod region;
+pub mod roguelike_fov;
 pub mod rulesets;
-#[cfg(feature = "graphics")]
 pub mod rusterix_utils;
 pub mod screen;
+pub mod simulation;
 pub mod terminal_screen;
 pub mod text_game;
 pub mod text_session;
@@ -37,6 +38,7 @@ pub mod prelude {
         BuilderGraphAsset, ProceduralRecipeAsset, Project, TileCollectionAsset, TileCollectionEntry,
     };
     pub use crate::region::Region;
+    pub use crate::roguelike_fov::*;
     pub use crate::rulesets::*;
     pub use crate::screen::*;
     pub use crate::terminal_screen::*;
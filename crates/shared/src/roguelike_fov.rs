@@ -0,0 +1,357 @@
+//! Field of view and explored-cell memory for the terminal roguelike view.
+//!
+//! Visibility is computed with recursive shadow casting over the source
+//! terrain, using the same blocking rules as player movement
+//! ([`is_roguelike_blocked`]). Lit cells stay visible beyond the sight radius
+//! as long as the player has a line of sight to them.
+
+use crate::project::Project;
+use crate::terminal_screen::{is_roguelike_blocked, source_terrain, world_to_cell};
+use rusterix::{Map, Value, ValueContainer};
+use std::collections::BTreeMap;
+use theframework::prelude::Uuid;
+use toml::Table;
+
+/// `[terminal]` settings controlling the roguelike field of view.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RoguelikeFovConfig {
+    /// `fov`: hide everything the player cannot see. Off by default.
+    pub enabled: bool,
+    /// `fov_radius`: sight radius in cells without light, `0` for unlimited.
+    pub radius: i32,
+    /// `fov_memory`: keep drawing explored terrain when out of sight.
+    pub memory: bool,
+}
+
+impl Default for RoguelikeFovConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            radius: 8,
+            memory: true,
+        }
+    }
+}
+
+impl RoguelikeFovConfig {
+    pub fn from_config(config: &str) -> Self {
+        let mut fov = Self::default();
+        let Some(terminal) = config
+            .parse::<Table>()
+            .ok()
+            .and_then(|table| table.get("terminal")?.as_table().cloned())
+        else {
+            return fov;
+        };
+        if let Some(enabled) = terminal.get("fov").and_then(toml::Value::as_bool) {
+            fov.enabled = enabled;
+        }
+        if let Some(radius) = terminal.get("fov_radius").and_then(toml::Value::as_integer) {
+            fov.radius = radius.max(0) as i32;
+        }
+        if let Some(memory) = terminal.get("fov_memory").and_then(toml::Value::as_bool) {
+            fov.memory = memory;
+        }
+        fov
+    }
+}
+
+/// How a map cell is drawn under the field of view.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RoguelikeCell {
+    Visible,
+    /// Explored but out of sight: terrain only, dimmed.
+    Remembered,
+    Hidden,
+}
+
+/// Visibility of the current region's cells, indexed `[y][x]` like the
+/// source terrain.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RoguelikeFov {
+    /// Cells the player currently sees.
+    pub visible: Vec<Vec<bool>>,
+    /// Cells seen before. Out of sight they show terrain only.
+    pub explored: Vec<Vec<bool>>,
+}
+
+impl RoguelikeFov {
+    pub fn is_visible(&self, x: i32, y: i32) -> bool {
+        cell(&self.visible, x, y)
+    }
+
+    pub fn is_explored(&self, x: i32, y: i32) -> bool {
+        cell(&self.explored, x, y)
+    }
+
+    pub fn cell(&self, x: i32, y: i32) -> RoguelikeCell {
+        if self.is_visible(x, y) {
+            RoguelikeCell::Visible
+        } else if self.is_explored(x, y) {
+            RoguelikeCell::Remembered
+        } else {
+            RoguelikeCell::Hidden
+        }
+    }
+}
+
+/// Explored cells per region, keyed by region name.
+#[derive(Clone, Debug, Default)]
+pub struct RoguelikeFovMemory {
+    regions: BTreeMap<String, RoguelikeFov>,
+}
+
+impl RoguelikeFovMemory {
+    /// Record what the player sees now in `region`. Without memory only the
+    /// visible cells count as explored.
+    pub fn update(&mut self, region: &str, visible: Vec<Vec<bool>>, memory: bool) {
+        let fov = self.regions.entry(region.to_string()).or_default();
+        if !memory || fov.explored.len() != visible.len() {
+            fov.explored = visible.iter().map(|row| vec![false; row.len()]).collect();
+        }
+        for (explored, visible) in fov.explored.iter_mut().zip(&visible) {
+            for (explored, visible) in explored.iter_mut().zip(visible) {
+                *explored |= *visible;
+            }
+        }
+        fov.visible = visible;
+    }
+
+    pub fn get(&self, region: &str) -> Option<&RoguelikeFov> {
+        self.regions.get(region)
+    }
+
+    pub fn clear(&mut self) {
+        self.regions.clear();
+    }
+}
+
+/// Cells a viewer at `origin` can see within `radius` cells, `0` meaning
+/// unlimited. Blocking cells are visible but stop the view behind them.
+pub fn shadowcast(terrain: &[Vec<char>], origin: (i32, i32), radius: i32) -> Vec<Vec<bool>> {
+    let mut visible = terrain
+        .iter()
+        .map(|row| vec![false; row.len()])
+        .collect::<Vec<_>>();
+    let radius = if radius > 0 {
+        radius
+    } else {
+        let width = terrain.iter().map(Vec::len).max().unwrap_or(0);
+        (width + terrain.len()) as i32
+    };
+    mark(&mut visible, origin.0, origin.1);
+    for octant in OCTANTS {
+        cast_light(terrain, &mut visible, origin, radius, 1, 1.0, 0.0, octant);
+    }
+    visible
+}
+
+/// Compute what the player at `player` sees in `map` under `config`.
+pub fn roguelike_visibility(
+    project: &Project,
+    map: &Map,
+    player: (i32, i32),
+    config: &RoguelikeFovConfig,
+) -> Option<Vec<Vec<bool>>> {
+    let terrain = source_terrain(map)?;
+    let mut visible = shadowcast(&terrain, player, config.radius);
+    if config.radius <= 0 {
+        return Some(visible);
+    }
+
+    let lights = roguelike_light_sources(project, map);
+    if lights.is_empty() {
+        return Some(visible);
+    }
+    let line_of_sight = shadowcast(&terrain, player, 0);
+    for (x, y, radius) in lights {
+        let lit = shadowcast(&terrain, (x, y), radius.max(1));
+        for (row, (lit, sight)) in visible.iter_mut().zip(lit.iter().zip(&line_of_sight)) {
+            for (cell, (lit, sight)) in row.iter_mut().zip(lit.iter().zip(sight)) {
+                *cell |= *lit && *sight;
+            }
+        }
+    }
+    Some(visible)
+}
+
+/// Active lights in the map as `(x, y, radius)` cells: map lights, lights
+/// carried by entities and items, and terrain tiles with a light emitter.
+pub fn roguelike_light_sources(project: &Project, map: &Map) -> Vec<(i32, i32, i32)> {
+    let mut lights = Vec::new();
+    let mut push = |x: f32, z: f32, range: f32| {
+        let (x, y) = world_to_cell(x, z);
+        lights.push((x, y, range.ceil() as i32));
+    };
+
+    for light in map.lights.iter().filter(|light| light.active) {
+        let position = light.position_2d();
+        push(position.x, position.y, light.get_end_distance());
+    }
+    for item in &map.items {
+        if let Some(Value::Light(light)) = item.attributes.get("light")
+            && light.active
+        {
+            push(item.position.x, item.position.z, light.get_end_distance());
+        }
+    }
+    for entity in &map.entities {
+        let carried = std::iter::once(entity.attributes.get("light")).chain(
+            entity
+                .iter_inventory()
+                .map(|(_, item)| item.attributes.get("light")),
+        );
+        for light in carried {
+            if let Some(Value::Light(light)) = light
+                && light.active
+            {
+                push(
+                    entity.position.x,
+                    entity.position.z,
+                    light.get_end_distance(),
+                );
+            }
+        }
+    }
+
+    let metadata = map
+        .sectors
+        .iter()
+        .map(|sector| &sector.properties)
+        .find(|properties| properties.contains("eldiron_source_terrain"));
+    if let Some(properties) = metadata
+        && let Some(terrain) = properties.get_str("eldiron_source_terrain")
+    {
+        let tiles = source_tile_ids(properties);
+        for (y, line) in terrain.lines().enumerate() {
+            for (x, glyph) in line.chars().enumerate() {
+                let Some(emitter) = tiles
+                    .get(&glyph)
+                    .and_then(|id| project.tiles.get(id))
+                    .and_then(|tile| tile.light_emitter.as_ref())
+                else {
+                    continue;
+                };
+                lights.push((x as i32, y as i32, emitter.range.ceil() as i32));
+            }
+        }
+    }
+    lights
+}
+
+/// The single-glyph terrain keys the source compiler bound to tiles.
+fn source_tile_ids(properties: &ValueContainer) -> BTreeMap<char, Uuid> {
+    let metadata = properties
+        .get_str("eldiron_source_tiles")
+        .and_then(|metadata| metadata.parse::<Table>().ok())
+        .unwrap_or_default();
+    metadata
+        .iter()
+        .filter_map(|(key, value)| {
+            let mut chars = key.chars();
+            let glyph = chars.next()?;
+            if chars.next().is_some() {
+                return None;
+            }
+            Some((glyph, Uuid::parse_str(value.as_str()?).ok()?))
+        })
+        .collect()
+}
+
+/// Octant transforms `(xx, xy, yx, yy)` mapping octant-local offsets to the
+/// grid.
+const OCTANTS: [(i32, i32, i32, i32); 8] = [
+    (1, 0, 0, 1),
+    (0, 1, 1, 0),
+    (0, -1, 1, 0),
+    (-1, 0, 0, 1),
+    (-1, 0, 0, -1),
+    (0, -1, -1, 0),
+    (0, 1, -1, 0),
+    (1, 0, 0, -1),
+];
+
+#[allow(clippy::too_many_arguments)]
+fn cast_light(
+    terrain: &[Vec<char>],
+    visible: &mut [Vec<bool>],
+    origin: (i32, i32),
+    radius: i32,
+    row: i32,
+    mut start: f32,
+    end: f32,
+    (xx, xy, yx, yy): (i32, i32, i32, i32),
+) {
+    if start < end {
+        return;
+    }
+    let mut next_start = start;
+    for distance in row..=radius {
+        let dy = -distance;
+        let mut blocked = false;
+        for dx in -distance..=0 {
+            let left_slope = (dx as f32 - 0.5) / (dy as f32 + 0.5);
+            let right_slope = (dx as f32 + 0.5) / (dy as f32 - 0.5);
+            if start < right_slope {
+                continue;
+            }
+            if end > left_slope {
+                break;
+            }
+
+            let x = origin.0 + dx * xx + dy * xy;
+            let y = origin.1 + dx * yx + dy * yy;
+            if dx * dx + dy * dy <= radius * radius {
+                mark(visible, x, y);
+            }
+
+            let opaque = is_roguelike_blocked(terrain, x, y);
+            if blocked {
+                if opaque {
+                    next_start = right_slope;
+                } else {
+                    blocked = false;
+                    start = next_start;
+                }
+            } else if opaque && distance < radius {
+                blocked = true;
+                cast_light(
+                    terrain,
+                    visible,
+                    origin,
+                    radius,
+                    distance + 1,
+                    start,
+                    left_slope,
+                    (xx, xy, yx, yy),
+                );
+                next_start = right_slope;
+            }
+        }
+        if blocked {
+            break;
+        }
+    }
+}
+
+fn mark(grid: &mut [Vec<bool>], x: i32, y: i32) {
+    if x < 0 || y < 0 {
+        return;
+    }
+    if let Some(cell) = grid
+        .get_mut(y as usize)
+        .and_then(|row| row.get_mut(x as usize))
+    {
+        *cell = true;
+    }
+}
+
+fn cell(grid: &[Vec<bool>], x: i32, y: i32) -> bool {
+    x >= 0
+        && y >= 0
+        && grid
+            .get(y as usize)
+            .and_then(|row| row.get(x as usize))
+            .copied()
+            .unwrap_or(false)
+}
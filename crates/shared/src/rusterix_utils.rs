@@ -1,5 +1,8 @@
 use crate::prelude::*;
-use rusterix::{Command, Entity, Rusterix, Value};
+use rusterix::server::assets::Assets;
+#[cfg(feature = "graphics")]
+use rusterix::{Command, Rusterix};
+use rusterix::{Entity, Server, Value};
 
 fn insert_bundled_ruleset_avatars(
     assets: &mut rusterix::server::assets::Assets,
@@ -30,90 +33,97 @@ fn insert_bundled_ruleset_textures(
 }
 
 /// Start the server
+#[cfg(feature = "graphics")]
 pub fn start_server(rusterix: &mut Rusterix, project: &mut Project, debug: bool) {
-    rusterix.server.clear();
-    rusterix.server.debug_mode = debug;
-    rusterix.server.log_changed = true;
+    start_server_regions(&mut rusterix.server, &mut rusterix.assets, project, debug);
+    // Force dynamic overlays (lights/billboards/avatars) to rebuild after restarts.
+    rusterix.scene_handler.mark_dynamics_dirty();
+}
+
+/// Load the game assets and create the region instances, without any
+/// client. Used by `start_server` and by headless runs.
+pub fn start_server_regions(
+    server: &mut Server,
+    assets: &mut Assets,
+    project: &mut Project,
+    debug: bool,
+) {
+    server.clear();
+    server.debug_mode = debug;
+    server.log_changed = true;
 
     insert_content_into_maps_mode(project, debug);
-    rusterix.assets.rules = crate::rulesets::resolve_project_rules(&project.config, &project.rules)
+    assets.rules = crate::rulesets::resolve_project_rules(&project.config, &project.rules)
         .unwrap_or_else(|err| {
             eprintln!("Ruleset resolution error: {}", err);
             project.rules.clone()
         });
-    rusterix.assets.read_rules_metadata();
-    rusterix.assets.locales_src =
+    assets.read_rules_metadata();
+    assets.locales_src =
         crate::rulesets::resolve_project_locales(&project.config, &project.locales).unwrap_or_else(
             |err| {
                 eprintln!("Ruleset locale resolution error: {}", err);
                 project.locales.clone()
             },
         );
-    rusterix.assets.audio_fx_src = project.audio_fx.clone();
-    rusterix.assets.authoring_src = project.authoring.clone();
+    assets.audio_fx_src = project.audio_fx.clone();
+    assets.authoring_src = project.authoring.clone();
     if debug && !project.world_source_debug.is_empty() {
-        rusterix.assets.world_source = project.world_source_debug.clone();
+        assets.world_source = project.world_source_debug.clone();
     } else {
-        rusterix.assets.world_source = project.world_source.clone();
+        assets.world_source = project.world_source.clone();
     }
-    rusterix.assets.region_sources.clear();
-    rusterix.assets.read_locales();
+    assets.region_sources.clear();
+    assets.read_locales();
 
     // Characters
-    rusterix.assets.entities.clear();
-    rusterix.assets.entity_authoring.clear();
-    rusterix.assets.character_maps.clear();
-    rusterix.assets.entity_tiles.clear();
+    assets.entities.clear();
+    assets.entity_authoring.clear();
+    assets.character_maps.clear();
+    assets.entity_tiles.clear();
     for character in project.characters.values_mut() {
         if debug && !character.source_debug.is_empty() {
-            rusterix.assets.entities.insert(
+            assets.entities.insert(
                 character.name.clone(),
                 (character.source_debug.clone(), character.data.clone()),
             );
         } else {
-            rusterix.assets.entities.insert(
+            assets.entities.insert(
                 character.name.clone(),
                 (character.source.clone(), character.data.clone()),
             );
         }
-        rusterix
-            .assets
+        assets
             .entity_authoring
             .insert(character.name.clone(), character.authoring.clone());
         if !character.map.vertices.is_empty() {
-            rusterix
-                .assets
+            assets
                 .character_maps
                 .insert(character.name.clone(), character.map.clone());
         }
     }
 
     // Items
-    rusterix.assets.items.clear();
-    rusterix.assets.item_authoring.clear();
-    rusterix.assets.item_maps.clear();
-    rusterix.assets.item_tiles.clear();
+    assets.items.clear();
+    assets.item_authoring.clear();
+    assets.item_maps.clear();
+    assets.item_tiles.clear();
     for item in project.items.values_mut() {
         if debug && !item.source_debug.is_empty() {
-            rusterix.assets.items.insert(
+            assets.items.insert(
                 item.name.clone(),
                 (item.source_debug.clone(), item.data.clone()),
             );
         } else {
-            rusterix
-                .assets
+            assets
                 .items
                 .insert(item.name.clone(), (item.source.clone(), item.data.clone()));
         }
-        rusterix
-            .assets
+        assets
             .item_authoring
             .insert(item.name.clone(), item.authoring.clone());
         if !item.map.vertices.is_empty() {
-            rusterix
-                .assets
-                .item_maps
-                .insert(item.name.clone(), item.map.clone());
+            assets.item_maps.insert(item.name.clone(), item.map.clone());
         }
     }
 
@@ -124,44 +134,37 @@ pub fn start_server(rusterix: &mut Rusterix, project: &mut Project, debug: bool)
         } else {
             region.source.clone()
         };
-        rusterix
-            .assets
-            .region_sources
-            .insert(region.map.id, region_source);
+        assets.region_sources.insert(region.map.id, region_source);
         let region_config = crate::project::merge_config_toml(&project.config, &region.config);
-        rusterix.server.create_region_instance(
+        server.create_region_instance(
             region.name.clone(),
             region.map.clone(),
-            &rusterix.assets,
+            assets,
             region_config,
         );
     }
 
     // Create the avatars
-    rusterix.assets.avatars.clear();
-    insert_bundled_ruleset_avatars(&mut rusterix.assets, project);
+    assets.avatars.clear();
+    insert_bundled_ruleset_avatars(assets, project);
     for avatar in &mut project.avatars.values() {
-        rusterix
-            .assets
-            .avatars
-            .insert(avatar.name.clone(), avatar.clone());
+        assets.avatars.insert(avatar.name.clone(), avatar.clone());
     }
-    insert_bundled_ruleset_textures(&mut rusterix.assets, project);
+    insert_bundled_ruleset_textures(assets, project);
 
     // Wait for the region to be created
     #[cfg(not(target_arch = "wasm32"))]
     std::thread::sleep(std::time::Duration::from_millis(10));
     // Set the time for each region to the project time
     for region in &mut project.regions {
-        rusterix.server.set_time(&region.map.id, project.time);
+        server.set_time(&region.map.id, project.time);
     }
 
-    rusterix.server.set_state(rusterix::ServerState::Running);
-    // Force dynamic overlays (lights/billboards/avatars) to rebuild after restarts.
-    rusterix.scene_handler.mark_dynamics_dirty();
+    server.set_state(rusterix::ServerState::Running);
 }
 
 /// Let freshly queued startup work settle after client commands create the local player.
+#[cfg(feature = "graphics")]
 pub fn warmup_runtime(rusterix: &mut Rusterix, project: &mut Project, ticks: usize) {
     for _ in 0..ticks {
         rusterix.server.system_tick();
@@ -181,7 +184,77 @@ pub fn warmup_runtime(rusterix: &mut Rusterix, project: &mut Project, ticks: usi
     }
 }
 
+/// Handle save / load requests raised by game scripts (`save_game` / `load_game`).
+/// Save slots are stored next to the game file and failures go to the server
+/// log. Returns the region of the local player when a save game was loaded.
+#[cfg(feature = "graphics")]
+pub fn process_save_game_requests(
+    rusterix: &mut Rusterix,
+    game_path: &std::path::Path,
+) -> Option<String> {
+    use rusterix::server::SaveGameRequest;
+
+    let mut player_region = None;
+    for request in rusterix.server.get_save_game_requests() {
+        match request {
+            SaveGameRequest::Save(slot) => {
+                if let Err(err) = save_game_slot(rusterix, game_path, &slot) {
+                    rusterix.server.log_message(err);
+                }
+            }
+            SaveGameRequest::Load(slot) => match load_game_slot(rusterix, game_path, &slot) {
+                Ok(region) => player_region = region.or(player_region),
+                Err(err) => rusterix.server.log_message(err),
+            },
+        }
+    }
+    player_region
+}
+
+/// Save the running game into the given slot.
+#[cfg(feature = "graphics")]
+pub fn save_game_slot(
+    rusterix: &mut Rusterix,
+    game_path: &std::path::Path,
+    slot: &str,
+) -> Result<(), String> {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let path = rusterix::server::savegame::save_slot_path(game_path, slot);
+        rusterix.server.save_state().write(&path)
+    }
+    #[cfg(target_arch = "wasm32")]
+    {
+        let _ = (rusterix, game_path, slot);
+        Err("Saving games is not supported on the web.".into())
+    }
+}
+
+/// Restore the running game from the given slot.
+#[cfg(feature = "graphics")]
+pub fn load_game_slot(
+    rusterix: &mut Rusterix,
+    game_path: &std::path::Path,
+    slot: &str,
+) -> Result<Option<String>, String> {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let path = rusterix::server::savegame::save_slot_path(game_path, slot);
+        let save = rusterix::server::savegame::SaveGame::read(&path)?;
+        let player_region = rusterix.server.load_state(&save)?;
+        rusterix.clear_say_messages();
+        rusterix.scene_handler.mark_dynamics_dirty();
+        Ok(player_region)
+    }
+    #[cfg(target_arch = "wasm32")]
+    {
+        let _ = (rusterix, game_path, slot);
+        Err("Loading games is not supported on the web.".into())
+    }
+}
+
 /// Setup the client
+#[cfg(feature = "graphics")]
 pub fn setup_client(rusterix: &mut Rusterix, project: &mut Project) -> Vec<Command> {
     rusterix.assets.config = project.config.clone();
     rusterix.assets.world_source = project.world_source.clone();
@@ -273,6 +346,57 @@ pub fn setup_client(rusterix: &mut Rusterix, project: &mut Project) -> Vec<Comma
     rusterix.setup_client()
 }
 
+/// The player entity placed in the `[game].start_region` of the project, used as
+/// the template for spawned players. Call after `insert_content_into_maps`.
+pub fn start_region_player(
+    project: &Project,
+) -> Result<(theframework::prelude::Uuid, Entity), String> {
+    let config = project.config.parse::<toml::Table>().unwrap_or_default();
+    let start_region = config
+        .get("game")
+        .and_then(toml::Value::as_table)
+        .and_then(|game| game.get("start_region"))
+        .and_then(toml::Value::as_str)
+        .ok_or("Game config is missing [game].start_region")?;
+    let region = project
+        .regions
+        .iter()
+        .find(|region| region.map.name == start_region)
+        .ok_or_else(|| format!("Start region '{}' not found", start_region))?;
+
+    let player_classes: Vec<&String> = project
+        .characters
+        .values()
+        .filter(|character| {
+            character
+                .data
+                .parse::<toml::Table>()
+                .ok()
+                .and_then(|table| {
+                    table
+                        .get("attributes")
+                        .and_then(toml::Value::as_table)
+                        .and_then(|attributes| attributes.get("player"))
+                        .and_then(toml::Value::as_bool)
+                })
+                .unwrap_or(false)
+        })
+        .map(|character| &character.name)
+        .collect();
+
+    region
+        .map
+        .entities
+        .iter()
+        .find(|entity| {
+            entity
+                .get_attr_string("class_name")
+                .is_some_and(|class_name| player_classes.contains(&&class_name))
+        })
+        .map(|entity| (region.map.id, entity.clone()))
+        .ok_or_else(|| format!("No player entity found in start region '{}'", start_region))
+}
+
 /// Convert the characters and items into Entities / Items for the rusterix server
 pub fn insert_content_into_maps(project: &mut Project) {
     insert_content_into_maps_mode(project, false);
@@ -0,0 +1,375 @@
+use crate::prelude::*;
+use crate::rusterix_utils::{insert_content_into_maps, start_region_player, start_server_regions};
+use rusterix::server::assets::Assets;
+use rusterix::server::region::reset_global_id_gen;
+use rusterix::{Command, EntityAction, Server};
+use std::path::Path;
+use std::time::Duration;
+use theframework::prelude::Uuid;
+use toml::Table;
+
+/// One scripted input, applied at the start of the given tick. Exactly one of
+/// `action` and `text` is set. Inputs go to the local player unless `entity`
+/// names another player entity.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SimulationInput {
+    pub tick: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub entity: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub action: Option<EntityAction>,
+    /// A text command like `look` or `go north`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+}
+
+impl SimulationInput {
+    pub fn text(tick: u64, text: impl Into<String>) -> Self {
+        Self {
+            tick,
+            entity: None,
+            action: None,
+            text: Some(text.into()),
+        }
+    }
+
+    pub fn action(tick: u64, action: EntityAction) -> Self {
+        Self {
+            tick,
+            entity: None,
+            action: Some(action),
+            text: None,
+        }
+    }
+
+    fn entity_action(&self) -> Result<EntityAction, String> {
+        match (&self.action, &self.text) {
+            (Some(action), None) => Ok(action.clone()),
+            (None, Some(text)) => Ok(EntityAction::TextCommand(text.clone())),
+            _ => Err(format!(
+                "Input at tick {} needs exactly one of 'action' or 'text'.",
+                self.tick
+            )),
+        }
+    }
+}
+
+/// A simulation script and, once recorded, an input log: the seed, the number
+/// of ticks to run and the inputs. A recorded log also carries the digest of
+/// the final state, which a replay has to reproduce.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct SimulationScript {
+    #[serde(default)]
+    pub seed: u64,
+    #[serde(default)]
+    pub ticks: u64,
+    #[serde(default, rename = "input", skip_serializing_if = "Vec::is_empty")]
+    pub inputs: Vec<SimulationInput>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub digest: Option<String>,
+}
+
+impl SimulationScript {
+    /// Parse a script from TOML or, if it starts with `{`, from JSON.
+    pub fn parse(src: &str) -> Result<Self, String> {
+        let mut script: Self = if src.trim_start().starts_with('{') {
+            serde_json::from_str(src).map_err(|err| err.to_string())?
+        } else {
+            toml::from_str(src).map_err(|err| err.to_string())?
+        };
+        for input in &script.inputs {
+            input.entity_action()?;
+        }
+        script.inputs.sort_by_key(|input| input.tick);
+        let last_input = script.inputs.last().map(|input| input.tick + 1);
+        script.ticks = script.ticks.max(last_input.unwrap_or(0));
+        Ok(script)
+    }
+
+    pub fn to_toml(&self) -> Result<String, String> {
+        toml::to_string(self).map_err(|err| err.to_string())
+    }
+}
+
+/// The outcome of a simulation run.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SimulationReport {
+    pub ticks: u64,
+    pub digest: String,
+    pub transcript: Vec<String>,
+}
+
+/// A headless game running deterministically: randomness is seeded, time
+/// advances a fixed amount per tick and the regions are ticked in order.
+/// The same game, seed and inputs always lead to the same state digest and
+/// transcript.
+///
+/// The server keeps its players and regions in process wide registries, so
+/// only one simulation (or game) should run at a time.
+pub struct Simulation {
+    server: Server,
+    assets: Assets,
+    regions: Vec<Uuid>,
+    redraws_per_tick: u32,
+    tick: u64,
+    seed: u64,
+    log: Vec<SimulationInput>,
+    transcript: Vec<String>,
+}
+
+impl Simulation {
+    /// Load a `.eldiron` game and start it with the given seed.
+    pub fn load(path: &Path, seed: u64) -> Result<Self, String> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|err| format!("Failed to read {}: {}", path.display(), err))?;
+        let mut project: Project = serde_json::from_str(&contents)
+            .map_err(|err| format!("Failed to parse {}: {}", path.display(), err))?;
+        project.migrate_default_ruleset();
+        Self::new(project, seed)
+    }
+
+    /// Start the game with the given seed. When the game auto creates its
+    /// player, the player is spawned in the start region.
+    pub fn new(mut project: Project, seed: u64) -> Result<Self, String> {
+        let config = project.config.parse::<Table>().unwrap_or_default();
+        let game = config.get("game").and_then(toml::Value::as_table);
+        let game_int = |key: &str, default: i64| {
+            game.and_then(|game| game.get(key))
+                .and_then(toml::Value::as_integer)
+                .unwrap_or(default)
+        };
+        let tick_ms = game_int("game_tick_ms", 250).max(1) as u64;
+        let fps = game_int("target_fps", 30).clamp(1, 240) as u64;
+        let redraws_per_tick = ((tick_ms * fps) as f64 / 1000.0).round().max(1.0) as u32;
+        let step = Duration::from_micros(tick_ms * 1000 / redraws_per_tick as u64);
+
+        // Entity and item ids come from a process wide generator.
+        reset_global_id_gen();
+        insert_content_into_maps(&mut project);
+        let auto_create = game
+            .and_then(|game| game.get("auto_create_player"))
+            .and_then(toml::Value::as_bool)
+            .unwrap_or(false);
+        let player = if auto_create {
+            Some(start_region_player(&project)?)
+        } else {
+            None
+        };
+
+        let mut server = Server::default();
+        let mut assets = Assets::default();
+        server.print_log_messages = false;
+        assets.config = project.config.clone();
+        for region in &project.regions {
+            assets
+                .maps
+                .insert(region.map.name.clone(), region.map.clone());
+        }
+        server.set_deterministic(seed, step);
+        start_server_regions(&mut server, &mut assets, &mut project, false);
+        if let Some((region_id, entity)) = player {
+            server.process_client_commands(vec![Command::CreateEntity(region_id, entity)]);
+        }
+
+        let mut simulation = Self {
+            server,
+            assets,
+            regions: project.regions.iter().map(|region| region.map.id).collect(),
+            redraws_per_tick,
+            tick: 0,
+            seed,
+            log: vec![],
+            transcript: vec![],
+        };
+        // Let the regions process the startup and the player creation.
+        simulation.advance();
+        simulation.tick = 0;
+        Ok(simulation)
+    }
+
+    /// The number of ticks run so far.
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    pub fn server(&mut self) -> &mut Server {
+        &mut self.server
+    }
+
+    /// Feed an input now. It is recorded for the current tick.
+    pub fn input(&mut self, mut input: SimulationInput) -> Result<(), String> {
+        let action = input.entity_action()?;
+        input.tick = self.tick;
+        match input.entity {
+            Some(entity_id) => self.server.player_action(entity_id, action),
+            None => self.server.local_player_action(action),
+        }
+        self.log.push(input);
+        Ok(())
+    }
+
+    /// Advance one game tick: a system tick and the redraw frames in between.
+    pub fn step(&mut self) {
+        self.advance();
+        self.tick += 1;
+    }
+
+    fn advance(&mut self) {
+        self.server.system_tick();
+        for _ in 0..self.redraws_per_tick {
+            self.server.redraw_tick();
+            self.server.update(&mut self.assets);
+        }
+        self.collect_transcript();
+    }
+
+    fn collect_transcript(&mut self) {
+        let tick = self.tick;
+        for region_id in &self.regions {
+            for (sender, _, text, category) in self.server.get_says(region_id) {
+                let sender = sender.map(|id| id.to_string()).unwrap_or("-".into());
+                self.transcript.push(format!(
+                    "{:05} say {} [{}] {}",
+                    tick, sender, category, text
+                ));
+            }
+            for (_, _, receiver, text, category) in self.server.get_messages(region_id) {
+                self.transcript.push(format!(
+                    "{:05} message {} [{}] {}",
+                    tick, receiver, category, text
+                ));
+            }
+        }
+    }
+
+    /// Run the script to its end and return the report. Inputs scheduled
+    /// before the current tick are applied right away.
+    pub fn run(&mut self, script: &SimulationScript) -> Result<SimulationReport, String> {
+        let mut inputs = script.inputs.iter().peekable();
+        while self.tick < script.ticks {
+            while let Some(input) = inputs.next_if(|input| input.tick <= self.tick) {
+                self.input(input.clone())?;
+            }
+            self.step();
+        }
+        Ok(self.report())
+    }
+
+    /// Run a recorded log and check that it reproduces its digest.
+    pub fn replay(&mut self, log: &SimulationScript) -> Result<SimulationReport, String> {
+        let report = self.run(log)?;
+        match &log.digest {
+            Some(digest) if *digest != report.digest => Err(format!(
+                "Replay diverged: expected digest {}, got {}.",
+                digest, report.digest
+            )),
+            _ => Ok(report),
+        }
+    }
+
+    pub fn report(&self) -> SimulationReport {
+        SimulationReport {
+            ticks: self.tick,
+            digest: self.digest(),
+            transcript: self.transcript.clone(),
+        }
+    }
+
+    /// The inputs fed so far as a log, with the digest of the current state.
+    pub fn input_log(&self) -> SimulationScript {
+        SimulationScript {
+            seed: self.seed,
+            ticks: self.tick,
+            inputs: self.log.clone(),
+            digest: Some(self.digest()),
+        }
+    }
+
+    /// The Say and Message output so far, one line per message.
+    pub fn transcript(&self) -> &[String] {
+        &self.transcript
+    }
+
+    /// A digest of the game state: time, entities, items, inventories and
+    /// script state of all regions, as captured by a save game.
+    pub fn digest(&self) -> String {
+        let save = self.server.save_state();
+        // Going through a JSON value sorts all map keys.
+        let canonical = serde_json::to_value(&save)
+            .map(|value| value.to_string())
+            .unwrap_or_default();
+        format!("{:016x}", fnv1a(canonical.as_bytes()))
+    }
+}
+
+fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in bytes {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCRIPT: &str = r#"
+seed = 7
+ticks = 40
+
+[[input]]
+tick = 2
+text = "look"
+
+[[input]]
+tick = 5
+action = "Forward"
+
+[[input]]
+tick = 12
+text = "inventory"
+"#;
+
+    #[test]
+    fn scripts_parse_and_round_trip() {
+        let script = SimulationScript::parse(SCRIPT).unwrap();
+        assert_eq!(script.seed, 7);
+        assert_eq!(script.inputs.len(), 3);
+        assert_eq!(script.inputs[1].action, Some(EntityAction::Forward));
+        assert_eq!(
+            SimulationScript::parse(&script.to_toml().unwrap()).unwrap(),
+            script
+        );
+        assert!(SimulationScript::parse("[[input]]\ntick = 1\n").is_err());
+
+        let short = SimulationScript::parse("[[input]]\ntick = 9\ntext = \"look\"\n").unwrap();
+        assert_eq!(short.ticks, 10);
+    }
+
+    #[test]
+    fn runs_are_deterministic_and_replayable() {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("../../starters/projects/Roguelike2D.eldiron");
+        assert!(path.exists(), "Missing starter project {}", path.display());
+        let script = SimulationScript::parse(SCRIPT).unwrap();
+
+        let mut first = Simulation::load(&path, script.seed).unwrap();
+        let report = first.run(&script).unwrap();
+        let log = first.input_log();
+        assert_eq!(report.ticks, 40);
+        assert!(!report.transcript.is_empty());
+        assert_eq!(log.inputs.len(), 3);
+        drop(first);
+
+        let mut second = Simulation::load(&path, script.seed).unwrap();
+        assert_eq!(second.replay(&log).unwrap(), report);
+        drop(second);
+
+        let mut tampered = log.clone();
+        tampered.digest = Some("0000000000000000".into());
+        let mut third = Simulation::load(&path, script.seed).unwrap();
+        assert!(third.replay(&tampered).is_err());
+    }
+}
@@ -1,5 +1,6 @@
 use crate::project::Project;
 use crate::region::Region;
+use crate::roguelike_fov::{RoguelikeCell, RoguelikeFov};
 use crate::screen::Screen;
 use crate::text_game as sg;
 use rusterix::{Entity, Item, Map, Value};
@@ -35,6 +36,8 @@ pub struct TerminalScreenFrame {
     pub hint: String,
     pub message: Option<String>,
     pub input_prompt: Option<String>,
+    /// What the player can see and remembers; `None` draws the whole map.
+    pub fov: Option<RoguelikeFov>,
 }
 
 pub fn project_terminal_screen_layout(project: &Project) -> Option<TerminalScreenLayout> {
@@ -158,7 +161,7 @@ pub fn render_roguelike_view(region: &Region, frame: &TerminalScreenFrame) -> St
     if !lines.is_empty() {
         lines.push(String::new());
     }
-    match render_roguelike_map(&region.map) {
+    match render_roguelike_map_in_view(&region.map, frame.fov.as_ref()) {
         Some(map_lines) => lines.extend(map_lines),
         None => lines.push(format!(
             "No source terrain metadata found for region '{}'.",
@@ -175,7 +178,7 @@ pub fn terminal_widget_lines(
     rules_src: &str,
 ) -> Vec<String> {
     match widget.role.as_str() {
-        "game" => render_roguelike_map(&region.map)
+        "game" => render_roguelike_map_in_view(&region.map, frame.fov.as_ref())
             .unwrap_or_else(|| vec![format!("No source terrain for '{}'.", region.name)]),
         "messages" => {
             let mut lines = Vec::new();
@@ -209,22 +212,43 @@ pub fn terminal_widget_lines(
 }
 
 pub fn render_roguelike_map(map: &Map) -> Option<Vec<String>> {
+    render_roguelike_map_in_view(map, None)
+}
+
+/// Render the map as seen through `fov`: hidden cells are blank, remembered
+/// cells show terrain only and items and entities appear only when visible.
+pub fn render_roguelike_map_in_view(map: &Map, fov: Option<&RoguelikeFov>) -> Option<Vec<String>> {
     let mut terrain = source_terrain(map)?;
+    let visible = |x: i32, y: i32| fov.is_none_or(|fov| fov.is_visible(x, y));
     for item in &map.items {
         let (x, y) = world_to_cell(item.position.x, item.position.z);
-        put_glyph(&mut terrain, x, y, roguelike_item_glyph(item));
+        if visible(x, y) {
+            put_glyph(&mut terrain, x, y, roguelike_item_glyph(item));
+        }
     }
 
     for entity in &map.entities {
         let (x, y) = world_to_cell(entity.position.x, entity.position.z);
         let glyph = if entity.is_player() {
             '@'
-        } else {
+        } else if visible(x, y) {
             roguelike_entity_glyph(entity)
+        } else {
+            continue;
         };
         put_glyph(&mut terrain, x, y, glyph);
     }
 
+    if let Some(fov) = fov {
+        for (y, row) in terrain.iter_mut().enumerate() {
+            for (x, glyph) in row.iter_mut().enumerate() {
+                if fov.cell(x as i32, y as i32) == RoguelikeCell::Hidden {
+                    *glyph = ' ';
+                }
+            }
+        }
+    }
+
     Some(
         terrain
             .into_iter()
@@ -744,7 +768,8 @@ fn roguelike_entity_glyph(entity: &Entity) -> char {
 mod tests {
     use super::*;
     use crate::prelude::*;
-    use rusterix::{MapCamera, PixelSource, Value};
+    use rusterix::{Light, LightType, MapCamera, PixelSource, Value};
+    use theframework::prelude::Vec3;
 
     #[test]
     fn renders_widgets_from_screen_roles() {
@@ -777,6 +802,7 @@ mod tests {
             hint: "hints".to_string(),
             message: None,
             input_prompt: None,
+            fov: None,
         };
         let rendered = render_roguelike_screen(&project, &region, &frame);
         assert!(rendered.contains("##"));
@@ -816,6 +842,66 @@ mod tests {
         assert!(!region.map.entities[0].attributes.contains("HP"));
     }
 
+    #[test]
+    fn fov_hides_rooms_behind_walls_and_remembers_explored_terrain() {
+        let project = Project::default();
+        let mut region = Region::default();
+        add_terrain_sector(&mut region.map, "#######\n#...#.#\n#@..#.#\n#######");
+        let mut player = Entity::new();
+        player.set_attribute("player", Value::Bool(true));
+        player.set_position(Vec3::new(1.5, 0.0, 2.5));
+        region.map.entities.push(player);
+        let mut rat = Entity::new();
+        rat.set_attribute("name", Value::Str("Rat".into()));
+        rat.set_position(Vec3::new(5.5, 0.0, 1.5));
+        region.map.entities.push(rat);
+
+        let config = RoguelikeFovConfig::from_config("[terminal]\nfov = true\nfov_radius = 3\n");
+        assert!(config.enabled && config.memory);
+        let mut memory = RoguelikeFovMemory::default();
+        let visible = roguelike_visibility(&project, &region.map, (1, 2), &config).unwrap();
+        memory.update("cellar", visible, config.memory);
+        let fov = memory.get("cellar").unwrap();
+        assert_eq!(fov.cell(3, 1), RoguelikeCell::Visible);
+        assert_eq!(fov.cell(5, 1), RoguelikeCell::Hidden);
+        assert_eq!(
+            render_roguelike_map_in_view(&region.map, Some(fov)).unwrap(),
+            vec!["####   ", "#...   ", "#@..#  ", "####   "]
+        );
+
+        let visible = roguelike_visibility(&project, &region.map, (5, 1), &config).unwrap();
+        memory.update("cellar", visible, config.memory);
+        let fov = memory.get("cellar").unwrap();
+        assert_eq!(fov.cell(1, 1), RoguelikeCell::Remembered);
+        let rendered = render_roguelike_map_in_view(&region.map, Some(fov)).unwrap();
+        assert_eq!(rendered[1], "#...#R#");
+        assert_eq!(rendered[2], "#@..#.#");
+    }
+
+    #[test]
+    fn lights_reveal_lit_cells_in_line_of_sight() {
+        let project = Project::default();
+        let mut region = Region::default();
+        add_terrain_sector(&mut region.map, "##########\n#@.......#\n##########");
+        region.map.lights.push(
+            Light::new(LightType::Point)
+                .with_position(Vec3::new(7.5, 0.0, 1.5))
+                .with_end_distance(1.0),
+        );
+        let config = RoguelikeFovConfig {
+            enabled: true,
+            radius: 2,
+            memory: false,
+        };
+
+        let visible = roguelike_visibility(&project, &region.map, (1, 1), &config).unwrap();
+        let lit = visible[1]
+            .iter()
+            .map(|visible| if *visible { 'x' } else { '-' })
+            .collect::<String>();
+        assert_eq!(lit, "xxxx--xxx-");
+    }
+
     fn add_widget(map: &mut Map, name: &str, role: &str, x: f32, y: f32, w: f32, h: f32) {
         let v0 = map.add_vertex_at(x, y);
         let v1 = map.add_vertex_at(x + w, y);
@@ -540,6 +540,20 @@ pub fn render_player_inventory(map: &Map) -> Option<String> {
     Some(lines.join("\n"))
 }
 
+pub fn render_player_journal(map: &Map) -> Option<String> {
+    let (player, _) = current_player_and_sector(map)?;
+
+    let mut lines = vec!["Journal:".to_string()];
+    let journal = player.quests.journal_lines();
+    if journal.is_empty() {
+        lines.push("  <no quests>".to_string());
+    } else {
+        lines.extend(journal.into_iter().map(|line| format!("  {}", line)));
+    }
+
+    Some(lines.join("\n"))
+}
+
 fn display_name_for_inventory_item(item: &Item) -> String {
     let name = display_name_for_item(item);
     let quantity = item.stack_quantity();
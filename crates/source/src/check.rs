@@ -0,0 +1,670 @@
+use super::*;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Error => f.write_str("error"),
+            Severity::Warning => f.write_str("warning"),
+        }
+    }
+}
+
+/// A problem found by [`check_project`], located at a 1-based line and column.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceDiagnostic {
+    pub path: PathBuf,
+    pub line: usize,
+    pub column: usize,
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl fmt::Display for SourceDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}:{}: {}: {}",
+            self.path.display(),
+            self.line,
+            self.column,
+            self.severity,
+            self.message
+        )
+    }
+}
+
+/// Parse and validate a source project without writing any output.
+///
+/// Problems in the sources are returned as diagnostics; only I/O failures are
+/// reported as `Err`.
+pub fn check_project(project_dir: &Path) -> Result<Vec<SourceDiagnostic>, String> {
+    let mut checker = Checker::default();
+    let config_path = project_dir.join("eldiron.toml");
+    let config_text = fs::read_to_string(&config_path)
+        .map_err(|err| format!("failed to read {}: {err}", config_path.display()))?;
+    let config_file = checker.add_file(config_path, config_text.clone());
+
+    let config: ProjectToml = match toml::from_str(&config_text) {
+        Ok(config) => config,
+        Err(err) => {
+            let offset = err.span().map_or(0, |span| span.start);
+            checker.error(config_file, offset, err.message().trim().to_string());
+            return Ok(checker.finish());
+        }
+    };
+    let passthrough_config = match project_config_passthrough(&config_text) {
+        Ok(passthrough_config) => passthrough_config,
+        Err(err) => {
+            checker.error(config_file, 0, err);
+            return Ok(checker.finish());
+        }
+    };
+
+    let main_path = project_dir.join(&config.source.main);
+    let mut paths = Vec::new();
+    if main_path.is_file() {
+        paths.push(main_path);
+    } else {
+        let offset = find_field_pos(&config_text, "main").unwrap_or(0);
+        checker.error(
+            config_file,
+            offset,
+            format!("source file '{}' does not exist", main_path.display()),
+        );
+    }
+    for dir in ["characters", "items", "regions", "screens"] {
+        paths.extend(source_dir_paths(project_dir, dir)?);
+    }
+
+    let mut source = CheckedSource::default();
+    for path in paths {
+        let text = fs::read_to_string(&path)
+            .map_err(|err| format!("failed to read {}: {err}", path.display()))?;
+        let file = checker.add_file(path, text);
+        checker.parse_file(file, &mut source);
+    }
+
+    match source_base_project(&config, Some(project_dir), &passthrough_config) {
+        Ok((project, lookup)) => {
+            checker.check_semantics(&config, &project, &lookup, &source);
+        }
+        Err(err) => checker.error(config_file, 0, err),
+    }
+
+    if source.region_ids.is_empty() {
+        checker.error(
+            config_file,
+            find_field_pos(&config_text, "main").unwrap_or(0),
+            "source project does not define any Region blocks".to_string(),
+        );
+    }
+    if !config.game.start_region.trim().is_empty()
+        && !source.region_ids.contains(&config.game.start_region)
+    {
+        checker.error(
+            config_file,
+            find_field_pos(&config_text, "start_region").unwrap_or(0),
+            format!(
+                "[game].start_region '{}' does not match any Region",
+                config.game.start_region
+            ),
+        );
+    }
+    if !config.game.start_screen.trim().is_empty()
+        && !source.screen_ids.contains(&config.game.start_screen)
+    {
+        checker.error(
+            config_file,
+            find_field_pos(&config_text, "start_screen").unwrap_or(0),
+            format!(
+                "[game].start_screen '{}' does not match any Screen",
+                config.game.start_screen
+            ),
+        );
+    }
+
+    Ok(checker.finish())
+}
+
+struct CheckedFile {
+    path: PathBuf,
+    text: String,
+}
+
+/// A parsed block together with the file it came from.
+struct Located<T> {
+    file: usize,
+    block: NamedBlock,
+    /// Byte offsets of the `tiles` entries inside the block, by glyph.
+    tile_entries: IndexMap<char, usize>,
+    value: T,
+}
+
+#[derive(Default)]
+struct CheckedSource {
+    /// Top-level `tiles` entries as `(symbol, file, offset)`.
+    tile_symbols: IndexMap<char, (SourceTileSymbol, usize, usize)>,
+    niches: IndexMap<String, Located<SourceNiche>>,
+    /// Character and Item lookup keys (ids and glyphs), as spawned by terrain.
+    characters: Vec<String>,
+    items: Vec<String>,
+    regions: Vec<Located<SourceRegion>>,
+    screens: Vec<Located<SourceScreen>>,
+    /// Names of all Region and Screen blocks, including those that failed to
+    /// parse, so their errors are not repeated as missing blocks.
+    region_ids: Vec<String>,
+    screen_ids: Vec<String>,
+}
+
+#[derive(Default)]
+struct Checker {
+    files: Vec<CheckedFile>,
+    diagnostics: Vec<(usize, usize, SourceDiagnostic)>,
+}
+
+impl Checker {
+    fn add_file(&mut self, path: PathBuf, text: String) -> usize {
+        self.files.push(CheckedFile { path, text });
+        self.files.len() - 1
+    }
+
+    fn error(&mut self, file: usize, offset: usize, message: String) {
+        self.report(file, offset, Severity::Error, message);
+    }
+
+    fn warning(&mut self, file: usize, offset: usize, message: String) {
+        self.report(file, offset, Severity::Warning, message);
+    }
+
+    fn report(&mut self, file: usize, offset: usize, severity: Severity, message: String) {
+        // The block parsers repeat errors that were already reported for a
+        // single line, keep the precise one.
+        if self
+            .diagnostics
+            .iter()
+            .any(|(other, _, diagnostic)| *other == file && diagnostic.message == message)
+        {
+            return;
+        }
+        let checked = &self.files[file];
+        let (line, column) = line_column(&checked.text, offset);
+        self.diagnostics.push((
+            file,
+            offset,
+            SourceDiagnostic {
+                path: checked.path.clone(),
+                line,
+                column,
+                severity,
+                message,
+            },
+        ));
+    }
+
+    fn finish(mut self) -> Vec<SourceDiagnostic> {
+        self.diagnostics
+            .sort_by_key(|(file, offset, _)| (*file, *offset));
+        self.diagnostics
+            .into_iter()
+            .map(|(_, _, diagnostic)| diagnostic)
+            .collect()
+    }
+
+    /// Parse one file block by block, so a broken block does not hide the
+    /// problems of the others.
+    fn parse_file(&mut self, file: usize, source: &mut CheckedSource) {
+        let text = self.files[file].text.clone();
+
+        let mut entries = Vec::new();
+        for (offset, entry) in tile_entries(&text) {
+            match parse_tile_symbol_assignments(entry) {
+                Ok(symbols) => entries.extend(symbols.into_keys().map(|glyph| (glyph, offset))),
+                Err(err) => self.error(file, offset, err),
+            }
+        }
+
+        let mut blocks: IndexMap<&str, Vec<NamedBlock>> = IndexMap::default();
+        for keyword in ["Niche", "Character", "Item", "Region", "Screen"] {
+            match locate_named_blocks(&text, keyword) {
+                Ok(found) => {
+                    blocks.insert(keyword, found);
+                }
+                Err((offset, err)) => self.error(file, offset, err),
+            }
+        }
+        let block_entries = |block: &NamedBlock| {
+            let body = block.body_offset..block.body_offset + block.body.len();
+            entries
+                .iter()
+                .filter(|(_, offset)| body.contains(offset))
+                .copied()
+                .collect::<IndexMap<char, usize>>()
+        };
+
+        match parse_top_level_tile_symbol_blocks(&text) {
+            Ok(symbols) => {
+                let nested = blocks
+                    .values()
+                    .flatten()
+                    .flat_map(|block| block_entries(block).into_values())
+                    .collect::<Vec<_>>();
+                let top_level = entries
+                    .iter()
+                    .filter(|(_, offset)| !nested.contains(offset))
+                    .copied()
+                    .collect::<IndexMap<char, usize>>();
+                for (glyph, symbol) in symbols {
+                    let offset = top_level.get(&glyph).copied().unwrap_or(0);
+                    source.tile_symbols.insert(glyph, (symbol, file, offset));
+                }
+            }
+            Err(err) => self.error(file, 0, err),
+        }
+
+        for block in blocks.shift_remove("Niche").unwrap_or_default() {
+            match parse_niche(&block) {
+                Ok(niche) => {
+                    if source.niches.contains_key(&niche.id) {
+                        self.error(
+                            file,
+                            block.offset,
+                            format!("Niche '{}' is defined more than once", block.name),
+                        );
+                        continue;
+                    }
+                    source.niches.insert(
+                        niche.id.clone(),
+                        Located {
+                            file,
+                            tile_entries: IndexMap::default(),
+                            block,
+                            value: niche,
+                        },
+                    );
+                }
+                Err((offset, err)) => self.error(file, offset, err),
+            }
+        }
+        for block in blocks.shift_remove("Character").unwrap_or_default() {
+            match parse_character(&block) {
+                Ok(character) => {
+                    source.characters.push(character.id);
+                    source
+                        .characters
+                        .extend(character.glyph.map(|glyph| glyph.to_string()));
+                }
+                Err((offset, err)) => self.error(file, offset, err),
+            }
+        }
+        for block in blocks.shift_remove("Item").unwrap_or_default() {
+            match parse_item(&block) {
+                Ok(item) => {
+                    source.items.push(item.id);
+                    source
+                        .items
+                        .extend(item.glyph.map(|glyph| glyph.to_string()));
+                }
+                Err((offset, err)) => self.error(file, offset, err),
+            }
+        }
+        for block in blocks.shift_remove("Region").unwrap_or_default() {
+            source.region_ids.push(block.name.clone());
+            match parse_region(&block) {
+                Ok(region) => source.regions.push(Located {
+                    file,
+                    tile_entries: block_entries(&block),
+                    block,
+                    value: region,
+                }),
+                Err((offset, err)) => self.error(file, offset, err),
+            }
+        }
+        for block in blocks.shift_remove("Screen").unwrap_or_default() {
+            source.screen_ids.push(block.name.clone());
+            match parse_screen(&block) {
+                Ok(screen) => source.screens.push(Located {
+                    file,
+                    tile_entries: IndexMap::default(),
+                    block,
+                    value: screen,
+                }),
+                Err(err) => self.error(file, block.offset, err),
+            }
+        }
+    }
+
+    /// Resolve everything the sources reference against the loaded tiles,
+    /// niches, characters and items.
+    fn check_semantics(
+        &mut self,
+        config: &ProjectToml,
+        project: &Project,
+        lookup: &SourceTileLookup,
+        source: &CheckedSource,
+    ) {
+        let mut item_templates = IndexMap::default();
+        register_ruleset_item_glyphs(&project.items, &mut item_templates);
+        let items = source
+            .items
+            .iter()
+            .cloned()
+            .chain(item_templates.into_keys())
+            .collect::<Vec<_>>();
+        let mut characters = source.characters.clone();
+        characters.push(config.game.player.clone());
+
+        let mut shared_problems = false;
+        for (glyph, (symbol, file, offset)) in &source.tile_symbols {
+            for problem in tile_symbol_problems(lookup, &source.niches, *glyph, symbol) {
+                shared_problems = true;
+                self.error(*file, *offset, format!("tiles entry {problem}"));
+            }
+        }
+        for niche in source.niches.values() {
+            if lookup.source_for(&niche.value.tile).is_none() {
+                shared_problems = true;
+                self.error(
+                    niche.file,
+                    niche.block.field_offset("tile"),
+                    format!(
+                        "Niche '{}' uses tile '{}', but no loaded tile with that alias/name exists",
+                        niche.value.id, niche.value.tile
+                    ),
+                );
+            }
+        }
+
+        let global_tile_symbols = source
+            .tile_symbols
+            .iter()
+            .map(|(glyph, (symbol, _, _))| (*glyph, symbol.clone()))
+            .collect::<IndexMap<_, _>>();
+        let source_niches = source
+            .niches
+            .iter()
+            .map(|(id, niche)| (id.clone(), niche.value.clone()))
+            .collect::<IndexMap<_, _>>();
+        let context = RegionContext {
+            lookup,
+            global_tile_symbols: &global_tile_symbols,
+            source_niches: &source_niches,
+            located_niches: &source.niches,
+            characters: &characters,
+            items: &items,
+            mode_3d: game_client_mode_is_3d(&config.game),
+            shared_problems,
+        };
+        for region in &source.regions {
+            self.check_region(region, &context);
+        }
+
+        for screen in &source.screens {
+            if let Err(err) = compile_screen(screen.value.clone(), &config.viewport, lookup) {
+                self.error(screen.file, screen.block.offset, err);
+            }
+        }
+    }
+
+    fn check_region(&mut self, region: &Located<SourceRegion>, context: &RegionContext) {
+        let lookup = context.lookup;
+        let source_region = &region.value;
+        let file = region.file;
+        let mut problems = 0;
+        for (glyph, symbol) in &source_region.tile_symbols {
+            let offset = region
+                .tile_entries
+                .get(glyph)
+                .copied()
+                .unwrap_or(region.block.offset);
+            for problem in tile_symbol_problems(lookup, context.located_niches, *glyph, symbol) {
+                self.error(
+                    file,
+                    offset,
+                    format!("Region '{}' {problem}", source_region.id),
+                );
+                problems += 1;
+            }
+        }
+        for (field, tile) in [
+            ("default", &source_region.default),
+            ("floor", &source_region.floor),
+            ("ceiling", &source_region.ceiling),
+        ] {
+            if find_field_pos(&region.block.body, field).is_none() {
+                continue;
+            }
+            if lookup.tile_only_for(tile).is_none() {
+                self.warning(
+                    file,
+                    region.block.field_offset(field),
+                    format!(
+                        "Region '{}' {field} tile '{tile}' does not match any loaded tile",
+                        source_region.id
+                    ),
+                );
+            }
+        }
+
+        let cells = terrain_cell_offsets(&region.block);
+        let cell_offset = |x: usize, y: usize| {
+            cells
+                .get(y)
+                .and_then(|row| row.get(x))
+                .map(|offset| region.block.body_offset + offset)
+                .unwrap_or(region.block.offset)
+        };
+
+        let mut unknown: IndexMap<char, (usize, usize, usize)> = IndexMap::default();
+        for (y, row) in source_region.terrain.iter().enumerate() {
+            for (x, glyph) in row.chars().enumerate() {
+                if matches!(glyph, '@' | '#' | '.' | ' ')
+                    || context.global_tile_symbols.contains_key(&glyph)
+                    || source_region.tile_symbols.contains_key(&glyph)
+                {
+                    continue;
+                }
+                let key = glyph.to_string();
+                if (glyph.is_ascii_uppercase() && context.characters.contains(&key))
+                    || (glyph.is_ascii_lowercase() && context.items.contains(&key))
+                {
+                    continue;
+                }
+                unknown.entry(glyph).or_insert((x, y, 0)).2 += 1;
+            }
+        }
+        for (glyph, (x, y, count)) in unknown {
+            let cells = if count > 1 {
+                format!(" ({count} cells)")
+            } else {
+                String::new()
+            };
+            let offset = cell_offset(x, y);
+            if glyph.is_ascii_uppercase() {
+                self.error(
+                    file,
+                    offset,
+                    format!(
+                        "Region '{}' places character '{glyph}', but no Character with that id or glyph exists{cells}",
+                        source_region.id
+                    ),
+                );
+            } else if glyph.is_ascii_lowercase() {
+                self.warning(
+                    file,
+                    offset,
+                    format!(
+                        "Region '{}' places item '{glyph}', but no Item with that id or glyph exists, a placeholder item is used{cells}",
+                        source_region.id
+                    ),
+                );
+            } else {
+                self.warning(
+                    file,
+                    offset,
+                    format!(
+                        "Region '{}' uses glyph '{glyph}', but no tiles block maps it, it compiles as floor{cells}",
+                        source_region.id
+                    ),
+                );
+            }
+        }
+
+        match resolve_source_tiles(
+            lookup,
+            context.global_tile_symbols,
+            context.source_niches,
+            source_region,
+        ) {
+            Ok(source_tiles) => {
+                if let Some((x, y, err)) = find_source_niche_violation(source_region, &source_tiles)
+                {
+                    self.error(file, cell_offset(x, y), err);
+                } else if let Err(err) = build_map(source_region, context.mode_3d, &source_tiles) {
+                    self.error(file, region.block.offset, err);
+                }
+            }
+            // Every resolve failure has been reported per entry above.
+            Err(err) if problems == 0 && !context.shared_problems => {
+                self.error(file, region.block.offset, err)
+            }
+            Err(_) => {}
+        }
+    }
+}
+
+/// Everything a Region is checked against.
+struct RegionContext<'a> {
+    lookup: &'a SourceTileLookup,
+    global_tile_symbols: &'a IndexMap<char, SourceTileSymbol>,
+    source_niches: &'a IndexMap<String, SourceNiche>,
+    located_niches: &'a IndexMap<String, Located<SourceNiche>>,
+    characters: &'a [String],
+    items: &'a [String],
+    mode_3d: bool,
+    shared_problems: bool,
+}
+
+/// What is wrong with a single `tiles` entry, without the Region context.
+fn tile_symbol_problems(
+    lookup: &SourceTileLookup,
+    niches: &IndexMap<String, Located<SourceNiche>>,
+    glyph: char,
+    symbol: &SourceTileSymbol,
+) -> Vec<String> {
+    let mut problems = Vec::new();
+    match lookup.source_for(&symbol.tile) {
+        Some(source) => {
+            if let Err(err) = lookup.recipe_geometry_for(&source) {
+                problems.push(format!("maps '{glyph}' to tile '{}': {err}", symbol.tile));
+            }
+        }
+        None => problems.push(format!(
+            "maps '{glyph}' to tile '{}', but no loaded tile with that alias/name exists",
+            symbol.tile
+        )),
+    }
+    if let Some(ceiling) = symbol.ceiling.as_deref()
+        && lookup.source_for(ceiling).is_none()
+    {
+        problems.push(format!(
+            "maps '{glyph}' to ceiling tile '{ceiling}', but no loaded tile with that alias/name exists"
+        ));
+    }
+    if let Some(niche) = symbol.niche.as_deref()
+        && !niches.contains_key(niche)
+    {
+        problems.push(format!(
+            "maps '{glyph}' to Niche '{niche}', but no Niche with that name exists"
+        ));
+    }
+    problems
+}
+
+/// 1-based line and column of a byte offset.
+fn line_column(text: &str, offset: usize) -> (usize, usize) {
+    let mut offset = offset.min(text.len());
+    while !text.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    let before = &text[..offset];
+    let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+    (
+        before.matches('\n').count() + 1,
+        before[line_start..].chars().count() + 1,
+    )
+}
+
+/// Every non-empty line inside a `tiles { ... }` block, with its byte offset.
+fn tile_entries(src: &str) -> Vec<(usize, &str)> {
+    let mut entries = Vec::new();
+    let mut search_from = 0;
+    while let Some(relative) = src[search_from..].find("tiles") {
+        let pos = search_from + relative;
+        if !is_boundary(src, pos, "tiles".len()) {
+            search_from = pos + "tiles".len();
+            continue;
+        }
+        let cursor = skip_ws(src, pos + "tiles".len());
+        if !src[cursor..].starts_with('{') {
+            search_from = cursor;
+            continue;
+        }
+        let Some(end) = find_matching_brace(src, cursor) else {
+            break;
+        };
+        let mut line_start = cursor + 1;
+        for line in src[cursor + 1..end].split_inclusive('\n') {
+            let content = strip_line_comment(line).trim();
+            if !content.is_empty() {
+                let indent = line.len() - line.trim_start().len();
+                entries.push((line_start + indent, line));
+            }
+            line_start += line.len();
+        }
+        search_from = end + 1;
+    }
+    entries
+}
+
+/// Byte offsets, relative to the block body, of every terrain cell as
+/// `[row][column]`, matching the rows of [`normalize_terrain_lines`].
+fn terrain_cell_offsets(block: &NamedBlock) -> Vec<Vec<usize>> {
+    let body = &block.body;
+    let Some(content_start) = find_field_pos(body, "terrain")
+        .and_then(|pos| body[pos..].find("\"\"\"").map(|open| pos + open + 3))
+    else {
+        return Vec::new();
+    };
+    let content_end = body[content_start..]
+        .find("\"\"\"")
+        .map_or(body.len(), |close| content_start + close);
+
+    let mut rows = Vec::new();
+    let mut line_start = content_start;
+    for line in body[content_start..content_end].split_inclusive('\n') {
+        if !line.trim().is_empty() {
+            rows.push((line_start, line.trim_end()));
+        }
+        line_start += line.len();
+    }
+    let indent = rows
+        .iter()
+        .map(|(_, line)| line.chars().take_while(|ch| ch.is_whitespace()).count())
+        .min()
+        .unwrap_or(0);
+    rows.into_iter()
+        .map(|(start, line)| {
+            line.char_indices()
+                .skip(indent)
+                .map(|(index, _)| start + index)
+                .collect()
+        })
+        .collect()
+}
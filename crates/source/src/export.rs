@@ -0,0 +1,1400 @@
+use super::*;
+use std::collections::{HashMap, HashSet};
+use std::io::Cursor;
+
+/// Glyphs handed out to extra tiles of a rasterized region, after `#` for
+/// the most common blocking tile and `.` for the most common floor.
+const REGION_TILE_GLYPHS: &str = "+:;,~-_*%&!?^$<>/|0123456789";
+
+/// The result of [`export_project`].
+#[derive(Debug, Default)]
+pub struct ExportReport {
+    /// Written files, relative to the export directory.
+    pub files: Vec<PathBuf>,
+    /// Project content that Eldiron Source cannot express and was dropped or
+    /// approximated.
+    pub warnings: Vec<String>,
+}
+
+/// Decompile a built `.eldiron` project into an Eldiron Source tree.
+///
+/// Writes `eldiron.toml`, `main.els`, one file per character, item, region
+/// and screen, tile images below `tiles/` and fonts, audio and images below
+/// `assets/`. Tiles keep their ids, so data referring to a `tile_id` still
+/// resolves after a rebuild. Regions are written as glyph maps when their
+/// geometry lies on the unit grid; other regions are skipped with a warning.
+///
+/// Refuses to write into a non-empty `out_dir` unless `force` is set, so an
+/// existing source project is not overwritten by accident.
+pub fn export_project(
+    game_path: &Path,
+    out_dir: &Path,
+    force: bool,
+) -> Result<ExportReport, String> {
+    if !force
+        && out_dir.is_dir()
+        && out_dir
+            .read_dir()
+            .map_err(|err| format!("failed to read {}: {err}", out_dir.display()))?
+            .next()
+            .is_some()
+    {
+        return Err(format!(
+            "{} already exists and is not empty. Use --force to export anyway.",
+            out_dir.display()
+        ));
+    }
+    let text = fs::read_to_string(game_path)
+        .map_err(|err| format!("failed to read {}: {err}", game_path.display()))?;
+    let project: Project = serde_json::from_str(&text)
+        .map_err(|err| format!("failed to parse {}: {err}", game_path.display()))?;
+    let source_name = game_path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    let mut exporter = Exporter {
+        project: &project,
+        out_dir,
+        report: ExportReport::default(),
+        tile_aliases: IndexMap::default(),
+        character_glyphs: IndexMap::default(),
+        item_glyphs: IndexMap::default(),
+        player: None,
+    };
+    let tile_config = exporter.export_tiles()?;
+    exporter.export_assets()?;
+    exporter.export_characters()?;
+    exporter.export_items()?;
+    exporter.export_regions()?;
+    exporter.export_screens()?;
+    exporter.warn_unexported_project_data();
+    exporter.write(
+        Path::new("main.els"),
+        format!(
+            "# Exported from {source_name} by `eldiron-source export`.\n# The source files live in the folders next to this file.\n"
+        )
+        .as_bytes(),
+    )?;
+    let config = exporter.eldiron_toml(&tile_config)?;
+    exporter.write(Path::new("eldiron.toml"), config.as_bytes())?;
+    Ok(exporter.report)
+}
+
+struct Exporter<'a> {
+    project: &'a Project,
+    out_dir: &'a Path,
+    report: ExportReport,
+    tile_aliases: IndexMap<Uuid, String>,
+    /// Template id to the glyph that spawns it in region terrain.
+    character_glyphs: IndexMap<Uuid, char>,
+    item_glyphs: IndexMap<Uuid, char>,
+    /// The player template and its source id.
+    player: Option<(Uuid, String)>,
+}
+
+impl Exporter<'_> {
+    fn write(&mut self, relative: &Path, contents: &[u8]) -> Result<(), String> {
+        let path = self.out_dir.join(relative);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|err| format!("failed to create {}: {err}", parent.display()))?;
+        }
+        fs::write(&path, contents)
+            .map_err(|err| format!("failed to write {}: {err}", path.display()))?;
+        self.report.files.push(relative.to_path_buf());
+        Ok(())
+    }
+
+    /// Write every project tile as PNG images and return the
+    /// `[[source.tiles]]` / `[[source.tile_animations]]` entries declaring
+    /// them. Bundled ruleset tiles are skipped, the rebuild adds them again.
+    fn export_tiles(&mut self) -> Result<String, String> {
+        let bundled: HashSet<Uuid> =
+            shared::rulesets::bundled_tiles_for_project(&self.project.config)?
+                .into_iter()
+                .map(|(id, _)| id)
+                .collect();
+
+        let mut used = HashSet::new();
+        let mut entries = Vec::new();
+        let mut recipe_tiles = 0;
+        for tile in self.project.tiles.values() {
+            if bundled.contains(&tile.id) {
+                self.tile_aliases.insert(tile.id, tile.id.to_string());
+                continue;
+            }
+            if tile.textures.is_empty() {
+                self.report.warnings.push(format!(
+                    "tile {} has no image and was not exported",
+                    tile.id
+                ));
+                continue;
+            }
+            let alias = unique_name(&tile_alias(tile), &mut used, "-");
+            let mut frames = Vec::with_capacity(tile.textures.len());
+            for (index, texture) in tile.textures.iter().enumerate() {
+                let path = if tile.textures.len() == 1 {
+                    format!("tiles/{alias}.png")
+                } else {
+                    format!("tiles/{alias}/{}.png", index + 1)
+                };
+                let png = encode_png(texture).ok_or_else(|| {
+                    format!(
+                        "tile {} has a malformed {}x{} image",
+                        tile.id, texture.width, texture.height
+                    )
+                })?;
+                self.write(Path::new(&path), &png)?;
+                frames.push(path);
+            }
+            if !tile.geometry.is_empty()
+                || !tile.attachments.is_empty()
+                || !tile.light_effects.is_empty()
+                || !tile.particle_effects.is_empty()
+            {
+                recipe_tiles += 1;
+            }
+
+            let mut entry = if frames.len() == 1 {
+                format!(
+                    "[[source.tiles]]\nalias = \"{}\"\npath = \"{}\"\n",
+                    escape_toml_string(&alias),
+                    escape_toml_string(&frames[0])
+                )
+            } else {
+                format!(
+                    "[[source.tile_animations]]\nalias = \"{}\"\nframes = [{}]\n",
+                    escape_toml_string(&alias),
+                    frames
+                        .iter()
+                        .map(|frame| format!("\"{}\"", escape_toml_string(frame)))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )
+            };
+            entry.push_str(&format!(
+                "id = \"{}\"\nrole = \"{}\"\n",
+                tile.id,
+                source_tile_role_name(tile.role)
+            ));
+            if tile.blocking {
+                entry.push_str("blocking = true\n");
+            }
+            if tile.scale != 1.0 {
+                entry.push_str(&format!("scale = {}\n", toml_float(tile.scale)));
+            }
+            if let Some(light) = &tile.light_emitter {
+                let [r, g, b, a] = light.color;
+                entry.push_str(&format!(
+                    "light_color = \"#{r:02X}{g:02X}{b:02X}{a:02X}\"\nlight_intensity = {}\nlight_range = {}\nlight_flicker = {}\nlight_lift = {}\n",
+                    toml_float(light.intensity),
+                    toml_float(light.range),
+                    toml_float(light.flicker),
+                    toml_float(light.lift)
+                ));
+            }
+            entries.push(entry);
+            self.tile_aliases.insert(tile.id, alias);
+        }
+        if recipe_tiles > 0 {
+            self.report.warnings.push(format!(
+                "{recipe_tiles} tile(s) carry recipe geometry or effects, which were exported as plain images"
+            ));
+        }
+        Ok(entries.join("\n"))
+    }
+
+    fn export_assets(&mut self) -> Result<(), String> {
+        let mut used = HashSet::new();
+        for asset in self.project.assets.values() {
+            let (extension, bytes) = match &asset.buffer {
+                AssetBuffer::Empty => continue,
+                AssetBuffer::Font(bytes) => {
+                    let extension = if bytes.starts_with(b"OTTO") {
+                        "otf"
+                    } else {
+                        "ttf"
+                    };
+                    (extension, bytes.clone())
+                }
+                AssetBuffer::Audio(bytes) => (audio_extension(bytes), bytes.clone()),
+                AssetBuffer::Image(buffer) => {
+                    let dim = buffer.dim();
+                    let texture = Texture::new(
+                        buffer.pixels().to_vec(),
+                        dim.width.max(0) as usize,
+                        dim.height.max(0) as usize,
+                    );
+                    let png = encode_png(&texture)
+                        .ok_or_else(|| format!("asset '{}' has a malformed image", asset.name))?;
+                    ("png", png)
+                }
+            };
+            let name = sanitize_path(&asset.name).unwrap_or_else(|| short_id(&asset.id));
+            let name = unique_name(&name, &mut used, "-");
+            self.write(Path::new(&format!("assets/{name}.{extension}")), &bytes)?;
+        }
+        Ok(())
+    }
+
+    fn export_characters(&mut self) -> Result<(), String> {
+        let mut ids = HashSet::new();
+        let mut glyphs = HashSet::new();
+        for character in self.project.characters.values() {
+            let attributes = data_attributes(&character.data);
+            let id = attributes
+                .get("source_id")
+                .and_then(toml::Value::as_str)
+                .map(source_identifier)
+                .filter(|id| !id.is_empty())
+                .unwrap_or_else(|| source_identifier(&character.name));
+            let id = unique_name(if id.is_empty() { "character" } else { &id }, &mut ids, "_");
+            let is_player = self.player.is_none()
+                && attributes
+                    .get("player")
+                    .and_then(toml::Value::as_bool)
+                    .unwrap_or(false);
+            let glyph = if is_player {
+                self.player = Some((character.id, id.clone()));
+                Some('@')
+            } else {
+                let glyph = pick_glyph(
+                    &template_glyph_hints(&character.authoring, &attributes, &character.name),
+                    &mut glyphs,
+                    char::is_ascii_uppercase,
+                    ('A'..='Z').collect(),
+                );
+                if glyph.is_none() {
+                    self.report.warnings.push(format!(
+                        "character '{}' got no glyph (A-Z are taken); its instances cannot be placed",
+                        character.name
+                    ));
+                }
+                glyph
+            };
+            if let Some(glyph) = glyph {
+                self.character_glyphs.insert(character.id, glyph);
+            }
+
+            let template = SourceCharacter {
+                id: id.clone(),
+                name: character.name.clone(),
+                glyph,
+                data: character.data.trim().to_string(),
+                authoring: character.authoring.trim().to_string(),
+                script: character.source.trim().to_string(),
+            };
+            let raw = template_source(
+                "Character",
+                &template.id,
+                &template.name,
+                template.glyph,
+                &template.data,
+                &template.authoring,
+                &template.script,
+            );
+            let text = self.checked_source(&format!("Character '{id}'"), raw, |document| {
+                document.characters.iter().any(|parsed| {
+                    parsed.id == template.id
+                        && parsed.name == template.name
+                        && parsed.glyph == template.glyph
+                        && same_toml(&parsed.data, &template.data)
+                        && same_toml(&parsed.authoring, &template.authoring)
+                        && same_code(&parsed.script, &template.script)
+                })
+            });
+            self.write(
+                Path::new(&format!("characters/{}.els", id.replace('_', "-"))),
+                text.as_bytes(),
+            )?;
+        }
+        Ok(())
+    }
+
+    fn export_items(&mut self) -> Result<(), String> {
+        let mut ids = HashSet::new();
+        // `h` and `b` spawn the ruleset herbs unless a template claims them.
+        let mut glyphs = HashSet::from(['h', 'b']);
+        for item in self.project.items.values() {
+            let attributes = data_attributes(&item.data);
+            if attributes.contains_key("ruleset_path") {
+                let glyph = match attributes.get("ruleset_id").and_then(toml::Value::as_str) {
+                    Some("wild_herb") => Some('h'),
+                    Some("blessed_herb") => Some('b'),
+                    _ => None,
+                };
+                if let Some(glyph) = glyph {
+                    self.item_glyphs.insert(item.id, glyph);
+                }
+                continue;
+            }
+
+            let id = attributes
+                .get("source_id")
+                .and_then(toml::Value::as_str)
+                .map(source_identifier)
+                .filter(|id| !id.is_empty())
+                .unwrap_or_else(|| source_identifier(&item.name));
+            let id = unique_name(if id.is_empty() { "item" } else { &id }, &mut ids, "_");
+            let mut hints = template_glyph_hints(&item.authoring, &attributes, &item.name);
+            hints.retain(|glyph| glyph.is_ascii_lowercase());
+            if let Some(symbol) = attributes
+                .get("source_symbol")
+                .and_then(toml::Value::as_str)
+                .and_then(|symbol| symbol.chars().next())
+            {
+                // Claimed explicitly, so `h`/`b` are fine here.
+                if symbol.is_ascii_lowercase() {
+                    glyphs.remove(&symbol);
+                    hints.insert(0, symbol);
+                }
+            }
+            let glyph = pick_glyph(
+                &hints,
+                &mut glyphs,
+                char::is_ascii_lowercase,
+                ('a'..='z').collect(),
+            );
+            match glyph {
+                Some(glyph) => {
+                    self.item_glyphs.insert(item.id, glyph);
+                }
+                None => self.report.warnings.push(format!(
+                    "item '{}' got no glyph (a-z are taken); its instances cannot be placed",
+                    item.name
+                )),
+            }
+
+            let template = SourceItem {
+                id: id.clone(),
+                name: item.name.clone(),
+                glyph,
+                data: item.data.trim().to_string(),
+                authoring: item.authoring.trim().to_string(),
+                script: item.source.trim().to_string(),
+            };
+            let raw = template_source(
+                "Item",
+                &template.id,
+                &template.name,
+                template.glyph,
+                &template.data,
+                &template.authoring,
+                &template.script,
+            );
+            let text = self.checked_source(&format!("Item '{id}'"), raw, |document| {
+                document.items.iter().any(|parsed| {
+                    parsed.id == template.id
+                        && parsed.name == template.name
+                        && parsed.glyph == template.glyph
+                        && same_toml(&parsed.data, &template.data)
+                        && same_toml(&parsed.authoring, &template.authoring)
+                        && same_code(&parsed.script, &template.script)
+                })
+            });
+            self.write(
+                Path::new(&format!("items/{}.els", id.replace('_', "-"))),
+                text.as_bytes(),
+            )?;
+        }
+        Ok(())
+    }
+
+    fn export_regions(&mut self) -> Result<(), String> {
+        let mut ids = HashSet::new();
+        let mut files = HashSet::new();
+        for region in &self.project.regions {
+            let map = &region.map;
+            let id = if map.name.trim().is_empty() {
+                region.name.clone()
+            } else {
+                map.name.clone()
+            };
+            if !ids.insert(id.clone()) {
+                self.report.warnings.push(format!(
+                    "region '{id}' is defined more than once; only the first was exported"
+                ));
+                continue;
+            }
+
+            let source_metadata = std::iter::once(&map.properties)
+                .chain(map.sectors.iter().map(|sector| &sector.properties))
+                .find(|properties| properties.get_str("eldiron_source_terrain").is_some());
+            let body = match source_metadata {
+                Some(properties) => self.source_region_body(&id, map, properties),
+                None => match self.rasterized_region_body(&id, region) {
+                    Ok(body) => body,
+                    Err(reason) => {
+                        self.report.warnings.push(format!(
+                            "region '{id}' is not grid-representable ({reason}) and was not exported"
+                        ));
+                        continue;
+                    }
+                },
+            };
+
+            if !region.source.trim().is_empty() {
+                self.report
+                    .warnings
+                    .push(format!("region '{id}': its region script was not exported"));
+            }
+            if !region.config.trim().is_empty() {
+                self.report
+                    .warnings
+                    .push(format!("region '{id}': its region config was not exported"));
+            }
+
+            let raw = format!(
+                "Region \"{}\" {{\nname = \"{}\"\n{}}}\n",
+                escape_toml_string(&id),
+                escape_toml_string(&region.name),
+                body.text
+            );
+            let text = self.checked_source(&format!("Region '{id}'"), raw, |document| {
+                document
+                    .regions
+                    .iter()
+                    .any(|parsed| parsed.id == id && parsed.terrain == body.terrain)
+            });
+            let file = unique_name(&file_stem(&id, "region"), &mut files, "-");
+            self.write(Path::new(&format!("regions/{file}.els")), text.as_bytes())?;
+        }
+        if ids.is_empty() {
+            self.report
+                .warnings
+                .push("no region was exported; add a Region block before building".to_string());
+        }
+        Ok(())
+    }
+
+    /// A region built from source keeps its recorded terrain and tiles. Its
+    /// instances are already encoded in the terrain glyphs.
+    fn source_region_body(
+        &mut self,
+        id: &str,
+        map: &Map,
+        properties: &ValueContainer,
+    ) -> RegionBody {
+        let terrain =
+            normalize_terrain_lines(properties.get_str("eldiron_source_terrain").unwrap_or(""));
+        let tiles = properties
+            .get_str("eldiron_source_tiles")
+            .and_then(|tiles| tiles.parse::<toml::Table>().ok())
+            .unwrap_or_default();
+        let tile_alias = |key: &str| {
+            tiles
+                .get(key)
+                .and_then(toml::Value::as_str)
+                .and_then(|id| Uuid::parse_str(id).ok())
+                .map(|id| self.alias_for(id))
+        };
+
+        let mut text = String::new();
+        let default = tile_alias("wall").or_else(|| {
+            properties
+                .get_str("eldiron_source_default")
+                .map(str::to_string)
+        });
+        for (key, alias) in [
+            ("default", default),
+            ("floor", tile_alias("floor")),
+            ("ceiling", tile_alias("ceiling")),
+        ] {
+            if let Some(alias) = alias {
+                text.push_str(&format!("{key} = {alias}\n"));
+            }
+        }
+        text.push_str(&format!(
+            "ceiling_height = {}\n",
+            toml_float(properties.get_float_default(
+                "eldiron_source_ceiling_height",
+                DEFAULT_SOURCE_CEILING_HEIGHT
+            ))
+        ));
+        let glyph_tiles: Vec<(String, String)> = tiles
+            .keys()
+            .filter(|key| key.chars().count() == 1)
+            .filter_map(|glyph| Some((glyph.clone(), tile_alias(glyph)?)))
+            .collect();
+        if !glyph_tiles.is_empty() {
+            text.push_str("\ntiles {\n");
+            for (glyph, alias) in glyph_tiles {
+                text.push_str(&format!("\"{}\" = {alias}\n", escape_toml_string(&glyph)));
+            }
+            text.push_str("}\n");
+        }
+        if map.camera != MapCamera::TwoD {
+            self.report.warnings.push(format!(
+                "region '{id}': per-glyph ceiling, material and niche options are not kept in a build and were dropped"
+            ));
+        }
+        text.push_str(&terrain_source(&terrain));
+        RegionBody { text, terrain }
+    }
+
+    /// Rasterize a 2D region whose vertices lie on the unit grid: every cell
+    /// takes the tile of the sector covering its centre, and instances are
+    /// placed as glyphs.
+    fn rasterized_region_body(&mut self, id: &str, region: &Region) -> Result<RegionBody, String> {
+        let map = &region.map;
+        if map.sectors.is_empty() {
+            return Err("it has no sectors".to_string());
+        }
+        if !map.geometry_objects.is_empty() {
+            return Err("it contains 3D geometry".to_string());
+        }
+        if map
+            .vertices
+            .iter()
+            .any(|vertex| vertex.x.fract() != 0.0 || vertex.y.fract() != 0.0)
+        {
+            return Err("its vertices are not on whole grid cells".to_string());
+        }
+        let bbox = map.bbox();
+        let (min_x, min_y) = (bbox.min.x, bbox.min.y);
+        let width = (bbox.max.x - min_x) as usize;
+        let height = (bbox.max.y - min_y) as usize;
+        if !(2..=256).contains(&width) || !(2..=256).contains(&height) {
+            return Err(format!("it spans {width}x{height} cells, outside 2..=256"));
+        }
+
+        // `None` is void, `Some(None)` a sector without a tile.
+        let mut cells: Vec<Vec<Option<Option<Uuid>>>> = vec![vec![None; width]; height];
+        let mut sectors: Vec<&Sector> = map.sectors.iter().collect();
+        sectors.sort_by_key(|sector| sector.layer);
+        let mut named = Vec::new();
+        for sector in sectors {
+            if !sector.name.trim().is_empty() {
+                named.push(sector.name.clone());
+            }
+            let tile = match sector.properties.get_source("source") {
+                Some(PixelSource::TileId(id)) => Some(*id),
+                _ => None,
+            };
+            let bounds = sector.bounding_box(map);
+            let x0 = (bounds.min.x - min_x).floor().max(0.0) as usize;
+            let y0 = (bounds.min.y - min_y).floor().max(0.0) as usize;
+            let x1 = ((bounds.max.x - min_x).ceil() as usize).min(width);
+            let y1 = ((bounds.max.y - min_y).ceil() as usize).min(height);
+            for (y, row) in cells.iter_mut().enumerate().take(y1).skip(y0) {
+                for (x, cell) in row.iter_mut().enumerate().take(x1).skip(x0) {
+                    let centre = Vec2::new(min_x + x as f32 + 0.5, min_y + y as f32 + 0.5);
+                    if sector.is_inside(map, centre) {
+                        *cell = Some(tile);
+                    }
+                }
+            }
+        }
+        if !named.is_empty() {
+            self.report.warnings.push(format!(
+                "region '{id}': named sectors ({}) have no source equivalent and were dropped",
+                named.join(", ")
+            ));
+        }
+
+        // The most common blocking tile becomes `#`, the most common floor
+        // `.`, and every other tile gets its own glyph.
+        let blocking = |tile: &Option<Uuid>| {
+            tile.and_then(|id| self.project.tiles.get(&id))
+                .is_some_and(|tile| tile.blocking)
+        };
+        let mut counts: IndexMap<Option<Uuid>, usize> = IndexMap::default();
+        for tile in cells.iter().flatten().flatten() {
+            *counts.entry(*tile).or_default() += 1;
+        }
+        let most_common = |wanted: bool| {
+            counts
+                .iter()
+                .filter(|(tile, _)| blocking(tile) == wanted)
+                .max_by_key(|(_, count)| **count)
+                .map(|(tile, _)| *tile)
+        };
+        let wall = most_common(true);
+        let floor = most_common(false);
+        let mut glyphs: HashMap<Option<Uuid>, char> = HashMap::new();
+        let mut pool = REGION_TILE_GLYPHS.chars();
+        let mut tile_entries = Vec::new();
+        let mut out_of_glyphs = false;
+        for tile in counts.keys() {
+            let glyph = if Some(*tile) == wall {
+                '#'
+            } else if Some(*tile) == floor {
+                '.'
+            } else if let (Some(id), Some(glyph)) = (tile, pool.next()) {
+                let blocking_option = if blocking(tile) { " blocking=true" } else { "" };
+                tile_entries.push(format!(
+                    "\"{glyph}\" = {}{blocking_option}",
+                    self.alias_for(*id)
+                ));
+                glyph
+            } else {
+                out_of_glyphs |= tile.is_some();
+                if blocking(tile) { '#' } else { '.' }
+            };
+            glyphs.insert(*tile, glyph);
+        }
+        if out_of_glyphs {
+            self.report.warnings.push(format!(
+                "region '{id}' uses more tiles than there are glyphs; the rest became wall or floor"
+            ));
+        }
+
+        let mut rows: Vec<Vec<char>> = cells
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|cell| cell.map(|tile| glyphs[&tile]).unwrap_or(' '))
+                    .collect()
+            })
+            .collect();
+        // Void at the edges would be trimmed off the terrain and shift it.
+        let lines: Vec<String> = rows.iter().map(|row| row.iter().collect()).collect();
+        let trimmed: Vec<String> = lines
+            .iter()
+            .map(|line| line.trim_end().to_string())
+            .collect();
+        if normalize_terrain_lines(&lines.join("\n")) != trimmed {
+            for glyph in rows.iter_mut().flatten() {
+                if *glyph == ' ' {
+                    *glyph = '#';
+                }
+            }
+        }
+        self.place_instances(id, region, &mut rows, min_x, min_y);
+        let terrain: Vec<String> = rows
+            .iter()
+            .map(|row| row.iter().collect::<String>().trim_end().to_string())
+            .collect();
+
+        let mut text = String::new();
+        if let Some(Some(wall)) = wall {
+            text.push_str(&format!("default = {}\n", self.alias_for(wall)));
+        }
+        if let Some(Some(floor)) = floor {
+            text.push_str(&format!("floor = {}\n", self.alias_for(floor)));
+        }
+        if !tile_entries.is_empty() {
+            text.push_str(&format!("\ntiles {{\n{}\n}}\n", tile_entries.join("\n")));
+        }
+        text.push_str(&terrain_source(&terrain));
+        Ok(RegionBody { text, terrain })
+    }
+
+    fn place_instances(
+        &mut self,
+        id: &str,
+        region: &Region,
+        rows: &mut [Vec<char>],
+        min_x: f32,
+        min_y: f32,
+    ) {
+        let player = self.player.as_ref().map(|(template, _)| *template);
+        let characters = region.characters.values().map(|character| {
+            let glyph = if Some(character.character_id) == player {
+                Some('@')
+            } else {
+                self.character_glyphs.get(&character.character_id).copied()
+            };
+            (&character.name, character.position, glyph)
+        });
+        let items = region.items.values().map(|item| {
+            (
+                &item.name,
+                item.position,
+                self.item_glyphs.get(&item.item_id).copied(),
+            )
+        });
+        let mut problems = Vec::new();
+        for (name, position, glyph) in characters.chain(items) {
+            let Some(glyph) = glyph else {
+                problems.push(format!("'{name}' has no template glyph"));
+                continue;
+            };
+            let x = (position.x - min_x).floor();
+            let y = (position.z - min_y).floor();
+            let Some(cell) = (x >= 0.0 && y >= 0.0)
+                .then(|| rows.get_mut(y as usize)?.get_mut(x as usize))
+                .flatten()
+            else {
+                problems.push(format!("'{name}' lies outside the map"));
+                continue;
+            };
+            if cell.is_ascii_alphabetic() || *cell == '@' {
+                problems.push(format!("'{name}' shares its cell with another instance"));
+                continue;
+            }
+            *cell = glyph;
+        }
+        for problem in problems {
+            self.report
+                .warnings
+                .push(format!("region '{id}': {problem} and was not placed"));
+        }
+    }
+
+    fn export_screens(&mut self) -> Result<(), String> {
+        let config = self
+            .project
+            .config
+            .parse::<toml::Table>()
+            .unwrap_or_default();
+        let viewport_number = |key: &str, default: f32| {
+            config
+                .get("viewport")
+                .and_then(|viewport| viewport.get(key))
+                .and_then(|value| {
+                    value
+                        .as_float()
+                        .map(|value| value as f32)
+                        .or_else(|| value.as_integer().map(|value| value as f32))
+                })
+                .unwrap_or(default)
+        };
+        let start_x = -viewport_number("width", RUNTIME_VIEWPORT_WIDTH as f32) / 2.0;
+        let start_y = -viewport_number("height", RUNTIME_VIEWPORT_HEIGHT as f32) / 2.0;
+        let viewport_grid_size = viewport_number("grid_size", RUNTIME_VIEWPORT_GRID_SIZE as f32);
+
+        let mut ids = HashSet::new();
+        let mut files = HashSet::new();
+        for screen in self.project.screens.values() {
+            let map = &screen.map;
+            let id = if map.name.trim().is_empty() {
+                screen.name.clone()
+            } else {
+                map.name.clone()
+            };
+            let id = unique_name(&id, &mut ids, "_");
+            if map.sectors.is_empty() {
+                self.report
+                    .warnings
+                    .push(format!("screen '{id}' has no widgets and was not exported"));
+                continue;
+            }
+
+            let mut raw = format!(
+                "Screen \"{}\" {{\nname = \"{}\"\n",
+                escape_toml_string(&id),
+                escape_toml_string(&screen.name)
+            );
+            if map.grid_size != viewport_grid_size {
+                raw.push_str(&format!("grid_size = {}\n", source_number(map.grid_size)));
+            }
+            let mut widgets = Vec::new();
+            let mut unsupported = 0;
+            for sector in &map.sectors {
+                let bounds = sector.bounding_box(map);
+                let data = sector
+                    .properties
+                    .get_str("data")
+                    .unwrap_or_default()
+                    .trim()
+                    .to_string();
+                let role = data
+                    .parse::<toml::Table>()
+                    .ok()
+                    .and_then(|data| data.get("ui")?.get("role")?.as_str().map(str::to_string))
+                    .unwrap_or_else(|| "none".to_string());
+                let source = match sector.properties.get_source("source") {
+                    Some(PixelSource::TileId(id)) => Some(self.alias_for(*id)),
+                    _ => None,
+                };
+                if matches!(
+                    sector.properties.get_source("ceiling_source"),
+                    Some(PixelSource::TileId(_))
+                ) || sector.properties.get("screen_graph").is_some()
+                    || sector.linedefs.len() != 4
+                {
+                    unsupported += 1;
+                }
+                let widget = SourceWidget {
+                    name: sector.name.clone(),
+                    role,
+                    source,
+                    x: bounds.min.x - start_x,
+                    y: bounds.min.y - start_y,
+                    width: bounds.max.x - bounds.min.x,
+                    height: bounds.max.y - bounds.min.y,
+                    data,
+                };
+                raw.push_str(&format!(
+                    "\nwidget \"{}\" {{\nrole = \"{}\"\n",
+                    escape_toml_string(&widget.name),
+                    escape_toml_string(&widget.role)
+                ));
+                if let Some(source) = &widget.source {
+                    raw.push_str(&format!("source = {source}\n"));
+                }
+                raw.push_str(&format!(
+                    "x = {}\ny = {}\nwidth = {}\nheight = {}\n",
+                    source_number(widget.x),
+                    source_number(widget.y),
+                    source_number(widget.width),
+                    source_number(widget.height)
+                ));
+                if !widget.data.is_empty() {
+                    raw.push_str(&format!("\ndata {{\n{}\n}}\n", widget.data));
+                }
+                raw.push_str("}\n");
+                widgets.push(widget);
+            }
+            raw.push_str("}\n");
+            if unsupported > 0 {
+                self.report.warnings.push(format!(
+                    "screen '{id}': {unsupported} widget(s) use hover tiles, screen graphs or non-rectangular shapes, which source widgets cannot express"
+                ));
+            }
+
+            let text = self.checked_source(&format!("Screen '{id}'"), raw, |document| {
+                document.screens.iter().any(|parsed| {
+                    parsed.id == id
+                        && parsed.widgets.len() == widgets.len()
+                        && parsed.widgets.iter().zip(&widgets).all(|(parsed, widget)| {
+                            parsed.name == widget.name
+                                && parsed.source == widget.source
+                                && (parsed.x - widget.x).abs() < 0.001
+                                && (parsed.y - widget.y).abs() < 0.001
+                                && same_toml(&parsed.data, &widget.data)
+                        })
+                })
+            });
+            let file = unique_name(&file_stem(&id, "screen"), &mut files, "-");
+            self.write(Path::new(&format!("screens/{file}.els")), text.as_bytes())?;
+        }
+        Ok(())
+    }
+
+    fn warn_unexported_project_data(&mut self) {
+        let defaults = Project::new();
+        let project = self.project;
+        let mut dropped = Vec::new();
+        if !project.world_source.trim().is_empty() {
+            dropped.push("the world script");
+        }
+        if !project.rules.trim().is_empty()
+            && project.rules != shared::rulesets::DEFAULT_RULES_OVERRIDE
+        {
+            dropped.push("the project rules");
+        }
+        if project.locales != defaults.locales {
+            dropped.push("the project locales");
+        }
+        if project.audio_fx != defaults.audio_fx {
+            dropped.push("the audio effects");
+        }
+        if project.authoring != defaults.authoring && project.authoring != SOURCE_PROJECT_AUTHORING
+        {
+            dropped.push("the project authoring");
+        }
+        if !project.avatars.is_empty() {
+            dropped.push("the avatars");
+        }
+        if !dropped.is_empty() {
+            self.report.warnings.push(format!(
+                "{} cannot be expressed in Eldiron Source and were not exported",
+                dropped.join(", ")
+            ));
+        }
+    }
+
+    /// The project config with the `[project]`, `[source]` and `[build]`
+    /// sections added, and every compiler-owned key the config leaves to a
+    /// runtime default spelled out, because the compiler's defaults differ.
+    fn eldiron_toml(&self, tile_config: &str) -> Result<String, String> {
+        let config = self
+            .project
+            .config
+            .parse::<toml::Table>()
+            .map_err(|err| format!("failed to parse the project config: {err}"))?;
+        let has = |section: &str, key: &str| {
+            config
+                .get(section)
+                .and_then(toml::Value::as_table)
+                .is_some_and(|table| table.contains_key(key))
+        };
+
+        let start_region = config
+            .get("game")
+            .and_then(|game| game.get("start_region"))
+            .and_then(toml::Value::as_str)
+            .unwrap_or_default();
+        let client_mode = self
+            .project
+            .regions
+            .iter()
+            .find(|region| region.map.name == start_region)
+            .or(self.project.regions.first())
+            .map(|region| {
+                if region.map.camera == MapCamera::TwoD {
+                    "2d"
+                } else {
+                    "3d"
+                }
+            })
+            .unwrap_or("terminal");
+        let mut game = Vec::new();
+        if let Some((_, player)) = &self.player
+            && !has("game", "player")
+        {
+            game.push(format!("player = \"{}\"", escape_toml_string(player)));
+        }
+        for (key, value) in [
+            ("client_mode", client_mode),
+            ("terminal_mode", "text"),
+            ("simulation_mode", "realtime"),
+        ] {
+            if !has("game", key) {
+                game.push(format!("{key} = \"{value}\""));
+            }
+        }
+        let mut viewport = Vec::new();
+        for (key, value) in [
+            ("width", RUNTIME_VIEWPORT_WIDTH.to_string()),
+            ("height", RUNTIME_VIEWPORT_HEIGHT.to_string()),
+            ("grid_size", RUNTIME_VIEWPORT_GRID_SIZE.to_string()),
+            ("unit", "\"pixel\"".to_string()),
+            ("resize", "\"fixed\"".to_string()),
+        ] {
+            if !has("viewport", key) {
+                viewport.push(format!("{key} = {value}"));
+            }
+        }
+        let runtime = append_section_lines(&self.project.config, "game", &game);
+        let runtime = append_section_lines(&runtime, "viewport", &viewport);
+
+        let name = if self.project.name.trim().is_empty() {
+            "Eldiron Project"
+        } else {
+            self.project.name.trim()
+        };
+        let mut text = format!(
+            "[project]\nname = \"{}\"\n\n[source]\nmain = \"main.els\"\n",
+            escape_toml_string(name)
+        );
+        if !tile_config.is_empty() {
+            text.push('\n');
+            text.push_str(tile_config);
+        }
+        text.push('\n');
+        text.push_str(runtime.trim());
+        text.push_str(&format!(
+            "\n\n[build]\noutput = \"build/{}.eldiron\"\n",
+            file_stem(name, "game")
+        ));
+        toml::from_str::<ProjectToml>(&text)
+            .map_err(|err| format!("exported eldiron.toml is invalid: {err}"))?;
+        Ok(text)
+    }
+
+    fn alias_for(&self, id: Uuid) -> String {
+        self.tile_aliases
+            .get(&id)
+            .cloned()
+            .unwrap_or_else(|| id.to_string())
+    }
+
+    /// Formatted source, unless formatting would change what the compiler
+    /// reads back; then the unformatted text, or a warning if neither reads
+    /// back as intended.
+    fn checked_source(
+        &mut self,
+        label: &str,
+        raw: String,
+        reads_back: impl Fn(&SourceDocument) -> bool,
+    ) -> String {
+        let formatted = format_source(&raw);
+        for text in [&formatted, &raw] {
+            if parse_source(text).is_ok_and(|document| reads_back(&document)) {
+                return text.clone();
+            }
+        }
+        self.report.warnings.push(format!(
+            "{label} does not read back unchanged from its exported source; review it before building"
+        ));
+        formatted
+    }
+}
+
+/// Runtime defaults for viewport keys missing from a project config.
+const RUNTIME_VIEWPORT_WIDTH: u32 = 1280;
+const RUNTIME_VIEWPORT_HEIGHT: u32 = 720;
+const RUNTIME_VIEWPORT_GRID_SIZE: u32 = 32;
+
+/// The project authoring every source build starts with.
+const SOURCE_PROJECT_AUTHORING: &str = "[startup]\nshow = \"room\"\n";
+
+struct RegionBody {
+    /// Region fields, tiles and terrain, without the name.
+    text: String,
+    terrain: Vec<String>,
+}
+
+fn template_source(
+    keyword: &str,
+    id: &str,
+    name: &str,
+    glyph: Option<char>,
+    data: &str,
+    authoring: &str,
+    script: &str,
+) -> String {
+    let mut text = format!(
+        "{keyword} \"{}\" {{\nname = \"{}\"\n",
+        escape_toml_string(id),
+        escape_toml_string(name)
+    );
+    if let Some(glyph) = glyph {
+        text.push_str(&format!(
+            "glyph = \"{}\"\n",
+            escape_toml_string(&glyph.to_string())
+        ));
+    }
+    for (block, body) in [("data", data), ("authoring", authoring), ("script", script)] {
+        if !body.is_empty() {
+            text.push_str(&format!("\n{block} {{\n{body}\n}}\n"));
+        }
+    }
+    text.push_str("}\n");
+    text
+}
+
+fn terrain_source(terrain: &[String]) -> String {
+    format!("\nterrain \"\"\"\n{}\n\"\"\"\n", terrain.join("\n"))
+}
+
+fn data_attributes(data: &str) -> toml::Table {
+    data.parse::<toml::Table>()
+        .ok()
+        .and_then(|data| data.get("attributes")?.as_table().cloned())
+        .unwrap_or_default()
+}
+
+/// Glyph candidates for a template, most preferred first: its terminal
+/// glyph, its recorded source symbol and the first letter of its name.
+fn template_glyph_hints(authoring: &str, attributes: &toml::Table, name: &str) -> Vec<char> {
+    let terminal_glyph = authoring.parse::<toml::Table>().ok().and_then(|authoring| {
+        authoring
+            .get("terminal")?
+            .get("glyph")?
+            .as_str()
+            .map(str::to_string)
+    });
+    let symbol = attributes
+        .get("source_symbol")
+        .or_else(|| attributes.get("terminal_glyph"))
+        .and_then(toml::Value::as_str)
+        .map(str::to_string);
+    let initial = name.chars().find(char::is_ascii_alphabetic);
+    let mut hints = Vec::new();
+    for glyph in [terminal_glyph, symbol]
+        .into_iter()
+        .flatten()
+        .filter_map(|glyph| glyph.chars().next())
+    {
+        hints.push(glyph);
+    }
+    if let Some(initial) = initial {
+        hints.push(initial.to_ascii_uppercase());
+        hints.push(initial.to_ascii_lowercase());
+    }
+    hints
+}
+
+fn pick_glyph(
+    hints: &[char],
+    used: &mut HashSet<char>,
+    allowed: impl Fn(&char) -> bool,
+    fallback: Vec<char>,
+) -> Option<char> {
+    let glyph = hints
+        .iter()
+        .chain(&fallback)
+        .copied()
+        .find(|glyph| allowed(glyph) && !used.contains(glyph))?;
+    used.insert(glyph);
+    Some(glyph)
+}
+
+/// Insert `lines` at the end of `[section]` in a TOML text, keeping its
+/// comments, or append the section when it does not exist.
+fn append_section_lines(config: &str, section: &str, lines: &[String]) -> String {
+    if lines.is_empty() {
+        return config.to_string();
+    }
+    let header = format!("[{section}]");
+    let src: Vec<&str> = config.lines().collect();
+    let Some(start) = src
+        .iter()
+        .position(|line| strip_line_comment(line).trim() == header)
+    else {
+        let config = config.trim_end();
+        let separator = if config.is_empty() { "" } else { "\n\n" };
+        return format!("{config}{separator}{header}\n{}\n", lines.join("\n"));
+    };
+    let mut end = src[start + 1..]
+        .iter()
+        .position(|line| line.trim_start().starts_with('['))
+        .map(|offset| start + 1 + offset)
+        .unwrap_or(src.len());
+    while end > start + 1 && src[end - 1].trim().is_empty() {
+        end -= 1;
+    }
+    let mut out: Vec<String> = src[..end].iter().map(|line| line.to_string()).collect();
+    out.extend(lines.iter().cloned());
+    out.extend(src[end..].iter().map(|line| line.to_string()));
+    out.join("\n") + "\n"
+}
+
+fn same_toml(a: &str, b: &str) -> bool {
+    match (a.parse::<toml::Table>(), b.parse::<toml::Table>()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => same_code(a, b),
+    }
+}
+
+/// Equal up to indentation and blank lines.
+fn same_code(a: &str, b: &str) -> bool {
+    let lines = |text: &str| {
+        text.lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(str::to_string)
+            .collect::<Vec<_>>()
+    };
+    lines(a) == lines(b)
+}
+
+fn encode_png(texture: &Texture) -> Option<Vec<u8>> {
+    let image = image::RgbaImage::from_raw(
+        texture.width as u32,
+        texture.height as u32,
+        texture.data.clone(),
+    )?;
+    let mut bytes = Vec::new();
+    image
+        .write_to(&mut Cursor::new(&mut bytes), image::ImageFormat::Png)
+        .ok()?;
+    Some(bytes)
+}
+
+fn audio_extension(bytes: &[u8]) -> &'static str {
+    if bytes.starts_with(b"OggS") {
+        "ogg"
+    } else if bytes.starts_with(b"RIFF") {
+        "wav"
+    } else if bytes.starts_with(b"fLaC") {
+        "flac"
+    } else {
+        "mp3"
+    }
+}
+
+fn source_tile_role_name(role: TileRole) -> &'static str {
+    match role {
+        TileRole::Character => "character",
+        TileRole::Nature => "nature",
+        TileRole::Mountain => "mountain",
+        TileRole::Road => "road",
+        TileRole::Water => "water",
+        TileRole::ManMade => "man_made",
+        TileRole::Dungeon => "dungeon",
+        TileRole::Effect => "effect",
+        TileRole::Icon => "icon",
+        TileRole::UI => "ui",
+    }
+}
+
+/// A tile's alias as a bare source token, or a role-based name for tiles
+/// without one.
+fn tile_alias(tile: &Tile) -> String {
+    sanitize_path(&tile.alias).unwrap_or_else(|| {
+        format!(
+            "{}-{}",
+            source_tile_role_name(tile.role).replace('_', "-"),
+            short_id(&tile.id)
+        )
+    })
+}
+
+/// A `/`-separated name reduced to characters safe in paths and bare source
+/// tokens.
+fn sanitize_path(name: &str) -> Option<String> {
+    let parts: Vec<String> = name
+        .split('/')
+        .map(|part| {
+            part.trim()
+                .chars()
+                .map(|ch| {
+                    if ch.is_ascii_alphanumeric() || matches!(ch, '-' | '_' | '.') {
+                        ch
+                    } else {
+                        '-'
+                    }
+                })
+                .collect::<String>()
+        })
+        .filter(|part| !part.chars().all(|ch| ch == '.'))
+        .collect();
+    (!parts.is_empty()).then(|| parts.join("/"))
+}
+
+/// A lowercase `snake_case` identifier.
+fn source_identifier(name: &str) -> String {
+    let mut id = String::new();
+    for ch in name.trim().chars() {
+        if ch.is_ascii_alphanumeric() {
+            id.push(ch.to_ascii_lowercase());
+        } else if !id.is_empty() && !id.ends_with('_') {
+            id.push('_');
+        }
+    }
+    id.trim_end_matches('_').to_string()
+}
+
+fn file_stem(name: &str, fallback: &str) -> String {
+    let stem = source_identifier(name).replace('_', "-");
+    if stem.is_empty() {
+        fallback.to_string()
+    } else {
+        stem
+    }
+}
+
+fn short_id(id: &Uuid) -> String {
+    id.simple().to_string()[..8].to_string()
+}
+
+fn unique_name(base: &str, used: &mut HashSet<String>, separator: &str) -> String {
+    let mut name = base.to_string();
+    let mut index = 2;
+    while !used.insert(name.clone()) {
+        name = format!("{base}{separator}{index}");
+        index += 1;
+    }
+    name
+}
+
+/// A number for an `.els` field, without a fraction when it is whole.
+fn source_number(value: f32) -> String {
+    if value.fract() == 0.0 {
+        format!("{}", value as i64)
+    } else {
+        format!("{value}")
+    }
+}
+
+/// A number for a TOML float field.
+fn toml_float(value: f32) -> String {
+    if value.fract() == 0.0 {
+        format!("{value:.1}")
+    } else {
+        format!("{value}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn export_refuses_a_non_empty_directory_unless_forced() {
+        let game = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("../../starters/projects/Roguelike2D.eldiron");
+        let root = std::env::temp_dir().join(format!("eldiron-source-export-{}", Uuid::new_v4()));
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("eldiron.toml"), "[project]\nname = \"Mine\"\n").unwrap();
+
+        assert!(export_project(&game, &root, false).is_err());
+        assert_eq!(
+            fs::read_to_string(root.join("eldiron.toml")).unwrap(),
+            "[project]\nname = \"Mine\"\n"
+        );
+        export_project(&game, &root, true).expect("forced export overwrites");
+        assert_ne!(
+            fs::read_to_string(root.join("eldiron.toml")).unwrap(),
+            "[project]\nname = \"Mine\"\n"
+        );
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn export_round_trips_a_built_project() {
+        let game = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("../../starters/projects/Roguelike2D.eldiron");
+        let original: Project =
+            serde_json::from_str(&fs::read_to_string(&game).expect("Roguelike2D starter exists"))
+                .expect("Roguelike2D starter parses");
+        let root = std::env::temp_dir().join(format!("eldiron-source-export-{}", Uuid::new_v4()));
+
+        let report = export_project(&game, &root, false).expect("project exports");
+        assert!(report.files.contains(&PathBuf::from("eldiron.toml")));
+        assert!(report.files.contains(&PathBuf::from("regions/dungeon.els")));
+        assert!(
+            report
+                .warnings
+                .iter()
+                .all(|warning| !warning.contains("does not read back"))
+        );
+        let region = fs::read_to_string(root.join("regions/dungeon.els")).expect("region written");
+        let terrain = region.split("terrain").nth(1).expect("region has terrain");
+        assert_eq!(terrain.matches('@').count(), 1);
+        assert_eq!(terrain.matches('d').count(), 3);
+
+        let output = build_project(&root).expect("exported project builds");
+        let rebuilt: Project =
+            serde_json::from_str(&fs::read_to_string(&output).expect("rebuilt project written"))
+                .expect("rebuilt project parses");
+
+        for tile in original.tiles.keys() {
+            assert!(rebuilt.tiles.contains_key(tile), "tile {tile} kept its id");
+        }
+        for character in original.characters.values() {
+            let copy = rebuilt
+                .characters
+                .values()
+                .find(|copy| copy.name == character.name)
+                .expect("character rebuilt");
+            assert_eq!(
+                copy.data.parse::<toml::Table>().ok(),
+                character.data.parse::<toml::Table>().ok()
+            );
+            let code = |source: &str| {
+                source
+                    .lines()
+                    .map(str::trim)
+                    .filter(|line| !line.is_empty())
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            };
+            assert_eq!(code(&copy.source), code(&character.source));
+        }
+        for item in original.items.values() {
+            assert!(
+                rebuilt.items.values().any(|copy| copy.name == item.name
+                    && copy.data.parse::<toml::Table>().ok()
+                        == item.data.parse::<toml::Table>().ok()),
+                "item '{}' rebuilt",
+                item.name
+            );
+        }
+
+        let dungeon = &rebuilt.regions[0];
+        assert_eq!(dungeon.map.name, "Dungeon");
+        assert_eq!(
+            dungeon.characters.len(),
+            original.regions[0].characters.len()
+        );
+        assert_eq!(dungeon.items.len(), original.regions[0].items.len());
+        let screen = rebuilt.screens.values().next().expect("screen rebuilt");
+        let original_screen = original.screens.values().next().expect("screen exists");
+        assert_eq!(screen.map.sectors.len(), original_screen.map.sectors.len());
+        assert_eq!(screen.map.grid_size, original_screen.map.grid_size);
+        let offset = screen.map.bbox().min - original_screen.map.bbox().min;
+        assert!(
+            offset.x.abs() < 0.001 && offset.y.abs() < 0.001,
+            "widgets keep their screen positions"
+        );
+
+        let _ = fs::remove_dir_all(root);
+    }
+}
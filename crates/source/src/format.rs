@@ -0,0 +1,189 @@
+use super::*;
+
+const INDENT: &str = "  ";
+
+/// Pretty-print `.els` source canonically.
+///
+/// Lines are indented two spaces per open `{` or `[`, trailing whitespace is
+/// removed and runs of blank lines collapse to one. Blank lines after an
+/// opening or before a closing brace are dropped. `script` bodies keep their
+/// own relative indentation and `terrain` maps are re-indented to their field.
+/// Other triple-quoted strings are kept verbatim.
+pub fn format_source(src: &str) -> String {
+    let lines: Vec<&str> = src.lines().collect();
+    let mut out: Vec<String> = Vec::new();
+    let mut depth = 0usize;
+    let mut blank = false;
+    let mut index = 0;
+    while index < lines.len() {
+        let trimmed = lines[index].trim();
+        index += 1;
+        if trimmed.is_empty() {
+            blank = !out.is_empty();
+            continue;
+        }
+        let closers = trimmed
+            .chars()
+            .take_while(|ch| matches!(ch, '}' | ']'))
+            .count();
+        let opens_block = out.last().is_some_and(|last| {
+            let last = strip_line_comment(last).trim_end();
+            last.ends_with('{') || last.ends_with('[')
+        });
+        if blank && closers == 0 && !opens_block {
+            out.push(String::new());
+        }
+        blank = false;
+
+        let indent = INDENT.repeat(depth.saturating_sub(closers));
+        out.push(format!("{indent}{trimmed}"));
+        let (delta, open_string) = scan_line(trimmed);
+        depth = depth.saturating_add_signed(delta);
+
+        if trimmed.starts_with("script") && trimmed.ends_with('{') && delta == 1 {
+            let body_start = index;
+            let mut balance = 0isize;
+            while index < lines.len() {
+                let next = balance + scan_line(lines[index]).0;
+                if next < 0 {
+                    break;
+                }
+                balance = next;
+                index += 1;
+            }
+            push_shifted(&mut out, &lines[body_start..index], &INDENT.repeat(depth));
+        } else if open_string {
+            let body_start = index;
+            while index < lines.len() && !lines[index].contains("\"\"\"") {
+                index += 1;
+            }
+            let content = &lines[body_start..index];
+            let closing = lines.get(index).copied();
+            index += 1;
+            let terrain = trimmed.starts_with("terrain")
+                && trimmed.ends_with("\"\"\"")
+                && closing.is_some_and(|line| line.trim() == "\"\"\"");
+            if terrain {
+                for row in normalize_terrain_lines(&content.join("\n")) {
+                    out.push(format!("{indent}{row}"));
+                }
+                out.push(format!("{indent}\"\"\""));
+            } else {
+                out.extend(content.iter().map(|line| line.to_string()));
+                if let Some(closing) = closing {
+                    out.push(closing.trim_end().to_string());
+                    let tail = &closing[closing.find("\"\"\"").unwrap_or(0) + 3..];
+                    depth = depth.saturating_add_signed(scan_line(tail).0);
+                }
+            }
+        }
+    }
+    let mut formatted = out.join("\n");
+    formatted.push('\n');
+    formatted
+}
+
+/// Push embedded lines with their common indentation replaced by `indent`,
+/// keeping single blank lines between them.
+fn push_shifted(out: &mut Vec<String>, lines: &[&str], indent: &str) {
+    let common = lines
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.chars().take_while(|ch| ch.is_whitespace()).count())
+        .min()
+        .unwrap_or(0);
+    let mut blank = false;
+    let mut started = false;
+    for line in lines {
+        if line.trim().is_empty() {
+            blank = started;
+            continue;
+        }
+        if blank {
+            out.push(String::new());
+            blank = false;
+        }
+        started = true;
+        let line: String = line.trim_end().chars().skip(common).collect();
+        out.push(format!("{indent}{line}"));
+    }
+}
+
+/// The change in nesting depth over a line, and whether it leaves a
+/// triple-quoted string open. Strings and comments do not count.
+fn scan_line(line: &str) -> (isize, bool) {
+    let mut delta = 0isize;
+    let mut in_string = false;
+    let mut in_triple = false;
+    let mut escaped = false;
+    let mut index = 0;
+    while let Some(ch) = line[index..].chars().next() {
+        if line[index..].starts_with("\"\"\"") && !in_string {
+            in_triple = !in_triple;
+            index += 3;
+            continue;
+        }
+        index += ch.len_utf8();
+        if in_triple {
+            continue;
+        }
+        if in_string {
+            if escaped {
+                escaped = false;
+            } else if ch == '\\' {
+                escaped = true;
+            } else if ch == '"' {
+                in_string = false;
+            }
+            continue;
+        }
+        match ch {
+            '"' => in_string = true,
+            '{' | '[' => delta += 1,
+            '}' | ']' => delta -= 1,
+            '#' => break,
+            '/' if line[index..].starts_with('/') => break,
+            _ => {}
+        }
+    }
+    (delta, in_triple)
+}
+
+/// Format every `.els` file below `path`, outside of `build`, `dist` and
+/// `.git`, or `path` itself when it is a file. Returns the files whose
+/// formatting changed; with `write` unset the files are only compared.
+pub fn format_path(path: &Path, write: bool) -> Result<Vec<PathBuf>, String> {
+    let files = if path.is_dir() {
+        collect_files_recursive(path)?
+            .into_iter()
+            .filter(|file| file.extension().is_some_and(|extension| extension == "els"))
+            .filter(|file| {
+                !file
+                    .strip_prefix(path)
+                    .unwrap_or(file)
+                    .components()
+                    .any(|part| {
+                        matches!(part.as_os_str().to_str(), Some("build" | "dist" | ".git"))
+                    })
+            })
+            .collect()
+    } else {
+        vec![path.to_path_buf()]
+    };
+
+    let mut changed = Vec::new();
+    for file in files {
+        let text = fs::read_to_string(&file)
+            .map_err(|err| format!("failed to read {}: {err}", file.display()))?;
+        let formatted = format_source(&text);
+        if formatted == text {
+            continue;
+        }
+        if write {
+            fs::write(&file, formatted)
+                .map_err(|err| format!("failed to write {}: {err}", file.display()))?;
+        }
+        changed.push(file);
+    }
+    Ok(changed)
+}
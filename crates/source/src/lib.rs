@@ -1,5 +1,6 @@
 use procedural_recipes::{
-    MaterialRecipe, RecipeDocument, RecipeRenderer, RenderOptions, WrapMode, parse_document,
+    FileIncludes, MaterialRecipe, ParseOptions, RecipeDocument, RecipeRenderer, RenderOptions,
+    WrapMode, expand_recipe_source, parse_document,
 };
 #[cfg(test)]
 use rusterix::ParticleEmitter;
@@ -18,6 +19,14 @@ use std::{collections::BTreeMap, fs};
 use uuid::Uuid;
 use vek::{Vec2, Vec3};
 
+mod check;
+mod export;
+mod format;
+
+pub use check::{Severity, SourceDiagnostic, check_project};
+pub use export::{ExportReport, export_project};
+pub use format::{format_path, format_source};
+
 #[derive(Debug, Deserialize)]
 struct ProjectToml {
     #[serde(default)]
@@ -50,6 +59,8 @@ struct SourceSection {
     tiles: Vec<SourceTileAsset>,
     #[serde(default)]
     tile_animations: Vec<SourceTileAnimation>,
+    #[serde(default)]
+    recipe_variants: Vec<SourceRecipeVariant>,
 }
 
 impl Default for SourceSection {
@@ -59,14 +70,30 @@ impl Default for SourceSection {
             tile_dirs: Vec::new(),
             tiles: Vec::new(),
             tile_animations: Vec::new(),
+            recipe_variants: Vec::new(),
         }
     }
 }
 
+/// An extra procedural asset compiled from a recipe template with its
+/// `Param` values overridden, e.g. mossy bricks from `recipes/bricks.recipe`.
+#[derive(Debug, Clone, Deserialize)]
+struct SourceRecipeVariant {
+    alias: String,
+    recipe: String,
+    /// Param overrides. Strings are inserted as recipe source text, so a
+    /// quoted recipe string needs its own quotes, e.g. `name = '"Moss"'`.
+    #[serde(default)]
+    params: BTreeMap<String, toml::Value>,
+}
+
 #[derive(Debug, Clone, Deserialize)]
 struct SourceTileAsset {
     alias: String,
     path: String,
+    /// Optional fixed tile id, so data referring to the tile by id resolves.
+    #[serde(default)]
+    id: String,
     #[serde(default = "default_source_tile_role")]
     role: String,
     #[serde(default)]
@@ -85,10 +112,30 @@ struct SourceTileAsset {
     light_lift: f32,
 }
 
+/// Manifest written next to a packed atlas image, e.g. by `tilegraph atlas`.
+/// Every entry becomes its own tile, sliced out of the atlas image.
+#[derive(Debug, Deserialize)]
+struct SourceTileAtlas {
+    image: String,
+    #[serde(default)]
+    tiles: Vec<SourceTileAtlasEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SourceTileAtlasEntry {
+    alias: String,
+    x: usize,
+    y: usize,
+    width: usize,
+    height: usize,
+}
+
 #[derive(Debug, Clone, Deserialize)]
 struct SourceTileAnimation {
     alias: String,
     frames: Vec<String>,
+    #[serde(default)]
+    id: String,
     #[serde(default = "default_source_tile_role")]
     role: String,
     #[serde(default)]
@@ -226,6 +273,7 @@ struct SourceCharacter {
     name: String,
     glyph: Option<char>,
     data: String,
+    authoring: String,
     script: String,
 }
 
@@ -235,6 +283,7 @@ struct SourceItem {
     name: String,
     glyph: Option<char>,
     data: String,
+    authoring: String,
     script: String,
 }
 
@@ -311,22 +360,23 @@ struct SourceRegion {
     terrain: Vec<String>,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq)]
 struct SourceScreen {
     id: String,
     name: String,
+    grid_size: Option<f32>,
     widgets: Vec<SourceWidget>,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq)]
 struct SourceWidget {
     name: String,
     role: String,
     source: Option<String>,
-    x: i32,
-    y: i32,
-    width: i32,
-    height: i32,
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
     data: String,
 }
 
@@ -498,9 +548,22 @@ pub fn build_project(project_dir: &Path) -> Result<PathBuf, String> {
 }
 
 fn load_source_dir(project_dir: &Path, name: &str) -> Result<SourceDocument, String> {
+    let mut document = SourceDocument::default();
+    for path in source_dir_paths(project_dir, name)? {
+        let source_text = fs::read_to_string(&path)
+            .map_err(|err| format!("failed to read {}: {err}", path.display()))?;
+        let parsed = parse_source(&source_text)
+            .map_err(|err| format!("failed to parse {}: {err}", path.display()))?;
+        document.extend(parsed);
+    }
+    Ok(document)
+}
+
+/// The sorted `.els` files of one conventional source folder.
+fn source_dir_paths(project_dir: &Path, name: &str) -> Result<Vec<PathBuf>, String> {
     let dir = project_dir.join(name);
     if !dir.exists() {
-        return Ok(SourceDocument::default());
+        return Ok(Vec::new());
     }
     if !dir.is_dir() {
         return Err(format!("{} exists but is not a directory", dir.display()));
@@ -513,16 +576,7 @@ fn load_source_dir(project_dir: &Path, name: &str) -> Result<SourceDocument, Str
         .map_err(|err| format!("failed to read {}: {err}", dir.display()))?;
     paths.retain(|path| path.extension().is_some_and(|extension| extension == "els"));
     paths.sort();
-
-    let mut document = SourceDocument::default();
-    for path in paths {
-        let source_text = fs::read_to_string(&path)
-            .map_err(|err| format!("failed to read {}: {err}", path.display()))?;
-        let parsed = parse_source(&source_text)
-            .map_err(|err| format!("failed to parse {}: {err}", path.display()))?;
-        document.extend(parsed);
-    }
-    Ok(document)
+    Ok(paths)
 }
 
 fn load_project_directory_assets(
@@ -556,13 +610,18 @@ fn load_project_directory_assets(
         &tile_roots,
         &declared_tile_files,
     )?;
-    load_tile_image_dir(project, project_dir, "tiles")?;
-    load_tile_image_dir(project, project_dir, "images")?;
-    load_procedural_recipe_dir(project, project_dir, "recipes")?;
+    load_tile_image_dir(project, project_dir, "tiles", &declared_tile_files)?;
+    load_tile_image_dir(project, project_dir, "images", &declared_tile_files)?;
+    load_procedural_recipe_dir(project, project_dir, "recipes", &source.recipe_variants)?;
     for tile_dir in &source.tile_dirs {
         let tile_dir = tile_dir.trim();
         if !tile_dir.is_empty() {
-            load_tile_image_root(project, &project_dir.join(tile_dir), TileRole::Dungeon)?;
+            load_tile_image_root(
+                project,
+                &project_dir.join(tile_dir),
+                TileRole::Dungeon,
+                &declared_tile_files,
+            )?;
         }
     }
     for tile in &source.tiles {
@@ -627,15 +686,25 @@ fn load_tile_image_dir(
     project: &mut Project,
     project_dir: &Path,
     dir_name: &str,
+    excluded_files: &[PathBuf],
 ) -> Result<(), String> {
     let root = project_dir.join(dir_name);
     if !root.exists() {
         return Ok(());
     }
-    load_tile_image_root(project, &root, TileRole::ManMade)
+    load_tile_image_root(project, &root, TileRole::ManMade, excluded_files)
 }
 
-fn load_tile_image_root(project: &mut Project, root: &Path, role: TileRole) -> Result<(), String> {
+/// Load every image below `root` as a tile, except the files declared in
+/// `[[source.tiles]]` or `[[source.tile_animations]]`, which are loaded with
+/// their declared settings instead. Images with a `*.atlas.toml` or
+/// `*.atlas.json` manifest are split into the tiles the manifest lists.
+fn load_tile_image_root(
+    project: &mut Project,
+    root: &Path,
+    role: TileRole,
+    excluded_files: &[PathBuf],
+) -> Result<(), String> {
     if !root.exists() {
         return Err(format!("tile directory {} does not exist", root.display()));
     }
@@ -643,7 +712,18 @@ fn load_tile_image_root(project: &mut Project, root: &Path, role: TileRole) -> R
         return Err(format!("{} must be a directory", root.display()));
     }
 
-    for path in collect_files_recursive(root)? {
+    let files = collect_files_recursive(root)?;
+    let mut atlas_images = Vec::new();
+    for path in &files {
+        if let Some(image) = load_tile_atlas(project, root, path, role)? {
+            atlas_images.push(image);
+        }
+    }
+
+    for path in files {
+        if excluded_files.contains(&path) || atlas_images.contains(&path) {
+            continue;
+        }
         let Some(ext) = path.extension().and_then(|ext| ext.to_str()) else {
             continue;
         };
@@ -662,10 +742,81 @@ fn load_tile_image_root(project: &mut Project, root: &Path, role: TileRole) -> R
     Ok(())
 }
 
+/// Load the tiles of an atlas manifest and return the atlas image path, or
+/// `None` when `path` is not an atlas manifest.
+fn load_tile_atlas(
+    project: &mut Project,
+    root: &Path,
+    path: &Path,
+    role: TileRole,
+) -> Result<Option<PathBuf>, String> {
+    let Some(file_name) = path.file_name().and_then(|name| name.to_str()) else {
+        return Ok(None);
+    };
+    let is_toml = file_name.ends_with(".atlas.toml");
+    if !is_toml && !file_name.ends_with(".atlas.json") {
+        return Ok(None);
+    }
+    let text = fs::read_to_string(path)
+        .map_err(|err| format!("failed to read tile atlas {}: {err}", path.display()))?;
+    let manifest = if is_toml {
+        toml::from_str::<SourceTileAtlas>(&text).map_err(|err| err.to_string())
+    } else {
+        serde_json::from_str::<SourceTileAtlas>(&text).map_err(|err| err.to_string())
+    }
+    .map_err(|err| format!("failed to parse tile atlas {}: {err}", path.display()))?;
+
+    let dir = path.parent().unwrap_or(root);
+    let image_path = dir.join(&manifest.image);
+    let atlas = Texture::from_image_safe(image_path.as_path()).ok_or_else(|| {
+        format!(
+            "failed to decode tile atlas image {} referenced by {}",
+            image_path.display(),
+            path.display()
+        )
+    })?;
+    let prefix = dir
+        .strip_prefix(root)
+        .unwrap_or(Path::new(""))
+        .components()
+        .filter_map(|component| match component {
+            std::path::Component::Normal(part) => part.to_str(),
+            _ => None,
+        })
+        .map(|part| format!("{part}/"))
+        .collect::<String>();
+    for entry in &manifest.tiles {
+        if entry.width == 0
+            || entry.height == 0
+            || entry.x + entry.width > atlas.width
+            || entry.y + entry.height > atlas.height
+        {
+            return Err(format!(
+                "tile atlas {} entry '{}' lies outside its {}x{} image",
+                path.display(),
+                entry.alias,
+                atlas.width,
+                atlas.height
+            ));
+        }
+        let mut data = Vec::with_capacity(entry.width * entry.height * 4);
+        for row in entry.y..entry.y + entry.height {
+            let start = (row * atlas.width + entry.x) * 4;
+            data.extend_from_slice(&atlas.data[start..start + entry.width * 4]);
+        }
+        let mut tile = Tile::from_texture(Texture::new(data, entry.width, entry.height));
+        tile.role = role;
+        tile.alias = format!("{prefix}{}", entry.alias.trim());
+        project.tiles.insert(tile.id, tile);
+    }
+    Ok(Some(image_path))
+}
+
 fn load_procedural_recipe_dir(
     project: &mut Project,
     project_dir: &Path,
     dir_name: &str,
+    variants: &[SourceRecipeVariant],
 ) -> Result<(), String> {
     let root = project_dir.join(dir_name);
     if !root.exists() {
@@ -675,7 +826,38 @@ fn load_procedural_recipe_dir(
         return Err(format!("{} must be a directory", root.display()));
     }
 
+    let includes = FileIncludes::new(&root);
     let mut documents = Vec::new();
+    let mut load =
+        |path: PathBuf, file_alias: String, params: Option<&BTreeMap<String, String>>| {
+            let mut source = fs::read_to_string(&path)
+                .map_err(|error| format!("failed to read recipe {}: {error}", path.display()))?;
+            // Recipes with includes or overridden params are stored expanded, so
+            // the compiled project only holds self-contained recipe sources.
+            let document = match (params, parse_document(&source)) {
+                (None, Ok(document)) => document,
+                _ => {
+                    let source_name = path.display().to_string();
+                    let options = ParseOptions {
+                        source_name: Some(&source_name),
+                        params,
+                        includes: Some(&includes),
+                    };
+                    source = expand_recipe_source(&source, &options)
+                        .map_err(|error| error.to_string())?;
+                    parse_document(&source).map_err(|error| {
+                        error
+                            .with_source_name(path.display().to_string())
+                            .to_string()
+                    })?
+                }
+            };
+            let asset = ProceduralRecipeAsset::new(file_alias.to_ascii_lowercase(), source.clone());
+            let asset_id = asset.id;
+            project.procedural_recipes.insert(asset_id, asset);
+            documents.push((path, file_alias, source, document, asset_id));
+            Ok::<_, String>(())
+        };
     for path in collect_files_recursive(&root)? {
         let Some(extension) = path.extension().and_then(|extension| extension.to_str()) else {
             continue;
@@ -683,27 +865,44 @@ fn load_procedural_recipe_dir(
         if !extension.eq_ignore_ascii_case("recipe") {
             continue;
         }
-
-        let source = fs::read_to_string(&path)
-            .map_err(|error| format!("failed to read recipe {}: {error}", path.display()))?;
-        let document = parse_document(&source).map_err(|error| {
-            error
-                .with_source_name(path.display().to_string())
-                .to_string()
-        })?;
-        let alias = asset_name_from_path(&root, &path).to_ascii_lowercase();
-        let asset = ProceduralRecipeAsset::new(alias, source.clone());
-        let asset_id = asset.id;
-        project.procedural_recipes.insert(asset_id, asset);
-        documents.push((path, source, document, asset_id));
+        // `_name.recipe` files are partials that only exist to be included.
+        if path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.starts_with('_'))
+        {
+            continue;
+        }
+        let alias = asset_name_from_path(&root, &path);
+        load(path, alias, None)?;
+    }
+    for variant in variants {
+        let params = variant
+            .params
+            .iter()
+            .map(|(name, value)| {
+                recipe_param_text(value)
+                    .map(|text| (name.clone(), text))
+                    .ok_or_else(|| {
+                        format!(
+                            "recipe variant '{}' param '{name}' must be a string, number or boolean",
+                            variant.alias
+                        )
+                    })
+            })
+            .collect::<Result<BTreeMap<_, _>, _>>()?;
+        load(
+            project_dir.join(&variant.recipe),
+            variant.alias.trim().to_string(),
+            Some(&params),
+        )?;
     }
 
     let mut materials = BTreeMap::<String, MaterialRecipe>::new();
-    for (path, source, document, _) in &documents {
+    for (_, file_alias, source, document, _) in &documents {
         let RecipeDocument::Materials(document) = document else {
             continue;
         };
-        let file_alias = asset_name_from_path(&root, path);
         if document.materials.len() == 1 {
             let alias = file_alias.to_ascii_lowercase();
             if materials
@@ -723,11 +922,10 @@ fn load_procedural_recipe_dir(
         }
     }
 
-    for (path, source, document, _) in &documents {
+    for (_, file_alias, source, document, _) in &documents {
         let RecipeDocument::Sdfs(document) = document else {
             continue;
         };
-        let file_alias = asset_name_from_path(&root, path);
         if document.recipes.len() == 1 {
             let alias = file_alias.to_ascii_lowercase();
             if project
@@ -756,7 +954,7 @@ fn load_procedural_recipe_dir(
             root.display()
         )
     })?;
-    for (path, _, document, recipe_asset_id) in documents {
+    for (path, file_alias, _, document, recipe_asset_id) in documents {
         let RecipeDocument::Tile(recipe) = document else {
             continue;
         };
@@ -911,7 +1109,7 @@ fn load_procedural_recipe_dir(
         }
         let mut tile = Tile::from_textures(textures);
         tile.role = TileRole::ManMade;
-        tile.alias = asset_name_from_path(&root, &path);
+        tile.alias = file_alias;
         tile.apply_procedural_recipe_metadata(&recipe);
         tile.procedural.coverage = [rendered.grid_width, rendered.grid_height];
         if let Some(alias) = base_material_alias {
@@ -946,6 +1144,9 @@ fn load_source_tile_asset(
         )
     })?;
     let mut tile = Tile::from_texture(texture);
+    if let Some(id) = parse_source_tile_id(alias, &source_tile.id)? {
+        tile.id = id;
+    }
     tile.alias = alias.to_string();
     tile.role = parse_source_tile_role(&source_tile.role)?;
     tile.blocking = source_tile.blocking;
@@ -991,6 +1192,9 @@ fn load_source_tile_animation(
     }
 
     let mut tile = Tile::from_textures(textures);
+    if let Some(id) = parse_source_tile_id(alias, &animation.id)? {
+        tile.id = id;
+    }
     tile.alias = alias.to_string();
     tile.role = parse_source_tile_role(&animation.role)?;
     tile.blocking = animation.blocking;
@@ -1006,6 +1210,16 @@ fn load_source_tile_animation(
     Ok(())
 }
 
+fn parse_source_tile_id(alias: &str, id: &str) -> Result<Option<Uuid>, String> {
+    let id = id.trim();
+    if id.is_empty() {
+        return Ok(None);
+    }
+    Uuid::parse_str(id)
+        .map(Some)
+        .map_err(|_| format!("source tile '{}' has an invalid id '{}'", alias, id))
+}
+
 fn source_tile_light_emitter(
     color: &str,
     intensity: f32,
@@ -1104,6 +1318,16 @@ fn read_bytes(path: &Path) -> Result<Vec<u8>, String> {
     fs::read(path).map_err(|err| format!("failed to read {}: {err}", path.display()))
 }
 
+fn recipe_param_text(value: &toml::Value) -> Option<String> {
+    match value {
+        toml::Value::String(value) => Some(value.clone()),
+        toml::Value::Integer(value) => Some(value.to_string()),
+        toml::Value::Float(value) => Some(value.to_string()),
+        toml::Value::Boolean(value) => Some(value.to_string()),
+        _ => None,
+    }
+}
+
 fn asset_name_from_path(root: &Path, path: &Path) -> String {
     let relative = path.strip_prefix(root).unwrap_or(path);
     let without_ext = relative.with_extension("");
@@ -1559,37 +1783,7 @@ fn compile_project_inner(
     project_dir: Option<&Path>,
     passthrough_config: &str,
 ) -> Result<Project, String> {
-    let mut project = Project::new();
-    project.name = if config.project.name.trim().is_empty() {
-        "Eldiron Source Project".to_string()
-    } else {
-        config.project.name.trim().to_string()
-    };
-    project.regions.clear();
-    project.characters.clear();
-    project.items.clear();
-    project.screens.clear();
-    project.config = project_config(
-        &config.game,
-        &config.viewport,
-        &config.terminal,
-        None,
-        passthrough_config,
-    );
-    project.migrate_default_ruleset();
-    project.authoring = "[startup]\nshow = \"room\"\n".to_string();
-    project.sync_ruleset_items()?;
-    if let Some(project_dir) = project_dir {
-        load_project_directory_assets(&mut project, project_dir, &config.source)?;
-    }
-    let tile_lookup = SourceTileLookup::from_project(&project);
-    project.config = project_config(
-        &config.game,
-        &config.viewport,
-        &config.terminal,
-        viewport_cursor_id(&config.viewport, &tile_lookup),
-        passthrough_config,
-    );
+    let (mut project, tile_lookup) = source_base_project(config, project_dir, passthrough_config)?;
     let global_tile_symbols = source.tile_symbols.clone();
     let source_niches = source.niches.clone();
 
@@ -1612,7 +1806,7 @@ fn compile_project_inner(
             };
             ensure_source_player_camera(&source_character.data, camera)
         };
-        character.authoring = character_authoring(source_character.glyph);
+        character.authoring = source_authoring(&source_character.authoring, source_character.glyph);
         character.module = module_shell(
             "CharacterTemplate",
             &character.name,
@@ -1636,7 +1830,7 @@ fn compile_project_inner(
         } else {
             source_item.data
         };
-        item.authoring = item_authoring(source_item.glyph);
+        item.authoring = source_authoring(&source_item.authoring, source_item.glyph);
         item.module = module_shell("ItemTemplate", &item.name, false);
         let template_id = item.id;
         item_templates.insert(source_item.id.clone(), template_id);
@@ -1707,6 +1901,47 @@ fn compile_project_inner(
     Ok(project)
 }
 
+/// The project every source build starts from: configuration, the default
+/// ruleset and the project directory assets, before any source is compiled.
+fn source_base_project(
+    config: &ProjectToml,
+    project_dir: Option<&Path>,
+    passthrough_config: &str,
+) -> Result<(Project, SourceTileLookup), String> {
+    let mut project = Project::new();
+    project.name = if config.project.name.trim().is_empty() {
+        "Eldiron Source Project".to_string()
+    } else {
+        config.project.name.trim().to_string()
+    };
+    project.regions.clear();
+    project.characters.clear();
+    project.items.clear();
+    project.screens.clear();
+    project.config = project_config(
+        &config.game,
+        &config.viewport,
+        &config.terminal,
+        None,
+        passthrough_config,
+    )?;
+    project.migrate_default_ruleset();
+    project.authoring = "[startup]\nshow = \"room\"\n".to_string();
+    project.sync_ruleset_items()?;
+    if let Some(project_dir) = project_dir {
+        load_project_directory_assets(&mut project, project_dir, &config.source)?;
+    }
+    let tile_lookup = SourceTileLookup::from_project(&project);
+    project.config = project_config(
+        &config.game,
+        &config.viewport,
+        &config.terminal,
+        viewport_cursor_id(&config.viewport, &tile_lookup),
+        passthrough_config,
+    )?;
+    Ok((project, tile_lookup))
+}
+
 fn compile_region(
     source_region: SourceRegion,
     character_templates: &IndexMap<String, Uuid>,
@@ -2134,6 +2369,18 @@ fn validate_source_niches(
     source_region: &SourceRegion,
     source_tiles: &ResolvedSourceTiles,
 ) -> Result<(), String> {
+    match find_source_niche_violation(source_region, source_tiles) {
+        Some((_, _, err)) => Err(err),
+        None => Ok(()),
+    }
+}
+
+/// The first terrain cell whose Niche does not fit under an adjacent ceiling,
+/// as `(x, y, message)`.
+fn find_source_niche_violation(
+    source_region: &SourceRegion,
+    source_tiles: &ResolvedSourceTiles,
+) -> Option<(usize, usize, String)> {
     for (y, row) in source_region.terrain.iter().enumerate() {
         for (x, glyph) in row.chars().enumerate() {
             let Some(niche) = source_tiles
@@ -2155,15 +2402,19 @@ fn validate_source_niches(
                 };
                 let top = niche.position.y + niche.size.y;
                 if top > height {
-                    return Err(format!(
-                        "Region '{}' places Niche '{}' at ({x}, {y}), but position.y + size.y ({top}) exceeds the adjacent ceiling height ({height})",
-                        source_region.id, niche.id
+                    return Some((
+                        x,
+                        y,
+                        format!(
+                            "Region '{}' places Niche '{}' at ({x}, {y}), but position.y + size.y ({top}) exceeds the adjacent ceiling height ({height})",
+                            source_region.id, niche.id
+                        ),
                     ));
                 }
             }
         }
     }
-    Ok(())
+    None
 }
 
 #[derive(Clone, Copy)]
@@ -3313,7 +3564,9 @@ fn build_screen_map(
     let mut map = Map::default();
     map.name = source_screen.id.clone();
     map.camera = MapCamera::TwoD;
-    map.grid_size = viewport.grid_size.max(1) as f32;
+    map.grid_size = source_screen
+        .grid_size
+        .unwrap_or(viewport.grid_size.max(1) as f32);
     map.vertices.clear();
     map.linedefs.clear();
     map.sectors.clear();
@@ -3336,21 +3589,22 @@ fn add_screen_widget_sector(
     start_y: f32,
     tile_lookup: &SourceTileLookup,
 ) -> Result<(), String> {
-    if widget.width <= 0 || widget.height <= 0 {
+    if widget.width <= 0.0 || widget.height <= 0.0 {
         return Err(format!(
             "Widget '{}' must have positive width and height",
             widget.name
         ));
     }
 
-    let x0 = start_x + widget.x as f32;
-    let y0 = start_y + widget.y as f32;
-    let x1 = x0 + widget.width as f32;
-    let y1 = y0 + widget.height as f32;
-    let v0 = map.add_vertex_at(x0, y0);
-    let v1 = map.add_vertex_at(x1, y0);
-    let v2 = map.add_vertex_at(x1, y1);
-    let v3 = map.add_vertex_at(x0, y1);
+    let x0 = start_x + widget.x;
+    let y0 = start_y + widget.y;
+    let x1 = x0 + widget.width;
+    let y1 = y0 + widget.height;
+    // Unsnapped, so widgets keep fractional positions.
+    let v0 = map.add_vertex_at_3d(x0, y0, 0.0, false);
+    let v1 = map.add_vertex_at_3d(x1, y0, 0.0, false);
+    let v2 = map.add_vertex_at_3d(x1, y1, 0.0, false);
+    let v3 = map.add_vertex_at_3d(x0, y1, 0.0, false);
     map.possible_polygon.clear();
     let linedefs = vec![
         map.create_linedef_manual(v0, v1),
@@ -3417,19 +3671,25 @@ fn parse_source(src: &str) -> Result<SourceDocument, String> {
         .tile_symbols
         .extend(parse_top_level_tile_symbol_blocks(src)?);
     for block in find_named_blocks(src, "Niche")? {
-        let niche = parse_niche(&block)?;
+        let niche = parse_niche(&block).map_err(|(_, err)| err)?;
         if document.niches.insert(niche.id.clone(), niche).is_some() {
             return Err(format!("Niche '{}' is defined more than once", block.name));
         }
     }
     for block in find_named_blocks(src, "Character")? {
-        document.characters.push(parse_character(&block)?);
+        document
+            .characters
+            .push(parse_character(&block).map_err(|(_, err)| err)?);
     }
     for block in find_named_blocks(src, "Item")? {
-        document.items.push(parse_item(&block)?);
+        document
+            .items
+            .push(parse_item(&block).map_err(|(_, err)| err)?);
     }
     for block in find_named_blocks(src, "Region")? {
-        document.regions.push(parse_region(&block)?);
+        document
+            .regions
+            .push(parse_region(&block).map_err(|(_, err)| err)?);
     }
     for block in find_named_blocks(src, "Screen")? {
         document.screens.push(parse_screen(&block)?);
@@ -3437,41 +3697,50 @@ fn parse_source(src: &str) -> Result<SourceDocument, String> {
     Ok(document)
 }
 
-fn parse_niche(block: &NamedBlock) -> Result<SourceNiche, String> {
+/// Parse a Niche block. Errors carry the byte offset of the offending field
+/// in the parsed source.
+fn parse_niche(block: &NamedBlock) -> Result<SourceNiche, (usize, String)> {
     let context = format!("Niche '{}'", block.name);
+    let at = |field: &str| {
+        let offset = block.field_offset(field);
+        move |err: String| (offset, err)
+    };
     let tile = bare_field(&block.body, "tile")
         .map(|value| strip_line_comment(&value).trim().to_string())
         .filter(|value| !value.is_empty())
-        .ok_or_else(|| format!("{context} is missing tile"))?;
-    let position = parse_source_f2_field(&block.body, "position", &context)?;
-    let size = parse_source_f2_field(&block.body, "size", &context)?;
-    let depth = parse_source_required_float_field(&block.body, "depth", &context)?;
-    let sill = parse_source_required_float_field(&block.body, "sill", &context)?;
+        .ok_or_else(|| (block.offset, format!("{context} is missing tile")))?;
+    let position =
+        parse_source_f2_field(&block.body, "position", &context).map_err(at("position"))?;
+    let size = parse_source_f2_field(&block.body, "size", &context).map_err(at("size"))?;
+    let depth =
+        parse_source_required_float_field(&block.body, "depth", &context).map_err(at("depth"))?;
+    let sill =
+        parse_source_required_float_field(&block.body, "sill", &context).map_err(at("sill"))?;
 
     if position.x < 0.0 || position.y < 0.0 {
-        return Err(format!(
+        return Err(at("position")(format!(
             "{context} position must use non-negative wall-local coordinates"
-        ));
+        )));
     }
     if size.x <= 0.0 || size.y <= 0.0 {
-        return Err(format!(
+        return Err(at("size")(format!(
             "{context} size components must be greater than zero"
-        ));
+        )));
     }
     if position.x + size.x > 1.0 {
-        return Err(format!(
+        return Err(at("position")(format!(
             "{context} position.x + size.x must fit within the 1.0-unit wall width"
-        ));
+        )));
     }
     if depth <= 0.0 || depth >= 1.0 {
-        return Err(format!(
+        return Err(at("depth")(format!(
             "{context} depth must be greater than 0.0 and less than 1.0"
-        ));
+        )));
     }
     if sill <= 0.0 || sill > size.y {
-        return Err(format!(
+        return Err(at("sill")(format!(
             "{context} sill must be greater than 0.0 and no larger than size.y"
-        ));
+        )));
     }
 
     Ok(SourceNiche {
@@ -3521,13 +3790,17 @@ fn parse_source_required_float_field(src: &str, key: &str, context: &str) -> Res
     Ok(value)
 }
 
-fn parse_character(block: &NamedBlock) -> Result<SourceCharacter, String> {
+fn parse_character(block: &NamedBlock) -> Result<SourceCharacter, (usize, String)> {
     let name = string_field(&block.body, "name").unwrap_or_else(|| title_case_id(&block.name));
     let glyph = string_field(&block.body, "glyph").and_then(|value| value.chars().next());
     let data = brace_block(&block.body, "data")
         .map(str::trim)
         .unwrap_or_default()
         .to_string();
+    let authoring = brace_block(&block.body, "authoring")
+        .map(str::trim)
+        .unwrap_or_default()
+        .to_string();
     let script = brace_block(&block.body, "script")
         .map(str::trim)
         .unwrap_or_default()
@@ -3537,17 +3810,22 @@ fn parse_character(block: &NamedBlock) -> Result<SourceCharacter, String> {
         name,
         glyph,
         data,
+        authoring,
         script,
     })
 }
 
-fn parse_item(block: &NamedBlock) -> Result<SourceItem, String> {
+fn parse_item(block: &NamedBlock) -> Result<SourceItem, (usize, String)> {
     let name = string_field(&block.body, "name").unwrap_or_else(|| title_case_id(&block.name));
     let glyph = string_field(&block.body, "glyph").and_then(|value| value.chars().next());
     let data = brace_block(&block.body, "data")
         .map(str::trim)
         .unwrap_or_default()
         .to_string();
+    let authoring = brace_block(&block.body, "authoring")
+        .map(str::trim)
+        .unwrap_or_default()
+        .to_string();
     let script = brace_block(&block.body, "script")
         .map(str::trim)
         .unwrap_or_default()
@@ -3557,24 +3835,33 @@ fn parse_item(block: &NamedBlock) -> Result<SourceItem, String> {
         name,
         glyph,
         data,
+        authoring,
         script,
     })
 }
 
-fn parse_region(block: &NamedBlock) -> Result<SourceRegion, String> {
+/// Parse a Region block. Errors carry the byte offset of the offending field
+/// in the parsed source.
+fn parse_region(block: &NamedBlock) -> Result<SourceRegion, (usize, String)> {
     let name = string_field(&block.body, "name").unwrap_or_else(|| title_case_id(&block.name));
     let default = bare_field(&block.body, "default").unwrap_or_else(|| "wall.stone".to_string());
     let floor = bare_field(&block.body, "floor").unwrap_or_else(|| "floor".to_string());
     let ceiling = bare_field(&block.body, "ceiling").unwrap_or_else(|| "ceiling".to_string());
     let ceiling_height = match bare_field(&block.body, "ceiling_height") {
         Some(value) => {
-            parse_source_ceiling_height(&format!("Region '{}' ceiling_height", block.name), &value)?
+            parse_source_ceiling_height(&format!("Region '{}' ceiling_height", block.name), &value)
+                .map_err(|err| (block.field_offset("ceiling_height"), err))?
         }
         None => DEFAULT_SOURCE_CEILING_HEIGHT,
     };
-    let tile_symbols = parse_tile_symbol_blocks(&block.body)?;
-    let terrain = triple_string_field(&block.body, "terrain")
-        .ok_or_else(|| format!("Region '{}' is missing terrain \"\"\"...\"\"\"", block.name))?;
+    let tile_symbols =
+        parse_tile_symbol_blocks(&block.body).map_err(|err| (block.field_offset("tiles"), err))?;
+    let terrain = triple_string_field(&block.body, "terrain").ok_or_else(|| {
+        (
+            block.offset,
+            format!("Region '{}' is missing terrain \"\"\"...\"\"\"", block.name),
+        )
+    })?;
     let lines = normalize_terrain_lines(&terrain);
     Ok(SourceRegion {
         id: block.name.clone(),
@@ -3909,16 +4196,37 @@ fn strip_line_comment(line: &str) -> &str {
 
 fn parse_screen(block: &NamedBlock) -> Result<SourceScreen, String> {
     let name = string_field(&block.body, "name").unwrap_or_else(|| title_case_id(&block.name));
+    let mut widget_blocks = find_named_blocks(&block.body, "widget")?;
+    widget_blocks.extend(find_named_blocks(&block.body, "Widget")?);
+    // Screen fields precede the widgets, whose data may reuse the same keys.
+    let header_end = widget_blocks
+        .iter()
+        .map(|widget| widget.offset)
+        .min()
+        .unwrap_or(block.body.len());
+    let grid_size = match bare_field(&block.body[..header_end], "grid_size") {
+        Some(value) => Some(
+            value
+                .parse::<f32>()
+                .ok()
+                .filter(|grid_size| *grid_size > 0.0)
+                .ok_or_else(|| {
+                    format!(
+                        "Screen '{}' grid_size must be a positive number, but found '{}'",
+                        block.name, value
+                    )
+                })?,
+        ),
+        None => None,
+    };
     let mut widgets = Vec::new();
-    for widget in find_named_blocks(&block.body, "widget")? {
-        widgets.push(parse_widget(&widget)?);
-    }
-    for widget in find_named_blocks(&block.body, "Widget")? {
-        widgets.push(parse_widget(&widget)?);
+    for widget in &widget_blocks {
+        widgets.push(parse_widget(widget)?);
     }
     Ok(SourceScreen {
         id: block.name.clone(),
         name,
+        grid_size,
         widgets,
     })
 }
@@ -3931,13 +4239,13 @@ fn parse_widget(block: &NamedBlock) -> Result<SourceWidget, String> {
         .or_else(|| bare_field(&block.body, "tile"))
         .map(|value| value.trim().to_string())
         .filter(|value| !value.is_empty());
-    let x = int_field(&block.body, "x").unwrap_or(0);
-    let y = int_field(&block.body, "y").unwrap_or(0);
-    let width = int_field(&block.body, "width")
-        .or_else(|| int_field(&block.body, "w"))
+    let x = float_field(&block.body, "x").unwrap_or(0.0);
+    let y = float_field(&block.body, "y").unwrap_or(0.0);
+    let width = float_field(&block.body, "width")
+        .or_else(|| float_field(&block.body, "w"))
         .ok_or_else(|| format!("widget '{}' is missing width", block.name))?;
-    let height = int_field(&block.body, "height")
-        .or_else(|| int_field(&block.body, "h"))
+    let height = float_field(&block.body, "height")
+        .or_else(|| float_field(&block.body, "h"))
         .ok_or_else(|| format!("widget '{}' is missing height", block.name))?;
     let data = brace_block(&block.body, "data")
         .map(str::trim)
@@ -3959,9 +4267,27 @@ fn parse_widget(block: &NamedBlock) -> Result<SourceWidget, String> {
 struct NamedBlock {
     name: String,
     body: String,
+    /// Byte offset of the block keyword in the parsed source.
+    offset: usize,
+    /// Byte offset of `body` in the parsed source.
+    body_offset: usize,
+}
+
+impl NamedBlock {
+    /// The byte offset of a field in the parsed source, falling back to the
+    /// block keyword.
+    fn field_offset(&self, field: &str) -> usize {
+        find_field_pos(&self.body, field).map_or(self.offset, |pos| self.body_offset + pos)
+    }
 }
 
 fn find_named_blocks(src: &str, keyword: &str) -> Result<Vec<NamedBlock>, String> {
+    locate_named_blocks(src, keyword).map_err(|(_, err)| err)
+}
+
+/// Like [`find_named_blocks`], but errors carry the byte offset of the
+/// offending block.
+fn locate_named_blocks(src: &str, keyword: &str) -> Result<Vec<NamedBlock>, (usize, String)> {
     let mut blocks = Vec::new();
     let mut index = 0;
     while let Some(relative) = src[index..].find(keyword) {
@@ -3972,17 +4298,27 @@ fn find_named_blocks(src: &str, keyword: &str) -> Result<Vec<NamedBlock>, String
         }
         let mut cursor = start + keyword.len();
         cursor = skip_ws(src, cursor);
-        let (name, after_name) = parse_quoted(src, cursor)
-            .ok_or_else(|| format!("{keyword} block at byte {start} is missing a quoted name"))?;
+        let (name, after_name) = parse_quoted(src, cursor).ok_or_else(|| {
+            (
+                start,
+                format!("{keyword} block at byte {start} is missing a quoted name"),
+            )
+        })?;
         cursor = skip_ws(src, after_name);
-        if src[cursor..].chars().next() != Some('{') {
-            return Err(format!("{keyword} \"{name}\" is missing '{{'"));
+        if !src[cursor..].starts_with('{') {
+            return Err((start, format!("{keyword} \"{name}\" is missing '{{'")));
         }
-        let end = find_matching_brace(src, cursor)
-            .ok_or_else(|| format!("{keyword} \"{name}\" has an unterminated body"))?;
+        let end = find_matching_brace(src, cursor).ok_or_else(|| {
+            (
+                start,
+                format!("{keyword} \"{name}\" has an unterminated body"),
+            )
+        })?;
         blocks.push(NamedBlock {
             name,
             body: src[cursor + 1..end].to_string(),
+            offset: start,
+            body_offset: cursor + 1,
         });
         index = end + 1;
     }
@@ -4096,7 +4432,7 @@ fn bare_field(src: &str, key: &str) -> Option<String> {
     Some(rest[..end].trim().trim_matches('"').to_string())
 }
 
-fn int_field(src: &str, key: &str) -> Option<i32> {
+fn float_field(src: &str, key: &str) -> Option<f32> {
     bare_field(src, key)?.parse().ok()
 }
 
@@ -4124,13 +4460,17 @@ fn triple_string_field(src: &str, key: &str) -> Option<String> {
 }
 
 fn brace_block<'a>(src: &'a str, key: &str) -> Option<&'a str> {
-    let pos = find_field_pos(src, key)?;
-    let cursor = skip_ws(src, pos + key.len());
-    if src[cursor..].chars().next()? != '{' {
-        return None;
+    let mut search_from = 0;
+    while let Some(relative) = find_field_pos(&src[search_from..], key) {
+        let pos = search_from + relative;
+        let cursor = skip_ws(src, pos + key.len());
+        if src[cursor..].starts_with('{') {
+            let end = find_matching_brace(src, cursor)?;
+            return Some(&src[cursor + 1..end]);
+        }
+        search_from = pos + key.len();
     }
-    let end = find_matching_brace(src, cursor)?;
-    Some(&src[cursor + 1..end])
+    None
 }
 
 fn find_field_pos(src: &str, key: &str) -> Option<usize> {
@@ -4377,12 +4717,25 @@ fn project_config(
     terminal: &TerminalSection,
     cursor_id: Option<Uuid>,
     passthrough_config: &str,
-) -> String {
+) -> Result<String, String> {
     let cursor_line = cursor_id
         .map(|id| format!("cursor_id = \"{}\"\n", id))
         .unwrap_or_default();
+    let mut passthrough = passthrough_config
+        .parse::<toml::Table>()
+        .map_err(|err| format!("invalid runtime config in eldiron.toml: {err}"))?;
+    let mut extra_keys = |section: &str| {
+        passthrough
+            .remove(section)
+            .and_then(|value| value.as_table().cloned())
+            .and_then(|table| toml::to_string(&table).ok())
+            .unwrap_or_default()
+    };
+    let game_extra = extra_keys("game");
+    let viewport_extra = extra_keys("viewport");
+    let terminal_extra = extra_keys("terminal");
     let mut config = format!(
-        "[game]\nstart_region = \"{}\"\nstart_screen = \"{}\"\nplay_screen = \"{}\"\nclient_mode = \"{}\"\nterminal_mode = \"{}\"\nsimulation_mode = \"{}\"\ngame_tick_ms = {}\nturn_timeout_ms = {}\nmovement_units_per_sec = {}\nturn_speed_deg_per_sec = {}\nauto_create_player = {}\ncollision_mode = \"{}\"\npersistent_intents = {}\n\n[viewport]\nwidth = {}\nheight = {}\ngrid_size = {}\nunit = \"{}\"\nresize = \"{}\"\n{}\n[terminal]\ntext_updates = {}\n",
+        "[game]\nstart_region = \"{}\"\nstart_screen = \"{}\"\nplay_screen = \"{}\"\nclient_mode = \"{}\"\nterminal_mode = \"{}\"\nsimulation_mode = \"{}\"\ngame_tick_ms = {}\nturn_timeout_ms = {}\nmovement_units_per_sec = {}\nturn_speed_deg_per_sec = {}\nauto_create_player = {}\ncollision_mode = \"{}\"\npersistent_intents = {}\n{}\n[viewport]\nwidth = {}\nheight = {}\ngrid_size = {}\nunit = \"{}\"\nresize = \"{}\"\n{}{}\n[terminal]\ntext_updates = {}\n{}",
         escape_toml_string(&game.start_region),
         escape_toml_string(&game.start_screen),
         escape_toml_string(&game.play_screen),
@@ -4396,31 +4749,81 @@ fn project_config(
         game.auto_create_player,
         escape_toml_string(&game.collision_mode),
         game.persistent_intents,
+        game_extra,
         viewport.width,
         viewport.height,
         viewport.grid_size,
         escape_toml_string(&viewport.unit),
         escape_toml_string(&viewport.resize),
         cursor_line,
-        terminal.text_updates
+        viewport_extra,
+        terminal.text_updates,
+        terminal_extra
     );
+    let passthrough_config = toml::to_string(&passthrough).unwrap_or_default();
     let passthrough_config = passthrough_config.trim();
     if !passthrough_config.is_empty() {
         config.push('\n');
         config.push_str(passthrough_config);
         config.push('\n');
     }
-    config
+    Ok(config)
 }
 
+/// The parts of `eldiron.toml` the source compiler does not own, passed on
+/// to the runtime config. `[game]`, `[viewport]` and `[terminal]` keep the
+/// plain keys the compiler does not generate itself, e.g. `target_fps`.
 fn project_config_passthrough(config_text: &str) -> Result<String, String> {
-    const SOURCE_OWNED_SECTIONS: &[&str] =
-        &["project", "source", "game", "viewport", "terminal", "build"];
+    const SOURCE_OWNED_SECTIONS: &[&str] = &["project", "source", "build"];
+    const SOURCE_OWNED_KEYS: &[(&str, &[&str])] = &[
+        (
+            "game",
+            &[
+                "start_region",
+                "start_screen",
+                "play_screen",
+                "client_mode",
+                "terminal_mode",
+                "simulation_mode",
+                "game_tick_ms",
+                "turn_timeout_ms",
+                "movement_units_per_sec",
+                "turn_speed_deg_per_sec",
+                "collision_mode",
+                "persistent_intents",
+                "auto_create_player",
+                "player",
+            ],
+        ),
+        (
+            "viewport",
+            &[
+                "width",
+                "height",
+                "grid_size",
+                "unit",
+                "resize",
+                "cursor",
+                "cursor_id",
+            ],
+        ),
+        ("terminal", &["text_updates"]),
+    ];
 
     let mut config: toml::Table = toml::from_str(config_text).map_err(|err| err.to_string())?;
     for section in SOURCE_OWNED_SECTIONS {
         config.remove(*section);
     }
+    for (section, owned) in SOURCE_OWNED_KEYS {
+        let Some(toml::Value::Table(table)) = config.get_mut(*section) else {
+            config.remove(*section);
+            continue;
+        };
+        table.retain(|key, value| !owned.contains(&key) && !value.is_table());
+        if table.is_empty() {
+            config.remove(*section);
+        }
+    }
     if config.is_empty() {
         Ok(String::new())
     } else {
@@ -4499,6 +4902,23 @@ fn character_authoring(glyph: Option<char>) -> String {
     }
 }
 
+/// Authoring for a template: the explicit `authoring` block, with the glyph
+/// added as `[terminal] glyph` unless the block configures the terminal.
+fn source_authoring(authoring: &str, glyph: Option<char>) -> String {
+    let glyph_authoring = character_authoring(glyph);
+    if authoring.trim().is_empty() {
+        return glyph_authoring;
+    }
+    let configures_terminal = authoring
+        .parse::<toml::Table>()
+        .is_ok_and(|table| table.contains_key("terminal"));
+    if configures_terminal || glyph_authoring.is_empty() {
+        format!("{}\n", authoring.trim_end())
+    } else {
+        format!("{}\n\n{}", authoring.trim_end(), glyph_authoring)
+    }
+}
+
 fn item_data(id: &str, glyph: Option<char>) -> String {
     let glyph = glyph
         .map(|glyph| glyph.to_string())
@@ -5917,13 +6337,88 @@ Sdf helmet
         let _ = fs::remove_dir_all(root);
     }
 
+    #[test]
+    fn recipe_templates_compile_variants_and_skip_partials() {
+        let root =
+            std::env::temp_dir().join(format!("eldiron-source-templates-{}", Uuid::new_v4()));
+        let recipes_dir = root.join("recipes/walls");
+        fs::create_dir_all(&recipes_dir).expect("recipe dir created");
+        fs::write(
+            recipes_dir.join("_grain.recipe"),
+            "Noise Grain\n    scale = F2($Scale, $Scale)\n\nParam Scale\n    default = 4.0\n",
+        )
+        .expect("partial written");
+        fs::write(
+            recipes_dir.join("bricks.recipe"),
+            r#"Tile
+    name = "Bricks"
+
+    Param Moss
+        default = 0.0
+
+    Include "_grain.recipe"
+        scale = 6.0
+
+    Pattern Wall
+        Bricks
+            columns = 2
+            rows = 2
+
+    Output
+        height = Wall.height * (1.0 - $Moss) + Grain * $Moss
+"#,
+        )
+        .expect("template written");
+        let variants = [SourceRecipeVariant {
+            alias: "walls/mossy-bricks".to_string(),
+            recipe: "recipes/walls/bricks.recipe".to_string(),
+            params: BTreeMap::from([("moss".to_string(), toml::Value::Float(0.5))]),
+        }];
+        let mut project = Project::new();
+
+        load_procedural_recipe_dir(&mut project, &root, "recipes", &variants)
+            .expect("templated recipes load");
+
+        assert_eq!(project.procedural_recipes.len(), 2);
+        let mossy = project
+            .procedural_recipes
+            .values()
+            .find(|asset| asset.alias == "walls/mossy-bricks")
+            .expect("variant compiled");
+        assert!(mossy.source.contains("Grain * 0.5"));
+        assert!(mossy.source.contains("scale = F2(6.0, 6.0)"));
+        assert!(parse_document(&mossy.source).is_ok());
+        assert!(
+            project
+                .tiles
+                .values()
+                .any(|tile| tile.alias == "walls/bricks")
+        );
+        assert!(
+            project
+                .tiles
+                .values()
+                .any(|tile| tile.alias == "walls/mossy-bricks")
+        );
+
+        let variants = [SourceRecipeVariant {
+            alias: "walls/rusty-bricks".to_string(),
+            recipe: "recipes/walls/bricks.recipe".to_string(),
+            params: BTreeMap::from([("rust".to_string(), toml::Value::Float(0.5))]),
+        }];
+        let error = load_procedural_recipe_dir(&mut Project::new(), &root, "recipes", &variants)
+            .expect_err("unknown params are rejected");
+        assert!(error.contains("unknown param 'rust'"));
+        let _ = fs::remove_dir_all(root);
+    }
+
     #[test]
     fn loads_stonefall_recipes_through_the_legacy_tile_material_contract() {
         let stonefall =
             Path::new(env!("CARGO_MANIFEST_DIR")).join("../../source_projects/stonefall-dungeon");
         let mut project = Project::new();
 
-        load_procedural_recipe_dir(&mut project, &stonefall, "recipes")
+        load_procedural_recipe_dir(&mut project, &stonefall, "recipes", &[])
             .expect("Stonefall procedural recipes load");
 
         assert_eq!(project.procedural_recipes.len(), 15);
@@ -6051,6 +6546,7 @@ Sdf helmet
                     "animation/frame-1.png".to_string(),
                     "animation/frame-2.png".to_string(),
                 ],
+                id: String::new(),
                 role: "dungeon".to_string(),
                 blocking: true,
                 scale: 1.0,
@@ -6060,6 +6556,7 @@ Sdf helmet
                 light_flicker: 0.1,
                 light_lift: 0.5,
             }],
+            recipe_variants: Vec::new(),
         };
         let mut project = Project::new();
 
@@ -6087,6 +6584,90 @@ Sdf helmet
         let _ = fs::remove_dir_all(base);
     }
 
+    #[test]
+    fn tile_atlas_manifests_split_their_image_into_tiles() {
+        let root = std::env::temp_dir().join(format!("eldiron-source-atlas-{}", Uuid::new_v4()));
+        let atlas_dir = root.join("tiles/walls");
+        fs::create_dir_all(&atlas_dir).expect("atlas dir created");
+        let atlas = image::RgbaImage::from_fn(6, 2, |x, _| {
+            if x < 4 {
+                image::Rgba([255, 0, 0, 255])
+            } else {
+                image::Rgba([0, 0, 255, 255])
+            }
+        });
+        atlas
+            .save(atlas_dir.join("stones.png"))
+            .expect("atlas image written");
+        fs::write(
+            atlas_dir.join("stones.atlas.toml"),
+            r#"
+version = 1
+image = "stones.png"
+width = 6
+height = 2
+
+[[tiles]]
+alias = "stones_0"
+graph = "stones"
+seed = 0
+x = 0
+y = 0
+width = 4
+height = 2
+
+[[tiles]]
+alias = "stones_1"
+graph = "stones"
+seed = 1
+x = 4
+y = 0
+width = 2
+height = 2
+"#,
+        )
+        .expect("atlas manifest written");
+        fs::write(
+            atlas_dir.join("broken.atlas.json"),
+            r#"{"image": "stones.png", "tiles": [{"alias": "big", "x": 4, "y": 0, "width": 4, "height": 2}]}"#,
+        )
+        .expect("broken manifest written");
+
+        let mut project = Project::new();
+        let error = load_project_directory_assets(&mut project, &root, &SourceSection::default())
+            .expect_err("out of bounds atlas entries fail");
+        assert!(
+            error.contains("entry 'big' lies outside its 6x2 image"),
+            "{error}"
+        );
+
+        fs::remove_file(atlas_dir.join("broken.atlas.json")).expect("broken manifest removed");
+        let mut project = Project::new();
+        load_project_directory_assets(&mut project, &root, &SourceSection::default())
+            .expect("atlas tiles load");
+
+        assert_eq!(
+            project.tiles.len(),
+            2,
+            "the atlas image is not a tile itself"
+        );
+        let tile = |alias: &str| {
+            project
+                .tiles
+                .values()
+                .find(|tile| tile.alias == alias)
+                .unwrap_or_else(|| panic!("{alias} loaded"))
+        };
+        let first = &tile("walls/stones_0").textures[0];
+        assert_eq!((first.width, first.height), (4, 2));
+        assert_eq!(&first.data[0..4], &[255, 0, 0, 255]);
+        let second = &tile("walls/stones_1").textures[0];
+        assert_eq!((second.width, second.height), (2, 2));
+        assert!(second.data.chunks(4).all(|pixel| pixel == [0, 0, 255, 255]));
+
+        let _ = fs::remove_dir_all(root);
+    }
+
     #[test]
     fn local_asset_tiles_are_not_duplicated_as_generic_images() {
         let root = std::env::temp_dir().join(format!(
@@ -6107,6 +6688,7 @@ Sdf helmet
             tiles: vec![SourceTileAsset {
                 alias: "environment/torch".to_string(),
                 path: "assets/environment/features/torch.png".to_string(),
+                id: String::new(),
                 role: "dungeon".to_string(),
                 blocking: true,
                 scale: 1.0,
@@ -6117,6 +6699,7 @@ Sdf helmet
                 light_lift: 0.5,
             }],
             tile_animations: Vec::new(),
+            recipe_variants: Vec::new(),
         };
         let mut project = Project::new();
 
@@ -6532,4 +7115,252 @@ Region "cellar" {
             "north wall should block firstp direct movement, start={start:?}, end={north:?}"
         );
     }
+
+    #[test]
+    fn check_reports_file_line_and_column_diagnostics() {
+        let root = std::env::temp_dir().join(format!("eldiron-source-check-{}", Uuid::new_v4()));
+        fs::create_dir_all(root.join("regions")).expect("regions dir created");
+        fs::write(root.join("eldiron.toml"), "[project]\nname = \"Check\"\n")
+            .expect("config written");
+        fs::write(
+            root.join("main.els"),
+            r#"Niche "arch" {
+  tile = niche.missing
+  position = F2(0.2, 0.1)
+  size = F2(0.6, 0.8)
+  depth = 0.3
+  sill = 0.1
+}
+"#,
+        )
+        .expect("main written");
+        fs::write(
+            root.join("regions/cellar.els"),
+            r#"Region "cellar" {
+  tiles {
+    "!" = { tile = "wall", niche = "nope" }
+  }
+  terrain """
+  #####
+  #@Z%#
+  #q..#
+  #####
+  """
+}
+"#,
+        )
+        .expect("region written");
+
+        let diagnostics = check_project(&root).expect("check runs");
+        let located = diagnostics
+            .iter()
+            .map(|diagnostic| {
+                (
+                    diagnostic
+                        .path
+                        .file_name()
+                        .and_then(|name| name.to_str())
+                        .unwrap_or_default()
+                        .to_string(),
+                    diagnostic.line,
+                    diagnostic.column,
+                    diagnostic.severity,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let _ = fs::remove_dir_all(root);
+        assert_eq!(
+            located,
+            vec![
+                ("main.els".to_string(), 2, 3, Severity::Error),
+                ("cellar.els".to_string(), 3, 5, Severity::Error),
+                ("cellar.els".to_string(), 3, 5, Severity::Error),
+                ("cellar.els".to_string(), 7, 5, Severity::Error),
+                ("cellar.els".to_string(), 7, 6, Severity::Warning),
+                ("cellar.els".to_string(), 8, 4, Severity::Warning),
+            ],
+            "{diagnostics:#?}"
+        );
+        assert!(diagnostics[0].message.contains("Niche 'arch' uses tile"));
+        assert!(diagnostics[2].message.contains("Niche 'nope'"));
+        assert!(diagnostics[3].message.contains("character 'Z'"));
+        assert!(diagnostics[4].message.contains("glyph '%'"));
+        assert!(diagnostics[5].message.contains("item 'q'"));
+        assert!(
+            diagnostics[3]
+                .to_string()
+                .ends_with("cellar.els:7:5: error: Region 'cellar' places character 'Z', but no Character with that id or glyph exists")
+        );
+    }
+
+    #[test]
+    fn check_locates_parse_errors_per_block() {
+        let root =
+            std::env::temp_dir().join(format!("eldiron-source-check-parse-{}", Uuid::new_v4()));
+        fs::create_dir_all(&root).expect("project dir created");
+        fs::write(
+            root.join("eldiron.toml"),
+            "[game]\nstart_region = \"cellar\"\n",
+        )
+        .expect("config written");
+        fs::write(
+            root.join("main.els"),
+            r#"tiles {
+  "~~" = water
+}
+
+Niche "arch" {
+  tile = wall
+  position = F2(0.2, 0.1)
+  size = F2(0.6, 0.8)
+  depth = 3
+  sill = 0.1
+}
+
+Niche "bay" {
+  tile = wall
+  size = F2(0.9, 0.8)
+  position = F2(0.2, 0.1)
+  depth = 0.3
+  sill = 0.1
+}
+
+Region "cellar" {
+  ceiling_height = 0.5
+  terrain """
+  #@#
+  """
+}
+"#,
+        )
+        .expect("main written");
+
+        let diagnostics = check_project(&root).expect("check runs");
+        let _ = fs::remove_dir_all(root);
+        let lines = diagnostics
+            .iter()
+            .map(|diagnostic| (diagnostic.line, diagnostic.column))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            lines,
+            vec![(2, 3), (9, 3), (16, 3), (22, 3)],
+            "{diagnostics:#?}"
+        );
+        assert!(diagnostics[0].message.contains("single-character glyph"));
+        assert!(diagnostics[1].message.contains("depth"));
+        assert!(diagnostics[2].message.contains("position.x + size.x"));
+        assert!(diagnostics[3].message.contains("ceiling_height"));
+    }
+
+    #[test]
+    fn format_source_is_canonical_and_idempotent() {
+        let source = "\n\nRegion \"cellar\" {\n\n    name   = \"Cellar\"   \n\ttiles {\n\"!\" = wall\n      }\n\n\n  terrain \"\"\"\n        ###\n        #@#\n\n        ###\n  \"\"\"\n\n}\nCharacter \"orc\" {\n  data {\n    choices = [\n    { label = \"Hi\" },\n    ]\n  }\n  script {\n      fn event(event, value) {\n          if event == \"startup\" {\n              face(\"right\");\n          }\n      }\n  }\n}\n\n\n";
+        let formatted = format_source(source);
+
+        assert_eq!(
+            formatted,
+            "Region \"cellar\" {\n  name   = \"Cellar\"\n  tiles {\n    \"!\" = wall\n  }\n\n  terrain \"\"\"\n  ###\n  #@#\n  ###\n  \"\"\"\n}\nCharacter \"orc\" {\n  data {\n    choices = [\n      { label = \"Hi\" },\n    ]\n  }\n  script {\n    fn event(event, value) {\n        if event == \"startup\" {\n            face(\"right\");\n        }\n    }\n  }\n}\n"
+        );
+        assert_eq!(format_source(&formatted), formatted);
+        let before = parse_source(source).expect("source parses");
+        let after = parse_source(&formatted).expect("formatted source parses");
+        assert_eq!(before.regions, after.regions);
+        assert_eq!(
+            before.characters[0].data,
+            after.characters[0].data.replace("      {", "    {")
+        );
+    }
+
+    #[test]
+    fn screen_widgets_keep_fractional_geometry() {
+        let src = r#"
+Screen "main" {
+    grid_size = 16
+    widget "status" {
+        role = "messages"
+        x = 0.5
+        y = 1.25
+        width = 10.5
+        height = 2.75
+    }
+}
+"#;
+        let block = find_named_blocks(src, "Screen").unwrap().remove(0);
+        let screen = parse_screen(&block).unwrap();
+        assert_eq!(screen.grid_size, Some(16.0));
+        assert_eq!((screen.widgets[0].x, screen.widgets[0].y), (0.5, 1.25));
+
+        let map = build_screen_map(
+            &screen,
+            &ViewportSection::default(),
+            &SourceTileLookup::default(),
+        )
+        .unwrap();
+        assert_eq!(map.grid_size, 16.0);
+        let size = map.bbox().size();
+        assert!((size.x - 10.5).abs() < 0.001 && (size.y - 2.75).abs() < 0.001);
+    }
+
+    #[test]
+    fn brace_block_skips_plain_fields_and_keeps_nested_braces() {
+        let src =
+            "authoring = \"none\"\nauthoring {\n[glyph]\nmap = { a = { b = 1 } }\n}\nscript {}\n";
+        assert_eq!(
+            brace_block(src, "authoring").map(str::trim),
+            Some("[glyph]\nmap = { a = { b = 1 } }")
+        );
+        assert_eq!(brace_block(src, "script"), Some(""));
+        assert_eq!(brace_block(src, "data"), None);
+    }
+
+    #[test]
+    fn project_config_passthrough_keeps_only_unowned_keys() {
+        let passthrough = project_config_passthrough(
+            r#"
+[project]
+name = "Demo"
+
+[game]
+start_region = "Town"
+target_fps = 60
+
+[viewport]
+width = 640
+
+[network]
+port = 7000
+"#,
+        )
+        .unwrap();
+        let table = passthrough.parse::<toml::Table>().unwrap();
+        assert!(!table.contains_key("project"));
+        assert!(!table.contains_key("viewport"));
+        assert_eq!(table["game"].as_table().unwrap().len(), 1);
+
+        let config = project_config(
+            &GameSection::default(),
+            &ViewportSection::default(),
+            &TerminalSection::default(),
+            None,
+            &passthrough,
+        )
+        .unwrap();
+        let config = config.parse::<toml::Table>().unwrap();
+        assert_eq!(config["game"]["target_fps"].as_integer(), Some(60));
+        assert_eq!(config["network"]["port"].as_integer(), Some(7000));
+        assert!(config["game"].get("start_region").is_some());
+
+        assert!(
+            project_config(
+                &GameSection::default(),
+                &ViewportSection::default(),
+                &TerminalSection::default(),
+                None,
+                "[network\nport = 7000",
+            )
+            .is_err()
+        );
+    }
 }
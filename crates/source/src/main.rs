@@ -12,8 +12,8 @@ use std::time::{Duration, Instant};
     name = "eldiron-source",
     version,
     about = "Source-first compiler and project tool for Eldiron games.",
-    long_about = "Eldiron Source compiles eldiron.toml plus .els source files into regular .eldiron projects. It can scaffold source projects, build them, play them through the configured client, and watch source folders for live rebuilds.",
-    after_help = "Examples:\n  eldiron-source new my-game\n  eldiron-source build my-game\n  eldiron-source play my-game\n  eldiron-source watch my-game\n\nRun `eldiron-source help <command>` for command-specific help."
+    long_about = "Eldiron Source compiles eldiron.toml plus .els source files into regular .eldiron projects. It can scaffold source projects, build, check and format them, export existing .eldiron projects back to source, play them through the configured client, and watch source folders for live rebuilds.",
+    after_help = "Examples:\n  eldiron-source new my-game\n  eldiron-source build my-game\n  eldiron-source check my-game\n  eldiron-source fmt my-game\n  eldiron-source export game.eldiron my-game\n  eldiron-source play my-game\n  eldiron-source watch my-game\n\nRun `eldiron-source help <command>` for command-specific help."
 )]
 struct Cli {
     #[command(subcommand)]
@@ -43,6 +43,37 @@ enum Commands {
         project_dir: PathBuf,
     },
 
+    /// Parse and validate a source project without writing any output.
+    Check {
+        /// Project folder containing eldiron.toml.
+        #[arg(default_value = ".")]
+        project_dir: PathBuf,
+    },
+
+    /// Pretty-print .els files canonically.
+    Fmt {
+        /// Project folder or .els file to format.
+        #[arg(default_value = ".")]
+        path: PathBuf,
+
+        /// List files that are not formatted instead of rewriting them, and fail if there are any.
+        #[arg(long)]
+        check: bool,
+    },
+
+    /// Decompile a .eldiron project into an Eldiron Source folder.
+    Export {
+        /// The .eldiron project to export.
+        game: PathBuf,
+
+        /// Folder to write the source project to.
+        project_dir: PathBuf,
+
+        /// Allow exporting into a non-empty directory, overwriting files.
+        #[arg(long)]
+        force: bool,
+    },
+
     /// Build, then play the generated .eldiron with the configured client.
     Play {
         /// Project folder containing eldiron.toml.
@@ -80,6 +111,13 @@ fn run() -> Result<(), String> {
             force,
         } => scaffold_project(&project_dir, name, force),
         Commands::Build { project_dir } => build_once(&project_dir),
+        Commands::Check { project_dir } => check_project(&project_dir),
+        Commands::Fmt { path, check } => format_sources(&path, check),
+        Commands::Export {
+            game,
+            project_dir,
+            force,
+        } => export_project(&game, &project_dir, force),
         Commands::Play { project_dir } => play_project(&project_dir),
         Commands::Watch {
             project_dir,
@@ -94,6 +132,60 @@ fn build_once(project_dir: &Path) -> Result<(), String> {
     Ok(())
 }
 
+fn check_project(project_dir: &Path) -> Result<(), String> {
+    let diagnostics = eldiron_source::check_project(project_dir)?;
+    for diagnostic in &diagnostics {
+        eprintln!("{diagnostic}");
+    }
+    let errors = diagnostics
+        .iter()
+        .filter(|diagnostic| diagnostic.severity == eldiron_source::Severity::Error)
+        .count();
+    let warnings = diagnostics.len() - errors;
+    if errors > 0 {
+        return Err(format!("{errors} error(s), {warnings} warning(s)"));
+    }
+    if warnings > 0 {
+        println!(
+            "Checked {} with {warnings} warning(s)",
+            project_dir.display()
+        );
+    } else {
+        println!("Checked {}", project_dir.display());
+    }
+    Ok(())
+}
+
+fn format_sources(path: &Path, check: bool) -> Result<(), String> {
+    let changed = eldiron_source::format_path(path, !check)?;
+    for file in &changed {
+        if check {
+            println!("Would format {}", file.display());
+        } else {
+            println!("Formatted {}", file.display());
+        }
+    }
+    if check && !changed.is_empty() {
+        return Err(format!("{} file(s) are not formatted", changed.len()));
+    }
+    Ok(())
+}
+
+fn export_project(game: &Path, project_dir: &Path, force: bool) -> Result<(), String> {
+    let report = eldiron_source::export_project(game, project_dir, force)?;
+    for warning in &report.warnings {
+        eprintln!("warning: {warning}");
+    }
+    println!(
+        "Exported {} to {} ({} files)",
+        game.display(),
+        project_dir.display(),
+        report.files.len()
+    );
+    println!("Next: eldiron-source build {}", project_dir.display());
+    Ok(())
+}
+
 fn play_project(project_dir: &Path) -> Result<(), String> {
     let client_mode = source_client_mode(project_dir)?;
     let output = eldiron_source::build_project(project_dir)?;
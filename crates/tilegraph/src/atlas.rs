@@ -0,0 +1,393 @@
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use theframework::prelude::TheColor;
+
+use crate::{TileGraphError, TileGraphRenderer, TileNodeGraphExchange};
+
+/// Current version of the atlas manifest format.
+pub const TILE_ATLAS_VERSION: u32 = 1;
+
+/// A named palette to render every graph of a batch with.
+#[derive(Clone, Debug)]
+pub struct AtlasPalette {
+    pub name: String,
+    pub colors: Vec<TheColor>,
+}
+
+/// The variants rendered for each graph of an atlas.
+///
+/// Every graph is rendered once for each combination of palette, tile size
+/// and seed. Empty `palettes` use the graph's own palette (or
+/// `default_palette` when it has none), and empty `sizes` keep the graph's
+/// own tile size.
+#[derive(Clone, Debug)]
+pub struct AtlasBatch {
+    pub seeds: Vec<u32>,
+    pub palettes: Vec<AtlasPalette>,
+    pub sizes: Vec<(u16, u16)>,
+    pub default_palette: Vec<TheColor>,
+    /// Transparent pixels between packed sheets.
+    pub padding: u32,
+}
+
+impl Default for AtlasBatch {
+    fn default() -> Self {
+        Self {
+            seeds: vec![0],
+            palettes: Vec::new(),
+            sizes: Vec::new(),
+            default_palette: Vec::new(),
+            padding: 0,
+        }
+    }
+}
+
+/// A graph to render into an atlas, flattened and ready to render.
+#[derive(Clone, Debug)]
+pub struct AtlasGraph {
+    pub alias: String,
+    pub graph: TileNodeGraphExchange,
+}
+
+/// One rendered graph sheet inside the atlas image.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct TileAtlasEntry {
+    pub alias: String,
+    pub graph: String,
+    pub seed: u32,
+    pub palette: String,
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+    pub grid_width: u16,
+    pub grid_height: u16,
+    pub tile_width: u16,
+    pub tile_height: u16,
+}
+
+/// Describes where each rendered sheet lives in the atlas image.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct TileAtlasManifest {
+    #[serde(default = "default_atlas_version")]
+    pub version: u32,
+    /// Atlas image file, relative to the manifest.
+    pub image: String,
+    pub width: u32,
+    pub height: u32,
+    #[serde(default)]
+    pub tiles: Vec<TileAtlasEntry>,
+}
+
+fn default_atlas_version() -> u32 {
+    TILE_ATLAS_VERSION
+}
+
+impl TileAtlasManifest {
+    pub fn from_toml_str(input: &str) -> Result<Self, TileGraphError> {
+        Ok(toml::from_str(input)?)
+    }
+
+    pub fn to_toml_pretty(&self) -> Result<String, TileGraphError> {
+        Ok(toml::to_string_pretty(self)?)
+    }
+
+    pub fn from_json_str(input: &str) -> Result<Self, TileGraphError> {
+        Ok(serde_json::from_str(input)?)
+    }
+
+    pub fn to_json_pretty(&self) -> Result<String, TileGraphError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+}
+
+/// A packed atlas: the manifest and the RGBA8 pixels of its image.
+#[derive(Clone, Debug)]
+pub struct TileAtlas {
+    pub manifest: TileAtlasManifest,
+    pub pixels: Vec<u8>,
+}
+
+struct AtlasSheet {
+    entry: TileAtlasEntry,
+    pixels: Vec<u8>,
+}
+
+/// Render every variant of `graphs` and pack the color sheets into one atlas.
+///
+/// Entry aliases are the graph alias, suffixed with the palette name, tile
+/// size and seed for each of those the batch has more than one of, e.g.
+/// `stones_cc29_3`.
+pub fn render_atlas(graphs: &[AtlasGraph], batch: &AtlasBatch, image: &str) -> TileAtlas {
+    let seeds = if batch.seeds.is_empty() {
+        vec![0]
+    } else {
+        batch.seeds.clone()
+    };
+    let palettes = if batch.palettes.is_empty() {
+        vec![None]
+    } else {
+        batch.palettes.iter().map(Some).collect()
+    };
+    let sizes = if batch.sizes.is_empty() {
+        vec![None]
+    } else {
+        batch.sizes.iter().copied().map(Some).collect()
+    };
+
+    let mut jobs = Vec::new();
+    for graph in graphs {
+        for palette in &palettes {
+            for size in &sizes {
+                for seed in &seeds {
+                    jobs.push((graph, *palette, *size, *seed));
+                }
+            }
+        }
+    }
+
+    let sheets = jobs
+        .into_par_iter()
+        .map(|(graph, palette, size, seed)| {
+            let mut exchange = graph.graph.clone();
+            if let Some((width, height)) = size {
+                exchange.tile_pixel_width = width.max(1);
+                exchange.tile_pixel_height = height.max(1);
+            }
+            let (palette_name, colors) = match palette {
+                Some(palette) => (palette.name.clone(), palette.colors.clone()),
+                None if !exchange.palette_colors.is_empty() => {
+                    ("graph".to_string(), exchange.palette_colors.clone())
+                }
+                None => ("default".to_string(), batch.default_palette.clone()),
+            };
+            let rendered = TileGraphRenderer::new(colors)
+                .with_id_seed(seed)
+                .render_graph(&exchange);
+
+            let mut alias = graph.alias.clone();
+            if palettes.len() > 1 {
+                alias.push_str(&format!("_{palette_name}"));
+            }
+            if sizes.len() > 1 {
+                alias.push_str(&format!(
+                    "_{}x{}",
+                    exchange.tile_pixel_width, exchange.tile_pixel_height
+                ));
+            }
+            if seeds.len() > 1 {
+                alias.push_str(&format!("_{seed}"));
+            }
+            AtlasSheet {
+                entry: TileAtlasEntry {
+                    alias,
+                    graph: graph.alias.clone(),
+                    seed,
+                    palette: palette_name,
+                    x: 0,
+                    y: 0,
+                    width: (rendered.tile_width * rendered.grid_width) as u32,
+                    height: (rendered.tile_height * rendered.grid_height) as u32,
+                    grid_width: rendered.grid_width as u16,
+                    grid_height: rendered.grid_height as u16,
+                    tile_width: rendered.tile_width as u16,
+                    tile_height: rendered.tile_height as u16,
+                },
+                pixels: rendered.sheet_color,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    pack_sheets(sheets, batch.padding, image)
+}
+
+/// Shelf-pack the sheets, tallest first, into an atlas whose width is the
+/// smallest power of two that fits the widest sheet and keeps it roughly
+/// square.
+fn pack_sheets(mut sheets: Vec<AtlasSheet>, padding: u32, image: &str) -> TileAtlas {
+    let area: u64 = sheets
+        .iter()
+        .map(|sheet| {
+            u64::from(sheet.entry.width + padding) * u64::from(sheet.entry.height + padding)
+        })
+        .sum();
+    let widest = sheets
+        .iter()
+        .map(|sheet| sheet.entry.width)
+        .max()
+        .unwrap_or(0);
+    let width = widest
+        .max((area as f64).sqrt().ceil() as u32)
+        .max(1)
+        .next_power_of_two();
+
+    let mut order = (0..sheets.len()).collect::<Vec<_>>();
+    order.sort_by_key(|&index| {
+        let entry = &sheets[index].entry;
+        (
+            std::cmp::Reverse(entry.height),
+            std::cmp::Reverse(entry.width),
+        )
+    });
+    let (mut x, mut y, mut shelf_height, mut height) = (0, 0, 0, 0);
+    for index in order {
+        let entry = &mut sheets[index].entry;
+        if x > 0 && x + entry.width > width {
+            x = 0;
+            y += shelf_height + padding;
+            shelf_height = 0;
+        }
+        entry.x = x;
+        entry.y = y;
+        x += entry.width + padding;
+        shelf_height = shelf_height.max(entry.height);
+        height = height.max(y + entry.height);
+    }
+    let height = height.max(1);
+
+    let mut pixels = vec![0_u8; width as usize * height as usize * 4];
+    for sheet in &sheets {
+        let entry = &sheet.entry;
+        let row_bytes = entry.width as usize * 4;
+        for row in 0..entry.height as usize {
+            let src = row * row_bytes;
+            let dst = ((entry.y as usize + row) * width as usize + entry.x as usize) * 4;
+            pixels[dst..dst + row_bytes].copy_from_slice(&sheet.pixels[src..src + row_bytes]);
+        }
+    }
+
+    TileAtlas {
+        manifest: TileAtlasManifest {
+            version: TILE_ATLAS_VERSION,
+            image: image.to_string(),
+            width,
+            height,
+            tiles: sheets.into_iter().map(|sheet| sheet.entry).collect(),
+        },
+        pixels,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TileGraphDocument;
+
+    const STONES: &str = r##"
+version = 1
+name = "Stones"
+grid = "2x1"
+tile_size = "8x8"
+
+[palette]
+colors = ["#000000", "#ffffff"]
+
+[node.voronoi.main]
+scale = 0.5
+seed = 3
+
+[node.id_random.main]
+id = "voronoi.main:cell_id"
+
+[node.output.main]
+color = "id_random.main:field"
+"##;
+
+    fn stones() -> AtlasGraph {
+        let document = TileGraphDocument::from_toml_str(STONES).unwrap();
+        AtlasGraph {
+            alias: "stones".to_string(),
+            graph: document.to_exchange().unwrap(),
+        }
+    }
+
+    #[test]
+    fn packs_every_variant_without_overlap() {
+        let batch = AtlasBatch {
+            seeds: vec![0, 1, 2],
+            sizes: vec![(8, 8), (16, 16)],
+            padding: 1,
+            ..AtlasBatch::default()
+        };
+        let atlas = render_atlas(&[stones()], &batch, "stones.png");
+        let manifest = &atlas.manifest;
+
+        assert_eq!(manifest.tiles.len(), 6);
+        assert_eq!(
+            atlas.pixels.len(),
+            manifest.width as usize * manifest.height as usize * 4
+        );
+        assert!(manifest.width.is_power_of_two());
+        let aliases = manifest
+            .tiles
+            .iter()
+            .map(|entry| entry.alias.as_str())
+            .collect::<Vec<_>>();
+        assert!(aliases.contains(&"stones_16x16_2"));
+        assert!(aliases.contains(&"stones_8x8_0"));
+
+        for (index, a) in manifest.tiles.iter().enumerate() {
+            assert_eq!(
+                (a.width, a.height),
+                (a.tile_width as u32 * 2, a.tile_height as u32)
+            );
+            assert!(a.x + a.width <= manifest.width && a.y + a.height <= manifest.height);
+            for b in &manifest.tiles[index + 1..] {
+                let apart = a.x + a.width < b.x
+                    || b.x + b.width < a.x
+                    || a.y + a.height < b.y
+                    || b.y + b.height < a.y;
+                assert!(apart, "{} overlaps {}", a.alias, b.alias);
+            }
+        }
+    }
+
+    #[test]
+    fn id_seeds_vary_cells_and_seed_zero_matches_the_plain_render() {
+        let graph = stones();
+        let batch = AtlasBatch {
+            seeds: vec![0, 7],
+            ..AtlasBatch::default()
+        };
+        let atlas = render_atlas(std::slice::from_ref(&graph), &batch, "stones.png");
+        let sheet = |seed: u32| {
+            let entry = atlas
+                .manifest
+                .tiles
+                .iter()
+                .find(|entry| entry.seed == seed)
+                .unwrap();
+            assert_eq!(entry.palette, "graph");
+            (0..entry.height as usize)
+                .flat_map(|row| {
+                    let start = ((entry.y as usize + row) * atlas.manifest.width as usize
+                        + entry.x as usize)
+                        * 4;
+                    atlas.pixels[start..start + entry.width as usize * 4].to_vec()
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let plain = TileGraphRenderer::new(graph.graph.palette_colors.clone())
+            .render_graph(&graph.graph)
+            .sheet_color;
+        assert_eq!(sheet(0), plain);
+        assert_ne!(sheet(7), plain);
+    }
+
+    #[test]
+    fn manifest_round_trips_through_toml_and_json() {
+        let atlas = render_atlas(&[stones()], &AtlasBatch::default(), "stones.png");
+        let toml = atlas.manifest.to_toml_pretty().unwrap();
+        assert_eq!(
+            TileAtlasManifest::from_toml_str(&toml).unwrap(),
+            atlas.manifest
+        );
+        let json = atlas.manifest.to_json_pretty().unwrap();
+        assert_eq!(
+            TileAtlasManifest::from_json_str(&json).unwrap(),
+            atlas.manifest
+        );
+        assert_eq!(atlas.manifest.tiles[0].alias, "stones");
+    }
+}
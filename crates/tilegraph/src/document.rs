@@ -11,6 +11,7 @@ use crate::{
 pub enum TileGraphError {
     TomlDeserialize(toml::de::Error),
     TomlSerialize(toml::ser::Error),
+    Json(serde_json::Error),
     InvalidGraph(String),
 }
 
@@ -19,6 +20,7 @@ impl fmt::Display for TileGraphError {
         match self {
             Self::TomlDeserialize(err) => write!(f, "failed to parse tile graph TOML: {err}"),
             Self::TomlSerialize(err) => write!(f, "failed to serialize tile graph TOML: {err}"),
+            Self::Json(err) => write!(f, "failed to process tile graph JSON: {err}"),
             Self::InvalidGraph(err) => write!(f, "invalid tile graph: {err}"),
         }
     }
@@ -38,6 +40,12 @@ impl From<toml::ser::Error> for TileGraphError {
     }
 }
 
+impl From<serde_json::Error> for TileGraphError {
+    fn from(value: serde_json::Error) -> Self {
+        Self::Json(value)
+    }
+}
+
 #[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
 pub struct PaletteDocument {
     #[serde(default)]
@@ -481,8 +489,8 @@ impl TileGraphDocument {
                 {
                     output_root.position = pos;
                 }
-                if let Some(output_root) = nodes.first_mut() {
-                    if let TileNodeKind::OutputRoot {
+                if let Some(output_root) = nodes.first_mut()
+                    && let TileNodeKind::OutputRoot {
                         roughness,
                         metallic,
                         opacity,
@@ -490,38 +498,32 @@ impl TileGraphDocument {
                         particle_enabled,
                         light_enabled,
                     } = &mut output_root.kind
+                {
+                    if let Some(v) = graph_node.table.get("roughness").and_then(|v| v.as_float()) {
+                        *roughness = v as f32;
+                    }
+                    if let Some(v) = graph_node.table.get("metallic").and_then(|v| v.as_float()) {
+                        *metallic = v as f32;
+                    }
+                    if let Some(v) = graph_node.table.get("opacity").and_then(|v| v.as_float()) {
+                        *opacity = v as f32;
+                    }
+                    if let Some(v) = graph_node.table.get("emissive").and_then(|v| v.as_float()) {
+                        *emissive = v as f32;
+                    }
+                    if let Some(v) = graph_node
+                        .table
+                        .get("particle_enabled")
+                        .and_then(|v| v.as_bool())
+                    {
+                        *particle_enabled = v;
+                    }
+                    if let Some(v) = graph_node
+                        .table
+                        .get("light_enabled")
+                        .and_then(|v| v.as_bool())
                     {
-                        if let Some(v) =
-                            graph_node.table.get("roughness").and_then(|v| v.as_float())
-                        {
-                            *roughness = v as f32;
-                        }
-                        if let Some(v) = graph_node.table.get("metallic").and_then(|v| v.as_float())
-                        {
-                            *metallic = v as f32;
-                        }
-                        if let Some(v) = graph_node.table.get("opacity").and_then(|v| v.as_float())
-                        {
-                            *opacity = v as f32;
-                        }
-                        if let Some(v) = graph_node.table.get("emissive").and_then(|v| v.as_float())
-                        {
-                            *emissive = v as f32;
-                        }
-                        if let Some(v) = graph_node
-                            .table
-                            .get("particle_enabled")
-                            .and_then(|v| v.as_bool())
-                        {
-                            *particle_enabled = v;
-                        }
-                        if let Some(v) = graph_node
-                            .table
-                            .get("light_enabled")
-                            .and_then(|v| v.as_bool())
-                        {
-                            *light_enabled = v;
-                        }
+                        *light_enabled = v;
                     }
                 }
                 for key in [
@@ -1009,23 +1011,15 @@ fn export_input_name(kind: &str, input: u8) -> Option<&'static str> {
             0 => Some("in"),
             _ => None,
         },
-        "particle_emitter" => match input {
-            _ => None,
-        },
-        "particle_spawn" => match input {
-            _ => None,
-        },
-        "particle_motion" => match input {
-            _ => None,
-        },
+        "particle_emitter" => None,
+        "particle_spawn" => None,
+        "particle_motion" => None,
         "particle_render" => match input {
             0 => Some("spawn"),
             1 => Some("motion"),
             _ => None,
         },
-        "light_emitter" => match input {
-            _ => None,
-        },
+        "light_emitter" => None,
         "id_random" => match input {
             0 => Some("id"),
             _ => None,
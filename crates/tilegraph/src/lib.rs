@@ -1,6 +1,11 @@
+mod atlas;
 mod document;
 mod runtime;
 
+pub use atlas::{
+    AtlasBatch, AtlasGraph, AtlasPalette, TILE_ATLAS_VERSION, TileAtlas, TileAtlasEntry,
+    TileAtlasManifest, render_atlas,
+};
 pub use document::{
     NodeEndpoint, PaletteDocument, TileGraphDocument, TileGraphError, TileGraphNode, TileGraphRef,
 };
@@ -5,8 +5,9 @@ use std::path::{Path, PathBuf};
 use theframework::prelude::*;
 
 use tilegraph::{
-    RenderedTileGraph, TileGraphDocument, TileGraphRenderer, TileGraphSubgraphResolver,
-    TileNodeGraphExchange, TileNodeGraphState, flatten_graph_exchange_with,
+    AtlasBatch, AtlasGraph, AtlasPalette, RenderedTileGraph, TileGraphDocument, TileGraphRenderer,
+    TileGraphSubgraphResolver, TileNodeGraphExchange, TileNodeGraphState,
+    flatten_graph_exchange_with, render_atlas,
 };
 
 struct FileLayerResolver {
@@ -83,6 +84,9 @@ fn print_usage() {
     eprintln!(
         "Usage: tilegraph <graph.eldiron_graph|graph.tilegraph> [output_dir] [--palette #RRGGBB,#RRGGBB,...]"
     );
+    eprintln!(
+        "       tilegraph atlas <dir> [--output atlas.png] [--seeds 0-3|0,2,5] [--palette [name=]#RRGGBB,...|palette.hex]... [--size 16x16,32x32]... [--padding px] [--manifest toml|json]"
+    );
 }
 
 fn write_rendered_graph(output_dir: &Path, rendered: RenderedTileGraph) -> Result<(), String> {
@@ -159,6 +163,9 @@ fn run() -> Result<(), String> {
         print_usage();
         return Err("Missing graph input path.".to_string());
     };
+    if input == "atlas" {
+        return run_atlas(args);
+    }
     let mut output_path: Option<String> = None;
 
     let mut palette = Vec::new();
@@ -234,3 +241,230 @@ fn run() -> Result<(), String> {
     }
     Ok(())
 }
+
+fn is_tilegraph_file(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|f| f.to_str())
+        .is_some_and(|f| f.ends_with(".tilegraph") || f.ends_with(".tilegraph.toml"))
+}
+
+/// Collect the `.tilegraph` files below `dir`, skipping `_` prefixed files
+/// which are only used as layers of other graphs.
+fn collect_tilegraph_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<(), String> {
+    let mut entries = fs::read_dir(dir)
+        .map_err(|e| format!("{}: {e}", dir.display()))?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    entries.sort();
+    for path in entries {
+        if path.is_dir() {
+            collect_tilegraph_files(&path, files)?;
+        } else if is_tilegraph_file(&path)
+            && !path
+                .file_name()
+                .and_then(|f| f.to_str())
+                .is_some_and(|f| f.starts_with('_'))
+        {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn graph_alias(root: &Path, path: &Path) -> String {
+    let relative = path.strip_prefix(root).unwrap_or(path);
+    let alias = relative
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/");
+    alias
+        .trim_end_matches(".tilegraph.toml")
+        .trim_end_matches(".tilegraph")
+        .to_string()
+}
+
+fn parse_seeds_arg(value: &str) -> Result<Vec<u32>, String> {
+    let invalid = || format!("Invalid seeds '{value}', expected e.g. 0-3 or 0,2,5.");
+    let mut seeds = Vec::new();
+    for part in value.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        if let Some((start, end)) = part.split_once('-') {
+            let start = start.trim().parse::<u32>().map_err(|_| invalid())?;
+            let end = end.trim().parse::<u32>().map_err(|_| invalid())?;
+            if end < start {
+                return Err(invalid());
+            }
+            seeds.extend(start..=end);
+        } else {
+            seeds.push(part.parse::<u32>().map_err(|_| invalid())?);
+        }
+    }
+    Ok(seeds)
+}
+
+fn parse_sizes_arg(value: &str) -> Result<Vec<(u16, u16)>, String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+        .map(|part| {
+            let (w, h) = part.split_once('x').unwrap_or((part, part));
+            match (w.trim().parse::<u16>(), h.trim().parse::<u16>()) {
+                (Ok(w), Ok(h)) if w > 0 && h > 0 => Ok((w, h)),
+                _ => Err(format!("Invalid tile size '{part}', expected e.g. 32x32.")),
+            }
+        })
+        .collect()
+}
+
+/// Parse `[name=]#RRGGBB,...` or `[name=]palette.hex` (one hex color per
+/// line, as exported by Lospec).
+fn parse_atlas_palette(value: &str, index: usize) -> Result<AtlasPalette, String> {
+    let (name, spec) = match value.split_once('=') {
+        Some((name, spec)) if !name.contains('#') => (Some(name.trim().to_string()), spec),
+        _ => (None, value),
+    };
+    let (default_name, colors) = if spec.trim().ends_with(".hex") {
+        let path = Path::new(spec.trim());
+        let text = fs::read_to_string(path).map_err(|e| format!("{}: {e}", path.display()))?;
+        let colors = text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(|line| TheColor::from_hex(&format!("#{}", line.trim_start_matches('#'))))
+            .collect();
+        let stem = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or("palette")
+            .to_string();
+        (stem, colors)
+    } else {
+        (format!("palette{}", index + 1), parse_palette_arg(spec))
+    };
+    let colors: Vec<TheColor> = colors;
+    if colors.is_empty() {
+        return Err(format!("Palette '{value}' has no colors."));
+    }
+    Ok(AtlasPalette {
+        name: name.unwrap_or(default_name),
+        colors,
+    })
+}
+
+fn run_atlas(mut args: impl Iterator<Item = String>) -> Result<(), String> {
+    let Some(input) = args.next() else {
+        print_usage();
+        return Err("Missing tile graph directory.".to_string());
+    };
+    let mut output_path: Option<PathBuf> = None;
+    let mut manifest_format = "toml".to_string();
+    let mut batch = AtlasBatch {
+        default_palette: default_cc29_palette(),
+        ..AtlasBatch::default()
+    };
+    let mut seeds = Vec::new();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| format!("Missing value for {arg}."))
+        };
+        match arg.as_str() {
+            "--output" => output_path = Some(PathBuf::from(value()?)),
+            "--seeds" => seeds.extend(parse_seeds_arg(&value()?)?),
+            "--palette" => {
+                let palette = parse_atlas_palette(&value()?, batch.palettes.len())?;
+                batch.palettes.push(palette);
+            }
+            "--size" => batch.sizes.extend(parse_sizes_arg(&value()?)?),
+            "--padding" => {
+                let padding = value()?;
+                batch.padding = padding
+                    .parse()
+                    .map_err(|_| format!("Invalid padding '{padding}'."))?;
+            }
+            "--manifest" => {
+                manifest_format = value()?;
+                if !matches!(manifest_format.as_str(), "toml" | "json") {
+                    return Err(format!(
+                        "Unknown manifest format '{manifest_format}', expected toml or json."
+                    ));
+                }
+            }
+            _ => return Err(format!("Unknown argument: {arg}")),
+        }
+    }
+    if !seeds.is_empty() {
+        batch.seeds = seeds;
+    }
+
+    let root = PathBuf::from(&input);
+    if !root.is_dir() {
+        return Err(format!("{} is not a directory.", root.display()));
+    }
+    let mut files = Vec::new();
+    collect_tilegraph_files(&root, &mut files)?;
+    if files.is_empty() {
+        return Err(format!("No .tilegraph files found in {}.", root.display()));
+    }
+    let graphs = files
+        .iter()
+        .map(|path| {
+            let text = fs::read_to_string(path).map_err(|e| format!("{}: {e}", path.display()))?;
+            let mut graph = load_graph_from_text(path, &text)
+                .map_err(|e| format!("{}: {e}", path.display()))?;
+            graph.graph_state.ensure_root();
+            let resolver = FileLayerResolver {
+                base_dir: path
+                    .parent()
+                    .map(Path::to_path_buf)
+                    .unwrap_or_else(|| PathBuf::from(".")),
+            };
+            Ok(AtlasGraph {
+                alias: graph_alias(&root, path),
+                graph: flatten_graph_exchange_with(&graph, &resolver),
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    let output_path = output_path.unwrap_or_else(|| {
+        let trimmed = input.trim_end_matches(['/', '\\']);
+        PathBuf::from(format!("{trimmed}.png"))
+    });
+    let image_name = output_path
+        .file_name()
+        .and_then(|f| f.to_str())
+        .ok_or_else(|| format!("Invalid output path {}.", output_path.display()))?
+        .to_string();
+    let atlas = render_atlas(&graphs, &batch, &image_name);
+
+    if let Some(parent) = output_path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    save_png(
+        &output_path,
+        atlas.manifest.width,
+        atlas.manifest.height,
+        atlas.pixels,
+    )?;
+    let manifest_path = output_path.with_extension(format!("atlas.{manifest_format}"));
+    let manifest = if manifest_format == "json" {
+        atlas.manifest.to_json_pretty()
+    } else {
+        atlas.manifest.to_toml_pretty()
+    }
+    .map_err(|e| e.to_string())?;
+    fs::write(&manifest_path, manifest).map_err(|e| e.to_string())?;
+
+    println!(
+        "Rendered {} sheet(s) from {} graph(s) into {} ({}x{}) with manifest {}",
+        atlas.manifest.tiles.len(),
+        graphs.len(),
+        output_path.display(),
+        atlas.manifest.width,
+        atlas.manifest.height,
+        manifest_path.display()
+    );
+    Ok(())
+}
@@ -925,6 +925,7 @@ fn remap_sub_index(index: u16, sub_map: &[Option<u16>], _base: u16) -> Option<u1
 
 pub struct TileGraphRenderer {
     palette: Vec<TheColor>,
+    id_seed: u32,
     colorize4_ranges: RwLock<Vec<Option<(f32, f32)>>>,
 }
 
@@ -932,10 +933,18 @@ impl TileGraphRenderer {
     pub fn new(palette: Vec<TheColor>) -> Self {
         Self {
             palette,
+            id_seed: 0,
             colorize4_ranges: RwLock::new(Vec::new()),
         }
     }
 
+    /// Reseed every `IdRandom` node, giving a variant of the graph with the
+    /// same layout but new per-cell values. Seed `0` is the authored graph.
+    pub fn with_id_seed(mut self, seed: u32) -> Self {
+        self.id_seed = seed;
+        self
+    }
+
     pub fn render_graph(&self, graph: &TileNodeGraphExchange) -> RenderedTileGraph {
         let mut state = graph.graph_state.clone();
         state.ensure_root();
@@ -1067,9 +1076,7 @@ impl TileGraphRenderer {
         let mut tiles_material = Vec::with_capacity(tile_count);
         let mut tiles_height = Vec::with_capacity(tile_count);
 
-        for (_tile_index, (tile_color, tile_material, tile_height_data)) in
-            rendered_tiles.into_iter().enumerate()
-        {
+        for (tile_color, tile_material, tile_height_data) in rendered_tiles.into_iter() {
             tiles_color.push(tile_color);
             tiles_material.push(tile_material);
             tiles_height.push(tile_height_data);
@@ -1092,9 +1099,7 @@ impl TileGraphRenderer {
     }
 
     pub fn output_particle(&self, state: &TileNodeGraphState) -> Option<TileParticleOutput> {
-        let Some(root) = state.nodes.first() else {
-            return None;
-        };
+        let root = state.nodes.first()?;
         let particle_enabled = match &root.kind {
             TileNodeKind::OutputRoot {
                 particle_enabled, ..
@@ -1221,9 +1226,7 @@ impl TileGraphRenderer {
     }
 
     pub fn output_light(&self, state: &TileNodeGraphState) -> Option<TileLightOutput> {
-        let Some(root) = state.nodes.first() else {
-            return None;
-        };
+        let root = state.nodes.first()?;
         let light_enabled = match &root.kind {
             TileNodeKind::OutputRoot { light_enabled, .. } => *light_enabled,
             _ => false,
@@ -1581,17 +1584,18 @@ impl TileGraphRenderer {
             return None;
         }
         let result = state.nodes.get(node_index).and_then(|node| {
-            if node.bypass && !matches!(node.kind, TileNodeKind::OutputRoot { .. }) {
-                if let Some(value) = self.evaluate_connected_material(
+            if node.bypass
+                && !matches!(node.kind, TileNodeKind::OutputRoot { .. })
+                && let Some(value) = self.evaluate_connected_material(
                     state,
                     node_index,
                     0,
                     eval,
                     visiting,
                     visiting_subgraphs,
-                ) {
-                    return Some(value);
-                }
+                )
+            {
+                return Some(value);
             }
             match &node.kind {
                 TileNodeKind::OutputRoot {
@@ -1791,6 +1795,7 @@ impl TileGraphRenderer {
             })
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn connected_warp_vector(
         &self,
         state: &TileNodeGraphState,
@@ -1906,17 +1911,16 @@ impl TileGraphRenderer {
                     node.kind,
                     TileNodeKind::OutputRoot { .. } | TileNodeKind::GroupUV
                 )
-            {
-                if let Some(color) = self.evaluate_connected_color(
+                && let Some(color) = self.evaluate_connected_color(
                     state,
                     node_index,
                     0,
                     eval,
                     visiting,
                     visiting_subgraphs,
-                ) {
-                    return Some(color);
-                }
+                )
+            {
+                return Some(color);
             }
             match &node.kind {
                 TileNodeKind::OutputRoot { .. } => {
@@ -2393,7 +2397,8 @@ impl TileGraphRenderer {
                         .map(Self::color_to_mask)
                         .unwrap_or(0.0);
                     let key = (id.clamp(0.0, 1.0) * 65535.0).round() as i32;
-                    let v = unit_to_u8(Self::hash2(key, key ^ 0x45d9f3, 0x9e37_79b9));
+                    let v =
+                        unit_to_u8(Self::hash2(key, key ^ 0x45d9f3, 0x9e37_79b9 ^ self.id_seed));
                     Some(TheColor::from_u8_array([v, v, v, 255]))
                 }
                 TileNodeKind::Min => {
@@ -3086,7 +3091,7 @@ impl TileGraphRenderer {
                 }
             }
         }
-        let center = (1.0 - (min_dist / 1.4142)).clamp(0.0, 1.0);
+        let center = (1.0 - (min_dist / std::f32::consts::SQRT_2)).clamp(0.0, 1.0);
         let edge_distance = ((second_dist - min_dist) / second_dist.max(0.0001)).clamp(0.0, 1.0);
         let height = edge_distance.powf(falloff.clamp(0.1, 4.0));
         let id = Self::hash2(nearest.0, nearest.1, seed ^ 0x51f1_5e11);
@@ -3152,7 +3157,8 @@ impl TileGraphRenderer {
 
         let dx = ((local_x - 0.5).abs() * 2.0).clamp(0.0, 1.0);
         let dy = ((local_y - 0.5).abs() * 2.0).clamp(0.0, 1.0);
-        let center = (1.0 - ((dx * dx + dy * dy).sqrt() / 1.4142)).clamp(0.0, 1.0);
+        let center =
+            (1.0 - ((dx * dx + dy * dy).sqrt() / std::f32::consts::SQRT_2)).clamp(0.0, 1.0);
 
         let edge =
             (local_x.min(1.0 - local_x).min(local_y.min(1.0 - local_y)) * 2.0).clamp(0.0, 1.0);
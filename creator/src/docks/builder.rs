@@ -3,8 +3,8 @@ use crate::prelude::*;
 use rusterix::Surface;
 use scenevm::GeoId;
 use shared::buildergraph::{
-    BuilderCutMask, BuilderCutMode, BuilderDocument, BuilderHost, BuilderOutputTarget,
-    BuilderPreviewHost, BuilderPrimitive, BuilderScriptParameterValue, BuilderTransform,
+    BuilderDocument, BuilderHost, BuilderOutputTarget, BuilderPreviewHost, BuilderPrimitive,
+    BuilderScriptParameterValue, BuilderTransform,
 };
 use std::{
     collections::HashMap,
@@ -112,26 +112,6 @@ pub struct BuilderDock {
     treasury_load_rx: Option<Mutex<Receiver<Result<Vec<BuilderTreasuryItem>, String>>>>,
 }
 
-fn builder_document_hides_host(document: &BuilderDocument) -> bool {
-    document
-        .evaluate()
-        .map(|assembly| {
-            assembly.cuts.iter().any(|cut| {
-                matches!(
-                    cut,
-                    BuilderCutMask::Rect {
-                        mode: BuilderCutMode::Replace,
-                        ..
-                    } | BuilderCutMask::Loop {
-                        mode: BuilderCutMode::Replace,
-                        ..
-                    }
-                )
-            })
-        })
-        .unwrap_or(false)
-}
-
 impl Dock for BuilderDock {
     fn new() -> Self
     where
@@ -1327,7 +1307,7 @@ impl BuilderDock {
             return;
         };
         let spec = document.output_spec();
-        let builder_hide_host = builder_document_hides_host(&document);
+        let builder_hide_host = document.hides_host();
         let Some(map) = project.get_map_mut(server_ctx) else {
             return;
         };
@@ -2282,7 +2262,7 @@ impl BuilderDock {
             }
         };
         let spec = graph.output_spec();
-        let builder_hide_host = builder_document_hides_host(&graph);
+        let builder_hide_host = graph.hides_host();
         let group_id = Uuid::new_v4();
 
         server_ctx.curr_map_tool_type = match spec.target {
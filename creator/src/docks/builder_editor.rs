@@ -2,7 +2,7 @@ use crate::prelude::*;
 use rusterix::Surface;
 use rusterix::builderpreview::{BuilderPreviewOptions, PreviewVariants};
 use scenevm::GeoId;
-use shared::buildergraph::{BuilderCutMask, BuilderCutMode, BuilderDocument, BuilderGraph};
+use shared::buildergraph::{BuilderDocument, BuilderGraph};
 
 const BUILDER_SCRIPT_EDITOR: &str = "Builder Script Editor";
 const BUILDER_SCRIPT_PREVIEW: &str = "Builder Script Preview";
@@ -15,26 +15,6 @@ pub struct BuilderEditorDock {
     active_builder_id: Option<Uuid>,
 }
 
-fn builder_document_hides_host(document: &BuilderDocument) -> bool {
-    document
-        .evaluate()
-        .map(|assembly| {
-            assembly.cuts.iter().any(|cut| {
-                matches!(
-                    cut,
-                    BuilderCutMask::Rect {
-                        mode: BuilderCutMode::Replace,
-                        ..
-                    } | BuilderCutMask::Loop {
-                        mode: BuilderCutMode::Replace,
-                        ..
-                    }
-                )
-            })
-        })
-        .unwrap_or(false)
-}
-
 impl BuilderEditorDock {
     fn default_source() -> String {
         BuilderGraph::preset_table_script_named("Table".to_string())
@@ -347,7 +327,7 @@ impl BuilderEditorDock {
         let spec = parsed.as_ref().map(|doc| doc.output_spec());
         let builder_hide_host = parsed
             .as_ref()
-            .map(builder_document_hides_host)
+            .map(BuilderDocument::hides_host)
             .unwrap_or(false);
 
         eprintln!(
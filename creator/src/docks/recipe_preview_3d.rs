@@ -1,7 +1,5 @@
 use crate::prelude::*;
-use procedural_recipes::{
-    GeometryFeature, GeometryOperation, RecipeDocument, RecipePlacement, parse_document,
-};
+use procedural_recipes::{GeometryFeature, GeometryOperation, RecipeDocument, RecipePlacement};
 use rusterix::{
     D3Camera, D3IsoCamera, GeometryObject, GeometryObjectKind, Light, LightType, Map, MapCamera,
     PixelSource, Rusterix, SceneManager, SceneManagerResult, Tile, Value,
@@ -174,7 +172,7 @@ fn build_preview_map(project: &Project, asset_id: Uuid) -> Result<Option<Preview
         .get(&asset_id)
         .ok_or_else(|| "Recipe asset was not found".to_string())?;
     let RecipeDocument::Tile(recipe) =
-        parse_document(&asset.source).map_err(|error| error.to_string())?
+        crate::recipe_utils::parse_project_recipe(project, Some(&asset.alias), &asset.source)?
     else {
         return Ok(None);
     };
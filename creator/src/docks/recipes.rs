@@ -99,7 +99,9 @@ impl Dock for RecipeEditorDock {
                 TheValue::Text(format!(
                     "{} — {}",
                     fl!("recipe_editor"),
-                    recipe_name(&asset.source)
+                    recipe_description(project, asset)
+                        .map(|(name, _)| name)
+                        .unwrap_or_else(|_| recipe_name(&asset.source))
                 )),
             );
         }
@@ -294,14 +296,10 @@ impl RecipeEditorDock {
         project: &mut Project,
         asset_id: Uuid,
     ) {
-        let Some(source) = project
-            .procedural_recipes
-            .get(&asset_id)
-            .map(|asset| asset.source.clone())
-        else {
+        let Some(asset) = project.procedural_recipes.get(&asset_id) else {
             return;
         };
-        match recipe_description(&source) {
+        match recipe_description(project, asset) {
             Ok((name, _)) => {
                 sync_recipe_compatibility_catalogs(project);
                 self.schedule_preview(ctx, project, asset_id);
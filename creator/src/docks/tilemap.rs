@@ -441,6 +441,7 @@ impl Dock for TilemapDock {
                                             id,
                                             role: tile_for_runtime.role,
                                             textures: texture_array.clone(),
+                                            fps: 0.0,
                                             module: None,
                                             blocking: tile_for_runtime.blocking,
                                             scale: tile_for_runtime.scale,
@@ -658,6 +659,7 @@ impl TilemapDock {
             id: rgba_tile.id,
             role: rusterix::TileRole::from_index(rgba_tile.role),
             textures: texture_array.clone(),
+            fps: 0.0,
             module: None,
             blocking: rgba_tile.blocking,
             scale: rgba_tile.scale,
@@ -1,7 +1,8 @@
 use crate::prelude::*;
 use procedural_recipes::{
-    MaterialRecipe, Recipe, RecipeDocument, RecipePlacement, RecipeRenderer, RenderOptions,
-    RenderSurface, RenderSurfaceFrame, RenderSurfaceMapping, SdfRenderer, parse_document,
+    FileIncludes, IncludeResolver, MaterialRecipe, ParseOptions, Recipe, RecipeDocument,
+    RecipePlacement, RecipeRenderer, RenderOptions, RenderSurface, RenderSurfaceFrame,
+    RenderSurfaceMapping, SdfRenderer, expand_recipe_source, parse_document, parse_document_with,
 };
 use rusterix::{Texture, Tile, TileRole};
 use std::collections::hash_map::DefaultHasher;
@@ -65,8 +66,78 @@ pub fn compact_recipe_diagnostic(error: &str) -> String {
     }
 }
 
-pub fn recipe_description(source: &str) -> Result<(String, ProceduralRecipeKind), String> {
-    match parse_document(source).map_err(|error| error.to_string())? {
+/// Resolves `Include "walls/bricks.recipe"` to the Recipe asset aliased
+/// `walls/bricks`, looking next to the including Recipe first.
+struct ProjectRecipeIncludes<'a>(&'a Project);
+
+impl IncludeResolver for ProjectRecipeIncludes<'_> {
+    fn resolve(&self, from: Option<&str>, path: &str) -> Result<(String, String), String> {
+        let path = path.replace('\\', "/");
+        let requested = path.strip_suffix(".recipe").unwrap_or(&path);
+        let sibling = from
+            .and_then(|from| from.rsplit_once('/'))
+            .map(|(dir, _)| format!("{dir}/{requested}"));
+        sibling
+            .iter()
+            .map(String::as_str)
+            .chain(std::iter::once(requested))
+            .find_map(|alias| {
+                self.0
+                    .procedural_recipes
+                    .values()
+                    .find(|asset| asset.alias.eq_ignore_ascii_case(alias))
+            })
+            .map(|asset| (asset.alias.clone(), asset.source.clone()))
+            .ok_or_else(|| format!("no Recipe with alias '{requested}'"))
+    }
+}
+
+/// Parse Recipe source, resolving its includes against the project's Recipes.
+pub fn parse_project_recipe(
+    project: &Project,
+    alias: Option<&str>,
+    source: &str,
+) -> Result<RecipeDocument, String> {
+    let includes = ProjectRecipeIncludes(project);
+    let options = ParseOptions {
+        source_name: alias,
+        includes: Some(&includes),
+        ..ParseOptions::default()
+    };
+    parse_document_with(source, &options).map_err(|error| error.to_string())
+}
+
+/// Validate a Recipe file for import. Files that include other files are
+/// expanded relative to their directory so the imported asset stands alone.
+pub fn import_recipe_source(path: &std::path::Path, source: String) -> Result<String, String> {
+    if parse_document(&source).is_ok() {
+        return Ok(source);
+    }
+    let includes = FileIncludes::new(path.parent().unwrap_or(std::path::Path::new(".")));
+    let source_name = path.display().to_string();
+    let options = ParseOptions {
+        source_name: Some(&source_name),
+        includes: Some(&includes),
+        ..ParseOptions::default()
+    };
+    expand_recipe_source(&source, &options).map_err(|error| error.to_string())
+}
+
+pub fn recipe_description(
+    project: &Project,
+    asset: &ProceduralRecipeAsset,
+) -> Result<(String, ProceduralRecipeKind), String> {
+    describe_recipe_document(parse_project_recipe(
+        project,
+        Some(&asset.alias),
+        &asset.source,
+    )?)
+}
+
+fn describe_recipe_document(
+    document: RecipeDocument,
+) -> Result<(String, ProceduralRecipeKind), String> {
+    match document {
         RecipeDocument::Tile(recipe) => {
             let kind = if recipe.placement == RecipePlacement::Fixture {
                 ProceduralRecipeKind::Fixture
@@ -103,7 +174,9 @@ pub fn recipe_description(source: &str) -> Result<(String, ProceduralRecipeKind)
 }
 
 pub fn recipe_name(source: &str) -> String {
-    recipe_description(source)
+    parse_document(source)
+        .map_err(|error| error.to_string())
+        .and_then(describe_recipe_document)
         .map(|(name, _)| name)
         .unwrap_or_else(|_| "Invalid Recipe".to_string())
 }
@@ -172,7 +245,8 @@ fn material_source_from_project<'a>(project: &'a Project, alias: &str) -> Option
 
 fn material_from_project(project: &Project, alias: &str) -> Option<MaterialRecipe> {
     let source = material_source_from_project(project, alias)?;
-    let RecipeDocument::Materials(document) = parse_document(source).ok()? else {
+    let RecipeDocument::Materials(document) = parse_project_recipe(project, None, source).ok()?
+    else {
         return None;
     };
     document
@@ -218,7 +292,8 @@ fn recipe_preview_fingerprint(project: &Project, asset: &ProceduralRecipeAsset)
 
     // Tile previews can depend on a separate Material Recipe. Include only
     // that source so editing one Recipe does not invalidate every thumbnail.
-    if let Ok(RecipeDocument::Tile(recipe)) = parse_document(&asset.source)
+    if let Ok(RecipeDocument::Tile(recipe)) =
+        parse_project_recipe(project, Some(&asset.alias), &asset.source)
         && let Some(alias) = recipe
             .material_map
             .as_ref()
@@ -342,7 +417,7 @@ fn render_recipe_preview_uncached(
         .procedural_recipes
         .get(&asset_id)
         .ok_or_else(|| "Recipe asset was not found".to_string())?;
-    let document = parse_document(&asset.source).map_err(|error| error.to_string())?;
+    let document = parse_project_recipe(project, Some(&asset.alias), &asset.source)?;
     let renderer =
         RecipeRenderer::new(&project.art_palette).unwrap_or_else(|_| RecipeRenderer::grayscale());
     let options = RenderOptions::default();
@@ -452,23 +527,41 @@ pub(crate) fn cache_recipe_preview_result(
 
 /// Rebuild the compatibility material/SDF lookup maps after canonical Recipe edits.
 pub fn sync_recipe_compatibility_catalogs(project: &mut Project) {
+    // Runtime consumers parse these sources without access to other Recipes,
+    // so sources that include other Recipes are stored expanded.
+    let includes = ProjectRecipeIncludes(project);
+    let catalog = project
+        .procedural_recipes
+        .values()
+        .filter_map(|asset| {
+            let options = ParseOptions {
+                source_name: Some(&asset.alias),
+                includes: Some(&includes),
+                ..ParseOptions::default()
+            };
+            let source = match parse_document(&asset.source) {
+                Ok(_) => asset.source.clone(),
+                Err(_) => expand_recipe_source(&asset.source, &options).ok()?,
+            };
+            let document = parse_document(&source).ok()?;
+            Some((asset.alias.clone(), source, document))
+        })
+        .collect::<Vec<_>>();
+
     project.procedural_materials.clear();
     project.procedural_sdfs.clear();
-    for asset in project.procedural_recipes.values() {
-        let Ok(document) = parse_document(&asset.source) else {
-            continue;
-        };
+    for (alias, source, document) in catalog {
         match document {
             RecipeDocument::Materials(document) => {
                 if document.materials.len() == 1 {
                     project
                         .procedural_materials
-                        .insert(asset.alias.to_ascii_lowercase(), asset.source.clone());
+                        .insert(alias.to_ascii_lowercase(), source.clone());
                 }
                 for material in document.materials {
                     project.procedural_materials.insert(
-                        format!("{}/{}", asset.alias, material.id).to_ascii_lowercase(),
-                        asset.source.clone(),
+                        format!("{alias}/{}", material.id).to_ascii_lowercase(),
+                        source.clone(),
                     );
                 }
             }
@@ -476,12 +569,12 @@ pub fn sync_recipe_compatibility_catalogs(project: &mut Project) {
                 if document.recipes.len() == 1 {
                     project
                         .procedural_sdfs
-                        .insert(asset.alias.to_ascii_lowercase(), asset.source.clone());
+                        .insert(alias.to_ascii_lowercase(), source.clone());
                 }
                 for recipe in document.recipes {
                     project.procedural_sdfs.insert(
-                        format!("{}/{}", asset.alias, recipe.id).to_ascii_lowercase(),
-                        asset.source.clone(),
+                        format!("{alias}/{}", recipe.id).to_ascii_lowercase(),
+                        source.clone(),
                     );
                 }
             }
@@ -508,24 +601,28 @@ pub fn rebake_tile_recipe_with_preview(
         .cloned()
         .ok_or_else(|| "Recipe asset was not found".to_string())?;
     let RecipeDocument::Tile(recipe) =
-        parse_document(&asset.source).map_err(|error| error.to_string())?
+        parse_project_recipe(project, Some(&asset.alias), &asset.source)?
     else {
         return Ok(());
     };
-    let texture = Texture::new(
-        preview.pixels().to_vec(),
-        preview.dim().width as usize,
-        preview.dim().height as usize,
-    );
+    let textures = if recipe.animation.frames > 1 {
+        render_recipe_frames(project, &recipe)?
+    } else {
+        vec![Texture::new(
+            preview.pixels().to_vec(),
+            preview.dim().width as usize,
+            preview.dim().height as usize,
+        )]
+    };
     let tile_id = if let Some(tile_id) = asset.tile_id
         && let Some(tile) = project.tiles.get_mut(&tile_id)
     {
-        tile.textures = vec![texture];
+        tile.textures = textures;
         tile.alias = asset.alias.clone();
         tile.apply_procedural_recipe_metadata(&recipe);
         tile_id
     } else {
-        let mut tile = Tile::from_texture(texture);
+        let mut tile = Tile::from_textures(textures);
         tile.alias = asset.alias.clone();
         tile.role = TileRole::ManMade;
         tile.apply_procedural_recipe_metadata(&recipe);
@@ -539,6 +636,43 @@ pub fn rebake_tile_recipe_with_preview(
     Ok(())
 }
 
+/// Render every frame of an animated Tile Recipe, applying its base material
+/// the same way the preview does, so the baked Tile loops like the recipe.
+fn render_recipe_frames(project: &Project, recipe: &Recipe) -> Result<Vec<Texture>, String> {
+    let renderer =
+        RecipeRenderer::new(&project.art_palette).unwrap_or_else(|_| RecipeRenderer::grayscale());
+    let options = RenderOptions::default();
+    let rendered = renderer
+        .render(recipe, &options)
+        .map_err(|error| error.to_string())?;
+    let material = recipe
+        .material_map
+        .as_ref()
+        .map(|map| map.base.as_str())
+        .or(recipe.material.as_deref())
+        .and_then(|alias| material_from_project(project, alias))
+        .and_then(|material| {
+            renderer
+                .render_material(&material, &rendered, &options)
+                .ok()
+        });
+    Ok(rendered
+        .frames
+        .iter()
+        .enumerate()
+        .map(|(index, frame)| {
+            let rgba = material
+                .as_ref()
+                .and_then(|material| material.frames.get(index))
+                .map_or_else(
+                    || frame.rgba.clone(),
+                    |material_frame| material_frame.rgba.clone(),
+                );
+            Texture::new(rgba, rendered.width as usize, rendered.height as usize)
+        })
+        .collect())
+}
+
 /// Upgrade projects created while only the runtime material/SDF maps were
 /// persisted. Identical multi-declaration sources become one editable asset.
 pub fn migrate_legacy_recipe_catalog(project: &mut Project) -> bool {
@@ -571,10 +705,11 @@ mod tests {
 
     #[test]
     fn default_asset_name_comes_from_canonical_source() {
+        let project = Project::new();
         let asset = ProceduralRecipeAsset::default();
         assert_eq!(recipe_name(&asset.source), "Untitled Tile");
         assert_eq!(
-            recipe_description(&asset.source).unwrap().1,
+            recipe_description(&project, &asset).unwrap().1,
             ProceduralRecipeKind::Tile
         );
     }
@@ -633,6 +768,60 @@ mod tests {
         assert_eq!(tile.textures.len(), 1);
     }
 
+    #[test]
+    fn animated_tile_recipe_rebakes_every_frame() {
+        let mut project = Project::new();
+        let asset = ProceduralRecipeAsset::new(
+            "water",
+            r#"Tile
+    name = "Water"
+    Animation
+        frames = 4
+        fps = 8
+    Noise Ripple
+        drift = F2(1, 0)
+    Output
+        height = Ripple
+"#,
+        );
+        let id = asset.id;
+        project.procedural_recipes.insert(id, asset);
+        rebake_tile_recipe(&mut project, id).unwrap();
+        let tile_id = project.procedural_recipes[&id].tile_id.unwrap();
+        let tile = &project.tiles[&tile_id];
+        assert_eq!(tile.textures.len(), 4);
+        assert_ne!(tile.textures[0].data, tile.textures[1].data);
+        assert_eq!(tile.fps, 8.0);
+    }
+
+    #[test]
+    fn tile_recipe_includes_a_sibling_template_with_params() {
+        let mut project = Project::new();
+        let template = ProceduralRecipeAsset::new(
+            "walls/bricks",
+            r#"Tile
+    name = $Name
+    Param Name
+        default = "Bricks"
+    Output
+        height = 0.5
+"#,
+        );
+        project.procedural_recipes.insert(template.id, template);
+        let variant = ProceduralRecipeAsset::new(
+            "walls/red-bricks",
+            "Include \"bricks.recipe\"\n    name = \"Red Bricks\"\n",
+        );
+        let id = variant.id;
+        project.procedural_recipes.insert(id, variant);
+
+        let (name, kind) = recipe_description(&project, &project.procedural_recipes[&id]).unwrap();
+        assert_eq!(name, "Red Bricks");
+        assert_eq!(kind, ProceduralRecipeKind::Tile);
+        rebake_tile_recipe(&mut project, id).unwrap();
+        assert!(project.procedural_recipes[&id].tile_id.is_some());
+    }
+
     #[test]
     fn fixture_recipe_rebakes_with_shared_fixture_metadata() {
         let mut project = Project::new();
@@ -654,7 +843,7 @@ mod tests {
             rusterix::TileRecipePlacement::Fixture
         );
         assert_eq!(
-            recipe_description(&project.procedural_recipes[&id].source)
+            recipe_description(&project, &project.procedural_recipes[&id])
                 .unwrap()
                 .1,
             ProceduralRecipeKind::Fixture
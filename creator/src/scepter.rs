@@ -1,7 +1,8 @@
 use eldiron_scepter::{
-    AttributesGet, AttributesPatch, RegionPaintCells, RegionPaintRect, RegionRef,
-    RegionRenderPreview, ScepterCommand, ScepterLorebook, ScriptGet, ScriptPatch, ScriptValidate,
+    RegionRef, RegionSnapshot, RegionSummary, ScepterCommand, ScepterLorebook, TileList,
+    parse_request_line, path_without_query, ping_message,
 };
+use eldiron_scepter_server::ScepterResponse;
 use serde_json::json;
 use std::io::{Read, Write};
 use std::net::{TcpListener, TcpStream};
@@ -27,65 +28,27 @@ pub enum ScepterEvent {
     ProjectRedo {
         reply: Sender<serde_json::Value>,
     },
-    TilesSnapshot {
-        reply: Sender<serde_json::Value>,
-    },
-    RegionSnapshot {
-        request: ScepterRegionRequest,
-        reply: Sender<serde_json::Value>,
-    },
-    RegionSummary {
-        request: ScepterRegionRequest,
-        reply: Sender<serde_json::Value>,
-    },
-    RegionRenderPreview {
-        command: RegionRenderPreview,
-        reply: Sender<serde_json::Value>,
-    },
-    RegionPaintRect {
-        command: RegionPaintRect,
-        reply: Sender<serde_json::Value>,
-    },
-    RegionPaintCells {
-        command: RegionPaintCells,
-        reply: Sender<serde_json::Value>,
-    },
-    ScriptGet {
-        command: ScriptGet,
-        reply: Sender<serde_json::Value>,
-    },
-    ScriptPatch {
-        command: ScriptPatch,
-        reply: Sender<serde_json::Value>,
-    },
-    ScriptValidate {
-        command: ScriptValidate,
-        reply: Sender<serde_json::Value>,
-    },
-    AttributesGet {
-        command: AttributesGet,
-        reply: Sender<serde_json::Value>,
-    },
-    AttributesPatch {
-        command: AttributesPatch,
-        reply: Sender<serde_json::Value>,
+    /// Any other command, run through the headless Scepter session against
+    /// Creator's open project.
+    SessionCommand {
+        command: Box<ScepterCommand>,
+        reply: Sender<ScepterResponse>,
     },
     ServiceError(String),
 }
 
+/// Region parameters of the `GET /scepter/region` endpoints.
 #[derive(Debug, Clone)]
-pub struct ScepterRegionRequest {
-    pub id: Option<String>,
-    pub name: Option<String>,
-    pub include_tiles: bool,
-    pub include_ascii: bool,
+struct ScepterRegionRequest {
+    region: Option<RegionRef>,
+    include_tiles: bool,
+    include_ascii: bool,
 }
 
 impl Default for ScepterRegionRequest {
     fn default() -> Self {
         Self {
-            id: None,
-            name: None,
+            region: None,
             include_tiles: false,
             include_ascii: true,
         }
@@ -259,60 +222,36 @@ fn handle_stream(mut stream: TcpStream, tx: &Sender<ScepterEvent>) {
         }
         ("GET", "/scepter/region") => {
             let request = region_request_from_query(path);
-            request_creator_snapshot(
+            request_creator_session(
                 &mut stream,
                 tx,
-                "region",
-                |reply| ScepterEvent::RegionSnapshot { request, reply },
-                "Creator did not accept region snapshot request",
-                "region snapshot timed out",
+                ScepterCommand::RegionSnapshot(RegionSnapshot {
+                    region: request.region,
+                    include_tiles: request.include_tiles,
+                }),
             );
         }
         ("GET", "/scepter/region/summary") => {
             let request = region_request_from_query(path);
-            request_creator_snapshot(
+            request_creator_session(
                 &mut stream,
                 tx,
-                "summary",
-                |reply| ScepterEvent::RegionSummary { request, reply },
-                "Creator did not accept region summary request",
-                "region summary timed out",
+                ScepterCommand::RegionSummary(RegionSummary {
+                    region: request.region,
+                    include_ascii: request.include_ascii,
+                }),
             );
         }
         ("GET", "/scepter/tiles") => {
-            let (reply_tx, reply_rx) = channel();
-            if tx
-                .send(ScepterEvent::TilesSnapshot { reply: reply_tx })
-                .is_err()
-            {
-                let _ = write_json(
-                    &mut stream,
-                    500,
-                    json!({ "ok": false, "error": "Creator did not accept tile snapshot request" }),
-                );
-                return;
-            }
-
-            match reply_rx.recv_timeout(Duration::from_secs(2)) {
-                Ok(tiles) => {
-                    let _ = write_json(
-                        &mut stream,
-                        200,
-                        json!({
-                            "ok": true,
-                            "service": "eldiron_scepter",
-                            "tiles": tiles,
-                        }),
-                    );
-                }
-                Err(err) => {
-                    let _ = write_json(
-                        &mut stream,
-                        500,
-                        json!({ "ok": false, "error": format!("tile snapshot timed out: {err}") }),
-                    );
-                }
-            }
+            request_creator_session(
+                &mut stream,
+                tx,
+                ScepterCommand::TileList(TileList {
+                    role: None,
+                    kind: None,
+                    style: None,
+                }),
+            );
         }
         _ => {
             let _ = write_json(
@@ -422,130 +361,7 @@ fn handle_command(command: ScepterCommand, stream: &mut TcpStream, tx: &Sender<S
             "Creator did not accept redo request",
             "redo timed out",
         ),
-        ScepterCommand::RegionSnapshot(params) => {
-            let mut request = ScepterRegionRequest {
-                include_tiles: params.include_tiles,
-                ..ScepterRegionRequest::default()
-            };
-            if let Some(region) = params.region {
-                match region {
-                    RegionRef::Id { id } => request.id = Some(id),
-                    RegionRef::Name { name } => request.name = Some(name),
-                }
-            }
-            request_creator_snapshot(
-                stream,
-                tx,
-                "region",
-                |reply| ScepterEvent::RegionSnapshot { request, reply },
-                "Creator did not accept region snapshot request",
-                "region snapshot timed out",
-            )
-        }
-        ScepterCommand::RegionSummary(params) => {
-            let mut request = ScepterRegionRequest {
-                include_ascii: params.include_ascii,
-                ..ScepterRegionRequest::default()
-            };
-            if let Some(region) = params.region {
-                match region {
-                    RegionRef::Id { id } => request.id = Some(id),
-                    RegionRef::Name { name } => request.name = Some(name),
-                }
-            }
-            request_creator_snapshot(
-                stream,
-                tx,
-                "summary",
-                |reply| ScepterEvent::RegionSummary { request, reply },
-                "Creator did not accept region summary request",
-                "region summary timed out",
-            )
-        }
-        ScepterCommand::RegionRenderPreview(command) => request_creator_snapshot(
-            stream,
-            tx,
-            "preview",
-            |reply| ScepterEvent::RegionRenderPreview { command, reply },
-            "Creator did not accept region preview request",
-            "region preview timed out",
-        ),
-        ScepterCommand::RegionPaintRect(command) => request_creator_snapshot(
-            stream,
-            tx,
-            "result",
-            |reply| ScepterEvent::RegionPaintRect { command, reply },
-            "Creator did not accept region paint request",
-            "region paint timed out",
-        ),
-        ScepterCommand::RegionPaintCells(command) => request_creator_snapshot(
-            stream,
-            tx,
-            "result",
-            |reply| ScepterEvent::RegionPaintCells { command, reply },
-            "Creator did not accept region paint request",
-            "region paint timed out",
-        ),
-        ScepterCommand::TileList(_) => request_creator_snapshot(
-            stream,
-            tx,
-            "tiles",
-            |reply| ScepterEvent::TilesSnapshot { reply },
-            "Creator did not accept tile snapshot request",
-            "tile snapshot timed out",
-        ),
-        ScepterCommand::ScriptGet(command) => request_creator_snapshot(
-            stream,
-            tx,
-            "script",
-            |reply| ScepterEvent::ScriptGet { command, reply },
-            "Creator did not accept script read request",
-            "script read timed out",
-        ),
-        ScepterCommand::ScriptPatch(command) => request_creator_snapshot(
-            stream,
-            tx,
-            "result",
-            |reply| ScepterEvent::ScriptPatch { command, reply },
-            "Creator did not accept script patch request",
-            "script patch timed out",
-        ),
-        ScepterCommand::ScriptValidate(command) => request_creator_snapshot(
-            stream,
-            tx,
-            "validation",
-            |reply| ScepterEvent::ScriptValidate { command, reply },
-            "Creator did not accept script validation request",
-            "script validation timed out",
-        ),
-        ScepterCommand::AttributesGet(command) => request_creator_snapshot(
-            stream,
-            tx,
-            "attributes",
-            |reply| ScepterEvent::AttributesGet { command, reply },
-            "Creator did not accept attributes read request",
-            "attributes read timed out",
-        ),
-        ScepterCommand::AttributesPatch(command) => request_creator_snapshot(
-            stream,
-            tx,
-            "result",
-            |reply| ScepterEvent::AttributesPatch { command, reply },
-            "Creator did not accept attributes patch request",
-            "attributes patch timed out",
-        ),
-        command => {
-            let _ = write_json(
-                stream,
-                501,
-                json!({
-                    "ok": false,
-                    "service": "eldiron_scepter",
-                    "command": command.name(),
-                    "error": "command is in the Lorebook but is not executable yet",
-                }),
-            );
-        }
+        command => request_creator_session(stream, tx, command),
     }
 }
 
@@ -584,33 +400,40 @@ fn request_creator_snapshot(
     }
 }
 
-fn parse_request_line(line: &str) -> Option<(&str, &str)> {
-    let mut parts = line.split_whitespace();
-    let method = parts.next()?;
-    let path = parts.next()?;
-    Some((method, path))
-}
-
-fn path_without_query(path: &str) -> &str {
-    path.split_once('?').map(|(path, _)| path).unwrap_or(path)
-}
-
-fn ping_message(path: &str, body: &str) -> String {
-    if let Some(query) = path.split_once('?').map(|(_, query)| query) {
-        for part in query.split('&') {
-            if let Some(value) = part.strip_prefix("message=") {
-                return value.replace('+', " ");
-            }
-        }
-    }
-
-    if let Ok(value) = serde_json::from_str::<serde_json::Value>(body)
-        && let Some(message) = value.get("message").and_then(serde_json::Value::as_str)
+fn request_creator_session(
+    stream: &mut TcpStream,
+    tx: &Sender<ScepterEvent>,
+    command: ScepterCommand,
+) {
+    let name = command.name();
+    let (reply_tx, reply_rx) = channel();
+    if tx
+        .send(ScepterEvent::SessionCommand {
+            command: Box::new(command),
+            reply: reply_tx,
+        })
+        .is_err()
     {
-        return message.to_string();
+        let _ = write_json(
+            stream,
+            500,
+            json!({ "ok": false, "error": format!("Creator did not accept {name} request") }),
+        );
+        return;
     }
 
-    body.trim().to_string()
+    match reply_rx.recv_timeout(Duration::from_secs(2)) {
+        Ok(response) => {
+            let _ = write_json(stream, response.status, response.body);
+        }
+        Err(err) => {
+            let _ = write_json(
+                stream,
+                500,
+                json!({ "ok": false, "error": format!("{name} timed out: {err}") }),
+            );
+        }
+    }
 }
 
 fn region_request_from_query(path: &str) -> ScepterRegionRequest {
@@ -619,17 +442,20 @@ fn region_request_from_query(path: &str) -> ScepterRegionRequest {
         return request;
     };
 
+    let mut id = None;
+    let mut name = None;
     for part in query.split('&') {
         if let Some(value) = part.strip_prefix("id=") {
-            request.id = Some(value.replace('+', " "));
+            id = Some(value.replace('+', " "));
         } else if let Some(value) = part.strip_prefix("name=") {
-            request.name = Some(value.replace('+', " "));
+            name = Some(value.replace('+', " "));
         } else if let Some(value) = part.strip_prefix("include_tiles=") {
             request.include_tiles = matches!(value, "1" | "true" | "yes");
         } else if let Some(value) = part.strip_prefix("include_ascii=") {
             request.include_ascii = matches!(value, "1" | "true" | "yes");
         }
     }
+    request.region = id.map(RegionRef::id).or_else(|| name.map(RegionRef::name));
 
     request
 }
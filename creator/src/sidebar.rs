@@ -2115,13 +2115,16 @@ impl Sidebar {
                         let Ok(source) = std::fs::read_to_string(p) else {
                             continue;
                         };
-                        if let Err(error) = procedural_recipes::parse_document(&source) {
-                            ctx.ui.send(TheEvent::SetStatusText(
-                                TheId::empty(),
-                                format!("{}: {error}", fl!("invalid_recipe")),
-                            ));
-                            continue;
-                        }
+                        let source = match crate::recipe_utils::import_recipe_source(p, source) {
+                            Ok(source) => source,
+                            Err(error) => {
+                                ctx.ui.send(TheEvent::SetStatusText(
+                                    TheId::empty(),
+                                    format!("{}: {error}", fl!("invalid_recipe")),
+                                ));
+                                continue;
+                            }
+                        };
                         let requested = p
                             .file_stem()
                             .unwrap_or_default()
@@ -3815,7 +3818,7 @@ impl Sidebar {
             return;
         };
         let (name, kind) =
-            crate::recipe_utils::recipe_description(&recipe.source).unwrap_or_else(|_| {
+            crate::recipe_utils::recipe_description(project, recipe).unwrap_or_else(|_| {
                 (
                     fl!("invalid_recipe"),
                     crate::recipe_utils::ProceduralRecipeKind::Tile,
@@ -274,6 +274,9 @@ impl TextGameState {
                         "  look | l           Show the current room",
                         "  inventory | inv    Show your inventory",
                         "  stats | stat       Show your character stats",
+                        "  list [vendor]      Show the wares of a nearby merchant",
+                        "  buy [n] <item>     Buy from a nearby merchant",
+                        "  sell [n] <item>    Sell to a nearby merchant",
                         "  north | east | south | west",
                         "  n | e | s | w",
                         "                     Move through a text exit",
@@ -354,7 +357,10 @@ impl TextGameState {
                             self.push_plain_line(&error);
                         }
                     }
-                    rusterix::client::text_command::TextCommand::Craft { recipe: _ } => {
+                    rusterix::client::text_command::TextCommand::Craft { .. }
+                    | rusterix::client::text_command::TextCommand::List { .. }
+                    | rusterix::client::text_command::TextCommand::Buy { .. }
+                    | rusterix::client::text_command::TextCommand::Sell { .. } => {
                         RUSTERIX
                             .write()
                             .unwrap()
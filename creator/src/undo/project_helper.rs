@@ -9,7 +9,7 @@ pub fn gen_procedural_recipe_tree_item(
 ) -> TheTreeItem {
     let mut item = TheTreeItem::new(TheId::named_with_id("Procedural Recipe Item", recipe.id));
     let (name, kind) =
-        crate::recipe_utils::recipe_description(&recipe.source).unwrap_or_else(|_| {
+        crate::recipe_utils::recipe_description(project, recipe).unwrap_or_else(|_| {
             (
                 fl!("invalid_recipe"),
                 crate::recipe_utils::ProceduralRecipeKind::Tile,